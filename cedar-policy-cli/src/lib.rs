@@ -24,7 +24,7 @@ use miette::{miette, IntoDiagnostic, NamedSource, Report, Result, WrapErr};
 use serde::{Deserialize, Serialize};
 use std::io::Write;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::{self, Display},
     fs::OpenOptions,
     path::Path,
@@ -34,7 +34,7 @@ use std::{
 };
 
 use cedar_policy::*;
-use cedar_policy_formatter::{policies_str_to_pretty, Config};
+use cedar_policy_formatter::{is_formatted, policies_str_to_pretty, Config, IndentStyle};
 
 /// Basic Cedar CLI for evaluating authorization queries
 #[derive(Parser)]
@@ -86,9 +86,16 @@ pub enum Commands {
     /// Evaluate an authorization request
     Authorize(AuthorizeArgs),
     /// Evaluate a Cedar expression
+    #[command(alias = "eval")]
     Evaluate(EvaluateArgs),
     /// Validate a policy set against a schema
     Validate(ValidateArgs),
+    /// Analyze a policy set for policies which can never apply, conflict, or
+    /// are shadowed
+    Analyze(AnalyzeArgs),
+    /// Show the transitive members of an action, and the principal and
+    /// resource types it and its members apply to
+    Actions(ActionsArgs),
     /// Check that policies successfully parse
     CheckParse(CheckParseArgs),
     /// Link a template
@@ -103,9 +110,20 @@ pub enum Commands {
 
 #[derive(Args, Debug)]
 pub struct TranslateSchemaArgs {
-    /// The direction of translation,
+    /// The direction of translation. Alternatively, give the formats with `--from` and `--to`.
+    #[arg(long, required_unless_present_all = ["from", "to"], conflicts_with_all = ["from", "to"])]
+    pub direction: Option<TranslationDirection>,
+    /// The format of the input schema
+    #[arg(long, value_enum, requires = "to")]
+    pub from: Option<SchemaFormat>,
+    /// The format to translate the schema into
+    #[arg(long, value_enum, requires = "from")]
+    pub to: Option<SchemaFormat>,
+    /// Instead of printing the translated schema, check that translating it and translating
+    /// the result back gives an equivalent schema. If not, print the differences and exit with
+    /// a nonzero status.
     #[arg(long)]
-    pub direction: TranslationDirection,
+    pub check_roundtrip: bool,
     /// Filename to read the schema from.
     /// If not provided, will default to reading stdin.
     #[arg(short = 's', long = "schema", value_name = "FILE")]
@@ -121,9 +139,10 @@ pub enum TranslationDirection {
     HumanToJson,
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum SchemaFormat {
     /// Human-readable format
+    #[value(alias = "cedar")]
     Human,
     /// JSON format
     Json,
@@ -156,6 +175,57 @@ pub struct ValidateArgs {
     pub schema_format: SchemaFormat,
 }
 
+#[derive(Args, Debug)]
+pub struct AnalyzeArgs {
+    /// File containing the schema
+    #[arg(short, long = "schema", value_name = "FILE")]
+    pub schema_file: String,
+    /// Policies args (incorporated by reference)
+    #[command(flatten)]
+    pub policies: PoliciesArgs,
+    /// Schema format (Human-readable or json)
+    #[arg(long, value_enum, default_value_t = SchemaFormat::Human)]
+    pub schema_format: SchemaFormat,
+    /// Format of the findings
+    #[arg(long, value_enum, default_value_t = AnalysisFormat::Text)]
+    pub output_format: AnalysisFormat,
+}
+
+/// The output format of the `analyze` subcommand
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum AnalysisFormat {
+    /// One finding per line
+    Text,
+    /// A JSON array of findings
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub struct ActionsArgs {
+    /// File containing the schema
+    #[arg(short, long = "schema", value_name = "FILE")]
+    pub schema_file: String,
+    /// Schema format (Human-readable or json)
+    #[arg(long, value_enum, default_value_t = SchemaFormat::Human)]
+    pub schema_format: SchemaFormat,
+    /// Action or action group to show, e.g., Action::"manage"
+    #[arg(short, long)]
+    pub action: String,
+    /// Format of the output
+    #[arg(long = "format", value_enum, default_value_t = ActionsFormat::Text)]
+    pub output_format: ActionsFormat,
+}
+
+/// The output format of the `actions` subcommand
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ActionsFormat {
+    /// The action, then each of its members, with their principal and
+    /// resource types
+    Text,
+    /// A JSON object
+    Json,
+}
+
 #[derive(Args, Debug)]
 pub struct CheckParseArgs {
     /// Policies args (incorporated by reference)
@@ -207,47 +277,7 @@ impl RequestArgs {
                 let qjson: RequestJSON = serde_json::from_str(&jsonstring)
                     .into_diagnostic()
                     .wrap_err_with(|| format!("failed to parse request-json file {jsonfile}"))?;
-                let principal = qjson
-                    .principal
-                    .map(|s| {
-                        s.parse().wrap_err_with(|| {
-                            format!("failed to parse principal in {jsonfile} as entity Uid")
-                        })
-                    })
-                    .transpose()?;
-                let action = qjson
-                    .action
-                    .map(|s| {
-                        s.parse().wrap_err_with(|| {
-                            format!("failed to parse action in {jsonfile} as entity Uid")
-                        })
-                    })
-                    .transpose()?;
-                let resource = qjson
-                    .resource
-                    .map(|s| {
-                        s.parse().wrap_err_with(|| {
-                            format!("failed to parse resource in {jsonfile} as entity Uid")
-                        })
-                    })
-                    .transpose()?;
-                let context = Context::from_json_value(
-                    qjson.context,
-                    schema.and_then(|s| Some((s, action.as_ref()?))),
-                )
-                .wrap_err_with(|| format!("failed to create a context from {jsonfile}"))?;
-                Request::new(
-                    principal,
-                    action,
-                    resource,
-                    context,
-                    if self.request_validation {
-                        schema
-                    } else {
-                        None
-                    },
-                )
-                .map_err(|e| miette!("{e}"))
+                qjson.into_request(schema, self.request_validation, jsonfile)
             }
             None => {
                 let principal = self
@@ -359,6 +389,13 @@ pub struct AuthorizeArgs {
     /// Time authorization and report timing information
     #[arg(short, long)]
     pub timing: bool,
+    /// Read requests from stdin as JSON Lines, one request per line in the
+    /// format of --request-json, and write one JSON object per line to stdout
+    /// with the index of the input line, the decision, and the diagnostics.
+    /// The policies, entities, and schema are loaded once for all requests.
+    /// Requires --policies, as stdin holds the requests.
+    #[arg(long, requires = "policies_file", conflicts_with_all = &["principal", "action", "resource", "context_json_file", "request_json_file"])]
+    pub batch: bool,
 }
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
@@ -400,13 +437,29 @@ pub struct FormatArgs {
     #[arg(short, long, value_name = "INT", default_value_t = 2)]
     pub indent_width: isize,
 
+    /// Indent with tabs instead of spaces. A tab counts as `indent-width` columns towards the
+    /// line width.
+    #[arg(long)]
+    pub use_tabs: bool,
+
+    /// Sort the policies into a canonical order: by their annotations, such as `@id`, then by
+    /// their effect, scope, and conditions. Comments before a policy move with it.
+    #[arg(long)]
+    pub sort: bool,
+
     /// Automatically write back the formatted policies to the input file.
     #[arg(short, long, group = "action", requires = "policies_file")]
     pub write: bool,
 
     /// Check that the policies formats without any changes. Mutually exclusive with `write`.
+    /// If they do not, print a unified diff of the changes formatting would make, and exit
+    /// with a nonzero status.
     #[arg(short, long, group = "action")]
     pub check: bool,
+
+    /// Don't print the diff of the needed changes when using `check`.
+    #[arg(short, long, requires = "check")]
+    pub quiet: bool,
 }
 
 #[derive(Args, Debug)]
@@ -460,6 +513,72 @@ struct RequestJSON {
     context: serde_json::Value,
 }
 
+impl RequestJSON {
+    /// Turn this `RequestJSON`, read from `source`, into a `Request`
+    ///
+    /// `schema` will be used for schema-based parsing of the context, and also
+    /// (if `request_validation` is `true`) for request validation.
+    fn into_request(
+        self,
+        schema: Option<&Schema>,
+        request_validation: bool,
+        source: &str,
+    ) -> Result<Request> {
+        let principal = self
+            .principal
+            .map(|s| {
+                s.parse().wrap_err_with(|| {
+                    format!("failed to parse principal in {source} as entity Uid")
+                })
+            })
+            .transpose()?;
+        let action = self
+            .action
+            .map(|s| {
+                s.parse()
+                    .wrap_err_with(|| format!("failed to parse action in {source} as entity Uid"))
+            })
+            .transpose()?;
+        let resource = self
+            .resource
+            .map(|s| {
+                s.parse()
+                    .wrap_err_with(|| format!("failed to parse resource in {source} as entity Uid"))
+            })
+            .transpose()?;
+        let context = Context::from_json_value(
+            self.context,
+            schema.and_then(|s| Some((s, action.as_ref()?))),
+        )
+        .wrap_err_with(|| format!("failed to create a context from {source}"))?;
+        Request::new(
+            principal,
+            action,
+            resource,
+            context,
+            if request_validation { schema } else { None },
+        )
+        .map_err(|e| miette!("{e}"))
+    }
+}
+
+/// A line of the output of `authorize --batch`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchResponse {
+    /// Index of the input line, starting at 0
+    index: usize,
+    /// Decision, or `None` if the line could not be turned into a request
+    decision: Option<Decision>,
+    /// Ids of the policies that contributed to the decision
+    reason: Vec<String>,
+    /// Errors that occurred while parsing the request or during authorization
+    errors: Vec<String>,
+    /// Authorization time in microseconds, with --timing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authorization_time_micros: Option<u128>,
+}
+
 #[derive(Args, Debug)]
 pub struct EvaluateArgs {
     /// Request args (incorporated by reference)
@@ -478,8 +597,25 @@ pub struct EvaluateArgs {
     #[arg(long = "entities", value_name = "FILE")]
     pub entities_file: Option<String>,
     /// Expression to evaluate
-    #[arg(value_name = "EXPRESSION")]
-    pub expression: String,
+    #[arg(value_name = "EXPRESSION", required_unless_present = "expr")]
+    pub expression: Option<String>,
+    /// Expression to evaluate, as an alternative to giving it positionally
+    #[arg(
+        long = "expr",
+        value_name = "EXPRESSION",
+        conflicts_with = "expression"
+    )]
+    pub expr: Option<String>,
+}
+
+impl EvaluateArgs {
+    /// The expression to evaluate, whether it was given positionally or with `--expr`
+    fn expression_src(&self) -> &str {
+        self.expression
+            .as_deref()
+            .or(self.expr.as_deref())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -567,6 +703,257 @@ pub fn validate(args: &ValidateArgs) -> CedarExitCode {
     }
 }
 
+/// A finding of the `analyze` subcommand
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// Machine-readable reason code, e.g., `shadowed-permit`
+    pub code: &'static str,
+    /// Ids of the policies the finding is about
+    pub policies: Vec<String>,
+    /// Human-readable description of the finding
+    pub message: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}",
+            self.code,
+            self.policies.join(", "),
+            self.message
+        )
+    }
+}
+
+/// Find the policies in `pset` which can never apply to a request valid for
+/// `schema`, the `permit` and `forbid` policies with identical scopes, and the
+/// `permit` policies shadowed by a `forbid` policy. The findings are sorted by
+/// the policies they are about.
+pub fn analyze_policy_set(pset: &PolicySet, schema: &Schema) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    // Policies which can never apply, according to the validator
+    let result = Validator::new(schema.clone()).validate(pset, ValidationMode::default());
+    for err in result.validation_errors() {
+        let code = match err.error_kind() {
+            ValidationErrorKind::UnrecognizedEntityType(_) => "unrecognized-entity-type",
+            ValidationErrorKind::UnrecognizedActionId(_) => "unrecognized-action",
+            ValidationErrorKind::InvalidActionApplication(_) => "invalid-action-application",
+            _ => continue,
+        };
+        findings.push(Finding {
+            code,
+            policies: vec![err.location().policy_id().to_string()],
+            message: err.error_kind().to_string(),
+        });
+    }
+    for warning in result.validation_warnings() {
+        if let ValidationWarningKind::ImpossiblePolicy = warning.warning_kind() {
+            findings.push(Finding {
+                code: "impossible-policy",
+                policies: vec![warning.location().policy_id().to_string()],
+                message: warning.warning_kind().to_string(),
+            });
+        }
+    }
+
+    // `permit` and `forbid` policies with identical scopes
+    let (permits, forbids): (Vec<_>, Vec<_>) = pset
+        .policies()
+        .partition(|policy| policy.effect() == Effect::Permit);
+    for permit in &permits {
+        for forbid in &forbids {
+            if permit.principal_constraint() == forbid.principal_constraint()
+                && permit.action_constraint() == forbid.action_constraint()
+                && permit.resource_constraint() == forbid.resource_constraint()
+            {
+                findings.push(Finding {
+                    code: "conflicting-scopes",
+                    policies: vec![permit.id().to_string(), forbid.id().to_string()],
+                    message: "a `permit` policy and a `forbid` policy have identical scopes"
+                        .to_string(),
+                });
+            }
+        }
+    }
+
+    // `permit` policies shadowed by a `forbid` policy
+    for (permit, forbid) in pset.find_shadowed_permits(schema) {
+        findings.push(Finding {
+            code: "shadowed-permit",
+            policies: vec![permit.to_string(), forbid.to_string()],
+            message: format!("the `permit` policy never applies, as `{forbid}` forbids every request it applies to"),
+        });
+    }
+
+    findings.sort_by(|a, b| (&a.policies, a.code).cmp(&(&b.policies, b.code)));
+    findings
+}
+
+pub fn analyze(args: &AnalyzeArgs) -> CedarExitCode {
+    let pset = match args.policies.get_policy_set() {
+        Ok(pset) => pset,
+        Err(e) => {
+            println!("{e:?}");
+            return CedarExitCode::Failure;
+        }
+    };
+
+    let schema = match read_schema_file(&args.schema_file, args.schema_format) {
+        Ok(schema) => schema,
+        Err(e) => {
+            println!("{e:?}");
+            return CedarExitCode::Failure;
+        }
+    };
+
+    let findings = analyze_policy_set(&pset, &schema);
+    match args.output_format {
+        AnalysisFormat::Text => {
+            for finding in &findings {
+                println!("{finding}");
+            }
+        }
+        AnalysisFormat::Json => match serde_json::to_string_pretty(&findings) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                println!("{e:?}");
+                return CedarExitCode::Failure;
+            }
+        },
+    }
+
+    if findings.is_empty() {
+        CedarExitCode::Success
+    } else {
+        CedarExitCode::ValidationFailure
+    }
+}
+
+/// An action, and the principal and resource types it applies to, as shown by
+/// the `actions` subcommand
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActionSummary {
+    /// The action, e.g., `Action::"view"`
+    pub action: String,
+    /// The principal types the action applies to, sorted
+    pub principals: Vec<String>,
+    /// The resource types the action applies to, sorted
+    pub resources: Vec<String>,
+}
+
+/// The transitive members of an action, as shown by the `actions` subcommand
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ActionHierarchy {
+    /// The action itself
+    #[serde(flatten)]
+    pub action: ActionSummary,
+    /// The actions which are members of the action, directly or transitively,
+    /// sorted
+    pub members: Vec<ActionSummary>,
+}
+
+impl ActionSummary {
+    /// Summarize `action`, which must be declared in `schema`
+    fn new(schema: &Schema, action: &EntityUid) -> Self {
+        fn sorted<'a>(types: Option<impl Iterator<Item = &'a EntityTypeName>>) -> Vec<String> {
+            let mut types = types
+                .into_iter()
+                .flatten()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            types.sort();
+            types
+        }
+        Self {
+            action: action.to_string(),
+            principals: sorted(schema.principals_for_action(action)),
+            resources: sorted(schema.resources_for_action(action)),
+        }
+    }
+}
+
+impl Display for ActionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |types: &[String]| {
+            if types.is_empty() {
+                "none".to_string()
+            } else {
+                types.join(", ")
+            }
+        };
+        writeln!(f, "{}", self.action)?;
+        writeln!(f, "  principals: {}", list(&self.principals))?;
+        write!(f, "  resources: {}", list(&self.resources))
+    }
+}
+
+/// Get the transitive members of `action` in `schema`, with the principal and
+/// resource types each applies to, or `None` if `action` is not declared in
+/// `schema`
+pub fn action_hierarchy(schema: &Schema, action: &EntityUid) -> Option<ActionHierarchy> {
+    let mut members = schema
+        .action_descendants(action)?
+        .map(|member| ActionSummary::new(schema, member))
+        .collect::<Vec<_>>();
+    members.sort_by(|a, b| a.action.cmp(&b.action));
+    Some(ActionHierarchy {
+        action: ActionSummary::new(schema, action),
+        members,
+    })
+}
+
+pub fn actions(args: &ActionsArgs) -> CedarExitCode {
+    let schema = match read_schema_file(&args.schema_file, args.schema_format) {
+        Ok(schema) => schema,
+        Err(e) => {
+            println!("{e:?}");
+            return CedarExitCode::Failure;
+        }
+    };
+    let action = match EntityUid::from_str(&args.action)
+        .wrap_err_with(|| format!("failed to parse action {} as entity Uid", args.action))
+    {
+        Ok(action) => action,
+        Err(e) => {
+            println!("{e:?}");
+            return CedarExitCode::Failure;
+        }
+    };
+    let Some(hierarchy) = action_hierarchy(&schema, &action) else {
+        println!(
+            "{:?}",
+            miette!("action {action} is not declared in {}", args.schema_file)
+        );
+        return CedarExitCode::Failure;
+    };
+
+    match args.output_format {
+        ActionsFormat::Text => {
+            println!("{}", hierarchy.action);
+            if hierarchy.members.is_empty() {
+                println!("members: none");
+            } else {
+                println!("members:");
+                for member in &hierarchy.members {
+                    for line in member.to_string().lines() {
+                        println!("  {line}");
+                    }
+                }
+            }
+        }
+        ActionsFormat::Json => match serde_json::to_string_pretty(&hierarchy) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                println!("{e:?}");
+                return CedarExitCode::Failure;
+            }
+        },
+    }
+    CedarExitCode::Success
+}
+
 pub fn evaluate(args: &EvaluateArgs) -> (CedarExitCode, EvalResult) {
     println!();
     let schema = match args
@@ -588,14 +975,14 @@ pub fn evaluate(args: &EvaluateArgs) -> (CedarExitCode, EvalResult) {
             return (CedarExitCode::Failure, EvalResult::Bool(false));
         }
     };
-    let expr =
-        match Expression::from_str(&args.expression).wrap_err("failed to parse the expression") {
-            Ok(expr) => expr,
-            Err(e) => {
-                println!("{:?}", e.with_source_code(args.expression.clone()));
-                return (CedarExitCode::Failure, EvalResult::Bool(false));
-            }
-        };
+    let src = args.expression_src();
+    let expr = match Expression::from_str(src).wrap_err("failed to parse the expression") {
+        Ok(expr) => expr,
+        Err(e) => {
+            println!("{:?}", e.with_source_code(src.to_string()));
+            return (CedarExitCode::Failure, EvalResult::Bool(false));
+        }
+    };
     let entities = match &args.entities_file {
         None => Entities::empty(),
         Some(file) => match load_entities(file, schema.as_ref()) {
@@ -609,7 +996,7 @@ pub fn evaluate(args: &EvaluateArgs) -> (CedarExitCode, EvalResult) {
     match eval_expression(&request, &entities, &expr).wrap_err("failed to evaluate the expression")
     {
         Err(e) => {
-            println!("{e:?}");
+            println!("{:?}", e.with_source_code(src.to_string()));
             return (CedarExitCode::Failure, EvalResult::Bool(false));
         }
         Ok(result) => {
@@ -637,11 +1024,40 @@ fn format_policies_inner(args: &FormatArgs) -> Result<bool> {
     let config = Config {
         line_width: args.line_width,
         indent_width: args.indent_width,
+        indent_style: if args.use_tabs {
+            IndentStyle::Tabs
+        } else {
+            IndentStyle::Spaces
+        },
+        sort_policies: args.sort,
     };
     let formatted_policy = policies_str_to_pretty(&policies_str, &config)?;
-    let are_policies_equivalent = policies_str == formatted_policy;
+    let are_policies_equivalent = is_formatted(&policies_str, &config);
 
     match &args.policies_file {
+        _ if args.check => {
+            if !are_policies_equivalent && !args.quiet {
+                let name = args.policies_file.as_deref().unwrap_or("<stdin>");
+                let original = policies_str.lines().collect::<Vec<_>>();
+                let formatted = formatted_policy.lines().collect::<Vec<_>>();
+                let diff = difflib::unified_diff(
+                    &original,
+                    &formatted,
+                    name,
+                    &format!("{name} (formatted)"),
+                    "",
+                    "",
+                    3,
+                );
+                // `difflib` ends the file header lines with a tab separating the
+                // (empty) file dates, so print our own headers instead
+                println!("--- {name}");
+                println!("+++ {name} (formatted)");
+                for line in diff.iter().skip(2) {
+                    println!("{}", line.trim_end_matches('\n'));
+                }
+            }
+        }
         Some(policies_file) if args.write => {
             let mut file = OpenOptions::new()
                 .write(true)
@@ -687,15 +1103,101 @@ fn translate_to_json(natural_src: impl AsRef<str>) -> Result<String> {
     Ok(output)
 }
 
-fn translate_schema_inner(args: &TranslateSchemaArgs) -> Result<String> {
-    let translate = match args.direction {
-        TranslationDirection::JsonToHuman => translate_to_human,
-        TranslationDirection::HumanToJson => translate_to_json,
+impl TranslateSchemaArgs {
+    /// The direction of translation, given either by `--direction` or by
+    /// `--from` and `--to`
+    fn translation_direction(&self) -> Result<TranslationDirection> {
+        match (self.direction, self.from, self.to) {
+            (Some(direction), _, _) => Ok(direction),
+            (None, Some(SchemaFormat::Json), Some(SchemaFormat::Human)) => {
+                Ok(TranslationDirection::JsonToHuman)
+            }
+            (None, Some(SchemaFormat::Human), Some(SchemaFormat::Json)) => {
+                Ok(TranslationDirection::HumanToJson)
+            }
+            (None, Some(_), Some(_)) => Err(miette!(
+                "`--from` and `--to` must be different schema formats"
+            )),
+            (None, _, _) => Err(miette!(
+                "either `--direction`, or both `--from` and `--to`, must be provided"
+            )),
+        }
+    }
+}
+
+/// Check that translating the schema `src` in `direction` and translating the
+/// result back gives an equivalent schema, i.e., one with the same JSON
+/// representation
+fn check_schema_roundtrip(src: &str, direction: TranslationDirection) -> Result<()> {
+    let (original, roundtripped, other_format) = match direction {
+        TranslationDirection::JsonToHuman => {
+            let original = SchemaFragment::from_str(src)?;
+            let (roundtripped, _) = SchemaFragment::from_str_natural(&original.as_natural()?)?;
+            (original, roundtripped, "human-readable")
+        }
+        TranslationDirection::HumanToJson => {
+            let (original, warnings) = SchemaFragment::from_str_natural(src)?;
+            for warning in warnings {
+                eprintln!("{:?}", Report::new(warning));
+            }
+            let roundtripped = SchemaFragment::from_str(&original.as_json_string()?)?;
+            (original, roundtripped, "JSON")
+        }
     };
-    read_from_file_or_stdin(args.input_file.clone(), "schema").and_then(translate)
+    let (original, roundtripped) = (original.to_json_value()?, roundtripped.to_json_value()?);
+    // Object equality doesn't depend on the order of the keys, which isn't
+    // deterministic for entity types and actions
+    if original == roundtripped {
+        return Ok(());
+    }
+    let pretty = |value: serde_json::Value| -> Result<String> {
+        serde_json::to_string_pretty(&sort_keys(value)).into_diagnostic()
+    };
+    let (original, roundtripped) = (pretty(original)?, pretty(roundtripped)?);
+    let original = original.lines().collect::<Vec<_>>();
+    let roundtripped = roundtripped.lines().collect::<Vec<_>>();
+    let diff = difflib::unified_diff(&original, &roundtripped, "", "", "", "", 3);
+    Err(miette!(
+        "the schema changes when translated to the {other_format} format and back. The differences in its JSON representation are:\n{}",
+        diff.iter()
+            .skip(2)
+            .map(|line| line.trim_end_matches('\n'))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ))
+}
+
+/// Sort the keys of every object in `value`, so that equal values are
+/// displayed the same way
+fn sort_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let sorted: BTreeMap<_, _> = map.into_iter().map(|(k, v)| (k, sort_keys(v))).collect();
+            serde_json::Value::Object(sorted.into_iter().collect())
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(sort_keys).collect())
+        }
+        value => value,
+    }
 }
+
+fn translate_schema_inner(args: &TranslateSchemaArgs) -> Result<String> {
+    let direction = args.translation_direction()?;
+    let src = read_from_file_or_stdin(args.input_file.clone(), "schema")?;
+    if args.check_roundtrip {
+        check_schema_roundtrip(&src, direction)?;
+        return Ok(String::new());
+    }
+    match direction {
+        TranslationDirection::JsonToHuman => translate_to_human(src),
+        TranslationDirection::HumanToJson => translate_to_json(src),
+    }
+}
+
 pub fn translate_schema(args: &TranslateSchemaArgs) -> CedarExitCode {
     match translate_schema_inner(args) {
+        Ok(_) if args.check_roundtrip => CedarExitCode::Success,
         Ok(sf) => {
             println!("{sf}");
             CedarExitCode::Success
@@ -953,6 +1455,9 @@ fn write_template_linked_file(linked: &[TemplateLinked], path: impl AsRef<Path>)
 }
 
 pub fn authorize(args: &AuthorizeArgs) -> CedarExitCode {
+    if args.batch {
+        return authorize_batch(args);
+    }
     println!();
     let ans = execute_request(
         &args.request,
@@ -1003,6 +1508,100 @@ pub fn authorize(args: &AuthorizeArgs) -> CedarExitCode {
     }
 }
 
+/// Authorize each request read from stdin, as JSON Lines, against the policies,
+/// entities, and schema loaded once from `args`, writing a `BatchResponse` per
+/// line to stdout. Blank lines are skipped.
+///
+/// Fails if the policies, entities, or schema fail to load, or if any line
+/// fails to turn into a request. Denied requests do not make it fail.
+fn authorize_batch(args: &AuthorizeArgs) -> CedarExitCode {
+    let (policies, schema, entities, errs) = load_authorization_inputs(
+        &args.policies,
+        &args.entities_file,
+        args.schema_file.as_ref(),
+        args.schema_format,
+    );
+    if !errs.is_empty() {
+        for err in errs {
+            println!("{err:?}");
+        }
+        return CedarExitCode::Failure;
+    }
+    let authorizer = Authorizer::new();
+    let mut status = CedarExitCode::Success;
+    let mut stdout = std::io::stdout().lock();
+    for (index, line) in std::io::stdin().lines().enumerate() {
+        let line = match line.into_diagnostic() {
+            Ok(line) => line,
+            Err(e) => {
+                println!("{:?}", e.wrap_err("failed to read from stdin"));
+                return CedarExitCode::Failure;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        // `index` starts at 0, but line numbers in messages start at 1
+        let line_number = index + 1;
+        let request = serde_json::from_str::<RequestJSON>(&line)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to parse request on line {line_number}"))
+            .and_then(|qjson| {
+                qjson.into_request(
+                    schema.as_ref(),
+                    args.request.request_validation,
+                    &format!("line {line_number}"),
+                )
+            });
+        let response = match request {
+            Ok(request) => {
+                let auth_start = Instant::now();
+                let ans = authorizer.is_authorized(&request, &policies, &entities);
+                let auth_dur = auth_start.elapsed();
+                BatchResponse {
+                    index,
+                    decision: Some(ans.decision()),
+                    reason: ans
+                        .diagnostics()
+                        .reason()
+                        .map(ToString::to_string)
+                        .collect(),
+                    errors: ans
+                        .diagnostics()
+                        .errors()
+                        .map(ToString::to_string)
+                        .collect(),
+                    authorization_time_micros: args.timing.then_some(auth_dur.as_micros()),
+                }
+            }
+            Err(e) => {
+                status = CedarExitCode::Failure;
+                BatchResponse {
+                    index,
+                    decision: None,
+                    reason: vec![],
+                    errors: vec![e
+                        .chain()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(": ")],
+                    authorization_time_micros: None,
+                }
+            }
+        };
+        // Flush every line, for consumers streaming the output
+        let written = serde_json::to_writer(&mut stdout, &response)
+            .into_diagnostic()
+            .and_then(|()| writeln!(stdout).into_diagnostic())
+            .and_then(|()| stdout.flush().into_diagnostic());
+        if let Err(e) = written {
+            eprintln!("{:?}", e.wrap_err("failed to write to stdout"));
+            return CedarExitCode::Failure;
+        }
+    }
+    status
+}
+
 /// Load an `Entities` object from the given JSON filename and optional schema.
 fn load_entities(entities_filename: impl AsRef<Path>, schema: Option<&Schema>) -> Result<Entities> {
     match std::fs::OpenOptions::new()
@@ -1157,6 +1756,38 @@ fn execute_request(
     schema_format: SchemaFormat,
     compute_duration: bool,
 ) -> Result<Response, Vec<Report>> {
+    let (policies, schema, entities, mut errs) =
+        load_authorization_inputs(policies, entities_filename, schema_filename, schema_format);
+    match request.get_request(schema.as_ref()) {
+        Ok(request) if errs.is_empty() => {
+            let authorizer = Authorizer::new();
+            let auth_start = Instant::now();
+            let ans = authorizer.is_authorized(&request, &policies, &entities);
+            let auth_dur = auth_start.elapsed();
+            if compute_duration {
+                println!(
+                    "Authorization Time (micro seconds) : {}",
+                    auth_dur.as_micros()
+                );
+            }
+            Ok(ans)
+        }
+        Ok(_) => Err(errs),
+        Err(e) => {
+            errs.push(e.wrap_err("failed to parse request"));
+            Err(errs)
+        }
+    }
+}
+
+/// Load the policies, optional schema, and entities to authorize requests
+/// against, along with every error encountered while loading them
+fn load_authorization_inputs(
+    policies: &PoliciesArgs,
+    entities_filename: impl AsRef<Path>,
+    schema_filename: Option<impl AsRef<Path> + std::marker::Copy>,
+    schema_format: SchemaFormat,
+) -> (PolicySet, Option<Schema>, Entities, Vec<Report>) {
     let mut errs = vec![];
     let policies = match policies.get_policy_set() {
         Ok(pset) => pset,
@@ -1180,24 +1811,5 @@ fn execute_request(
             Entities::empty()
         }
     };
-    match request.get_request(schema.as_ref()) {
-        Ok(request) if errs.is_empty() => {
-            let authorizer = Authorizer::new();
-            let auth_start = Instant::now();
-            let ans = authorizer.is_authorized(&request, &policies, &entities);
-            let auth_dur = auth_start.elapsed();
-            if compute_duration {
-                println!(
-                    "Authorization Time (micro seconds) : {}",
-                    auth_dur.as_micros()
-                );
-            }
-            Ok(ans)
-        }
-        Ok(_) => Err(errs),
-        Err(e) => {
-            errs.push(e.wrap_err("failed to parse request"));
-            Err(errs)
-        }
-    }
+    (policies, schema, entities, errs)
 }
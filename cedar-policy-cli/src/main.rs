@@ -20,8 +20,8 @@ use clap::Parser;
 use miette::ErrorHook;
 
 use cedar_policy_cli::{
-    authorize, check_parse, evaluate, format_policies, link, new, translate_schema, validate,
-    CedarExitCode, Cli, Commands, ErrorFormat,
+    actions, analyze, authorize, check_parse, evaluate, format_policies, link, new,
+    translate_schema, validate, CedarExitCode, Cli, Commands, ErrorFormat,
 };
 
 fn main() -> CedarExitCode {
@@ -43,8 +43,10 @@ fn main() -> CedarExitCode {
     match cli.command {
         Commands::Authorize(args) => authorize(&args),
         Commands::Evaluate(args) => evaluate(&args).0,
+        Commands::Actions(args) => actions(&args),
         Commands::CheckParse(args) => check_parse(&args),
         Commands::Validate(args) => validate(&args),
+        Commands::Analyze(args) => analyze(&args),
         Commands::Format(args) => format_policies(&args),
         Commands::Link(args) => link(&args),
         Commands::TranslateSchema(args) => translate_schema(&args),
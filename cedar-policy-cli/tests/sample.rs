@@ -18,6 +18,7 @@
 #![allow(clippy::expect_used)]
 // PANIC SAFETY tests
 #![allow(clippy::unwrap_used)]
+use predicates::prelude::*;
 use std::collections::HashMap;
 
 use cedar_policy::EvalResult;
@@ -25,8 +26,9 @@ use cedar_policy::SlotId;
 use cedar_policy_cli::check_parse;
 use cedar_policy_cli::SchemaFormat;
 use cedar_policy_cli::{
-    authorize, evaluate, link, validate, Arguments, AuthorizeArgs, CedarExitCode, CheckParseArgs,
-    EvaluateArgs, LinkArgs, PoliciesArgs, PolicyFormat, RequestArgs, ValidateArgs,
+    action_hierarchy, actions, analyze, authorize, evaluate, link, validate, ActionsArgs,
+    ActionsFormat, AnalysisFormat, AnalyzeArgs, Arguments, AuthorizeArgs, CedarExitCode,
+    CheckParseArgs, EvaluateArgs, LinkArgs, PoliciesArgs, PolicyFormat, RequestArgs, ValidateArgs,
 };
 
 fn run_check_parse_test(policies_file: impl Into<String>, expected_exit_code: CedarExitCode) {
@@ -88,6 +90,7 @@ fn run_authorize_test_with_linked_policies(
         entities_file: entities_file.into(),
         verbose: true,
         timing: false,
+        batch: false,
     };
     let output = authorize(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd,);
@@ -164,6 +167,7 @@ fn run_authorize_test_context(
         entities_file: entities_file.into(),
         verbose: true,
         timing: false,
+        batch: false,
     };
     let output = authorize(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd,);
@@ -194,6 +198,7 @@ fn run_authorize_test_json(
         entities_file: entities_file.into(),
         verbose: true,
         timing: false,
+        batch: false,
     };
     let output = authorize(&cmd);
     assert_eq!(exit_code, output, "{:#?}", cmd,);
@@ -614,6 +619,181 @@ fn test_validate_samples() {
     );
 }
 
+#[track_caller]
+fn run_analyze_test(
+    policies_file: impl Into<String>,
+    schema_file: impl Into<String>,
+    exit_code: CedarExitCode,
+) {
+    let cmd = AnalyzeArgs {
+        schema_file: schema_file.into(),
+        policies: PoliciesArgs {
+            policies_file: Some(policies_file.into()),
+            policy_format: PolicyFormat::Human,
+            template_linked_file: None,
+        },
+        schema_format: SchemaFormat::Human,
+        output_format: AnalysisFormat::Text,
+    };
+    let output = analyze(&cmd);
+    assert_eq!(exit_code, output, "{:#?}", cmd)
+}
+
+#[test]
+fn test_analyze_samples() {
+    run_analyze_test(
+        "sample-data/doesnotexist.cedar",
+        "sample-data/tiny_sandboxes/analyze/schema.cedarschema",
+        CedarExitCode::Failure,
+    );
+    run_analyze_test(
+        "sample-data/tiny_sandboxes/analyze/policies_clean.cedar",
+        "sample-data/tiny_sandboxes/analyze/schema.cedarschema",
+        CedarExitCode::Success,
+    );
+    // Contains conflicting, shadowed, and impossible policies.
+    run_analyze_test(
+        "sample-data/tiny_sandboxes/analyze/policies.cedar",
+        "sample-data/tiny_sandboxes/analyze/schema.cedarschema",
+        CedarExitCode::ValidationFailure,
+    );
+}
+
+#[track_caller]
+fn run_actions_test(action: impl Into<String>, exit_code: CedarExitCode) {
+    let cmd = ActionsArgs {
+        schema_file: "sample-data/tiny_sandboxes/actions/schema.cedarschema".into(),
+        schema_format: SchemaFormat::Human,
+        action: action.into(),
+        output_format: ActionsFormat::Text,
+    };
+    let output = actions(&cmd);
+    assert_eq!(exit_code, output, "{:#?}", cmd)
+}
+
+#[test]
+fn test_actions_samples() {
+    run_actions_test("Action::\"manage\"", CedarExitCode::Success);
+    run_actions_test("Action::\"view\"", CedarExitCode::Success);
+    // Not declared in the schema
+    run_actions_test("Action::\"share\"", CedarExitCode::Failure);
+    // Not an entity uid
+    run_actions_test("manage", CedarExitCode::Failure);
+}
+
+#[test]
+fn test_action_hierarchy() {
+    let schema_src =
+        std::fs::read_to_string("sample-data/tiny_sandboxes/actions/schema.cedarschema").unwrap();
+    let (schema, _) = cedar_policy::Schema::from_str_natural(&schema_src).unwrap();
+    let manage = r#"Action::"manage""#.parse().unwrap();
+    let hierarchy = action_hierarchy(&schema, &manage).unwrap();
+    assert_eq!(hierarchy.action.action, r#"Action::"manage""#);
+    assert!(hierarchy.action.principals.is_empty());
+    let members = hierarchy
+        .members
+        .iter()
+        .map(|member| {
+            (
+                member.action.as_str(),
+                member.principals.join(","),
+                member.resources.join(","),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        members,
+        vec![
+            (
+                r#"Action::"delete""#,
+                "User,UserGroup".to_string(),
+                "Photo".to_string()
+            ),
+            (
+                r#"Action::"edit""#,
+                "User".to_string(),
+                "Album,Photo".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_actions_json_output() {
+    let output = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("actions")
+        .arg("-s")
+        .arg("sample-data/tiny_sandboxes/actions/schema.cedarschema")
+        .arg("--action")
+        .arg(r#"Action::"edit""#)
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let hierarchy: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        hierarchy,
+        serde_json::json!({
+            "action": r#"Action::"edit""#,
+            "principals": ["User"],
+            "resources": ["Album", "Photo"],
+            "members": [{
+                "action": r#"Action::"delete""#,
+                "principals": ["User", "UserGroup"],
+                "resources": ["Photo"],
+            }],
+        })
+    );
+}
+
+#[test]
+fn test_analyze_json_output() {
+    let output = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("analyze")
+        .arg("-p")
+        .arg("sample-data/tiny_sandboxes/analyze/policies.cedar")
+        .arg("-s")
+        .arg("sample-data/tiny_sandboxes/analyze/schema.cedarschema")
+        .arg("--output-format")
+        .arg("json")
+        .assert()
+        .code(3)
+        .get_output()
+        .stdout
+        .clone();
+    let findings: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let findings = findings
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|finding| {
+            let policies = finding["policies"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|id| id.as_str().unwrap())
+                .collect::<Vec<_>>();
+            (finding["code"].as_str().unwrap(), policies.join(","))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        findings,
+        vec![
+            ("conflicting-scopes", "policy0,policy1".to_string()),
+            ("shadowed-permit", "policy0,policy1".to_string()),
+            ("impossible-policy", "policy2".to_string()),
+            ("invalid-action-application", "policy2".to_string()),
+            ("shadowed-permit", "policy2,policy4".to_string()),
+            ("shadowed-permit", "policy3,policy4".to_string()),
+        ]
+    );
+}
+
 fn run_evaluate_test(
     request_json_file: impl Into<String>,
     entities_file: impl Into<String>,
@@ -633,7 +813,8 @@ fn run_evaluate_test(
             request_json_file: Some(request_json_file.into()),
             request_validation: true,
         },
-        expression: expression.into(),
+        expression: Some(expression.into()),
+        expr: None,
     };
     let output = evaluate(&cmd);
     assert_eq!(exit_code, output.0, "{:#?}", cmd,);
@@ -749,6 +930,46 @@ fn test_evaluate_samples() {
     );
 }
 
+#[test]
+fn test_eval_with_expr_flag() {
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("eval")
+        .arg("--expr")
+        .arg("principal.age + 1")
+        .arg("--principal")
+        .arg(r#"User::"alice""#)
+        .arg("--action")
+        .arg(r#"Action::"view""#)
+        .arg("--resource")
+        .arg(r#"Photo::"VacationPhoto94.jpg""#)
+        .arg("--entities")
+        .arg("sample-data/tiny_sandboxes/sample1/entity.json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("19"));
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("eval")
+        .arg("--expr")
+        .arg("principal.height")
+        .arg("--principal")
+        .arg(r#"User::"alice""#)
+        .arg("--action")
+        .arg(r#"Action::"view""#)
+        .arg("--resource")
+        .arg(r#"Photo::"VacationPhoto94.jpg""#)
+        .arg("--entities")
+        .arg("sample-data/tiny_sandboxes/sample1/entity.json")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(
+            "failed to evaluate the expression",
+        ))
+        .stdout(predicate::str::contains("principal.height"));
+}
+
 #[test]
 fn test_link_samples() {
     run_authorize_test(
@@ -979,7 +1200,27 @@ fn test_format_check() {
         .arg(POLICY_REQUIRING_FORMAT)
         .arg("-c")
         .assert()
-        .code(1);
+        .code(1)
+        .stdout(
+            predicates::str::starts_with(format!(
+                "--- {POLICY_REQUIRING_FORMAT}\n+++ {POLICY_REQUIRING_FORMAT} (formatted)\n@@"
+            ))
+            .and(predicates::str::contains("\n-permit\n"))
+            .and(predicates::str::contains(
+                "\n+permit (\n+  principal == User::\"alice\",\n",
+            )),
+        );
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("format")
+        .arg("-p")
+        .arg(POLICY_REQUIRING_FORMAT)
+        .arg("-c")
+        .arg("-q")
+        .assert()
+        .code(1)
+        .stdout(predicates::str::is_empty());
 
     assert_cmd::Command::cargo_bin("cedar")
         .expect("bin exists")
@@ -988,7 +1229,69 @@ fn test_format_check() {
         .arg(POLICY_ALREADY_FORMATTED)
         .arg("-c")
         .assert()
-        .code(0);
+        .code(0)
+        .stdout(predicates::str::is_empty());
+}
+
+#[test]
+fn test_format_sort() {
+    const UNSORTED: &str = r#"// only admins may delete
+@id("delete")
+forbid (principal, action == Action::"delete", resource)
+unless { principal.is_admin };
+
+@id("view")
+permit (principal, action == Action::"view", resource);
+
+@id("admin")
+permit (principal, action, resource)
+when { principal.is_admin };
+"#;
+    let tmp_dir = env!("CARGO_TARGET_TMPDIR");
+    let policies_file = format!("{tmp_dir}/unsorted.cedar");
+    std::fs::write(&policies_file, UNSORTED).unwrap();
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("format")
+        .arg("-p")
+        .arg(&policies_file)
+        .arg("-c")
+        .arg("-q")
+        .arg("--sort")
+        .assert()
+        .code(1);
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("format")
+        .arg("-p")
+        .arg(&policies_file)
+        .arg("-w")
+        .arg("--sort")
+        .assert()
+        .success();
+    let sorted = std::fs::read_to_string(&policies_file).unwrap();
+    let ids = sorted
+        .lines()
+        .filter(|line| line.starts_with("@id"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ids,
+        vec![r#"@id("admin")"#, r#"@id("delete")"#, r#"@id("view")"#]
+    );
+    assert!(sorted.contains("// only admins may delete\n@id(\"delete\")"));
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("format")
+        .arg("-p")
+        .arg(&policies_file)
+        .arg("-c")
+        .arg("--sort")
+        .assert()
+        .code(0)
+        .stdout(predicates::str::is_empty());
 }
 
 #[test]
@@ -1021,3 +1324,144 @@ fn test_require_policies_for_write() {
             "the following required arguments were not provided:\n  --policies <FILE>",
         ));
 }
+
+#[test]
+fn test_authorize_batch() {
+    let requests = [
+        r#"{"principal": "User::\"tim\"", "action": "Action::\"view\"", "resource": "Photo::\"VacationPhoto94.jpg\"", "context": {}}"#,
+        "",
+        r#"{"principal": "User::\"bob\"", "action": "Action::\"delete\"", "resource": "Photo::\"VacationPhoto94.jpg\"", "context": {}}"#,
+        r#"{"principal": "User::\"bob\"", "action": "Action::\"view\"", "context": {}"#,
+    ];
+    let output = assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("authorize")
+        .arg("--batch")
+        .arg("--policies")
+        .arg("sample-data/sandbox_a/policies_1.cedar")
+        .arg("--entities")
+        .arg("sample-data/sandbox_a/entities.json")
+        .write_stdin(requests.join("\n"))
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let lines = String::from_utf8(output).unwrap();
+    let mut lines = lines
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap());
+    assert_eq!(
+        lines.next(),
+        Some(serde_json::json!({
+            "index": 0,
+            "decision": "Deny",
+            "reason": ["disallow tim policy"],
+            "errors": [],
+        }))
+    );
+    // The blank line is skipped, but still counts towards the index
+    assert_eq!(
+        lines.next(),
+        Some(serde_json::json!({ "index": 2, "decision": "Deny", "reason": [], "errors": [] }))
+    );
+    // The index starts at 0, but error messages count lines from 1
+    let last = lines.next().unwrap();
+    assert_eq!(last.get("index"), Some(&serde_json::json!(3)));
+    assert_eq!(last.get("decision"), Some(&serde_json::Value::Null));
+    assert!(last
+        .get("errors")
+        .and_then(|errors| errors.get(0))
+        .and_then(serde_json::Value::as_str)
+        .unwrap()
+        .starts_with("failed to parse request on line 4"));
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn test_require_policies_for_batch() {
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("authorize")
+        .arg("--batch")
+        .arg("--entities")
+        .arg("sample-data/sandbox_a/entities.json")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "the following required arguments were not provided:\n  --policies <FILE>",
+        ));
+}
+
+#[test]
+fn test_translate_schema_from_to() {
+    let translate = |args: &[&str]| {
+        let output = assert_cmd::Command::cargo_bin("cedar")
+            .expect("bin exists")
+            .arg("translate-schema")
+            .args(args)
+            .arg("-s")
+            .arg("sample-data/sandbox_a/schema.cedarschema.json")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        // The order of declarations in the output isn't deterministic
+        let mut lines = String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines
+    };
+    let with_direction = translate(&["--direction", "json-to-human"]);
+    assert!(with_direction.iter().any(|line| line.starts_with("entity")));
+    assert_eq!(
+        translate(&["--from", "json", "--to", "cedar"]),
+        with_direction
+    );
+    assert_eq!(
+        translate(&["--from", "json", "--to", "human"]),
+        with_direction
+    );
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("translate-schema")
+        .args(["--from", "json", "--to", "json"])
+        .arg("-s")
+        .arg("sample-data/sandbox_a/schema.cedarschema.json")
+        .assert()
+        .failure();
+
+    assert_cmd::Command::cargo_bin("cedar")
+        .expect("bin exists")
+        .arg("translate-schema")
+        .args(["--from", "json"])
+        .arg("-s")
+        .arg("sample-data/sandbox_a/schema.cedarschema.json")
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_translate_schema_check_roundtrip() {
+    for (from, to, schema_file) in [
+        (
+            "json",
+            "cedar",
+            "sample-data/sandbox_a/schema.cedarschema.json",
+        ),
+        ("cedar", "json", "sample-data/sandbox_a/schema.cedarschema"),
+    ] {
+        assert_cmd::Command::cargo_bin("cedar")
+            .expect("bin exists")
+            .arg("translate-schema")
+            .args(["--from", from, "--to", to, "--check-roundtrip"])
+            .arg("-s")
+            .arg(schema_file)
+            .assert()
+            .success()
+            .stdout("");
+    }
+}
@@ -35,6 +35,7 @@ pub use ops::*;
 mod pattern;
 pub use pattern::*;
 mod partial_value;
+mod rebase;
 pub use partial_value::*;
 mod policy;
 pub use policy::*;
@@ -192,6 +192,35 @@ impl EntityUID {
     pub fn is_action(&self) -> bool {
         self.entity_type().is_action()
     }
+
+    /// Get the normalized form of this `EntityUID`, which doesn't depend on
+    /// the source it was parsed from.
+    ///
+    /// The type path is always stored as its components, so whitespace and
+    /// comments around the `::` separators (as in `User :: "alice"`) are
+    /// never part of it, and it is displayed with plain `::` separators. The
+    /// normalized form additionally drops the source locations of the
+    /// `EntityUID` and its type. The EID is kept exactly as is, because
+    /// entity ids are case- and whitespace-sensitive: `User::" alice"` is a
+    /// different entity from `User::"alice"`.
+    ///
+    /// Two `EntityUID`s refer to the same entity exactly when their normalized
+    /// forms are equal, which is what `PartialEq` and `Hash` compare.
+    pub fn normalize(&self) -> Self {
+        let ty = match &self.ty {
+            EntityType::Specified(name) => EntityType::Specified(Name::new(
+                name.basename().clone(),
+                name.namespace_components().cloned(),
+                None,
+            )),
+            EntityType::Unspecified => EntityType::Unspecified,
+        };
+        Self {
+            ty,
+            eid: self.eid.clone(),
+            loc: None,
+        }
+    }
 }
 
 impl std::fmt::Display for EntityUID {
@@ -278,6 +307,11 @@ pub struct Entity {
     /// Set of ancestors of this `Entity` (i.e., all direct and transitive
     /// parents), as UIDs
     ancestors: HashSet<EntityUID>,
+
+    /// Set of parents of this `Entity`, as UIDs, as they were given when the
+    /// `Entity` was constructed, i.e., before computing the transitive
+    /// closure of the hierarchy
+    parents: HashSet<EntityUID>,
 }
 
 impl Entity {
@@ -305,6 +339,7 @@ impl Entity {
         Ok(Entity {
             uid,
             attrs: evaluated_attrs,
+            parents: ancestors.clone(),
             ancestors,
         })
     }
@@ -321,6 +356,7 @@ impl Entity {
         Entity {
             uid,
             attrs: attrs.into_iter().map(|(k, v)| (k, v.into())).collect(), // TODO(#540): can we do this without disassembling and reassembling the HashMap
+            parents: ancestors.clone(),
             ancestors,
         }
     }
@@ -337,6 +373,7 @@ impl Entity {
         Entity {
             uid,
             attrs,
+            parents: ancestors.clone(),
             ancestors,
         }
     }
@@ -361,6 +398,13 @@ impl Entity {
         self.ancestors.iter()
     }
 
+    /// Iterate over this entity's parents, as they were given when it was
+    /// constructed. Unlike [`Entity::ancestors`], this doesn't include the
+    /// ancestors added by computing the transitive closure of the hierarchy.
+    pub fn parents(&self) -> impl Iterator<Item = &EntityUID> {
+        self.parents.iter()
+    }
+
     /// Iterate over this entity's attributes
     pub fn attrs(&self) -> impl Iterator<Item = (&SmolStr, &PartialValue)> {
         self.attrs.iter().map(|(k, v)| (k, v.as_ref()))
@@ -372,6 +416,7 @@ impl Entity {
             uid,
             attrs: HashMap::new(),
             ancestors: HashSet::new(),
+            parents: HashSet::new(),
         }
     }
 
@@ -383,8 +428,6 @@ impl Entity {
     }
 
     /// Set the given attribute to the given value.
-    // Only used for convenience in some tests and when fuzzing
-    #[cfg(any(test, fuzzing))]
     pub fn set_attr(
         &mut self,
         attr: SmolStr,
@@ -408,6 +451,12 @@ impl Entity {
         self.ancestors.insert(uid);
     }
 
+    /// Reset the ancestors of this `Entity` to its parents, so that the
+    /// transitive closure can be computed again for it.
+    pub(crate) fn reset_ancestors_to_parents(&mut self) {
+        self.ancestors.clone_from(&self.parents);
+    }
+
     /// Consume the entity and return the entity's owned Uid, attributes and parents.
     pub fn into_inner(
         self,
@@ -420,6 +469,7 @@ impl Entity {
             uid,
             attrs,
             ancestors,
+            ..
         } = self;
         (
             uid,
@@ -577,4 +627,31 @@ mod test {
         // e3 and e5 are displayed differently
         assert!(format!("{e3}") != format!("{e5}"));
     }
+
+    #[test]
+    fn normalize() {
+        let variants: Vec<EntityUID> = [
+            r#"NS::User::"alice""#,
+            r#"NS :: User :: "alice""#,
+            "NS::\n  User // comment\n  ::\"alice\"",
+        ]
+        .into_iter()
+        .map(|src| src.parse().expect("should be a valid entity uid"))
+        .collect();
+        for euid in &variants {
+            let normalized = euid.normalize();
+            assert_eq!(&normalized, &variants[0]);
+            assert_eq!(normalized.to_string(), r#"NS::User::"alice""#);
+            assert_eq!(normalized.loc(), None);
+            match normalized.entity_type() {
+                EntityType::Specified(name) => assert_eq!(name.loc(), None),
+                EntityType::Unspecified => panic!("expected a specified entity type"),
+            }
+        }
+
+        // the EID is not trimmed
+        let padded: EntityUID = r#"NS::User::" alice""#.parse().unwrap();
+        assert_ne!(padded.normalize(), variants[0]);
+        assert_eq!(AsRef::<str>::as_ref(padded.normalize().eid()), " alice");
+    }
 }
@@ -16,6 +16,7 @@
 
 use crate::{ast::*, parser::err::ParseErrors, parser::Loc};
 use miette::Diagnostic;
+use nonempty::NonEmpty;
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use std::{
@@ -112,12 +113,19 @@ pub enum ExprKind<T = ()> {
         /// Attribute or field to get
         attr: SmolStr,
     },
-    /// Does the given `expr` have the given `attr`?
+    /// Does the given `expr` have the given `attr`, and, if `path` isn't
+    /// empty, does that attribute have the nested attributes in `path`?
+    ///
+    /// `e has a.b.c` is `true` when `e has a`, `e.a has b`, and `e.a.b has c`
+    /// are all `true`. Unlike that conjunction, it is `false`, rather than an
+    /// error, when `e.a` or `e.a.b` is neither an entity nor a record.
     HasAttr {
         /// Expression to test. Must evaluate to either Entity or Record type
         expr: Arc<Expr<T>>,
         /// Attribute or field to check for
         attr: SmolStr,
+        /// Attributes or fields to check for within `attr`, in order
+        path: Vec<SmolStr>,
     },
     /// Regex-like string matching similar to IAM's `StringLike` operator.
     Like {
@@ -466,6 +474,14 @@ impl Expr {
         ExprBuilder::new().has_attr(expr, attr)
     }
 
+    /// Create an `Expr` which tests for the existence of a path of nested
+    /// attributes, like `expr has a.b.c`.
+    ///
+    /// `expr` must evaluate to either Entity or Record type
+    pub fn has_attr_path(expr: Expr, path: NonEmpty<SmolStr>) -> Self {
+        ExprBuilder::new().has_attr_path(expr, path)
+    }
+
     /// Create a 'like' expression.
     ///
     /// `expr` must evaluate to a String type
@@ -565,9 +581,9 @@ impl Expr {
                 expr.substitute_general::<T>(definitions)?,
                 attr.clone(),
             )),
-            ExprKind::HasAttr { expr, attr } => Ok(Expr::has_attr(
+            ExprKind::HasAttr { expr, attr, path } => Ok(Expr::has_attr_path(
                 expr.substitute_general::<T>(definitions)?,
-                attr.clone(),
+                NonEmpty::from((attr.clone(), path.clone())),
             )),
             ExprKind::Like { expr, pattern } => Ok(Expr::like(
                 expr.substitute_general::<T>(definitions)?,
@@ -1050,6 +1066,19 @@ impl<T> ExprBuilder<T> {
         self.with_expr_kind(ExprKind::HasAttr {
             expr: Arc::new(expr),
             attr,
+            path: Vec::new(),
+        })
+    }
+
+    /// Create an `Expr` which tests for the existence of a path of nested
+    /// attributes, like `expr has a.b.c`.
+    ///
+    /// `expr` must evaluate to either Entity or Record type
+    pub fn has_attr_path(self, expr: Expr<T>, path: NonEmpty<SmolStr>) -> Expr<T> {
+        self.with_expr_kind(ExprKind::HasAttr {
+            expr: Arc::new(expr),
+            attr: path.head,
+            path: path.tail,
         })
     }
 
@@ -1237,14 +1266,15 @@ impl<T> Expr<T> {
                     expr: expr1,
                     attr: attr1,
                 },
-            )
-            | (
-                HasAttr { expr, attr },
+            ) => attr == attr1 && expr.eq_shape(expr1),
+            (
+                HasAttr { expr, attr, path },
                 HasAttr {
                     expr: expr1,
                     attr: attr1,
+                    path: path1,
                 },
-            ) => attr == attr1 && expr.eq_shape(expr1),
+            ) => attr == attr1 && path == path1 && expr.eq_shape(expr1),
             (
                 Like { expr, pattern },
                 Like {
@@ -1324,9 +1354,10 @@ impl<T> Expr<T> {
                 expr.hash_shape(state);
                 attr.hash(state);
             }
-            ExprKind::HasAttr { expr, attr } => {
+            ExprKind::HasAttr { expr, attr, path } => {
                 expr.hash_shape(state);
                 attr.hash(state);
+                path.hash(state);
             }
             ExprKind::Like { expr, pattern } => {
                 expr.hash_shape(state);
@@ -1502,7 +1533,8 @@ mod test {
                         None,
                         ()
                     )),
-                    attr: "some_attr".into()
+                    attr: "some_attr".into(),
+                    path: Vec::new(),
                 },
                 None,
                 ()
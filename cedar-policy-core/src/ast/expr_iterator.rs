@@ -79,7 +79,11 @@ impl<'a, T> Iterator for ExprIterator<'a, T> {
             ExprKind::GetAttr { expr, attr: _ } => {
                 self.expression_stack.push(expr);
             }
-            ExprKind::HasAttr { expr, attr: _ } => {
+            ExprKind::HasAttr {
+                expr,
+                attr: _,
+                path: _,
+            } => {
                 self.expression_stack.push(expr);
             }
             ExprKind::Like { expr, pattern: _ } => {
@@ -0,0 +1,457 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains the renaming of entity types, for every kind of AST
+//! node which can refer to an entity type: both with an arbitrary renaming
+//! (`rename_entity_types`), and by rebasing names from one namespace into
+//! another, e.g., from `Vendor` into `MyApp::Vendor` (`rebase_namespace`).
+//!
+//! A name is rebased if it is in the namespace `from` or in a namespace
+//! nested in `from`; other names are left unchanged. Rebasing from `from` to
+//! `to` and then back from `to` to `from` gives the original, unless the
+//! original already referred to names in `to`.
+
+use nonempty::NonEmpty;
+use std::sync::Arc;
+
+use super::{
+    ActionConstraint, EntityReference, EntityType, EntityUID, Expr, ExprBuilder, ExprKind, Literal,
+    Name, PartialValue, PrincipalConstraint, PrincipalOrResourceConstraint, ResourceConstraint,
+    Template, Type, Unknown, Value, ValueKind,
+};
+
+impl Name {
+    /// If this name is in the namespace `from`, or in a namespace nested in
+    /// `from`, get the same name in `to` instead. For instance, rebasing
+    /// `Vendor::Auth::User` from `Vendor` to `MyApp::Vendor` gives
+    /// `MyApp::Vendor::Auth::User`. Other names are returned unchanged.
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> Name {
+        let from_components = from.path.iter().chain(std::iter::once(&from.id));
+        let mut path = self.path.iter();
+        // `zip` stops at the shorter iterator, so also check the length
+        let in_from = self.path.len() > from.path.len()
+            && from_components
+                .zip(path.by_ref())
+                .all(|(expected, actual)| expected == actual);
+        if !in_from {
+            return self.clone();
+        }
+        Name::new(
+            self.id.clone(),
+            to.path
+                .iter()
+                .chain(std::iter::once(&to.id))
+                .chain(path)
+                .cloned(),
+            self.loc.clone(),
+        )
+    }
+}
+
+impl EntityType {
+    /// Rename this entity type with `rename`
+    pub fn rename_entity_types(&self, rename: &impl Fn(&Name) -> Name) -> EntityType {
+        match self {
+            EntityType::Specified(name) => EntityType::Specified(rename(name)),
+            EntityType::Unspecified => EntityType::Unspecified,
+        }
+    }
+
+    /// Rebase the name of this entity type with [`Name::rebase_namespace`]
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> EntityType {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+impl EntityUID {
+    /// Rename the entity type of this UID with `rename`, keeping its id
+    pub fn rename_entity_types(&self, rename: &impl Fn(&Name) -> Name) -> EntityUID {
+        match self.entity_type() {
+            EntityType::Specified(name) => {
+                EntityUID::from_components(rename(name), self.eid().clone(), self.loc().cloned())
+            }
+            EntityType::Unspecified => self.clone(),
+        }
+    }
+
+    /// Rebase the entity type of this UID with [`Name::rebase_namespace`]
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> EntityUID {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+impl Type {
+    /// Rename the entity type in this type, if any, with `rename`
+    pub fn rename_entity_types(&self, rename: &impl Fn(&Name) -> Name) -> Type {
+        match self {
+            Type::Entity { ty } => Type::Entity {
+                ty: ty.rename_entity_types(rename),
+            },
+            ty => ty.clone(),
+        }
+    }
+
+    /// Rebase the entity type in this type, if any, with
+    /// [`Name::rebase_namespace`]
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> Type {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+impl Value {
+    /// Rename every entity type in this value, including in nested sets and
+    /// records, with `rename`
+    pub fn rename_entity_types(&self, rename: &impl Fn(&Name) -> Name) -> Value {
+        match &self.value {
+            ValueKind::Lit(Literal::EntityUID(euid)) => Value::new(
+                Literal::EntityUID(Arc::new(euid.rename_entity_types(rename))),
+                self.loc.clone(),
+            ),
+            ValueKind::Lit(_) | ValueKind::ExtensionValue(_) => self.clone(),
+            ValueKind::Set(set) => Value::set(
+                set.iter().map(|v| v.rename_entity_types(rename)),
+                self.loc.clone(),
+            ),
+            ValueKind::Record(record) => Value::record(
+                record
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.rename_entity_types(rename))),
+                self.loc.clone(),
+            ),
+        }
+    }
+
+    /// Rebase every entity type in this value, including in nested sets and
+    /// records, with [`Name::rebase_namespace`]
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> Value {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+impl PartialValue {
+    /// Rename every entity type in this value or residual with `rename`
+    pub fn rename_entity_types(&self, rename: &impl Fn(&Name) -> Name) -> PartialValue {
+        match self {
+            PartialValue::Value(v) => PartialValue::Value(v.rename_entity_types(rename)),
+            PartialValue::Residual(e) => PartialValue::Residual(e.rename_entity_types(rename)),
+        }
+    }
+
+    /// Rebase every entity type in this value or residual with
+    /// [`Name::rebase_namespace`]
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> PartialValue {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+impl<T: Clone> Expr<T> {
+    /// Rename every entity type in this expression, in entity literals, `is`
+    /// tests, and the type annotations of unknowns, with `rename`. Source
+    /// locations are preserved.
+    pub fn rename_entity_types(&self, rename: &impl Fn(&Name) -> Name) -> Expr<T> {
+        let builder = ExprBuilder::with_data(self.data().clone()).with_same_source_loc(self);
+        let recur = |e: &Expr<T>| e.rename_entity_types(rename);
+        match self.expr_kind() {
+            ExprKind::Lit(Literal::EntityUID(euid)) => builder.val(Literal::EntityUID(Arc::new(
+                euid.rename_entity_types(rename),
+            ))),
+            ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) => self.clone(),
+            ExprKind::Unknown(Unknown {
+                name,
+                type_annotation,
+            }) => builder.unknown(Unknown {
+                name: name.clone(),
+                type_annotation: type_annotation
+                    .as_ref()
+                    .map(|ty| ty.rename_entity_types(rename)),
+            }),
+            ExprKind::If {
+                test_expr,
+                then_expr,
+                else_expr,
+            } => builder.ite(recur(test_expr), recur(then_expr), recur(else_expr)),
+            ExprKind::And { left, right } => builder.and(recur(left), recur(right)),
+            ExprKind::Or { left, right } => builder.or(recur(left), recur(right)),
+            ExprKind::UnaryApp { op, arg } => builder.unary_app(*op, recur(arg)),
+            ExprKind::BinaryApp { op, arg1, arg2 } => {
+                builder.binary_app(*op, recur(arg1), recur(arg2))
+            }
+            ExprKind::ExtensionFunctionApp { fn_name, args } => {
+                builder.call_extension_fn(fn_name.clone(), args.iter().map(recur))
+            }
+            ExprKind::GetAttr { expr, attr } => builder.get_attr(recur(expr), attr.clone()),
+            ExprKind::HasAttr { expr, attr, path } => {
+                builder.has_attr_path(recur(expr), NonEmpty::from((attr.clone(), path.clone())))
+            }
+            ExprKind::Like { expr, pattern } => builder.like(recur(expr), pattern.iter().cloned()),
+            ExprKind::Set(members) => builder.set(members.iter().map(recur)),
+            ExprKind::Record(fields) => builder.record_arc(Arc::new(
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.rename_entity_types(rename)))
+                    .collect(),
+            )),
+            ExprKind::Is { expr, entity_type } => {
+                builder.is_entity_type(recur(expr), rename(entity_type))
+            }
+        }
+    }
+
+    /// Rebase every entity type in this expression, in entity literals, `is`
+    /// tests, and the type annotations of unknowns, with
+    /// [`Name::rebase_namespace`]. Source locations are preserved.
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> Expr<T> {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+impl EntityReference {
+    /// Rename the entity type of this reference, if it isn't a slot, with
+    /// `rename`
+    pub fn rename_entity_types(&self, rename: &impl Fn(&Name) -> Name) -> EntityReference {
+        match self {
+            EntityReference::EUID(euid) => EntityReference::euid(euid.rename_entity_types(rename)),
+            EntityReference::Slot => EntityReference::Slot,
+        }
+    }
+
+    /// Rebase the entity type of this reference, if it isn't a slot, with
+    /// [`Name::rebase_namespace`]
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> EntityReference {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+impl PrincipalOrResourceConstraint {
+    /// Rename every entity type in this constraint with `rename`
+    pub fn rename_entity_types(
+        &self,
+        rename: &impl Fn(&Name) -> Name,
+    ) -> PrincipalOrResourceConstraint {
+        match self {
+            PrincipalOrResourceConstraint::Any => PrincipalOrResourceConstraint::Any,
+            PrincipalOrResourceConstraint::In(eref) => {
+                PrincipalOrResourceConstraint::In(eref.rename_entity_types(rename))
+            }
+            PrincipalOrResourceConstraint::Eq(eref) => {
+                PrincipalOrResourceConstraint::Eq(eref.rename_entity_types(rename))
+            }
+            PrincipalOrResourceConstraint::Is(name) => {
+                PrincipalOrResourceConstraint::Is(rename(name))
+            }
+            PrincipalOrResourceConstraint::IsIn(name, eref) => {
+                PrincipalOrResourceConstraint::IsIn(rename(name), eref.rename_entity_types(rename))
+            }
+        }
+    }
+
+    /// Rebase every entity type in this constraint with
+    /// [`Name::rebase_namespace`]
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> PrincipalOrResourceConstraint {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+impl ActionConstraint {
+    /// Rename the entity type of every action in this constraint with
+    /// `rename`
+    pub fn rename_entity_types(&self, rename: &impl Fn(&Name) -> Name) -> ActionConstraint {
+        let recur = |euid: &Arc<EntityUID>| Arc::new(euid.rename_entity_types(rename));
+        match self {
+            ActionConstraint::Any => ActionConstraint::Any,
+            ActionConstraint::In(euids) => ActionConstraint::In(euids.iter().map(recur).collect()),
+            ActionConstraint::Eq(euid) => ActionConstraint::Eq(recur(euid)),
+        }
+    }
+
+    /// Rebase every action in this constraint with
+    /// [`Name::rebase_namespace`]
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> ActionConstraint {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+impl Template {
+    /// Rename every entity type in the scope and conditions of this template
+    /// with `rename`. The id, annotations, and effect of the template are
+    /// unchanged.
+    pub fn rename_entity_types(&self, rename: &impl Fn(&Name) -> Name) -> Template {
+        Template::new_shared(
+            self.id().clone(),
+            self.loc().clone(),
+            self.annotations_arc().clone(),
+            self.effect(),
+            PrincipalConstraint::new(
+                self.principal_constraint()
+                    .as_inner()
+                    .rename_entity_types(rename),
+            ),
+            self.action_constraint().rename_entity_types(rename),
+            ResourceConstraint::new(
+                self.resource_constraint()
+                    .as_inner()
+                    .rename_entity_types(rename),
+            ),
+            Arc::new(self.non_scope_constraints().rename_entity_types(rename)),
+        )
+    }
+
+    /// Rebase every entity type in the scope and conditions of this template
+    /// with [`Name::rebase_namespace`]. The id, annotations, and effect of the
+    /// template are unchanged.
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> Template {
+        self.rename_entity_types(&|name| name.rebase_namespace(from, to))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_policy_template;
+
+    fn name(s: &str) -> Name {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn names() {
+        let (from, to) = (name("Vendor"), name("MyApp::Vendor"));
+        assert_eq!(
+            name("Vendor::User").rebase_namespace(&from, &to),
+            name("MyApp::Vendor::User")
+        );
+        assert_eq!(
+            name("Vendor::Auth::User").rebase_namespace(&from, &to),
+            name("MyApp::Vendor::Auth::User")
+        );
+        // Not in `Vendor`
+        assert_eq!(name("Vendor").rebase_namespace(&from, &to), name("Vendor"));
+        assert_eq!(
+            name("Other::Vendor::User").rebase_namespace(&from, &to),
+            name("Other::Vendor::User")
+        );
+        assert_eq!(
+            name("VendorX::User").rebase_namespace(&from, &to),
+            name("VendorX::User")
+        );
+        // Rebasing out of a nested namespace
+        assert_eq!(
+            name("MyApp::Vendor::User").rebase_namespace(&to, &from),
+            name("Vendor::User")
+        );
+    }
+
+    #[test]
+    fn values() {
+        let (from, to) = (name("Vendor"), name("App"));
+        let euid: EntityUID = r#"Vendor::User::"alice""#.parse().unwrap();
+        let value = Value::record(
+            [
+                ("owner", Value::from(euid.clone())),
+                ("viewers", Value::set([Value::from(euid)], None)),
+                ("level", Value::from(1)),
+            ],
+            None,
+        );
+        let alice: EntityUID = r#"App::User::"alice""#.parse().unwrap();
+        let expected = Value::record(
+            [
+                ("owner", Value::from(alice.clone())),
+                ("viewers", Value::set([Value::from(alice)], None)),
+                ("level", Value::from(1)),
+            ],
+            None,
+        );
+        assert_eq!(value.rebase_namespace(&from, &to), expected);
+    }
+
+    #[test]
+    fn templates() {
+        let (from, to) = (name("Vendor"), name("MyApp::Vendor"));
+        let template = parse_policy_template(
+            Some("t".to_string()),
+            r#"@id("t")
+            permit(
+                principal in ?principal,
+                action in [Vendor::Action::"read", Action::"write"],
+                resource is Vendor::Doc in Vendor::Folder::"root"
+            ) when {
+                principal is Vendor::User &&
+                resource.owner == Vendor::User::"alice" &&
+                context.user in [Vendor::User::"bob", Other::User::"carol"]
+            };"#,
+        )
+        .unwrap();
+        let rebased = template.rebase_namespace(&from, &to);
+        let expected = parse_policy_template(
+            Some("t".to_string()),
+            r#"@id("t")
+            permit(
+                principal in ?principal,
+                action in [MyApp::Vendor::Action::"read", Action::"write"],
+                resource is MyApp::Vendor::Doc in MyApp::Vendor::Folder::"root"
+            ) when {
+                principal is MyApp::Vendor::User &&
+                resource.owner == MyApp::Vendor::User::"alice" &&
+                context.user in [MyApp::Vendor::User::"bob", Other::User::"carol"]
+            };"#,
+        )
+        .unwrap();
+        assert_eq!(rebased.to_string(), expected.to_string());
+        // Source locations are kept
+        assert_eq!(
+            rebased.non_scope_constraints().source_loc(),
+            template.non_scope_constraints().source_loc()
+        );
+        // Rebasing back gives the original
+        assert_eq!(
+            rebased.rebase_namespace(&to, &from).to_string(),
+            template.to_string()
+        );
+    }
+
+    #[test]
+    fn rename_entity_types() {
+        let template = parse_policy_template(
+            Some("t".to_string()),
+            r#"permit(
+                principal is Legacy::User in ?principal,
+                action == Action::"read",
+                resource == Legacy::Doc::"readme"
+            ) when {
+                principal is Legacy::User && context.users.contains(Legacy::User::"alice") &&
+                resource.folder != Legacy::UserFolder::"alice"
+            };"#,
+        )
+        .unwrap();
+        let (from, to) = (name("Legacy::User"), name("Corp::User"));
+        let renamed =
+            template.rename_entity_types(&|n| if n == &from { to.clone() } else { n.clone() });
+        let expected = parse_policy_template(
+            Some("t".to_string()),
+            r#"permit(
+                principal is Corp::User in ?principal,
+                action == Action::"read",
+                resource == Legacy::Doc::"readme"
+            ) when {
+                principal is Corp::User && context.users.contains(Corp::User::"alice") &&
+                resource.folder != Legacy::UserFolder::"alice"
+            };"#,
+        )
+        .unwrap();
+        assert_eq!(renamed.to_string(), expected.to_string());
+    }
+}
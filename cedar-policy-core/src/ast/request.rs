@@ -23,6 +23,7 @@ use crate::parser::Loc;
 use miette::Diagnostic;
 use serde::Serialize;
 use smol_str::SmolStr;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -336,6 +337,94 @@ impl Context {
             v => panic!("internal invariant violation: expected a record, got {v:?}"),
         }
     }
+
+    /// Get the value of the attribute `key`; or return `None` if the `Context`
+    /// has no such attribute, or is purely unknown
+    //
+    // PANIC SAFETY: This is safe due to the invariant on `self.context`, `self.context` must always be a record
+    pub fn get(&self, key: &str) -> Option<PartialValue> {
+        // PANIC SAFETY invariant on `self.context` ensures that it is a record
+        #[allow(clippy::panic)]
+        match self.context.as_ref() {
+            PartialValue::Value(Value {
+                value: ValueKind::Record(record),
+                ..
+            }) => record.get(key).cloned().map(PartialValue::Value),
+            PartialValue::Residual(expr) => match expr.expr_kind() {
+                ExprKind::Record(map) => map.get(key).cloned().map(PartialValue::Residual),
+                ExprKind::Unknown(_) => None,
+                kind => panic!("internal invariant violation: expected a record, got {kind:?}"),
+            },
+            v => panic!("internal invariant violation: expected a record, got {v:?}"),
+        }
+    }
+
+    /// Iterate over the attribute names in the `Context`, in sorted order.
+    /// This is empty if the `Context` is purely unknown.
+    pub fn keys(&self) -> impl Iterator<Item = &SmolStr> {
+        self.iter().into_iter().flatten().map(|(k, _)| k)
+    }
+
+    /// Fill in the given default values for any attributes that are not
+    /// already present in the `Context`. Attributes that are present keep
+    /// their existing values. If the `Context` is purely unknown, it is
+    /// returned unchanged.
+    //
+    // INVARIANT(ContextRecord): a record is only ever replaced by a record
+    pub fn with_defaults(
+        self,
+        defaults: impl IntoIterator<Item = (SmolStr, PartialValue)>,
+    ) -> Self {
+        let context = match PartialValue::from(self.context) {
+            PartialValue::Value(Value {
+                value: ValueKind::Record(mut record),
+                loc,
+            }) => {
+                let mut residuals = BTreeMap::new();
+                for (k, v) in defaults {
+                    if record.contains_key(&k) {
+                        continue;
+                    }
+                    match v {
+                        PartialValue::Value(v) => {
+                            Arc::make_mut(&mut record).insert(k, v);
+                        }
+                        PartialValue::Residual(e) => {
+                            residuals.insert(k, e);
+                        }
+                    }
+                }
+                if residuals.is_empty() {
+                    PartialValue::Value(Value {
+                        value: ValueKind::Record(record),
+                        loc,
+                    })
+                } else {
+                    // a residual default makes the whole context residual
+                    residuals.extend(
+                        record
+                            .iter()
+                            .map(|(k, v)| (k.clone(), Expr::from(v.clone()))),
+                    );
+                    PartialValue::Residual(Expr::record_arc(Arc::new(residuals)))
+                }
+            }
+            PartialValue::Residual(expr) => match expr.expr_kind() {
+                ExprKind::Record(map) => {
+                    let mut map = map.as_ref().clone();
+                    for (k, v) in defaults {
+                        map.entry(k).or_insert_with(|| v.into());
+                    }
+                    PartialValue::Residual(Expr::record_arc(Arc::new(map)))
+                }
+                _ => PartialValue::Residual(expr),
+            },
+            v => v,
+        };
+        Self {
+            context: context.into(),
+        }
+    }
 }
 
 impl AsRef<PartialValue> for Context {
@@ -444,4 +533,24 @@ mod test {
             ))
         );
     }
+
+    #[test]
+    fn with_defaults_fills_missing_attrs() {
+        let context =
+            Context::from_pairs([("a".into(), RestrictedExpr::val(1))], Extensions::none())
+                .unwrap()
+                .with_defaults([
+                    ("a".into(), PartialValue::from(2)),
+                    ("b".into(), PartialValue::from(true)),
+                ]);
+        let expected = Context::from_pairs(
+            [
+                ("a".into(), RestrictedExpr::val(1)),
+                ("b".into(), RestrictedExpr::val(true)),
+            ],
+            Extensions::none(),
+        )
+        .unwrap();
+        assert_eq!(context, expected);
+    }
 }
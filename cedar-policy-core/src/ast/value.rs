@@ -149,9 +149,38 @@ impl Value {
     pub fn eq_and_same_source_loc(&self, other: &Self) -> bool {
         self == other && self.source_loc() == other.source_loc()
     }
+
+    /// Render this value in Cedar syntax, e.g., `[1, 2, 3]`, `{"a": "x"}`,
+    /// `User::"alice"`, or `ip("10.0.0.1")`. Unlike the `Display`
+    /// implementation, which is meant for error messages and abbreviates
+    /// large sets and records, this prints the entire value, and parsing the
+    /// result as an expression and evaluating it gives back an equal value.
+    ///
+    /// Set elements and record attributes are printed in sorted order, so the
+    /// result is deterministic.
+    pub fn to_cedar_str(&self) -> String {
+        self.value.to_cedar_str()
+    }
 }
 
 impl ValueKind {
+    /// Render this value in Cedar syntax. See [`Value::to_cedar_str()`].
+    pub fn to_cedar_str(&self) -> String {
+        match self {
+            Self::Lit(lit) => lit.to_string(),
+            Self::Set(set) => format!("[{}]", set.iter().map(Value::to_cedar_str).join(", ")),
+            Self::Record(record) => format!(
+                "{{{}}}",
+                record
+                    .iter()
+                    .map(|(k, v)| format!("\"{}\": {}", k.escape_debug(), v.to_cedar_str()))
+                    .join(", ")
+            ),
+            // an extension value is printed as the constructor call producing it
+            Self::ExtensionValue(ev) => Expr::from(ev.as_ref().clone()).to_string(),
+        }
+    }
+
     /// Create a new empty set
     pub fn empty_set() -> Self {
         Self::Set(Set::empty())
@@ -719,4 +748,51 @@ mod test {
         let set2: Set = v2.into_iter().collect();
         assert_eq!(set2.len(), 1);
     }
+
+    #[test]
+    fn to_cedar_str_round_trips() {
+        let exts = crate::extensions::Extensions::all_available();
+        let evaluator = crate::evaluator::RestrictedEvaluator::new(&exts);
+        for src in [
+            "true",
+            "-17",
+            r#""a \"quoted\" string with \\ and \n""#,
+            r#"User::"alice""#,
+            r#"NS::Photo::"vacation \"22\"""#,
+            "[]",
+            "[1, 2, 3]",
+            "{}",
+            r#"{"a": 1, "b": "x", "needs quotes": [true, false]}"#,
+            r#"ip("10.0.0.1")"#,
+            r#"decimal("1.2345")"#,
+            r#"[{"ips": [ip("10.0.0.0/8"), ip("::1")]}, User::"alice"]"#,
+        ] {
+            let value = evaluator
+                .interpret(src.parse::<RestrictedExpr>().unwrap().as_borrowed())
+                .unwrap();
+            let printed = value.to_cedar_str();
+            let reparsed = evaluator
+                .interpret(printed.parse::<RestrictedExpr>().unwrap().as_borrowed())
+                .unwrap();
+            assert_eq!(value, reparsed, "`{src}` was printed as `{printed}`");
+        }
+    }
+
+    #[test]
+    fn to_cedar_str_does_not_abbreviate() {
+        let set = Value::set((1..=10).map(Value::from), None);
+        assert_eq!(set.to_string(), "<set with 10 elements>");
+        assert_eq!(set.to_cedar_str(), "[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]");
+        let record = Value::record([("a", Value::from(1)), ("b", Value::from("x"))], None);
+        assert_eq!(record.to_cedar_str(), r#"{"a": 1, "b": "x"}"#);
+        assert_eq!(
+            Value::from(EntityUID::with_eid_and_type("User", "alice").unwrap()).to_cedar_str(),
+            r#"User::"alice""#
+        );
+        let exts = crate::extensions::Extensions::all_available();
+        let ip = crate::evaluator::RestrictedEvaluator::new(&exts)
+            .interpret(r#"ip("10.0.0.1")"#.parse::<RestrictedExpr>().unwrap().as_borrowed())
+            .unwrap();
+        assert_eq!(Value::set([ip], None).to_cedar_str(), r#"[ip("10.0.0.1")]"#);
+    }
 }
@@ -22,7 +22,9 @@
 
 use crate::ast::*;
 use crate::entities::Entities;
-use crate::evaluator::Evaluator;
+use crate::evaluator::{
+    EvalLimitExceeded, EvalLimits, EvaluationError, EvaluationErrorKind, Evaluator,
+};
 use crate::extensions::Extensions;
 use itertools::{Either, Itertools};
 use serde::{Deserialize, Serialize};
@@ -47,29 +49,46 @@ pub struct Authorizer {
     error_handling: ErrorHandling,
 }
 
-/// Describes the possible Cedar error-handling modes.
-/// We currently only have one mode: [`ErrorHandling::Skip`].
-/// Other modes were debated during development, so this is here as an easy
-/// way to add modes if the future if we so decide.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum ErrorHandling {
+/// Describes the possible Cedar error-handling modes, i.e., how a policy
+/// whose evaluation errors affects the authorization decision.
+///
+/// In every mode, the error is reported in the diagnostics of the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorHandling {
     /// If a policy encounters an evaluation error, skip it.  The decision will
-    /// be as if the erroring policy did not exist.
+    /// be as if the erroring policy did not exist. This is the default, and
+    /// the semantics given by the language spec and formal model.
+    #[default]
     Skip,
+    /// If a `forbid` policy encounters an evaluation error, the decision is
+    /// `Deny`, as if the policy was satisfied, and the policy is reported in
+    /// the reason for the decision. A `permit` policy which errors is skipped,
+    /// as with [`ErrorHandling::Skip`].
+    DenyOnForbidError,
+    /// If any policy, `permit` or `forbid`, encounters an evaluation error,
+    /// the decision is `Deny`, and the policy is reported in the reason for
+    /// the decision, as a satisfied `forbid` policy would be.
+    DenyOnAnyError,
 }
 
-impl Default for ErrorHandling {
-    fn default() -> Self {
-        Self::Skip
-    }
+/// Configuration of an [`Authorizer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AuthorizerConfig {
+    /// How a policy whose evaluation errors affects the decision
+    pub error_handling: ErrorHandling,
 }
 
 impl Authorizer {
     /// Create a new `Authorizer`
     pub fn new() -> Self {
+        Self::with_config(AuthorizerConfig::default())
+    }
+
+    /// Create a new `Authorizer` with the given configuration
+    pub fn with_config(config: AuthorizerConfig) -> Self {
         Self {
             extensions: Extensions::all_available(), // set at compile time
-            error_handling: Default::default(),
+            error_handling: config.error_handling,
         }
     }
 
@@ -91,6 +110,64 @@ impl Authorizer {
         entities: &Entities,
     ) -> PartialResponse {
         let eval = Evaluator::new(q, entities, &self.extensions);
+        // PANIC SAFETY: `eval` has no limits, so it never raises `EvalLimitExceeded`
+        #[allow(clippy::expect_used)]
+        self.evaluate_policies(&eval, pset)
+            .expect("evaluation without limits should not exceed them")
+    }
+
+    /// Returns an authorization response for `q` with respect to the given
+    /// `Slice`, enforcing the given `EvalLimits` on the evaluation of all
+    /// policies.
+    ///
+    /// If evaluation exceeds the limits, no decision is reached, and the
+    /// returned error indicates which limit was exceeded.
+    pub fn is_authorized_bounded(
+        &self,
+        q: Request,
+        pset: &PolicySet,
+        entities: &Entities,
+        limits: EvalLimits,
+    ) -> Result<Response, EvalLimitExceeded> {
+        let eval = Evaluator::new(q, entities, &self.extensions).with_limits(limits);
+        self.evaluate_policies(&eval, pset)
+            .map(PartialResponse::concretize)
+    }
+
+    /// Evaluate every policy in `pset` for `q` independently, ignoring how
+    /// the results would be combined into a decision, and return the outcome
+    /// for each policy, ordered by policy id. Unlike `is_authorized`, this
+    /// reports policies which are not satisfied, and satisfied policies which
+    /// don't determine the decision, like a `permit` overridden by a `forbid`.
+    /// The `ErrorHandling` of this authorizer has no effect.
+    pub fn evaluate_each(
+        &self,
+        q: Request,
+        pset: &PolicySet,
+        entities: &Entities,
+    ) -> Vec<(PolicyID, PerPolicyOutcome)> {
+        let eval = Evaluator::new(q, entities, &self.extensions);
+        pset.policies()
+            .map(|p| {
+                let outcome = match eval.evaluate(p) {
+                    Ok(true) => PerPolicyOutcome::Satisfied { effect: p.effect() },
+                    Ok(false) => PerPolicyOutcome::NotSatisfied,
+                    Err(e) => PerPolicyOutcome::Error(Box::new(e)),
+                };
+                (p.id().clone(), outcome)
+            })
+            .sorted_by_cached_key(|(id, _)| id.to_string())
+            .collect()
+    }
+
+    /// Evaluate every policy in `pset` with `eval`, collecting the results into
+    /// a `PartialResponse`. Fails as soon as a policy exceeds the limits of
+    /// `eval`.
+    fn evaluate_policies(
+        &self,
+        eval: &Evaluator<'_>,
+        pset: &PolicySet,
+    ) -> Result<PartialResponse, EvalLimitExceeded> {
         let mut true_permits = vec![];
         let mut true_forbids = vec![];
         let mut false_permits = vec![];
@@ -121,16 +198,22 @@ impl Authorizer {
                     }
                 },
                 Err(e) => {
+                    if let EvaluationErrorKind::LimitExceeded(exceeded) = e.error_kind() {
+                        return Err(*exceeded);
+                    }
                     errors.push(AuthorizationError::PolicyEvaluationError {
                         id: id.clone(),
                         error: e,
                     });
-                    let satisfied = match self.error_handling {
+                    let forces_deny = match self.error_handling {
                         ErrorHandling::Skip => false,
+                        ErrorHandling::DenyOnForbidError => p.effect() == Effect::Forbid,
+                        ErrorHandling::DenyOnAnyError => true,
                     };
-                    match (satisfied, p.effect()) {
-                        (true, Effect::Permit) => true_permits.push((id, annotations)),
-                        (true, Effect::Forbid) => true_forbids.push((id, annotations)),
+                    match (forces_deny, p.effect()) {
+                        // An erroring policy which forces a deny counts as a
+                        // satisfied `forbid`, whatever its effect
+                        (true, _) => true_forbids.push((id, annotations)),
                         (false, Effect::Permit) => {
                             false_permits.push((id, (ErrorState::Error, annotations)))
                         }
@@ -142,7 +225,7 @@ impl Authorizer {
             };
         }
 
-        PartialResponse::new(
+        Ok(PartialResponse::new(
             true_permits,
             false_permits,
             residual_permits,
@@ -150,7 +233,7 @@ impl Authorizer {
             false_forbids,
             residual_forbids,
             errors,
-        )
+        ))
     }
 }
 
@@ -257,6 +340,167 @@ mod test {
         assert_eq!(ans.decision, Decision::Deny);
     }
 
+    /// Tests of the deny-on-error modes
+    #[test]
+    fn deny_on_error_tests() {
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let entities = Entities::new();
+        let pset = |srcs: &[&str]| {
+            let mut pset = PolicySet::new();
+            for (i, src) in srcs.iter().enumerate() {
+                pset.add_static(parser::parse_policy(Some(i.to_string()), src).unwrap())
+                    .unwrap();
+            }
+            pset
+        };
+        let permit = "permit(principal, action, resource);";
+        let erroring_permit = "permit(principal, action, resource) when { context.bad == 2 };";
+        let erroring_forbid = "forbid(principal, action, resource) when { context.bad == 2 };";
+        let decision = |error_handling, srcs: &[&str]| {
+            let a = Authorizer::with_config(AuthorizerConfig { error_handling });
+            let ans = a.is_authorized(q.clone(), &pset(srcs), &entities);
+            assert_eq!(ans.diagnostics.errors.len(), srcs.len() - 1);
+            (ans.decision, ans.diagnostics.reason)
+        };
+        let reason = |ids: &[&str]| ids.iter().map(|id| PolicyID::from_string(*id)).collect();
+
+        assert_eq!(
+            decision(ErrorHandling::Skip, &[permit, erroring_forbid]),
+            (Decision::Allow, reason(&["0"]))
+        );
+        assert_eq!(
+            decision(ErrorHandling::DenyOnForbidError, &[permit, erroring_forbid]),
+            (Decision::Deny, reason(&["1"]))
+        );
+        assert_eq!(
+            decision(ErrorHandling::DenyOnForbidError, &[permit, erroring_permit]),
+            (Decision::Allow, reason(&["0"]))
+        );
+        assert_eq!(
+            decision(ErrorHandling::DenyOnAnyError, &[permit, erroring_permit]),
+            (Decision::Deny, reason(&["1"]))
+        );
+        assert_eq!(
+            decision(ErrorHandling::DenyOnAnyError, &[permit, erroring_forbid]),
+            (Decision::Deny, reason(&["1"]))
+        );
+        assert_eq!(
+            decision(ErrorHandling::DenyOnAnyError, &[permit]),
+            (Decision::Allow, reason(&["0"]))
+        );
+    }
+
+    /// Evaluation exceeding its limits yields an error rather than a decision
+    #[test]
+    fn evaluate_each() {
+        let a = Authorizer::new();
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        pset.add_static(true_policy("0", Effect::Permit))
+            .expect("Policy ID already in PolicySet");
+        pset.add_static(true_policy("1", Effect::Forbid))
+            .expect("Policy ID already in PolicySet");
+        let src = r#"permit(principal == test_entity_type::"q", action, resource);"#;
+        pset.add_static(parser::parse_policy(Some("2".into()), src).unwrap())
+            .expect("Policy ID already in PolicySet");
+        let src = r#"forbid(principal, action, resource) when { context.risk > 5 };"#;
+        pset.add_static(parser::parse_policy(Some("3".into()), src).unwrap())
+            .expect("Policy ID already in PolicySet");
+
+        // the forbid overrides the permit, but both are reported as satisfied
+        let outcomes = a.evaluate_each(q, &pset, &Entities::new());
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|(id, _)| id.to_string())
+                .collect::<Vec<_>>(),
+            vec!["0", "1", "2", "3"]
+        );
+        assert_eq!(
+            outcomes[0].1,
+            PerPolicyOutcome::Satisfied {
+                effect: Effect::Permit
+            }
+        );
+        assert_eq!(
+            outcomes[1].1,
+            PerPolicyOutcome::Satisfied {
+                effect: Effect::Forbid
+            }
+        );
+        assert_eq!(outcomes[2].1, PerPolicyOutcome::NotSatisfied);
+        assert!(matches!(&outcomes[3].1, PerPolicyOutcome::Error(_)));
+    }
+
+    #[test]
+    fn bounded_evaluation() {
+        let a = Authorizer::new();
+        let q = Request::new(
+            (EntityUID::with_eid("p"), None),
+            (EntityUID::with_eid("a"), None),
+            (EntityUID::with_eid("r"), None),
+            Context::empty(),
+            None::<&RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        let entities = Entities::new();
+        let src = r#"
+        permit(principal, action, resource) when { [1, 2, 3].contains(2) };
+        "#;
+        pset.add_static(parser::parse_policy(Some("1".into()), src).unwrap())
+            .unwrap();
+
+        let ans = a
+            .is_authorized_bounded(q.clone(), &pset, &entities, EvalLimits::new())
+            .unwrap();
+        assert_eq!(ans.decision, Decision::Allow);
+
+        let limits = EvalLimits::new()
+            .with_max_set_size(3)
+            .with_max_steps(1000)
+            .with_max_depth(10);
+        let ans = a
+            .is_authorized_bounded(q.clone(), &pset, &entities, limits)
+            .unwrap();
+        assert_eq!(ans.decision, Decision::Allow);
+
+        let limits = EvalLimits::new().with_max_set_size(2);
+        assert_eq!(
+            a.is_authorized_bounded(q.clone(), &pset, &entities, limits),
+            Err(EvalLimitExceeded::SetSize { limit: 2, size: 3 })
+        );
+
+        let limits = EvalLimits::new().with_max_steps(3);
+        assert_eq!(
+            a.is_authorized_bounded(q.clone(), &pset, &entities, limits),
+            Err(EvalLimitExceeded::Steps { limit: 3 })
+        );
+
+        let limits = EvalLimits::new().with_max_depth(2);
+        assert_eq!(
+            a.is_authorized_bounded(q, &pset, &entities, limits),
+            Err(EvalLimitExceeded::Depth { limit: 2 })
+        );
+    }
+
     fn true_policy(id: &str, e: Effect) -> StaticPolicy {
         let pid = PolicyID::from_string(id);
         StaticPolicy::new(
@@ -648,6 +892,22 @@ impl Response {
     }
 }
 
+/// The outcome of evaluating a single policy for a request, independently of
+/// the other policies, as returned by [`Authorizer::evaluate_each`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PerPolicyOutcome {
+    /// The policy's scope and conditions are satisfied
+    Satisfied {
+        /// The effect of the policy
+        effect: Effect,
+    },
+    /// The policy's scope or conditions are not satisfied
+    NotSatisfied,
+    /// Evaluating the policy raised an error. This is boxed since it is much
+    /// larger than the other variants.
+    Error(Box<EvaluationError>),
+}
+
 /// Decision returned from the `Authorizer`
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
@@ -18,8 +18,9 @@
 
 use crate::ast::*;
 use crate::extensions::Extensions;
-use crate::transitive_closure::{compute_tc, enforce_tc_and_dag};
-use std::collections::{hash_map, HashMap};
+use crate::transitive_closure::{compute_tc, enforce_tc_and_dag, extend_tc};
+use smol_str::SmolStr;
+use std::collections::{hash_map, HashMap, HashSet};
 use std::fmt::Write;
 use std::sync::Arc;
 
@@ -70,6 +71,12 @@ pub struct Entities {
     #[serde(skip_deserializing)]
     #[serde(skip_serializing)]
     mode: Mode,
+
+    /// For the entity types of the entities in the store, the attributes which
+    /// are computed from the entity's other attributes, according to the
+    /// schema the entities were checked against.
+    #[serde(skip_serializing)]
+    derived_attrs: HashMap<EntityType, HashMap<SmolStr, Expr>>,
 }
 
 impl Entities {
@@ -78,6 +85,7 @@ impl Entities {
         Self {
             entities: HashMap::new(),
             mode: Mode::default(),
+            derived_attrs: HashMap::new(),
         }
     }
 
@@ -89,6 +97,22 @@ impl Entities {
         Self {
             entities: self.entities,
             mode: Mode::Partial,
+            derived_attrs: self.derived_attrs,
+        }
+    }
+
+    /// Create an `Entities` with the entities in this store which have the
+    /// given UIDs, skipping UIDs which aren't in the store. It has the same
+    /// mode and derived attributes as this store. The ancestors of the
+    /// entities are unchanged, so this keeps the TC.
+    pub fn subset<'a>(&self, uids: impl IntoIterator<Item = &'a EntityUID>) -> Self {
+        Self {
+            entities: uids
+                .into_iter()
+                .filter_map(|uid| Some((uid.clone(), self.entities.get(uid)?.clone())))
+                .collect(),
+            mode: self.mode,
+            derived_attrs: self.derived_attrs.clone(),
         }
     }
 
@@ -109,11 +133,29 @@ impl Entities {
         }
     }
 
+    /// Get the expression computing the attribute `attr` of entities of type
+    /// `entity_type`, if the schema the entities were checked against says
+    /// it is derived from their other attributes. In the expression, the
+    /// entity is `resource`.
+    pub fn derived_attr(&self, entity_type: &EntityType, attr: &str) -> Option<&Expr> {
+        self.derived_attrs.get(entity_type)?.get(attr)
+    }
+
     /// Iterate over the `Entity`s in the `Entities`
     pub fn iter(&self) -> impl Iterator<Item = &Entity> {
         self.entities.values()
     }
 
+    /// Get the number of `Entity`s in the `Entities`
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Test if the `Entities` is empty
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
     /// Adds the [`crate::ast::Entity`]s in the iterator to this [`Entities`].
     /// Fails if the passed iterator contains any duplicate entities with this structure,
     /// or if any error is encountered in the transitive closure computation.
@@ -125,6 +167,8 @@ impl Entities {
     ///
     /// If you pass [`TCComputation::AssumeAlreadyComputed`], then the caller is
     /// responsible for ensuring that TC and DAG hold before calling this method.
+    /// With [`TCComputation::ComputeNow`], the TC is only extended from the
+    /// added entities, rather than recomputed for the whole store.
     pub fn add_entities(
         mut self,
         collection: impl IntoIterator<Item = Entity>,
@@ -133,15 +177,20 @@ impl Entities {
         extensions: Extensions<'_>,
     ) -> Result<Self> {
         let checker = schema.map(|schema| EntitySchemaConformanceChecker::new(schema, extensions));
+        let mut added = HashSet::new();
         for entity in collection.into_iter() {
             if let Some(checker) = checker.as_ref() {
                 checker.validate_entity(&entity)?;
             }
+            if let Some(schema) = schema {
+                add_derived_attrs(&mut self.derived_attrs, &entity, schema);
+            }
             match self.entities.entry(entity.uid().clone()) {
                 hash_map::Entry::Occupied(_) => {
                     return Err(EntitiesError::duplicate(entity.uid().clone()))
                 }
                 hash_map::Entry::Vacant(vacant_entry) => {
+                    added.insert(entity.uid().clone());
                     vacant_entry.insert(entity);
                 }
             }
@@ -149,8 +198,71 @@ impl Entities {
         match tc_computation {
             TCComputation::AssumeAlreadyComputed => (),
             TCComputation::EnforceAlreadyComputed => enforce_tc_and_dag(&self.entities)?,
-            TCComputation::ComputeNow => compute_tc(&mut self.entities, true)?,
+            TCComputation::ComputeNow => extend_tc(&mut self.entities, &added, true)?,
+        };
+        Ok(self)
+    }
+
+    /// Removes the [`crate::ast::Entity`] with the given UID from this
+    /// [`Entities`]. Fails if there is no such entity.
+    ///
+    /// The ancestors of the entities which had the removed entity as an
+    /// ancestor are recomputed from their parents, so they lose the ancestors
+    /// they only had through it. A parent edge to the removed entity is kept,
+    /// like an edge to any other entity which is not in the store. This is
+    /// the same as if the removed entity had never been in the store, and
+    /// keeps the TC.
+    pub fn remove_entity(mut self, uid: &EntityUID) -> Result<Self> {
+        if self.entities.remove(uid).is_none() {
+            return Err(EntitiesError::no_such_entity(uid.clone()));
+        }
+        // Only the descendants of the removed entity can have ancestors
+        // through it, and the closure of every other entity is unaffected
+        let mut descendants = HashSet::new();
+        for entity in self.entities.values_mut() {
+            if entity.is_descendant_of(uid) {
+                entity.reset_ancestors_to_parents();
+                descendants.insert(entity.uid().clone());
+            }
+        }
+        extend_tc(&mut self.entities, &descendants, false)?;
+        Ok(self)
+    }
+
+    /// Sets the given attributes of the [`crate::ast::Entity`] with the given
+    /// UID in this [`Entities`], replacing the previous values of those
+    /// attributes and leaving its other attributes unchanged. Fails if there
+    /// is no such entity, or if an attribute value fails to evaluate.
+    ///
+    /// If `schema` is present, then the updated entity will be validated
+    /// against the `schema`, returning an error if it does not conform to the
+    /// schema.
+    ///
+    /// Attributes do not affect the entity hierarchy, so this keeps the TC.
+    pub fn update_attributes(
+        mut self,
+        uid: &EntityUID,
+        attrs: impl IntoIterator<Item = (SmolStr, RestrictedExpr)>,
+        schema: Option<&impl Schema>,
+        extensions: Extensions<'_>,
+    ) -> Result<Self> {
+        let Some(entity) = self.entities.get_mut(uid) else {
+            return Err(EntitiesError::no_such_entity(uid.clone()));
         };
+        let mut updated = entity.clone();
+        for (attr, val) in attrs {
+            updated
+                .set_attr(attr.clone(), val, &extensions)
+                .map_err(|err| EntityAttrEvaluationError {
+                    uid: uid.clone(),
+                    attr,
+                    err,
+                })?;
+        }
+        if let Some(schema) = schema {
+            EntitySchemaConformanceChecker::new(schema, extensions).validate_entity(&updated)?;
+        }
+        *entity = updated;
         Ok(self)
     }
 
@@ -170,6 +282,7 @@ impl Entities {
         extensions: Extensions<'_>,
     ) -> Result<Self> {
         let mut entity_map = create_entity_map(entities.into_iter())?;
+        let mut derived_attrs = HashMap::new();
         if let Some(schema) = schema {
             // Validate non-action entities against schema.
             // We do this before adding the actions, because we trust the
@@ -179,6 +292,7 @@ impl Entities {
             for entity in entity_map.values() {
                 if !entity.uid().entity_type().is_action() {
                     checker.validate_entity(entity)?;
+                    add_derived_attrs(&mut derived_attrs, entity, schema);
                 }
             }
         }
@@ -214,6 +328,7 @@ impl Entities {
         Ok(Self {
             entities: entity_map,
             mode: Mode::default(),
+            derived_attrs,
         })
     }
 
@@ -330,6 +445,23 @@ fn create_entity_map(es: impl Iterator<Item = Entity>) -> Result<HashMap<EntityU
     Ok(map)
 }
 
+/// Add the derived attributes of the type of `entity` according to `schema`
+/// to `derived_attrs`, if they aren't there already
+fn add_derived_attrs(
+    derived_attrs: &mut HashMap<EntityType, HashMap<SmolStr, Expr>>,
+    entity: &Entity,
+    schema: &impl Schema,
+) {
+    let entity_type = entity.uid().entity_type();
+    if !derived_attrs.contains_key(entity_type) {
+        let attrs = schema
+            .entity_type(entity_type)
+            .map(|desc| desc.derived_attrs().collect())
+            .unwrap_or_default();
+        derived_attrs.insert(entity_type.clone(), attrs);
+    }
+}
+
 impl IntoIterator for Entities {
     type Item = Entity;
 
@@ -437,6 +569,20 @@ pub enum TCComputation {
     ComputeNow,
 }
 
+/// How an `EntityJsonParser` treats the parents of an entity which are not
+/// among the entities it parses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum EntitiesParseMode {
+    /// A parent may be missing, in which case it behaves like an entity with
+    /// no attributes and no parents
+    #[default]
+    AllowMissingParents,
+    /// Every parent must be one of the parsed entities, or an action declared
+    /// in the schema. Otherwise, parsing fails with a
+    /// [`EntitiesError::MissingParent`] error.
+    RequireParentsPresent,
+}
+
 // PANIC SAFETY: Unit Test Code
 #[allow(clippy::panic)]
 #[cfg(test)]
@@ -464,6 +610,37 @@ mod json_parsing_tests {
         parser.from_json_value(v).unwrap();
     }
 
+    #[test]
+    fn require_parents_present() {
+        let v = serde_json::json!(
+            [
+                {
+                    "uid" : { "type" : "A", "id" : "b"},
+                    "attrs" : {},
+                    "parents" : [ { "type" : "A", "id" : "c" } ]
+                },
+                {
+                    "uid" : { "type" : "A", "id" : "c"},
+                    "attrs" : {},
+                    "parents" : [ { "type" : "A", "id" : "d" }, { "type" : "A", "id" : "e" } ]
+                },
+                {
+                    "uid" : { "type" : "A", "id" : "e"},
+                    "attrs" : {},
+                    "parents" : []
+                }
+            ]
+        );
+        let parser: EntityJsonParser<'_, '_> =
+            EntityJsonParser::new(None, Extensions::all_available(), TCComputation::ComputeNow);
+        parser.from_json_value(v.clone()).unwrap();
+        let parser = parser.with_parse_mode(EntitiesParseMode::RequireParentsPresent);
+        assert_matches!(parser.from_json_value(v), Err(EntitiesError::MissingParent(err)) => {
+            assert_eq!(err.child(), &r#"A::"c""#.parse().unwrap());
+            assert_eq!(err.parent(), &r#"A::"d""#.parse().unwrap());
+        });
+    }
+
     #[test]
     fn enforces_tc_fail_cycle_almost() {
         let parser: EntityJsonParser<'_, '_> =
@@ -1901,6 +2078,209 @@ mod entities_tests {
         )
         .expect("Should have succeeded");
     }
+
+    #[test]
+    fn test_add_entities_extends_tc() {
+        // Hierarchy
+        // a -> b, where b isn't in the store yet
+        let mut e1 = Entity::with_uid(EntityUID::with_eid("a"));
+        e1.add_ancestor(EntityUID::with_eid("b"));
+        let es = Entities::from_entities(
+            vec![e1],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+
+        // adding b -> c makes a a descendant of c
+        let mut e2 = Entity::with_uid(EntityUID::with_eid("b"));
+        e2.add_ancestor(EntityUID::with_eid("c"));
+        let es = es
+            .add_entities(
+                vec![e2],
+                None::<&NoEntitiesSchema>,
+                TCComputation::ComputeNow,
+                Extensions::all_available(),
+            )
+            .expect("Failed to add entities");
+        assert!(es
+            .entity(&EntityUID::with_eid("a"))
+            .unwrap()
+            .is_descendant_of(&EntityUID::with_eid("c")));
+        enforce_tc_and_dag(&es.entities).expect("TC should hold");
+
+        // adding c -> a makes a cycle
+        let mut e3 = Entity::with_uid(EntityUID::with_eid("c"));
+        e3.add_ancestor(EntityUID::with_eid("a"));
+        match es.add_entities(
+            vec![e3],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        ) {
+            Ok(_) => panic!("Should have found a cycle"),
+            Err(EntitiesError::TransitiveClosureError(_)) => (),
+            Err(_) => panic!("Wrong Error!"),
+        };
+    }
+
+    #[test]
+    fn test_parents_and_ancestors() {
+        // Hierarchy
+        // a -> b -> c
+        let with_parents = |eid: &str, parents: &[&str]| {
+            Entity::new(
+                EntityUID::with_eid(eid),
+                HashMap::new(),
+                parents.iter().map(|p| EntityUID::with_eid(p)).collect(),
+                &Extensions::all_available(),
+            )
+            .unwrap()
+        };
+        let es = Entities::from_entities(
+            vec![
+                with_parents("a", &["b"]),
+                with_parents("b", &["c"]),
+                with_parents("c", &[]),
+            ],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+        let a = es.entity(&EntityUID::with_eid("a")).unwrap();
+        assert_eq!(
+            a.parents().collect::<HashSet<_>>(),
+            HashSet::from([&EntityUID::with_eid("b")])
+        );
+        assert_eq!(
+            a.ancestors().collect::<HashSet<_>>(),
+            HashSet::from([&EntityUID::with_eid("b"), &EntityUID::with_eid("c")])
+        );
+    }
+
+    #[test]
+    fn test_remove_entity() {
+        let (e0, e1, _, _) = test_entities();
+        let es = Entities::from_entities(
+            vec![e0.clone(), e1.clone()],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+        let es = es.remove_entity(e0.uid()).expect("Failed to remove entity");
+        assert_eq!(es.iter().collect::<Vec<_>>(), vec![&e1]);
+        match es.remove_entity(e0.uid()) {
+            Ok(_) => panic!("Entity was already removed"),
+            Err(EntitiesError::NoSuchEntity(e)) => assert_eq!(e.euid(), e0.uid()),
+            Err(_) => panic!("Wrong Error!"),
+        };
+    }
+
+    #[test]
+    fn test_remove_entity_transitive() {
+        // alice in admins in staff, and bob in staff directly
+        let staff = EntityUID::with_eid("staff");
+        let admins = EntityUID::with_eid("admins");
+        let es = Entities::from_entities(
+            vec![
+                Entity::with_uid(staff.clone()),
+                Entity::new_with_attr_partial_value(
+                    admins.clone(),
+                    HashMap::new(),
+                    HashSet::from([staff.clone()]),
+                ),
+                Entity::new_with_attr_partial_value(
+                    EntityUID::with_eid("alice"),
+                    HashMap::new(),
+                    HashSet::from([admins.clone()]),
+                ),
+                Entity::new_with_attr_partial_value(
+                    EntityUID::with_eid("bob"),
+                    HashMap::new(),
+                    HashSet::from([staff.clone()]),
+                ),
+            ],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+        let es = es.remove_entity(&admins).expect("Failed to remove entity");
+        let alice = es.entity(&EntityUID::with_eid("alice")).unwrap();
+        assert!(!alice.is_descendant_of(&staff));
+        // the edge to the removed parent is kept
+        assert_eq!(
+            alice.ancestors().collect::<HashSet<_>>(),
+            HashSet::from([&admins])
+        );
+        let bob = es.entity(&EntityUID::with_eid("bob")).unwrap();
+        assert!(bob.is_descendant_of(&staff));
+    }
+
+    #[test]
+    fn test_update_attributes() {
+        let uid = EntityUID::with_eid("a");
+        let e = Entity::new(
+            uid.clone(),
+            HashMap::from([
+                ("x".into(), RestrictedExpr::val(1)),
+                ("y".into(), RestrictedExpr::val(2)),
+            ]),
+            HashSet::new(),
+            &Extensions::all_available(),
+        )
+        .unwrap();
+        let es = Entities::from_entities(
+            vec![e],
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .expect("Failed to construct entities");
+        let es = es
+            .update_attributes(
+                &uid,
+                [
+                    ("y".into(), RestrictedExpr::val(3)),
+                    ("z".into(), RestrictedExpr::val("z")),
+                ],
+                None::<&NoEntitiesSchema>,
+                Extensions::all_available(),
+            )
+            .expect("Failed to update attributes");
+        let e = es.entity(&uid).unwrap();
+        assert_eq!(e.get("x"), Some(&PartialValue::from(1)));
+        assert_eq!(e.get("y"), Some(&PartialValue::from(3)));
+        assert_eq!(e.get("z"), Some(&PartialValue::from("z")));
+
+        let bad_ip = RestrictedExpr::call_extension_fn(
+            "ip".parse().unwrap(),
+            [RestrictedExpr::val("not an ip")],
+        );
+        match es.clone().update_attributes(
+            &uid,
+            [("ip".into(), bad_ip)],
+            None::<&NoEntitiesSchema>,
+            Extensions::all_available(),
+        ) {
+            Ok(_) => panic!("Attribute should have failed to evaluate"),
+            Err(EntitiesError::AttributeEvaluation(e)) => assert_eq!(e.attr, "ip"),
+            Err(_) => panic!("Wrong Error!"),
+        };
+        match es.update_attributes(
+            &EntityUID::with_eid("b"),
+            [],
+            None::<&NoEntitiesSchema>,
+            Extensions::all_available(),
+        ) {
+            Ok(_) => panic!("Entity doesn't exist"),
+            Err(EntitiesError::NoSuchEntity(_)) => (),
+            Err(_) => panic!("Wrong Error!"),
+        };
+    }
 }
 
 // PANIC SAFETY: Unit Test Code
@@ -3165,4 +3545,292 @@ mod schema_based_parsing_tests {
             ).build());
         });
     }
+
+    /// Test that entity types and records with open attributes keep the
+    /// attributes not in the schema
+    #[test]
+    fn open_attributes() {
+        use crate::evaluator::Evaluator;
+
+        struct MockSchema;
+        impl Schema for MockSchema {
+            type EntityTypeDescription = MockContractorDescription;
+            type ActionEntityIterator = std::iter::Empty<Arc<Entity>>;
+            fn entity_type(&self, entity_type: &EntityType) -> Option<MockContractorDescription> {
+                match entity_type.to_string().as_str() {
+                    "Contractor" => Some(MockContractorDescription { open: true }),
+                    "Employee" => Some(MockContractorDescription { open: false }),
+                    _ => None,
+                }
+            }
+            fn action(&self, _action: &EntityUID) -> Option<Arc<Entity>> {
+                None
+            }
+            fn entity_types_with_basename<'a>(
+                &'a self,
+                _basename: &'a Id,
+            ) -> Box<dyn Iterator<Item = EntityType> + 'a> {
+                Box::new(std::iter::empty())
+            }
+            fn action_entities(&self) -> Self::ActionEntityIterator {
+                std::iter::empty()
+            }
+        }
+
+        /// `Contractor` has open attributes, `Employee` has the same
+        /// attributes, closed
+        struct MockContractorDescription {
+            open: bool,
+        }
+        impl EntityTypeDescription for MockContractorDescription {
+            fn entity_type(&self) -> EntityType {
+                let name = if self.open { "Contractor" } else { "Employee" };
+                EntityType::Specified(Name::parse_unqualified_name(name).expect("valid"))
+            }
+
+            fn attr_type(&self, attr: &str) -> Option<SchemaType> {
+                match attr {
+                    "manager" => Some(SchemaType::Entity {
+                        ty: self.entity_type(),
+                    }),
+                    "details" => Some(SchemaType::Record {
+                        attrs: [("level".into(), AttributeType::required(SchemaType::Long))]
+                            .into_iter()
+                            .collect(),
+                        open_attrs: self.open,
+                    }),
+                    _ => None,
+                }
+            }
+
+            fn required_attrs(&self) -> Box<dyn Iterator<Item = SmolStr>> {
+                Box::new(["manager", "details"].map(SmolStr::new).into_iter())
+            }
+
+            fn allowed_parent_types(&self) -> Arc<HashSet<EntityType>> {
+                Arc::new(HashSet::new())
+            }
+
+            fn open_attributes(&self) -> bool {
+                self.open
+            }
+        }
+
+        let entitiesjson = json!(
+            [
+                {
+                    "uid": { "type": "Contractor", "id": "alice" },
+                    "attrs": {
+                        "manager": { "type": "Contractor", "id": "bob" },
+                        "details": { "level": 3, "agency": "Acme" },
+                        "badge": { "__entity": { "type": "Badge", "id": "b1" } },
+                        "tags": ["onsite"],
+                        "contact": { "type": "Email", "id": "alice@example.com" }
+                    },
+                    "parents": []
+                }
+            ]
+        );
+        let eparser = EntityJsonParser::new(
+            Some(&MockSchema),
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        );
+        let parsed = eparser
+            .from_json_value(entitiesjson)
+            .expect("Should parse without error");
+        let alice = parsed
+            .entity(&r#"Contractor::"alice""#.parse().unwrap())
+            .expect("alice should exist");
+        // attributes in the schema are parsed according to their types
+        assert_eq!(
+            alice.get("manager"),
+            Some(&PartialValue::from(
+                r#"Contractor::"bob""#.parse::<EntityUID>().unwrap()
+            ))
+        );
+        // attributes not in the schema are parsed according to their JSON
+        // shape, so `contact` is a record, not an entity reference
+        assert_eq!(
+            alice.get("badge"),
+            Some(&PartialValue::from(
+                r#"Badge::"b1""#.parse::<EntityUID>().unwrap()
+            ))
+        );
+        assert_matches!(
+            alice.get("tags"),
+            Some(PartialValue::Value(Value {
+                value: ValueKind::Set(_),
+                ..
+            }))
+        );
+        assert_matches!(
+            alice.get("contact"),
+            Some(PartialValue::Value(Value {
+                value: ValueKind::Record(_),
+                ..
+            }))
+        );
+
+        // the entities can be used to evaluate expressions on both kinds of
+        // attributes, including the attributes of open records not in the
+        // schema
+        let request = Request::new_unchecked(
+            EntityUIDEntry::Known {
+                euid: Arc::new(alice.uid().clone()),
+                loc: None,
+            },
+            EntityUIDEntry::Known {
+                euid: Arc::new(r#"Action::"view""#.parse().unwrap()),
+                loc: None,
+            },
+            EntityUIDEntry::Known {
+                euid: Arc::new(alice.uid().clone()),
+                loc: None,
+            },
+            None,
+        );
+        let extensions = Extensions::all_available();
+        let eval = Evaluator::new(request, &parsed, &extensions);
+        for expr in [
+            r#"principal.details.level == 3"#,
+            r#"principal.details.agency == "Acme""#,
+            r#"principal.tags.contains("onsite")"#,
+            r#"principal.contact.id == "alice@example.com""#,
+        ] {
+            assert_eq!(
+                eval.interpret_inline_policy(&expr.parse().unwrap()),
+                Ok(Value::from(true)),
+                "for {expr}"
+            );
+        }
+
+        // closed entity types and records still reject attributes not in the
+        // schema
+        let entitiesjson = json!(
+            [
+                {
+                    "uid": { "type": "Employee", "id": "alice" },
+                    "attrs": {
+                        "manager": { "type": "Employee", "id": "bob" },
+                        "details": { "level": 3 },
+                        "tags": ["onsite"]
+                    },
+                    "parents": []
+                }
+            ]
+        );
+        assert_matches!(eparser.from_json_value(entitiesjson.clone()), Err(e) => {
+            expect_err(&entitiesjson, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error(
+                r#"error during entity deserialization: attribute `tags` on `Employee::"alice"` should not exist according to the schema"#,
+            ).build());
+        });
+        let entitiesjson = json!(
+            [
+                {
+                    "uid": { "type": "Employee", "id": "alice" },
+                    "attrs": {
+                        "manager": { "type": "Employee", "id": "bob" },
+                        "details": { "level": 3, "agency": "Acme" }
+                    },
+                    "parents": []
+                }
+            ]
+        );
+        assert_matches!(eparser.from_json_value(entitiesjson.clone()), Err(e) => {
+            expect_err(&entitiesjson, &miette::Report::new(e), &ExpectedErrorMessageBuilder::error(
+                r#"error during entity deserialization: in attribute `details` on `Employee::"alice"`, record attribute `agency` should not exist according to the schema"#,
+            ).build());
+        });
+    }
+
+    /// Test that the attribute transform runs before schema-based parsing and
+    /// validation
+    #[cfg(all(feature = "decimal", feature = "ipaddr"))]
+    #[test]
+    fn attribute_transform() {
+        let entitiesjson = json!(
+            [
+                {
+                    "uid": { "type": "Employee", "id": "12UA45" },
+                    "attrs": {
+                        "isFullTime": true,
+                        "numDirectReports": "3",
+                        "department": "SALES",
+                        "manager": { "type": "Employee", "id": "34FB87" },
+                        "hr_contacts": [],
+                        "json_blob": {
+                            "inner1": false,
+                            "inner2": "-*/",
+                            "inner3": { "innerinner": { "type": "Employee", "id": "09AE76" }},
+                        },
+                        "home_ip": "222.222.222.101",
+                        "work_ip": "2.2.2.0/24",
+                        "trust_score": "5.7",
+                    },
+                    "parents": []
+                }
+            ]
+        );
+        let eparser = EntityJsonParser::new(
+            Some(&MockSchema),
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        );
+        // `numDirectReports` is a string, but the schema expects a `Long`
+        assert_matches!(eparser.from_json_value(entitiesjson.clone()), Err(_));
+
+        let uid = r#"Employee::"12UA45""#.parse::<EntityUID>().unwrap();
+        let eparser = eparser.with_attribute_transform(|euid, attr, value| {
+            assert_eq!(euid, &uid);
+            match (attr, value) {
+                ("numDirectReports", serde_json::Value::String(s)) => {
+                    json!(s.parse::<i64>().unwrap())
+                }
+                ("department", serde_json::Value::String(s)) => json!(s.to_lowercase()),
+                (_, value) => value,
+            }
+        });
+        let parsed = eparser
+            .from_json_value(entitiesjson)
+            .expect("transformed entities should conform to the schema");
+        let parsed = parsed.entity(&uid).expect("employee should exist");
+        assert_eq!(parsed.get("numDirectReports"), Some(&PartialValue::from(3)));
+        assert_eq!(parsed.get("department"), Some(&PartialValue::from("sales")));
+        // extension values are constructed from the transformed values
+        assert_matches!(
+            parsed.get("home_ip"),
+            Some(PartialValue::Value(Value {
+                value: ValueKind::ExtensionValue(_),
+                ..
+            }))
+        );
+
+        // without a schema, a transform can construct extension values with
+        // explicit `__extn` escapes
+        let eparser: EntityJsonParser<'_, '_> =
+            EntityJsonParser::new(None, Extensions::all_available(), TCComputation::ComputeNow)
+                .with_attribute_transform(|_, attr, value| match attr {
+                    "home_ip" => json!({ "__extn": { "fn": "ip", "arg": value } }),
+                    _ => value,
+                });
+        let parsed = eparser
+            .from_json_value(json!([
+                {
+                    "uid": { "type": "Employee", "id": "12UA45" },
+                    "attrs": { "home_ip": "222.222.222.101", "department": "SALES" },
+                    "parents": []
+                }
+            ]))
+            .expect("Should parse without error");
+        let parsed = parsed.entity(&uid).expect("employee should exist");
+        assert_matches!(
+            parsed.get("home_ip"),
+            Some(PartialValue::Value(Value {
+                value: ValueKind::ExtensionValue(_),
+                ..
+            }))
+        );
+        assert_eq!(parsed.get("department"), Some(&PartialValue::from("SALES")));
+    }
 }
@@ -48,21 +48,32 @@ impl<'a, S: Schema> EntitySchemaConformanceChecker<'a, S> {
     /// Validate an entity against the schema, returning an
     /// [`EntitySchemaConformanceError`] if it does not comply.
     pub fn validate_entity(&self, entity: &Entity) -> Result<(), EntitySchemaConformanceError> {
+        match self.entity_violations(entity).into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Validate an entity against the schema, returning every way in which it
+    /// does not comply, in the order [`Self::validate_entity`] checks for
+    /// them. An action or entity type which is not in the schema is the only
+    /// error reported for the entity, since nothing else can be checked.
+    pub fn entity_violations(&self, entity: &Entity) -> Vec<EntitySchemaConformanceError> {
+        let mut violations = Vec::new();
         let uid = entity.uid();
         let etype = uid.entity_type();
         if etype.is_action() {
-            let schema_action = self
-                .schema
-                .action(uid)
-                .ok_or(EntitySchemaConformanceError::undeclared_action(uid.clone()))?;
+            let Some(schema_action) = self.schema.action(uid) else {
+                return vec![EntitySchemaConformanceError::undeclared_action(uid.clone())];
+            };
             // check that the action exactly matches the schema's definition
             if !entity.deep_eq(&schema_action) {
-                return Err(EntitySchemaConformanceError::action_declaration_mismatch(
+                violations.push(EntitySchemaConformanceError::action_declaration_mismatch(
                     uid.clone(),
                 ));
             }
         } else {
-            let schema_etype = self.schema.entity_type(etype).ok_or_else(|| {
+            let Some(schema_etype) = self.schema.entity_type(etype) else {
                 let suggested_types = match etype {
                     EntityType::Specified(name) => self
                         .schema
@@ -70,21 +81,46 @@ impl<'a, S: Schema> EntitySchemaConformanceChecker<'a, S> {
                         .collect(),
                     EntityType::Unspecified => vec![],
                 };
-                UnexpectedEntityTypeError {
+                return vec![UnexpectedEntityTypeError {
                     uid: uid.clone(),
                     suggested_types,
                 }
-            })?;
+                .into()];
+            };
             // Ensure that all required attributes for `etype` are actually
             // included in `entity`
             for required_attr in schema_etype.required_attrs() {
                 if entity.get(&required_attr).is_none() {
-                    return Err(EntitySchemaConformanceError::missing_entity_attr(
+                    violations.push(EntitySchemaConformanceError::missing_entity_attr(
                         uid.clone(),
                         required_attr,
                     ));
                 }
             }
+            // Derived attributes are computed when they are accessed, so they
+            // can't be given a value
+            for (derived_attr, _) in schema_etype.derived_attrs() {
+                if entity.get(&derived_attr).is_some() {
+                    violations.push(EntitySchemaConformanceError::derived_entity_attr(
+                        uid.clone(),
+                        derived_attr,
+                    ));
+                }
+            }
+            // Ensure that `entity` has at most one attribute of each group of
+            // mutually exclusive attributes
+            for attrs in schema_etype.mutually_exclusive_attrs() {
+                let mut present = attrs.iter().filter(|attr| entity.get(attr).is_some());
+                if let (Some(attr), Some(other_attr)) = (present.next(), present.next()) {
+                    violations.push(
+                        EntitySchemaConformanceError::mutually_exclusive_entity_attrs(
+                            uid.clone(),
+                            attr.clone(),
+                            other_attr.clone(),
+                        ),
+                    );
+                }
+            }
             // For each attribute that actually appears in `entity`, ensure it
             // complies with the schema
             for (attr, val) in entity.attrs() {
@@ -93,7 +129,7 @@ impl<'a, S: Schema> EntitySchemaConformanceChecker<'a, S> {
                         // `None` indicates the attribute shouldn't exist -- see
                         // docs on the `attr_type()` trait method
                         if !schema_etype.open_attributes() {
-                            return Err(EntitySchemaConformanceError::unexpected_entity_attr(
+                            violations.push(EntitySchemaConformanceError::unexpected_entity_attr(
                                 uid.clone(),
                                 attr.clone(),
                             ));
@@ -106,21 +142,21 @@ impl<'a, S: Schema> EntitySchemaConformanceChecker<'a, S> {
                         {
                             Ok(()) => {} // typecheck passes
                             Err(TypecheckError::TypeMismatch(err)) => {
-                                return Err(EntitySchemaConformanceError::type_mistmatch(
+                                violations.push(EntitySchemaConformanceError::type_mistmatch(
                                     uid.clone(),
                                     attr.clone(),
                                     err,
                                 ));
                             }
                             Err(TypecheckError::HeterogeneousSet(err)) => {
-                                return Err(EntitySchemaConformanceError::heterogeneous_set(
+                                violations.push(EntitySchemaConformanceError::heterogeneous_set(
                                     uid.clone(),
                                     attr.clone(),
                                     err,
                                 ));
                             }
                             Err(TypecheckError::ExtensionFunctionLookup(err)) => {
-                                return Err(
+                                violations.push(
                                     EntitySchemaConformanceError::extension_function_lookup(
                                         uid.clone(),
                                         attr.clone(),
@@ -142,14 +178,14 @@ impl<'a, S: Schema> EntitySchemaConformanceChecker<'a, S> {
                     //
                     // thus, the check passes in this case
                 } else {
-                    return Err(EntitySchemaConformanceError::invalid_ancestor_type(
+                    violations.push(EntitySchemaConformanceError::invalid_ancestor_type(
                         uid.clone(),
                         ancestor_type.clone(),
                     ));
                 }
             }
         }
-        Ok(())
+        violations
     }
 }
 
@@ -32,6 +32,15 @@ pub enum EntitySchemaConformanceError {
     #[error(transparent)]
     #[diagnostic(transparent)]
     MissingRequiredEntityAttr(MissingRequiredEntityAttr),
+    /// Encountered a value for an attribute which the schema says is derived
+    /// from the entity's other attributes
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    DerivedEntityAttr(DerivedEntityAttr),
+    /// Encountered two attributes which the schema says are mutually exclusive
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MutuallyExclusiveEntityAttrs(MutuallyExclusiveEntityAttrs),
     /// The given attribute on the given entity had a different type than the
     /// schema indicated
     #[error(transparent)]
@@ -83,6 +92,25 @@ impl EntitySchemaConformanceError {
         })
     }
 
+    pub(crate) fn derived_entity_attr(uid: EntityUID, attr: impl Into<SmolStr>) -> Self {
+        Self::DerivedEntityAttr(DerivedEntityAttr {
+            uid,
+            attr: attr.into(),
+        })
+    }
+
+    pub(crate) fn mutually_exclusive_entity_attrs(
+        uid: EntityUID,
+        attr: impl Into<SmolStr>,
+        other_attr: impl Into<SmolStr>,
+    ) -> Self {
+        Self::MutuallyExclusiveEntityAttrs(MutuallyExclusiveEntityAttrs {
+            uid,
+            attr: attr.into(),
+            other_attr: other_attr.into(),
+        })
+    }
+
     pub(crate) fn type_mistmatch(
         uid: EntityUID,
         attr: impl Into<SmolStr>,
@@ -204,6 +232,24 @@ pub struct UnexpectedEntityAttr {
     attr: SmolStr,
 }
 
+/// Encountered a value for an attribute which the schema says is derived from
+/// the entity's other attributes
+#[derive(Debug, Error, Diagnostic)]
+#[error("attribute `{attr}` on `{uid}` is derived from its other attributes according to the schema, so it cannot be given a value")]
+pub struct DerivedEntityAttr {
+    uid: EntityUID,
+    attr: SmolStr,
+}
+
+/// Encountered two attributes which the schema says are mutually exclusive
+#[derive(Debug, Error, Diagnostic)]
+#[error("`{uid}` has both attributes `{attr}` and `{other_attr}`, but they are mutually exclusive according to the schema")]
+pub struct MutuallyExclusiveEntityAttrs {
+    uid: EntityUID,
+    attr: SmolStr,
+    other_attr: SmolStr,
+}
+
 /// Didn't encounter attribute that should exist
 #[derive(Debug, Error, Diagnostic)]
 #[error("expected entity `{uid}` to have attribute `{attr}`, but it does not")]
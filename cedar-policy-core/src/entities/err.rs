@@ -14,7 +14,7 @@
  * limitations under the License.
  */
 
-use super::EntityUID;
+use super::{EntityAttrEvaluationError, EntityUID};
 use crate::transitive_closure;
 use miette::Diagnostic;
 use thiserror::Error;
@@ -43,12 +43,49 @@ pub enum EntitiesError {
     #[error("entity does not conform to the schema: {0}")]
     #[diagnostic(transparent)]
     InvalidEntity(#[from] crate::entities::conformance::err::EntitySchemaConformanceError),
+    /// Error because an entity to update or remove is not in the `Entities`
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    NoSuchEntity(NoSuchEntity),
+    /// Error evaluating a new attribute value for an entity
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    AttributeEvaluation(#[from] EntityAttrEvaluationError),
+    /// Error because a parent of an entity is not among the entities, when
+    /// parsing with `EntitiesParseMode::RequireParentsPresent`
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    MissingParent(MissingParent),
 }
 
 impl EntitiesError {
     pub(crate) fn duplicate(euid: EntityUID) -> Self {
         Self::Duplicate(Duplicate { euid })
     }
+
+    pub(crate) fn no_such_entity(euid: EntityUID) -> Self {
+        Self::NoSuchEntity(NoSuchEntity { euid })
+    }
+
+    pub(crate) fn missing_parent(child: EntityUID, parent: EntityUID) -> Self {
+        Self::MissingParent(MissingParent { child, parent })
+    }
+
+    /// A code identifying the kind of this error, e.g.,
+    /// `"cedar::entities::duplicate"`. The code for a kind of error is stable
+    /// across releases, even when the error message changes.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::Serialization(_) => "cedar::entities::serialization",
+            Self::Deserialization(_) => "cedar::entities::deserialization",
+            Self::Duplicate(_) => "cedar::entities::duplicate",
+            Self::TransitiveClosureError(_) => "cedar::entities::transitive_closure",
+            Self::InvalidEntity(_) => "cedar::entities::schema_conformance",
+            Self::NoSuchEntity(_) => "cedar::entities::no_such_entity",
+            Self::AttributeEvaluation(_) => "cedar::entities::attribute_evaluation",
+            Self::MissingParent(_) => "cedar::entities::missing_parent",
+        }
+    }
 }
 
 impl From<transitive_closure::TcError<EntityUID>> for EntitiesError {
@@ -95,5 +132,42 @@ impl Duplicate {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Error, Diagnostic)]
+#[error("entity `{}` does not exist", .euid)]
+/// Error type for updating or removing an entity which is not in the entity set
+pub struct NoSuchEntity {
+    /// The [`EntityUID`] that was not found
+    euid: EntityUID,
+}
+
+impl NoSuchEntity {
+    #[cfg(test)]
+    pub(crate) fn euid(&self) -> &EntityUID {
+        &self.euid
+    }
+}
+
 /// Type alias for convenience
 pub type Result<T> = std::result::Result<T, EntitiesError>;
+
+#[derive(Debug, PartialEq, Eq, Error, Diagnostic)]
+#[error("entity `{}` has parent `{}`, which is not one of the entities", .child, .parent)]
+/// Error type for entity sets in which the parent of an entity is missing
+pub struct MissingParent {
+    /// The [`EntityUID`] of the entity with the missing parent
+    child: EntityUID,
+    /// The [`EntityUID`] of the missing parent
+    parent: EntityUID,
+}
+
+impl MissingParent {
+    /// The entity with the missing parent
+    pub fn child(&self) -> &EntityUID {
+        &self.child
+    }
+
+    /// The missing parent
+    pub fn parent(&self) -> &EntityUID {
+        &self.parent
+    }
+}
@@ -24,14 +24,15 @@ use crate::ast::{
 };
 use crate::entities::{
     conformance::err::{EntitySchemaConformanceError, UnexpectedEntityTypeError},
-    schematype_of_partialvalue, Entities, EntitiesError, GetSchemaTypeError, TCComputation,
+    schematype_of_partialvalue, Entities, EntitiesError, EntitiesParseMode, GetSchemaTypeError,
+    TCComputation,
 };
 use crate::extensions::Extensions;
 use crate::jsonvalue::JsonValueWithNoDuplicateKeys;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use smol_str::SmolStr;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 #[cfg(feature = "wasm")]
@@ -59,8 +60,12 @@ pub struct EntityJson {
     parents: Vec<EntityUidJson>,
 }
 
+/// Function used to transform the JSON value of each entity attribute before it
+/// is parsed. See [`EntityJsonParser::with_attribute_transform()`].
+type AttributeTransform<'e> = dyn Fn(&EntityUID, &str, serde_json::Value) -> serde_json::Value + 'e;
+
 /// Struct used to parse entities from JSON.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct EntityJsonParser<'e, 's, S: Schema = NoEntitiesSchema> {
     /// See comments on [`EntityJsonParser::new()`] for the interpretation and
     /// effects of this `schema` field.
@@ -75,6 +80,25 @@ pub struct EntityJsonParser<'e, 's, S: Schema = NoEntitiesSchema> {
     /// Whether to compute, enforce, or assume TC for entities parsed using this
     /// parser.
     tc_computation: TCComputation,
+
+    /// Transform applied to the JSON value of each attribute before it is
+    /// parsed, if any
+    attribute_transform: Option<Arc<AttributeTransform<'e>>>,
+
+    /// Whether the parents of the parsed entities must be present
+    parse_mode: EntitiesParseMode,
+}
+
+impl<'e, 's, S: Schema + std::fmt::Debug> std::fmt::Debug for EntityJsonParser<'e, 's, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EntityJsonParser")
+            .field("schema", &self.schema)
+            .field("extensions", &self.extensions)
+            .field("tc_computation", &self.tc_computation)
+            .field("attribute_transform", &self.attribute_transform.is_some())
+            .field("parse_mode", &self.parse_mode)
+            .finish()
+    }
 }
 
 /// Schema information about a single entity can take one of these forms:
@@ -118,6 +142,44 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
             schema,
             extensions,
             tc_computation,
+            attribute_transform: None,
+            parse_mode: EntitiesParseMode::default(),
+        }
+    }
+
+    /// Check the parents of the parsed entities according to `parse_mode`.
+    ///
+    /// This only applies when parsing an [`Entities`], not when iterating over
+    /// the parsed entities with the `iter_from_json_*()` methods.
+    pub fn with_parse_mode(self, parse_mode: EntitiesParseMode) -> Self {
+        Self { parse_mode, ..self }
+    }
+
+    /// Transform the JSON value of each entity attribute with `transform`
+    /// before parsing it, e.g., to normalize values like email addresses.
+    /// `transform` is given the entity's UID, the attribute name, and the
+    /// attribute's JSON value, and returns the JSON value to parse instead.
+    ///
+    /// `transform` runs on the raw JSON, before any of the parsing described
+    /// on [`EntityJsonParser::new()`]: before `__entity` and `__extn` escapes
+    /// are interpreted, before extension values are constructed (whether from
+    /// explicit `__extn` escapes or, with a `schema`, from implicit ones), and
+    /// before the entity is checked against the `schema`. So the transformed
+    /// value is what gets validated, and to produce an extension value,
+    /// `transform` should return a value in one of the JSON forms of that
+    /// extension value, e.g., `{ "__extn": { "fn": "ip", "arg": "10.0.0.1" } }`.
+    ///
+    /// `transform` applies to every attribute of every entity in the JSON,
+    /// including action entities, but not to the action entities added from
+    /// the `schema`.
+    #[must_use]
+    pub fn with_attribute_transform(
+        self,
+        transform: impl Fn(&EntityUID, &str, serde_json::Value) -> serde_json::Value + 'e,
+    ) -> Self {
+        Self {
+            attribute_transform: Some(Arc::new(transform)),
+            ..self
         }
     }
 
@@ -225,9 +287,38 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
             .into_iter()
             .map(|ejson| self.parse_ejson(ejson))
             .collect::<Result<_, _>>()?;
+        if self.parse_mode == EntitiesParseMode::RequireParentsPresent {
+            self.check_parents_present(&entities)?;
+        }
         Entities::from_entities(entities, self.schema, self.tc_computation, self.extensions)
     }
 
+    /// Internal function that checks that the parents of `entities` are among
+    /// `entities` or the actions declared in the `schema`. This runs before
+    /// the transitive closure is computed, so only direct parents are checked.
+    fn check_parents_present(&self, entities: &[Entity]) -> Result<(), EntitiesError> {
+        let uids: HashSet<&EntityUID> = entities.iter().map(Entity::uid).collect();
+        let is_present = |parent: &EntityUID| {
+            uids.contains(parent)
+                || self
+                    .schema
+                    .is_some_and(|schema| schema.action(parent).is_some())
+        };
+        for entity in entities {
+            if let Some(parent) = entity
+                .ancestors()
+                .filter(|parent| !is_present(parent))
+                .min_by_key(|parent| parent.to_string())
+            {
+                return Err(EntitiesError::missing_parent(
+                    entity.uid().clone(),
+                    parent.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Internal function that parses an `EntityJson` into an `Entity`.
     ///
     /// This function is not responsible for fully validating the `Entity`
@@ -269,10 +360,17 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
         let attrs: HashMap<SmolStr, RestrictedExpr> = ejson
             .attrs
             .into_iter()
+            .map(|(k, v)| {
+                let v: serde_json::Value = match &self.attribute_transform {
+                    Some(transform) => transform(&uid, &k, v.into()),
+                    None => v.into(),
+                };
+                (k, v)
+            })
             .map(|(k, v)| match &entity_schema_info {
                 EntitySchemaInfo::NoSchema => Ok((
                     k.clone(),
-                    vparser.val_into_restricted_expr(v.into(), None, || {
+                    vparser.val_into_restricted_expr(v, None, || {
                         JsonDeserializationErrorContext::EntityAttribute {
                             uid: uid.clone(),
                             attr: k.clone(),
@@ -286,8 +384,15 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
                         // `None` indicates the attribute shouldn't exist -- see
                         // docs on the `attr_type()` trait method
                         None => {
-                            if desc.open_attributes() {
-                                vparser.val_into_restricted_expr(v.into(), None, || {
+                            if desc.derived_attrs().any(|(attr, _)| attr == k) {
+                                return Err(JsonDeserializationError::EntitySchemaConformance(
+                                    EntitySchemaConformanceError::derived_entity_attr(
+                                        uid.clone(),
+                                        k,
+                                    ),
+                                ));
+                            } else if desc.open_attributes() {
+                                vparser.val_into_restricted_expr(v, None, || {
                                     JsonDeserializationErrorContext::EntityAttribute {
                                         uid: uid.clone(),
                                         attr: k.clone(),
@@ -302,14 +407,14 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
                                 ));
                             }
                         }
-                        Some(expected_ty) => vparser.val_into_restricted_expr(
-                            v.into(),
-                            Some(&expected_ty),
-                            || JsonDeserializationErrorContext::EntityAttribute {
-                                uid: uid.clone(),
-                                attr: k.clone(),
-                            },
-                        )?,
+                        Some(expected_ty) => {
+                            vparser.val_into_restricted_expr(v, Some(&expected_ty), || {
+                                JsonDeserializationErrorContext::EntityAttribute {
+                                    uid: uid.clone(),
+                                    attr: k.clone(),
+                                }
+                            })?
+                        }
                     };
                     Ok((k.clone(), rexpr))
                 }
@@ -358,7 +463,7 @@ impl<'e, 's, S: Schema> EntityJsonParser<'e, 's, S> {
                             }
                         }?;
                     let rexpr =
-                        vparser.val_into_restricted_expr(v.into(), expected_ty.as_ref(), || {
+                        vparser.val_into_restricted_expr(v, expected_ty.as_ref(), || {
                             JsonDeserializationErrorContext::EntityAttribute {
                                 uid: uid.clone(),
                                 attr: k.clone(),
@@ -578,6 +578,8 @@ pub enum JsonDeserializationErrorContext {
     },
     /// The error occured while deserializing a template link
     TemplateLink,
+    /// The error occurred while deserializing a single restricted expression.
+    RestrictedExpression,
     /// The context was unknown, this shouldn't surface to users
     Unknown,
 }
@@ -616,6 +618,7 @@ impl std::fmt::Display for JsonDeserializationErrorContext {
             Self::Context => write!(f, "while parsing context"),
             Self::Policy { id } => write!(f, "while parsing JSON policy `{id}`"),
             Self::TemplateLink => write!(f, "while parsing a template link"),
+            Self::RestrictedExpression => write!(f, "while parsing a restricted expression"),
             Self::Unknown => write!(f, "parsing context was unknown, please file a bug report at https://github.com/cedar-policy/cedar so we can improve this error message"),
         }
     }
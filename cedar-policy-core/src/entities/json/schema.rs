@@ -15,7 +15,7 @@
  */
 
 use super::SchemaType;
-use crate::ast::{Entity, EntityType, EntityUID, Id, Name};
+use crate::ast::{Entity, EntityType, EntityUID, Expr, Id, Name};
 use smol_str::SmolStr;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -130,6 +130,21 @@ pub trait EntityTypeDescription {
     /// May entities with this type have attributes other than those specified
     /// in the schema
     fn open_attributes(&self) -> bool;
+
+    /// Get the attributes of this entity type which are computed from its
+    /// other attributes, each with the expression computing it. In the
+    /// expression, the entity is `resource`.
+    ///
+    /// Derived attributes must not be given a value in entity data.
+    fn derived_attrs<'s>(&'s self) -> Box<dyn Iterator<Item = (SmolStr, Expr)> + 's> {
+        Box::new(std::iter::empty())
+    }
+
+    /// Get the groups of attributes of this entity type of which an entity
+    /// may have at most one.
+    fn mutually_exclusive_attrs<'s>(&'s self) -> Box<dyn Iterator<Item = &'s [SmolStr]> + 's> {
+        Box::new(std::iter::empty())
+    }
 }
 
 /// Simple type that implements `EntityTypeDescription` by expecting no
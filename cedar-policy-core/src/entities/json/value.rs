@@ -226,6 +226,44 @@ impl CedarValueJson {
         }
     }
 
+    /// Rebase every entity type in this value, including in nested sets and
+    /// records, with [`Name::rebase_namespace`]. Entity types which are not
+    /// valid names are left unchanged.
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> Self {
+        match self {
+            Self::EntityEscape { __entity } => Self::EntityEscape {
+                __entity: TypeAndId {
+                    entity_type: match Name::from_normalized_str(&__entity.entity_type) {
+                        Ok(name) => name.rebase_namespace(from, to).to_string().into(),
+                        Err(_) => __entity.entity_type.clone(),
+                    },
+                    id: __entity.id.clone(),
+                },
+            },
+            Self::ExtnEscape { __extn } => Self::ExtnEscape {
+                __extn: FnAndArg {
+                    ext_fn: __extn.ext_fn.clone(),
+                    arg: Box::new(__extn.arg.rebase_namespace(from, to)),
+                },
+            },
+            Self::Set(vals) => {
+                Self::Set(vals.iter().map(|v| v.rebase_namespace(from, to)).collect())
+            }
+            Self::Record(record) => Self::Record(JsonRecord {
+                values: record
+                    .values
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.rebase_namespace(from, to)))
+                    .collect(),
+            }),
+            Self::ExprEscape { .. }
+            | Self::Bool(_)
+            | Self::Long(_)
+            | Self::String(_)
+            | Self::Null => self.clone(),
+        }
+    }
+
     /// Convert this `CedarValueJson` into a Cedar "restricted expression"
     pub fn into_expr(
         self,
@@ -527,7 +565,7 @@ impl<'e> ValueParser<'e> {
                 serde_json::Value::Object(mut actual_attrs) => {
                     let ctx2 = ctx.clone(); // for borrow-check, so the original `ctx` can be moved into the closure below
                     let mut_actual_attrs = &mut actual_attrs; // for borrow-check, so only a mut ref gets moved into the closure, and we retain ownership of `actual_attrs`
-                    let rexpr_pairs = expected_attrs
+                    let mut rexpr_pairs = expected_attrs
                         .iter()
                         .filter_map(move |(k, expected_attr_ty)| {
                             match mut_actual_attrs.remove(k.as_str()) {
@@ -543,15 +581,22 @@ impl<'e> ValueParser<'e> {
                         })
                         .collect::<Result<Vec<(SmolStr, RestrictedExpr)>, JsonDeserializationError>>()?;
 
-                    if !open_attrs {
-                        // we've now checked that all expected attrs exist, and removed them from `actual_attrs`.
-                        // we still need to verify that we didn't have any unexpected attrs.
-                        if let Some((record_attr, _)) = actual_attrs.into_iter().next() {
-                            return Err(JsonDeserializationError::unexpected_record_attr(
-                                ctx2(),
-                                record_attr,
-                            ));
+                    // we've now checked that all expected attrs exist, and removed them from `actual_attrs`.
+                    // any attrs which remain are unexpected attrs.
+                    if *open_attrs {
+                        // an open record may have attrs not in its type. keep
+                        // them, parsing them according to their JSON shape
+                        // like we do for values with no expected type
+                        for (k, actual_attr) in actual_attrs {
+                            let rexpr =
+                                self.val_into_restricted_expr(actual_attr, None, ctx2.clone())?;
+                            rexpr_pairs.push((k.into(), rexpr));
                         }
+                    } else if let Some((record_attr, _)) = actual_attrs.into_iter().next() {
+                        return Err(JsonDeserializationError::unexpected_record_attr(
+                            ctx2(),
+                            record_attr,
+                        ));
                     }
 
                     // having duplicate keys should be impossible here (because
@@ -96,6 +96,11 @@ impl Policy {
             annotations: self.annotations,
         })
     }
+
+    /// The `when` and `unless` clauses of the policy, in order
+    pub fn conditions(&self) -> impl Iterator<Item = &Clause> {
+        self.conditions.iter()
+    }
 }
 
 impl Clause {
@@ -3113,6 +3118,83 @@ mod test {
         );
     }
 
+    #[test]
+    fn has_attr_path() {
+        let policy = json!(
+            {
+                "effect": "permit",
+                "principal": {
+                    "op": "All"
+                },
+                "action": {
+                    "op": "All"
+                },
+                "resource": {
+                    "op": "All"
+                },
+                "conditions": [
+                    {
+                        "kind": "when",
+                        "body": {
+                            "has": {
+                                "left": { "Var": "context" },
+                                "attr": "a b",
+                                "path": ["c", "d"]
+                            }
+                        }
+                    }
+                ]
+            }
+        );
+        let est: Policy = serde_json::from_value(policy.clone()).unwrap();
+        let ast = est.clone().try_into_ast_policy(None).unwrap();
+        assert_eq!(
+            ast.to_string(),
+            "permit(\n  principal,\n  action,\n  resource\n) when {\n  context has \"a b\".c.d\n};"
+        );
+        assert_eq!(
+            serde_json::to_value(ast_roundtrip(est.clone())).unwrap(),
+            policy
+        );
+        assert_eq!(
+            serde_json::to_value(circular_roundtrip(est)).unwrap(),
+            policy
+        );
+
+        let bad = json!(
+            {
+                "effect": "permit",
+                "principal": {
+                    "op": "All"
+                },
+                "action": {
+                    "op": "All"
+                },
+                "resource": {
+                    "op": "All"
+                },
+                "conditions": [
+                    {
+                        "kind": "when",
+                        "body": {
+                            "has": {
+                                "left": { "Var": "context" },
+                                "attr": "a",
+                                "path": ["b c"]
+                            }
+                        }
+                    }
+                ]
+            }
+        );
+        assert_matches!(
+            serde_json::from_value::<Policy>(bad)
+                .unwrap()
+                .try_into_ast_policy(None),
+            Err(FromJsonError::InvalidAttributePath(_)),
+        );
+    }
+
     #[test]
     fn invalid_json_ests() {
         let bad = json!(
@@ -80,6 +80,11 @@ pub enum FromJsonError {
     #[error("invalid entity type: {0}")]
     #[diagnostic(transparent)]
     InvalidEntityType(ParseErrors),
+    /// Error reported when a nested attribute tested by a `has` expression,
+    /// as in `e has a.b`, is not a valid identifier.
+    #[error("invalid attribute in `has` path: {0}")]
+    #[diagnostic(transparent)]
+    InvalidAttributePath(ParseErrors),
     /// Error reported when a policy set has duplicate ids
     #[error("Error creating policy set: {0}")]
     #[diagnostic(transparent)]
@@ -28,6 +28,7 @@ use crate::parser::{Loc, Node};
 use crate::{ast, FromNormalizedStr};
 use either::Either;
 use itertools::Itertools;
+use nonempty::NonEmpty;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use smol_str::{SmolStr, ToSmolStr};
@@ -260,6 +261,10 @@ pub enum ExprNoExt {
         left: Arc<Expr>,
         /// Attribute name
         attr: SmolStr,
+        /// Names of the nested attributes within `attr`, as in `left has attr.b.c`
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        path: Vec<SmolStr>,
     },
     /// `like`
     #[serde(rename = "like")]
@@ -493,6 +498,16 @@ impl Expr {
         Expr::ExprNoExt(ExprNoExt::HasAttr {
             left: Arc::new(left),
             attr,
+            path: Vec::new(),
+        })
+    }
+
+    /// `left has a.b.c`
+    pub fn has_attr_path(left: Expr, path: NonEmpty<SmolStr>) -> Self {
+        Expr::ExprNoExt(ExprNoExt::HasAttr {
+            left: Arc::new(left),
+            attr: path.head,
+            path: path.tail,
         })
     }
 
@@ -641,8 +656,17 @@ impl Expr {
             Expr::ExprNoExt(ExprNoExt::GetAttr { left, attr }) => {
                 Ok(ast::Expr::get_attr((*left).clone().try_into_ast(id)?, attr))
             }
-            Expr::ExprNoExt(ExprNoExt::HasAttr { left, attr }) => {
-                Ok(ast::Expr::has_attr((*left).clone().try_into_ast(id)?, attr))
+            Expr::ExprNoExt(ExprNoExt::HasAttr { left, attr, path }) => {
+                // the nested attributes are written as identifiers, so they
+                // must be valid ones
+                for attr in &path {
+                    ast::Id::from_normalized_str(attr)
+                        .map_err(FromJsonError::InvalidAttributePath)?;
+                }
+                Ok(ast::Expr::has_attr_path(
+                    (*left).clone().try_into_ast(id)?,
+                    NonEmpty::from((attr, path)),
+                ))
             }
             Expr::ExprNoExt(ExprNoExt::Like { left, pattern }) => Ok(ast::Expr::like(
                 (*left).clone().try_into_ast(id)?,
@@ -782,9 +806,10 @@ impl From<ast::Expr> for Expr {
             ast::ExprKind::GetAttr { expr, attr } => {
                 Expr::get_attr(Arc::unwrap_or_clone(expr).into(), attr)
             }
-            ast::ExprKind::HasAttr { expr, attr } => {
-                Expr::has_attr(Arc::unwrap_or_clone(expr).into(), attr)
-            }
+            ast::ExprKind::HasAttr { expr, attr, path } => Expr::has_attr_path(
+                Arc::unwrap_or_clone(expr).into(),
+                NonEmpty::from((attr, path)),
+            ),
             ast::ExprKind::Like { expr, pattern } => Expr::like(
                 Arc::unwrap_or_clone(expr).into(),
                 Vec::<PatternElem>::from(pattern),
@@ -910,12 +935,10 @@ impl TryFrom<&Node<Option<cst::Relation>>> for Expr {
                 Ok(expr)
             }
             cst::Relation::Has { target, field } => {
-                let target_expr = target.try_into()?;
+                let target_expr: Expr = target.try_into()?;
                 let mut errs = ParseErrors::new();
-                if let Some(field_expr) = field.to_expr_or_special(&mut errs) {
-                    if let Some(attr) = field_expr.into_valid_attr(&mut errs) {
-                        return Ok(Expr::has_attr(target_expr, attr));
-                    }
+                if let Some(path) = field.to_has_attr_path(&mut errs) {
+                    return Ok(Expr::has_attr_path(target_expr, path));
                 }
                 if errs.is_empty() {
                     Err(field
@@ -1597,12 +1620,18 @@ impl std::fmt::Display for ExprNoExt {
                 maybe_with_parens(left),
                 attr.escape_debug()
             ),
-            ExprNoExt::HasAttr { left, attr } => write!(
-                f,
-                "{} has \"{}\"",
-                maybe_with_parens(left),
-                attr.escape_debug()
-            ),
+            ExprNoExt::HasAttr { left, attr, path } => {
+                write!(
+                    f,
+                    "{} has \"{}\"",
+                    maybe_with_parens(left),
+                    attr.escape_debug()
+                )?;
+                for attr in path {
+                    write!(f, ".{attr}")?;
+                }
+                Ok(())
+            }
             ExprNoExt::Like { left, pattern } => {
                 write!(
                     f,
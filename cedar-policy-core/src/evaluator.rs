@@ -20,16 +20,21 @@ use crate::ast::*;
 use crate::entities::{Dereference, Entities};
 use crate::extensions::Extensions;
 use crate::parser::Loc;
+use std::cell::Cell;
 #[cfg(test)]
 use std::collections::HashMap;
 use std::sync::Arc;
 
 mod err;
 pub(crate) use err::*;
-pub use err::{EvaluationError, EvaluationErrorKind};
+pub use err::{EvalLimitExceeded, EvaluationError, EvaluationErrorKind};
+mod fold;
+pub use fold::{fold_constants, fold_template_constants};
+mod trace;
 use itertools::Either;
-use nonempty::nonempty;
+use nonempty::{nonempty, NonEmpty};
 use smol_str::SmolStr;
+pub use trace::{AttributeAccess, AttributeAccessKind, EvalStep, EvalTrace};
 
 #[cfg(not(target_arch = "wasm32"))]
 const REQUIRED_STACK_SPACE: usize = 1024 * 100;
@@ -65,6 +70,77 @@ pub struct Evaluator<'e> {
     entities: &'e Entities,
     /// Extensions which are active for this evaluation
     extensions: &'e Extensions<'e>,
+    /// Limits on the work done by this evaluator
+    limits: EvalLimits,
+    /// Number of evaluation steps taken so far, across all the expressions
+    /// evaluated by this evaluator
+    steps: Cell<u64>,
+    /// Depth of the expression currently being evaluated, i.e., the number of
+    /// enclosing expressions whose evaluation is in progress
+    depth: Cell<usize>,
+}
+
+/// Limits on the work done by an `Evaluator`, to bound the cost of evaluating
+/// policies against untrusted requests and entities.
+///
+/// By default, no limit is enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvalLimits {
+    /// Maximum number of expression-evaluation steps
+    max_steps: Option<u64>,
+    /// Maximum number of elements of a set materialized during evaluation
+    max_set_size: Option<usize>,
+    /// Maximum depth of nested expression evaluation
+    max_depth: Option<usize>,
+}
+
+impl EvalLimits {
+    /// Create limits which do not restrict evaluation at all
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the total number of expression-evaluation steps. Each
+    /// subexpression evaluated counts as one step.
+    pub fn with_max_steps(self, max_steps: u64) -> Self {
+        Self {
+            max_steps: Some(max_steps),
+            ..self
+        }
+    }
+
+    /// Limit the number of elements of any set materialized during evaluation
+    pub fn with_max_set_size(self, max_set_size: usize) -> Self {
+        Self {
+            max_set_size: Some(max_set_size),
+            ..self
+        }
+    }
+
+    /// Limit the depth of nested expression evaluation, e.g., to bound the
+    /// stack space used to evaluate deeply nested policies. Each
+    /// subexpression is one level deeper than the expression containing it.
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self {
+            max_depth: Some(max_depth),
+            ..self
+        }
+    }
+
+    /// Get the maximum number of expression-evaluation steps, if limited
+    pub fn max_steps(&self) -> Option<u64> {
+        self.max_steps
+    }
+
+    /// Get the maximum number of elements of a materialized set, if limited
+    pub fn max_set_size(&self) -> Option<usize> {
+        self.max_set_size
+    }
+
+    /// Get the maximum depth of nested expression evaluation, if limited
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
 }
 
 /// Evaluator for "restricted" expressions. See notes on `RestrictedExpr`.
@@ -195,9 +271,51 @@ impl<'e> Evaluator<'e> {
             },
             entities,
             extensions,
+            limits: EvalLimits::default(),
+            steps: Cell::new(0),
+            depth: Cell::new(0),
+        }
+    }
+
+    /// Return this `Evaluator`, enforcing the given `EvalLimits` on all the
+    /// evaluation it does from now on. Evaluation exceeding the limits fails
+    /// with an [`EvalLimitExceeded`] error.
+    pub fn with_limits(self, limits: EvalLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    /// Count one evaluation step, failing if that exceeds the step limit
+    fn count_step(&self) -> Result<()> {
+        let steps = self.steps.get().saturating_add(1);
+        self.steps.set(steps);
+        match self.limits.max_steps {
+            Some(limit) if steps > limit => Err(EvaluationError::limit_exceeded(
+                EvalLimitExceeded::Steps { limit },
+                None,
+            )),
+            _ => Ok(()),
         }
     }
 
+    /// Enter the evaluation of a nested expression, failing if that exceeds
+    /// the depth limit. Every successful call must be matched by a call to
+    /// `exit_expr()`.
+    fn enter_expr(&self) -> std::result::Result<(), EvalLimitExceeded> {
+        let depth = self.depth.get() + 1;
+        match self.limits.max_depth {
+            Some(limit) if depth > limit => Err(EvalLimitExceeded::Depth { limit }),
+            _ => {
+                self.depth.set(depth);
+                Ok(())
+            }
+        }
+    }
+
+    /// Leave the evaluation of a nested expression entered with `enter_expr()`
+    fn exit_expr(&self) {
+        self.depth.set(self.depth.get() - 1);
+    }
+
     /// Evaluate the given `Policy`, returning either a bool or an error.
     /// The bool indicates whether the policy applies, ie, "is satisfied" for the
     /// current `request`.
@@ -267,7 +385,13 @@ impl<'e> Evaluator<'e> {
     pub fn partial_interpret(&self, expr: &Expr, slots: &SlotEnv) -> Result<PartialValue> {
         stack_size_check()?;
 
-        let res = self.partial_interpret_internal(expr, slots);
+        let res = self.count_step().and_then(|()| {
+            self.enter_expr()
+                .map_err(|err| EvaluationError::limit_exceeded(err, None))?;
+            let res = self.partial_interpret_internal(expr, slots);
+            self.exit_expr();
+            res
+        });
 
         // set the returned value's source location to the same source location
         // as the input expression had.
@@ -565,30 +689,10 @@ impl<'e> Evaluator<'e> {
                 }
             }
             ExprKind::GetAttr { expr, attr } => self.get_attr(expr.as_ref(), attr, slots, loc),
-            ExprKind::HasAttr { expr, attr } => match self.partial_interpret(expr, slots)? {
-                PartialValue::Value(Value {
-                    value: ValueKind::Record(record),
-                    ..
-                }) => Ok(record.get(attr).is_some().into()),
-                PartialValue::Value(Value {
-                    value: ValueKind::Lit(Literal::EntityUID(uid)),
-                    ..
-                }) => match self.entities.entity(&uid) {
-                    Dereference::NoSuchEntity => Ok(false.into()),
-                    Dereference::Residual(r) => {
-                        Ok(PartialValue::Residual(Expr::has_attr(r, attr.clone())))
-                    }
-                    Dereference::Data(e) => Ok(e.get(attr).is_some().into()),
-                },
-                PartialValue::Value(val) => Err(err::EvaluationError::type_error(
-                    nonempty![
-                        Type::Record,
-                        Type::entity_type(names::ANY_ENTITY_TYPE.clone())
-                    ],
-                    &val,
-                )),
-                PartialValue::Residual(r) => Ok(Expr::has_attr(r, attr.clone()).into()),
-            },
+            ExprKind::HasAttr { expr, attr, path } => {
+                let target = self.partial_interpret(expr, slots)?;
+                self.has_attr_path(target, attr, path, slots, loc)
+            }
             ExprKind::Like { expr, pattern } => {
                 let v = self.partial_interpret(expr, slots)?;
                 match v {
@@ -616,6 +720,17 @@ impl<'e> Evaluator<'e> {
                     .iter()
                     .map(|item| self.partial_interpret(item, slots))
                     .collect::<Result<Vec<_>>>()?;
+                if let Some(limit) = self.limits.max_set_size {
+                    if vals.len() > limit {
+                        return Err(EvaluationError::limit_exceeded(
+                            EvalLimitExceeded::SetSize {
+                                limit,
+                                size: vals.len(),
+                            },
+                            loc.cloned(),
+                        ));
+                    }
+                }
                 match split(vals) {
                     Either::Left(vals) => Ok(Value::set(vals, loc.cloned()).into()),
                     Either::Right(r) => Ok(Expr::set(r).into()),
@@ -711,6 +826,88 @@ impl<'e> Evaluator<'e> {
         }
     }
 
+    /// Does `target` have the attribute `attr`?
+    fn has_attr(&self, target: PartialValue, attr: &SmolStr) -> Result<PartialValue> {
+        match target {
+            PartialValue::Value(Value {
+                value: ValueKind::Record(record),
+                ..
+            }) => Ok(record.get(attr).is_some().into()),
+            PartialValue::Value(Value {
+                value: ValueKind::Lit(Literal::EntityUID(uid)),
+                ..
+            }) => match self.entities.entity(&uid) {
+                Dereference::NoSuchEntity => Ok(false.into()),
+                Dereference::Residual(r) => {
+                    Ok(PartialValue::Residual(Expr::has_attr(r, attr.clone())))
+                }
+                Dereference::Data(e) => Ok((e.get(attr).is_some()
+                    || self
+                        .entities
+                        .derived_attr(uid.entity_type(), attr)
+                        .is_some())
+                .into()),
+            },
+            PartialValue::Value(val) => Err(err::EvaluationError::type_error(
+                nonempty![
+                    Type::Record,
+                    Type::entity_type(names::ANY_ENTITY_TYPE.clone())
+                ],
+                &val,
+            )),
+            PartialValue::Residual(r) => Ok(Expr::has_attr(r, attr.clone()).into()),
+        }
+    }
+
+    /// Does `target` have the attribute `attr`, which in turn has the nested
+    /// attributes in `path`? Only `target` must be an entity or record: if one
+    /// of the nested attributes before the last is neither, it has no
+    /// attributes, so the result is `false`.
+    fn has_attr_path(
+        &self,
+        target: PartialValue,
+        attr: &SmolStr,
+        path: &[SmolStr],
+        slots: &SlotEnv,
+        source_loc: Option<&Loc>,
+    ) -> Result<PartialValue> {
+        let residual = |target: PartialValue| {
+            Ok(PartialValue::Residual(
+                Expr::has_attr_path(target.into(), NonEmpty::from((attr.clone(), path.to_vec())))
+                    .with_maybe_source_loc(source_loc.cloned()),
+            ))
+        };
+        let mut has = self.has_attr(target.clone(), attr)?;
+        let mut value = target.clone();
+        let mut prev = attr;
+        for next in path {
+            match has {
+                PartialValue::Value(has) => {
+                    if !has.get_as_bool()? {
+                        return Ok(false.into());
+                    }
+                }
+                PartialValue::Residual(_) => return residual(target),
+            }
+            value = self.get_attr(&value.into(), prev, slots, source_loc)?;
+            if let PartialValue::Value(Value {
+                value: ValueKind::Record(_) | ValueKind::Lit(Literal::EntityUID(_)),
+                ..
+            })
+            | PartialValue::Residual(_) = value
+            {
+                has = self.has_attr(value.clone(), next)?;
+                prev = next;
+            } else {
+                return Ok(false.into());
+            }
+        }
+        match has {
+            PartialValue::Residual(_) if !path.is_empty() => residual(target),
+            has => Ok(has),
+        }
+    }
+
     /// We don't use the `source_loc()` on `expr` because that's only the loc
     /// for the LHS of the GetAttr. `source_loc` argument should be the loc for
     /// the entire GetAttr expression
@@ -791,16 +988,17 @@ impl<'e> Evaluator<'e> {
                 Dereference::Residual(r) => {
                     Ok(PartialValue::Residual(Expr::get_attr(r, attr.clone())))
                 }
-                Dereference::Data(entity) => entity
-                    .get(attr)
-                    .ok_or_else(|| {
-                        EvaluationError::entity_attr_does_not_exist(
+                Dereference::Data(entity) => match entity.get(attr) {
+                    Some(v) => Ok(v.clone()),
+                    None => match self.entities.derived_attr(uid.entity_type(), attr) {
+                        Some(derived) => self.derived_attr(uid, derived),
+                        None => Err(EvaluationError::entity_attr_does_not_exist(
                             uid,
                             attr.clone(),
                             source_loc.cloned(),
-                        )
-                    })
-                    .cloned(),
+                        )),
+                    },
+                },
             },
             PartialValue::Value(v) => {
                 // PANIC SAFETY Entity type name is fully static and a valid unqualified `Name`
@@ -816,6 +1014,31 @@ impl<'e> Evaluator<'e> {
         }
     }
 
+    /// Compute a derived attribute of the entity `uid` using the expression
+    /// `derived` from the schema, in which the entity is `resource`. The
+    /// expression can't refer to the rest of the request, so it is evaluated
+    /// with an otherwise empty request. The steps it takes count towards this
+    /// evaluator's limit.
+    fn derived_attr(&self, uid: Arc<EntityUID>, derived: &Expr) -> Result<PartialValue> {
+        let evaluator = Evaluator {
+            principal: EntityUIDEntry::Unknown { loc: None },
+            action: EntityUIDEntry::Unknown { loc: None },
+            resource: EntityUIDEntry::Known {
+                euid: uid,
+                loc: None,
+            },
+            context: PartialValue::Value(Value::empty_record(None)),
+            entities: self.entities,
+            extensions: self.extensions,
+            limits: self.limits,
+            steps: Cell::new(self.steps.get()),
+            depth: Cell::new(self.depth.get()),
+        };
+        let result = evaluator.partial_interpret(derived, &SlotEnv::new());
+        self.steps.set(evaluator.steps.get());
+        result
+    }
+
     #[cfg(test)]
     pub fn interpret_inline_policy(&self, e: &Expr) -> Result<Value> {
         match self.partial_interpret(e, &HashMap::new())? {
@@ -1183,6 +1406,32 @@ pub mod test {
         assert_eq!(r, Either::Right(expected_residual));
     }
 
+    #[test]
+    fn depth_limit() {
+        let request = basic_request();
+        let entities = basic_entities();
+        let exts = Extensions::none();
+        let limits = EvalLimits::new().with_max_depth(10);
+        let eval = Evaluator::new(request, &entities, &exts).with_limits(limits);
+        let nested = |depth: usize| (1..depth).fold(Expr::val(true), |e, _| Expr::not(e));
+        assert_eq!(
+            eval.interpret_inline_policy(&nested(9)),
+            Ok(Value::from(true))
+        );
+        // the depth is back to 0 after evaluating an expression
+        assert_eq!(
+            eval.interpret_inline_policy(&nested(10)),
+            Ok(Value::from(false))
+        );
+        assert_matches!(
+            eval.interpret_inline_policy(&nested(11)),
+            Err(e) => assert_eq!(
+                e.error_kind(),
+                &EvaluationErrorKind::LimitExceeded(EvalLimitExceeded::Depth { limit: 10 })
+            )
+        );
+    }
+
     #[test]
     fn interpret_primitives() {
         let request = basic_request();
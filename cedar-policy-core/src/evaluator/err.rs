@@ -254,6 +254,14 @@ impl EvaluationError {
             source_loc,
         }
     }
+
+    /// Construct a [`LimitExceeded`] error
+    pub(crate) fn limit_exceeded(err: EvalLimitExceeded, source_loc: Option<Loc>) -> Self {
+        Self {
+            error_kind: err.into(),
+            source_loc,
+        }
+    }
 }
 
 impl From<RestrictedExprError> for EvaluationError {
@@ -356,6 +364,36 @@ pub enum EvaluationErrorKind {
     /// Maximum recursion limit reached for expression evaluation
     #[error("recursion limit reached")]
     RecursionLimit,
+
+    /// Evaluation exceeded one of the `EvalLimits` it was given
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    LimitExceeded(#[from] EvalLimitExceeded),
+}
+
+/// Error raised when evaluation exceeds one of the `EvalLimits` it was given
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Diagnostic, Error)]
+pub enum EvalLimitExceeded {
+    /// Evaluation took more steps than allowed
+    #[error("evaluation step limit of {limit} exceeded")]
+    Steps {
+        /// The maximum number of steps allowed
+        limit: u64,
+    },
+    /// Evaluation materialized a set with more elements than allowed
+    #[error("set size limit of {limit} exceeded by a set with {size} elements")]
+    SetSize {
+        /// The maximum number of elements allowed in a set
+        limit: usize,
+        /// The number of elements in the offending set
+        size: usize,
+    },
+    /// Evaluation nested more deeply than allowed
+    #[error("evaluation depth limit of {limit} exceeded")]
+    Depth {
+        /// The maximum depth of nested expression evaluation
+        limit: usize,
+    },
 }
 
 /// helper function for pretty-printing type errors
@@ -0,0 +1,422 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains constant folding for expressions and policies.
+//!
+//! Folding evaluates the sub-expressions which don't depend on the request,
+//! the entity store, or template slots, and removes the dead branches of
+//! `if`, `&&` and `||` whose condition is a constant. The folded expression is
+//! equivalent to the original for every request and entity store.
+
+use std::collections::BTreeMap;
+
+use super::Evaluator;
+use crate::ast::{EntityUIDEntry, Expr, ExprKind, Literal, Request, SlotEnv, Template, UnaryOp};
+use crate::entities::Entities;
+use crate::extensions::Extensions;
+use nonempty::NonEmpty;
+
+/// Fold the constant sub-expressions of `expr`.
+///
+/// Sub-expressions which would produce an error when evaluated are left as
+/// they are, so that the error is still reported at evaluation time.
+pub fn fold_constants(expr: &Expr, extensions: &Extensions<'_>) -> Expr {
+    Folder::new(extensions).fold(expr, false).expr
+}
+
+/// Fold the constant sub-expressions of the `when` and `unless` conditions
+/// of `template`. The scope of the template is unchanged.
+pub fn fold_template_constants(template: &Template, extensions: &Extensions<'_>) -> Template {
+    // The non-scope constraints are the right operand of the `&&` in
+    // `Template::condition()`, so they are always in a boolean context
+    let folded = Folder::new(extensions)
+        .fold(template.non_scope_constraints(), true)
+        .expr;
+    Template::new_shared(
+        template.id().clone(),
+        template.loc().clone(),
+        template.annotations_arc().clone(),
+        template.effect(),
+        template.principal_constraint().clone(),
+        template.action_constraint().clone(),
+        template.resource_constraint().clone(),
+        folded.into(),
+    )
+}
+
+/// A (possibly) folded expression
+struct Folded {
+    expr: Expr,
+    /// Whether `expr` is independent of the request, the entity store, and
+    /// template slots, so that evaluating it gives the same result every time.
+    /// Entity literals are not constant, as operations on them (`in`, `has`,
+    /// attribute access) depend on the entity store.
+    constant: bool,
+}
+
+impl Folded {
+    fn as_bool(&self) -> Option<bool> {
+        match self.expr.expr_kind() {
+            ExprKind::Lit(Literal::Bool(b)) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+struct Folder<'e> {
+    extensions: &'e Extensions<'e>,
+    entities: Entities,
+}
+
+impl<'e> Folder<'e> {
+    fn new(extensions: &'e Extensions<'e>) -> Self {
+        Self {
+            extensions,
+            entities: Entities::new(),
+        }
+    }
+
+    /// Fold `expr`. If `bool_context` is true, then the result of `expr` is
+    /// required to be a boolean by its parent expression (e.g., it is an
+    /// operand of `&&`), so a non-boolean result is an error either way. This
+    /// allows `true && e` to be replaced by `e`.
+    fn fold(&self, expr: &Expr, bool_context: bool) -> Folded {
+        let loc = expr.source_loc().cloned();
+        let folded = match expr.expr_kind() {
+            ExprKind::Lit(lit) => {
+                return Folded {
+                    expr: expr.clone(),
+                    constant: !matches!(lit, Literal::EntityUID(_)),
+                }
+            }
+            ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => {
+                return Folded {
+                    expr: expr.clone(),
+                    constant: false,
+                }
+            }
+            ExprKind::If {
+                test_expr,
+                then_expr,
+                else_expr,
+            } => {
+                let test = self.fold(test_expr, true);
+                match test.as_bool() {
+                    Some(true) => return self.fold(then_expr, bool_context),
+                    Some(false) => return self.fold(else_expr, bool_context),
+                    None => {
+                        let then_folded = self.fold(then_expr, bool_context);
+                        let else_folded = self.fold(else_expr, bool_context);
+                        Folded {
+                            constant: test.constant && then_folded.constant && else_folded.constant,
+                            expr: Expr::ite(test.expr, then_folded.expr, else_folded.expr),
+                        }
+                    }
+                }
+            }
+            ExprKind::And { left, right } => {
+                let left = self.fold(left, true);
+                match left.as_bool() {
+                    Some(false) => return left,
+                    Some(true) if bool_context => return self.fold(right, true),
+                    _ => {
+                        let right = self.fold(right, true);
+                        if bool_context && right.as_bool() == Some(true) {
+                            return left;
+                        }
+                        Folded {
+                            constant: left.constant && right.constant,
+                            expr: Expr::and(left.expr, right.expr),
+                        }
+                    }
+                }
+            }
+            ExprKind::Or { left, right } => {
+                let left = self.fold(left, true);
+                match left.as_bool() {
+                    Some(true) => return left,
+                    Some(false) if bool_context => return self.fold(right, true),
+                    _ => {
+                        let right = self.fold(right, true);
+                        if bool_context && right.as_bool() == Some(false) {
+                            return left;
+                        }
+                        Folded {
+                            constant: left.constant && right.constant,
+                            expr: Expr::or(left.expr, right.expr),
+                        }
+                    }
+                }
+            }
+            ExprKind::UnaryApp { op, arg } => {
+                let arg = self.fold(arg, matches!(op, UnaryOp::Not));
+                Folded {
+                    constant: arg.constant,
+                    expr: Expr::unary_app(*op, arg.expr),
+                }
+            }
+            ExprKind::BinaryApp { op, arg1, arg2 } => {
+                let arg1 = self.fold(arg1, false);
+                let arg2 = self.fold(arg2, false);
+                Folded {
+                    constant: arg1.constant && arg2.constant,
+                    expr: Expr::binary_app(*op, arg1.expr, arg2.expr),
+                }
+            }
+            ExprKind::ExtensionFunctionApp { fn_name, args } => {
+                let args: Vec<_> = args.iter().map(|arg| self.fold(arg, false)).collect();
+                Folded {
+                    constant: args.iter().all(|arg| arg.constant),
+                    expr: Expr::call_extension_fn(
+                        fn_name.clone(),
+                        args.into_iter().map(|arg| arg.expr).collect(),
+                    ),
+                }
+            }
+            ExprKind::GetAttr { expr, attr } => {
+                let expr = self.fold(expr, false);
+                Folded {
+                    constant: expr.constant,
+                    expr: Expr::get_attr(expr.expr, attr.clone()),
+                }
+            }
+            ExprKind::HasAttr { expr, attr, path } => {
+                let expr = self.fold(expr, false);
+                Folded {
+                    constant: expr.constant,
+                    expr: Expr::has_attr_path(
+                        expr.expr,
+                        NonEmpty::from((attr.clone(), path.clone())),
+                    ),
+                }
+            }
+            ExprKind::Like { expr, pattern } => {
+                let expr = self.fold(expr, false);
+                Folded {
+                    constant: expr.constant,
+                    expr: Expr::like(expr.expr, pattern.iter().cloned()),
+                }
+            }
+            ExprKind::Is { expr, entity_type } => {
+                let expr = self.fold(expr, false);
+                Folded {
+                    constant: expr.constant,
+                    expr: Expr::is_entity_type(expr.expr, entity_type.clone()),
+                }
+            }
+            // Sets and records are not evaluated themselves, as evaluating
+            // them would only reorder and deduplicate their elements
+            ExprKind::Set(elements) => {
+                let elements: Vec<_> = elements.iter().map(|e| self.fold(e, false)).collect();
+                return Folded {
+                    constant: elements.iter().all(|e| e.constant),
+                    expr: Expr::set(elements.into_iter().map(|e| e.expr))
+                        .with_maybe_source_loc(loc),
+                };
+            }
+            ExprKind::Record(fields) => {
+                let fields: BTreeMap<_, _> = fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), self.fold(v, false)))
+                    .collect();
+                let constant = fields.values().all(|v| v.constant);
+                // PANIC SAFETY: cannot have a duplicate key because the input was already a BTreeMap
+                #[allow(clippy::expect_used)]
+                let expr = Expr::record(fields.into_iter().map(|(k, v)| (k, v.expr)))
+                    .expect("cannot have a duplicate key because the input was already a BTreeMap")
+                    .with_maybe_source_loc(loc);
+                return Folded { expr, constant };
+            }
+        };
+        let expr = folded.expr.with_maybe_source_loc(loc.clone());
+        if folded.constant {
+            if let Some(value) = self.evaluate(&expr) {
+                return Folded {
+                    expr: value.with_maybe_source_loc(loc),
+                    constant: true,
+                };
+            }
+        }
+        Folded {
+            expr,
+            constant: folded.constant,
+        }
+    }
+
+    /// Evaluate a constant expression, returning `None` if it errors
+    fn evaluate(&self, expr: &Expr) -> Option<Expr> {
+        // A constant expression doesn't refer to any variables, so the request
+        // is irrelevant
+        let request = Request::new_unchecked(
+            EntityUIDEntry::Unknown { loc: None },
+            EntityUIDEntry::Unknown { loc: None },
+            EntityUIDEntry::Unknown { loc: None },
+            None,
+        );
+        let evaluator = Evaluator::new(request, &self.entities, self.extensions);
+        evaluator
+            .interpret(expr, &SlotEnv::new())
+            .ok()
+            .map(Expr::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{parse_expr, parse_policy_template};
+
+    fn fold(src: &str, bool_context: bool) -> Expr {
+        let expr = parse_expr(src).expect("should parse");
+        Folder::new(&Extensions::all_available())
+            .fold(&expr, bool_context)
+            .expr
+    }
+
+    #[track_caller]
+    fn assert_folds_to(src: &str, bool_context: bool, expected: &str) {
+        let expected = parse_expr(expected).expect("should parse");
+        let folded = fold(src, bool_context);
+        assert!(
+            folded.eq_shape(&expected),
+            "expected `{src}` to fold to `{expected}`, got `{folded}`"
+        );
+    }
+
+    #[test]
+    fn constant_expressions() {
+        assert_folds_to("1 + 2 * 3", false, "7");
+        assert_folds_to(r#""abc" like "a*""#, false, "true");
+        assert_folds_to("[1, 2, 3].containsAny([3, 4])", false, "true");
+        assert_folds_to("{a: 1 + 1}.a", false, "2");
+        assert_folds_to("{a: 1} has b", false, "false");
+        assert_folds_to("if 1 < 2 then \"yes\" else \"no\"", false, r#""yes""#);
+        assert_folds_to(r#"decimal("1.25").round(1)"#, false, r#"decimal("1.3000")"#);
+        assert_folds_to(
+            r#"ip("10.0.0.1").isInRange(ip("10.0.0.0/8"))"#,
+            false,
+            "true",
+        );
+        // sets and records are kept as written
+        assert_folds_to("[3, 1, 1 + 2]", false, "[3, 1, 3]");
+        assert_folds_to("{b: 2, a: 1 - 1}", false, "{a: 0, b: 2}");
+    }
+
+    #[test]
+    fn non_constant_expressions() {
+        assert_folds_to("principal.age + (1 + 2)", false, "principal.age + 3");
+        assert_folds_to("context.x like \"a*\"", false, "context.x like \"a*\"");
+        assert_folds_to(
+            "?principal == User::\"a\"",
+            false,
+            "?principal == User::\"a\"",
+        );
+        // operations on entity literals depend on the entity store
+        assert_folds_to(
+            r#"User::"alice" in Group::"admins""#,
+            false,
+            r#"User::"alice" in Group::"admins""#,
+        );
+        assert_folds_to(
+            r#"User::"alice" has age"#,
+            false,
+            r#"User::"alice" has age"#,
+        );
+    }
+
+    #[test]
+    fn errors_are_not_folded() {
+        assert_folds_to("1 + \"a\"", false, "1 + \"a\"");
+        assert_folds_to(
+            "9223372036854775807 + (0 + 1)",
+            false,
+            "9223372036854775807 + 1",
+        );
+        assert_folds_to("{a: 1}.b", false, "{a: 1}.b");
+        // short-circuiting doesn't evaluate the error
+        assert_folds_to("false && (1 + \"a\")", false, "false");
+        assert_folds_to("true || {a: 1}.b", false, "true");
+    }
+
+    #[test]
+    fn dead_branches() {
+        assert_folds_to(
+            "if true then resource.a else resource.b",
+            false,
+            "resource.a",
+        );
+        assert_folds_to(
+            "if 1 > 2 then resource.a else resource.b",
+            false,
+            "resource.b",
+        );
+        assert_folds_to("false && resource.public", false, "false");
+        assert_folds_to("true || resource.public", false, "true");
+        assert_folds_to(
+            "resource.public && (1 > 2)",
+            false,
+            "resource.public && false",
+        );
+    }
+
+    #[test]
+    fn boolean_context() {
+        // `true && e` is an error if `e` isn't a boolean, so it can only be
+        // replaced by `e` where a boolean is required anyway
+        assert_folds_to("true && resource.public", false, "true && resource.public");
+        assert_folds_to("true && resource.public", true, "resource.public");
+        assert_folds_to("false || resource.public", true, "resource.public");
+        assert_folds_to("resource.public && (1 < 2)", true, "resource.public");
+        assert_folds_to("resource.public || (1 > 2)", true, "resource.public");
+        assert_folds_to(
+            "(true && resource.public) == 1",
+            true,
+            "(true && resource.public) == 1",
+        );
+        assert_folds_to(
+            "!(true && resource.public) && (resource.a || false)",
+            false,
+            "!resource.public && resource.a",
+        );
+        assert_folds_to(
+            "if (true && context.a) then resource.b else (false || resource.c)",
+            true,
+            "if context.a then resource.b else resource.c",
+        );
+    }
+
+    #[test]
+    fn templates() {
+        let template = parse_policy_template(
+            None,
+            r#"@id("t") permit(principal == ?principal, action, resource)
+            when { true && resource.public }
+            unless { [1, 2].contains(3) };"#,
+        )
+        .expect("should parse");
+        let folded = fold_template_constants(&template, &Extensions::all_available());
+        assert_eq!(folded.id(), template.id());
+        assert_eq!(folded.annotations_arc(), template.annotations_arc());
+        assert_eq!(
+            folded.principal_constraint(),
+            template.principal_constraint()
+        );
+        assert_eq!(folded.slots().count(), 1);
+        assert!(folded
+            .non_scope_constraints()
+            .eq_shape(&parse_expr("resource.public").expect("should parse")));
+    }
+}
@@ -0,0 +1,554 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains a tracing mode of the evaluator, which records the
+//! attribute accesses and hierarchy membership checks performed while
+//! evaluating an expression, and optionally every subexpression evaluated. It
+//! is kept separate from `Evaluator::interpret()` so that ordinary evaluation
+//! pays nothing for it.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+use super::{Evaluator, Result};
+use crate::ast::{BinaryOp, EntityUID, Expr, ExprKind, Literal, SlotEnv, Value, ValueKind};
+use smol_str::SmolStr;
+
+/// Whether an [`AttributeAccess`] read the attribute or tested for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeAccessKind {
+    /// `e.attr`
+    Get,
+    /// `e has attr`
+    Has,
+}
+
+/// An attribute access performed while evaluating an expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeAccess {
+    /// Whether the attribute was read or tested for
+    pub kind: AttributeAccessKind,
+    /// The entity or record whose attribute was accessed. This is the value
+    /// the target expression evaluated to, so for an entity it is the entity's
+    /// UID.
+    pub target: Value,
+    /// The attribute accessed
+    pub attr: SmolStr,
+    /// The result of the access: the attribute's value for
+    /// [`AttributeAccessKind::Get`], or whether the attribute exists for
+    /// [`AttributeAccessKind::Has`]. This is `None` if the access failed,
+    /// e.g., because the attribute does not exist.
+    pub result: Option<Value>,
+}
+
+/// A step of the evaluation of an expression, as recorded in an [`EvalTrace`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalStep {
+    /// The subexpression `expr` was evaluated
+    Evaluated {
+        /// The subexpression
+        expr: Expr,
+        /// What it evaluated to, or `None` if its evaluation failed
+        result: Option<Value>,
+    },
+    /// The subexpression `expr` was skipped by short-circuiting: it is the
+    /// right operand of `&&` or `||`, or the branch of `if` not taken
+    Skipped {
+        /// The subexpression
+        expr: Expr,
+    },
+}
+
+/// The steps of the evaluation of an expression, as returned by
+/// [`Evaluator::evaluate_traced()`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvalTrace {
+    /// The steps, in evaluation order. A subexpression is recorded after the
+    /// subexpressions it contains, so the whole expression is the last step
+    /// unless evaluation failed.
+    pub steps: Vec<EvalStep>,
+}
+
+/// What tracing records while evaluating an expression
+#[derive(Debug, Default)]
+struct Trace {
+    /// The attribute accesses, in evaluation order
+    accesses: Vec<AttributeAccess>,
+    /// The left-hand sides of `in` which are entities, in evaluation order
+    memberships: Vec<EntityUID>,
+    /// Whether to record `steps`, which copies every subexpression evaluated
+    record_steps: bool,
+    /// The steps of the evaluation, if `record_steps` is set
+    steps: Vec<EvalStep>,
+}
+
+impl Trace {
+    /// Record that `expr` was skipped by short-circuiting
+    fn skip(&mut self, expr: &Expr) {
+        if self.record_steps {
+            self.steps.push(EvalStep::Skipped { expr: expr.clone() });
+        }
+    }
+}
+
+impl<'e> Evaluator<'e> {
+    /// Interpret `e` like [`Evaluator::interpret()`], also returning the
+    /// attribute accesses performed, in evaluation order.
+    ///
+    /// Short-circuiting is respected: the accesses in a branch of `&&`, `||`,
+    /// or `if` that evaluation skips are not reported. Evaluation stops at the
+    /// first error, so accesses after that point are not reported either.
+    ///
+    /// This copies the value of every subexpression, so it is meant for
+    /// debugging rather than for authorization.
+    pub fn interpret_traced(
+        &self,
+        e: &Expr,
+        slots: &SlotEnv,
+    ) -> (Result<Value>, Vec<AttributeAccess>) {
+        let mut trace = Trace::default();
+        let result = self.trace(e, slots, &mut trace);
+        (result, trace.accesses)
+    }
+
+    /// Interpret `e` like [`Evaluator::interpret()`], also returning the
+    /// entities whose data the evaluation used: the entities whose attributes
+    /// were accessed, and the entities on the left-hand side of `in`, whose
+    /// ancestors were used.
+    ///
+    /// As for [`Evaluator::interpret_traced()`], entities used in skipped
+    /// branches or after an error are not reported. Evaluating `e` against
+    /// just these entities (with the same attributes and ancestors) gives the
+    /// same result.
+    pub fn interpret_traced_entities(
+        &self,
+        e: &Expr,
+        slots: &SlotEnv,
+    ) -> (Result<Value>, HashSet<EntityUID>) {
+        let mut trace = Trace::default();
+        let result = self.trace(e, slots, &mut trace);
+        let entities = trace
+            .accesses
+            .into_iter()
+            .filter_map(|access| access.target.get_as_entity().ok().cloned())
+            .chain(trace.memberships)
+            .collect();
+        (result, entities)
+    }
+
+    /// Interpret `e` like [`Evaluator::interpret()`], also returning each
+    /// subexpression evaluated and its value, in evaluation order. The
+    /// subexpressions skipped by short-circuiting are recorded as
+    /// [`EvalStep::Skipped`], without evaluating them.
+    ///
+    /// As for [`Evaluator::interpret_traced()`], evaluation stops at the first
+    /// error. The subexpression which failed and each subexpression containing
+    /// it are recorded with no value.
+    pub fn evaluate_traced(&self, e: &Expr, slots: &SlotEnv) -> (Result<Value>, EvalTrace) {
+        let mut trace = Trace {
+            record_steps: true,
+            ..Trace::default()
+        };
+        let result = self.trace(e, slots, &mut trace);
+        (result, EvalTrace { steps: trace.steps })
+    }
+
+    fn trace(&self, e: &Expr, slots: &SlotEnv, trace: &mut Trace) -> Result<Value> {
+        let result = self.trace_expr(e, slots, trace);
+        if trace.record_steps {
+            trace.steps.push(EvalStep::Evaluated {
+                expr: e.clone(),
+                result: result.as_ref().ok().cloned(),
+            });
+        }
+        result
+    }
+
+    /// Access `attr` of `target`, the value of the target expression of `e`,
+    /// and record the access
+    fn trace_access(
+        &self,
+        kind: AttributeAccessKind,
+        target: Value,
+        attr: &SmolStr,
+        e: &Expr,
+        slots: &SlotEnv,
+        trace: &mut Trace,
+    ) -> Result<Value> {
+        let shallow = match kind {
+            AttributeAccessKind::Has => Expr::has_attr(Expr::from(target.clone()), attr.clone()),
+            AttributeAccessKind::Get => Expr::get_attr(Expr::from(target.clone()), attr.clone()),
+        };
+        let result = self.interpret(
+            &shallow.with_maybe_source_loc(e.source_loc().cloned()),
+            slots,
+        );
+        trace.accesses.push(AttributeAccess {
+            kind,
+            target,
+            attr: attr.clone(),
+            result: result.as_ref().ok().cloned(),
+        });
+        result
+    }
+
+    /// Trace the subexpressions of `e`, then compute the value of `e` from
+    /// their values, so that each subexpression is evaluated only once
+    fn trace_expr(&self, e: &Expr, slots: &SlotEnv, trace: &mut Trace) -> Result<Value> {
+        let shallow = match e.expr_kind() {
+            ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => {
+                return self.interpret(e, slots);
+            }
+            ExprKind::If {
+                test_expr,
+                then_expr,
+                else_expr,
+            } => {
+                let value = if self.trace(test_expr, slots, trace)?.get_as_bool()? {
+                    let value = self.trace(then_expr, slots, trace)?;
+                    trace.skip(else_expr);
+                    value
+                } else {
+                    trace.skip(then_expr);
+                    self.trace(else_expr, slots, trace)?
+                };
+                return Ok(value.with_maybe_source_loc(e.source_loc().cloned()));
+            }
+            ExprKind::And { left, right } => {
+                let value = if self.trace(left, slots, trace)?.get_as_bool()? {
+                    self.trace(right, slots, trace)?.get_as_bool()?
+                } else {
+                    trace.skip(right);
+                    false
+                };
+                return Ok(Value::from(value).with_maybe_source_loc(e.source_loc().cloned()));
+            }
+            ExprKind::Or { left, right } => {
+                let value = if self.trace(left, slots, trace)?.get_as_bool()? {
+                    trace.skip(right);
+                    true
+                } else {
+                    self.trace(right, slots, trace)?.get_as_bool()?
+                };
+                return Ok(Value::from(value).with_maybe_source_loc(e.source_loc().cloned()));
+            }
+            ExprKind::GetAttr { expr, attr } => {
+                let target = self.trace(expr, slots, trace)?;
+                return self.trace_access(AttributeAccessKind::Get, target, attr, e, slots, trace);
+            }
+            ExprKind::HasAttr { expr, attr, path } => {
+                // `target has a.b` tests for `a`, and only if `target.a` is an
+                // entity or record, reads it and tests for `b`
+                let mut target = self.trace(expr, slots, trace)?;
+                let (mut attr, mut path) = (attr, path.iter());
+                loop {
+                    let has = self.trace_access(
+                        AttributeAccessKind::Has,
+                        target.clone(),
+                        attr,
+                        e,
+                        slots,
+                        trace,
+                    )?;
+                    let Some(next) = path.next() else {
+                        return Ok(has);
+                    };
+                    if !has.get_as_bool()? {
+                        return Ok(has);
+                    }
+                    target =
+                        self.trace_access(AttributeAccessKind::Get, target, attr, e, slots, trace)?;
+                    if !matches!(
+                        target.value,
+                        ValueKind::Record(_) | ValueKind::Lit(Literal::EntityUID(_))
+                    ) {
+                        return Ok(
+                            Value::from(false).with_maybe_source_loc(e.source_loc().cloned())
+                        );
+                    }
+                    attr = next;
+                }
+            }
+            ExprKind::UnaryApp { op, arg } => {
+                Expr::unary_app(*op, Expr::from(self.trace(arg, slots, trace)?))
+            }
+            ExprKind::BinaryApp { op, arg1, arg2 } => {
+                let arg1 = self.trace(arg1, slots, trace)?;
+                let arg2 = self.trace(arg2, slots, trace)?;
+                if *op == BinaryOp::In {
+                    if let Ok(uid) = arg1.get_as_entity() {
+                        trace.memberships.push(uid.clone());
+                    }
+                }
+                Expr::binary_app(*op, Expr::from(arg1), Expr::from(arg2))
+            }
+            ExprKind::Like { expr, pattern } => Expr::like(
+                Expr::from(self.trace(expr, slots, trace)?),
+                pattern.iter().cloned(),
+            ),
+            ExprKind::Is { expr, entity_type } => Expr::is_entity_type(
+                Expr::from(self.trace(expr, slots, trace)?),
+                entity_type.clone(),
+            ),
+            ExprKind::ExtensionFunctionApp { fn_name, args } => {
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args.iter() {
+                    values.push(Expr::from(self.trace(arg, slots, trace)?));
+                }
+                Expr::call_extension_fn(fn_name.clone(), values)
+            }
+            ExprKind::Set(exprs) => {
+                let mut values = Vec::with_capacity(exprs.len());
+                for expr in exprs.iter() {
+                    values.push(Expr::from(self.trace(expr, slots, trace)?));
+                }
+                Expr::set(values)
+            }
+            ExprKind::Record(map) => {
+                let mut values = BTreeMap::new();
+                for (k, expr) in map.iter() {
+                    values.insert(k.clone(), Expr::from(self.trace(expr, slots, trace)?));
+                }
+                Expr::record_arc(Arc::new(values))
+            }
+        };
+        // evaluate `e` with its subexpressions replaced by their values
+        self.interpret(
+            &shallow.with_maybe_source_loc(e.source_loc().cloned()),
+            slots,
+        )
+    }
+}
+
+// PANIC SAFETY: Unit Test Code
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::evaluator::test::{basic_request, rich_entities};
+    use crate::evaluator::EvalLimits;
+    use crate::extensions::Extensions;
+    use crate::parser::parse_expr;
+
+    /// An attribute access, as the kind of access, the attribute, and the result
+    type Access = (AttributeAccessKind, String, Option<Value>);
+
+    /// Trace `src`, in which `E` stands for an entity with attributes `spoon`
+    /// and `address`
+    fn trace(src: &str) -> (Result<Value>, Vec<Access>) {
+        let entities = rich_entities();
+        let exts = Extensions::none();
+        let eval = Evaluator::new(basic_request(), &entities, &exts);
+        let src = src.replace('E', r#"test_entity_type::"entity_with_attrs""#);
+        let (result, accesses) = eval.interpret_traced(&parse_expr(&src).unwrap(), &SlotEnv::new());
+        let accesses = accesses
+            .into_iter()
+            .map(|access| (access.kind, access.attr.to_string(), access.result))
+            .collect();
+        (result, accesses)
+    }
+
+    #[test]
+    fn records_accesses_in_order() {
+        let (result, accesses) = trace(r#"E has address && E.address.town == "barmstadt""#);
+        assert_eq!(result.unwrap(), Value::from(true));
+        let address = Value::record(
+            [
+                ("street", "234 magnolia"),
+                ("town", "barmstadt"),
+                ("country", "amazonia"),
+            ],
+            None,
+        );
+        assert_eq!(
+            accesses,
+            vec![
+                (
+                    AttributeAccessKind::Has,
+                    "address".to_string(),
+                    Some(Value::from(true))
+                ),
+                (
+                    AttributeAccessKind::Get,
+                    "address".to_string(),
+                    Some(address)
+                ),
+                (
+                    AttributeAccessKind::Get,
+                    "town".to_string(),
+                    Some(Value::from("barmstadt"))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn respects_short_circuiting() {
+        let (result, accesses) = trace(r#"E.spoon < 18 && E.address.town == "barmstadt""#);
+        assert_eq!(result.unwrap(), Value::from(false));
+        assert_eq!(
+            accesses,
+            vec![(
+                AttributeAccessKind::Get,
+                "spoon".to_string(),
+                Some(Value::from(787))
+            )]
+        );
+
+        let (_, accesses) = trace("if E.spoon > 18 then E.address else E.tags");
+        assert_eq!(
+            accesses
+                .iter()
+                .map(|(_, attr, _)| attr.as_str())
+                .collect::<Vec<_>>(),
+            vec!["spoon", "address"]
+        );
+    }
+
+    #[test]
+    fn records_entities_used() {
+        let entities = rich_entities();
+        let exts = Extensions::none();
+        let eval = Evaluator::new(basic_request(), &entities, &exts);
+        let (result, used) = eval.interpret_traced_entities(
+            &parse_expr(
+                r#"(test_entity_type::"child" in test_entity_type::"grandparent"
+                    && test_entity_type::"entity_with_attrs".spoon > 0)
+                || test_entity_type::"sibling".foo
+                || test_entity_type::"unrelated" == test_entity_type::"parent""#,
+            )
+            .unwrap(),
+            &SlotEnv::new(),
+        );
+        assert_eq!(result.unwrap(), Value::from(true));
+        // the right-hand sides of `in` and `==` are not used, and neither is
+        // the skipped branch of `||`
+        assert_eq!(
+            used,
+            HashSet::from([
+                EntityUID::with_eid("child"),
+                EntityUID::with_eid("entity_with_attrs")
+            ])
+        );
+    }
+
+    #[test]
+    fn evaluates_each_subexpression_once() {
+        let entities = rich_entities();
+        let exts = Extensions::none();
+        // tracing this chain of 100 `&&`s and `+`s takes a few steps per
+        // operator, rather than re-evaluating each operand at every level
+        let limits = EvalLimits::new().with_max_steps(1000);
+        let eval = Evaluator::new(basic_request(), &entities, &exts).with_limits(limits);
+        let src = format!("{}1{} == 51", "1 == 1 && ".repeat(50), " + 1".repeat(50));
+        let (result, trace) = eval.evaluate_traced(&parse_expr(&src).unwrap(), &SlotEnv::new());
+        assert_eq!(result.unwrap(), Value::from(true));
+        assert_eq!(trace.steps.len(), 303);
+    }
+
+    #[test]
+    fn stops_at_first_error() {
+        let (result, accesses) = trace("E.fork || E.spoon > 0");
+        assert!(result.is_err());
+        assert_eq!(
+            accesses,
+            vec![(AttributeAccessKind::Get, "fork".to_string(), None)]
+        );
+    }
+
+    /// Render the steps of tracing `src` with `evaluate_traced()`, in which
+    /// `E` stands for an entity with attributes `spoon` and `address`
+    fn trace_steps(src: &str) -> (Result<Value>, Vec<String>) {
+        let entities = rich_entities();
+        let exts = Extensions::none();
+        let eval = Evaluator::new(basic_request(), &entities, &exts);
+        let src = src.replace('E', r#"test_entity_type::"entity_with_attrs""#);
+        let (result, trace) = eval.evaluate_traced(&parse_expr(&src).unwrap(), &SlotEnv::new());
+        let steps = trace
+            .steps
+            .into_iter()
+            .map(|step| match step {
+                EvalStep::Evaluated {
+                    expr,
+                    result: Some(value),
+                } => format!("{expr} => {value}"),
+                EvalStep::Evaluated { expr, result: None } => format!("{expr} => error"),
+                EvalStep::Skipped { expr } => format!("{expr} skipped"),
+            })
+            .collect();
+        (result, steps)
+    }
+
+    #[test]
+    fn records_steps() {
+        let (result, steps) = trace_steps("E.spoon + 1 == 788");
+        assert_eq!(result.unwrap(), Value::from(true));
+        let e = r#"test_entity_type::"entity_with_attrs""#;
+        assert_eq!(
+            steps,
+            vec![
+                format!("{e} => {e}"),
+                format!(r#"{e}["spoon"] => 787"#),
+                "1 => 1".to_string(),
+                format!(r#"({e}["spoon"]) + 1 => 788"#),
+                "788 => 788".to_string(),
+                format!(r#"(({e}["spoon"]) + 1) == 788 => true"#),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_skipped_operands() {
+        let (result, steps) = trace_steps("false && E.spoon < 0");
+        assert_eq!(result.unwrap(), Value::from(false));
+        let e = r#"test_entity_type::"entity_with_attrs""#;
+        assert_eq!(
+            steps,
+            vec![
+                "false => false".to_string(),
+                format!(r#"({e}["spoon"]) < 0 skipped"#),
+                format!(r#"false && (({e}["spoon"]) < 0) => false"#),
+            ]
+        );
+
+        let (_, steps) = trace_steps("if true then 1 else 2");
+        assert_eq!(
+            steps,
+            vec![
+                "true => true",
+                "1 => 1",
+                "2 skipped",
+                "if true then 1 else 2 => 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn records_failed_steps() {
+        let (result, steps) = trace_steps("1 + E.fork");
+        assert!(result.is_err());
+        let e = r#"test_entity_type::"entity_with_attrs""#;
+        assert_eq!(
+            steps,
+            vec![
+                "1 => 1".to_string(),
+                format!("{e} => {e}"),
+                format!(r#"{e}["fork"] => error"#),
+                format!(r#"1 + ({e}["fork"]) => error"#),
+            ]
+        );
+    }
+}
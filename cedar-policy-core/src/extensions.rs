@@ -23,8 +23,9 @@ pub mod ipaddr;
 pub mod decimal;
 pub mod partial_evaluation;
 
-use crate::ast::{Extension, ExtensionFunction, Name};
+use crate::ast::{CallStyle, Extension, ExtensionFunction, Name};
 use crate::entities::SchemaType;
+use itertools::Itertools;
 use miette::Diagnostic;
 use thiserror::Error;
 
@@ -116,6 +117,7 @@ impl<'a> Extensions<'a> {
             .all_funcs()
             .filter(|f| {
                 f.is_constructor()
+                    && f.arg_types().len() == 1
                     && f.return_type() == Some(return_type)
                     && f.arg_types().first().map(Option::as_ref) == Some(Some(arg_type))
             })
@@ -133,6 +135,97 @@ impl<'a> Extensions<'a> {
     }
 }
 
+/// Get the signatures of all the extension functions available to the
+/// evaluator, i.e., those of [`Extensions::all_available()`].
+///
+/// This reflects which extensions are enabled via features. Signatures are
+/// ordered by function name.
+pub fn available_functions() -> impl Iterator<Item = ExtensionFunctionSignature> {
+    Extensions::all_available()
+        .all_funcs()
+        .map(ExtensionFunctionSignature::from)
+        .sorted_by(|a, b| a.name.cmp(&b.name))
+}
+
+/// The signature of an extension function: its name, call style, argument
+/// types, and return type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionFunctionSignature {
+    /// Name of the function
+    name: Name,
+    /// Which `CallStyle` is used when calling the function
+    style: CallStyle,
+    /// Argument types, where `None` means the argument may have any type
+    arg_types: Vec<Option<SchemaType>>,
+    /// Return type, where `None` means the function never returns a value
+    return_type: Option<SchemaType>,
+}
+
+impl ExtensionFunctionSignature {
+    /// Get the name of the function
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    /// Get the `CallStyle` of the function
+    pub fn style(&self) -> CallStyle {
+        self.style
+    }
+
+    /// Get the argument types of the function. For method-style functions, the
+    /// first argument is the receiver.
+    ///
+    /// `None` means the argument may have any type.
+    pub fn arg_types(&self) -> &[Option<SchemaType>] {
+        &self.arg_types
+    }
+
+    /// Get the return type of the function.
+    /// `None` represents the `Never` type.
+    pub fn return_type(&self) -> Option<&SchemaType> {
+        self.return_type.as_ref()
+    }
+}
+
+impl From<&ExtensionFunction> for ExtensionFunctionSignature {
+    fn from(func: &ExtensionFunction) -> Self {
+        Self {
+            name: func.name().clone(),
+            style: func.style(),
+            arg_types: func.arg_types().to_vec(),
+            return_type: func.return_type().cloned(),
+        }
+    }
+}
+
+impl std::fmt::Display for ExtensionFunctionSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let arg_type = |ty: &Option<SchemaType>| match ty {
+            Some(ty) => ty.to_string(),
+            None => "any".to_string(),
+        };
+        match (self.style, self.arg_types.split_first()) {
+            (CallStyle::MethodStyle, Some((receiver, args))) => write!(
+                f,
+                "{}.{}({})",
+                arg_type(receiver),
+                self.name,
+                args.iter().map(arg_type).join(", ")
+            )?,
+            _ => write!(
+                f,
+                "{}({})",
+                self.name,
+                self.arg_types.iter().map(arg_type).join(", ")
+            )?,
+        }
+        match &self.return_type {
+            Some(ty) => write!(f, " -> {ty}"),
+            None => write!(f, " -> never"),
+        }
+    }
+}
+
 /// Errors thrown when looking up an extension function in [`Extensions`].
 #[derive(Debug, PartialEq, Eq, Clone, Diagnostic, Error)]
 pub enum ExtensionFunctionLookupError {
@@ -197,4 +290,21 @@ pub mod test {
         let dedup_names: HashSet<_> = all_names.iter().collect();
         assert_eq!(all_names.len(), dedup_names.len());
     }
+
+    #[test]
+    fn available_functions_sorted() {
+        let names: Vec<_> = available_functions()
+            .map(|sig| sig.name().clone())
+            .collect();
+        assert!(names.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(names.len(), Extensions::all_available().all_funcs().count());
+    }
+
+    #[test]
+    #[cfg(feature = "ipaddr")]
+    fn available_functions_display() {
+        let sigs: Vec<_> = available_functions().map(|sig| sig.to_string()).collect();
+        assert!(sigs.contains(&"ip(string) -> ipaddr".to_string()));
+        assert!(sigs.contains(&"ipaddr.isInRange(ipaddr) -> bool".to_string()));
+    }
 }
@@ -15,26 +15,76 @@
  */
 
 //! This module contains the Cedar 'decimal' extension.
+//!
+//! Besides comparisons, the extension provides the rounding functions `round`,
+//! `floor`, and `ceil`. It intentionally has no arithmetic, and in particular
+//! no division, which can't be exact for fixed-point values.
+//!
+//! Values constructed by `decimal` have 4 digits after the decimal. The
+//! `decimalWithScale` constructor takes the number of digits as a second
+//! argument, trading range for precision. Decimals with different scales are
+//! compared by their exact values.
+//! Since `decimalWithScale` takes two arguments, values it constructs with a
+//! scale other than 4 can't be written with the JSON `__extn` escape.
 
 use crate::ast::{
     CallStyle, Extension, ExtensionFunction, ExtensionOutputValue, ExtensionValue,
-    ExtensionValueWithArgs, Literal, Name, Type, Value, ValueKind,
+    ExtensionValueWithArgs, Literal, Name, RestrictedExpr, Type, Value, ValueKind,
 };
 use crate::entities::SchemaType;
 use crate::evaluator;
+use crate::parser::Loc;
 use miette::Diagnostic;
 use std::str::FromStr;
 use std::sync::Arc;
 use thiserror::Error;
 
-/// Number of digits supported after the decimal
+/// Number of digits supported after the decimal, for values constructed by
+/// `decimal`
 const NUM_DIGITS: u32 = 4;
 
+/// Maximum number of digits supported after the decimal by
+/// `decimalWithScale`. `10^MAX_SCALE` must fit in an `i64`.
+const MAX_SCALE: u32 = 18;
+
 /// Decimal value, represented internally as an integer.
-/// `Decimal{value}` represents `value / 10^NUM_DIGITS`.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+/// `Decimal{value, scale}` represents `value / 10^scale`.
+///
+/// Equality and ordering are by the represented value, so decimals with
+/// different scales can be compared.
+#[derive(Debug, Clone)]
 struct Decimal {
     value: i64,
+    scale: u32,
+}
+
+impl Decimal {
+    /// The value scaled to `scale` digits after the decimal, which can't
+    /// overflow because `10^MAX_SCALE * i64::MAX < i128::MAX`
+    fn scaled_to(&self, scale: u32) -> i128 {
+        i128::from(self.value) * i128::pow(10, scale - self.scale.min(scale))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let scale = self.scale.max(other.scale);
+        self.scaled_to(scale).cmp(&other.scaled_to(scale))
+    }
 }
 
 // PANIC SAFETY The `Name`s and `Regex` here are valid
@@ -46,10 +96,14 @@ mod constants {
     // PANIC SAFETY all of the names here are valid names
     lazy_static::lazy_static! {
         pub static ref DECIMAL_FROM_STR_NAME : Name = Name::parse_unqualified_name(EXTENSION_NAME).expect("should be a valid identifier");
+        pub static ref DECIMAL_WITH_SCALE : Name = Name::parse_unqualified_name("decimalWithScale").expect("should be a valid identifier");
         pub static ref LESS_THAN : Name = Name::parse_unqualified_name("lessThan").expect("should be a valid identifier");
         pub static ref LESS_THAN_OR_EQUAL : Name = Name::parse_unqualified_name("lessThanOrEqual").expect("should be a valid identifier");
         pub static ref GREATER_THAN : Name = Name::parse_unqualified_name("greaterThan").expect("should be a valid identifier");
         pub static ref GREATER_THAN_OR_EQUAL : Name = Name::parse_unqualified_name("greaterThanOrEqual").expect("should be a valid identifier");
+        pub static ref ROUND : Name = Name::parse_unqualified_name("round").expect("should be a valid identifier");
+        pub static ref FLOOR : Name = Name::parse_unqualified_name("floor").expect("should be a valid identifier");
+        pub static ref CEIL : Name = Name::parse_unqualified_name("ceil").expect("should be a valid identifier");
     }
 
     // Global regex, initialized at first use
@@ -74,12 +128,26 @@ enum Error {
 
     /// Too many digits after the decimal point
     #[error("too many digits after the decimal in `{0}`")]
-    #[diagnostic(help("at most {NUM_DIGITS} digits are supported"))]
-    TooManyDigits(String),
+    #[diagnostic(help("at most {1} digits are supported"))]
+    TooManyDigits(String, u32),
+
+    /// Invalid scale given to `decimalWithScale`
+    #[error("invalid scale {0}")]
+    #[diagnostic(help("the scale must be between 1 and {MAX_SCALE}"))]
+    InvalidScale(i64),
 
     /// Overflow occurred when converting to a decimal value
     #[error("overflow when converting to decimal")]
     Overflow,
+
+    /// Overflow occurred when rounding a decimal value
+    #[error("overflow when rounding `{0}`")]
+    RoundingOverflow(String),
+
+    /// Invalid number of decimal places to round to
+    #[error("cannot round to {0} decimal places")]
+    #[diagnostic(help("the number of decimal places must be between 0 and the scale, {1}"))]
+    InvalidPlaces(i64, u32),
 }
 
 /// Computes x * 10 ^ y while checking for overflows
@@ -98,15 +166,22 @@ impl Decimal {
         constants::DECIMAL_FROM_STR_NAME.clone()
     }
 
-    /// Convert a string into a `Decimal` value.
+    /// Convert a string into a `Decimal` value with `NUM_DIGITS` digits after
+    /// the decimal.
+    fn from_str(str: impl AsRef<str>) -> Result<Self, Error> {
+        Self::from_str_with_scale(str, NUM_DIGITS)
+    }
+
+    /// Convert a string into a `Decimal` value with `scale` digits after the
+    /// decimal.
     ///
     /// Matches against the regular expression `-?[0-9]+.[0-9]+`, which requires
     /// a decimal point and at least one digit before and after the decimal.
-    /// We also enforce at most NUM_DIGITS digits after the decimal.
+    /// We also enforce at most `scale` digits after the decimal.
     ///
     /// Our representation stores the decimal number `d` as the 64-bit integer
-    /// `d * 10 ^ NUM_DIGITS`; this function will error on overflow.
-    fn from_str(str: impl AsRef<str>) -> Result<Self, Error> {
+    /// `d * 10 ^ scale`; this function will error on overflow.
+    fn from_str_with_scale(str: impl AsRef<str>, scale: u32) -> Result<Self, Error> {
         // check that the string matches the regex
         if !constants::DECIMAL_REGEX.is_match(str.as_ref()) {
             return Err(Error::FailedParse(str.as_ref().to_owned()));
@@ -127,27 +202,106 @@ impl Decimal {
             .ok_or_else(|| Error::FailedParse(str.as_ref().to_owned()))?
             .as_str();
 
-        // convert the left component to i64 and multiply by `10 ^ NUM_DIGITS`
+        // convert the left component to i64 and multiply by `10 ^ scale`
         let l = i64::from_str(l).map_err(|_| Error::Overflow)?;
-        let l = checked_mul_pow(l, NUM_DIGITS)?;
+        let l = checked_mul_pow(l, scale)?;
 
-        // convert the right component to i64 and multiply by `10 ^ (NUM_DIGITS - len)`
+        // convert the right component to i64 and multiply by `10 ^ (scale - len)`
         let len: u32 = r.len().try_into().map_err(|_| Error::Overflow)?;
-        if NUM_DIGITS < len {
-            return Err(Error::TooManyDigits(str.as_ref().to_string()));
+        if scale < len {
+            return Err(Error::TooManyDigits(str.as_ref().to_string(), scale));
         }
         let r = i64::from_str(r).map_err(|_| Error::Overflow)?;
-        let r = checked_mul_pow(r, NUM_DIGITS - len)?;
+        let r = checked_mul_pow(r, scale - len)?;
 
-        // compute the value
-        if l >= 0 {
+        // compute the value. We check the sign of the string rather than of
+        // `l`, which is 0 for values between -1 and 0.
+        if !str.as_ref().starts_with('-') {
             l.checked_add(r)
         } else {
             l.checked_sub(r)
         }
-        .map(|value| Self { value })
+        .map(|value| Self { value, scale })
         .ok_or(Error::Overflow)
     }
+
+    /// Round to `places` digits after the decimal, rounding halfway values
+    /// away from zero
+    fn round(&self, places: i64) -> Result<Self, Error> {
+        let places = u32::try_from(places)
+            .ok()
+            .filter(|places| *places <= self.scale)
+            .ok_or(Error::InvalidPlaces(places, self.scale))?;
+        let unit = i64::pow(10, self.scale - places);
+        let (quotient, remainder) = (self.value / unit, self.value % unit);
+        // `remainder.abs() < unit`, so this can't overflow
+        let quotient = if remainder.abs() * 2 >= unit {
+            quotient + self.value.signum()
+        } else {
+            quotient
+        };
+        self.with_units(quotient, unit)
+    }
+
+    /// Round to the greatest integer less than or equal to this value
+    fn floor(&self) -> Result<Self, Error> {
+        let unit = i64::pow(10, self.scale);
+        let quotient = self.value.div_euclid(unit);
+        self.with_units(quotient, unit)
+    }
+
+    /// Round to the least integer greater than or equal to this value
+    fn ceil(&self) -> Result<Self, Error> {
+        let unit = i64::pow(10, self.scale);
+        let quotient = self.value.div_euclid(unit);
+        let quotient = if self.value.rem_euclid(unit) == 0 {
+            quotient
+        } else {
+            quotient + 1
+        };
+        self.with_units(quotient, unit)
+    }
+
+    /// The rounded result `quotient * unit` of rounding `self`, where `unit`
+    /// is the internal representation of the place rounded to
+    fn with_units(&self, quotient: i64, unit: i64) -> Result<Self, Error> {
+        quotient
+            .checked_mul(unit)
+            .map(|value| Self {
+                value,
+                scale: self.scale,
+            })
+            .ok_or_else(|| Error::RoundingOverflow(self.to_constructor_arg()))
+    }
+
+    /// The string which the `decimal` or `decimalWithScale` constructor parses
+    /// into this value, with all `scale` digits after the decimal
+    fn to_constructor_arg(&self) -> String {
+        let value = self.value.unsigned_abs();
+        let unit = u64::pow(10, self.scale);
+        format!(
+            "{}{}.{:0width$}",
+            if self.value < 0 { "-" } else { "" },
+            value / unit,
+            value % unit,
+            width = self.scale as usize
+        )
+    }
+
+    /// The constructor and arguments which produce this value: `decimal` if
+    /// it has `NUM_DIGITS` digits after the decimal, and `decimalWithScale`
+    /// otherwise
+    fn constructor_and_args(&self) -> (Name, Vec<RestrictedExpr>) {
+        let arg = RestrictedExpr::val(self.to_constructor_arg());
+        if self.scale == NUM_DIGITS {
+            (constants::DECIMAL_FROM_STR_NAME.clone(), vec![arg])
+        } else {
+            (
+                constants::DECIMAL_WITH_SCALE.clone(),
+                vec![arg, RestrictedExpr::val(i64::from(self.scale))],
+            )
+        }
+    }
 }
 
 impl std::fmt::Display for Decimal {
@@ -155,8 +309,8 @@ impl std::fmt::Display for Decimal {
         write!(
             f,
             "{}.{}",
-            self.value / i64::pow(10, NUM_DIGITS),
-            (self.value % i64::pow(10, NUM_DIGITS)).abs()
+            self.value / i64::pow(10, self.scale),
+            (self.value % i64::pow(10, self.scale)).abs()
         )
     }
 }
@@ -192,6 +346,39 @@ fn decimal_from_str(arg: Value) -> evaluator::Result<ExtensionOutputValue> {
     .into())
 }
 
+/// Construct the Cedar value for a `decimal` computed by an extension function,
+/// which is represented as a call to a constructor. `loc` is the
+/// source location of the argument it was computed from.
+fn decimal_value(decimal: Decimal, loc: Option<&Loc>) -> Value {
+    let (constructor, args) = decimal.constructor_and_args();
+    let e = ExtensionValueWithArgs::new(Arc::new(decimal), constructor, args);
+    Value::new(ValueKind::ExtensionValue(Arc::new(e)), loc.cloned())
+}
+
+/// Cedar function that constructs a `decimal` Cedar type with the number of
+/// digits after the decimal given by a Cedar long, from a Cedar string
+fn decimal_with_scale(arg: Value, scale: Value) -> evaluator::Result<ExtensionOutputValue> {
+    let str = arg.get_as_string()?;
+    let scale_arg = scale.get_as_long()?;
+    let num_digits = u32::try_from(scale_arg)
+        .ok()
+        .filter(|num_digits| (1..=MAX_SCALE).contains(num_digits))
+        .ok_or_else(|| extension_err(Error::InvalidScale(scale_arg).to_string()))?;
+    let decimal = Decimal::from_str_with_scale(str.as_str(), num_digits)
+        .map_err(|e| extension_err(e.to_string()))?;
+    let arg_source_loc = arg.source_loc().cloned();
+    let e = ExtensionValueWithArgs::new(
+        Arc::new(decimal),
+        constants::DECIMAL_WITH_SCALE.clone(),
+        vec![arg.into(), scale.into()],
+    );
+    Ok(Value {
+        value: ValueKind::ExtensionValue(Arc::new(e)),
+        loc: arg_source_loc,
+    }
+    .into())
+}
+
 /// Check that `v` is a decimal type and, if it is, return the wrapped value
 fn as_decimal(v: &Value) -> Result<&Decimal, evaluator::EvaluationError> {
     match &v.value {
@@ -255,6 +442,34 @@ fn decimal_ge(left: Value, right: Value) -> evaluator::Result<ExtensionOutputVal
     Ok(Value::from(left >= right).into())
 }
 
+/// Cedar function that rounds a `decimal` Cedar type to the number of digits
+/// after the decimal given by a Cedar long, returning a `decimal`. Halfway
+/// values are rounded away from zero.
+fn decimal_round(arg: Value, places: Value) -> evaluator::Result<ExtensionOutputValue> {
+    let decimal = as_decimal(&arg)?;
+    let places = places.get_as_long()?;
+    let rounded = decimal
+        .round(places)
+        .map_err(|e| extension_err(e.to_string()))?;
+    Ok(decimal_value(rounded, arg.source_loc()).into())
+}
+
+/// Cedar function that rounds a `decimal` Cedar type down to an integer,
+/// returning a `decimal`
+fn decimal_floor(arg: Value) -> evaluator::Result<ExtensionOutputValue> {
+    let decimal = as_decimal(&arg)?;
+    let rounded = decimal.floor().map_err(|e| extension_err(e.to_string()))?;
+    Ok(decimal_value(rounded, arg.source_loc()).into())
+}
+
+/// Cedar function that rounds a `decimal` Cedar type up to an integer,
+/// returning a `decimal`
+fn decimal_ceil(arg: Value) -> evaluator::Result<ExtensionOutputValue> {
+    let decimal = as_decimal(&arg)?;
+    let rounded = decimal.ceil().map_err(|e| extension_err(e.to_string()))?;
+    Ok(decimal_value(rounded, arg.source_loc()).into())
+}
+
 /// Construct the extension
 pub fn extension() -> Extension {
     let decimal_type = SchemaType::Extension {
@@ -270,6 +485,13 @@ pub fn extension() -> Extension {
                 decimal_type.clone(),
                 Some(SchemaType::String),
             ),
+            ExtensionFunction::binary(
+                constants::DECIMAL_WITH_SCALE.clone(),
+                CallStyle::FunctionStyle,
+                Box::new(decimal_with_scale),
+                decimal_type.clone(),
+                (Some(SchemaType::String), Some(SchemaType::Long)),
+            ),
             ExtensionFunction::binary(
                 constants::LESS_THAN.clone(),
                 CallStyle::MethodStyle,
@@ -296,7 +518,28 @@ pub fn extension() -> Extension {
                 CallStyle::MethodStyle,
                 Box::new(decimal_ge),
                 SchemaType::Bool,
-                (Some(decimal_type.clone()), Some(decimal_type)),
+                (Some(decimal_type.clone()), Some(decimal_type.clone())),
+            ),
+            ExtensionFunction::binary(
+                constants::ROUND.clone(),
+                CallStyle::MethodStyle,
+                Box::new(decimal_round),
+                decimal_type.clone(),
+                (Some(decimal_type.clone()), Some(SchemaType::Long)),
+            ),
+            ExtensionFunction::unary(
+                constants::FLOOR.clone(),
+                CallStyle::MethodStyle,
+                Box::new(decimal_floor),
+                decimal_type.clone(),
+                Some(decimal_type.clone()),
+            ),
+            ExtensionFunction::unary(
+                constants::CEIL.clone(),
+                CallStyle::MethodStyle,
+                Box::new(decimal_ceil),
+                decimal_type.clone(),
+                Some(decimal_type),
             ),
         ],
     )
@@ -377,6 +620,17 @@ mod tests {
             )
             .expect("function should exist")
             .is_constructor(),);
+        assert!(ext
+            .get_func(
+                &Name::parse_unqualified_name("decimalWithScale")
+                    .expect("should be a valid identifier")
+            )
+            .expect("function should exist")
+            .is_constructor());
+        assert!(!ext
+            .get_func(&Name::parse_unqualified_name("round").expect("should be a valid identifier"))
+            .expect("function should exist")
+            .is_constructor());
     }
 
     #[test]
@@ -651,6 +905,197 @@ mod tests {
         parse_expr(r#"lessThan(decimal("-1.23"), decimal("1.23"))"#).expect_err("should fail");
     }
 
+    /// Evaluate `expr`, which should evaluate to a decimal, and assert that it
+    /// is equal to `decimal(expected)`
+    #[track_caller] // report the caller's location as the location of the panic, not the location in this function
+    fn assert_decimal_eq(expr: &str, expected: &str) {
+        let ext_array = [extension()];
+        let exts = Extensions::specific_extensions(&ext_array);
+        let request = basic_request();
+        let entities = basic_entities();
+        let eval = Evaluator::new(request, &entities, &exts);
+        let expected = Decimal::from_str(expected).expect("should be a valid decimal");
+        assert_matches!(
+            eval.interpret_inline_policy(&parse_expr(expr).expect("parsing error")),
+            Ok(Value { value: ValueKind::ExtensionValue(ev), .. }) => {
+                let actual = ev.value().as_any().downcast_ref::<Decimal>().expect("should be a decimal");
+                assert_eq!(actual, &expected, "for {expr}");
+                // the value is represented as a call to the constructor
+                // which produces it
+                let (constructor, args) = actual.constructor_and_args();
+                assert_eq!(ev.constructor_and_args(), (&constructor, args.as_slice()));
+            }
+        );
+    }
+
+    #[test]
+    fn decimal_rounding() {
+        assert_decimal_eq(r#"decimal("1.2345").round(2)"#, "1.23");
+        assert_decimal_eq(r#"decimal("1.2350").round(2)"#, "1.24");
+        assert_decimal_eq(r#"decimal("-1.2350").round(2)"#, "-1.24");
+        assert_decimal_eq(r#"decimal("-1.2349").round(2)"#, "-1.23");
+        assert_decimal_eq(r#"decimal("0.5").round(0)"#, "1.0");
+        assert_decimal_eq(r#"decimal("-0.5").round(0)"#, "-1.0");
+        assert_decimal_eq(r#"decimal("-0.4999").round(0)"#, "0.0");
+        assert_decimal_eq(r#"decimal("1.2345").round(4)"#, "1.2345");
+
+        assert_decimal_eq(r#"decimal("1.9999").floor()"#, "1.0");
+        assert_decimal_eq(r#"decimal("-1.0001").floor()"#, "-2.0");
+        assert_decimal_eq(r#"decimal("-0.5").floor()"#, "-1.0");
+        assert_decimal_eq(r#"decimal("2.0").floor()"#, "2.0");
+
+        assert_decimal_eq(r#"decimal("1.0001").ceil()"#, "2.0");
+        assert_decimal_eq(r#"decimal("-1.9999").ceil()"#, "-1.0");
+        assert_decimal_eq(r#"decimal("-0.5").ceil()"#, "0.0");
+        assert_decimal_eq(r#"decimal("2.0").ceil()"#, "2.0");
+    }
+
+    #[test]
+    fn decimal_rounding_bounds() {
+        let ext_array = [extension()];
+        let exts = Extensions::specific_extensions(&ext_array);
+        let request = basic_request();
+        let entities = basic_entities();
+        let eval = Evaluator::new(request, &entities, &exts);
+
+        // the extreme values round toward zero without overflow
+        assert_decimal_eq(
+            r#"decimal("922337203685477.5807").floor()"#,
+            "922337203685477.0",
+        );
+        assert_decimal_eq(
+            r#"decimal("-922337203685477.5808").ceil()"#,
+            "-922337203685477.0",
+        );
+        assert_decimal_eq(
+            r#"decimal("922337203685477.5807").round(4)"#,
+            "922337203685477.5807",
+        );
+        assert_decimal_eq(
+            r#"decimal("-922337203685477.5808").round(4)"#,
+            "-922337203685477.5808",
+        );
+
+        // but overflow away from zero
+        assert_decimal_err(eval.interpret_inline_policy(
+            &parse_expr(r#"decimal("922337203685477.5807").ceil()"#).expect("parsing error"),
+        ));
+        assert_decimal_err(eval.interpret_inline_policy(
+            &parse_expr(r#"decimal("-922337203685477.5808").floor()"#).expect("parsing error"),
+        ));
+        assert_decimal_err(eval.interpret_inline_policy(
+            &parse_expr(r#"decimal("922337203685477.5807").round(3)"#).expect("parsing error"),
+        ));
+        assert_decimal_err(eval.interpret_inline_policy(
+            &parse_expr(r#"decimal("-922337203685477.5808").round(1)"#).expect("parsing error"),
+        ));
+
+        // invalid numbers of places
+        assert_decimal_err(eval.interpret_inline_policy(
+            &parse_expr(r#"decimal("1.23").round(5)"#).expect("parsing error"),
+        ));
+        assert_decimal_err(eval.interpret_inline_policy(
+            &parse_expr(r#"decimal("1.23").round(-1)"#).expect("parsing error"),
+        ));
+        assert_matches!(
+            eval.interpret_inline_policy(
+                &parse_expr(r#"decimal("1.23").round("1")"#).expect("parsing error")
+            ),
+            Err(e) => assert_eq!(e.error_kind(),
+                &EvaluationErrorKind::TypeError {
+                    expected: nonempty![Type::Long],
+                    actual: Type::String,
+                    advice: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn decimal_with_scale() {
+        let ext_array = [extension()];
+        let exts = Extensions::specific_extensions(&ext_array);
+        let request = basic_request();
+        let entities = basic_entities();
+        let eval = Evaluator::new(request, &entities, &exts);
+        let eval_str =
+            |src: &str| eval.interpret_inline_policy(&parse_expr(src).expect("parsing error"));
+
+        assert_decimal_valid(eval_str(r#"decimalWithScale("1.12345678", 8)"#));
+        assert_decimal_valid(eval_str(r#"decimalWithScale("1.5", 1)"#));
+        assert_decimal_valid(eval_str(r#"decimalWithScale("9.223372036854775807", 18)"#));
+        assert_decimal_valid(eval_str(r#"decimalWithScale("-9.223372036854775808", 18)"#));
+        assert_decimal_valid(eval_str(r#"decimalWithScale("92233720368547758.07", 2)"#));
+
+        // too many digits for the scale
+        assert_decimal_err(eval_str(r#"decimalWithScale("1.12345678", 7)"#));
+        // invalid scales
+        assert_decimal_err(eval_str(r#"decimalWithScale("1.0", 0)"#));
+        assert_decimal_err(eval_str(r#"decimalWithScale("1.0", 19)"#));
+        assert_decimal_err(eval_str(r#"decimalWithScale("1.0", -1)"#));
+        // overflows
+        assert_decimal_err(eval_str(r#"decimalWithScale("9.223372036854775808", 18)"#));
+        assert_decimal_err(eval_str(r#"decimalWithScale("10.0", 18)"#));
+        assert_decimal_err(eval_str(r#"decimalWithScale("92233720368547758.08", 2)"#));
+
+        // decimals with different scales are compared by value
+        assert_eq!(
+            eval_str(r#"decimalWithScale("1.50000000", 8) == decimal("1.5")"#),
+            Ok(Value::from(true))
+        );
+        assert_eq!(
+            eval_str(r#"decimalWithScale("1.50000001", 8) == decimal("1.5")"#),
+            Ok(Value::from(false))
+        );
+        assert_eq!(
+            eval_str(r#"decimalWithScale("1.50000001", 8).greaterThan(decimal("1.5"))"#),
+            Ok(Value::from(true))
+        );
+        assert_eq!(
+            eval_str(r#"decimalWithScale("-1.50000001", 8).lessThan(decimalWithScale("-1.5", 1))"#),
+            Ok(Value::from(true))
+        );
+        assert_eq!(
+            eval_str(
+                r#"decimalWithScale("-9.223372036854775808", 18).lessThanOrEqual(decimal("-922337203685477.5808"))"#
+            ),
+            Ok(Value::from(false))
+        );
+
+        // rounding keeps the scale
+        assert_decimal_eq(r#"decimalWithScale("1.23456789", 8).round(4)"#, "1.2346");
+        assert_decimal_eq(r#"decimalWithScale("-1.5", 1).floor()"#, "-2.0");
+        assert_decimal_err(eval_str(r#"decimalWithScale("1.5", 1).round(2)"#));
+        assert_decimal_err(eval_str(
+            r#"decimalWithScale("9.223372036854775807", 18).ceil()"#,
+        ));
+    }
+
+    #[test]
+    fn decimal_constructor_arg_round_trip() {
+        for s in ["1.05", "-0.5", "-0.0123", "0.0", "-922337203685477.5808"] {
+            let d = Decimal::from_str(s).expect("should be a valid decimal");
+            assert_eq!(
+                Decimal::from_str(d.to_constructor_arg()).expect("should be a valid decimal"),
+                d
+            );
+        }
+        let d = Decimal::from_str_with_scale("-1.00000001", 8).expect("should be a valid decimal");
+        assert_eq!(d.to_constructor_arg(), "-1.00000001");
+        assert_eq!(
+            Decimal::from_str_with_scale(d.to_constructor_arg(), 8)
+                .expect("should be a valid decimal"),
+            d
+        );
+        assert_eq!(
+            Decimal::from_str("-0.5").expect("should be a valid decimal"),
+            Decimal {
+                value: -5000,
+                scale: NUM_DIGITS
+            }
+        );
+    }
+
     fn check_round_trip(s: &str) {
         let d = Decimal::from_str(s).expect("should be a valid decimal");
         assert_eq!(s, d.to_string());
@@ -36,6 +36,7 @@ mod names {
         pub static ref IS_LOOPBACK : Name = Name::parse_unqualified_name("isLoopback").expect("should be a valid identifier");
         pub static ref IS_MULTICAST : Name = Name::parse_unqualified_name("isMulticast").expect("should be a valid identifier");
         pub static ref IS_IN_RANGE : Name = Name::parse_unqualified_name("isInRange").expect("should be a valid identifier");
+        pub static ref OVERLAPS : Name = Name::parse_unqualified_name("overlaps").expect("should be a valid identifier");
     }
 }
 
@@ -146,6 +147,14 @@ impl IPAddr {
             (_, _) => false,
         }
     }
+
+    /// Return true if this and the given `IPAddr` share at least one address.
+    /// Two CIDR ranges are either disjoint or one is contained in the other,
+    /// so this amounts to checking containment in both directions.
+    /// An IPv4 range never overlaps an IPv6 range.
+    fn overlaps(&self, other: &Self) -> bool {
+        self.is_in_range(other) || other.is_in_range(self)
+    }
 }
 
 fn parse_prefix(s: &str, max: u8, max_len: u8) -> Result<u8, String> {
@@ -350,6 +359,14 @@ fn is_in_range(child: Value, parent: Value) -> evaluator::Result<ExtensionOutput
     Ok(child_ip.is_in_range(parent_ip).into())
 }
 
+/// Cedar function which tests whether the IP ranges represented by two
+/// `ipaddr` Cedar types have any address in common, returning a Cedar bool
+fn overlaps(lhs: Value, rhs: Value) -> evaluator::Result<ExtensionOutputValue> {
+    let lhs_ip = as_ipaddr(&lhs)?;
+    let rhs_ip = as_ipaddr(&rhs)?;
+    Ok(lhs_ip.overlaps(rhs_ip).into())
+}
+
 /// Construct the extension
 pub fn extension() -> Extension {
     let ipaddr_type = SchemaType::Extension {
@@ -398,6 +415,13 @@ pub fn extension() -> Extension {
                 CallStyle::MethodStyle,
                 Box::new(is_in_range),
                 SchemaType::Bool,
+                (Some(ipaddr_type.clone()), Some(ipaddr_type.clone())),
+            ),
+            ExtensionFunction::binary(
+                names::OVERLAPS.clone(),
+                CallStyle::MethodStyle,
+                Box::new(overlaps),
+                SchemaType::Bool,
                 (Some(ipaddr_type.clone()), Some(ipaddr_type)),
             ),
         ],
@@ -1140,6 +1164,92 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn ip_overlaps() {
+        let ext_array = [extension()];
+        let exts = Extensions::specific_extensions(&ext_array);
+        let request = basic_request();
+        let entities = basic_entities();
+        let eval = Evaluator::new(request, &entities, &exts);
+
+        let overlaps = |lhs: &str, rhs: &str| {
+            eval.interpret_inline_policy(&Expr::call_extension_fn(
+                Name::parse_unqualified_name("overlaps").expect("should be a valid identifier"),
+                vec![ip(lhs), ip(rhs)],
+            ))
+        };
+        // nested ranges overlap, in either order
+        assert_eq!(overlaps("10.0.0.0/8", "10.1.0.0/16"), Ok(Value::from(true)));
+        assert_eq!(overlaps("10.1.0.0/16", "10.0.0.0/8"), Ok(Value::from(true)));
+        assert_eq!(overlaps("10.1.2.3", "10.0.0.0/8"), Ok(Value::from(true)));
+        assert_eq!(overlaps("10.0.0.0/8", "10.0.0.0/8"), Ok(Value::from(true)));
+        assert_eq!(overlaps("0.0.0.0/0", "192.168.0.1"), Ok(Value::from(true)));
+        assert_eq!(
+            overlaps("1:2:3:4::/48", "1:2:3::/32"),
+            Ok(Value::from(true))
+        );
+        // disjoint ranges
+        assert_eq!(overlaps("10.0.0.0/8", "11.0.0.0/8"), Ok(Value::from(false)));
+        assert_eq!(
+            overlaps("10.0.0.0/9", "10.128.0.0/9"),
+            Ok(Value::from(false))
+        );
+        assert_eq!(
+            overlaps("1:2:3:4::/64", "1:2:3:5::/64"),
+            Ok(Value::from(false))
+        );
+        // IPv4 and IPv6 ranges never overlap, even for the full address space
+        assert_eq!(overlaps("0.0.0.0/0", "::/0"), Ok(Value::from(false)));
+        assert_eq!(
+            overlaps("::ffff:ff00:1", "255.0.0.1"),
+            Ok(Value::from(false))
+        );
+        assert_eq!(overlaps("127.0.0.1", "::1"), Ok(Value::from(false)));
+        // non-ipaddr arguments are type errors
+        assert_matches!(eval.interpret_inline_policy(&Expr::call_extension_fn(
+                Name::parse_unqualified_name("overlaps").expect("should be a valid identifier"),
+                vec![ip("10.0.0.0/8"), Expr::val("10.0.0.0/8")],
+            )),
+            Err(e) => assert_matches!(e.error_kind(), EvaluationErrorKind::TypeError { .. })
+        );
+    }
+
+    #[test]
+    fn mixed_ip_versions() {
+        let ext_array = [extension()];
+        let exts = Extensions::specific_extensions(&ext_array);
+        let request = basic_request();
+        let entities = basic_entities();
+        let eval = Evaluator::new(request, &entities, &exts);
+
+        // comparisons between IPv4 and IPv6 values evaluate to `false` rather
+        // than erroring
+        for src in [
+            r#"ip("127.0.0.1").isInRange(ip("::/0"))"#,
+            r#"ip("::1").isInRange(ip("0.0.0.0/0"))"#,
+            r#"ip("10.0.0.1").overlaps(ip("::ffff:a00:1"))"#,
+            r#"ip("::1").isInRange(ip("10.0.0.0/8")) && !ip("::1").isLoopback()"#,
+        ] {
+            let expr = parse_expr(src).expect("should parse");
+            assert_eq!(
+                eval.interpret_inline_policy(&expr),
+                Ok(Value::from(false)),
+                "{src}"
+            );
+        }
+
+        let expr = parse_expr(
+            r#"ip("10.1.2.3").isInRange(ip("10.0.0.0/8")) && !ip("10.1.2.3").isLoopback()"#,
+        )
+        .expect("should parse");
+        assert_eq!(eval.interpret_inline_policy(&expr), Ok(Value::from(true)));
+        let expr = parse_expr(
+            r#"ip("127.0.0.1").isInRange(ip("127.0.0.0/8")) && !ip("127.0.0.1").isLoopback()"#,
+        )
+        .expect("should parse");
+        assert_eq!(eval.interpret_inline_policy(&expr), Ok(Value::from(false)));
+    }
+
     #[test]
     fn test_contains_at_least_two() {
         assert!(contains_at_least_two(":::", ':'));
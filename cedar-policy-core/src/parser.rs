@@ -25,6 +25,9 @@ pub mod err;
 /// implementations for formatting, like `Display`
 mod fmt;
 pub use fmt::join_with_conjunction;
+/// Limits on the policy text accepted by the parser
+mod limits;
+pub use limits::ParseLimits;
 /// Source location struct
 mod loc;
 pub use loc::Loc;
@@ -36,8 +39,9 @@ pub mod text_to_cst;
 /// Utility functions to unescape string literals
 pub mod unescape;
 
+use either::Either;
 use smol_str::SmolStr;
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, HashMap};
 
 use crate::ast;
 use crate::ast::RestrictedExprParseError;
@@ -58,6 +62,17 @@ pub fn parse_policyset(text: &str) -> Result<ast::PolicySet, err::ParseErrors> {
     }
 }
 
+/// Like `parse_policyset()`, but first checks that `text` is within `limits`,
+/// so that, e.g., over-deep policies are rejected rather than overflowing the
+/// stack
+pub fn parse_policyset_with_limits(
+    text: &str,
+    limits: ParseLimits,
+) -> Result<ast::PolicySet, err::ParseErrors> {
+    limits.check(text)?;
+    parse_policyset(text)
+}
+
 /// Like `parse_policyset()`, but also returns the (lossless) original text of
 /// each individual policy.
 /// INVARIANT: The `PolicyId` of every `Policy` and `Template` returned by the
@@ -66,12 +81,42 @@ pub fn parse_policyset(text: &str) -> Result<ast::PolicySet, err::ParseErrors> {
 pub fn parse_policyset_and_also_return_policy_text(
     text: &str,
 ) -> Result<(HashMap<ast::PolicyID, &str>, ast::PolicySet), err::ParseErrors> {
+    let (texts, pset, unknown_functions) = parse_policyset_allowing_unknown_functions(text)?;
+    if unknown_functions.is_empty() {
+        Ok((texts, pset))
+    } else {
+        Err(unknown_functions)
+    }
+}
+
+/// Like `parse_policyset_and_also_return_policy_text()`, but a call to a
+/// function which is not a known extension function is not an error: it is
+/// kept in the policy, and evaluates to an error if it is ever reached. The
+/// `NotAFunction` errors for these calls are returned alongside the policies,
+/// as warnings. If there is any other error, all errors are returned.
+///
+/// This is meant for policies written for a build of Cedar with more
+/// extensions enabled. A misspelled function name is also accepted, so this
+/// should only be used during a controlled migration.
+/// INVARIANT: The `PolicyId` of every `Policy` and `Template` returned by the
+/// `policies()` and `templates()` methods on the returned `Policy` _must_
+/// appear as a key in the returned map.
+pub fn parse_policyset_allowing_unknown_functions(
+    text: &str,
+) -> Result<
+    (
+        HashMap<ast::PolicyID, &str>,
+        ast::PolicySet,
+        err::ParseErrors,
+    ),
+    err::ParseErrors,
+> {
     let mut errs = err::ParseErrors::new();
     let cst = text_to_cst::parse_policies(text)?;
     let Some(pset) = cst.to_policyset(&mut errs) else {
         return Err(errs);
     };
-    if errs.is_empty() {
+    if errs.iter().all(err::ParseError::is_unknown_function) {
         // PANIC SAFETY Shouldn't be `none` since `parse_policies()` and `to_policyset()` didn't return `Err`
         #[allow(clippy::expect_used)]
         // PANIC SAFETY Indexing is safe because of how the `SourceSpan` is constructed
@@ -86,6 +131,82 @@ pub fn parse_policyset_and_also_return_policy_text(
             .expect("shouldn't be None since parse_policies() and to_policyset() didn't return Err")
             .map(|(id, policy)| (id, &text[policy.loc.start()..policy.loc.end()]))
             .collect::<HashMap<ast::PolicyID, &str>>();
+        Ok((texts, pset, errs))
+    } else {
+        Err(errs)
+    }
+}
+
+/// Like `parse_policyset_and_also_return_policy_text()`, but a policy or
+/// template with an `@id("...")` annotation gets that id instead of the
+/// generated one. It is an error for two policies or templates to end up with
+/// the same id.
+/// INVARIANT: The `PolicyId` of every `Policy` and `Template` returned by the
+/// `policies()` and `templates()` methods on the returned `Policy` _must_
+/// appear as a key in the returned map.
+pub fn parse_policyset_with_annotated_ids_and_also_return_policy_text(
+    text: &str,
+) -> Result<(HashMap<ast::PolicyID, &str>, ast::PolicySet), err::ParseErrors> {
+    let mut errs = err::ParseErrors::new();
+    let cst = text_to_cst::parse_policies(text)?;
+    let Some(policies) = cst.with_generated_policyids() else {
+        return Err(errs);
+    };
+    // PANIC SAFETY: `id` is a valid identifier
+    #[allow(clippy::unwrap_used)]
+    let id_key: ast::AnyId = "id".parse().unwrap();
+    let mut pset = ast::PolicySet::new();
+    let mut texts = HashMap::new();
+    // Location of the first policy or annotation using each id
+    let mut first_uses: HashMap<ast::PolicyID, Loc> = HashMap::new();
+    for (generated_id, policy) in policies {
+        let Some(p) = policy.to_policy_or_template(generated_id.clone(), &mut errs) else {
+            continue;
+        };
+        let annotation = match &p {
+            Either::Left(static_policy) => static_policy.annotation(&id_key),
+            Either::Right(template) => template.annotation(&id_key),
+        };
+        let (id, loc) = match annotation {
+            Some(annotation) => (
+                ast::PolicyID::from_smolstr(annotation.val.clone()),
+                annotation.loc.clone().unwrap_or_else(|| policy.loc.clone()),
+            ),
+            None => (generated_id, policy.loc.clone()),
+        };
+        match first_uses.entry(id.clone()) {
+            Entry::Occupied(first) => {
+                errs.push(err::ToASTError::new(
+                    err::ToASTErrorKind::DuplicateIdAnnotation {
+                        id,
+                        first: first.get().span,
+                    },
+                    loc,
+                ));
+                continue;
+            }
+            Entry::Vacant(v) => {
+                v.insert(loc);
+            }
+        }
+        let added = match p {
+            Either::Left(static_policy) => pset.add_static(static_policy.new_id(id.clone())),
+            Either::Right(template) => pset.add_template(template.new_id(id.clone())),
+        };
+        match added {
+            Ok(()) => {
+                // PANIC SAFETY Indexing is safe because of how the `SourceSpan` is constructed
+                #[allow(clippy::indexing_slicing)]
+                texts.insert(id, &text[policy.loc.start()..policy.loc.end()]);
+            }
+            // `first_uses` should already have caught this
+            Err(ast::PolicySetError::Occupied { id }) => errs.push(err::ToASTError::new(
+                err::ToASTErrorKind::DuplicatePolicyId(id),
+                policy.loc.clone(),
+            )),
+        }
+    }
+    if errs.is_empty() {
         Ok((texts, pset))
     } else {
         Err(errs)
@@ -143,6 +264,46 @@ pub fn parse_policy_template(
     }
 }
 
+/// A best-effort reconstruction of a policy or template with errors, as
+/// returned by `parse_policy_tolerant()`
+#[derive(Debug, Clone)]
+pub struct RecoveredTemplate {
+    /// The recovered template, in which scope constraints that could not be
+    /// recovered are unconstrained, and conditions that could not be recovered
+    /// are left out
+    pub template: ast::Template,
+    /// Whether the principal scope constraint was recovered
+    pub principal_recovered: bool,
+    /// Whether the action scope constraint was recovered
+    pub action_recovered: bool,
+    /// Whether the resource scope constraint was recovered
+    pub resource_recovered: bool,
+    /// The `when` and `unless` conditions, in source order, as ESTs, with
+    /// `None` for each condition that could not be recovered
+    pub conditions: Vec<Option<est::Clause>>,
+}
+
+/// Parse a policy or template, recovering as much of it as possible if it has
+/// errors. Returns the recovered template, if any, together with all the errors
+/// encountered; see `to_recovered_template()` for how errors are handled.
+/// If `id` is Some, then the resulting template will have that `id`.
+/// If the `id` is None, the parser will use "policy0".
+///
+/// The recovered template may be incomplete even when it is returned, so it
+/// must never be used for authorization.
+pub fn parse_policy_tolerant(
+    id: Option<String>,
+    text: &str,
+) -> (Option<RecoveredTemplate>, err::ParseErrors) {
+    let id = match id {
+        Some(id) => ast::PolicyID::from_string(id),
+        None => ast::PolicyID::from_string("policy0"),
+    };
+    let (cst, mut errs) = text_to_cst::parse_policy_tolerant(text);
+    let ast = cst.and_then(|cst| cst.to_recovered_template(id, &mut errs));
+    (ast, errs)
+}
+
 /// Like `parse_policy_template()`, but also returns the (lossless) EST -- that
 /// is, the EST of the original template without any of the lossy transforms
 /// involved in converting to AST.
@@ -450,6 +611,117 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_parse_policy_tolerant() {
+        // valid input is recovered in full, with no errors
+        let src =
+            r#"@id("p") permit(principal == User::"alice", action, resource) when { context.ok };"#;
+        let (recovered, errs) = parse_policy_tolerant(None, src);
+        assert!(errs.is_empty());
+        let recovered = recovered.unwrap();
+        assert_eq!(
+            recovered.template,
+            parse_policy_template(None, src).unwrap()
+        );
+        assert!(
+            recovered.principal_recovered
+                && recovered.action_recovered
+                && recovered.resource_recovered
+        );
+        assert_matches!(&recovered.conditions[..], [Some(est::Clause::When(_))]);
+
+        // invalid conditions are dropped and invalid scope constraints are
+        // replaced with unconstrained ones
+        let src = r#"
+            @id("p")
+            forbid(principal == User::"alice", action == , resource in ?resource)
+            when { resource.public }
+            unless { principal. }
+            when { ?principal == resource };
+        "#;
+        let (recovered, errs) = parse_policy_tolerant(Some("id".into()), src);
+        assert_eq!(errs.len(), 3, "{errs:?}");
+        let RecoveredTemplate {
+            template: recovered,
+            principal_recovered,
+            action_recovered,
+            resource_recovered,
+            conditions,
+        } = recovered.unwrap();
+        assert_eq!(recovered.id(), &ast::PolicyID::from_string("id"));
+        assert_eq!(recovered.effect(), ast::Effect::Forbid);
+        assert_eq!(
+            recovered
+                .annotation(&"id".parse().unwrap())
+                .map(AsRef::as_ref),
+            Some("p")
+        );
+        assert!(principal_recovered && !action_recovered && resource_recovered);
+        assert_eq!(
+            recovered.principal_constraint(),
+            &ast::PrincipalConstraint::is_eq(r#"User::"alice""#.parse().unwrap())
+        );
+        assert_eq!(recovered.action_constraint(), &ast::ActionConstraint::any());
+        assert_eq!(
+            recovered.resource_constraint(),
+            &ast::ResourceConstraint::is_in_slot()
+        );
+        assert!(recovered
+            .non_scope_constraints()
+            .eq_shape(&parse_expr("resource.public").unwrap()));
+        assert_matches!(&conditions[..], [Some(est::Clause::When(_)), None, None]);
+        assert_eq!(
+            conditions[0].as_ref().unwrap().to_string(),
+            r#"when { resource["public"] }"#
+        );
+
+        // nothing can be recovered without an effect
+        let (recovered, errs) = parse_policy_tolerant(None, "(principal, action, resource);");
+        assert!(recovered.is_none());
+        assert!(!errs.is_empty());
+    }
+
+    #[test]
+    fn test_parse_policyset_allowing_unknown_functions() {
+        let src = r#"
+            permit(principal, action, resource) when { datetime("2024-10-15") > context.now };
+            permit(principal, action, resource) when { ip("10.0.0.1").isLoopback() };
+        "#;
+        assert_matches!(parse_policyset(src), Err(errs) => {
+            assert_eq!(errs.len(), 1);
+            assert_eq!(errs.to_string(), "`datetime` is not a function");
+        });
+
+        let (texts, pset, unknown_functions) =
+            parse_policyset_allowing_unknown_functions(src).unwrap();
+        assert_eq!(pset.policies().count(), 2);
+        assert_eq!(texts.len(), 2);
+        assert_eq!(unknown_functions.len(), 1);
+        assert_eq!(
+            unknown_functions.to_string(),
+            "`datetime` is not a function"
+        );
+        let policy = pset.get(&ast::PolicyID::from_string("policy0")).unwrap();
+        assert!(policy
+            .condition()
+            .subexpressions()
+            .any(|e| matches!(e.expr_kind(), ast::ExprKind::ExtensionFunctionApp { fn_name, .. } if fn_name.to_string() == "datetime")));
+
+        // any other error is still an error, and all errors are reported
+        assert_matches!(
+            parse_policyset_allowing_unknown_functions(
+                r#"permit(principal, action, resource) when { datetime("x") && principal.foo( };"#
+            ),
+            Err(_)
+        );
+        assert_matches!(
+            parse_policyset_allowing_unknown_functions(
+                r#"permit(principal, action, resource) when { datetime("x") && principal.contains(1, 2) };"#
+            ),
+            Err(errs) => assert_eq!(errs.len(), 2)
+        );
+    }
+
     #[test]
     fn test_error_out() {
         assert_matches!(parse_policyset(
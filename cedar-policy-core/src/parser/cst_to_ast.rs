@@ -42,13 +42,15 @@ use super::err::{
 use super::loc::Loc;
 use super::node::Node;
 use super::unescape::{to_pattern, to_unescaped_string};
+use super::RecoveredTemplate;
 use crate::ast::{
     self, ActionConstraint, CallStyle, EntityReference, EntityType, EntityUID, Integer,
     PatternElem, PolicySetError, PrincipalConstraint, PrincipalOrResourceConstraint,
     ResourceConstraint,
 };
-use crate::est::extract_single_argument;
+use crate::est::{self, extract_single_argument};
 use itertools::{Either, Itertools};
+use nonempty::NonEmpty;
 use smol_str::SmolStr;
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashSet};
@@ -261,8 +263,9 @@ impl Node<Option<cst::Policy>> {
             failure = true
         }
 
-        // all data and errors are generated, so fail or construct result
-        if failure || !errs.is_empty() {
+        // all data and errors are generated, so fail or construct result. A
+        // call to an unknown function is still constructed, see `into_func()`
+        if failure || !errs.iter().all(ParseError::is_unknown_function) {
             return None;
         };
         let effect = maybe_effect?;
@@ -281,6 +284,67 @@ impl Node<Option<cst::Policy>> {
             &self.loc,
         ))
     }
+
+    /// Convert `cst::Policy` to a best-effort `ast::Template`, even if parts
+    /// of it are invalid. Errors are reported in `errs` as usual, but instead
+    /// of failing, invalid annotations and conditions are left out, and an
+    /// invalid scope constraint is treated as unconstrained. The returned
+    /// `RecoveredTemplate` records which scope constraints and conditions were
+    /// recovered. Fails only if the policy or its effect could not be
+    /// recovered at all.
+    ///
+    /// The result does not mean the same thing as the source policy, so it
+    /// must never be used for authorization.
+    pub fn to_recovered_template(
+        &self,
+        id: ast::PolicyID,
+        errs: &mut ParseErrors,
+    ) -> Option<RecoveredTemplate> {
+        let policy = self.as_inner()?;
+        let maybe_effect = policy.effect.to_effect(errs);
+        let (_, annotations) = policy.get_ast_annotations(errs);
+        let (maybe_principal, maybe_action, maybe_resource) = policy.extract_scope(errs);
+        let mut conds = Vec::new();
+        let mut conditions = Vec::new();
+        for c in &policy.conds {
+            let recovered = c.to_expr(errs).and_then(|(e, is_when)| {
+                let slots = e.slots().collect::<Vec<_>>();
+                for slot in &slots {
+                    errs.push(ToASTError::new(
+                        ToASTErrorKind::SlotsInConditionClause {
+                            slot: slot.id.into(),
+                            clausetype: if is_when { "when" } else { "unless" },
+                        },
+                        slot.loc.clone().unwrap_or_else(|| c.loc.clone()),
+                    ));
+                }
+                // the errors in `c` were reported by `to_expr()`
+                let clause = est::Clause::try_from(c.as_inner()?.clone()).ok()?;
+                slots.is_empty().then_some((e, clause))
+            });
+            conditions.push(recovered.map(|(e, clause)| {
+                conds.push(e);
+                clause
+            }));
+        }
+
+        Some(RecoveredTemplate {
+            principal_recovered: maybe_principal.is_some(),
+            action_recovered: maybe_action.is_some(),
+            resource_recovered: maybe_resource.is_some(),
+            template: construct_template_policy(
+                id,
+                annotations,
+                maybe_effect?,
+                maybe_principal.unwrap_or_else(PrincipalConstraint::any),
+                maybe_action.unwrap_or_else(ActionConstraint::any),
+                maybe_resource.unwrap_or_else(ResourceConstraint::any),
+                conds,
+                &self.loc,
+            ),
+            conditions,
+        })
+    }
 }
 
 impl cst::Policy {
@@ -1320,12 +1384,9 @@ impl Node<Option<cst::Relation>> {
                 }
             }
             cst::Relation::Has { target, field } => {
-                match (
-                    target.to_expr(errs),
-                    field.to_expr_or_special(errs)?.into_valid_attr(errs),
-                ) {
-                    (Some(t), Some(s)) => Some(ExprOrSpecial::Expr {
-                        expr: construct_expr_has(t, s, self.loc.clone()),
+                match (target.to_expr(errs), field.to_has_attr_path(errs)) {
+                    (Some(t), Some(path)) => Some(ExprOrSpecial::Expr {
+                        expr: construct_expr_has_path(t, path, self.loc.clone()),
                         loc: self.loc.clone(),
                     }),
                     _ => None,
@@ -1871,6 +1932,56 @@ impl Node<Option<cst::Member>> {
     }
 }
 
+impl Node<Option<cst::Add>> {
+    /// Convert the right-hand side of a `has` expression to the attribute it
+    /// tests for, or to the path of attributes it tests for when it's written
+    /// `a.b.c`, as in `context has a.b.c`
+    pub(crate) fn to_has_attr_path(&self, errs: &mut ParseErrors) -> Option<NonEmpty<SmolStr>> {
+        match self.as_attr_path() {
+            Some(member) => {
+                let first = member.item.to_expr_or_special(errs)?.into_valid_attr(errs);
+                // collect() allows all conversions to run and generate errors
+                let fields: Vec<_> = member
+                    .access
+                    .iter()
+                    .map(|access| match access.as_inner() {
+                        Some(cst::MemAccess::Field(field)) => field.to_valid_ident(errs),
+                        _ => None,
+                    })
+                    .collect();
+                let fields = fields
+                    .into_iter()
+                    .map(|id| id.map(ast::Id::into_smolstr))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(NonEmpty::from((first?, fields)))
+            }
+            None => self
+                .to_expr_or_special(errs)?
+                .into_valid_attr(errs)
+                .map(NonEmpty::new),
+        }
+    }
+
+    /// If this is a primary expression followed by one or more field
+    /// accesses and nothing else, like `a.b.c`, get it as a `cst::Member`
+    fn as_attr_path(&self) -> Option<&cst::Member> {
+        let add = self.as_inner()?;
+        let mult = add.initial.as_inner()?;
+        let unary = mult.initial.as_inner()?;
+        let member = unary.item.as_inner()?;
+        let only_fields = member
+            .access
+            .iter()
+            .all(|access| matches!(access.as_inner(), Some(cst::MemAccess::Field(_))));
+        (add.extended.is_empty()
+            && mult.extended.is_empty()
+            && unary.op.is_none()
+            && !member.access.is_empty()
+            && only_fields)
+            .then_some(member)
+    }
+}
+
 impl Node<Option<cst::MemAccess>> {
     fn to_access(&self, errs: &mut ParseErrors) -> Option<AstAccessor> {
         // if `self` doesn't have data, nothing we can do here, just propagate
@@ -2192,12 +2303,15 @@ impl ast::Name {
                 return None;
             }
         }
-        if EXTENSION_STYLES.functions.contains(&self) {
-            Some(construct_ext_func(self, args, loc))
-        } else {
-            errs.push(ToASTError::new(ToASTErrorKind::NotAFunction(self), loc));
-            None
+        if !EXTENSION_STYLES.functions.contains(&self) {
+            // Still construct the call, which is an error only when evaluated,
+            // for `parse_policyset_allowing_unknown_functions()`
+            errs.push(ToASTError::new(
+                ToASTErrorKind::NotAFunction(self.clone()),
+                loc.clone(),
+            ));
         }
+        Some(construct_ext_func(self, args, loc))
     }
 }
 
@@ -2462,8 +2576,10 @@ fn construct_expr_mul(
     }
     expr
 }
-fn construct_expr_has(t: ast::Expr, s: SmolStr, loc: Loc) -> ast::Expr {
-    ast::ExprBuilder::new().with_source_loc(loc).has_attr(t, s)
+fn construct_expr_has_path(t: ast::Expr, path: NonEmpty<SmolStr>, loc: Loc) -> ast::Expr {
+    ast::ExprBuilder::new()
+        .with_source_loc(loc)
+        .has_attr_path(t, path)
 }
 fn construct_expr_attr(e: ast::Expr, s: SmolStr, loc: Loc) -> ast::Expr {
     ast::ExprBuilder::new().with_source_loc(loc).get_attr(e, s)
@@ -3357,6 +3473,58 @@ mod tests {
         });
     }
 
+    #[test]
+    fn construct_has_path() {
+        let parse = |src: &str| {
+            let mut errs = ParseErrors::new();
+            text_to_cst::parse_expr(src)
+                .expect("failed parser")
+                .to_expr(&mut errs)
+                .unwrap_or_else(|| {
+                    panic!("failed convert to AST:\n{:?}", miette::Report::new(errs))
+                })
+        };
+        for (src, target, path) in [
+            ("context has a.b", "context", vec!["a", "b"]),
+            ("context has a.b.c", "context", vec!["a", "b", "c"]),
+            (
+                r#"principal.x has "a b".c"#,
+                "principal.x",
+                vec!["a b", "c"],
+            ),
+            ("context has principal.b", "context", vec!["principal", "b"]),
+        ] {
+            let expr = parse(src);
+            let path = NonEmpty::from_vec(path.into_iter().map(SmolStr::new).collect())
+                .expect("path is non-empty");
+            let expected = ast::Expr::has_attr_path(parse(target), path);
+            assert!(
+                expr.eq_shape(&expected),
+                "for `{src}`, expected `{expected}`, got `{expr}`"
+            );
+        }
+    }
+
+    #[test]
+    fn construct_has_path_invalid() {
+        for src in [
+            r#"context has a["b"]"#,
+            "context has a.b()",
+            "context has a.if",
+            "context has A::b.c",
+            "context has (a).b",
+            "context has a.b + 1",
+        ] {
+            let mut errs = ParseErrors::new();
+            let e = text_to_cst::parse_expr(src)
+                .expect("failed parser")
+                .to_expr(&mut errs);
+            assert_matches!(e, None => {
+                assert!(!errs.is_empty(), "expected an error for `{src}`");
+            });
+        }
+    }
+
     #[test]
     fn construct_like_1() {
         let mut errs = ParseErrors::new();
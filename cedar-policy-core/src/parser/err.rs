@@ -65,6 +65,13 @@ pub enum ParseError {
 }
 
 impl ParseError {
+    /// Whether this is a `NotAFunction` error. The AST is still constructed
+    /// for these, with the call to the unknown function kept as it is; see
+    /// `parse_policyset_allowing_unknown_functions()`.
+    pub(crate) fn is_unknown_function(&self) -> bool {
+        matches!(self, ParseError::ToAST(err) if matches!(err.kind(), ToASTErrorKind::NotAFunction(_)))
+    }
+
     /// Extract a primary source span locating the error, if one is available.
     pub fn primary_source_span(&self) -> Option<SourceSpan> {
         match self {
@@ -80,6 +87,19 @@ impl ParseError {
                 .and_then(|mut it| it.next().map(|lspan| *lspan.inner())),
         }
     }
+
+    /// A code identifying the kind of this error, e.g., `"cedar::parse::syntax"`,
+    /// for programs which need to handle errors without looking at variants.
+    /// Unlike the error message, the code for a kind of error is stable across
+    /// releases.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ParseError::ToCST(_) => "cedar::parse::syntax",
+            ParseError::ToAST(_) => "cedar::parse::ill_formed",
+            ParseError::RestrictedExpr(_) => "cedar::parse::restricted_expr",
+            ParseError::ParseLiteral(_) => "cedar::parse::literal",
+        }
+    }
 }
 
 /// Errors in the top-level parse literal entrypoint
@@ -103,7 +123,14 @@ pub struct ToASTError {
 // and everything else forwarded directly to `kind`.
 impl Diagnostic for ToASTError {
     fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
-        Some(Box::new(iter::once(LabeledSpan::underline(self.loc.span))))
+        let primary = iter::once(LabeledSpan::underline(self.loc.span));
+        match &self.kind {
+            // also point at the first use of the duplicated id
+            ToASTErrorKind::DuplicateIdAnnotation { .. } => Some(Box::new(
+                primary.chain(self.kind.labels().into_iter().flatten()),
+            )),
+            _ => Some(Box::new(primary)),
+        }
     }
 
     fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
@@ -157,6 +184,19 @@ pub enum ToASTErrorKind {
     /// Returned when we attempt to parse a policy with a conflicting id
     #[error("a policy with id `{0}` already exists in the policy set")]
     DuplicatePolicyId(PolicyID),
+    /// Returned when two policies or templates parsed together would have the
+    /// same id, as given by their `@id` annotations or their positions
+    #[error("policy id `{id}` is used by more than one policy")]
+    #[diagnostic(help(
+        "a policy without an `@id` annotation gets the id `policyN`, where `N` is its position in the policy set"
+    ))]
+    DuplicateIdAnnotation {
+        /// The policy id used more than once
+        id: PolicyID,
+        /// The first policy or `@id` annotation using `id`
+        #[label("first used here")]
+        first: SourceSpan,
+    },
     /// Returned when a template is encountered but a static policy is expected
     #[error("expected a static policy, got a template containing the slot {slot}")]
     #[diagnostic(help("try removing the template slot(s) from this policy"))]
@@ -393,6 +433,16 @@ pub enum ToASTErrorKind {
     #[error("`{0}` is not a valid template slot")]
     #[diagnostic(help("a template slot may only be `?principal` or `?resource`"))]
     InvalidSlot(SmolStr),
+    /// Returned when a policy is nested more deeply than allowed by the
+    /// [`super::ParseLimits`] it was parsed with
+    #[error("expression is nested more than {max_depth} levels deep")]
+    #[diagnostic(help(
+        "reduce the nesting of parentheses, brackets, braces, and `if` expressions"
+    ))]
+    NestingTooDeep {
+        /// The maximum nesting depth
+        max_depth: usize,
+    },
 }
 
 impl ToASTErrorKind {
@@ -710,6 +760,14 @@ impl ParseErrors {
     pub fn errors_as_strings(&self) -> Vec<String> {
         self.0.iter().map(ToString::to_string).collect()
     }
+
+    /// The stable [`ParseError::error_code`] of the first error, which is the
+    /// one this `ParseErrors` displays as, or `"cedar::parse::unknown"` if
+    /// there are no errors.
+    pub fn error_code(&self) -> &'static str {
+        self.first()
+            .map_or("cedar::parse::unknown", ParseError::error_code)
+    }
 }
 
 impl Display for ParseErrors {
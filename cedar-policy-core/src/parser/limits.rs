@@ -0,0 +1,312 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::sync::Arc;
+
+use super::err::{ParseErrors, ToASTError, ToASTErrorKind};
+use super::Loc;
+
+/// Limits on the policy text accepted by the parser, to bound the cost of
+/// parsing untrusted policies.
+///
+/// By default, no limit is enforced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximum nesting depth of expressions
+    max_depth: Option<usize>,
+}
+
+impl ParseLimits {
+    /// Create limits which do not restrict parsing at all
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the nesting depth of expressions. Each pair of parentheses,
+    /// brackets, or braces, each `if-then-else` expression, and each operator,
+    /// including `.` and `[]` accesses, is one level of nesting, so that the
+    /// condition in `when { principal.isAdmin }` is at depth 2. The operators
+    /// of a chain like `a && b && c` each count, as they are nested one in
+    /// another.
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self {
+            max_depth: Some(max_depth),
+        }
+    }
+
+    /// Get the maximum nesting depth of expressions, if limited
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Check that `text` is within these limits. This only scans the tokens of
+    /// `text`, without recursion, so that over-deep policies are rejected
+    /// before parsing them, which would need stack space proportional to
+    /// their depth.
+    pub fn check(&self, text: &str) -> Result<(), ParseErrors> {
+        match self.max_depth {
+            Some(max_depth) => check_depth(text, max_depth),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What a token of the policy text opens, for [`check_depth`]
+enum Opens {
+    /// Nothing
+    Nothing,
+    /// A level of nesting until the matching closing bracket
+    Bracket,
+    /// A level of nesting until the end of the innermost brackets enclosing
+    /// the token, or the next `,` or `;` within them
+    Segment,
+}
+
+/// Check that the nesting depth of `text` is at most `max_depth`.
+///
+/// An `if` counts as a level of nesting until the end of the innermost
+/// brackets enclosing it, or the next `,` or `;` within them, which is where
+/// its `else` branch ends at the latest. So does each operator, including
+/// `.` and the `[` of an index, as the operators of a chain like
+/// `a && b && c` or `a.b.c` are nested one in another in the AST. This never
+/// underestimates the depth of a policy.
+fn check_depth(text: &str, max_depth: usize) -> Result<(), ParseErrors> {
+    // For each level of open brackets, the number of levels opened in it by
+    // `if`s and operators
+    let mut open_levels: Vec<usize> = vec![0];
+    let mut depth: usize = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let opens = match c {
+            '[' => {
+                // `[` is either an index, which is an operator, or a set
+                // literal, so count both
+                depth += 1;
+                if let Some(levels) = open_levels.last_mut() {
+                    *levels += 1;
+                }
+                open_levels.push(0);
+                Opens::Bracket
+            }
+            '(' | '{' => {
+                open_levels.push(0);
+                Opens::Bracket
+            }
+            ')' | ']' | '}' => {
+                // unbalanced brackets are reported by the parser
+                if open_levels.len() > 1 {
+                    let levels = open_levels.pop().unwrap_or_default();
+                    depth = depth.saturating_sub(levels + 1);
+                }
+                Opens::Nothing
+            }
+            ',' | ';' => {
+                if let Some(levels) = open_levels.last_mut() {
+                    depth = depth.saturating_sub(*levels);
+                    *levels = 0;
+                }
+                Opens::Nothing
+            }
+            '"' => {
+                // skip the string literal, including escaped quotes
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => (),
+                    }
+                }
+                Opens::Nothing
+            }
+            '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                // skip the comment
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' || c == '\r' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            '&' | '|' => {
+                chars.next_if(|(_, next)| *next == c);
+                Opens::Segment
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next_if(|(_, next)| *next == '=');
+                Opens::Segment
+            }
+            '+' | '-' | '*' | '.' => Opens::Segment,
+            c if c.is_whitespace() => continue,
+            c if c == '_' || c.is_ascii_alphabetic() || c == '?' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) =
+                    chars.next_if(|(_, c)| *c == '_' || c.is_ascii_alphanumeric())
+                {
+                    end = i + c.len_utf8();
+                }
+                // these are reserved identifiers, so they are always keywords
+                match &text[start..end] {
+                    "if" | "in" | "has" | "like" | "is" => Opens::Segment,
+                    _ => Opens::Nothing,
+                }
+            }
+            _ => Opens::Nothing,
+        };
+        match opens {
+            Opens::Nothing => continue,
+            Opens::Bracket => (),
+            Opens::Segment => {
+                if let Some(levels) = open_levels.last_mut() {
+                    *levels += 1;
+                }
+            }
+        }
+        depth += 1;
+        if depth > max_depth {
+            return Err(ToASTError::new(
+                ToASTErrorKind::NestingTooDeep { max_depth },
+                Loc::new(start..start + c.len_utf8(), Arc::from(text)),
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn depth_ok(text: &str, max_depth: usize) -> bool {
+        ParseLimits::new()
+            .with_max_depth(max_depth)
+            .check(text)
+            .is_ok()
+    }
+
+    /// The smallest `max_depth` which `text` is within
+    fn depth(text: &str) -> usize {
+        (0..).find(|max_depth| depth_ok(text, *max_depth)).unwrap()
+    }
+
+    #[test]
+    fn brackets() {
+        assert_eq!(depth("permit(principal, action, resource);"), 1);
+        assert_eq!(
+            depth("permit(principal, action, resource) when { principal.isAdmin };"),
+            2
+        );
+        assert_eq!(
+            depth(
+                r#"permit(principal, action, resource) when { [{a: (1)}].contains(principal) };"#
+            ),
+            5
+        );
+        assert_eq!(
+            depth("permit(principal, action, resource) when { (((1))) == 1 } unless { (2) == 2 };"),
+            4
+        );
+    }
+
+    #[test]
+    fn ifs() {
+        assert_eq!(
+            depth(
+                "permit(principal, action, resource) when { if a then b else if c then d else e };"
+            ),
+            3
+        );
+        assert_eq!(
+            depth("permit(principal, action, resource) when { [if a then b else c, if d then e else f] };"),
+            4
+        );
+        assert_eq!(
+            depth("permit(principal, action, resource) when { (if a then b else c) && (if d then e else f) };"),
+            4
+        );
+    }
+
+    #[test]
+    fn strings_and_comments() {
+        assert_eq!(
+            depth(
+                r#"
+                // ((( if
+                @id("(((\" if")
+                permit(principal, action, resource) when { "[[[" == "\"{{" };
+                "#
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(
+            depth("permit(principal, action, resource) when { a && b || !c };"),
+            4
+        );
+        assert_eq!(
+            depth(
+                r#"permit(principal == User::"alice", action in [A::"a", A::"b"], resource is R in G::"g");"#
+            ),
+            4
+        );
+        assert_eq!(
+            depth(r#"permit(principal, action, resource) when { a.b["c"].d == [1, 2] };"#),
+            7
+        );
+        assert_eq!(
+            depth("permit(principal, action, resource) when { [a && b, c] && d };"),
+            4
+        );
+        assert_eq!(
+            depth("permit(principal, action, resource) when { a <= -1 && b != 2 };"),
+            5
+        );
+    }
+
+    #[test]
+    fn long_chains() {
+        for (op, term) in [
+            (" && ", "principal.a"),
+            (" + ", "1"),
+            ("", ".a"),
+            ("", r#"["a"]"#),
+        ] {
+            let chain = vec![term; 100_000].join(op);
+            let text = format!("permit(principal, action, resource) when {{ {chain} }};");
+            assert!(!depth_ok(&text, 5), "for {op}{term}");
+            assert!(!depth_ok(&text, 50_000), "for {op}{term}");
+        }
+    }
+
+    #[test]
+    fn no_limit() {
+        let text = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert!(ParseLimits::new().check(&text).is_ok());
+        assert_matches!(
+            ParseLimits::new().with_max_depth(1000).check(&text),
+            Err(errs) => {
+                assert_eq!(errs.len(), 1);
+                assert_eq!(errs.to_string(), "expression is nested more than 1000 levels deep");
+            }
+        );
+    }
+}
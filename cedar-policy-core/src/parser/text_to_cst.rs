@@ -47,6 +47,26 @@ fn parse_collect_errors<'a, P, T>(
     ) -> Result<T, err::RawParseError<'a>>,
     text: &'a str,
 ) -> Result<T, err::ParseErrors> {
+    match parse_tolerant(parser, parse, text) {
+        (Some(parsed), errors) if errors.is_empty() => Ok(parsed),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Like `parse_collect_errors()`, but returns the tree produced by the
+/// parser's error recovery (if any) alongside the errors, instead of
+/// discarding it whenever there are errors. Parts of the tree the parser
+/// could not recover from are represented by `Node`s without data.
+fn parse_tolerant<'a, P, T>(
+    parser: &P,
+    parse: impl FnOnce(
+        &P,
+        &mut Vec<err::RawErrorRecovery<'a>>,
+        &Arc<str>,
+        &'a str,
+    ) -> Result<T, err::RawParseError<'a>>,
+    text: &'a str,
+) -> (Option<T>, err::ParseErrors) {
     let mut errs = Vec::new();
     let result = parse(parser, &mut errs, &Arc::from(text), text);
 
@@ -54,17 +74,12 @@ fn parse_collect_errors<'a, P, T>(
         .into_iter()
         .map(err::ToCSTError::from_raw_err_recovery)
         .collect();
-    let parsed = match result {
-        Ok(parsed) => parsed,
+    match result {
+        Ok(parsed) => (Some(parsed), errors),
         Err(e) => {
             errors.push(err::ToCSTError::from_raw_parse_err(e));
-            return Err(errors);
+            (None, errors)
         }
-    };
-    if errors.is_empty() {
-        Ok(parsed)
-    } else {
-        Err(errors)
     }
 }
 
@@ -89,6 +104,12 @@ pub fn parse_policy(text: &str) -> Result<Node<Option<cst::Policy>>, err::ParseE
     parse_collect_errors(&*POLICY_PARSER, grammar::PolicyParser::parse, text)
 }
 
+/// Create CST for one policy statement from text, keeping whatever the
+/// parser recovered even if there are errors. See `parse_tolerant()`.
+pub fn parse_policy_tolerant(text: &str) -> (Option<Node<Option<cst::Policy>>>, err::ParseErrors) {
+    parse_tolerant(&*POLICY_PARSER, grammar::PolicyParser::parse, text)
+}
+
 /// Create CST for one Expression from text
 pub fn parse_expr(text: &str) -> Result<Node<Option<cst::Expr>>, err::ParseErrors> {
     parse_collect_errors(&*EXPR_PARSER, grammar::ExprParser::parse, text)
@@ -49,17 +49,23 @@ pub trait TCNode<K> {
 /// with type `V`, compute the transitive closure of the hierarchy. In case of
 /// error, the result contains an error structure `Err<K>` which contains the
 /// keys (with type `K`) for the nodes in the graph which caused the error.
-/// If `enforce_dag` then also check that the heirarchy is a DAG
+/// If `enforce_dag` then also check that the heirarchy is a DAG. A cycle is
+/// reported with the edges it is made of in the input graph, not with the
+/// edges added by the transitive closure.
 pub fn compute_tc<K, V>(nodes: &mut HashMap<K, V>, enforce_dag: bool) -> Result<(), K>
 where
     K: Clone + Eq + Hash + Debug + Display,
     V: TCNode<K>,
 {
-    let res = compute_tc_internal::<K, V>(nodes);
-    if res.is_ok() && enforce_dag {
-        return enforce_dag_from_tc(nodes);
-    }
-    res
+    // Look for a cycle before computing the TC, which would make every vertex
+    // on a cycle adjacent to every other
+    let cycle = if enforce_dag {
+        find_cycle(nodes.values(), &|k| nodes.get(k))
+    } else {
+        None
+    };
+    compute_tc_internal::<K, V>(nodes)?;
+    cycle.map_or(Ok(()), Err)
 }
 
 /// Given graph as a map from keys with type `K` to implementations of `TCNode`
@@ -78,7 +84,7 @@ where
     let mut ancestors: HashMap<K, HashSet<K>> = HashMap::new();
     for node in nodes.values() {
         let this_node_ancestors: &mut HashSet<K> = ancestors.entry(node.get_key()).or_default();
-        add_ancestors_to_set(node, nodes, this_node_ancestors)?;
+        add_ancestors_to_set(node, &|k| nodes.get(k), this_node_ancestors)?;
     }
     for node in nodes.values_mut() {
         // PANIC SAFETY All nodes in `ancestors` came from `nodes`
@@ -93,6 +99,97 @@ where
     Ok(())
 }
 
+/// Given a graph as a map from keys to `TCNode`s, in which the transitive
+/// closure has already been computed for every node except those with keys in
+/// `added`, compute the transitive closure of the whole graph in place. This
+/// only searches the ancestors of the `added` nodes, and then extends the
+/// nodes which have an edge to one of them, so it is cheaper than
+/// `compute_tc` when few nodes were added to a large graph.
+/// If `enforce_dag` then also check that the hierarchy is a DAG, assuming it
+/// was one before the `added` nodes were added. As in `compute_tc`, a cycle is
+/// reported with the edges it is made of in the input graph.
+pub fn extend_tc<K, V>(
+    nodes: &mut HashMap<K, V>,
+    added: &HashSet<K>,
+    enforce_dag: bool,
+) -> Result<(), K>
+where
+    K: Clone + Eq + Hash + Debug + Display,
+    V: TCNode<K>,
+{
+    // Any new cycle goes through an added node, so searching from those is
+    // enough to find it
+    let cycle = if enforce_dag {
+        find_cycle(added.iter().filter_map(|k| nodes.get(k)), &|k| nodes.get(k))
+    } else {
+        None
+    };
+    let mut ancestors: HashMap<K, HashSet<K>> = HashMap::new();
+    for key in added {
+        if let Some(node) = nodes.get(key) {
+            let mut this_node_ancestors = HashSet::new();
+            add_ancestors_to_set(node, &|k| nodes.get(k), &mut this_node_ancestors)?;
+            ancestors.insert(key.clone(), this_node_ancestors);
+        }
+    }
+    for node in nodes.values_mut() {
+        // A node which reaches an added node (through any path) already has a
+        // direct edge to it, as the closure was computed before it was added
+        let inherited = match ancestors.get(&node.get_key()) {
+            Some(node_ancestors) => node_ancestors.iter().cloned().collect::<Vec<_>>(),
+            None => node
+                .out_edges()
+                .filter_map(|k| ancestors.get(k))
+                .flatten()
+                .cloned()
+                .collect(),
+        };
+        for ancestor_uid in inherited {
+            node.add_edge_to(ancestor_uid);
+        }
+    }
+    cycle.map_or(Ok(()), Err)
+}
+
+/// Given a graph as a slice of `TCNode`s (each identified by its key of type
+/// `K`), compute the transitive closure of the graph in place. Edges to keys
+/// which do not belong to any node in the slice are kept, but not followed.
+/// If `enforce_dag` then also check that the hierarchy is a DAG, returning a
+/// `HasCycle` error with the path of a cycle in the input graph if it is not.
+/// The transitive closure has already been computed when that error is
+/// returned.
+pub fn compute_tc_slice<K, V>(nodes: &mut [V], enforce_dag: bool) -> Result<(), K>
+where
+    K: Clone + Eq + Hash + Debug + Display,
+    V: TCNode<K>,
+{
+    let index: HashMap<K, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.get_key(), i))
+        .collect();
+    // As in `compute_tc_internal`, collect all the needed updates first and
+    // then apply them in a second loop
+    let lookup = |k: &K| index.get(k).and_then(|i| nodes.get(*i));
+    let cycle = if enforce_dag {
+        find_cycle(nodes.iter(), &lookup)
+    } else {
+        None
+    };
+    let mut ancestors: Vec<HashSet<K>> = Vec::with_capacity(nodes.len());
+    for node in nodes.iter() {
+        let mut this_node_ancestors = HashSet::new();
+        add_ancestors_to_set(node, &lookup, &mut this_node_ancestors)?;
+        ancestors.push(this_node_ancestors);
+    }
+    for (node, node_ancestors) in nodes.iter_mut().zip(ancestors) {
+        for ancestor_uid in node_ancestors {
+            node.add_edge_to(ancestor_uid);
+        }
+    }
+    cycle.map_or(Ok(()), Err)
+}
+
 /// Given a graph (as a map from keys to `TCNode`), enforce that
 /// all transitive edges are included, ie, the transitive closure has already
 /// been computed and that it is a DAG. If this is not the case, return an appropriate
@@ -137,24 +234,25 @@ where
     Ok(())
 }
 
-/// For the given `node` in the given `hierarchy`, add all of the `node`'s
-/// transitive ancestors to the given set. Assume that any nodes already in
-/// `ancestors` don't need to be searched -- they have been already handled.
-fn add_ancestors_to_set<K, V>(
+/// For the given `node` in the hierarchy, add all of the `node`'s transitive
+/// ancestors to the given set. `lookup` retrieves the node (if any) for a key
+/// in the hierarchy. Assume that any nodes already in `ancestors` don't need to
+/// be searched -- they have been already handled.
+fn add_ancestors_to_set<'a, K, V>(
     node: &V,
-    hierarchy: &HashMap<K, V>,
+    lookup: &impl Fn(&K) -> Option<&'a V>,
     ancestors: &mut HashSet<K>,
 ) -> Result<(), K>
 where
     K: Clone + Eq + Hash + Debug + Display,
-    V: TCNode<K>,
+    V: TCNode<K> + 'a,
 {
     for ancestor_uid in node.out_edges() {
         if ancestors.insert(ancestor_uid.clone()) {
             // discovered a new ancestor, so add the ancestors of `ancestor` as
             // well
-            if let Some(ancestor) = hierarchy.get(ancestor_uid) {
-                add_ancestors_to_set(ancestor, hierarchy, ancestors)?;
+            if let Some(ancestor) = lookup(ancestor_uid) {
+                add_ancestors_to_set(ancestor, lookup, ancestors)?;
             }
         }
     }
@@ -166,20 +264,78 @@ where
 ///
 /// Then the graph has a cycle if
 /// \exists v \in Vertices. (v,v) \in Edges
+///
+/// We still search for the cycle with `find_cycle`, so that it may be reported
+/// as an ordered path of vertices rather than only as the vertex `v`.
 fn enforce_dag_from_tc<K, V>(entities: &HashMap<K, V>) -> Result<(), K>
 where
     K: Clone + Eq + Hash + Debug + Display,
     V: TCNode<K>,
 {
-    for entity in entities.values() {
+    if entities.values().any(|entity| {
         let key = entity.get_key();
-        if entity.out_edges().contains(&key) {
-            return Err(TcError::has_cycle(key));
+        entity.out_edges().contains(&key)
+    }) {
+        if let Some(err) = find_cycle(entities.values(), &|k| entities.get(k)) {
+            return Err(err);
         }
     }
     Ok(())
 }
 
+/// Search the graph made of `nodes` for a cycle, with a depth-first search
+/// following edges to the nodes retrieved by `lookup`. If there is one,
+/// return a `HasCycle` error holding the vertices of the cycle in order.
+fn find_cycle<'a, K, V>(
+    nodes: impl IntoIterator<Item = &'a V>,
+    lookup: &impl Fn(&K) -> Option<&'a V>,
+) -> Option<TcError<K>>
+where
+    K: Clone + Eq + Hash + Debug + Display,
+    V: TCNode<K> + 'a,
+{
+    let mut finished = HashSet::new();
+    let mut path = Vec::new();
+    nodes
+        .into_iter()
+        .find_map(|node| find_cycle_from(node, lookup, &mut path, &mut finished))
+}
+
+/// Continue the depth-first search of `find_cycle` from `node`. `path` holds
+/// the keys of the nodes currently being searched (ending with the parent of
+/// `node`). `finished` holds the keys of nodes whose descendants have all been
+/// searched without finding a cycle.
+fn find_cycle_from<'a, K, V>(
+    node: &'a V,
+    lookup: &impl Fn(&K) -> Option<&'a V>,
+    path: &mut Vec<K>,
+    finished: &mut HashSet<K>,
+) -> Option<TcError<K>>
+where
+    K: Clone + Eq + Hash + Debug + Display,
+    V: TCNode<K> + 'a,
+{
+    let key = node.get_key();
+    if finished.contains(&key) {
+        return None;
+    }
+    if let Some(start) = path.iter().position(|k| k == &key) {
+        // `node` is being searched already, so the path from it to here is a cycle
+        return Some(TcError::has_cycle(key, path.split_off(start)));
+    }
+    path.push(key.clone());
+    for edge in node.out_edges() {
+        if let Some(next) = lookup(edge) {
+            if let Some(err) = find_cycle_from(next, lookup, path, finished) {
+                return Some(err);
+            }
+        }
+    }
+    path.pop();
+    finished.insert(key);
+    None
+}
+
 // PANIC SAFETY test cases
 #[allow(clippy::indexing_slicing)]
 // PANIC SAFETY: Unit Test Code
@@ -574,6 +730,7 @@ mod tests {
             Ok(_) => panic!("enforce_dag_from_tc should have returned an error"),
             Err(TcError::HasCycle(err)) => {
                 assert!(err.vertex_with_loop() == &EntityUID::with_eid("B"));
+                assert_eq!(err.cycle(), [EntityUID::with_eid("B")]);
             }
             Err(_) => panic!("Unexpected error in enforce_dag_from_tc"),
         }
@@ -591,6 +748,7 @@ mod tests {
             Ok(_) => panic!("enforce_dag_from_tc should have returned an error"),
             Err(TcError::HasCycle(err)) => {
                 assert!(err.vertex_with_loop() == &EntityUID::with_eid("B"));
+                assert_eq!(err.cycle(), [EntityUID::with_eid("B")]);
             }
             Err(_) => panic!("Unexpected error in enforce_dag_from_tc"),
         }
@@ -657,6 +815,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cycle_path() {
+        // same graph as nontrivial_cycle(), but we check the reported cycle
+        //
+        //          D
+        //        /
+        // A -> B -> C -> A
+        let mut a = Entity::with_uid(EntityUID::with_eid("A"));
+        a.add_ancestor(EntityUID::with_eid("B"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("B"));
+        b.add_ancestor(EntityUID::with_eid("C"));
+        b.add_ancestor(EntityUID::with_eid("D"));
+        let mut c = Entity::with_uid(EntityUID::with_eid("C"));
+        c.add_ancestor(EntityUID::with_eid("A"));
+        let d = Entity::with_uid(EntityUID::with_eid("D"));
+        let mut entities = HashMap::from([
+            (a.uid().clone(), a),
+            (b.uid().clone(), b),
+            (c.uid().clone(), c),
+            (d.uid().clone(), d),
+        ]);
+        let cycle = ["A", "B", "C", "A", "B"].map(EntityUID::with_eid);
+        match compute_tc(&mut entities, true) {
+            Ok(_) => panic!("compute_tc should have returned an error"),
+            Err(TcError::HasCycle(err)) => {
+                // the cycle may start at any of its vertices, but must follow
+                // the edges of the input graph
+                assert_eq!(err.cycle().len(), 3);
+                assert_eq!(err.vertex_with_loop(), &err.cycle()[0]);
+                assert!(cycle.windows(3).any(|w| w == err.cycle()));
+                let path = err
+                    .cycle()
+                    .iter()
+                    .chain(std::iter::once(err.vertex_with_loop()))
+                    .map(|k| format!("`{k}`"))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                let expected = format!(
+                    "input graph has a cycle containing vertex `{}`: {path}",
+                    err.vertex_with_loop()
+                );
+                assert_eq!(TcError::HasCycle(err).to_string(), expected);
+            }
+            Err(_) => panic!("Unexpected error in compute_tc"),
+        }
+        // the TC was still computed
+        assert!(entities[&EntityUID::with_eid("A")].is_descendant_of(&EntityUID::with_eid("D")));
+    }
+
     #[test]
     fn disjoint_cycles() {
         // graph with disconnected components including cycles:
@@ -759,4 +966,136 @@ mod tests {
             Err(_) => panic!("Unexpected error in enforce_dag_from_tc"),
         }
     }
+
+    #[test]
+    fn slice() {
+        // same as deeper(), but computing TC over a slice: A -> B -> C -> D -> E
+        let mut a = Entity::with_uid(EntityUID::with_eid("A"));
+        a.add_ancestor(EntityUID::with_eid("B"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("B"));
+        b.add_ancestor(EntityUID::with_eid("C"));
+        let mut c = Entity::with_uid(EntityUID::with_eid("C"));
+        c.add_ancestor(EntityUID::with_eid("D"));
+        let mut d = Entity::with_uid(EntityUID::with_eid("D"));
+        d.add_ancestor(EntityUID::with_eid("E"));
+        let e = Entity::with_uid(EntityUID::with_eid("E"));
+        let mut entities = vec![c, e, a, d, b];
+        assert!(compute_tc_slice(&mut entities, true).is_ok());
+        let (c, a, b) = (&entities[0], &entities[2], &entities[4]);
+        assert!(a.is_descendant_of(&EntityUID::with_eid("C")));
+        assert!(a.is_descendant_of(&EntityUID::with_eid("D")));
+        assert!(a.is_descendant_of(&EntityUID::with_eid("E")));
+        assert!(b.is_descendant_of(&EntityUID::with_eid("D")));
+        assert!(b.is_descendant_of(&EntityUID::with_eid("E")));
+        assert!(c.is_descendant_of(&EntityUID::with_eid("E")));
+        assert!(!c.is_descendant_of(&EntityUID::with_eid("A")));
+        // agrees with TC enforcement over the equivalent map
+        let entities: HashMap<_, _> = entities.into_iter().map(|e| (e.uid().clone(), e)).collect();
+        assert!(enforce_tc_and_dag(&entities).is_ok());
+    }
+
+    #[test]
+    fn slice_cycle() {
+        // A -> B -> C -> B, plus an edge to D, which isn't in the slice
+        let mut a = Entity::with_uid(EntityUID::with_eid("A"));
+        a.add_ancestor(EntityUID::with_eid("B"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("B"));
+        b.add_ancestor(EntityUID::with_eid("C"));
+        let mut c = Entity::with_uid(EntityUID::with_eid("C"));
+        c.add_ancestor(EntityUID::with_eid("B"));
+        c.add_ancestor(EntityUID::with_eid("D"));
+        let mut entities = vec![a, b, c];
+        // without the DAG check, the closure is computed without error
+        assert!(compute_tc_slice(&mut entities.clone(), false).is_ok());
+        match compute_tc_slice(&mut entities, true) {
+            Ok(_) => panic!("compute_tc_slice should have returned an error"),
+            Err(TcError::HasCycle(err)) => {
+                let (b, c) = (EntityUID::with_eid("B"), EntityUID::with_eid("C"));
+                assert!(
+                    err.cycle() == [b.clone(), c.clone()] || err.cycle() == [c, b],
+                    "unexpected cycle {:?}",
+                    err.cycle()
+                );
+            }
+            Err(_) => panic!("Unexpected error in compute_tc_slice"),
+        }
+        // the closure was still computed, including the edge out of the slice
+        assert!(entities[0].is_descendant_of(&EntityUID::with_eid("C")));
+        assert!(entities[0].is_descendant_of(&EntityUID::with_eid("D")));
+        assert!(!entities[0].is_descendant_of(&EntityUID::with_eid("A")));
+    }
+
+    #[test]
+    fn extend() {
+        // start with the closed graph A -> B -> C, where A also has an edge to
+        // D, which isn't in the graph yet
+        let mut a = Entity::with_uid(EntityUID::with_eid("A"));
+        a.add_ancestor(EntityUID::with_eid("B"));
+        a.add_ancestor(EntityUID::with_eid("D"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("B"));
+        b.add_ancestor(EntityUID::with_eid("C"));
+        let c = Entity::with_uid(EntityUID::with_eid("C"));
+        let mut entities = HashMap::from([
+            (a.uid().clone(), a),
+            (b.uid().clone(), b),
+            (c.uid().clone(), c),
+        ]);
+        assert!(compute_tc(&mut entities, true).is_ok());
+        // now add D -> E -> F and G -> B
+        let mut d = Entity::with_uid(EntityUID::with_eid("D"));
+        d.add_ancestor(EntityUID::with_eid("E"));
+        let mut e = Entity::with_uid(EntityUID::with_eid("E"));
+        e.add_ancestor(EntityUID::with_eid("F"));
+        let mut g = Entity::with_uid(EntityUID::with_eid("G"));
+        g.add_ancestor(EntityUID::with_eid("B"));
+        let added = HashSet::from([d.uid().clone(), e.uid().clone(), g.uid().clone()]);
+        entities.extend([
+            (d.uid().clone(), d),
+            (e.uid().clone(), e),
+            (g.uid().clone(), g),
+        ]);
+        assert!(extend_tc(&mut entities, &added, true).is_ok());
+        let a = &entities[&EntityUID::with_eid("A")];
+        assert!(a.is_descendant_of(&EntityUID::with_eid("E")));
+        assert!(a.is_descendant_of(&EntityUID::with_eid("F")));
+        let d = &entities[&EntityUID::with_eid("D")];
+        assert!(d.is_descendant_of(&EntityUID::with_eid("F")));
+        let g = &entities[&EntityUID::with_eid("G")];
+        assert!(g.is_descendant_of(&EntityUID::with_eid("C")));
+        assert!(!g.is_descendant_of(&EntityUID::with_eid("A")));
+        // agrees with TC enforcement over the whole graph
+        assert!(enforce_tc_and_dag(&entities).is_ok());
+    }
+
+    #[test]
+    fn extend_cycle() {
+        // start with the closed graph A -> B -> C, and add C -> D -> A
+        let mut a = Entity::with_uid(EntityUID::with_eid("A"));
+        a.add_ancestor(EntityUID::with_eid("B"));
+        let mut b = Entity::with_uid(EntityUID::with_eid("B"));
+        b.add_ancestor(EntityUID::with_eid("C"));
+        let c = Entity::with_uid(EntityUID::with_eid("C"));
+        let mut entities = HashMap::from([(a.uid().clone(), a), (b.uid().clone(), b)]);
+        assert!(compute_tc(&mut entities, true).is_ok());
+        let mut c = c;
+        c.add_ancestor(EntityUID::with_eid("D"));
+        let mut d = Entity::with_uid(EntityUID::with_eid("D"));
+        d.add_ancestor(EntityUID::with_eid("A"));
+        let added = HashSet::from([c.uid().clone(), d.uid().clone()]);
+        entities.extend([(c.uid().clone(), c), (d.uid().clone(), d)]);
+        match extend_tc(&mut entities, &added, true) {
+            Ok(_) => panic!("extend_tc should have returned an error"),
+            Err(TcError::HasCycle(err)) => {
+                // `A` already has an edge to `C`, so the cycle may skip `B`
+                for eid in ["A", "C", "D"] {
+                    assert!(
+                        err.cycle().contains(&EntityUID::with_eid(eid)),
+                        "unexpected cycle {:?}",
+                        err.cycle()
+                    );
+                }
+            }
+            Err(_) => panic!("Unexpected error in extend_tc"),
+        }
+    }
 }
@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use itertools::Itertools;
 use miette::Diagnostic;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -30,7 +31,7 @@ pub enum TcError<K: Debug + Display> {
     #[error("expected all transitive edges to exist, but `{}` -> `{}` and `{}` -> `{}` exists, while `{}` -> `{}` does not", .0.child, .0.parent, .0.parent, .0.grandparent, .0.child, .0.grandparent)]
     MissingTcEdge(MissingTcEdge<K>),
     /// Error raised when enforce_dag finds that the graph is not a DAG
-    #[error("input graph has a cycle containing vertex `{}`", .0.vertex_with_loop)]
+    #[error("input graph has a cycle containing vertex `{}`: {}", .0.vertex_with_loop, .0.display_cycle())]
     HasCycle(HasCycle<K>),
 }
 
@@ -43,8 +44,11 @@ impl<K: Debug + Display> TcError<K> {
         })
     }
 
-    pub(crate) fn has_cycle(vertex_with_loop: K) -> Self {
-        Self::HasCycle(HasCycle { vertex_with_loop })
+    pub(crate) fn has_cycle(vertex_with_loop: K, cycle: Vec<K>) -> Self {
+        Self::HasCycle(HasCycle {
+            vertex_with_loop,
+            cycle,
+        })
     }
 }
 
@@ -60,8 +64,10 @@ pub struct MissingTcEdge<K: Debug + Display> {
 /// Error raised when enforce_dag finds that the graph is not a DAG
 #[derive(Debug, PartialEq, Eq)]
 pub struct HasCycle<K: Debug + Display> {
-    /// Because DAG enforcement can only be called after compute_tc/enforce_tc, a cycle will manifest as a vertex with a loop
+    /// A vertex on the cycle, which is also the first element of `cycle`
     vertex_with_loop: K,
+    /// The vertices on the cycle, in order
+    cycle: Vec<K>,
 }
 
 impl<K: Debug + Display> HasCycle<K> {
@@ -69,6 +75,22 @@ impl<K: Debug + Display> HasCycle<K> {
     pub fn vertex_with_loop(&self) -> &K {
         &self.vertex_with_loop
     }
+
+    /// The vertices forming the cycle, in order, starting at
+    /// `vertex_with_loop`. Each vertex has an edge to the next one, and the
+    /// last vertex has an edge back to the first.
+    pub fn cycle(&self) -> &[K] {
+        &self.cycle
+    }
+
+    /// Render the cycle as a path, e.g., `` `A` -> `B` -> `A` ``
+    fn display_cycle(&self) -> String {
+        self.cycle
+            .iter()
+            .chain(std::iter::once(&self.vertex_with_loop))
+            .map(|k| format!("`{k}`"))
+            .join(" -> ")
+    }
 }
 
 /// Type alias for convenience
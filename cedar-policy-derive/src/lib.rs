@@ -0,0 +1,159 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Derive macros for Cedar. Use them through the `derive` feature of the
+//! `cedar-policy` crate, which re-exports them.
+#![forbid(unsafe_code)]
+#![warn(missing_debug_implementations, rust_2018_idioms)]
+
+use std::collections::HashSet;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DataStruct, DeriveInput, Field, Fields, GenericArgument, LitStr,
+    PathArguments, Type,
+};
+
+/// Derive `TryFrom<T> for cedar_policy::Context` for a struct `T` with named
+/// fields. Every field becomes an attribute of the context, with the same
+/// name, and a value given by `cedar_policy::IntoRestrictedExpression`:
+/// `String` becomes a Cedar string, `i64` a long, `bool` a boolean, `Vec<_>`
+/// a set, and `cedar_policy::IpAddr` and `cedar_policy::Decimal` extension
+/// values. A field of type `Option<_>` is an optional attribute, which is left
+/// out when the field is `None`.
+///
+/// This also implements `IntoRestrictedExpression` for `T`, as a record, so a
+/// struct deriving `IntoContext` can be used as a field of another.
+///
+/// The attribute name of a field can be changed with
+/// `#[cedar(rename = "name")]`. See `cedar_policy::IntoContext` for an
+/// example.
+#[proc_macro_derive(IntoContext, attributes(cedar))]
+pub fn derive_into_context(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    into_context(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn into_context(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = &input.data
+    else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "`IntoContext` can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut names = HashSet::new();
+    let mut pushes = Vec::new();
+    for field in &fields.named {
+        let name = attribute_name(field)?;
+        if !names.insert(name.clone()) {
+            return Err(syn::Error::new_spanned(
+                field,
+                format!("duplicate attribute `{name}`"),
+            ));
+        }
+        let ident = &field.ident;
+        pushes.push(if is_option(&field.ty) {
+            quote! {
+                if let ::std::option::Option::Some(value) = value.#ident {
+                    fields.push((
+                        ::std::string::String::from(#name),
+                        ::cedar_policy::IntoRestrictedExpression::into_restricted_expression(value),
+                    ));
+                }
+            }
+        } else {
+            quote! {
+                fields.push((
+                    ::std::string::String::from(#name),
+                    ::cedar_policy::IntoRestrictedExpression::into_restricted_expression(value.#ident),
+                ));
+            }
+        });
+    }
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let capacity = fields.named.len();
+    Ok(quote! {
+        impl #impl_generics ::cedar_policy::IntoRestrictedExpression for #ty #ty_generics #where_clause {
+            fn into_restricted_expression(self) -> ::cedar_policy::RestrictedExpression {
+                let value = self;
+                let mut fields = ::std::vec::Vec::with_capacity(#capacity);
+                #(#pushes)*
+                // PANIC SAFETY: the derive checks that attribute names are unique
+                #[allow(clippy::expect_used)]
+                ::cedar_policy::RestrictedExpression::new_record(fields)
+                    .expect("attribute names are unique")
+            }
+        }
+
+        impl #impl_generics ::std::convert::TryFrom<#ty #ty_generics> for ::cedar_policy::Context #where_clause {
+            type Error = ::cedar_policy::__private::ContextCreationError;
+
+            fn try_from(value: #ty #ty_generics) -> ::std::result::Result<Self, Self::Error> {
+                let mut fields = ::std::vec::Vec::with_capacity(#capacity);
+                #(#pushes)*
+                ::cedar_policy::Context::from_pairs(fields)
+            }
+        }
+    })
+}
+
+/// The Cedar attribute for `field`: its name, unless it is renamed with
+/// `#[cedar(rename = "...")]`
+fn attribute_name(field: &Field) -> syn::Result<String> {
+    let mut name = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cedar"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported `cedar` attribute, expected `rename`"))
+            }
+        })?;
+    }
+    match (name, &field.ident) {
+        (Some(name), _) => Ok(name),
+        (None, Some(ident)) => Ok(ident.to_string().trim_start_matches("r#").to_string()),
+        (None, None) => Err(syn::Error::new_spanned(field, "expected a named field")),
+    }
+}
+
+/// Whether `ty` is written `Option<_>`, possibly with a path
+fn is_option(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.qself.is_none()
+        && path.path.segments.last().is_some_and(|segment| {
+            segment.ident == "Option"
+                && matches!(&segment.arguments, PathArguments::AngleBracketed(args)
+                    if args.args.len() == 1 && matches!(args.args.first(), Some(GenericArgument::Type(_))))
+        })
+}
@@ -21,6 +21,13 @@ use super::token::WrappedToken;
 pub struct Config {
     pub line_width: usize,
     pub indent_width: isize,
+    /// Whether to indent with spaces or tabs. With tabs, a tab counts as
+    /// `indent_width` columns towards `line_width`.
+    pub indent_style: IndentStyle,
+    /// Whether to sort the policies into a canonical order: by their text with
+    /// comments and formatting removed, i.e., by their annotations, then their
+    /// effect, scope, and conditions. Comments before a policy move with it.
+    pub sort_policies: bool,
 }
 
 impl Default for Config {
@@ -28,10 +35,23 @@ impl Default for Config {
         Self {
             line_width: 80,
             indent_width: 2,
+            indent_style: IndentStyle::default(),
+            sort_policies: false,
         }
     }
 }
 
+/// How the formatter indents lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndentStyle {
+    /// Indent with `indent_width` spaces per level
+    #[default]
+    Spaces,
+    /// Indent with a tab per level. Alignment within a level, e.g., of a
+    /// wrapped set literal, still uses spaces.
+    Tabs,
+}
+
 #[derive(Debug)]
 pub struct Context<'a> {
     pub config: &'a Config,
@@ -109,9 +109,14 @@ impl Doc for Node<Option<Cond>> {
         let rb_comment = get_comment_at_end(self.loc.span, &mut context.tokens)?;
         let cond_comment = get_comment_at_start(cond.cond.loc.span, &mut context.tokens)?;
 
-        let rb_doc = add_comment(RcDoc::text("}"), rb_comment, RcDoc::nil());
+        // The trailing comment of the closing brace is added after the
+        // clause's groups, so that it stays on the closing brace's line
+        // without forcing the clause to wrap
+        let rb_doc =
+            get_leading_comment_doc_from_str(&rb_comment.leading_comment).append(RcDoc::text("}"));
+        let rb_trailing_doc = get_trailing_comment_doc_from_str(&rb_comment.trailing_comment);
         let cond_doc = cond.cond.to_doc(context)?;
-        Some(match cond.expr.as_ref() {
+        let clause_doc = match cond.expr.as_ref() {
             Some(expr) => {
                 let expr_leading_comment =
                     get_leading_comment_at_start(expr.loc.span, &mut context.tokens)?;
@@ -160,7 +165,8 @@ impl Doc for Node<Option<Cond>> {
                     )
                     .group(),
             ),
-        })
+        };
+        Some(clause_doc.append(rb_trailing_doc))
     }
 }
 
@@ -775,16 +781,29 @@ impl Doc for Node<Option<Policy>> {
             get_leading_comment_at_start(policy.effect.loc.span, &mut context.tokens)?;
         let eff_doc = policy.effect.to_doc(context)?;
         let vars = &policy.variables;
+        // A comment anywhere in the scope puts each variable on its own line,
+        // so that trailing comments end the lines of the variables they follow
+        let scope_has_comment = has_comment_in_range(
+            (
+                policy.effect.loc.span.offset() + policy.effect.loc.span.len(),
+                vars.get(2)?.loc.span.offset() + vars.get(2)?.loc.span.len()
+                    - (policy.effect.loc.span.offset() + policy.effect.loc.span.len()),
+            )
+                .into(),
+            &context.tokens,
+        );
+        let lp_comment = get_comment_after_end(policy.effect.loc.span, &mut context.tokens)?;
         let principal_doc = vars.first()?.to_doc(context)?;
         let action_doc = vars.get(1)?.to_doc(context)?;
         let resource_doc = vars.get(2)?.to_doc(context)?;
-        let vars_doc = if vars.get(0..3)?.iter().all(|v| {
-            if let Some(v) = v.as_inner() {
-                v.ineq.is_none()
-            } else {
-                false
-            }
-        }) {
+        let vars_doc = if !scope_has_comment
+            && vars.get(0..3)?.iter().all(|v| {
+                if let Some(v) = v.as_inner() {
+                    v.ineq.is_none()
+                } else {
+                    false
+                }
+            }) {
             principal_doc
                 .append(add_comment(
                     RcDoc::text(","),
@@ -829,12 +848,14 @@ impl Doc for Node<Option<Policy>> {
                     get_leading_comment_doc_from_str(&eff_leading_comment).append(
                         eff_doc
                             .append(RcDoc::line())
-                            .append(add_comment(
-                                RcDoc::text("("),
-                                get_comment_after_end(policy.effect.loc.span, &mut context.tokens)?,
-                                RcDoc::nil(),
+                            .append(get_leading_comment_doc_from_str(
+                                &lp_comment.leading_comment,
                             ))
-                            .group(),
+                            .append(RcDoc::text("("))
+                            .group()
+                            .append(get_trailing_comment_doc_from_str(
+                                &lp_comment.trailing_comment,
+                            )),
                     ),
                 )
                 .append(vars_doc)
@@ -14,18 +14,20 @@
  * limitations under the License.
  */
 
+use itertools::Itertools;
 use miette::{miette, Result, WrapErr};
 
 use cedar_policy_core::ast::{PolicySet, Template};
 use cedar_policy_core::parser::parse_policyset;
+use cedar_policy_core::parser::Loc;
 use cedar_policy_core::parser::{err::ParseErrors, text_to_cst::parse_policies};
 
 use crate::token::get_comment;
 
 use super::lexer::get_token_stream;
-use super::utils::remove_empty_lines;
+use super::utils::{indent_with_tabs, remove_empty_lines};
 
-use super::config::{self, Config};
+use super::config::{self, Config, IndentStyle};
 use super::doc::*;
 
 fn tree_to_pretty<T: Doc>(t: &T, context: &mut config::Context<'_>) -> Result<String> {
@@ -39,18 +41,26 @@ fn tree_to_pretty<T: Doc>(t: &T, context: &mut config::Context<'_>) -> Result<St
         .map_err(|err| miette!(format!("failed to convert rendered doc to string: {err}")))
 }
 
-fn soundness_check(ps: &str, ast: &PolicySet) -> Result<()> {
+/// The templates of `ast`, which includes its static policies, in the order
+/// they appear in the source text
+fn templates_in_source_order(ast: &PolicySet) -> Vec<&Template> {
+    ast.all_templates()
+        .sorted_by_key(|t| t.loc().as_ref().map(Loc::start))
+        .collect()
+}
+
+/// Check that the formatted policies `ps` have the same meaning and annotations
+/// as `policies`, which are the original policies in the order they were
+/// formatted.
+fn soundness_check(ps: &str, policies: &[&Template]) -> Result<()> {
     let formatted_ast = parse_policyset(ps).wrap_err("formatter produces invalid policies")?;
-    let (formatted_policies, policies) = (
-        formatted_ast.templates().collect::<Vec<&Template>>(),
-        ast.templates().collect::<Vec<&Template>>(),
-    );
+    let formatted_policies = templates_in_source_order(&formatted_ast);
 
     if formatted_policies.len() != policies.len() {
         return Err(miette!("missing formatted policies"));
     }
 
-    for (f_p, p) in formatted_policies.into_iter().zip(policies.into_iter()) {
+    for (f_p, p) in formatted_policies.into_iter().zip(policies.iter()) {
         let (f_anno, anno) = (
             f_p.annotations()
                 .map(|(k, v)| (k, &v.val))
@@ -100,40 +110,64 @@ pub fn policies_str_to_pretty(ps: &str, config: &Config) -> Result<String> {
         .0
         .iter()
         .map(|p| Ok(remove_empty_lines(tree_to_pretty(p, &mut context)?.trim())))
-        .collect::<Result<Vec<String>>>()?
-        .join("\n\n");
-    // handle comment at the end of a policyset
+        .collect::<Result<Vec<String>>>()?;
+    // handle comment at the end of a policyset. A comment on the same line as
+    // the end of the last policy stays with that policy.
     let (trailing_comment, end_comment) = match end_comment_str.split_once('\n') {
         Some((f, r)) => (get_comment(f), get_comment(r)),
         None => (get_comment(end_comment_str), String::new()),
     };
-    match (trailing_comment.as_ref(), end_comment.as_ref()) {
-        ("", "") => {}
-        (_, "") => {
-            formatted_policies.push(' ');
-            formatted_policies.push_str(&trailing_comment);
-        }
-        ("", _) => {
-            formatted_policies.push('\n');
-            formatted_policies.push_str(&end_comment);
+    if let Some(last_policy) = formatted_policies.last_mut() {
+        if !trailing_comment.is_empty() {
+            last_policy.push(' ');
+            last_policy.push_str(trailing_comment.trim_end());
         }
-        _ => {
-            formatted_policies.push(' ');
-            formatted_policies.push_str(&trailing_comment);
-            formatted_policies.push_str(&end_comment);
-        }
-    };
+    }
+    let policies = templates_in_source_order(&ast);
+    if policies.len() != formatted_policies.len() {
+        return Err(miette!("missing formatted policies"));
+    }
+    let mut policies = policies
+        .into_iter()
+        .zip(formatted_policies)
+        .collect::<Vec<_>>();
+    if config.sort_policies {
+        policies.sort_by_cached_key(|(p, _)| p.to_string());
+    }
+    let (policies, formatted_policies): (Vec<_>, Vec<_>) = policies.into_iter().unzip();
+    let mut formatted_policies = formatted_policies.join("\n\n");
+    if !end_comment.is_empty() {
+        formatted_policies.push('\n');
+        formatted_policies.push_str(&end_comment);
+    }
+    // the comments end with a newline, which would otherwise add an empty line
+    // to the end of the output
+    formatted_policies.truncate(formatted_policies.trim_end().len());
+    if config.indent_style == IndentStyle::Tabs {
+        formatted_policies = indent_with_tabs(&formatted_policies, config.indent_width);
+    }
     // add soundness check to make sure formatting doesn't alter policy ASTs
-    soundness_check(&formatted_policies, &ast)?;
+    soundness_check(&formatted_policies, &policies)?;
     Ok(formatted_policies)
 }
 
+/// Whether `ps` is already formatted according to `config`, i.e., formatting
+/// it with [`policies_str_to_pretty`] would not change it, up to a single
+/// trailing newline. This is `false` if `ps` cannot be formatted, e.g., because
+/// it does not parse.
+pub fn is_formatted(ps: &str, config: &Config) -> bool {
+    policies_str_to_pretty(ps, config)
+        .is_ok_and(|formatted| ps.strip_suffix('\n').unwrap_or(ps) == formatted)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     const TEST_CONFIG: &Config = &Config {
         line_width: 40,
         indent_width: 2,
+        indent_style: IndentStyle::Spaces,
+        sort_policies: false,
     };
 
     #[test]
@@ -148,6 +182,135 @@ mod tests {
         assert_eq!(policies_str_to_pretty(policy, TEST_CONFIG).unwrap(), policy);
     }
 
+    #[test]
+    fn formatted_check() {
+        let policy = "permit (principal, action, resource);";
+        assert!(is_formatted(policy, TEST_CONFIG));
+        assert!(is_formatted(&format!("{policy}\n"), TEST_CONFIG));
+        assert!(!is_formatted(&format!("{policy}\n\n"), TEST_CONFIG));
+        assert!(!is_formatted(
+            "permit(principal,action,resource);",
+            TEST_CONFIG
+        ));
+        assert!(!is_formatted("permit(principal", TEST_CONFIG));
+    }
+
+    #[test]
+    fn trailing_comments_stay_on_their_line() {
+        // a trailing comment does not force a short clause to wrap
+        let policy = r#"permit (principal, action, resource)
+when { context.a } // short
+unless { context.b }; // end"#;
+        assert_eq!(policies_str_to_pretty(policy, TEST_CONFIG).unwrap(), policy);
+
+        // a trailing comment on a wrapped clause stays on the closing brace
+        assert_eq!(
+            policies_str_to_pretty(
+                r#"permit (principal, action, resource)
+when { principal.age > 18 && resource.is_public } // wrapped
+;"#,
+                TEST_CONFIG
+            )
+            .unwrap(),
+            r#"permit (principal, action, resource)
+when
+{
+  principal.age > 18 &&
+  resource.is_public
+} // wrapped
+;"#
+        );
+    }
+
+    #[test]
+    fn configured_width_and_indentation() {
+        let policy = r#"permit (principal, action in [Action::"view", Action::"edit"], resource)
+when { principal.age > 18 && resource.tags.contains("vacation") };"#;
+        let wide = Config {
+            line_width: 100,
+            ..Config::default()
+        };
+        assert_eq!(
+            policies_str_to_pretty(policy, &wide).unwrap(),
+            r#"permit (
+  principal,
+  action in [Action::"view", Action::"edit"],
+  resource
+)
+when { principal.age > 18 && resource.tags.contains("vacation") };"#
+        );
+
+        let narrow = Config {
+            line_width: 30,
+            indent_width: 4,
+            indent_style: IndentStyle::Spaces,
+            sort_policies: false,
+        };
+        let narrow_formatted = r#"permit (
+    principal,
+    action in
+        [Action::"view",
+         Action::"edit"],
+    resource
+)
+when
+{
+    principal.age > 18 &&
+    resource.tags
+        .contains
+        (
+            "vacation"
+        )
+};"#;
+        assert_eq!(
+            policies_str_to_pretty(policy, &narrow).unwrap(),
+            narrow_formatted
+        );
+
+        // with tabs, the layout is the same as with `indent_width` spaces
+        let tabs = Config {
+            indent_style: IndentStyle::Tabs,
+            ..narrow
+        };
+        assert_eq!(
+            policies_str_to_pretty(policy, &tabs).unwrap(),
+            narrow_formatted
+                .replace("        ", "\t\t")
+                .replace("    ", "\t")
+        );
+    }
+
+    #[test]
+    fn sort_policies() {
+        let policies = r#"// only admins may delete
+@id("b")
+forbid (principal, action, resource)
+unless { principal.is_admin };
+
+@id("a")
+permit (principal, action, resource); // everyone
+// end of file"#;
+        let sorted = Config {
+            sort_policies: true,
+            ..Config::default()
+        };
+        let sorted_policies = r#"@id("a")
+permit (principal, action, resource); // everyone
+
+// only admins may delete
+@id("b")
+forbid (principal, action, resource)
+unless { principal.is_admin };
+// end of file"#;
+        assert_eq!(
+            policies_str_to_pretty(policies, &sorted).unwrap(),
+            sorted_policies
+        );
+        assert!(is_formatted(sorted_policies, &sorted));
+        assert!(!is_formatted(policies, &sorted));
+        assert!(is_formatted(policies, &Config::default()));
+    }
+
     #[test]
     fn action_in_set() {
         let policy = r#"permit (
@@ -172,15 +335,13 @@ mod tests {
         use std::fs::read_to_string;
         use std::path::Path;
 
-        let config = Config {
-            line_width: 80,
-            indent_width: 2,
-        };
+        let config = Config::default();
         let dir_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
         let pairs = vec![
             ("test.cedar", "test_formatted.cedar"),
             ("policies.cedar", "policies_formatted.cedar"),
             ("is_policies.cedar", "is_policies_formatted.cedar"),
+            ("comments.cedar", "comments_formatted.cedar"),
         ];
         for (pf, ef) in pairs {
             // editors or cargo run try to append a newline at the end of files
@@ -112,6 +112,16 @@ pub fn get_comment_in_range(span: miette::SourceSpan, tokens: &mut [WrappedToken
         .collect()
 }
 
+/// Whether any token starting in `span` has a leading or a trailing comment,
+/// without consuming the comments
+pub fn has_comment_in_range(span: miette::SourceSpan, tokens: &[WrappedToken]) -> bool {
+    tokens
+        .iter()
+        .skip_while(|t| t.span.start < span.offset())
+        .take_while(|t| t.span.start < span.offset() + span.len())
+        .any(|t| !t.comment.leading_comment.is_empty() || !t.comment.trailing_comment.is_empty())
+}
+
 // Wrap doc with comment
 pub fn add_comment<'a>(d: RcDoc<'a>, comment: Comment, next_doc: RcDoc<'a>) -> RcDoc<'a> {
     let leading_comment = comment.leading_comment;
@@ -128,6 +138,29 @@ pub fn add_comment<'a>(d: RcDoc<'a>, comment: Comment, next_doc: RcDoc<'a>) -> R
     leading_comment_doc.append(trailing_comment_doc.clone())
 }
 
+/// Replace the indentation of each line of `s`, made of `indent_width` spaces
+/// per level, with a tab per level. Spaces left over are kept after the tabs.
+pub fn indent_with_tabs(s: &str, indent_width: isize) -> String {
+    let Ok(indent_width) = usize::try_from(indent_width) else {
+        return s.to_owned();
+    };
+    if indent_width == 0 {
+        return s.to_owned();
+    }
+    s.lines()
+        .map(|line| {
+            let content = line.trim_start_matches(' ');
+            let indent = line.len() - content.len();
+            format!(
+                "{}{}{content}",
+                "\t".repeat(indent / indent_width),
+                " ".repeat(indent % indent_width)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
 pub fn remove_empty_lines(s: &str) -> String {
     s.lines()
         .filter(|ss| !ss.trim().is_empty())
@@ -0,0 +1,184 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module finds the attributes of each entity type which the policies in
+//! a policy set may access.
+
+use std::collections::{HashMap, HashSet};
+
+use cedar_policy_core::ast::{ExprKind, Name, PolicySet};
+use smol_str::SmolStr;
+
+use crate::{
+    typecheck::Typechecker,
+    types::{EntityRecordKind, Type},
+    ValidationMode, Validator,
+};
+
+impl Validator {
+    /// Find the attributes of each entity type which the policies and
+    /// templates in `policies` may read, either with `.` or `[]` or by testing
+    /// for them with `has`. Entity types with no accessed attributes are not
+    /// included.
+    ///
+    /// The entity type of the entity an attribute is accessed on is the type
+    /// inferred by the typechecker. When that is ambiguous, e.g., for an
+    /// expression of an arbitrary entity type or one which fails to typecheck,
+    /// the attribute is included for every entity type which may have it.
+    pub fn accessed_attributes(&self, policies: &PolicySet) -> HashMap<Name, HashSet<SmolStr>> {
+        let typechecker = Typechecker::new(&self.schema, ValidationMode::Permissive);
+        let mut accessed: HashMap<Name, HashSet<SmolStr>> = HashMap::new();
+        for template in policies.all_templates() {
+            for condition in typechecker.typed_conditions_by_request_env(template) {
+                for expr in condition.subexpressions() {
+                    let targets = match expr.expr_kind() {
+                        ExprKind::GetAttr { expr, attr } => vec![(expr.data().clone(), attr)],
+                        ExprKind::HasAttr { expr, attr, path } => Type::attribute_path_targets(
+                            &self.schema,
+                            expr.data().as_ref(),
+                            std::iter::once(attr).chain(path),
+                        ),
+                        _ => continue,
+                    };
+                    for (ty, attr) in targets {
+                        for entity_type in self.entity_types_of(ty.as_ref(), attr) {
+                            accessed
+                                .entry(entity_type)
+                                .or_default()
+                                .insert(attr.clone());
+                        }
+                    }
+                }
+            }
+        }
+        accessed
+    }
+
+    /// The entity types an expression of type `ty` may have, when accessing
+    /// the attribute `attr` on it
+    fn entity_types_of(&self, ty: Option<&Type>, attr: &str) -> Vec<Name> {
+        match ty {
+            Some(Type::EntityOrRecord(EntityRecordKind::Entity(lub))) => {
+                lub.iter().cloned().collect()
+            }
+            Some(Type::EntityOrRecord(EntityRecordKind::ActionEntity { name, .. })) => {
+                vec![name.clone()]
+            }
+            Some(Type::EntityOrRecord(EntityRecordKind::Record { .. })) => Vec::new(),
+            // The entity type is ambiguous, so be conservative
+            _ => self
+                .schema
+                .entity_types()
+                .filter(|(_, ety)| ety.has_open_attributes() || ety.attr(attr).is_some())
+                .map(|(name, _)| name.clone())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cedar_policy_core::{extensions::Extensions, parser::parse_policyset};
+
+    use super::*;
+    use crate::ValidatorSchema;
+
+    fn accessed_attributes(policies: &str) -> Vec<(String, Vec<String>)> {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity User = { age: Long, name: String, manager?: User };
+            entity Admin = { name: String, level: Long };
+            entity Photo = { owner: User, tags: Set<String>, location: { city: String } };
+            action view appliesTo { principal: [User, Admin], resource: Photo };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let policies = parse_policyset(policies).unwrap();
+        let mut accessed = Validator::new(schema)
+            .accessed_attributes(&policies)
+            .into_iter()
+            .map(|(name, attrs)| {
+                let mut attrs = attrs.into_iter().map(String::from).collect::<Vec<_>>();
+                attrs.sort();
+                (name.to_string(), attrs)
+            })
+            .collect::<Vec<_>>();
+        accessed.sort();
+        accessed
+    }
+
+    #[test]
+    fn attributes_by_entity_type() {
+        assert_eq!(
+            accessed_attributes(
+                r#"permit(principal, action, resource) when {
+                    resource.owner.age > 18 && resource.location.city == "Seattle" && principal.name != ""
+                };"#
+            ),
+            vec![
+                ("Admin".to_string(), vec!["name".to_string()]),
+                (
+                    "Photo".to_string(),
+                    vec!["location".to_string(), "owner".to_string()]
+                ),
+                (
+                    "User".to_string(),
+                    vec!["age".to_string(), "name".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn has_and_untyped_expressions() {
+        assert_eq!(
+            accessed_attributes(
+                r#"permit(principal == User::"alice", action, resource) when {
+                    principal has manager
+                };
+                permit(principal, action, resource) when {
+                    principal.missing.level > 0
+                };"#
+            ),
+            vec![
+                (
+                    "Admin".to_string(),
+                    vec!["level".to_string(), "missing".to_string()]
+                ),
+                (
+                    "User".to_string(),
+                    vec!["manager".to_string(), "missing".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn has_attribute_path() {
+        assert_eq!(
+            accessed_attributes(
+                r#"permit(principal, action, resource) when {
+                    resource has owner.manager
+                };"#
+            ),
+            vec![
+                ("Photo".to_string(), vec!["owner".to_string()]),
+                ("User".to_string(), vec!["manager".to_string()]),
+            ]
+        );
+    }
+}
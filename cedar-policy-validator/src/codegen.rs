@@ -0,0 +1,529 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module generates Rust types for the entity types and action contexts
+//! declared in a [`ValidatorSchema`], so that a Rust program can keep its
+//! types in sync with its schema, e.g., from a build script.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use cedar_policy_core::ast::{EntityType, Name};
+use itertools::Itertools;
+use smol_str::SmolStr;
+
+use crate::types::{AttributeType, EntityRecordKind, OpenTag, Primitive, Type};
+use crate::ValidatorSchema;
+
+/// Generate the source of Rust types for the entity types and the contexts of
+/// the actions declared in `schema`, for instance to `include!` from a build
+/// script's output.
+///
+/// Every entity type `NS::User` becomes a struct `User` in the module `NS`,
+/// with the entity's `id`, its `parents`, and its `attributes`, in a struct
+/// `UserAttributes`. It serializes to the JSON format of an entity. The
+/// context of every action `NS::Action::"view"` becomes a struct
+/// `ViewContext` in the module `NS`, which serializes to the JSON format of a
+/// context. Record types become structs, sets become `Vec`s, optional
+/// attributes become `Option`s which are left out when `None`, and entity
+/// references become the generated `EntityUid`. The generated `IpAddr` and
+/// `Decimal` hold extension values by their string representation.
+///
+/// The generated code uses `serde`, with the `derive` feature, and, for
+/// records and entity types with additional attributes and for values whose
+/// type the schema does not give, `serde_json`.
+pub fn rust_types(schema: &ValidatorSchema) -> String {
+    let mut generator = Generator::default();
+    // Entity types get their own names before any generated struct can take
+    // them
+    let entity_types = schema
+        .entity_types()
+        .sorted_by_key(|(name, _)| name.to_string())
+        .map(|(name, ety)| {
+            let struct_name = generator
+                .fresh_type_name(&module_path(name), &rust_ident(name.basename().as_ref()));
+            (name, ety, struct_name)
+        })
+        .collect_vec();
+    for (name, ety, struct_name) in entity_types {
+        // Derived attributes are computed, so entity data doesn't have them
+        generator.entity_type(
+            name,
+            &struct_name,
+            ety.attributes()
+                .filter(|(attr, _)| !ety.is_derived_attr(attr)),
+            ety.open_attributes,
+        );
+    }
+    for action in schema
+        .action_ids()
+        .sorted_by_key(|action| action.name().to_string())
+    {
+        let name = action.name();
+        let EntityType::Specified(action_type) = name.entity_type() else {
+            continue;
+        };
+        if let Type::EntityOrRecord(EntityRecordKind::Record {
+            attrs,
+            open_attributes,
+        }) = action.context_type()
+        {
+            let path = module_path(action_type);
+            let struct_name = generator.fresh_type_name(
+                &path,
+                &format!("{}Context", camel_case(name.eid().as_ref())),
+            );
+            generator.record_struct(
+                &path,
+                &struct_name,
+                &format!("The context of action `{name}`."),
+                attrs.iter(),
+                open_attributes,
+            );
+        }
+    }
+
+    let mut out = String::from(PRELUDE);
+    generator.root.write(&mut out, 0);
+    out
+}
+
+/// The types used by all the generated types, at the root of the output
+const PRELUDE: &str = r#"// Generated from a Cedar schema by `cedar_policy_validator::codegen::rust_types`.
+
+/// A reference to an entity, which serializes with the `__entity` escape.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntityUid {
+    /// The entity type, e.g., `"MyApp::User"`
+    pub type_name: String,
+    /// The entity id
+    pub id: String,
+}
+
+impl ::serde::Serialize for EntityUid {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        use ::serde::ser::SerializeMap;
+        let mut escape = serializer.serialize_map(Some(1))?;
+        escape.serialize_entry(
+            "__entity",
+            &::std::collections::BTreeMap::from([("type", &self.type_name), ("id", &self.id)]),
+        )?;
+        escape.end()
+    }
+}
+
+/// An `ipaddr` value, e.g., `"10.0.0.1"` or `"10.0.0.0/8"`, which serializes
+/// with the `__extn` escape.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IpAddr(pub String);
+
+impl ::serde::Serialize for IpAddr {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        use ::serde::ser::SerializeMap;
+        let mut escape = serializer.serialize_map(Some(1))?;
+        escape.serialize_entry(
+            "__extn",
+            &::std::collections::BTreeMap::from([("fn", "ip"), ("arg", self.0.as_str())]),
+        )?;
+        escape.end()
+    }
+}
+
+/// A `decimal` value, e.g., `"1.25"`, which serializes with the `__extn`
+/// escape.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Decimal(pub String);
+
+impl ::serde::Serialize for Decimal {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        use ::serde::ser::SerializeMap;
+        let mut escape = serializer.serialize_map(Some(1))?;
+        escape.serialize_entry(
+            "__extn",
+            &::std::collections::BTreeMap::from([("fn", "decimal"), ("arg", self.0.as_str())]),
+        )?;
+        escape.end()
+    }
+}
+"#;
+
+/// The generated items of the module for a namespace
+#[derive(Debug, Default)]
+struct Module {
+    items: Vec<String>,
+    /// The type names already used in this module
+    type_names: HashSet<String>,
+    /// The modules for the namespaces nested in this one
+    children: BTreeMap<String, Module>,
+}
+
+impl Module {
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "    ".repeat(depth);
+        for item in &self.items {
+            out.push('\n');
+            for line in item.lines() {
+                if line.is_empty() {
+                    out.push('\n');
+                } else {
+                    // PANIC SAFETY: writing to a `String` never fails
+                    #[allow(clippy::unwrap_used)]
+                    writeln!(out, "{indent}{line}").unwrap();
+                }
+            }
+        }
+        for (name, child) in &self.children {
+            // PANIC SAFETY: writing to a `String` never fails
+            #[allow(clippy::unwrap_used)]
+            {
+                writeln!(out, "\n{indent}#[allow(non_snake_case)]").unwrap();
+                writeln!(out, "{indent}pub mod {name} {{").unwrap();
+                child.write(out, depth + 1);
+                writeln!(out, "{indent}}}").unwrap();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Generator {
+    root: Module,
+}
+
+impl Generator {
+    fn module(&mut self, path: &[String]) -> &mut Module {
+        path.iter().fold(&mut self.root, |module, name| {
+            module.children.entry(name.clone()).or_default()
+        })
+    }
+
+    /// `name`, or `name` with a numeric suffix if it is already used in the
+    /// module at `path`
+    fn fresh_type_name(&mut self, path: &[String], name: &str) -> String {
+        let module = self.module(path);
+        let name = (1..)
+            .map(|i| {
+                if i == 1 {
+                    name.to_string()
+                } else {
+                    format!("{name}{i}")
+                }
+            })
+            .find(|name| !module.type_names.contains(name))
+            .unwrap_or_default();
+        module.type_names.insert(name.clone());
+        name
+    }
+
+    fn entity_type<'a>(
+        &mut self,
+        name: &Name,
+        struct_name: &str,
+        attrs: impl Iterator<Item = (&'a SmolStr, &'a AttributeType)>,
+        open_attributes: OpenTag,
+    ) {
+        let path = module_path(name);
+        let root = "super::".repeat(path.len());
+        let attrs_name = self.fresh_type_name(
+            &path,
+            &format!("{}Attributes", struct_name.trim_start_matches("r#")),
+        );
+        self.record_struct(
+            &path,
+            &attrs_name,
+            &format!("The attributes of an entity of type `{name}`."),
+            attrs,
+            open_attributes,
+        );
+        let item = format!(
+            r#"/// An entity of type `{name}`, which serializes to the JSON format of an
+/// entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct {struct_name} {{
+    /// The entity id
+    pub id: String,
+    /// The direct parents of the entity
+    pub parents: Vec<{root}EntityUid>,
+    /// The attributes of the entity
+    pub attributes: {attrs_name},
+}}
+
+impl {struct_name} {{
+    /// The name of the entity type
+    pub const TYPE_NAME: &'static str = "{name}";
+
+    /// The uid of this entity
+    pub fn uid(&self) -> {root}EntityUid {{
+        {root}EntityUid {{
+            type_name: Self::TYPE_NAME.to_string(),
+            id: self.id.clone(),
+        }}
+    }}
+}}
+
+impl ::serde::Serialize for {struct_name} {{
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {{
+        use ::serde::ser::SerializeStruct;
+        let mut entity = serializer.serialize_struct("{struct_name}", 3)?;
+        entity.serialize_field("uid", &self.uid())?;
+        entity.serialize_field("attrs", &self.attributes)?;
+        entity.serialize_field("parents", &self.parents)?;
+        entity.end()
+    }}
+}}
+"#
+        );
+        self.module(&path).items.push(item);
+    }
+
+    /// Generate a struct named `struct_name`, which must be fresh, in the
+    /// module at `path`, with a field for each of `attrs`
+    fn record_struct<'a>(
+        &mut self,
+        path: &[String],
+        struct_name: &str,
+        doc: &str,
+        attrs: impl Iterator<Item = (&'a SmolStr, &'a AttributeType)>,
+        open_attributes: OpenTag,
+    ) {
+        let mut item = format!(
+            "/// {doc}\n#[derive(Debug, Clone, PartialEq, ::serde::Serialize)]\npub struct {struct_name} {{\n"
+        );
+        let mut field_names = HashSet::new();
+        for (attr, attr_ty) in attrs {
+            let field = (1..)
+                .map(|i| {
+                    let field = rust_ident(attr);
+                    if i == 1 {
+                        field
+                    } else {
+                        format!("{}{i}", field.trim_start_matches("r#"))
+                    }
+                })
+                .find(|field| !field_names.contains(field))
+                .unwrap_or_default();
+            field_names.insert(field.clone());
+            let ty = self.rust_type(
+                path,
+                &attr_ty.attr_type,
+                &format!("{struct_name}{}", camel_case(attr)),
+            );
+            let attr = attr.escape_debug();
+            // PANIC SAFETY: writing to a `String` never fails
+            #[allow(clippy::unwrap_used)]
+            if attr_ty.is_required {
+                writeln!(
+                    item,
+                    "    #[serde(rename = \"{attr}\")]\n    pub {field}: {ty},"
+                )
+                .unwrap();
+            } else {
+                writeln!(item, "    #[serde(rename = \"{attr}\", skip_serializing_if = \"Option::is_none\")]\n    pub {field}: Option<{ty}>,").unwrap();
+            }
+        }
+        if open_attributes == OpenTag::OpenAttributes {
+            item.push_str("    /// Attributes which the schema does not declare\n    #[serde(flatten)]\n    pub additional_attributes: ::std::collections::BTreeMap<String, ::serde_json::Value>,\n");
+        }
+        item.push_str("}\n");
+        self.module(path).items.push(item);
+    }
+
+    /// The Rust type for `ty`, generating a struct for a record type, named
+    /// after `hint`, in the module at `path`
+    fn rust_type(&mut self, path: &[String], ty: &Type, hint: &str) -> String {
+        let root = "super::".repeat(path.len());
+        match ty {
+            Type::True
+            | Type::False
+            | Type::Primitive {
+                primitive_type: Primitive::Bool,
+            } => "bool".to_string(),
+            Type::Primitive {
+                primitive_type: Primitive::Long,
+            } => "i64".to_string(),
+            Type::Primitive {
+                primitive_type: Primitive::String,
+            } => "String".to_string(),
+            Type::Set {
+                element_type: Some(element_type),
+            } => format!("Vec<{}>", self.rust_type(path, element_type, hint)),
+            Type::EntityOrRecord(EntityRecordKind::Entity(_) | EntityRecordKind::AnyEntity) => {
+                format!("{root}EntityUid")
+            }
+            Type::EntityOrRecord(EntityRecordKind::Record {
+                attrs,
+                open_attributes,
+            }) => {
+                let struct_name = self.fresh_type_name(path, hint);
+                self.record_struct(
+                    path,
+                    &struct_name,
+                    "A record in the schema.",
+                    attrs.iter(),
+                    *open_attributes,
+                );
+                struct_name
+            }
+            Type::ExtensionType { name } if name.to_string() == "ipaddr" => {
+                format!("{root}IpAddr")
+            }
+            Type::ExtensionType { name } if name.to_string() == "decimal" => {
+                format!("{root}Decimal")
+            }
+            _ => "::serde_json::Value".to_string(),
+        }
+    }
+}
+
+/// The path of the module for the namespace of `name`
+fn module_path(name: &Name) -> Vec<String> {
+    name.namespace_components()
+        .map(|id| rust_ident(id.as_ref()))
+        .collect()
+}
+
+/// Rust keywords, which must be written as raw identifiers
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// A Rust identifier for `s`, replacing characters which can't appear in
+/// identifiers with `_`
+fn rust_ident(s: &str) -> String {
+    let mut ident: String = s
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("r#{ident}")
+    } else if matches!(ident.as_str(), "_" | "self" | "Self" | "super" | "crate") {
+        format!("{ident}_")
+    } else {
+        ident
+    }
+}
+
+/// A `CamelCase` Rust identifier for `s`, e.g., `ViewPhoto` for `"view photo"`
+fn camel_case(s: &str) -> String {
+    let ident: String = s
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("T{ident}")
+    } else {
+        ident
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cedar_policy_core::extensions::Extensions;
+
+    use super::*;
+
+    fn rust_types_for(src: &str) -> String {
+        let (schema, _) =
+            ValidatorSchema::from_str_natural(src, Extensions::all_available()).unwrap();
+        rust_types(&schema)
+    }
+
+    #[test]
+    fn entity_types() {
+        let out = rust_types_for(
+            r#"
+            namespace MyApp {
+                type Address = { street: String, zip?: Long };
+                entity Group;
+                entity User in [Group] = {
+                    name: String,
+                    "display-name"?: String,
+                    address: Address,
+                    groups: Set<Group>,
+                    ip: ipaddr,
+                    score: decimal,
+                };
+            }
+            entity match;
+            "#,
+        );
+        assert!(out.contains("pub struct EntityUid {"));
+        assert!(out.contains("#[allow(non_snake_case)]\npub mod MyApp {"));
+        assert!(out.contains("    pub struct User {\n"));
+        assert!(out.contains("        pub parents: Vec<super::EntityUid>,\n"));
+        assert!(out.contains("        pub attributes: UserAttributes,\n"));
+        assert!(out.contains(r#"        pub const TYPE_NAME: &'static str = "MyApp::User";"#));
+        assert!(out.contains("        #[serde(rename = \"name\")]\n        pub name: String,\n"));
+        assert!(out.contains("        #[serde(rename = \"display-name\", skip_serializing_if = \"Option::is_none\")]\n        pub display_name: Option<String>,\n"));
+        assert!(out.contains("        pub address: UserAttributesAddress,\n"));
+        assert!(out.contains("    pub struct UserAttributesAddress {\n"));
+        assert!(out.contains("        pub zip: Option<i64>,\n"));
+        assert!(out.contains("        pub groups: Vec<super::EntityUid>,\n"));
+        assert!(out.contains("        pub ip: super::IpAddr,\n"));
+        assert!(out.contains("        pub score: super::Decimal,\n"));
+        // Keywords become raw identifiers
+        assert!(out.contains("\npub struct r#match {\n"));
+        assert!(out.contains("    pub attributes: matchAttributes,\n"));
+    }
+
+    #[test]
+    fn action_contexts() {
+        let out = rust_types_for(
+            r#"
+            entity User;
+            action "view photo" appliesTo {
+                principal: User,
+                resource: User,
+                context: { mfa: Bool, "source-ip"?: ipaddr },
+            };
+            action edit appliesTo { principal: User, resource: User };
+            "#,
+        );
+        assert!(out.contains("/// The context of action `Action::\"view photo\"`.\n#[derive(Debug, Clone, PartialEq, ::serde::Serialize)]\npub struct ViewPhotoContext {\n"));
+        assert!(out.contains("    pub mfa: bool,\n"));
+        assert!(out.contains("    pub source_ip: Option<IpAddr>,\n"));
+        assert!(out.contains("pub struct EditContext {\n}\n"));
+    }
+
+    #[test]
+    fn name_collisions() {
+        let out = rust_types_for(
+            r#"
+            entity UserAttributes;
+            entity User = { "a-b": Long, a_b: Long };
+            "#,
+        );
+        assert!(out.contains("pub struct UserAttributes {\n"));
+        assert!(out.contains("pub struct UserAttributes2 {\n"));
+        assert!(out.contains("    pub attributes: UserAttributes2,\n"));
+        assert!(out.contains("    pub a_b: i64,\n"));
+        assert!(out.contains("    pub a_b2: i64,\n"));
+    }
+}
@@ -0,0 +1,69 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Contains the check enabled by [`Validator::with_closed_context`], which
+//! treats the `context` of every action as a closed record.
+
+use cedar_policy_core::ast::{ExprKind, Template, Var};
+use itertools::Itertools;
+
+use crate::{
+    fuzzy_match::fuzzy_search, validation_result::ValidationErrorKind, ValidationError, Validator,
+};
+
+impl Validator {
+    /// Generate an `UndeclaredContextAttribute` error for every access to
+    /// (with `.` or `has`) a top-level attribute of `context` in the
+    /// conditions of the policy, for every action the policy applies to whose
+    /// context type does not declare that attribute.
+    pub(crate) fn validate_closed_context<'a>(
+        &'a self,
+        template: &'a Template,
+    ) -> impl Iterator<Item = ValidationError> + 'a {
+        let actions = self
+            .get_actions_satisfying_constraint(template.action_constraint())
+            .filter_map(|action| Some((action, self.schema.context_type(action)?)))
+            .sorted_by_key(|(action, _)| action.to_string())
+            .collect::<Vec<_>>();
+        let mut errors = Vec::new();
+        for e in template.non_scope_constraints().subexpressions() {
+            let attr = match e.expr_kind() {
+                ExprKind::GetAttr { expr, attr } | ExprKind::HasAttr { expr, attr, .. }
+                    if matches!(expr.expr_kind(), ExprKind::Var(Var::Context)) =>
+                {
+                    attr
+                }
+                _ => continue,
+            };
+            for (action, context_type) in &actions {
+                let declared = context_type.all_attributes(&self.schema);
+                if !declared.contains(attr) {
+                    errors.push(ValidationError::with_policy_id(
+                        template.id().clone(),
+                        e.source_loc().cloned(),
+                        ValidationErrorKind::undeclared_context_attribute(
+                            action.to_string(),
+                            attr.to_string(),
+                            context_type.display_cedarschema(),
+                            fuzzy_search(attr, &declared),
+                        ),
+                    ));
+                }
+            }
+        }
+        errors.into_iter()
+    }
+}
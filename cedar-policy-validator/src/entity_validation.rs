@@ -0,0 +1,263 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Contains the validation of large sets of entities against the schema,
+//! counting every violation but keeping only a sample of the detailed errors.
+
+use std::collections::{BTreeMap, HashMap};
+
+use cedar_policy_core::{
+    ast::EntityType,
+    entities::{
+        conformance::{err::EntitySchemaConformanceError, EntitySchemaConformanceChecker},
+        Entities,
+    },
+    extensions::Extensions,
+};
+
+use crate::{CoreSchema, Validator};
+
+/// The kind of a violation of the schema by an entity, as counted in an
+/// [`EntityValidationReport`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EntityViolationKind {
+    /// The entity has an attribute which its entity type does not declare
+    UnexpectedAttr,
+    /// The entity is missing a required attribute
+    MissingRequiredAttr,
+    /// The entity gives a value to a derived attribute
+    DerivedAttr,
+    /// The entity has more than one of a group of mutually exclusive attributes
+    MutuallyExclusiveAttrs,
+    /// An attribute of the entity has the wrong type
+    TypeMismatch,
+    /// An attribute of the entity is a set whose elements have different types
+    HeterogeneousSet,
+    /// The entity has an ancestor of a type it can't be a member of
+    InvalidAncestorType,
+    /// The entity has a type which is not declared in the schema
+    UnexpectedEntityType,
+    /// The entity is an action which is not declared in the schema
+    UndeclaredAction,
+    /// The entity is an action which differs from its declaration in the schema
+    ActionDeclarationMismatch,
+    /// An attribute of the entity calls an unknown extension function
+    ExtensionFunctionLookup,
+}
+
+impl From<&EntitySchemaConformanceError> for EntityViolationKind {
+    fn from(err: &EntitySchemaConformanceError) -> Self {
+        match err {
+            EntitySchemaConformanceError::UnexpectedEntityAttr(_) => Self::UnexpectedAttr,
+            EntitySchemaConformanceError::MissingRequiredEntityAttr(_) => Self::MissingRequiredAttr,
+            EntitySchemaConformanceError::DerivedEntityAttr(_) => Self::DerivedAttr,
+            EntitySchemaConformanceError::MutuallyExclusiveEntityAttrs(_) => {
+                Self::MutuallyExclusiveAttrs
+            }
+            EntitySchemaConformanceError::TypeMismatch(_) => Self::TypeMismatch,
+            EntitySchemaConformanceError::HeterogeneousSet(_) => Self::HeterogeneousSet,
+            EntitySchemaConformanceError::InvalidAncestorType(_) => Self::InvalidAncestorType,
+            EntitySchemaConformanceError::UnexpectedEntityType(_) => Self::UnexpectedEntityType,
+            EntitySchemaConformanceError::UndeclaredAction(_) => Self::UndeclaredAction,
+            EntitySchemaConformanceError::ActionDeclarationMismatch(_) => {
+                Self::ActionDeclarationMismatch
+            }
+            EntitySchemaConformanceError::ExtensionFunctionLookup(_) => {
+                Self::ExtensionFunctionLookup
+            }
+        }
+    }
+}
+
+/// The result of [`Validator::validate_entities_sampled`]: the number of
+/// violations of the schema of each kind and for each entity type, and the
+/// detailed errors for the first few of them
+#[derive(Debug, Default)]
+pub struct EntityValidationReport {
+    entities_checked: usize,
+    invalid_entities: usize,
+    by_kind: BTreeMap<EntityViolationKind, usize>,
+    by_entity_type: HashMap<EntityType, usize>,
+    sample: Vec<EntitySchemaConformanceError>,
+}
+
+impl EntityValidationReport {
+    /// Check if every entity conforms to the schema
+    pub fn validation_passed(&self) -> bool {
+        self.invalid_entities == 0
+    }
+
+    /// The number of entities which were checked
+    pub fn entities_checked(&self) -> usize {
+        self.entities_checked
+    }
+
+    /// The number of entities with at least one violation
+    pub fn invalid_entities(&self) -> usize {
+        self.invalid_entities
+    }
+
+    /// The total number of violations, which may be more than the number of
+    /// invalid entities, since an entity may violate the schema in several
+    /// ways
+    pub fn total_violations(&self) -> usize {
+        self.by_kind.values().sum()
+    }
+
+    /// The number of violations of each kind. Kinds without violations are not
+    /// included.
+    pub fn violations_by_kind(&self) -> &BTreeMap<EntityViolationKind, usize> {
+        &self.by_kind
+    }
+
+    /// The number of violations by entities of each entity type. Entity types
+    /// without violations are not included.
+    pub fn violations_by_entity_type(&self) -> &HashMap<EntityType, usize> {
+        &self.by_entity_type
+    }
+
+    /// The detailed errors for the first violations found, up to the maximum
+    /// passed to [`Validator::validate_entities_sampled`]
+    pub fn sample(&self) -> &[EntitySchemaConformanceError] {
+        &self.sample
+    }
+
+    /// Check if there were more violations than are in the
+    /// [`sample`](Self::sample)
+    pub fn is_truncated(&self) -> bool {
+        self.total_violations() > self.sample.len()
+    }
+}
+
+impl Validator {
+    /// Check every entity in `entities` against the schema, reporting all the
+    /// ways in which each entity violates it. Unlike constructing `Entities`
+    /// with a schema, this does not stop at the first error: all violations
+    /// are counted, by kind and by entity type, but the detailed error is only
+    /// kept for the first `max_errors` of them, so that validating a huge
+    /// number of entities uses a bounded amount of memory.
+    pub fn validate_entities_sampled(
+        &self,
+        entities: &Entities,
+        max_errors: usize,
+    ) -> EntityValidationReport {
+        let schema = CoreSchema::new(&self.schema);
+        let checker = EntitySchemaConformanceChecker::new(&schema, Extensions::all_available());
+        let mut report = EntityValidationReport::default();
+        for entity in entities.iter() {
+            report.entities_checked += 1;
+            let violations = checker.entity_violations(entity);
+            if violations.is_empty() {
+                continue;
+            }
+            report.invalid_entities += 1;
+            *report
+                .by_entity_type
+                .entry(entity.uid().entity_type().clone())
+                .or_default() += violations.len();
+            for violation in violations {
+                *report
+                    .by_kind
+                    .entry(EntityViolationKind::from(&violation))
+                    .or_default() += 1;
+                if report.sample.len() < max_errors {
+                    report.sample.push(violation);
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cedar_policy_core::entities::{EntityJsonParser, TCComputation};
+
+    use super::*;
+    use crate::ValidatorSchema;
+
+    fn report(entities: serde_json::Value, max_errors: usize) -> EntityValidationReport {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity Group;
+            entity User in [Group] = { name: String, age?: Long };
+            entity Photo = { owner: User };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let entities = EntityJsonParser::new(
+            None::<&CoreSchema<'_>>,
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        )
+        .from_json_value(entities)
+        .unwrap();
+        Validator::new(schema).validate_entities_sampled(&entities, max_errors)
+    }
+
+    #[test]
+    fn counts_all_violations() {
+        let report = report(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "name": "Alice" }, "parents": [{ "type": "Group", "id": "admins" }] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": { "age": "old", "extra": 1 }, "parents": [{ "type": "Photo", "id": "p" }] },
+                { "uid": { "type": "Photo", "id": "p" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Album", "id": "a" }, "attrs": {}, "parents": [] },
+            ]),
+            2,
+        );
+        assert!(!report.validation_passed());
+        assert_eq!(report.entities_checked(), 4);
+        assert_eq!(report.invalid_entities(), 3);
+        assert_eq!(report.total_violations(), 6);
+        assert_eq!(
+            report.violations_by_kind(),
+            &BTreeMap::from([
+                (EntityViolationKind::UnexpectedAttr, 1),
+                (EntityViolationKind::MissingRequiredAttr, 2),
+                (EntityViolationKind::TypeMismatch, 1),
+                (EntityViolationKind::InvalidAncestorType, 1),
+                (EntityViolationKind::UnexpectedEntityType, 1),
+            ])
+        );
+        assert_eq!(
+            report.violations_by_entity_type(),
+            &HashMap::from([
+                (EntityType::Specified("User".parse().unwrap()), 4),
+                (EntityType::Specified("Photo".parse().unwrap()), 1),
+                (EntityType::Specified("Album".parse().unwrap()), 1),
+            ])
+        );
+        assert_eq!(report.sample().len(), 2);
+        assert!(report.is_truncated());
+    }
+
+    #[test]
+    fn valid_entities() {
+        let report = report(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "name": "Alice" }, "parents": [] },
+            ]),
+            10,
+        );
+        assert!(report.validation_passed());
+        assert_eq!(report.entities_checked(), 1);
+        assert_eq!(report.total_violations(), 0);
+        assert!(report.sample().is_empty());
+        assert!(!report.is_truncated());
+    }
+}
@@ -21,10 +21,11 @@ use cedar_policy_core::{
     transitive_closure,
 };
 use itertools::Itertools;
-use miette::Diagnostic;
+use miette::{Diagnostic, SourceSpan};
+use smol_str::SmolStr;
 use thiserror::Error;
 
-use crate::human_schema::parser::HumanSyntaxParseErrors;
+use crate::human_schema::{parser::HumanSyntaxParseErrors, ParseError, ToJsonSchemaError};
 
 #[derive(Debug, Error, Diagnostic)]
 pub enum HumanSchemaError {
@@ -127,6 +128,40 @@ pub enum SchemaError {
     #[error("the `__expr` escape is no longer supported")]
     #[diagnostic(help("to create an entity reference, use `__entity`; to create an extension value, use `__extn`; and for all other values, use JSON directly"))]
     ExprEscapeUsed,
+    /// A default value given in `contextDefaults` is invalid: it does not
+    /// evaluate, is not for an optional attribute of the action's context, or
+    /// does not have that attribute's type.
+    #[error("invalid default for context attribute `{attr}` of action `{action}`: {reason}")]
+    InvalidContextDefault {
+        /// Action whose `contextDefaults` contains the invalid default
+        action: EntityUID,
+        /// Context attribute with the invalid default
+        attr: SmolStr,
+        /// Why the default is invalid
+        reason: String,
+    },
+    /// The expression given in `derivedAttributes` for an attribute of an
+    /// entity type is invalid: it does not parse or typecheck, reads something
+    /// other than the entity's own attributes, or depends on itself.
+    #[error("invalid derived attribute `{attr}` of entity type `{entity_type}`: {reason}")]
+    InvalidDerivedAttribute {
+        /// Entity type whose `derivedAttributes` contains the invalid attribute
+        entity_type: Name,
+        /// The invalid derived attribute
+        attr: SmolStr,
+        /// Why the derived attribute is invalid
+        reason: String,
+    },
+    /// A `mutuallyExclusive` group of attributes of an entity type is invalid:
+    /// it has fewer than two attributes, or an attribute which is not an
+    /// optional attribute of the entity type.
+    #[error("invalid `mutuallyExclusive` attributes of entity type `{entity_type}`: {reason}")]
+    InvalidMutuallyExclusiveAttrs {
+        /// Entity type with the invalid `mutuallyExclusive` group
+        entity_type: Name,
+        /// Why the group is invalid
+        reason: String,
+    },
 }
 
 impl From<transitive_closure::TcError<EntityUID>> for SchemaError {
@@ -145,6 +180,60 @@ impl From<transitive_closure::TcError<EntityUID>> for SchemaError {
     }
 }
 
+/// An error in a schema in the natural syntax, as returned by
+/// [`parse_schema_with_recovery`](crate::parse_schema_with_recovery). Unlike
+/// [`SchemaError`], each undeclared entity type or action is its own error,
+/// labeled with where it is first referenced.
+#[derive(Debug, Diagnostic, Error)]
+pub enum SchemaParseError {
+    /// A syntax error
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Syntax(Box<ParseError>),
+    /// An error translating the schema into the JSON syntax, e.g., an unknown
+    /// type name
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Conversion(Box<ToJsonSchemaError>),
+    /// An undeclared entity type used in the `memberOf` field of an entity
+    /// type, the `appliesTo` fields of an action, or an attribute type
+    #[error("undeclared entity type `{name}`")]
+    #[diagnostic(help("any entity types appearing anywhere in a schema need to be declared"))]
+    UndeclaredEntityType {
+        /// The undeclared entity type, fully qualified
+        name: Name,
+        /// Where the entity type is first referenced
+        #[label]
+        span: Option<SourceSpan>,
+    },
+    /// An undeclared action used in the `memberOf` field of an action
+    #[error("undeclared action `{action}`")]
+    #[diagnostic(help("any actions appearing in `memberOf` need to be declared"))]
+    UndeclaredAction {
+        /// The undeclared action
+        action: EntityUID,
+        /// Where the action is first referenced
+        #[label]
+        span: Option<SourceSpan>,
+    },
+    /// Any other error constructing the schema
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Schema(Box<SchemaError>),
+}
+
+impl SchemaParseError {
+    pub(crate) fn undeclared_entity_type(name: Name) -> Self {
+        let span = name.loc().map(|loc| loc.span);
+        Self::UndeclaredEntityType { name, span }
+    }
+
+    pub(crate) fn undeclared_action(action: EntityUID) -> Self {
+        let span = action.loc().map(|loc| loc.span);
+        Self::UndeclaredAction { action, span }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, SchemaError>;
 
 #[derive(Debug)]
@@ -168,6 +257,8 @@ impl std::fmt::Display for ContextOrShape {
 pub enum UnsupportedFeature {
     #[error("records and entities with `additionalAttributes` are experimental, but the experimental `partial-validate` feature is not enabled")]
     OpenRecordsAndEntities,
+    #[error("entity types with `derivedAttributes` are experimental, but the experimental `derived-attributes` feature is not enabled")]
+    DerivedAttributes,
     // Action attributes are allowed if `ActionBehavior` is `PermitAttributes`
     #[error("action declared with attributes: [{}]", .0.iter().join(", "))]
     ActionAttributes(Vec<String>),
@@ -97,7 +97,10 @@ fn text_in_expr(e: &Expr) -> impl IntoIterator<Item = TextKind<'_>> {
             text_in_name(e.source_loc(), fn_name).collect()
         }
         ExprKind::GetAttr { attr, .. } => vec![TextKind::Identifier(e.source_loc(), attr)],
-        ExprKind::HasAttr { attr, .. } => vec![TextKind::Identifier(e.source_loc(), attr)],
+        ExprKind::HasAttr { attr, path, .. } => std::iter::once(attr)
+            .chain(path)
+            .map(|attr| TextKind::Identifier(e.source_loc(), attr))
+            .collect(),
         ExprKind::Like { pattern, .. } => {
             vec![TextKind::Pattern(e.source_loc(), pattern.get_elems())]
         }
@@ -35,9 +35,12 @@ use std::str::FromStr;
 fn get_argument_types(fname: &str, decimal_ty: &Type) -> Vec<types::Type> {
     match fname {
         "decimal" => vec![Type::primitive_string()],
+        "decimalWithScale" => vec![Type::primitive_string(), Type::primitive_long()],
         "lessThan" | "lessThanOrEqual" | "greaterThan" | "greaterThanOrEqual" => {
             vec![decimal_ty.clone(), decimal_ty.clone()]
         }
+        "round" => vec![decimal_ty.clone(), Type::primitive_long()],
+        "floor" | "ceil" => vec![decimal_ty.clone()],
         _ => panic!("unexpected decimal extension function name: {fname}"),
     }
 }
@@ -46,7 +49,7 @@ fn get_argument_types(fname: &str, decimal_ty: &Type) -> Vec<types::Type> {
 #[allow(clippy::panic)]
 fn get_return_type(fname: &str, decimal_ty: &Type) -> Type {
     match fname {
-        "decimal" => decimal_ty.clone(),
+        "decimal" | "decimalWithScale" | "round" | "floor" | "ceil" => decimal_ty.clone(),
         "lessThan" | "lessThanOrEqual" | "greaterThan" | "greaterThanOrEqual" => {
             Type::primitive_boolean()
         }
@@ -59,7 +62,9 @@ fn get_return_type(fname: &str, decimal_ty: &Type) -> Type {
 fn get_argument_check(fname: &str) -> Option<ArgumentCheckFn> {
     match fname {
         "decimal" => Some(Box::new(validate_decimal_string)),
-        "lessThan" | "lessThanOrEqual" | "greaterThan" | "greaterThanOrEqual" => None,
+        "decimalWithScale" => Some(Box::new(validate_decimal_with_scale_args)),
+        "lessThan" | "lessThanOrEqual" | "greaterThan" | "greaterThanOrEqual" | "round"
+        | "floor" | "ceil" => None,
         _ => panic!("unexpected decimal extension function name: {fname}"),
     }
 }
@@ -109,6 +114,33 @@ fn validate_decimal_string(exprs: &[Expr]) -> Result<(), String> {
     }
 }
 
+/// Extra validation step for the `decimalWithScale` function, when both of its
+/// arguments are literals.
+/// Note that `exprs` will have already been checked to contain the correct number of arguments.
+fn validate_decimal_with_scale_args(exprs: &[Expr]) -> Result<(), String> {
+    match exprs {
+        [str, scale]
+            if matches!(str.expr_kind(), ExprKind::Lit(Literal::String(_)))
+                && matches!(scale.expr_kind(), ExprKind::Lit(Literal::Long(_))) =>
+        {
+            let exts = Extensions::all_available();
+            let evaluator = RestrictedEvaluator::new(&exts);
+            match RestrictedExpr::from_str(&format!("decimalWithScale({str}, {scale})")) {
+                Ok(expr) => match evaluator.interpret(expr.as_borrowed()) {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(format!(
+                        "Failed to parse as a decimal value with scale {scale}: `{str}`"
+                    )),
+                },
+                Err(_) => Err(format!(
+                    "Failed to parse as a decimal value with scale {scale}: `{str}`"
+                )),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
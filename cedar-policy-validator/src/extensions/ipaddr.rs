@@ -36,7 +36,7 @@ fn get_argument_types(fname: &str, ipaddr_ty: &Type) -> Vec<types::Type> {
     match fname {
         "ip" => vec![Type::primitive_string()],
         "isIpv4" | "isIpv6" | "isLoopback" | "isMulticast" => vec![ipaddr_ty.clone()],
-        "isInRange" => vec![ipaddr_ty.clone(), ipaddr_ty.clone()],
+        "isInRange" | "overlaps" => vec![ipaddr_ty.clone(), ipaddr_ty.clone()],
         _ => panic!("unexpected ipaddr extension function name: {fname}"),
     }
 }
@@ -46,7 +46,7 @@ fn get_argument_types(fname: &str, ipaddr_ty: &Type) -> Vec<types::Type> {
 fn get_return_type(fname: &str, ipaddr_ty: &Type) -> Type {
     match fname {
         "ip" => ipaddr_ty.clone(),
-        "isIpv4" | "isIpv6" | "isLoopback" | "isMulticast" | "isInRange" => {
+        "isIpv4" | "isIpv6" | "isLoopback" | "isMulticast" | "isInRange" | "overlaps" => {
             Type::primitive_boolean()
         }
         _ => panic!("unexpected ipaddr extension function name: {fname}"),
@@ -58,7 +58,7 @@ fn get_return_type(fname: &str, ipaddr_ty: &Type) -> Type {
 fn get_argument_check(fname: &str) -> Option<ArgumentCheckFn> {
     match fname {
         "ip" => Some(Box::new(validate_ip_string)),
-        "isIpv4" | "isIpv6" | "isLoopback" | "isMulticast" | "isInRange" => None,
+        "isIpv4" | "isIpv6" | "isLoopback" | "isMulticast" | "isInRange" | "overlaps" => None,
         _ => panic!("unexpected ipaddr extension function name: {fname}"),
     }
 }
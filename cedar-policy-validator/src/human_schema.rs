@@ -23,3 +23,4 @@ mod test;
 pub mod to_json_schema;
 pub use err::ParseError;
 pub use err::SchemaWarning;
+pub use err::ToJsonSchemaError;
@@ -240,6 +240,9 @@ pub struct EntityDecl {
     pub member_of_types: Vec<Path>,
     /// Attributes this entity has
     pub attrs: Vec<Node<AttrDecl>>,
+    /// Groups of attributes of which an entity may have at most one, from
+    /// `@mutuallyExclusive` annotations
+    pub mutually_exclusive: Vec<Vec<SmolStr>>,
 }
 
 /// Type definitions
@@ -283,6 +286,9 @@ pub struct AttrDecl {
     pub required: bool,
     /// The type of this attribute
     pub ty: Node<Type>,
+    /// The source of the restricted expression given in a `@default(...)`
+    /// annotation on this attribute, if any
+    pub default: Option<Node<SmolStr>>,
 }
 
 /// The target of a [`PRAppDecl`]
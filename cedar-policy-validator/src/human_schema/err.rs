@@ -41,6 +41,8 @@ pub enum UserError {
     StringEscape(NonEmpty<UnescapeError>),
     #[error("`{0}` is a reserved identifier")]
     ReservedIdentifierUsed(SmolStr),
+    #[error("Unknown annotation `@{0}`. The only supported annotation is `@default`")]
+    UnknownAnnotation(SmolStr),
 }
 
 pub(crate) type RawLocation = usize;
@@ -369,6 +371,16 @@ pub enum ToJsonSchemaError {
     UnknownTypeName(Node<SmolStr>),
     #[error("Use reserved namespace `__cedar`")]
     UseReservedNamespace(Loc),
+    /// A `@default` annotation was used somewhere other than on an optional
+    /// attribute of an action's context
+    #[error("`@default` is only allowed on optional attributes of an action's context")]
+    UnexpectedDefault(Loc),
+    /// The expression in a `@default` annotation is not a valid default value
+    #[error("Invalid default value `{}`: {reason}", .value.node)]
+    InvalidDefault {
+        value: Node<SmolStr>,
+        reason: String,
+    },
 }
 
 impl ToJsonSchemaError {
@@ -408,9 +420,13 @@ impl Diagnostic for ToJsonSchemaError {
             ToJsonSchemaError::UnknownTypeName(node) => Some(Box::new(std::iter::once(
                 LabeledSpan::underline(node.loc.span),
             ))),
-            ToJsonSchemaError::UseReservedNamespace(loc) => {
+            ToJsonSchemaError::UseReservedNamespace(loc)
+            | ToJsonSchemaError::UnexpectedDefault(loc) => {
                 Some(Box::new(std::iter::once(LabeledSpan::underline(loc.span))))
             }
+            ToJsonSchemaError::InvalidDefault { value, .. } => Some(Box::new(std::iter::once(
+                LabeledSpan::underline(value.loc.span),
+            ))),
         }
     }
 }
@@ -14,9 +14,15 @@
  * limitations under the License.
  */
 
-use std::{collections::HashSet, fmt::Display};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+};
 
-use cedar_policy_core::ast::Name;
+use cedar_policy_core::{
+    ast::{Id, Name, RestrictedExpr},
+    entities::{json::err::JsonDeserializationErrorContext, CedarValueJson},
+};
 use itertools::Itertools;
 use miette::Diagnostic;
 use nonempty::NonEmpty;
@@ -24,7 +30,8 @@ use smol_str::{SmolStr, ToSmolStr};
 use thiserror::Error;
 
 use crate::{
-    ActionType, EntityType, NamespaceDefinition, SchemaFragment, SchemaType, SchemaTypeVariant,
+    ActionType, ApplySpec, EntityType, NamespaceDefinition, SchemaFragment, SchemaType,
+    SchemaTypeVariant, TypeOfAttribute,
 };
 
 impl Display for SchemaFragment {
@@ -45,6 +52,13 @@ impl Display for NamespaceDefinition {
             writeln!(f, "type {n} = {ty};")?
         }
         for (n, ty) in &self.entity_types {
+            for attrs in ty.mutually_exclusive.iter().flatten() {
+                let attrs = attrs
+                    .iter()
+                    .map(|attr| format!("\"{}\"", attr.escape_debug()))
+                    .join(", ");
+                writeln!(f, "@mutuallyExclusive({attrs})")?;
+            }
             writeln!(f, "entity {n}{ty};")?
         }
         for (n, a) in &self.actions {
@@ -65,23 +79,7 @@ impl Display for SchemaType {
                 SchemaTypeVariant::Record {
                     attributes,
                     additional_attributes: _,
-                } => {
-                    write!(f, "{{")?;
-                    for (i, (n, ty)) in attributes.iter().enumerate() {
-                        write!(
-                            f,
-                            "\"{}\"{}: {}",
-                            n.escape_debug(),
-                            if ty.required { "" } else { "?" },
-                            ty.ty
-                        )?;
-                        if i < (attributes.len() - 1) {
-                            write!(f, ", ")?;
-                        }
-                    }
-                    write!(f, "}}")?;
-                    Ok(())
-                }
+                } => fmt_record(f, attributes, None),
                 SchemaTypeVariant::Set { element } => write!(f, "Set < {element} >"),
                 SchemaTypeVariant::String => write!(f, "__cedar::String"),
             },
@@ -90,6 +88,54 @@ impl Display for SchemaType {
     }
 }
 
+/// Print a record type. If `defaults` is provided, attributes with a default
+/// value are annotated with `@default(...)`.
+fn fmt_record(
+    f: &mut std::fmt::Formatter<'_>,
+    attributes: &BTreeMap<SmolStr, TypeOfAttribute>,
+    defaults: Option<&HashMap<SmolStr, CedarValueJson>>,
+) -> std::fmt::Result {
+    write!(f, "{{")?;
+    for (i, (n, ty)) in attributes.iter().enumerate() {
+        if let Some(default) = defaults
+            .and_then(|defaults| defaults.get(n))
+            .and_then(default_to_expr)
+        {
+            write!(f, "@default(\"{}\") ", default.to_string().escape_debug())?;
+        }
+        write!(
+            f,
+            "\"{}\"{}: {}",
+            n.escape_debug(),
+            if ty.required { "" } else { "?" },
+            ty.ty
+        )?;
+        if i < (attributes.len() - 1) {
+            write!(f, ", ")?;
+        }
+    }
+    write!(f, "}}")
+}
+
+/// Get the restricted expression written in a `@default(...)` annotation for
+/// the given default value, if it has one
+fn default_to_expr(default: &CedarValueJson) -> Option<RestrictedExpr> {
+    default
+        .clone()
+        .into_expr(|| JsonDeserializationErrorContext::Context)
+        .ok()
+}
+
+/// Print the `context` type of an apply spec, including any context defaults
+fn fmt_context(f: &mut std::fmt::Formatter<'_>, spec: &ApplySpec) -> std::fmt::Result {
+    match &spec.context.0 {
+        SchemaType::Type(SchemaTypeVariant::Record { attributes, .. }) => {
+            fmt_record(f, attributes, spec.context_defaults.as_ref())
+        }
+        ty => write!(f, "{ty}"),
+    }
+}
+
 /// Create a non-empty with borrowed contents from a slice
 fn non_empty_slice<T>(v: &[T]) -> Option<NonEmpty<&T>> {
     let vs: Vec<&T> = v.iter().collect();
@@ -150,7 +196,8 @@ impl Display for ActionType {
                     fmt_vec(f, ps)?;
                     write!(f, ",\n  resource: ")?;
                     fmt_vec(f, rs)?;
-                    write!(f, ",\n  context: {}", &spec.context.0)?;
+                    write!(f, ",\n  context: ")?;
+                    fmt_context(f, spec)?;
                     write!(f, "\n}}")?;
                 }
                 // Only principals are present, resource is unspecified
@@ -158,7 +205,8 @@ impl Display for ActionType {
                     write!(f, " appliesTo {{")?;
                     write!(f, "\n  principal: ")?;
                     fmt_vec(f, ps)?;
-                    write!(f, ",\n  context: {}", &spec.context.0)?;
+                    write!(f, ",\n  context: ")?;
+                    fmt_context(f, spec)?;
                     write!(f, "\n}}")?;
                 }
                 // Only resources is present, principal is unspecified
@@ -166,13 +214,15 @@ impl Display for ActionType {
                     write!(f, " appliesTo {{")?;
                     write!(f, "\n  resource: ")?;
                     fmt_vec(f, rs)?;
-                    write!(f, ",\n  context: {}", &spec.context.0)?;
+                    write!(f, ",\n  context: ")?;
+                    fmt_context(f, spec)?;
                     write!(f, "\n}}")?;
                 }
                 // Neither are present, both principal and resource are unspecified
                 (None, None) => {
                     write!(f, " appliesTo {{")?;
-                    write!(f, "\n  context: {}", &spec.context.0)?;
+                    write!(f, "\n  context: ")?;
+                    fmt_context(f, spec)?;
                     write!(f, "\n}}")?;
                 }
             }
@@ -191,6 +241,55 @@ impl Display for ActionType {
 pub enum ToHumanSchemaStrError {
     #[error("There exist type name collisions: {:?}", .0)]
     NameCollisions(NonEmpty<SmolStr>),
+    #[error(
+        "The context defaults of action `{0}` cannot be represented in the natural schema syntax"
+    )]
+    #[diagnostic(help(
+        "defaults may only be given for attributes of a context declared as a record type"
+    ))]
+    UnrepresentableContextDefaults(SmolStr),
+    #[error("The derived attributes of entity type `{0}` cannot be represented in the natural schema syntax")]
+    #[diagnostic(help("derived attributes can only be declared in the JSON schema syntax"))]
+    UnrepresentableDerivedAttributes(SmolStr),
+    #[error("The attributes of action `{0}` cannot be represented in the natural schema syntax")]
+    #[diagnostic(help("action attributes can only be declared in the JSON schema syntax"))]
+    UnrepresentableActionAttributes(SmolStr),
+    #[error("The declaration of `{0}` has a record type with additional attributes, which cannot be represented in the natural schema syntax")]
+    #[diagnostic(help(
+        "records with `additionalAttributes` can only be declared in the JSON schema syntax"
+    ))]
+    UnrepresentableAdditionalAttributes(SmolStr),
+}
+
+/// Does this type contain a record type which allows additional attributes?
+fn has_additional_attributes(ty: &SchemaType) -> bool {
+    match ty {
+        SchemaType::Type(SchemaTypeVariant::Record {
+            attributes,
+            additional_attributes,
+        }) => {
+            *additional_attributes
+                || attributes
+                    .values()
+                    .any(|attr| has_additional_attributes(&attr.ty))
+        }
+        SchemaType::Type(SchemaTypeVariant::Set { element }) => has_additional_attributes(element),
+        _ => false,
+    }
+}
+
+/// Can the context defaults in this apply spec be written as `@default(...)`
+/// annotations on the attributes of the context?
+fn context_defaults_representable(spec: &ApplySpec) -> bool {
+    match (&spec.context.0, &spec.context_defaults) {
+        (_, None) => true,
+        (SchemaType::Type(SchemaTypeVariant::Record { attributes, .. }), Some(defaults)) => {
+            defaults.iter().all(|(attr, default)| {
+                attributes.contains_key(attr) && default_to_expr(default).is_some()
+            })
+        }
+        (_, Some(defaults)) => defaults.is_empty(),
+    }
 }
 
 pub fn json_schema_to_custom_schema_str(
@@ -218,6 +317,72 @@ pub fn json_schema_to_custom_schema_str(
             .collect();
         name_collisions.extend(entity_types.intersection(&common_types).cloned());
     }
+    for (name, ns) in json_schema.0.iter() {
+        let qualified = |id: &Id| {
+            Name::unqualified_name(id.clone())
+                .prefix_namespace_if_unqualified(name.clone())
+                .to_smolstr()
+        };
+        for (ty_name, ty) in ns.common_types.iter() {
+            if has_additional_attributes(ty) {
+                return Err(ToHumanSchemaStrError::UnrepresentableAdditionalAttributes(
+                    qualified(ty_name),
+                ));
+            }
+        }
+        for (ety, ty) in ns.entity_types.iter() {
+            if ty
+                .derived_attributes
+                .as_ref()
+                .is_some_and(|derived| !derived.is_empty())
+            {
+                return Err(ToHumanSchemaStrError::UnrepresentableDerivedAttributes(
+                    qualified(ety),
+                ));
+            }
+            if has_additional_attributes(&ty.shape.0) {
+                return Err(ToHumanSchemaStrError::UnrepresentableAdditionalAttributes(
+                    qualified(ety),
+                ));
+            }
+        }
+        for (action, ty) in ns.actions.iter() {
+            let action_name = || -> SmolStr {
+                match name {
+                    Some(name) => format!("{name}::Action::\"{}\"", action.escape_debug()),
+                    None => format!("Action::\"{}\"", action.escape_debug()),
+                }
+                .into()
+            };
+            if ty
+                .applies_to
+                .as_ref()
+                .is_some_and(|spec| !context_defaults_representable(spec))
+            {
+                return Err(ToHumanSchemaStrError::UnrepresentableContextDefaults(
+                    action_name(),
+                ));
+            }
+            if ty
+                .applies_to
+                .as_ref()
+                .is_some_and(|spec| has_additional_attributes(&spec.context.0))
+            {
+                return Err(ToHumanSchemaStrError::UnrepresentableAdditionalAttributes(
+                    action_name(),
+                ));
+            }
+            if ty
+                .attributes
+                .as_ref()
+                .is_some_and(|attrs| !attrs.is_empty())
+            {
+                return Err(ToHumanSchemaStrError::UnrepresentableActionAttributes(
+                    action_name(),
+                ));
+            }
+        }
+    }
     if let Some((head, tail)) = name_collisions.split_first() {
         return Err(ToHumanSchemaStrError::NameCollisions(NonEmpty {
             head: head.clone(),
@@ -24,6 +24,7 @@ mod demo_tests {
         iter::{empty, once},
     };
 
+    use cedar_policy_core::entities::CedarValueJson;
     use cool_asserts::assert_matches;
     use smol_str::ToSmolStr;
 
@@ -334,6 +335,68 @@ mod demo_tests {
         assert!(as_src.contains(expected), "src was:\n`{as_src}`");
     }
 
+    #[test]
+    fn unrepresentable_constructs() {
+        let to_natural = |json: serde_json::Value| {
+            SchemaFragment::from_json_value(json)
+                .unwrap()
+                .as_natural_schema()
+        };
+        assert_matches!(
+            to_natural(serde_json::json!({"NS": {
+                "entityTypes": {},
+                "actions": { "view": { "attributes": { "level": 1 } } }
+            }})),
+            Err(human_schema::ToHumanSchemaStrError::UnrepresentableActionAttributes(action)) => {
+                assert_eq!(action, r#"NS::Action::"view""#);
+            }
+        );
+        assert_matches!(
+            to_natural(serde_json::json!({"": {
+                "entityTypes": { "User": { "shape": {
+                    "type": "Record",
+                    "attributes": { "prefs": { "type": "Record", "attributes": {}, "additionalAttributes": true } }
+                } } },
+                "actions": {}
+            }})),
+            Err(human_schema::ToHumanSchemaStrError::UnrepresentableAdditionalAttributes(name)) => {
+                assert_eq!(name, "User");
+            }
+        );
+        assert_matches!(
+            to_natural(serde_json::json!({"NS": {
+                "commonTypes": { "Prefs": {
+                    "type": "Set",
+                    "element": { "type": "Record", "attributes": {}, "additionalAttributes": true }
+                } },
+                "entityTypes": {},
+                "actions": {}
+            }})),
+            Err(human_schema::ToHumanSchemaStrError::UnrepresentableAdditionalAttributes(name)) => {
+                assert_eq!(name, "NS::Prefs");
+            }
+        );
+        assert_matches!(
+            to_natural(serde_json::json!({"": {
+                "entityTypes": {},
+                "actions": { "view": { "appliesTo": { "context": {
+                    "type": "Record", "attributes": {}, "additionalAttributes": true
+                } } } }
+            }})),
+            Err(human_schema::ToHumanSchemaStrError::UnrepresentableAdditionalAttributes(name)) => {
+                assert_eq!(name, r#"Action::"view""#);
+            }
+        );
+        // closed records and actions without attributes are fine
+        to_natural(serde_json::json!({"": {
+            "entityTypes": { "User": { "shape": {
+                "type": "Record", "attributes": {}, "additionalAttributes": false
+            } } },
+            "actions": { "view": { "attributes": {} } }
+        }}))
+        .unwrap();
+    }
+
     #[test]
     fn context_is_common_type() {
         assert!(SchemaFragment::from_str_natural(
@@ -391,6 +454,121 @@ namespace Baz {action "Foo" appliesTo {
         .is_ok());
     }
 
+    #[test]
+    fn context_defaults() {
+        let src = r#"
+        action "Foo" appliesTo {
+            context: {
+                authenticated: Bool,
+                @default("false") mfa?: Bool,
+                @default("ip(\"10.0.0.1\")") source?: ipaddr,
+                region?: String,
+            }
+        };
+    "#;
+        let (fragment, _) = SchemaFragment::from_str_natural(src).unwrap();
+        let spec = fragment.0[&None].actions["Foo"].applies_to.clone().unwrap();
+        assert_eq!(
+            spec.context_defaults,
+            Some(HashMap::from([
+                ("mfa".into(), CedarValueJson::Bool(false)),
+                (
+                    "source".into(),
+                    serde_json::from_value(
+                        serde_json::json!({ "__extn": { "fn": "ip", "arg": "10.0.0.1" } })
+                    )
+                    .unwrap()
+                ),
+            ]))
+        );
+
+        // The defaults survive a roundtrip through the natural syntax
+        let as_src = fragment.as_natural_schema().unwrap();
+        let (roundtripped, _) = SchemaFragment::from_str_natural(&as_src).unwrap();
+        assert_eq!(fragment, roundtripped, "src was:\n`{as_src}`");
+    }
+
+    #[test]
+    fn context_defaults_invalid() {
+        let expect_json_error = |src: &str, expected: fn(&ToJsonSchemaError) -> bool| {
+            // Can't unwrap here as impl iter doesn't implement debug
+            let err = match SchemaFragment::from_str_natural(src) {
+                Err(e) => e,
+                _ => panic!("Should have failed to parse"),
+            };
+            assert_matches!(err,
+            crate::HumanSchemaError::Parsing(err) => assert_matches!(err,
+                human_schema::parser::HumanSyntaxParseErrors::JsonError(json_errs) => {
+                    assert!(json_errs.into_iter().any(|err| expected(&err)));
+                }));
+        };
+        // Required attribute
+        expect_json_error(
+            r#"action "Foo" appliesTo { context: { @default("false") mfa: Bool } };"#,
+            |err| matches!(err, ToJsonSchemaError::UnexpectedDefault(_)),
+        );
+        // Entity attribute
+        expect_json_error(r#"entity User { @default("false") mfa?: Bool };"#, |err| {
+            matches!(err, ToJsonSchemaError::UnexpectedDefault(_))
+        });
+        // Nested record attribute
+        expect_json_error(
+            r#"action "Foo" appliesTo { context: { r?: { @default("1") a?: Long } } };"#,
+            |err| matches!(err, ToJsonSchemaError::UnexpectedDefault(_)),
+        );
+        // Not a restricted expression
+        expect_json_error(
+            r#"action "Foo" appliesTo { context: { @default("1 + 1") a?: Long } };"#,
+            |err| matches!(err, ToJsonSchemaError::InvalidDefault { .. }),
+        );
+        // Unknown annotation
+        assert_matches!(
+            SchemaFragment::from_str_natural(
+                r#"action "Foo" appliesTo { context: { @doc("x") a?: Long } };"#
+            )
+            .map(|(fragment, _)| fragment),
+            Err(crate::HumanSchemaError::Parsing(
+                human_schema::parser::HumanSyntaxParseErrors::NaturalSyntaxError(_)
+            ))
+        );
+    }
+
+    #[test]
+    fn mutually_exclusive() {
+        let src = r#"
+        @mutuallyExclusive("userId", "serviceAccountId")
+        @mutuallyExclusive("email", "phone")
+        entity Principal {
+            userId?: String,
+            serviceAccountId?: String,
+            email?: String,
+            phone?: String,
+        };
+    "#;
+        let (fragment, _) = SchemaFragment::from_str_natural(src).unwrap();
+        assert_eq!(
+            fragment.0[&None].entity_types[&"Principal".parse().unwrap()].mutually_exclusive,
+            Some(vec![
+                vec!["userId".into(), "serviceAccountId".into()],
+                vec!["email".into(), "phone".into()],
+            ])
+        );
+
+        // The groups survive a roundtrip through the natural syntax
+        let as_src = fragment.as_natural_schema().unwrap();
+        let (roundtripped, _) = SchemaFragment::from_str_natural(&as_src).unwrap();
+        assert_eq!(fragment, roundtripped, "src was:\n`{as_src}`");
+
+        // Unknown annotation
+        assert_matches!(
+            SchemaFragment::from_str_natural(r#"@exclusive("a", "b") entity User;"#)
+                .map(|(fragment, _)| fragment),
+            Err(crate::HumanSchemaError::Parsing(
+                human_schema::parser::HumanSyntaxParseErrors::NaturalSyntaxError(_)
+            ))
+        );
+    }
+
     #[test]
     fn print_actions() {
         let namespace = NamespaceDefinition {
@@ -400,6 +578,8 @@ namespace Baz {action "Foo" appliesTo {
                 EntityType {
                     member_of_types: vec![],
                     shape: AttributesOrContext::default(),
+                    derived_attributes: None,
+                    mutually_exclusive: None,
                 },
             )]),
             actions: HashMap::from([(
@@ -410,6 +590,7 @@ namespace Baz {action "Foo" appliesTo {
                         resource_types: Some(vec![]),
                         principal_types: Some(vec!["a".parse().unwrap()]),
                         context: AttributesOrContext::default(),
+                        context_defaults: None,
                     }),
                     member_of: None,
                 },
@@ -896,7 +1077,7 @@ namespace Baz {action "Foo" appliesTo {
         assert_labeled_span("type t =", "expected `{`, identifier, or `Set`");
         assert_labeled_span(
             "entity User {",
-            "expected `}`, identifier, or string literal",
+            "expected `@`, `}`, identifier, or string literal",
         );
         assert_labeled_span("entity User { name:", "expected `{`, identifier, or `Set`");
     }
@@ -14,10 +14,14 @@
  * limitations under the License.
  */
 
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
 
 use cedar_policy_core::{
-    ast::{Id, Name},
+    ast::{Id, Name, RestrictedExpr},
+    entities::CedarValueJson,
     parser::{Loc, Node},
 };
 use itertools::Either;
@@ -207,6 +211,7 @@ impl<'a> ConversionContext<'a> {
                 resource_types: Some(vec![]),
                 principal_types: Some(vec![]),
                 context: AttributesOrContext::default(),
+                context_defaults: None,
             });
         let member_of = parents.map(|parents| self.convert_parents(parents));
         let ty = ActionType {
@@ -240,6 +245,7 @@ impl<'a> ConversionContext<'a> {
         let mut principal_types: Option<Node<Vec<Name>>> = None;
         let mut resource_types: Option<Node<Vec<Name>>> = None;
         let mut context: Option<Node<AttributesOrContext>> = None;
+        let mut context_defaults = None;
 
         for decl in decls {
             match decl {
@@ -255,10 +261,9 @@ impl<'a> ConversionContext<'a> {
                         .into());
                     }
                     None => {
-                        context = Some(Node::with_source_loc(
-                            self.convert_context_decl(context_decl)?,
-                            loc,
-                        ));
+                        let (context_decl, defaults) = self.convert_context_decl(context_decl)?;
+                        context = Some(Node::with_source_loc(context_decl, loc));
+                        context_defaults = defaults;
                     }
                 },
                 Node {
@@ -320,6 +325,7 @@ impl<'a> ConversionContext<'a> {
             resource_types: resource_types.map(|tys| tys.node),
             principal_types: principal_types.map(|tys| tys.node),
             context: context.map(|c| c.node).unwrap_or_default(),
+            context_defaults,
         })
     }
 
@@ -332,6 +338,7 @@ impl<'a> ConversionContext<'a> {
             names,
             member_of_types,
             attrs,
+            mutually_exclusive,
         } = e;
         // First build up the defined entity type
         let member_of_types = member_of_types.into_iter().map(|p| p.into()).collect();
@@ -339,6 +346,8 @@ impl<'a> ConversionContext<'a> {
         let etype = EntityType {
             member_of_types,
             shape,
+            derived_attributes: None,
+            mutually_exclusive: (!mutually_exclusive.is_empty()).then_some(mutually_exclusive),
         };
 
         // Then map over all of the bound names
@@ -363,23 +372,65 @@ impl<'a> ConversionContext<'a> {
         )))
     }
 
-    /// Create a context decl
+    /// Create a context decl, along with the defaults given by any `@default`
+    /// annotations on its attributes
     fn convert_context_decl(
         &self,
         decl: Either<Path, Vec<Node<AttrDecl>>>,
-    ) -> Result<AttributesOrContext, ToJsonSchemaErrors> {
-        Ok(AttributesOrContext(match decl {
-            Either::Left(p) => SchemaType::TypeDef {
-                type_name: p.into(),
-            },
-            Either::Right(attrs) => SchemaType::Type(SchemaTypeVariant::Record {
-                attributes: collect_all_errors(
-                    attrs.into_iter().map(|attr| self.convert_attr_decl(attr)),
-                )?
-                .collect(),
-                additional_attributes: false,
-            }),
-        }))
+    ) -> Result<
+        (
+            AttributesOrContext,
+            Option<HashMap<SmolStr, CedarValueJson>>,
+        ),
+        ToJsonSchemaErrors,
+    > {
+        match decl {
+            Either::Left(p) => Ok((
+                AttributesOrContext(SchemaType::TypeDef {
+                    type_name: p.into(),
+                }),
+                None,
+            )),
+            Either::Right(attrs) => {
+                let mut attributes = BTreeMap::new();
+                let mut defaults = HashMap::new();
+                for (name, ty, default) in collect_all_errors(
+                    attrs
+                        .into_iter()
+                        .map(|attr| self.convert_context_attr_decl(attr)),
+                )? {
+                    if let Some(default) = default {
+                        defaults.insert(name.clone(), default);
+                    }
+                    attributes.insert(name, ty);
+                }
+                Ok((
+                    AttributesOrContext(SchemaType::Type(SchemaTypeVariant::Record {
+                        attributes,
+                        additional_attributes: false,
+                    })),
+                    (!defaults.is_empty()).then_some(defaults),
+                ))
+            }
+        }
+    }
+
+    /// Convert an attribute of a context record, which may have a `@default`
+    /// annotation if it is optional
+    fn convert_context_attr_decl(
+        &self,
+        mut attr: Node<AttrDecl>,
+    ) -> Result<(SmolStr, TypeOfAttribute, Option<CedarValueJson>), ToJsonSchemaErrors> {
+        let default = attr.node.default.take();
+        let (name, ty) = self.convert_attr_decl(attr)?;
+        let default = match default {
+            Some(default) if ty.required => {
+                return Err(ToJsonSchemaError::UnexpectedDefault(default.loc).into())
+            }
+            Some(default) => Some(convert_default(default)?),
+            None => None,
+        };
+        Ok((name, ty, default))
     }
 
     /// Convert an attribute type from an AttrDecl
@@ -387,7 +438,15 @@ impl<'a> ConversionContext<'a> {
         &self,
         attr: Node<AttrDecl>,
     ) -> Result<(SmolStr, TypeOfAttribute), ToJsonSchemaErrors> {
-        let AttrDecl { name, required, ty } = attr.node;
+        let AttrDecl {
+            name,
+            required,
+            ty,
+            default,
+        } = attr.node;
+        if let Some(default) = default {
+            return Err(ToJsonSchemaError::UnexpectedDefault(default.loc).into());
+        }
         Ok((
             name.node,
             TypeOfAttribute {
@@ -512,6 +571,17 @@ where
     }
 }
 
+/// Convert the restricted expression written in a `@default` annotation into
+/// its JSON representation
+fn convert_default(default: Node<SmolStr>) -> Result<CedarValueJson, ToJsonSchemaError> {
+    let invalid = |reason: String| ToJsonSchemaError::InvalidDefault {
+        value: default.clone(),
+        reason,
+    };
+    let expr = RestrictedExpr::from_str(&default.node).map_err(|e| invalid(e.to_string()))?;
+    CedarValueJson::from_expr(expr.as_borrowed()).map_err(|e| invalid(e.to_string()))
+}
+
 /// Search the cedar namespace, the things that live here are cedar builtins, unless overridden within a context.
 fn search_cedar_namespace(name: Id, loc: Loc) -> Result<SchemaType, ToJsonSchemaError> {
     match name.as_ref() {
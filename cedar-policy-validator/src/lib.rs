@@ -17,20 +17,33 @@
 //! Validator for Cedar policies
 #![forbid(unsafe_code)]
 
-use cedar_policy_core::ast::{Policy, PolicySet, Template};
+use cedar_policy_core::ast::{ExprKind, Policy, PolicySet, Template};
+use cedar_policy_core::extensions::Extensions;
+use expr_iterator::{policy_entity_type_names, policy_entity_uids};
+use itertools::Itertools;
 use serde::Serialize;
 use std::collections::HashSet;
 
 mod err;
 pub use err::*;
+mod accessed_attrs;
+#[cfg(feature = "codegen")]
+pub mod codegen;
 mod coreschema;
 pub use coreschema::*;
+mod context_checks;
+mod entity_validation;
+pub use entity_validation::*;
 mod expr_iterator;
 mod extension_schema;
 mod extensions;
 mod fuzzy_match;
+mod validation_cache;
+pub use validation_cache::ValidationCache;
 mod validation_result;
 pub use validation_result::*;
+mod link_validation;
+pub use link_validation::*;
 mod rbac;
 mod schema;
 pub use schema::*;
@@ -40,13 +53,15 @@ mod str_checks;
 pub use str_checks::confusable_string_checks;
 mod type_error;
 pub use type_error::*;
+mod typed_policy;
+pub use typed_policy::TypedPolicy;
 pub mod human_schema;
 pub mod typecheck;
 use typecheck::Typechecker;
 pub mod types;
 
 /// Used to select how a policy will be validated.
-#[derive(Default, Eq, PartialEq, Copy, Clone, Debug, Serialize)]
+#[derive(Default, Eq, PartialEq, Copy, Clone, Hash, Debug, Serialize)]
 pub enum ValidationMode {
     #[default]
     Strict,
@@ -78,16 +93,116 @@ impl ValidationMode {
 }
 
 /// Structure containing the context needed for policy validation. This is
-/// currently only the `EntityType`s and `ActionType`s from a single schema.
+/// currently only the `EntityType`s and `ActionType`s from a single schema,
+/// or from the union of several schemas.
 #[derive(Debug)]
 pub struct Validator {
     schema: ValidatorSchema,
+    /// Where each entity type and action was declared, when validating
+    /// against several schemas
+    provenance: Option<SchemaProvenance>,
+    /// See [`Validator::with_strict_closed_attributes`]
+    strict_closed_attributes: bool,
+    /// See [`Validator::with_closed_context`]
+    closed_context: bool,
+    /// See [`Validator::with_unknown_functions_as_warnings`]
+    unknown_functions_as_warnings: bool,
 }
 
 impl Validator {
     /// Construct a new Validator from a schema file.
     pub fn new(schema: ValidatorSchema) -> Validator {
-        Self { schema }
+        Self {
+            schema,
+            provenance: None,
+            strict_closed_attributes: false,
+            closed_context: false,
+            unknown_functions_as_warnings: false,
+        }
+    }
+
+    /// Construct a Validator which validates policies against the union of
+    /// `schemas`, without first merging them into a single schema. A policy
+    /// may reference entity types and actions declared by any of the schemas.
+    ///
+    /// An entity type or action declared by more than one schema uses the
+    /// declaration from the first of them, except that its descendants are
+    /// those given by any of them. Policies referencing such a name get an
+    /// [`ValidationWarningKind::AmbiguousSchemaName`] warning. Use
+    /// [`Validator::provenance`] to find which schemas declare a name.
+    ///
+    /// This fails if the action hierarchies of the schemas together contain a
+    /// cycle.
+    pub fn from_schemas(schemas: &[ValidatorSchema]) -> Result<Validator> {
+        let (schema, provenance) = ValidatorSchema::union(schemas)?;
+        Ok(Self {
+            schema,
+            provenance: Some(provenance),
+            strict_closed_attributes: false,
+            closed_context: false,
+            unknown_functions_as_warnings: false,
+        })
+    }
+
+    /// Set whether partial validation treats entity types with closed
+    /// attributes strictly.
+    ///
+    /// In partial validation, accessing an attribute which the schema does not
+    /// declare is an error if the entity is of an entity type with closed
+    /// attributes (`"additionalAttributes": false`, the default), and is
+    /// allowed if it is of an entity type with open attributes or of an
+    /// undeclared entity type. But when the entity may be of several entity
+    /// types, e.g., `(if c then User::"alice" else Group::"admins").name`, the
+    /// access is allowed as long as any of them has open attributes, even if
+    /// another has closed attributes and does not declare the attribute.
+    ///
+    /// When `strict` is `true`, such an access is an error unless every entity
+    /// type with closed attributes that the entity may be of declares the
+    /// attribute. Entity types with open attributes stay permissive. This has
+    /// no effect in strict or permissive validation, where accessing an
+    /// undeclared attribute is always an error. The default is `false`.
+    pub fn with_strict_closed_attributes(mut self, strict: bool) -> Self {
+        self.strict_closed_attributes = strict;
+        self
+    }
+
+    /// Set whether validation treats the `context` of every action as a
+    /// closed record, reporting an `UndeclaredContextAttribute` error for
+    /// every access to a `context` attribute, with `.` or `has`, which is not
+    /// declared in the context of an action the policy applies to.
+    ///
+    /// Without this, `context has attr` is allowed for an undeclared `attr`
+    /// (it is just `false`), so a misspelled attribute in a `has` test goes
+    /// unnoticed, and a policy applying to several actions may access an
+    /// attribute declared for only some of them, as long as it tests for it
+    /// first. With it, the policy must only access attributes declared for
+    /// all the actions it applies to. The default is `false`.
+    pub fn with_closed_context(mut self, closed: bool) -> Self {
+        self.closed_context = closed;
+        self
+    }
+
+    /// Set whether a call to a function which is not a known extension
+    /// function is reported as an `UnknownFunction` warning instead of an
+    /// error. This is for policies written for a build of Cedar with more
+    /// extensions enabled, parsed with
+    /// `parse_policyset_allowing_unknown_functions`: such a call is an error
+    /// when evaluated, so it is given the type `Never`, and the rest of the
+    /// policy is still typechecked.
+    ///
+    /// This weakens the guarantees of validation, since a policy which
+    /// validates may now error when evaluated, and a misspelled function name
+    /// is no longer caught. It should only be used during a controlled
+    /// migration. The default is `false`.
+    pub fn with_unknown_functions_as_warnings(mut self, allow: bool) -> Self {
+        self.unknown_functions_as_warnings = allow;
+        self
+    }
+
+    /// The schemas declaring each entity type and action, if this Validator
+    /// was constructed with [`Validator::from_schemas`].
+    pub fn provenance(&self) -> Option<&SchemaProvenance> {
+        self.provenance.as_ref()
     }
 
     /// Validate all templates, links, and static policies in a policy set.
@@ -110,6 +225,29 @@ impl Validator {
         )
     }
 
+    /// Return the validation errors for `policies` which are found in strict
+    /// mode but not in permissive mode, in the order strict validation finds
+    /// them. These are the errors which must be fixed before switching from
+    /// permissive to strict validation.
+    ///
+    /// An error is considered to be found in both modes if permissive
+    /// validation finds an error of the same kind in the same policy.
+    pub fn validation_delta(&self, policies: &PolicySet) -> Vec<ValidationError> {
+        let permissive = self.validate(policies, ValidationMode::Permissive);
+        // `ValidationErrorKind` isn't `Hash`, and there are usually few errors
+        let permissive_errors: Vec<_> = permissive
+            .validation_errors()
+            .map(|err| (err.location().policy_id(), err.error_kind()))
+            .collect();
+        self.validate(policies, ValidationMode::Strict)
+            .into_errors_and_warnings()
+            .0
+            .filter(|err| {
+                !permissive_errors.contains(&(err.location().policy_id(), err.error_kind()))
+            })
+            .collect()
+    }
+
     /// Run all validations against a single static policy or template (note
     /// that Core `Template` includes static policies as well), gathering all
     /// validation errors and warnings in the returned iterators.
@@ -141,7 +279,88 @@ impl Validator {
         .into_iter()
         .flatten();
         let (type_errors, warnings) = self.typecheck_policy(p, mode);
-        (validation_errors.chain(type_errors), warnings)
+        let context_errors = self
+            .closed_context
+            .then(|| self.validate_closed_context(p))
+            .into_iter()
+            .flatten();
+        (
+            validation_errors.chain(type_errors).chain(context_errors),
+            warnings
+                .chain(self.validate_schema_provenance(p))
+                .chain(self.validate_unknown_functions(p)),
+        )
+    }
+
+    /// With `unknown_functions_as_warnings`, generate an `UnknownFunction`
+    /// warning for every call in the policy to a function which is not a
+    /// known extension function.
+    fn validate_unknown_functions<'a>(
+        &'a self,
+        p: &'a Template,
+    ) -> impl Iterator<Item = ValidationWarning> + 'a {
+        let extensions = Extensions::all_available();
+        self.unknown_functions_as_warnings
+            .then(|| p.non_scope_constraints().subexpressions())
+            .into_iter()
+            .flatten()
+            .filter_map(move |e| match e.expr_kind() {
+                ExprKind::ExtensionFunctionApp { fn_name, .. }
+                    if extensions.func(fn_name).is_err() =>
+                {
+                    Some(ValidationWarning::with_policy_id(
+                        p.id().clone(),
+                        e.source_loc().cloned(),
+                        ValidationWarningKind::UnknownFunction(fn_name.to_string()),
+                    ))
+                }
+                _ => None,
+            })
+    }
+
+    /// When validating against several schemas, generate an
+    /// `AmbiguousSchemaName` warning for every entity type or action in the
+    /// policy which is declared by more than one of them.
+    fn validate_schema_provenance<'a>(
+        &'a self,
+        p: &'a Template,
+    ) -> impl Iterator<Item = ValidationWarning> + 'a {
+        self.provenance.iter().flat_map(move |provenance| {
+            let entity_types = policy_entity_type_names(p)
+                .unique()
+                .map(|ety| {
+                    (
+                        ety.to_string(),
+                        ety.loc(),
+                        provenance.entity_type_sources(ety),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let actions = policy_entity_uids(p)
+                .unique()
+                .map(|euid| {
+                    (
+                        euid.to_string(),
+                        euid.loc(),
+                        provenance.action_sources(euid),
+                    )
+                })
+                .collect::<Vec<_>>();
+            entity_types
+                .into_iter()
+                .chain(actions)
+                .filter(|(_, _, sources)| sources.len() > 1)
+                .map(move |(name, loc, sources)| {
+                    ValidationWarning::with_policy_id(
+                        p.id().clone(),
+                        loc.cloned(),
+                        ValidationWarningKind::AmbiguousSchemaName {
+                            name,
+                            schemas: sources.to_vec(),
+                        },
+                    )
+                })
+        })
     }
 
     /// Run relevant validations against a single template-linked policy,
@@ -158,19 +377,50 @@ impl Validator {
         // In partial validation, there may be arbitrary extra entity types and
         // actions, so we can never claim that one doesn't exist or that the
         // action application is invalid.
-        if mode.is_partial() {
-            return None;
-        }
-        // For template-linked policies `Policy::principal_constraint()` and
-        // `Policy::resource_constraint()` return a copy of the constraint with
-        // the slot filled by the appropriate value.
+        let scope_errors = if mode.is_partial() {
+            None
+        } else {
+            // For template-linked policies `Policy::principal_constraint()` and
+            // `Policy::resource_constraint()` return a copy of the constraint
+            // with the slot filled by the appropriate value.
+            Some(
+                self.validate_entity_types_in_slots(p.env())
+                    .map(move |note| ValidationError::with_policy_id(p.id().clone(), None, note))
+                    .chain(self.validate_linked_action_application(p)),
+            )
+        };
         Some(
-            self.validate_entity_types_in_slots(p.env())
-                .map(move |note| ValidationError::with_policy_id(p.id().clone(), None, note))
-                .chain(self.validate_linked_action_application(p)),
+            scope_errors
+                .into_iter()
+                .flatten()
+                .chain(self.typecheck_link(p, mode)),
         )
     }
 
+    /// Detect any type errors in the template-linked policy `p` with its slots
+    /// bound to the entity types of the entities it is linked to. These are
+    /// also errors for its template, but are reported for `p` so that it's
+    /// clear which links of a template are affected by them.
+    fn typecheck_link<'a>(
+        &'a self,
+        p: &'a Policy,
+        mode: ValidationMode,
+    ) -> impl Iterator<Item = ValidationError> + 'a {
+        let typecheck = Typechecker::new(&self.schema, mode)
+            .with_strict_closed_attributes(self.strict_closed_attributes)
+            .with_unknown_functions_as_warnings(self.unknown_functions_as_warnings);
+        let mut type_errors = HashSet::new();
+        typecheck.typecheck_link(p.template(), p.env(), &mut type_errors);
+        type_errors.into_iter().map(|type_error| {
+            let (kind, location) = type_error.kind_and_location();
+            ValidationError::with_policy_id(
+                p.id().clone(),
+                location,
+                ValidationErrorKind::type_error(kind),
+            )
+        })
+    }
+
     /// Construct a Typechecker instance and use it to detect any type errors in
     /// the argument static policy or template (note that Core `Template`
     /// includes static policies as well) in the context of the schema for this
@@ -184,7 +434,9 @@ impl Validator {
         impl Iterator<Item = ValidationError> + 'a,
         impl Iterator<Item = ValidationWarning> + 'a,
     ) {
-        let typecheck = Typechecker::new(&self.schema, mode);
+        let typecheck = Typechecker::new(&self.schema, mode)
+            .with_strict_closed_attributes(self.strict_closed_attributes)
+            .with_unknown_functions_as_warnings(self.unknown_functions_as_warnings);
         let mut type_errors = HashSet::new();
         let mut warnings = HashSet::new();
         typecheck.typecheck_policy(t, &mut type_errors, &mut warnings);
@@ -211,6 +463,7 @@ mod test {
     use super::*;
     use cedar_policy_core::{
         ast::{self, Expr},
+        extensions::Extensions,
         parser,
     };
 
@@ -227,6 +480,8 @@ mod test {
                     EntityType {
                         member_of_types: vec![],
                         shape: AttributesOrContext::default(),
+                        derived_attributes: None,
+                        mutually_exclusive: None,
                     },
                 ),
                 (
@@ -234,6 +489,8 @@ mod test {
                     EntityType {
                         member_of_types: vec![],
                         shape: AttributesOrContext::default(),
+                        derived_attributes: None,
+                        mutually_exclusive: None,
                     },
                 ),
             ],
@@ -244,6 +501,7 @@ mod test {
                         resource_types: None,
                         principal_types: None,
                         context: AttributesOrContext::default(),
+                        context_defaults: None,
                     }),
                     member_of: None,
                     attributes: None,
@@ -457,6 +715,53 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn link_type_errors() {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity Group, Team;
+            entity User in [Group] = { level: Long };
+            entity Bot in [Team];
+            action act appliesTo { principal: [User, Bot], resource: User };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let mut set = PolicySet::new();
+        let t = parser::parse_policy_template(
+            Some("template".to_string()),
+            "permit(principal in ?principal, action, resource) when { principal.level > 0 };",
+        )
+        .unwrap();
+        set.add_template(t).unwrap();
+        for (link, ty) in [("group_link", "Group"), ("team_link", "Team")] {
+            set.link(
+                ast::PolicyID::from_string("template"),
+                ast::PolicyID::from_string(link),
+                HashMap::from([(
+                    ast::SlotId::principal(),
+                    ast::EntityUID::with_eid_and_type(ty, "x").unwrap(),
+                )]),
+            )
+            .unwrap();
+        }
+
+        // Principals in a `Team` are `Bot`s, which don't have a `level`. The
+        // error is reported for the template and for the link to a `Team`.
+        let result = validator.validate(&set, ValidationMode::default());
+        let mut ids = result
+            .validation_errors()
+            .map(|err| err.location().policy_id().to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec!["team_link", "template"]);
+        assert!(result.validation_errors().all(|err| matches!(
+            err.error_kind(),
+            ValidationErrorKind::TypeError(TypeErrorKind::UnsafeAttributeAccess(_))
+        )));
+    }
+
     #[test]
     fn validate_finds_warning_and_error() {
         let schema: ValidatorSchema = serde_json::from_str::<SchemaFragment>(
@@ -517,4 +822,352 @@ mod test {
             )]
         );
     }
+
+    #[test]
+    fn validate_against_multiple_schemas() {
+        let (photos, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity User, Photo;
+            action view appliesTo { principal: User, resource: Photo };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let (docs, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity User = { name: String };
+            entity Doc;
+            action edit appliesTo { principal: User, resource: Doc };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::from_schemas(&[photos, docs]).unwrap();
+        let provenance = validator.provenance().unwrap();
+        assert_eq!(
+            provenance.entity_type_sources(&"Doc".parse().unwrap()),
+            &[1]
+        );
+
+        let set = parser::parse_policyset(
+            r#"
+            permit(principal, action == Action::"view", resource is Photo);
+            permit(principal, action == Action::"edit", resource == Doc::"d");
+            permit(principal == User::"alice", action == Action::"edit", resource);
+            permit(principal, action == Action::"delete", resource);
+            "#,
+        )
+        .unwrap();
+        let result = validator.validate(&set, ValidationMode::default());
+        // Only `Action::"delete"` is unknown to both schemas
+        assert!(result
+            .validation_errors()
+            .all(|err| err.location().policy_id().to_string() == "policy3"));
+        assert!(result.validation_errors().any(|err| err.error_kind()
+            == &ValidationErrorKind::unrecognized_action_id(
+                r#"Action::"delete""#.to_string(),
+                Some(r#"Action::"edit""#.to_string())
+            )));
+        // `User` is declared by both schemas
+        assert_eq!(
+            result
+                .validation_warnings()
+                .filter(|warn| warn.kind() != &ValidationWarningKind::ImpossiblePolicy)
+                .map(|warn| (warn.location().policy_id().to_string(), warn.kind()))
+                .collect::<Vec<_>>(),
+            vec![(
+                "policy2".to_string(),
+                &ValidationWarningKind::AmbiguousSchemaName {
+                    name: "User".to_string(),
+                    schemas: vec![0, 1]
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn validation_delta() {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity User = { age: Long };
+            entity Photo;
+            action view appliesTo { principal: User, resource: Photo };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let set = parser::parse_policyset(
+            r#"
+            permit(principal, action, resource) when { principal.age > 18 };
+            permit(principal, action, resource) when { [principal, resource].contains(resource) };
+            permit(principal, action, resource) when { principal.age == "18" };
+            permit(principal, action, resource) when { principal.name == "alice" };
+            "#,
+        )
+        .unwrap();
+        let strict = validator.validate(&set, ValidationMode::Strict);
+        let permissive = validator.validate(&set, ValidationMode::Permissive);
+        assert!(permissive.validation_errors().count() < strict.validation_errors().count());
+
+        // The heterogeneous set and the comparison of a `Long` with a `String`
+        // are only errors in strict mode. The undeclared attribute is an error
+        // in both modes.
+        let delta = validator.validation_delta(&set);
+        assert_eq!(
+            delta
+                .iter()
+                .map(|err| err.location().policy_id().to_string())
+                .sorted()
+                .collect::<Vec<_>>(),
+            vec!["policy1", "policy2"]
+        );
+        assert!(delta
+            .iter()
+            .all(|err| !permissive.validation_errors().any(|p| p == err)));
+        assert!(delta
+            .iter()
+            .all(|err| strict.validation_errors().any(|s| s == err)));
+
+        // A policy set which is valid in strict mode has no delta
+        let set = parser::parse_policyset(
+            r#"permit(principal, action, resource) when { principal.age > 18 };"#,
+        )
+        .unwrap();
+        assert!(validator.validation_delta(&set).is_empty());
+    }
+
+    #[test]
+    fn closed_context() {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity User;
+            action view appliesTo { principal: User, resource: User, context: { ip: ipaddr, mfa?: Bool } };
+            action edit appliesTo { principal: User, resource: User, context: { mfa: Bool } };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let set = parser::parse_policyset(
+            r#"
+            permit(principal, action == Action::"view", resource) when { context has mfa && context.mfa };
+            permit(principal, action == Action::"view", resource) when { context has mfaa };
+            permit(principal, action in [Action::"view", Action::"edit"], resource) when { context has ip && context.ip.isLoopback() };
+            "#,
+        )
+        .unwrap();
+
+        // `has` tests for undeclared attributes are allowed by default
+        let validator = Validator::new(schema);
+        assert!(validator
+            .validate(&set, ValidationMode::Strict)
+            .validation_passed());
+
+        let validator = validator.with_closed_context(true);
+        let result = validator.validate(&set, ValidationMode::Strict);
+        let errors = result
+            .validation_errors()
+            .map(|err| {
+                (
+                    err.location().policy_id().to_string(),
+                    err.error_kind().clone(),
+                )
+            })
+            .sorted_by_key(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "policy1".to_string(),
+                    ValidationErrorKind::undeclared_context_attribute(
+                        r#"Action::"view""#.to_string(),
+                        "mfaa".to_string(),
+                        "{ ip: ipaddr, mfa?: Bool }".to_string(),
+                        Some("mfa".to_string()),
+                    )
+                ),
+                (
+                    "policy2".to_string(),
+                    ValidationErrorKind::undeclared_context_attribute(
+                        r#"Action::"edit""#.to_string(),
+                        "ip".to_string(),
+                        "{ mfa: Bool }".to_string(),
+                        Some("mfa".to_string()),
+                    )
+                ),
+                (
+                    "policy2".to_string(),
+                    ValidationErrorKind::undeclared_context_attribute(
+                        r#"Action::"edit""#.to_string(),
+                        "ip".to_string(),
+                        "{ mfa: Bool }".to_string(),
+                        Some("mfa".to_string()),
+                    )
+                ),
+            ]
+        );
+        let (_, kind) = &errors[0];
+        assert_eq!(
+            kind.to_string(),
+            r#"attribute `mfaa` is not declared in the context of action `Action::"view"`"#
+        );
+        assert_eq!(
+            miette::Diagnostic::help(kind).unwrap().to_string(),
+            r#"did you mean `mfa`? the context of action `Action::"view"` has type `{ ip: ipaddr, mfa?: Bool }`"#
+        );
+        assert_eq!(
+            kind.error_code(),
+            "cedar::validation::undeclared_context_attribute"
+        );
+    }
+
+    #[test]
+    fn unknown_functions_as_warnings() {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity User = { name: String };
+            action view appliesTo { principal: User, resource: User };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let (_, set, _) = parser::parse_policyset_allowing_unknown_functions(
+            r#"
+            permit(principal, action, resource) when { datetime("2024-10-15") == principal.name };
+            permit(principal, action, resource) when { datetime(principal.nmae) };
+            "#,
+        )
+        .unwrap();
+
+        let validator = Validator::new(schema);
+        let result = validator.validate(&set, ValidationMode::Strict);
+        assert!(result.validation_errors().any(|err| matches!(
+            err.error_kind(),
+            ValidationErrorKind::TypeError(TypeErrorKind::UndefinedFunction(_))
+        )));
+
+        // The calls are warnings, but the rest of the policies are still
+        // typechecked
+        let validator = validator.with_unknown_functions_as_warnings(true);
+        let result = validator.validate(&set, ValidationMode::Strict);
+        let errors = result
+            .validation_errors()
+            .map(|err| err.location().policy_id().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(errors, vec!["policy1"]);
+        let warnings = result
+            .validation_warnings()
+            .map(|warning| {
+                (
+                    warning.location().policy_id().to_string(),
+                    warning.kind().clone(),
+                )
+            })
+            .sorted_by_key(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    "policy0".to_string(),
+                    ValidationWarningKind::UnknownFunction("datetime".to_string())
+                ),
+                (
+                    "policy1".to_string(),
+                    ValidationWarningKind::UnknownFunction("datetime".to_string())
+                ),
+            ]
+        );
+        assert_eq!(
+            warnings[0].1.warning_code(),
+            "cedar::validation::unknown_function"
+        );
+    }
+
+    #[test]
+    fn validate_cached() {
+        let schema_text = r#"
+            entity User = { name: String };
+            action view appliesTo { principal: User, resource: User };
+        "#;
+        let (schema, _) =
+            ValidatorSchema::from_str_natural(schema_text, Extensions::all_available()).unwrap();
+        let validator = Validator::new(schema);
+        let errors_and_warnings = |result: ValidationResult| {
+            let (errors, warnings) = result.into_errors_and_warnings();
+            (errors.collect::<Vec<_>>(), warnings.collect::<Vec<_>>())
+        };
+        let mut cache = ValidationCache::new();
+
+        let set = parser::parse_policyset(
+            r#"
+            permit(principal, action, resource) when { principal.name == "alice" };
+            permit(principal, action, resource) when { principal.nmae == "bob" };
+            "#,
+        )
+        .unwrap();
+        let cached = validator.validate_cached(&set, ValidationMode::Strict, &mut cache);
+        assert_eq!(
+            errors_and_warnings(cached),
+            errors_and_warnings(validator.validate(&set, ValidationMode::Strict))
+        );
+        assert_eq!(cache.len(), 2);
+
+        // Changing the length of the first policy moves the second one, whose
+        // cached error must now point into the new policy text
+        let set = parser::parse_policyset(
+            r#"
+            permit(principal, action, resource) when { principal.name == "alice" || principal.name == "carol" };
+            permit(principal, action, resource) when { principal.nmae == "bob" };
+            permit(principal, action, resource) when { principal.name == 1 };
+            "#,
+        )
+        .unwrap();
+        let cached = validator.validate_cached(&set, ValidationMode::Strict, &mut cache);
+        let (errors, warnings) = errors_and_warnings(cached);
+        assert_eq!(
+            (errors.clone(), warnings),
+            errors_and_warnings(validator.validate(&set, ValidationMode::Strict))
+        );
+        assert_eq!(
+            errors
+                .iter()
+                .map(|err| err.location().source_loc().unwrap().snippet().unwrap())
+                .sorted()
+                .collect::<Vec<_>>(),
+            vec!["principal.name == 1", "principal.nmae"]
+        );
+        assert_eq!(cache.len(), 3);
+
+        // Removing a policy removes its entry
+        let set = parser::parse_policyset(
+            r#"permit(principal, action, resource) when { principal.name == "alice" };"#,
+        )
+        .unwrap();
+        let cached = validator.validate_cached(&set, ValidationMode::Strict, &mut cache);
+        assert!(cached.validation_passed());
+        assert_eq!(cache.len(), 1);
+
+        // A different schema or mode invalidates the cached results
+        let set = parser::parse_policyset(
+            r#"permit(principal, action, resource) when { principal.nmae == "bob" };"#,
+        )
+        .unwrap();
+        let cached = validator.validate_cached(&set, ValidationMode::Strict, &mut cache);
+        assert!(!cached.validation_passed());
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            &schema_text.replace("name", "nmae"),
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let other_validator = Validator::new(schema);
+        let cached = other_validator.validate_cached(&set, ValidationMode::Strict, &mut cache);
+        assert!(cached.validation_passed());
+        let cached = validator.validate_cached(&set, ValidationMode::Permissive, &mut cache);
+        assert!(!cached.validation_passed());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
 }
@@ -0,0 +1,161 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Contains the checks that the slot values of template-linked policies are
+//! still valid for their templates and the schema.
+
+use std::collections::{BTreeSet, HashSet};
+
+use cedar_policy_core::ast::{self, EntityUID, Name, Policy, PolicyID, PolicySet, SlotId};
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::Validator;
+
+/// A problem with one slot of a template-linked policy, as found by
+/// [`Validator::validate_links`]
+#[derive(Debug, Clone, PartialEq, Eq, Error, Diagnostic)]
+#[error("in policy `{link_id}` linked from template `{template_id}`, {kind}")]
+pub struct LinkValidationError {
+    template_id: PolicyID,
+    link_id: PolicyID,
+    slot: SlotId,
+    kind: LinkValidationErrorKind,
+}
+
+impl LinkValidationError {
+    /// The id of the template the policy is linked from
+    pub fn template_id(&self) -> &PolicyID {
+        &self.template_id
+    }
+
+    /// The id of the template-linked policy
+    pub fn link_id(&self) -> &PolicyID {
+        &self.link_id
+    }
+
+    /// The slot with the problem
+    pub fn slot(&self) -> SlotId {
+        self.slot
+    }
+
+    /// The problem with the slot
+    pub fn kind(&self) -> &LinkValidationErrorKind {
+        &self.kind
+    }
+}
+
+/// The problem with a slot of a template-linked policy
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LinkValidationErrorKind {
+    /// The template has the slot, but the policy does not link it to a value
+    #[error("slot `{0}` is not filled")]
+    Unfilled(SlotId),
+    /// The slot is linked to an entity whose type is not declared in the schema
+    #[error("slot `{0}` is linked to `{1}`, whose entity type is not declared in the schema")]
+    UndeclaredEntityType(SlotId, EntityUID),
+    /// The slot is linked to an entity for which the scope of the template
+    /// matches no principal (or resource) that its actions apply to
+    #[error("slot `{0}` is linked to `{1}`, which has the wrong entity type for the actions of the template")]
+    MismatchedEntityType(SlotId, EntityUID),
+}
+
+impl Validator {
+    /// Check every template-linked policy in `policies` against its template
+    /// and the schema, reporting each slot of the template which the policy
+    /// does not fill, or which it links to an entity of an undeclared or
+    /// inapplicable entity type. The errors are ordered by template id, then
+    /// by policy id, then by slot.
+    pub fn validate_links(&self, policies: &PolicySet) -> Vec<LinkValidationError> {
+        let mut errors = policies
+            .policies()
+            .filter(|p| !p.is_static())
+            .flat_map(|p| self.validate_link(p))
+            .collect::<Vec<_>>();
+        errors.sort_by(|e1, e2| {
+            (&e1.template_id, &e1.link_id, e1.slot).cmp(&(&e2.template_id, &e2.link_id, e2.slot))
+        });
+        errors
+    }
+
+    fn validate_link<'a>(
+        &'a self,
+        p: &'a Policy,
+    ) -> impl Iterator<Item = LinkValidationError> + 'a {
+        let slots = p
+            .template()
+            .slots()
+            .map(|slot| slot.id)
+            .collect::<BTreeSet<_>>();
+        slots.into_iter().filter_map(move |slot| {
+            let kind = match p.env().get(&slot) {
+                None => LinkValidationErrorKind::Unfilled(slot),
+                Some(euid) => match euid.entity_type() {
+                    ast::EntityType::Specified(name) if self.schema.is_known_entity_type(name) => {
+                        if self.slot_matches_actions(p, slot) {
+                            return None;
+                        }
+                        LinkValidationErrorKind::MismatchedEntityType(slot, euid.clone())
+                    }
+                    _ => LinkValidationErrorKind::UndeclaredEntityType(slot, euid.clone()),
+                },
+            };
+            Some(LinkValidationError {
+                template_id: p.template().id().clone(),
+                link_id: p.id().clone(),
+                slot,
+                kind,
+            })
+        })
+    }
+
+    /// Check if, with `slot` filled, the scope of `p` matches any principal (or
+    /// resource) that one of the actions in its scope applies to
+    fn slot_matches_actions(&self, p: &Policy, slot: SlotId) -> bool {
+        let apply_specs = self
+            .get_apply_specs_for_action(p.action_constraint())
+            .collect::<Vec<_>>();
+        if slot.is_principal() {
+            let constraint = p.principal_constraint();
+            let types = self
+                .get_principals_satisfying_constraint(&constraint)
+                .collect::<HashSet<_>>();
+            apply_specs
+                .iter()
+                .any(|spec| any_type_in(spec.applicable_principal_types(), &types))
+        } else {
+            let constraint = p.resource_constraint();
+            let types = self
+                .get_resources_satisfying_constraint(&constraint)
+                .collect::<HashSet<_>>();
+            apply_specs
+                .iter()
+                .any(|spec| any_type_in(spec.applicable_resource_types(), &types))
+        }
+    }
+}
+
+/// Check if any of the `applicable` entity types of an action is in `types`.
+/// An unspecified entity type applies to every entity.
+fn any_type_in<'a>(
+    mut applicable: impl Iterator<Item = &'a ast::EntityType>,
+    types: &HashSet<&Name>,
+) -> bool {
+    applicable.any(|ty| match ty {
+        ast::EntityType::Specified(name) => types.contains(name),
+        ast::EntityType::Unspecified => true,
+    })
+}
@@ -366,7 +366,7 @@ impl Validator {
 
     /// Get the set of actions (action entity id strings) that satisfy the
     /// action scope constraint of the policy.
-    fn get_actions_satisfying_constraint<'a>(
+    pub(crate) fn get_actions_satisfying_constraint<'a>(
         &'a self,
         action_constraint: &'a ActionConstraint,
     ) -> Box<dyn Iterator<Item = &'a EntityUID> + 'a> {
@@ -551,6 +551,8 @@ mod test {
                 EntityType {
                     member_of_types: vec![],
                     shape: AttributesOrContext::default(),
+                    derived_attributes: None,
+                    mutually_exclusive: None,
                 },
             )],
             [],
@@ -587,6 +589,8 @@ mod test {
                 EntityType {
                     member_of_types: vec![],
                     shape: AttributesOrContext::default(),
+                    derived_attributes: None,
+                    mutually_exclusive: None,
                 },
             )],
             [],
@@ -674,6 +678,8 @@ mod test {
                 EntityType {
                     member_of_types: vec![],
                     shape: AttributesOrContext::default(),
+                    derived_attributes: None,
+                    mutually_exclusive: None,
                 },
             )],
             [],
@@ -698,6 +704,8 @@ mod test {
                 EntityType {
                     member_of_types: vec![],
                     shape: AttributesOrContext::default(),
+                    derived_attributes: None,
+                    mutually_exclusive: None,
                 },
             )],
             [],
@@ -722,6 +730,8 @@ mod test {
                 EntityType {
                     member_of_types: vec![],
                     shape: AttributesOrContext::default(),
+                    derived_attributes: None,
+                    mutually_exclusive: None,
                 },
             )],
             [],
@@ -1027,6 +1037,8 @@ mod test {
                 EntityType {
                     member_of_types: vec![],
                     shape: AttributesOrContext::default(),
+                    derived_attributes: None,
+                    mutually_exclusive: None,
                 },
             )],
             [],
@@ -1063,6 +1075,8 @@ mod test {
                     EntityType {
                         member_of_types: vec![],
                         shape: AttributesOrContext::default(),
+                        derived_attributes: None,
+                        mutually_exclusive: None,
                     },
                 ),
                 (
@@ -1070,6 +1084,8 @@ mod test {
                     EntityType {
                         member_of_types: vec![],
                         shape: AttributesOrContext::default(),
+                        derived_attributes: None,
+                        mutually_exclusive: None,
                     },
                 ),
             ],
@@ -1080,6 +1096,7 @@ mod test {
                         resource_types: Some(vec![resource_type.parse().unwrap()]),
                         principal_types: Some(vec![principal_type.parse().unwrap()]),
                         context: AttributesOrContext::default(),
+                        context_defaults: None,
                     }),
                     member_of: Some(vec![]),
                     attributes: None,
@@ -1420,6 +1437,8 @@ mod test {
                     EntityType {
                         member_of_types: vec![],
                         shape: AttributesOrContext::default(),
+                        derived_attributes: None,
+                        mutually_exclusive: None,
                     },
                 ),
                 (
@@ -1427,6 +1446,8 @@ mod test {
                     EntityType {
                         member_of_types: vec![resource_parent_type.parse().unwrap()],
                         shape: AttributesOrContext::default(),
+                        derived_attributes: None,
+                        mutually_exclusive: None,
                     },
                 ),
                 (
@@ -1434,6 +1455,8 @@ mod test {
                     EntityType {
                         member_of_types: vec![resource_grandparent_type.parse().unwrap()],
                         shape: AttributesOrContext::default(),
+                        derived_attributes: None,
+                        mutually_exclusive: None,
                     },
                 ),
                 (
@@ -1441,6 +1464,8 @@ mod test {
                     EntityType {
                         member_of_types: vec![],
                         shape: AttributesOrContext::default(),
+                        derived_attributes: None,
+                        mutually_exclusive: None,
                     },
                 ),
             ],
@@ -1452,6 +1477,7 @@ mod test {
                             resource_types: Some(vec![resource_type.parse().unwrap()]),
                             principal_types: Some(vec![principal_type.parse().unwrap()]),
                             context: AttributesOrContext::default(),
+                            context_defaults: None,
                         }),
                         member_of: Some(vec![ActionEntityUID {
                             ty: None,
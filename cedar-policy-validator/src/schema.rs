@@ -20,35 +20,55 @@
 //! `member_of` relation from the schema is reversed and the transitive closure is
 //! computed to obtain a `descendants` relation.
 
-use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
+use std::collections::{
+    hash_map::{DefaultHasher, Entry},
+    BTreeMap, BTreeSet, HashMap, HashSet,
+};
+use std::hash::{Hash, Hasher};
 
 use cedar_policy_core::{
-    ast::{Entity, EntityType, EntityUID, Name},
+    ast::{
+        Entity, EntityType, EntityUID, Expr, ExprKind, Name, PartialValue,
+        PartialValueSerializedAsExpr, Var,
+    },
     entities::{err::EntitiesError, Entities, TCComputation},
     extensions::Extensions,
+    parser::Loc,
     transitive_closure::compute_tc,
 };
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
+use smol_str::SmolStr;
 
 use super::NamespaceDefinition;
 use crate::{
     err::*,
-    human_schema::SchemaWarning,
-    types::{Attributes, EntityRecordKind, OpenTag, Type},
-    SchemaFragment, SchemaType, SchemaTypeVariant, TypeOfAttribute,
+    human_schema::{
+        parser::parse_schema, to_json_schema::custom_schema_to_json_schema, SchemaWarning,
+    },
+    typecheck::Typechecker,
+    types::{AttributeType, Attributes, EntityRecordKind, OpenTag, Type},
+    SchemaFragment, SchemaType, SchemaTypeVariant, TypeOfAttribute, ValidationMode,
 };
 
 mod action;
 pub use action::ValidatorActionId;
+#[cfg(feature = "arbitrary")]
+mod arbitrary_data;
 pub(crate) use action::ValidatorApplySpec;
+mod compatibility;
+pub use compatibility::{AttributeOwner, CompatibilityReport, SchemaChange};
+mod completion;
+pub use completion::CompletionContext;
 mod entity_type;
 pub use entity_type::ValidatorEntityType;
 mod namespace_def;
 pub(crate) use namespace_def::is_action_entity_type;
 pub use namespace_def::ValidatorNamespaceDef;
-#[cfg(test)]
+mod rebase;
+mod union;
 pub(crate) use namespace_def::ACTION_ENTITY_TYPE;
+pub use union::SchemaProvenance;
 
 // We do not have a formal model for action attributes, so we disable them by default.
 #[derive(Eq, PartialEq, Copy, Clone, Default)]
@@ -109,7 +129,7 @@ impl ValidatorSchemaFragment {
 }
 
 #[serde_as]
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct ValidatorSchema {
     /// Map from entity type names to the ValidatorEntityType object.
     #[serde(rename = "entityTypes")]
@@ -120,6 +140,12 @@ pub struct ValidatorSchema {
     #[serde(rename = "actionIds")]
     #[serde_as(as = "Vec<(_, _)>")]
     action_ids: HashMap<EntityUID, ValidatorActionId>,
+
+    /// Names of the common types declared in the schema. Their definitions
+    /// have already been substituted wherever they were referenced, so only
+    /// the names are kept.
+    #[serde(skip)]
+    common_types: HashSet<Name>,
 }
 
 impl std::str::FromStr for ValidatorSchema {
@@ -154,6 +180,7 @@ impl ValidatorSchema {
         Self {
             entity_types: HashMap::new(),
             action_ids: HashMap::new(),
+            common_types: HashSet::new(),
         }
     }
 
@@ -214,6 +241,40 @@ impl ValidatorSchema {
     pub fn from_schema_fragments(
         fragments: impl IntoIterator<Item = ValidatorSchemaFragment>,
     ) -> Result<ValidatorSchema> {
+        let (mut schema, deferred) = Self::from_schema_fragments_deferring_errors(fragments)?;
+        if let Some(err) = deferred.into_first_error() {
+            return Err(err);
+        }
+        schema.add_derived_attribute_types()?;
+        Ok(schema)
+    }
+
+    /// Like [`ValidatorSchema::from_schema_fragments`], but reporting all the
+    /// cycles and undeclared entity types and actions in the fragments
+    /// together, rather than only the first of them
+    fn from_schema_fragments_collecting_errors(
+        fragments: impl IntoIterator<Item = ValidatorSchemaFragment>,
+    ) -> std::result::Result<ValidatorSchema, Vec<SchemaParseError>> {
+        let (mut schema, deferred) = Self::from_schema_fragments_deferring_errors(fragments)
+            .map_err(|err| vec![SchemaParseError::Schema(Box::new(err))])?;
+        let errors = deferred.into_errors();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        schema
+            .add_derived_attribute_types()
+            .map_err(|err| vec![SchemaParseError::Schema(Box::new(err))])?;
+        Ok(schema)
+    }
+
+    /// Build the schema from the fragments, failing on the first error except
+    /// for cycles and undeclared entity types and actions, which are
+    /// independent of each other and so are returned alongside the schema. The
+    /// schema is only valid if there are no such errors, and it does not yet
+    /// have the types of derived attributes.
+    fn from_schema_fragments_deferring_errors(
+        fragments: impl IntoIterator<Item = ValidatorSchemaFragment>,
+    ) -> Result<(ValidatorSchema, DeferredSchemaErrors)> {
         let mut type_defs = HashMap::new();
         let mut entity_type_fragments = HashMap::new();
         let mut action_fragments = HashMap::new();
@@ -286,6 +347,22 @@ impl ValidatorSchema {
                 .ok_or(SchemaError::ContextOrShapeNotRecord(
                     ContextOrShape::EntityTypeShape(name.clone()),
                 ))?;
+                if let Some(attr) = entity_type
+                    .derived_attributes
+                    .keys()
+                    .find(|attr| attributes.get_attr(attr).is_some())
+                {
+                    return Err(SchemaError::InvalidDerivedAttribute {
+                        entity_type: name,
+                        attr: attr.clone(),
+                        reason: "the attribute is also declared in the shape".to_owned(),
+                    });
+                }
+                Self::check_mutually_exclusive_attrs(
+                    &name,
+                    &attributes,
+                    &entity_type.mutually_exclusive,
+                )?;
                 Ok((
                     name.clone(),
                     ValidatorEntityType {
@@ -293,6 +370,8 @@ impl ValidatorSchema {
                         descendants,
                         attributes,
                         open_attributes,
+                        derived_attributes: entity_type.derived_attributes,
+                        mutually_exclusive: entity_type.mutually_exclusive,
                     },
                 ))
             })
@@ -316,6 +395,7 @@ impl ValidatorSchema {
                         .ok_or(SchemaError::ContextOrShapeNotRecord(
                             ContextOrShape::ActionContext(name.clone()),
                         ))?;
+                Self::check_context_defaults(&name, &context, &action.context_defaults)?;
                 Ok((
                     name.clone(),
                     ValidatorActionId {
@@ -328,6 +408,7 @@ impl ValidatorSchema {
                         ),
                         attribute_types: action.attribute_types,
                         attributes: action.attributes,
+                        context_defaults: action.context_defaults,
                     },
                 ))
             })
@@ -335,28 +416,105 @@ impl ValidatorSchema {
 
         // We constructed entity types and actions with child maps, but we need
         // transitively closed descendants.
-        compute_tc(&mut entity_types, false)?;
+        let mut deferred = DeferredSchemaErrors::default();
+        if let Err(err) = compute_tc(&mut entity_types, false) {
+            deferred.hierarchy.push(err.into());
+        }
         // Pass `true` here so that we also check that the action hierarchy does
         // not contain cycles.
-        compute_tc(&mut action_ids, true)?;
-
-        // Return with an error if there is an undeclared entity or action
-        // referenced in any fragment. `{entity,action}_children` are provided
-        // for the `undeclared_parent_{entities,actions}` arguments because
-        // removed keys from these maps as we encountered declarations for the
-        // entity types or actions. Any keys left in the map are therefore
-        // undeclared.
+        if let Err(err) = compute_tc(&mut action_ids, true) {
+            deferred.hierarchy.push(err.into());
+        }
+
+        // Record any undeclared entity or action referenced in any fragment.
+        // `{entity,action}_children` are provided for the
+        // `undeclared_parent_{entities,actions}` arguments because removed keys
+        // from these maps as we encountered declarations for the entity types
+        // or actions. Any keys left in the map are therefore undeclared.
         Self::check_for_undeclared(
             &entity_types,
             entity_children.into_keys(),
             &action_ids,
             action_children.into_keys(),
-        )?;
+            &mut deferred,
+        );
 
-        Ok(ValidatorSchema {
+        let schema = ValidatorSchema {
             entity_types,
             action_ids,
-        })
+            common_types: type_defs.into_keys().collect(),
+        };
+        Ok((schema, deferred))
+    }
+
+    /// Typecheck the expressions computing derived attributes, adding each
+    /// derived attribute to the attributes of its entity type with the type of
+    /// its expression. The expression for a derived attribute may use other
+    /// derived attributes of the entity, so they are typechecked in dependency
+    /// order, failing if a derived attribute depends on itself.
+    fn add_derived_attribute_types(&mut self) -> Result<()> {
+        let derived_attributes = self
+            .entity_types
+            .values()
+            .flat_map(|ety| {
+                ety.derived_attributes
+                    .keys()
+                    .map(|attr| (ety.name.clone(), attr.clone()))
+            })
+            .collect::<Vec<_>>();
+        for (entity_type, attr) in derived_attributes {
+            self.add_derived_attribute_type(&entity_type, &attr, &mut Vec::new())?;
+        }
+        Ok(())
+    }
+
+    /// Typecheck the expression for the derived attribute `attr` of
+    /// `entity_type` after the derived attributes it uses, unless that was
+    /// done already. `pending` holds the derived attributes of the entity type
+    /// which are waiting for this one.
+    fn add_derived_attribute_type(
+        &mut self,
+        entity_type: &Name,
+        attr: &SmolStr,
+        pending: &mut Vec<SmolStr>,
+    ) -> Result<()> {
+        let Some(ety) = self.entity_types.get(entity_type) else {
+            return Ok(());
+        };
+        let Some(expr) = ety.derived_attributes.get(attr).cloned() else {
+            return Ok(());
+        };
+        if ety.attributes.get_attr(attr).is_some() {
+            return Ok(());
+        }
+        let invalid = |reason: String| SchemaError::InvalidDerivedAttribute {
+            entity_type: entity_type.clone(),
+            attr: attr.clone(),
+            reason,
+        };
+        if pending.contains(attr) {
+            return Err(invalid("the attribute is derived from itself".to_owned()));
+        }
+        pending.push(attr.clone());
+        for e in expr.subexpressions() {
+            if let ExprKind::GetAttr { expr, attr } | ExprKind::HasAttr { expr, attr, .. } =
+                e.expr_kind()
+            {
+                if matches!(expr.expr_kind(), ExprKind::Var(Var::Resource)) {
+                    self.add_derived_attribute_type(entity_type, attr, pending)?;
+                }
+            }
+        }
+        pending.pop();
+        let ty = Typechecker::new(self, ValidationMode::default())
+            .typecheck_derived_attribute(entity_type, &expr)
+            .map_err(invalid)?;
+        if let Some(ety) = self.entity_types.get_mut(entity_type) {
+            ety.attributes
+                .attrs
+                .insert(attr.clone(), AttributeType::required_attribute(ty));
+        }
+        Ok(())
     }
 
     /// Check that all entity types and actions referenced in the schema are in
@@ -368,15 +526,14 @@ impl ValidatorSchema {
         undeclared_parent_entities: impl IntoIterator<Item = Name>,
         action_ids: &HashMap<EntityUID, ValidatorActionId>,
         undeclared_parent_actions: impl IntoIterator<Item = EntityUID>,
-    ) -> Result<()> {
+        deferred: &mut DeferredSchemaErrors,
+    ) {
         // When we constructed `entity_types`, we removed entity types from  the
         // `entity_children` map as we encountered a declaration for that type.
         // Any entity types left in the map are therefore undeclared. These are
         // any undeclared entity types which appeared in a `memberOf` list.
-        let mut undeclared_e = undeclared_parent_entities
-            .into_iter()
-            .map(|n| n.to_string())
-            .collect::<HashSet<_>>();
+        let undeclared_e = &mut deferred.undeclared_entity_types;
+        undeclared_e.extend(undeclared_parent_entities);
         // Looking at entity types, we need to check entity references in
         // attribute types. We already know that all elements of the
         // `descendants` list were declared because the list is a result of
@@ -384,30 +541,25 @@ impl ValidatorSchema {
         // types to their parent entity types.
         for entity_type in entity_types.values() {
             for (_, attr_typ) in entity_type.attributes() {
-                Self::check_undeclared_in_type(
-                    &attr_typ.attr_type,
-                    entity_types,
-                    &mut undeclared_e,
-                );
+                Self::check_undeclared_in_type(&attr_typ.attr_type, entity_types, undeclared_e);
             }
         }
 
         // Undeclared actions in a `memberOf` list.
-        let undeclared_a = undeclared_parent_actions
-            .into_iter()
-            .map(|n| n.to_string())
-            .collect::<HashSet<_>>();
+        deferred
+            .undeclared_actions
+            .extend(undeclared_parent_actions);
         // For actions, we check entity references in the context attribute
         // types and `appliesTo` lists. See the `entity_types` loop for why the
         // `descendants` list is not checked.
         for action in action_ids.values() {
-            Self::check_undeclared_in_type(&action.context, entity_types, &mut undeclared_e);
+            Self::check_undeclared_in_type(&action.context, entity_types, undeclared_e);
 
             for p_entity in action.applies_to.applicable_principal_types() {
                 match p_entity {
                     EntityType::Specified(p_entity) => {
                         if !entity_types.contains_key(p_entity) {
-                            undeclared_e.insert(p_entity.to_string());
+                            undeclared_e.push(p_entity.clone());
                         }
                     }
                     EntityType::Unspecified => (),
@@ -418,20 +570,54 @@ impl ValidatorSchema {
                 match r_entity {
                     EntityType::Specified(r_entity) => {
                         if !entity_types.contains_key(r_entity) {
-                            undeclared_e.insert(r_entity.to_string());
+                            undeclared_e.push(r_entity.clone());
                         }
                     }
                     EntityType::Unspecified => (),
                 }
             }
         }
-        if !undeclared_e.is_empty() {
-            return Err(SchemaError::UndeclaredEntityTypes(undeclared_e));
-        }
-        if !undeclared_a.is_empty() {
-            return Err(SchemaError::UndeclaredActions(undeclared_a));
-        }
+    }
 
+    /// Check that every context default is for an optional attribute of the
+    /// action's context, and that the default has that attribute's type.
+    fn check_context_defaults(
+        action: &EntityUID,
+        context: &Attributes,
+        defaults: &HashMap<SmolStr, PartialValueSerializedAsExpr>,
+    ) -> Result<()> {
+        let extensions = Extensions::all_available();
+        for (attr, default) in defaults {
+            let invalid = |reason: String| SchemaError::InvalidContextDefault {
+                action: action.clone(),
+                attr: attr.clone(),
+                reason,
+            };
+            match context.get_attr(attr) {
+                None => {
+                    return Err(invalid(
+                        "the attribute is not declared in the context".to_owned(),
+                    ))
+                }
+                Some(attr_ty) if attr_ty.is_required => {
+                    return Err(invalid(
+                        "only optional attributes may have a default".to_owned(),
+                    ))
+                }
+                Some(attr_ty) => {
+                    if !attr_ty
+                        .attr_type
+                        .typecheck_partial_value(default.as_ref(), extensions)
+                        .map_err(|err| invalid(err.to_string()))?
+                    {
+                        return Err(invalid(format!(
+                            "`{default}` does not have the declared type `{}`",
+                            attr_ty.attr_type
+                        )));
+                    }
+                }
+            }
+        }
         Ok(())
     }
 
@@ -445,19 +631,51 @@ impl ValidatorSchema {
         }
     }
 
+    // Check that every group of mutually exclusive attributes of an entity
+    // type has at least two distinct attributes, all of which are optional
+    // attributes of the entity type. A required attribute would make the
+    // others in its group impossible to set.
+    fn check_mutually_exclusive_attrs(
+        entity_type: &Name,
+        attributes: &Attributes,
+        groups: &[Vec<SmolStr>],
+    ) -> Result<()> {
+        let invalid = |reason: String| SchemaError::InvalidMutuallyExclusiveAttrs {
+            entity_type: entity_type.clone(),
+            reason,
+        };
+        for group in groups {
+            if group.iter().collect::<HashSet<_>>().len() < 2 {
+                return Err(invalid(
+                    "a group must have at least two distinct attributes".to_owned(),
+                ));
+            }
+            for attr in group {
+                match attributes.get_attr(attr) {
+                    None => return Err(invalid(format!("`{attr}` is not a declared attribute"))),
+                    Some(ty) if ty.is_required => {
+                        return Err(invalid(format!("`{attr}` is a required attribute")))
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
     // Check that all entity types appearing inside a type are in the set of
     // declared entity types, adding any undeclared entity types to the
-    // `undeclared_types` set.
+    // `undeclared_types` list.
     fn check_undeclared_in_type(
         ty: &Type,
         entity_types: &HashMap<Name, ValidatorEntityType>,
-        undeclared_types: &mut HashSet<String>,
+        undeclared_types: &mut Vec<Name>,
     ) {
         match ty {
             Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => {
                 for name in lub.iter() {
                     if !entity_types.contains_key(name) {
-                        undeclared_types.insert(name.to_string());
+                        undeclared_types.push(name.clone());
                     }
                 }
             }
@@ -485,6 +703,11 @@ impl ValidatorSchema {
         self.action_ids.get(action_id)
     }
 
+    /// An iterator over the ValidatorActionId objects in the schema.
+    pub fn action_ids(&self) -> impl Iterator<Item = &ValidatorActionId> {
+        self.action_ids.values()
+    }
+
     /// Lookup the ValidatorEntityType object in the schema with the given name.
     pub fn get_entity_type<'a>(&'a self, entity_type_id: &Name) -> Option<&'a ValidatorEntityType> {
         self.entity_types.get(entity_type_id)
@@ -526,6 +749,39 @@ impl ValidatorSchema {
         self.entity_types.iter()
     }
 
+    /// Get the entity types reachable from `root` by following attributes
+    /// whose types refer to entities, including attributes nested in sets and
+    /// records, e.g., `User.manager` or `User.org.parent`. Unlike the
+    /// descendants of an entity type, which follow `memberOfTypes`, this
+    /// follows the entities an entity of type `root` can refer to.
+    ///
+    /// `root` is only in the result if it is reachable from itself, e.g.,
+    /// through `User.manager: User`. The result is empty if `root` is
+    /// unspecified or not declared in the schema.
+    pub fn reachable_entity_types(&self, root: &EntityType) -> HashSet<EntityType> {
+        let mut reachable: HashSet<&Name> = HashSet::new();
+        let mut worklist = match root {
+            EntityType::Specified(root) => vec![root],
+            EntityType::Unspecified => Vec::new(),
+        };
+        while let Some(name) = worklist.pop() {
+            let Some(ety) = self.get_entity_type(name) else {
+                continue;
+            };
+            let mut referenced = Vec::new();
+            for (_, attr_ty) in ety.attributes() {
+                entity_types_in_type(&attr_ty.attr_type, &mut referenced);
+            }
+            // an entity type is only explored when it's first reached, so
+            // cycles like `User.manager: User` terminate
+            worklist.extend(referenced.into_iter().filter(|ety| reachable.insert(ety)));
+        }
+        reachable
+            .into_iter()
+            .map(|name| EntityType::Specified(name.clone()))
+            .collect()
+    }
+
     /// Get all entity types in the schema where an `{entity0} in {entity}` can
     /// evaluate to `true` for some `entity0` with that entity type. This
     /// includes all entity types that are descendants of the type of `entity`
@@ -576,15 +832,71 @@ impl ValidatorSchema {
             .map(|v| v.into_iter().flatten().collect::<Vec<_>>())
     }
 
-    /// Get the `Type` of context expected for the given `action`.
-    /// This always reutrns a closed record type.
+    /// Get the `Type` of context expected for the given `action`, i.e., the
+    /// record type declared in its `appliesTo`, or the empty record type if
+    /// it declares no context.
+    /// This always returns a closed record type.
+    ///
+    /// A request names a single action, so this is the context declared for
+    /// `action` itself, even if `action` is an action group: the contexts
+    /// declared for the members of the group, which may differ from each
+    /// other, are neither checked against it nor combined into it.
     ///
     /// Returns `None` if the action is not in the schema.
-    pub fn context_type(&self, action: &EntityUID) -> Option<Type> {
-        // INVARIANT: `ValidatorActionId::context_type` always returns a closed
+    pub fn context_type(&self, action: &EntityUID) -> Option<&Type> {
+        // INVARIANT: the context of a `ValidatorActionId` is always a closed
         // record type
-        self.get_action_id(action)
-            .map(ValidatorActionId::context_type)
+        self.get_action_id(action).map(|action| &action.context)
+    }
+
+    /// A hash of the declarations in this schema, which changes whenever the
+    /// schema changes in a way that could change the result of validation.
+    /// This is only stable within a single run of a program.
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let entity_types: BTreeMap<_, _> = self.entity_types.iter().collect();
+        for (name, ety) in entity_types {
+            name.hash(&mut hasher);
+            ety.descendants
+                .iter()
+                .collect::<BTreeSet<_>>()
+                .hash(&mut hasher);
+            ety.attributes.hash(&mut hasher);
+            ety.open_attributes.hash(&mut hasher);
+        }
+        let action_ids: BTreeMap<_, _> = self.action_ids.iter().collect();
+        for (name, action) in action_ids {
+            name.hash(&mut hasher);
+            action
+                .applicable_principal_types()
+                .collect::<BTreeSet<_>>()
+                .hash(&mut hasher);
+            action
+                .applicable_resource_types()
+                .collect::<BTreeSet<_>>()
+                .hash(&mut hasher);
+            action
+                .descendants
+                .iter()
+                .collect::<BTreeSet<_>>()
+                .hash(&mut hasher);
+            action.context.hash(&mut hasher);
+            action.attribute_types.hash(&mut hasher);
+            // `PartialValue` isn't `Hash`, so we hash it as an `Expr` instead,
+            // which has the elements of sets and records in a canonical order
+            for values in [&action.attributes, &action.context_defaults] {
+                let values: BTreeMap<_, _> = values.iter().collect();
+                for (attr, value) in values {
+                    attr.hash(&mut hasher);
+                    Expr::from(PartialValue::from((*value).clone())).hash(&mut hasher);
+                }
+            }
+        }
+        self.common_types
+            .iter()
+            .collect::<BTreeSet<_>>()
+            .hash(&mut hasher);
+        hasher.finish()
     }
 
     /// Invert the action hierarchy to get the ancestor relation expected for
@@ -628,6 +940,126 @@ impl ValidatorSchema {
     }
 }
 
+/// Parse a schema in the natural syntax, reporting as many errors as possible
+/// instead of stopping at the first. All the syntax errors are reported
+/// together, as are all the unknown type names, and all the cycles and
+/// undeclared entity types and actions, each labeled with where it occurs.
+/// Errors which depend on an earlier stage are not reported: e.g., cycles are
+/// not checked for in a schema with syntax errors.
+///
+/// The schema is returned if and only if there are no errors.
+pub fn parse_schema_with_recovery(
+    text: &str,
+    extensions: Extensions<'_>,
+) -> (Option<ValidatorSchema>, Vec<SchemaParseError>) {
+    let ast = match parse_schema(text) {
+        Ok(ast) => ast,
+        Err(errs) => {
+            return (
+                None,
+                errs.iter()
+                    .map(|err| SchemaParseError::Syntax(Box::new(err.clone())))
+                    .collect(),
+            )
+        }
+    };
+    let fragment = match custom_schema_to_json_schema(ast) {
+        Ok((fragment, _)) => fragment,
+        Err(errs) => {
+            return (
+                None,
+                errs.into_iter()
+                    .map(|err| SchemaParseError::Conversion(Box::new(err)))
+                    .collect(),
+            )
+        }
+    };
+    let schema = ValidatorSchemaFragment::from_schema_fragment(
+        fragment,
+        ActionBehavior::default(),
+        extensions,
+    )
+    .map_err(|err| vec![SchemaParseError::Schema(Box::new(err))])
+    .and_then(|fragment| ValidatorSchema::from_schema_fragments_collecting_errors([fragment]));
+    match schema {
+        Ok(schema) => (Some(schema), Vec::new()),
+        Err(errs) => (None, errs),
+    }
+}
+
+/// The errors in the entity type and action hierarchies found while building a
+/// `ValidatorSchema`, which don't prevent finding the others
+#[derive(Debug, Default)]
+struct DeferredSchemaErrors {
+    /// Errors computing the transitive closure of the hierarchies, including
+    /// cycles in the action hierarchy
+    hierarchy: Vec<SchemaError>,
+    /// Every reference to an undeclared entity type
+    undeclared_entity_types: Vec<Name>,
+    /// Every reference to an undeclared action
+    undeclared_actions: Vec<EntityUID>,
+}
+
+impl DeferredSchemaErrors {
+    /// The error `ValidatorSchema::from_schema_fragments` has always reported:
+    /// the first hierarchy error, or else all the undeclared entity types, or
+    /// else all the undeclared actions
+    fn into_first_error(self) -> Option<SchemaError> {
+        if let Some(err) = self.hierarchy.into_iter().next() {
+            Some(err)
+        } else if !self.undeclared_entity_types.is_empty() {
+            Some(SchemaError::UndeclaredEntityTypes(
+                self.undeclared_entity_types
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+            ))
+        } else if !self.undeclared_actions.is_empty() {
+            Some(SchemaError::UndeclaredActions(
+                self.undeclared_actions
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// All the errors, with one error for each undeclared entity type or
+    /// action, labeled with its first reference in the source
+    fn into_errors(self) -> Vec<SchemaParseError> {
+        let mut undeclared_entity_types = self.undeclared_entity_types;
+        undeclared_entity_types.sort_by_cached_key(|name| (name.to_string(), offset(name.loc())));
+        undeclared_entity_types.dedup();
+        undeclared_entity_types.sort_by_cached_key(|name| (offset(name.loc()), name.to_string()));
+        let mut undeclared_actions = self.undeclared_actions;
+        undeclared_actions.sort_by_cached_key(|uid| (uid.to_string(), offset(uid.loc())));
+        undeclared_actions.dedup();
+        undeclared_actions.sort_by_cached_key(|uid| (offset(uid.loc()), uid.to_string()));
+
+        self.hierarchy
+            .into_iter()
+            .map(|err| SchemaParseError::Schema(Box::new(err)))
+            .chain(
+                undeclared_entity_types
+                    .into_iter()
+                    .map(SchemaParseError::undeclared_entity_type),
+            )
+            .chain(
+                undeclared_actions
+                    .into_iter()
+                    .map(SchemaParseError::undeclared_action),
+            )
+            .collect()
+    }
+}
+
+/// The offset of `loc` in the source, with unknown locations last
+fn offset(loc: Option<&Loc>) -> usize {
+    loc.map_or(usize::MAX, |loc| loc.span.offset())
+}
+
 /// Used to write a schema implicitly overriding the default handling of action
 /// groups.
 #[derive(Debug, Clone, Deserialize)]
@@ -836,6 +1268,23 @@ impl<'a> CommonTypeResolver<'a> {
     }
 }
 
+/// Push the entity types referred to by `ty` onto `out`, including those in
+/// the element types of sets and the attribute types of records
+fn entity_types_in_type<'a>(ty: &'a Type, out: &mut Vec<&'a Name>) {
+    match ty {
+        Type::Set {
+            element_type: Some(element_type),
+        } => entity_types_in_type(element_type, out),
+        Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => out.extend(lub.iter()),
+        Type::EntityOrRecord(EntityRecordKind::Record { attrs, .. }) => {
+            for (_, attr_ty) in attrs.iter() {
+                entity_types_in_type(&attr_ty.attr_type, out);
+            }
+        }
+        _ => (),
+    }
+}
+
 // PANIC SAFETY unit tests
 #[allow(clippy::panic)]
 // PANIC SAFETY unit tests
@@ -844,10 +1293,10 @@ impl<'a> CommonTypeResolver<'a> {
 mod test {
     use std::{collections::BTreeMap, str::FromStr};
 
-    use crate::types::Type;
+    use crate::types::{AttributeType, Type};
     use crate::{SchemaType, SchemaTypeVariant};
 
-    use cedar_policy_core::ast::RestrictedExpr;
+    use cedar_policy_core::ast::{PartialValue, RestrictedExpr};
     use cool_asserts::assert_matches;
     use serde_json::json;
 
@@ -1315,6 +1764,27 @@ mod test {
         TryInto::<ValidatorSchema>::try_into(schema_json).expect("Did not expect any errors.");
     }
 
+    #[test]
+    fn fingerprint_of_action_attributes() {
+        // The set is stored with a `HashSet` of its elements, which can be
+        // iterated in a different order in each schema
+        let schema = || {
+            ValidatorSchema::from_schema_file(
+                SchemaFragment::from_json_value(json!({"": {
+                    "entityTypes": {},
+                    "actions": {
+                        "view": { "attributes": { "ids": (0..100).collect::<Vec<_>>() } }
+                    }
+                }}))
+                .expect("Expected valid schema"),
+                crate::ActionBehavior::PermitAttributes,
+                Extensions::all_available(),
+            )
+            .expect("Expected valid schema")
+        };
+        assert_eq!(schema().fingerprint(), schema().fingerprint());
+    }
+
     #[test]
     fn cannot_declare_action_in_group_when_prohibited() {
         let schema_json: SchemaFragment = serde_json::from_str(
@@ -2018,6 +2488,223 @@ mod test {
         );
     }
 
+    fn context_defaults_schema(context_defaults: serde_json::Value) -> Result<ValidatorSchema> {
+        let src = json!(
+        {
+            "entityTypes": { },
+            "actions": {
+                "view_photo": {
+                    "appliesTo": {
+                        "context": {
+                            "type": "Record",
+                            "attributes": {
+                                "authenticated": { "type": "Boolean" },
+                                "mfa": { "type": "Boolean", "required": false },
+                                "region": { "type": "String", "required": false },
+                            }
+                        },
+                        "contextDefaults": context_defaults,
+                    }
+                },
+            }
+        });
+        let schema_file: NamespaceDefinition = serde_json::from_value(src).expect("Parse Error");
+        schema_file.try_into()
+    }
+
+    #[test]
+    fn context_defaults() {
+        let schema = context_defaults_schema(json!({ "mfa": false, "region": "us-east-1" }))
+            .expect("Schema Error");
+        let action = schema
+            .get_action_id(&EntityUID::from_str("Action::\"view_photo\"").unwrap())
+            .unwrap();
+        let defaults: HashMap<_, _> = action.context_defaults().collect();
+        assert_eq!(
+            defaults,
+            HashMap::from([
+                (&"mfa".into(), &PartialValue::from(false)),
+                (&"region".into(), &PartialValue::from("us-east-1")),
+            ])
+        );
+    }
+
+    #[test]
+    fn context_defaults_invalid() {
+        assert_matches!(
+            context_defaults_schema(json!({ "authenticated": true })),
+            Err(SchemaError::InvalidContextDefault { attr, .. }) => assert_eq!(attr, "authenticated")
+        );
+        assert_matches!(
+            context_defaults_schema(json!({ "undeclared": true })),
+            Err(SchemaError::InvalidContextDefault { attr, .. }) => assert_eq!(attr, "undeclared")
+        );
+        assert_matches!(
+            context_defaults_schema(json!({ "mfa": "yes" })),
+            Err(SchemaError::InvalidContextDefault { attr, .. }) => assert_eq!(attr, "mfa")
+        );
+        assert_matches!(
+            context_defaults_schema(json!({ "mfa": null })),
+            Err(SchemaError::InvalidContextDefault { attr, .. }) => assert_eq!(attr, "mfa")
+        );
+    }
+
+    fn derived_attributes_schema(derived_attributes: serde_json::Value) -> Result<ValidatorSchema> {
+        ValidatorSchema::from_json_value(
+            json!({
+                "": {
+                    "entityTypes": {
+                        "User": {
+                            "shape": {
+                                "type": "Record",
+                                "attributes": {
+                                    "age": { "type": "Long" },
+                                    "name": { "type": "String" },
+                                    "manager": { "type": "Entity", "name": "User" },
+                                    "address": {
+                                        "type": "Record",
+                                        "attributes": { "city": { "type": "String" } }
+                                    }
+                                }
+                            },
+                            "derivedAttributes": derived_attributes
+                        }
+                    },
+                    "actions": {}
+                }
+            }),
+            Extensions::all_available(),
+        )
+    }
+
+    #[cfg(feature = "derived-attributes")]
+    #[test]
+    fn derived_attributes() {
+        let schema = derived_attributes_schema(json!({
+            "isAdult": "resource.age >= 18",
+            "canVote": "resource.isAdult && resource has address",
+            "city": "resource.address.city",
+            "first": "true",
+        }))
+        .expect("schema should be valid");
+        let user = schema
+            .get_entity_type(&"User".parse().unwrap())
+            .expect("`User` should be declared");
+        assert!(user.is_derived_attr("isAdult"));
+        assert!(!user.is_derived_attr("age"));
+        assert_eq!(
+            user.attr("isAdult"),
+            Some(&AttributeType::required_attribute(Type::primitive_boolean()))
+        );
+        assert_eq!(
+            user.attr("canVote"),
+            Some(&AttributeType::required_attribute(Type::primitive_boolean()))
+        );
+        assert_eq!(
+            user.attr("city"),
+            Some(&AttributeType::required_attribute(Type::primitive_string()))
+        );
+        assert_eq!(
+            user.attr("first"),
+            Some(&AttributeType::required_attribute(Type::primitive_boolean()))
+        );
+    }
+
+    #[cfg(feature = "derived-attributes")]
+    #[test]
+    fn derived_attributes_invalid() {
+        for (derived_attributes, invalid) in [
+            (json!({ "a": "resource.age +" }), "a"),
+            (json!({ "a": "resource.age + resource.name" }), "a"),
+            (json!({ "a": "principal.age" }), "a"),
+            (json!({ "a": "context.age" }), "a"),
+            (json!({ "a": "resource.manager.age" }), "a"),
+            (json!({ "a": "resource.manager has age" }), "a"),
+            (json!({ "a": "resource == User::\"alice\"" }), "a"),
+            (json!({ "a": "resource in resource.manager" }), "a"),
+            (json!({ "a": "resource is User" }), "a"),
+            (json!({ "age": "1" }), "age"),
+            (json!({ "a": "resource.a" }), "a"),
+        ] {
+            assert_matches!(
+                derived_attributes_schema(derived_attributes),
+                Err(SchemaError::InvalidDerivedAttribute { entity_type, attr, .. }) => {
+                    assert_eq!(entity_type.to_string(), "User");
+                    assert_eq!(attr, invalid);
+                }
+            );
+        }
+        assert_matches!(
+            derived_attributes_schema(json!({ "a": "resource.b", "b": "resource.a" })),
+            Err(SchemaError::InvalidDerivedAttribute { .. })
+        );
+    }
+
+    #[cfg(not(feature = "derived-attributes"))]
+    #[test]
+    fn derived_attributes_unsupported() {
+        assert_matches!(
+            derived_attributes_schema(json!({ "isAdult": "resource.age >= 18" })),
+            Err(SchemaError::UnsupportedFeature(
+                UnsupportedFeature::DerivedAttributes
+            ))
+        );
+    }
+
+    fn mutually_exclusive_schema(mutually_exclusive: serde_json::Value) -> Result<ValidatorSchema> {
+        ValidatorSchema::from_json_value(
+            json!({
+                "": {
+                    "entityTypes": {
+                        "User": {
+                            "shape": {
+                                "type": "Record",
+                                "attributes": {
+                                    "userId": { "type": "String", "required": false },
+                                    "serviceAccountId": { "type": "String", "required": false },
+                                    "name": { "type": "String" }
+                                }
+                            },
+                            "mutuallyExclusive": mutually_exclusive
+                        }
+                    },
+                    "actions": {}
+                }
+            }),
+            Extensions::all_available(),
+        )
+    }
+
+    #[test]
+    fn mutually_exclusive_attrs() {
+        let schema = mutually_exclusive_schema(json!([["userId", "serviceAccountId"]]))
+            .expect("schema should be valid");
+        let user = schema
+            .get_entity_type(&"User".parse().unwrap())
+            .expect("`User` should be declared");
+        assert_eq!(
+            user.mutually_exclusive_attrs().collect::<Vec<_>>(),
+            vec![[SmolStr::from("userId"), SmolStr::from("serviceAccountId")]]
+        );
+    }
+
+    #[test]
+    fn mutually_exclusive_attrs_invalid() {
+        for mutually_exclusive in [
+            json!([["userId"]]),
+            json!([["userId", "userId"]]),
+            json!([["userId", "email"]]),
+            json!([["userId", "name"]]),
+        ] {
+            assert_matches!(
+                mutually_exclusive_schema(mutually_exclusive),
+                Err(SchemaError::InvalidMutuallyExclusiveAttrs { entity_type, .. }) => {
+                    assert_eq!(entity_type.to_string(), "User");
+                }
+            );
+        }
+    }
+
     #[test]
     fn test_action_namespace_inference_multi_success() {
         let src = json!({
@@ -2117,6 +2804,52 @@ mod test {
         );
     }
 
+    #[test]
+    fn context_type() {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity User;
+            action read;
+            action view in [read] appliesTo {
+                principal: User,
+                resource: User,
+                context: { mfa: Bool, ip?: String },
+            };
+            action edit in [read] appliesTo {
+                principal: User,
+                resource: User,
+                context: { reason: String },
+            };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let action = |id: &str| EntityUID::with_eid_and_type("Action", id).unwrap();
+
+        assert_eq!(
+            schema.context_type(&action("view")),
+            Some(&Type::record_with_attributes(
+                [
+                    (
+                        "mfa".into(),
+                        AttributeType::required_attribute(Type::primitive_boolean())
+                    ),
+                    (
+                        "ip".into(),
+                        AttributeType::new(Type::primitive_string(), false)
+                    ),
+                ],
+                OpenTag::ClosedAttributes
+            ))
+        );
+        // The context of an action group is its own, not that of its members
+        assert_eq!(
+            schema.context_type(&action("read")),
+            Some(&Type::record_with_attributes([], OpenTag::ClosedAttributes))
+        );
+        assert_eq!(schema.context_type(&action("delete")), None);
+    }
+
     #[test]
     fn qualified_undeclared_common_types() {
         let src = json!(
@@ -2184,6 +2917,145 @@ mod test {
         assert_matches!(schema, Err(SchemaError::UndeclaredCommonTypes(types)) =>
             assert_eq!(types, HashSet::from(["Demo::id".to_string()])));
     }
+    #[test]
+    fn reachable_entity_types() {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity Organization = { parent?: Organization };
+            entity Address;
+            entity Group;
+            entity User in [Group] = {
+                manager?: User,
+                org: Organization,
+                contact: { addresses: Set<Address> },
+            };
+            entity Photo = { owner: User };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let ety = |name: &str| EntityType::Specified(Name::parse_unqualified_name(name).unwrap());
+        let reachable = |name: &str| schema.reachable_entity_types(&ety(name));
+
+        assert_eq!(
+            reachable("User"),
+            HashSet::from([ety("User"), ety("Organization"), ety("Address")])
+        );
+        assert_eq!(
+            reachable("Photo"),
+            HashSet::from([ety("User"), ety("Organization"), ety("Address")])
+        );
+        assert_eq!(
+            reachable("Organization"),
+            HashSet::from([ety("Organization")])
+        );
+        assert_eq!(reachable("Group"), HashSet::new());
+        assert_eq!(reachable("Unknown"), HashSet::new());
+        assert_eq!(
+            schema.reachable_entity_types(&EntityType::Unspecified),
+            HashSet::new()
+        );
+    }
+
+    /// The messages and labeled source text of the errors of
+    /// `parse_schema_with_recovery` on `src`
+    fn errors_with_recovery(src: &str) -> Vec<(String, Option<String>)> {
+        let (schema, errs) = parse_schema_with_recovery(src, Extensions::all_available());
+        assert!(schema.is_none());
+        errs.iter()
+            .map(|err| {
+                let label = miette::Diagnostic::labels(err)
+                    .and_then(|mut labels| labels.next())
+                    .map(|label| src[label.offset()..label.offset() + label.len()].to_string());
+                (err.to_string(), label)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recovery_reports_each_undeclared_entity_type() {
+        assert_eq!(
+            errors_with_recovery(
+                r#"entity User in [Group];
+                entity Photo;
+                action view appliesTo { principal: [User, Admin], resource: Photo };
+                action edit appliesTo { principal: Group, resource: Photo };"#
+            ),
+            vec![
+                (
+                    "undeclared entity type `Group`".to_string(),
+                    Some("Group".to_string())
+                ),
+                (
+                    "undeclared entity type `Admin`".to_string(),
+                    Some("Admin".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn recovery_reports_cycles_with_undeclared() {
+        let errs = errors_with_recovery(
+            r#"entity User in [Group];
+            action a in [b];
+            action b in [a];
+            action c in [d];"#,
+        );
+        assert_eq!(errs.len(), 3, "{errs:?}");
+        assert!(errs[0].0.starts_with("cycle in action hierarchy"));
+        assert_eq!(errs[1].0, "undeclared entity type `Group`");
+        assert_eq!(errs[2].0, r#"undeclared action `Action::"d"`"#);
+
+        // The schema constructors still report only the first error
+        assert_matches!(
+            ValidatorSchema::from_str_natural(
+                r#"entity User in [Group]; action a in [b]; action b in [a];"#,
+                Extensions::all_available(),
+            )
+            .map(|(schema, _)| schema),
+            Err(HumanSchemaError::Core(SchemaError::CycleInActionHierarchy(
+                _
+            )))
+        );
+    }
+
+    #[test]
+    fn recovery_reports_all_unknown_types_and_syntax_errors() {
+        let errs = errors_with_recovery(r#"entity User { team: Team, manager: Boss };"#);
+        assert_eq!(
+            errs,
+            vec![
+                (
+                    "Unknown type name: `Team`".to_string(),
+                    Some("Team".to_string())
+                ),
+                (
+                    "Unknown type name: `Boss`".to_string(),
+                    Some("Boss".to_string())
+                ),
+            ]
+        );
+
+        let (schema, errs) =
+            parse_schema_with_recovery("entity User {", Extensions::all_available());
+        assert!(schema.is_none());
+        assert_matches!(errs.as_slice(), [SchemaParseError::Syntax(_)]);
+    }
+
+    #[test]
+    fn recovery_valid_schema() {
+        let (schema, errs) = parse_schema_with_recovery(
+            r#"entity User in [Group]; entity Group;
+            action view appliesTo { principal: User, resource: Group };"#,
+            Extensions::all_available(),
+        );
+        assert!(errs.is_empty());
+        assert!(schema
+            .unwrap()
+            .get_entity_type(&"User".parse().unwrap())
+            .is_some());
+    }
 }
 
 #[cfg(test)]
@@ -17,7 +17,7 @@
 //! This module contains the definition of `ValidatorActionId` and the types it relies on
 
 use cedar_policy_core::{
-    ast::{EntityType, EntityUID, PartialValueSerializedAsExpr},
+    ast::{EntityType, EntityUID, PartialValue, PartialValueSerializedAsExpr},
     transitive_closure::TCNode,
 };
 use serde::Serialize;
@@ -29,7 +29,7 @@ use crate::types::{Attributes, Type};
 /// Contains information about actions used by the validator.  The contents of
 /// the struct are the same as the schema entity type structure, but the
 /// `member_of` relation is reversed to instead be `descendants`.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct ValidatorActionId {
     /// The name of the action.
     pub(crate) name: EntityUID,
@@ -57,15 +57,56 @@ pub struct ValidatorActionId {
     /// Attributes are serialized as `RestrictedExpr`s, so that roundtripping
     /// works seamlessly.
     pub(crate) attributes: HashMap<SmolStr, PartialValueSerializedAsExpr>,
+
+    /// Default values for optional attributes of the context record, used to
+    /// fill in those attributes when a request omits them.
+    #[serde(rename = "contextDefaults")]
+    pub(crate) context_defaults: HashMap<SmolStr, PartialValueSerializedAsExpr>,
 }
 
 impl ValidatorActionId {
+    /// The name of this action.
+    pub fn name(&self) -> &EntityUID {
+        &self.name
+    }
+
+    /// Is the given principal type applicable for this action?
+    pub fn is_applicable_principal_type(&self, ty: &EntityType) -> bool {
+        self.applies_to.is_applicable_principal_type(ty)
+    }
+
+    /// Is the given resource type applicable for this action?
+    pub fn is_applicable_resource_type(&self, ty: &EntityType) -> bool {
+        self.applies_to.is_applicable_resource_type(ty)
+    }
+
+    /// The principal entity types this action applies to.
+    pub fn applicable_principal_types(&self) -> impl Iterator<Item = &EntityType> {
+        self.applies_to.applicable_principal_types()
+    }
+
+    /// The resource entity types this action applies to.
+    pub fn applicable_resource_types(&self) -> impl Iterator<Item = &EntityType> {
+        self.applies_to.applicable_resource_types()
+    }
+
+    /// The actions that are members of this action, directly or transitively.
+    pub fn descendants(&self) -> impl Iterator<Item = &EntityUID> {
+        self.descendants.iter()
+    }
+
     /// The `Type` that this action requires for its context.
     ///
     /// This always returns a closed record type.
     pub fn context_type(&self) -> Type {
         self.context.clone()
     }
+
+    /// The default values declared for optional attributes of this action's
+    /// context.
+    pub fn context_defaults(&self) -> impl Iterator<Item = (&SmolStr, &PartialValue)> {
+        self.context_defaults.iter().map(|(k, v)| (k, v.as_ref()))
+    }
 }
 
 impl TCNode<EntityUID> for ValidatorActionId {
@@ -87,7 +128,7 @@ impl TCNode<EntityUID> for ValidatorActionId {
 }
 
 /// The principals and resources that an action can be applied to.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub(crate) struct ValidatorApplySpec {
     /// The principal entity types the action can be applied to. This set may
     /// be a singleton set containing the unspecified entity type when the
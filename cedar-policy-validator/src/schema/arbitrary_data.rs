@@ -0,0 +1,341 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Generation of arbitrary requests and entities conforming to a
+//! `ValidatorSchema`, for property testing policies against the schema.
+
+use std::collections::{HashMap, HashSet};
+
+use arbitrary::{Error, Result, Unstructured};
+use cedar_policy_core::{
+    ast::{
+        Context, Eid, Entity, EntityType, EntityUID, EntityUIDEntry, Name, Request, RestrictedExpr,
+    },
+    entities::{Entities, TCComputation},
+    extensions::Extensions,
+};
+use smol_str::SmolStr;
+
+use super::ValidatorSchema;
+use crate::{
+    types::{AttributeType, EntityRecordKind, Primitive, Type},
+    CoreSchema,
+};
+
+/// Number of distinct entity ids used for each entity type. Requests and entity
+/// references draw their ids from the same small range as the generated
+/// entities, so that they frequently refer to entities which exist.
+const IDS_PER_ENTITY_TYPE: usize = 4;
+
+/// Maximum number of elements in a generated set
+const MAX_SET_SIZE: usize = 3;
+
+impl ValidatorSchema {
+    /// Generate an arbitrary `Request` which is valid for this schema: the
+    /// action is declared in the schema, the principal and resource have types
+    /// the action applies to, and the context has the type declared for the
+    /// action.
+    ///
+    /// Fails with `Error::IncorrectFormat` if the schema declares no action
+    /// which applies to any principal and resource type.
+    pub fn arbitrary_request(&self, u: &mut Unstructured<'_>) -> Result<Request> {
+        let mut actions = self.action_ids.values().collect::<Vec<_>>();
+        actions.sort_by(|a, b| a.name.cmp(&b.name));
+        let candidates = actions
+            .into_iter()
+            .filter(|action| {
+                action
+                    .applies_to
+                    .applicable_principal_types()
+                    .next()
+                    .is_some()
+                    && action
+                        .applies_to
+                        .applicable_resource_types()
+                        .next()
+                        .is_some()
+            })
+            .collect::<Vec<_>>();
+        let action = *u.choose(&candidates).map_err(|_| Error::IncorrectFormat)?;
+        let principal = arbitrary_uid(u, sorted(action.applies_to.applicable_principal_types()))?;
+        let resource = arbitrary_uid(u, sorted(action.applies_to.applicable_resource_types()))?;
+        let context = match self.arbitrary_value(u, &action.context)? {
+            Some(context) => Context::from_expr(context.as_borrowed(), Extensions::all_available())
+                .map_err(|_| Error::IncorrectFormat)?,
+            None => Context::empty(),
+        };
+        Ok(Request::new_unchecked(
+            EntityUIDEntry::concrete(principal, None),
+            EntityUIDEntry::concrete(action.name.clone(), None),
+            EntityUIDEntry::concrete(resource, None),
+            Some(context),
+        ))
+    }
+
+    /// Generate arbitrary `Entities` which are valid for this schema: every
+    /// entity has a declared entity type, all of its required attributes with
+    /// values of the declared types, and parents only of types it may be a
+    /// member of. The action entities declared in the schema are included.
+    pub fn arbitrary_entities(&self, u: &mut Unstructured<'_>) -> Result<Entities> {
+        let mut entity_types = self.entity_types.keys().collect::<Vec<_>>();
+        entity_types.sort();
+        let mut uids = Vec::new();
+        for name in entity_types {
+            let count = u.int_in_range(0..=IDS_PER_ENTITY_TYPE)?;
+            uids.extend((0..count).map(|i| entity_uid(name, i)));
+        }
+        // Parents are only drawn among the entities with a lower rank, so the
+        // generated hierarchy is always acyclic.
+        let ranks = uids
+            .iter()
+            .map(|_| u.arbitrary::<u32>())
+            .collect::<Result<Vec<_>>>()?;
+        let mut entities = Vec::with_capacity(uids.len());
+        for (i, (uid, rank)) in uids.iter().zip(&ranks).enumerate() {
+            let EntityType::Specified(name) = uid.entity_type() else {
+                return Err(Error::IncorrectFormat);
+            };
+            let entity_type = self.get_entity_type(name).ok_or(Error::IncorrectFormat)?;
+            // Derived attributes are computed, so entities don't have values
+            // for them
+            let attrs = self.arbitrary_attrs(
+                u,
+                entity_type
+                    .attributes()
+                    .filter(|(attr, _)| !entity_type.is_derived_attr(attr)),
+            )?;
+            let mut parents = HashSet::new();
+            for (j, (parent, parent_rank)) in uids.iter().zip(&ranks).enumerate() {
+                let may_be_parent = (parent_rank, j) < (rank, i)
+                    && match parent.entity_type() {
+                        EntityType::Specified(parent_name) => self
+                            .get_entity_type(parent_name)
+                            .is_some_and(|ety| ety.descendants.contains(name)),
+                        EntityType::Unspecified => false,
+                    };
+                if may_be_parent && u.arbitrary()? {
+                    parents.insert(parent.clone());
+                }
+            }
+            entities.push(
+                Entity::new(uid.clone(), attrs, parents, &Extensions::all_available())
+                    .map_err(|_| Error::IncorrectFormat)?,
+            );
+        }
+        Entities::from_entities(
+            entities,
+            Some(&CoreSchema::new(self)),
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .map_err(|_| Error::IncorrectFormat)
+    }
+
+    /// Generate the attributes of a record with the given attribute types.
+    /// Optional attributes are only present sometimes.
+    fn arbitrary_attrs<'a>(
+        &self,
+        u: &mut Unstructured<'_>,
+        attrs: impl IntoIterator<Item = (&'a SmolStr, &'a AttributeType)>,
+    ) -> Result<HashMap<SmolStr, RestrictedExpr>> {
+        let mut values = HashMap::new();
+        for (attr, attr_ty) in attrs {
+            if attr_ty.is_required || u.arbitrary()? {
+                if let Some(value) = self.arbitrary_value(u, &attr_ty.attr_type)? {
+                    values.insert(attr.clone(), value);
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    /// Generate a value of the given type, as a `RestrictedExpr`. Returns
+    /// `None` for a type without values, which can only be the type of an
+    /// optional attribute, and fails for types which do not appear in schemas.
+    fn arbitrary_value(
+        &self,
+        u: &mut Unstructured<'_>,
+        ty: &Type,
+    ) -> Result<Option<RestrictedExpr>> {
+        Ok(Some(match ty {
+            Type::Never => return Ok(None),
+            Type::True => RestrictedExpr::val(true),
+            Type::False => RestrictedExpr::val(false),
+            Type::Primitive {
+                primitive_type: Primitive::Bool,
+            } => RestrictedExpr::val(u.arbitrary::<bool>()?),
+            Type::Primitive {
+                primitive_type: Primitive::Long,
+            } => RestrictedExpr::val(u.arbitrary::<i64>()?),
+            Type::Primitive {
+                primitive_type: Primitive::String,
+            } => RestrictedExpr::val(u.arbitrary::<String>()?),
+            Type::Set { element_type } => {
+                let mut elements = Vec::new();
+                if let Some(element_type) = element_type {
+                    for _ in 0..u.int_in_range(0..=MAX_SET_SIZE)? {
+                        if let Some(element) = self.arbitrary_value(u, element_type)? {
+                            elements.push(element);
+                        }
+                    }
+                }
+                RestrictedExpr::set(elements)
+            }
+            Type::EntityOrRecord(EntityRecordKind::Record { attrs, .. }) => {
+                RestrictedExpr::record(self.arbitrary_attrs(u, attrs.iter())?)
+                    .map_err(|_| Error::IncorrectFormat)?
+            }
+            Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => {
+                let names = lub.iter().cloned().map(EntityType::Specified).collect();
+                RestrictedExpr::val(arbitrary_uid(u, names)?)
+            }
+            Type::EntityOrRecord(
+                EntityRecordKind::AnyEntity | EntityRecordKind::ActionEntity { .. },
+            ) => return Err(Error::IncorrectFormat),
+            Type::ExtensionType { name } => arbitrary_extension_value(u, name)?,
+        }))
+    }
+}
+
+/// Generate a value of the extension type `name` by calling its constructor
+fn arbitrary_extension_value(u: &mut Unstructured<'_>, name: &Name) -> Result<RestrictedExpr> {
+    let (constructor, arg) = match name.to_string().as_str() {
+        "ipaddr" => {
+            let [a, b, c, d] = u.arbitrary::<[u8; 4]>()?;
+            let prefix = u.int_in_range(0..=32)?;
+            ("ip", format!("{a}.{b}.{c}.{d}/{prefix}"))
+        }
+        "decimal" => {
+            let whole = u.arbitrary::<i32>()?;
+            let fraction = u.int_in_range(0..=9999)?;
+            ("decimal", format!("{whole}.{fraction:04}"))
+        }
+        _ => return Err(Error::IncorrectFormat),
+    };
+    let constructor = constructor.parse().map_err(|_| Error::IncorrectFormat)?;
+    Ok(RestrictedExpr::call_extension_fn(
+        constructor,
+        [RestrictedExpr::val(arg)],
+    ))
+}
+
+/// Generate a uid with one of the given entity types, and an id in the range
+/// used for generated entities
+fn arbitrary_uid(u: &mut Unstructured<'_>, types: Vec<EntityType>) -> Result<EntityUID> {
+    let ty = u.choose(&types)?;
+    let i = u.choose_index(IDS_PER_ENTITY_TYPE)?;
+    Ok(match ty {
+        EntityType::Specified(name) => entity_uid(name, i),
+        EntityType::Unspecified => EntityUID::unspecified_from_eid(Eid::new(i.to_string())),
+    })
+}
+
+fn entity_uid(name: &Name, i: usize) -> EntityUID {
+    EntityUID::from_components(name.clone(), Eid::new(i.to_string()), None)
+}
+
+/// Collect entity types in a deterministic order, so that the same input
+/// bytes always generate the same data
+fn sorted<'a>(types: impl Iterator<Item = &'a EntityType>) -> Vec<EntityType> {
+    let mut types = types.cloned().collect::<Vec<_>>();
+    types.sort();
+    types
+}
+
+// PANIC SAFETY: Unit Test Code
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cedar_policy_core::ast::RequestSchema;
+
+    /// Deterministic pseudo-random bytes for seeding `Unstructured`
+    fn bytes(seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..4096)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                state.to_be_bytes()[0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn arbitrary_data_conforms_to_schema() {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity Group in [Group];
+            entity User in [Group] {
+                name: String,
+                age?: Long,
+                ip: __cedar::ipaddr,
+                manager?: User,
+                tags: Set<String>,
+            };
+            entity Document {
+                owner: User,
+                limits: { size: __cedar::decimal, public?: Bool },
+            };
+            action read, write appliesTo {
+                principal: [User],
+                resource: [Document],
+                context: { mfa: Bool, reason?: String }
+            };
+            action join appliesTo { principal: [User], resource: [Group] };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let mut saw_parent = false;
+        for seed in 0..64 {
+            let data = bytes(seed);
+            let mut u = Unstructured::new(&data);
+            // `arbitrary_entities` validates the entities against the schema
+            let entities = schema.arbitrary_entities(&mut u).unwrap();
+            saw_parent |= entities.iter().any(|e| {
+                e.uid().entity_type() != &EntityType::Specified("Action".parse().unwrap())
+                    && e.ancestors().next().is_some()
+            });
+            let request = schema.arbitrary_request(&mut u).unwrap();
+            schema
+                .validate_request(&request, Extensions::all_available())
+                .unwrap_or_else(|err| panic!("invalid request {request}: {err}"));
+        }
+        assert!(
+            saw_parent,
+            "expected some generated entity to have a parent"
+        );
+    }
+
+    #[test]
+    fn no_applicable_action() {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            "entity User; action read;",
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let data = bytes(0);
+        assert!(matches!(
+            schema.arbitrary_request(&mut Unstructured::new(&data)),
+            Err(Error::IncorrectFormat)
+        ));
+        assert!(schema
+            .arbitrary_entities(&mut Unstructured::new(&data))
+            .is_ok());
+    }
+}
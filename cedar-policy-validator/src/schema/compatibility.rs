@@ -0,0 +1,595 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module compares two versions of a `ValidatorSchema`, classifying the
+//! changes between them as compatible or breaking.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use cedar_policy_core::ast::Name;
+use serde::Serialize;
+
+use super::ValidatorSchema;
+use crate::types::{Attributes, EntityRecordKind, Type};
+
+/// The changes from one schema to a newer version of it, as returned by
+/// [`ValidatorSchema::compatibility`].
+///
+/// A change is breaking if a policy which validates against the older schema
+/// may fail to validate against the newer one, or if entity data or a request
+/// which is valid for the older schema may be invalid for the newer one.
+/// Changes are ordered by the entity type or action they affect, entity types
+/// first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompatibilityReport {
+    changes: Vec<SchemaChange>,
+}
+
+impl CompatibilityReport {
+    /// All the changes, compatible and breaking
+    pub fn changes(&self) -> impl Iterator<Item = &SchemaChange> {
+        self.changes.iter()
+    }
+
+    /// The breaking changes
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &SchemaChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+
+    /// Whether none of the changes are breaking
+    pub fn is_compatible(&self) -> bool {
+        self.breaking_changes().next().is_none()
+    }
+}
+
+/// Where an attribute compared by [`ValidatorSchema::compatibility`] is
+/// declared
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "name", rename_all = "camelCase")]
+pub enum AttributeOwner {
+    /// The attributes of the named entity type
+    EntityType(String),
+    /// The context of the named action
+    Context(String),
+}
+
+impl Display for AttributeOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EntityType(name) => write!(f, "entity type `{name}`"),
+            Self::Context(name) => write!(f, "the context of action `{name}`"),
+        }
+    }
+}
+
+/// One change from a schema to a newer version of it, found by
+/// [`ValidatorSchema::compatibility`]. Entity types and actions are written
+/// as in policies, and attribute types as in the human-readable schema
+/// syntax. The `path` of an attribute is the attribute, preceded by the
+/// attributes of the records it is nested in, if any.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SchemaChange {
+    /// An entity type was added. This is compatible.
+    #[serde(rename_all = "camelCase")]
+    EntityTypeAdded {
+        /// The entity type
+        entity_type: String,
+    },
+    /// An entity type was removed. This is breaking.
+    #[serde(rename_all = "camelCase")]
+    EntityTypeRemoved {
+        /// The entity type
+        entity_type: String,
+    },
+    /// An action was added. This is compatible.
+    ActionAdded {
+        /// The action
+        action: String,
+    },
+    /// An action was removed. This is breaking.
+    ActionRemoved {
+        /// The action
+        action: String,
+    },
+    /// An entity type may now be a member of another entity type. This is
+    /// compatible.
+    #[serde(rename_all = "camelCase")]
+    MemberOfTypeAdded {
+        /// The member entity type
+        entity_type: String,
+        /// The entity type it may now be a member of
+        parent_type: String,
+    },
+    /// An entity type may no longer be a member of another entity type, so
+    /// entities with such parents are now invalid. This is breaking.
+    #[serde(rename_all = "camelCase")]
+    MemberOfTypeRemoved {
+        /// The member entity type
+        entity_type: String,
+        /// The entity type it may no longer be a member of
+        parent_type: String,
+    },
+    /// An action now applies to another principal or resource entity type.
+    /// This is compatible.
+    #[serde(rename_all = "camelCase")]
+    AppliesToTypeAdded {
+        /// The action
+        action: String,
+        /// `principal` or `resource`
+        variable: String,
+        /// The entity type
+        entity_type: String,
+    },
+    /// An action no longer applies to a principal or resource entity type.
+    /// This is breaking.
+    #[serde(rename_all = "camelCase")]
+    AppliesToTypeRemoved {
+        /// The action
+        action: String,
+        /// `principal` or `resource`
+        variable: String,
+        /// The entity type
+        entity_type: String,
+    },
+    /// An attribute was added. This is compatible if the attribute is
+    /// optional, and breaking if it is required, since existing entities or
+    /// requests don't have it.
+    AttributeAdded {
+        /// Where the attribute is declared
+        owner: AttributeOwner,
+        /// The path to the attribute
+        path: Vec<String>,
+        /// Whether the attribute is required
+        required: bool,
+    },
+    /// An attribute was removed, so policies using it no longer validate.
+    /// This is breaking.
+    AttributeRemoved {
+        /// Where the attribute was declared
+        owner: AttributeOwner,
+        /// The path to the attribute
+        path: Vec<String>,
+    },
+    /// An optional attribute became required, so existing entities or
+    /// requests without it are now invalid. This is breaking.
+    AttributeMadeRequired {
+        /// Where the attribute is declared
+        owner: AttributeOwner,
+        /// The path to the attribute
+        path: Vec<String>,
+    },
+    /// A required attribute became optional, so policies using it without
+    /// first testing for it with `has` no longer validate. This is breaking.
+    AttributeMadeOptional {
+        /// Where the attribute is declared
+        owner: AttributeOwner,
+        /// The path to the attribute
+        path: Vec<String>,
+    },
+    /// The type of an attribute changed, so existing values of it, or
+    /// policies using it, are now invalid. This is breaking. Attributes of
+    /// records are compared individually instead, so the types are never
+    /// both records.
+    #[serde(rename_all = "camelCase")]
+    AttributeTypeChanged {
+        /// Where the attribute is declared
+        owner: AttributeOwner,
+        /// The path to the attribute
+        path: Vec<String>,
+        /// The type in the older schema
+        old_type: String,
+        /// The type in the newer schema
+        new_type: String,
+    },
+}
+
+impl SchemaChange {
+    /// Whether this change is breaking
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            Self::EntityTypeAdded { .. }
+            | Self::ActionAdded { .. }
+            | Self::MemberOfTypeAdded { .. }
+            | Self::AppliesToTypeAdded { .. } => false,
+            Self::AttributeAdded { required, .. } => *required,
+            Self::EntityTypeRemoved { .. }
+            | Self::ActionRemoved { .. }
+            | Self::MemberOfTypeRemoved { .. }
+            | Self::AppliesToTypeRemoved { .. }
+            | Self::AttributeRemoved { .. }
+            | Self::AttributeMadeRequired { .. }
+            | Self::AttributeMadeOptional { .. }
+            | Self::AttributeTypeChanged { .. } => true,
+        }
+    }
+}
+
+impl Display for SchemaChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EntityTypeAdded { entity_type } => {
+                write!(f, "entity type `{entity_type}` was added")
+            }
+            Self::EntityTypeRemoved { entity_type } => {
+                write!(f, "entity type `{entity_type}` was removed")
+            }
+            Self::ActionAdded { action } => write!(f, "action `{action}` was added"),
+            Self::ActionRemoved { action } => write!(f, "action `{action}` was removed"),
+            Self::MemberOfTypeAdded {
+                entity_type,
+                parent_type,
+            } => write!(
+                f,
+                "entity type `{entity_type}` may now be a member of `{parent_type}`"
+            ),
+            Self::MemberOfTypeRemoved {
+                entity_type,
+                parent_type,
+            } => write!(
+                f,
+                "entity type `{entity_type}` may no longer be a member of `{parent_type}`"
+            ),
+            Self::AppliesToTypeAdded {
+                action,
+                variable,
+                entity_type,
+            } => write!(
+                f,
+                "action `{action}` now applies to {variable} type `{entity_type}`"
+            ),
+            Self::AppliesToTypeRemoved {
+                action,
+                variable,
+                entity_type,
+            } => write!(
+                f,
+                "action `{action}` no longer applies to {variable} type `{entity_type}`"
+            ),
+            Self::AttributeAdded {
+                owner,
+                path,
+                required,
+            } => write!(
+                f,
+                "{} attribute `{}` was added to {owner}",
+                if *required { "required" } else { "optional" },
+                path.join(".")
+            ),
+            Self::AttributeRemoved { owner, path } => {
+                write!(f, "attribute `{}` was removed from {owner}", path.join("."))
+            }
+            Self::AttributeMadeRequired { owner, path } => write!(
+                f,
+                "attribute `{}` of {owner} is now required",
+                path.join(".")
+            ),
+            Self::AttributeMadeOptional { owner, path } => write!(
+                f,
+                "attribute `{}` of {owner} is now optional",
+                path.join(".")
+            ),
+            Self::AttributeTypeChanged {
+                owner,
+                path,
+                old_type,
+                new_type,
+            } => write!(
+                f,
+                "attribute `{}` of {owner} changed type from `{old_type}` to `{new_type}`",
+                path.join(".")
+            ),
+        }
+    }
+}
+
+impl ValidatorSchema {
+    /// Compare this schema with a `newer` version of it, classifying each
+    /// change as compatible or breaking. Attributes of records are compared
+    /// individually, however deeply nested.
+    pub fn compatibility(&self, newer: &ValidatorSchema) -> CompatibilityReport {
+        let mut changes = Vec::new();
+
+        let old_entity_types: BTreeMap<_, _> = self.entity_types.iter().collect();
+        let new_entity_types: BTreeMap<_, _> = newer.entity_types.iter().collect();
+        let old_parents = self.parent_types();
+        let new_parents = newer.parent_types();
+        for (name, old_ety) in &old_entity_types {
+            let entity_type = name.to_string();
+            let Some(new_ety) = new_entity_types.get(name) else {
+                changes.push(SchemaChange::EntityTypeRemoved { entity_type });
+                continue;
+            };
+            let no_parents = BTreeSet::new();
+            let old_parents = old_parents.get(name).unwrap_or(&no_parents);
+            let new_parents = new_parents.get(name).unwrap_or(&no_parents);
+            changes.extend(old_parents.difference(new_parents).map(|parent| {
+                SchemaChange::MemberOfTypeRemoved {
+                    entity_type: entity_type.clone(),
+                    parent_type: parent.to_string(),
+                }
+            }));
+            changes.extend(new_parents.difference(old_parents).map(|parent| {
+                SchemaChange::MemberOfTypeAdded {
+                    entity_type: entity_type.clone(),
+                    parent_type: parent.to_string(),
+                }
+            }));
+            compare_attributes(
+                &AttributeOwner::EntityType(entity_type),
+                &[],
+                &old_ety.attributes,
+                &new_ety.attributes,
+                &mut changes,
+            );
+        }
+        changes.extend(
+            new_entity_types
+                .keys()
+                .filter(|name| !old_entity_types.contains_key(*name))
+                .map(|name| SchemaChange::EntityTypeAdded {
+                    entity_type: name.to_string(),
+                }),
+        );
+
+        let old_actions: BTreeMap<_, _> = self.action_ids.iter().collect();
+        let new_actions: BTreeMap<_, _> = newer.action_ids.iter().collect();
+        for (name, old_action) in &old_actions {
+            let action = name.to_string();
+            let Some(new_action) = new_actions.get(name) else {
+                changes.push(SchemaChange::ActionRemoved { action });
+                continue;
+            };
+            for (variable, old_types, new_types) in [
+                (
+                    "principal",
+                    old_action
+                        .applicable_principal_types()
+                        .collect::<BTreeSet<_>>(),
+                    new_action
+                        .applicable_principal_types()
+                        .collect::<BTreeSet<_>>(),
+                ),
+                (
+                    "resource",
+                    old_action
+                        .applicable_resource_types()
+                        .collect::<BTreeSet<_>>(),
+                    new_action
+                        .applicable_resource_types()
+                        .collect::<BTreeSet<_>>(),
+                ),
+            ] {
+                changes.extend(old_types.difference(&new_types).map(|ety| {
+                    SchemaChange::AppliesToTypeRemoved {
+                        action: action.clone(),
+                        variable: variable.to_string(),
+                        entity_type: ety.to_string(),
+                    }
+                }));
+                changes.extend(new_types.difference(&old_types).map(|ety| {
+                    SchemaChange::AppliesToTypeAdded {
+                        action: action.clone(),
+                        variable: variable.to_string(),
+                        entity_type: ety.to_string(),
+                    }
+                }));
+            }
+            compare_types(
+                &AttributeOwner::Context(action),
+                &[],
+                &old_action.context,
+                &new_action.context,
+                &mut changes,
+            );
+        }
+        changes.extend(
+            new_actions
+                .keys()
+                .filter(|name| !old_actions.contains_key(*name))
+                .map(|name| SchemaChange::ActionAdded {
+                    action: name.to_string(),
+                }),
+        );
+
+        CompatibilityReport { changes }
+    }
+
+    /// Map each entity type to the entity types it may be a member of,
+    /// directly or transitively
+    fn parent_types(&self) -> BTreeMap<&Name, BTreeSet<&Name>> {
+        let mut parents: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for (name, ety) in &self.entity_types {
+            for descendant in &ety.descendants {
+                parents.entry(descendant).or_default().insert(name);
+            }
+        }
+        parents
+    }
+}
+
+/// Compare the attributes of two record types, which are the attributes at
+/// `path` of `owner`
+fn compare_attributes(
+    owner: &AttributeOwner,
+    path: &[String],
+    old: &Attributes,
+    new: &Attributes,
+    changes: &mut Vec<SchemaChange>,
+) {
+    let attr_path = |attr: &str| {
+        let mut path = path.to_vec();
+        path.push(attr.to_string());
+        path
+    };
+    for (attr, old_ty) in old.iter() {
+        let path = attr_path(attr);
+        let Some(new_ty) = new.get_attr(attr) else {
+            changes.push(SchemaChange::AttributeRemoved {
+                owner: owner.clone(),
+                path,
+            });
+            continue;
+        };
+        match (old_ty.is_required, new_ty.is_required) {
+            (false, true) => changes.push(SchemaChange::AttributeMadeRequired {
+                owner: owner.clone(),
+                path: path.clone(),
+            }),
+            (true, false) => changes.push(SchemaChange::AttributeMadeOptional {
+                owner: owner.clone(),
+                path: path.clone(),
+            }),
+            _ => (),
+        }
+        compare_types(owner, &path, &old_ty.attr_type, &new_ty.attr_type, changes);
+    }
+    changes.extend(
+        new.iter()
+            .filter(|(attr, _)| old.get_attr(attr).is_none())
+            .map(|(attr, ty)| SchemaChange::AttributeAdded {
+                owner: owner.clone(),
+                path: attr_path(attr),
+                required: ty.is_required,
+            }),
+    );
+}
+
+/// Compare two types of the attribute at `path` of `owner`. If `path` is
+/// empty, these are the types of the context of an action.
+fn compare_types(
+    owner: &AttributeOwner,
+    path: &[String],
+    old: &Type,
+    new: &Type,
+    changes: &mut Vec<SchemaChange>,
+) {
+    match (old, new) {
+        (
+            Type::EntityOrRecord(EntityRecordKind::Record { attrs: old, .. }),
+            Type::EntityOrRecord(EntityRecordKind::Record { attrs: new, .. }),
+        ) => compare_attributes(owner, path, old, new, changes),
+        _ if old != new => changes.push(SchemaChange::AttributeTypeChanged {
+            owner: owner.clone(),
+            path: path.to_vec(),
+            old_type: old.display_cedarschema(),
+            new_type: new.display_cedarschema(),
+        }),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cedar_policy_core::extensions::Extensions;
+
+    use super::*;
+
+    fn schema(src: &str) -> ValidatorSchema {
+        ValidatorSchema::from_str_natural(src, Extensions::all_available())
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn identical_schemas() {
+        let src = r#"
+            entity Group;
+            entity User in [Group] = { name: String, address?: { city: String } };
+            action view appliesTo { principal: User, resource: Group, context: { mfa: Bool } };
+        "#;
+        let report = schema(src).compatibility(&schema(src));
+        assert_eq!(report.changes().count(), 0);
+        assert!(report.is_compatible());
+    }
+
+    #[test]
+    fn compatible_changes() {
+        let older = schema(
+            r#"
+            entity Group;
+            entity User = { name: String };
+            action view appliesTo { principal: User, resource: Group };
+            "#,
+        );
+        let newer = schema(
+            r#"
+            entity Group;
+            entity Team;
+            entity User in [Group] = { name: String, nickname?: String };
+            action view appliesTo { principal: User, resource: [Group, Team] };
+            action edit appliesTo { principal: User, resource: Group };
+            "#,
+        );
+        let report = older.compatibility(&newer);
+        assert!(report.is_compatible());
+        assert_eq!(
+            report
+                .changes()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "entity type `User` may now be a member of `Group`",
+                "optional attribute `nickname` was added to entity type `User`",
+                "entity type `Team` was added",
+                r#"action `Action::"view"` now applies to resource type `Team`"#,
+                r#"action `Action::"edit"` was added"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn breaking_changes() {
+        let older = schema(
+            r#"
+            entity Group;
+            entity Doc;
+            entity User in [Group] = { name: String, age?: Long, address: { city: String, zip: String } };
+            action view appliesTo { principal: User, resource: [Doc, Group], context: { mfa: Bool } };
+            action edit appliesTo { principal: User, resource: Doc };
+            "#,
+        );
+        let newer = schema(
+            r#"
+            entity Group;
+            entity User = { name: Long, age: Long, address: { city?: String }, email: String };
+            action view appliesTo { principal: User, resource: Group, context: { mfa: Bool, ip: ipaddr } };
+            "#,
+        );
+        let report = older.compatibility(&newer);
+        assert!(!report.is_compatible());
+        assert_eq!(report.breaking_changes().count(), report.changes().count());
+        assert_eq!(
+            report
+                .changes()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "entity type `Doc` was removed",
+                "entity type `User` may no longer be a member of `Group`",
+                "attribute `address.city` of entity type `User` is now optional",
+                "attribute `address.zip` was removed from entity type `User`",
+                "attribute `age` of entity type `User` is now required",
+                "attribute `name` of entity type `User` changed type from `String` to `Long`",
+                "required attribute `email` was added to entity type `User`",
+                r#"action `Action::"edit"` was removed"#,
+                r#"action `Action::"view"` no longer applies to resource type `Doc`"#,
+                r#"required attribute `ip` was added to the context of action `Action::"view"`"#,
+            ]
+        );
+    }
+}
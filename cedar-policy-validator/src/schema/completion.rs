@@ -0,0 +1,211 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains the definition of `CompletionContext`, a summary of
+//! the names declared by a schema for use by editor autocompletion.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use cedar_policy_core::ast::{EntityType, EntityUID, Name};
+use smol_str::SmolStr;
+
+use super::ValidatorSchema;
+use crate::types::AttributeType;
+
+/// Everything an editor needs from a schema to offer completions while a
+/// policy is written, gathered in a single pass over the schema. Every
+/// collection is sorted so that completions are listed in a stable order.
+#[derive(Clone, Debug)]
+pub struct CompletionContext {
+    namespaces: BTreeSet<SmolStr>,
+    entity_types: BTreeMap<Name, BTreeMap<SmolStr, AttributeType>>,
+    actions: BTreeSet<EntityUID>,
+    common_types: BTreeSet<Name>,
+}
+
+impl CompletionContext {
+    /// The non-empty namespaces in which the schema declares an entity type,
+    /// action, or common type, e.g., `"NS1::NS2"`.
+    pub fn namespaces(&self) -> impl Iterator<Item = &str> {
+        self.namespaces.iter().map(SmolStr::as_str)
+    }
+
+    /// The fully qualified names of the entity types declared in the schema.
+    pub fn entity_types(&self) -> impl Iterator<Item = &Name> {
+        self.entity_types.keys()
+    }
+
+    /// The attributes declared for the entity type `ety`, or `None` if `ety`
+    /// is not declared in the schema.
+    pub fn entity_type_attributes(
+        &self,
+        ety: &Name,
+    ) -> Option<impl Iterator<Item = (&SmolStr, &AttributeType)>> {
+        self.entity_types.get(ety).map(BTreeMap::iter)
+    }
+
+    /// The UIDs of the actions declared in the schema.
+    pub fn actions(&self) -> impl Iterator<Item = &EntityUID> {
+        self.actions.iter()
+    }
+
+    /// The fully qualified names of the common types declared in the schema.
+    pub fn common_types(&self) -> impl Iterator<Item = &Name> {
+        self.common_types.iter()
+    }
+}
+
+impl ValidatorSchema {
+    /// Collect the names declared by this schema into a [`CompletionContext`].
+    /// This is meant to be computed once when a schema is loaded, rather than
+    /// querying the schema for each completion request.
+    pub fn completion_context(&self) -> CompletionContext {
+        let entity_types: BTreeMap<_, _> = self
+            .entity_types
+            .iter()
+            .map(|(name, ety)| {
+                let attrs = ety
+                    .attributes()
+                    .map(|(attr, ty)| (attr.clone(), ty.clone()))
+                    .collect();
+                (name.clone(), attrs)
+            })
+            .collect();
+        let actions: BTreeSet<_> = self.action_ids.keys().cloned().collect();
+        let common_types: BTreeSet<_> = self.common_types.iter().cloned().collect();
+        let namespaces = entity_types
+            .keys()
+            .chain(&common_types)
+            .chain(actions.iter().filter_map(|euid| match euid.entity_type() {
+                EntityType::Specified(name) => Some(name),
+                EntityType::Unspecified => None,
+            }))
+            .map(Name::namespace)
+            .filter(|namespace| !namespace.is_empty())
+            .map(SmolStr::from)
+            .collect();
+        CompletionContext {
+            namespaces,
+            entity_types,
+            actions,
+            common_types,
+        }
+    }
+}
+
+// PANIC SAFETY: Unit Test Code
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use cedar_policy_core::extensions::Extensions;
+
+    use super::*;
+    use crate::types::Type;
+
+    #[test]
+    fn completion_context() {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            namespace Photos {
+                type Tags = Set<String>;
+                entity User = { name: String, tags?: Tags };
+                entity Album;
+                entity Photo in [Album] = { owner: User };
+                action view, edit appliesTo { principal: User, resource: Photo };
+            }
+            namespace Admin::Tools {
+                type Level = Long;
+            }
+            entity Global;
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let completions = schema.completion_context();
+
+        assert_eq!(
+            completions.namespaces().collect::<Vec<_>>(),
+            vec!["Admin::Tools", "Photos"]
+        );
+        let entity_types = completions
+            .entity_types()
+            .map(ToString::to_string)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            entity_types,
+            BTreeSet::from([
+                "Global".to_string(),
+                "Photos::Album".to_string(),
+                "Photos::Photo".to_string(),
+                "Photos::User".to_string()
+            ])
+        );
+        let actions = completions
+            .actions()
+            .map(ToString::to_string)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            actions,
+            BTreeSet::from([
+                r#"Photos::Action::"edit""#.to_string(),
+                r#"Photos::Action::"view""#.to_string()
+            ])
+        );
+        let common_types = completions
+            .common_types()
+            .map(ToString::to_string)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            common_types,
+            BTreeSet::from([
+                "Admin::Tools::Level".to_string(),
+                "Photos::Tags".to_string()
+            ])
+        );
+
+        let user_attrs = completions
+            .entity_type_attributes(&"Photos::User".parse().unwrap())
+            .unwrap()
+            .map(|(attr, ty)| (attr.as_str(), ty.is_required))
+            .collect::<Vec<_>>();
+        assert_eq!(user_attrs, vec![("name", true), ("tags", false)]);
+        let (_, tags) = completions
+            .entity_type_attributes(&"Photos::User".parse().unwrap())
+            .unwrap()
+            .find(|(attr, _)| attr.as_str() == "tags")
+            .unwrap();
+        assert_eq!(tags.attr_type, Type::set(Type::primitive_string()));
+        assert_eq!(
+            completions
+                .entity_type_attributes(&"Photos::Album".parse().unwrap())
+                .unwrap()
+                .count(),
+            0
+        );
+        assert!(completions
+            .entity_type_attributes(&"Photos::Missing".parse().unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn completion_context_empty() {
+        let completions = ValidatorSchema::empty().completion_context();
+        assert_eq!(completions.namespaces().count(), 0);
+        assert_eq!(completions.entity_types().count(), 0);
+        assert_eq!(completions.actions().count(), 0);
+        assert_eq!(completions.common_types().count(), 0);
+    }
+}
@@ -18,10 +18,10 @@
 
 use serde::Serialize;
 use smol_str::SmolStr;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use cedar_policy_core::{
-    ast::{EntityType, Name},
+    ast::{EntityType, Expr, Name},
     transitive_closure::TCNode,
 };
 
@@ -30,7 +30,7 @@ use crate::types::{AttributeType, Attributes, OpenTag};
 /// Contains entity type information for use by the validator. The contents of
 /// the struct are the same as the schema entity type structure, but the
 /// `member_of` relation is reversed to instead be `descendants`.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct ValidatorEntityType {
     /// The name of the entity type.
     pub(crate) name: Name,
@@ -51,6 +51,16 @@ pub struct ValidatorEntityType {
     /// their type when they are present. Attempting to access an undeclared
     /// attribute under standard validation is an error regardless of this flag.
     pub(crate) open_attributes: OpenTag,
+
+    /// The expressions computing the attributes of this entity type which are
+    /// derived from its other attributes, in which the entity is `resource`.
+    /// Derived attributes are also in `attributes`, with the types of their
+    /// expressions.
+    pub(crate) derived_attributes: HashMap<SmolStr, Expr>,
+
+    /// Groups of optional attributes of which an entity of this type may have
+    /// at most one.
+    pub(crate) mutually_exclusive: Vec<Vec<SmolStr>>,
 }
 
 impl ValidatorEntityType {
@@ -59,11 +69,29 @@ impl ValidatorEntityType {
         self.attributes.get_attr(attr)
     }
 
+    /// Is the attribute with the given name computed from the other attributes
+    /// of this entity, rather than given in entity data?
+    pub fn is_derived_attr(&self, attr: &str) -> bool {
+        self.derived_attributes.contains_key(attr)
+    }
+
+    /// The groups of attributes of which an entity of this type may have at
+    /// most one
+    pub fn mutually_exclusive_attrs(&self) -> impl Iterator<Item = &[SmolStr]> {
+        self.mutually_exclusive.iter().map(Vec::as_slice)
+    }
+
     /// An iterator over the attributes of this entity
     pub fn attributes(&self) -> impl Iterator<Item = (&SmolStr, &AttributeType)> {
         self.attributes.iter()
     }
 
+    /// May entities of this type have attributes other than the declared
+    /// ones? This is only possible with partial schema validation.
+    pub fn has_open_attributes(&self) -> bool {
+        self.open_attributes.is_open()
+    }
+
     /// Return `true` if this entity type has an `EntityType` declared as a
     /// possible descendant in the schema. This takes an `EntityType` rather
     /// than a `Name`, It's not possible to declare the unspecified entity type
@@ -21,8 +21,8 @@ use std::collections::{HashMap, HashSet};
 
 use cedar_policy_core::{
     ast::{
-        Eid, EntityAttrEvaluationError, EntityType, EntityUID, Id, Name,
-        PartialValueSerializedAsExpr,
+        BinaryOp, Eid, EntityAttrEvaluationError, EntityType, EntityUID, Expr, ExprKind, Id,
+        Literal, Name, PartialValueSerializedAsExpr, Var,
     },
     entities::{json::err::JsonDeserializationErrorContext, CedarValueJson},
     evaluator::RestrictedEvaluator,
@@ -113,6 +113,14 @@ pub struct EntityTypeFragment {
     /// namespace, so we will check if they are declared in any fragment when
     /// constructing a `ValidatorSchema`.
     pub(super) parents: HashSet<Name>,
+    /// The expressions computing the derived attributes of this entity type.
+    /// These only read the attributes of the entity, but their types are not
+    /// known until the whole schema is available.
+    pub(super) derived_attributes: HashMap<SmolStr, Expr>,
+    /// Groups of attributes of which an entity of this type may have at most
+    /// one. These are checked against the attributes once typedefs are
+    /// resolved.
+    pub(super) mutually_exclusive: Vec<Vec<SmolStr>>,
 }
 
 /// Action declarations held in a `ValidatorNamespaceDef`. Entity types
@@ -138,6 +146,9 @@ pub struct ActionFragment {
     /// separately so that we can later extract use these values to construct
     /// the actual `Entity` objects defined by the schema.
     pub(super) attributes: HashMap<SmolStr, PartialValueSerializedAsExpr>,
+    /// The default values for optional context attributes. These are checked
+    /// against the context type once its typedefs have been resolved.
+    pub(super) context_defaults: HashMap<SmolStr, PartialValueSerializedAsExpr>,
 }
 
 type ResolveFunc<T> = dyn FnOnce(&HashMap<Name, Type>) -> Result<T>;
@@ -302,11 +313,18 @@ impl ValidatorNamespaceDef {
                         entity_type.shape.into_inner(),
                     )?;
 
+                    let derived_attributes = Self::parse_derived_attributes(
+                        entity_type.derived_attributes.unwrap_or_default(),
+                        &name,
+                    )?;
+
                     Ok((
                         name,
                         EntityTypeFragment {
                             attributes,
                             parents,
+                            derived_attributes,
+                            mutually_exclusive: entity_type.mutually_exclusive.unwrap_or_default(),
                         },
                     ))
                 })
@@ -314,6 +332,62 @@ impl ValidatorNamespaceDef {
         })
     }
 
+    // Parse the expressions computing the derived attributes of an entity
+    // type, checking that they don't refer to anything but the entity itself.
+    // Reading attributes of other entities is checked once the expressions are
+    // typechecked.
+    fn parse_derived_attributes(
+        derived_attributes: HashMap<SmolStr, SmolStr>,
+        entity_type: &Name,
+    ) -> Result<HashMap<SmolStr, Expr>> {
+        if cfg!(not(feature = "derived-attributes")) && !derived_attributes.is_empty() {
+            return Err(SchemaError::UnsupportedFeature(
+                UnsupportedFeature::DerivedAttributes,
+            ));
+        }
+        derived_attributes
+            .into_iter()
+            .map(|(attr, src)| {
+                let invalid = |reason: String| SchemaError::InvalidDerivedAttribute {
+                    entity_type: entity_type.clone(),
+                    attr: attr.clone(),
+                    reason,
+                };
+                let expr = src
+                    .parse::<Expr>()
+                    .map_err(|err| invalid(err.to_string()))?;
+                for e in expr.subexpressions() {
+                    match e.expr_kind() {
+                        ExprKind::Var(Var::Resource) => {}
+                        ExprKind::Var(var) => {
+                            return Err(invalid(format!(
+                                "`{var}` is not available, since the entity is `resource`"
+                            )))
+                        }
+                        ExprKind::Slot(_) | ExprKind::Unknown(_) => {
+                            return Err(invalid(format!("`{e}` is not allowed")))
+                        }
+                        ExprKind::Lit(Literal::EntityUID(_)) | ExprKind::Is { .. } => {
+                            return Err(invalid(
+                                "entity types and entities may not be referenced".to_owned(),
+                            ))
+                        }
+                        ExprKind::BinaryApp {
+                            op: BinaryOp::In, ..
+                        } => {
+                            return Err(invalid(
+                                "`in` is not allowed, since it reads the entity hierarchy"
+                                    .to_owned(),
+                            ))
+                        }
+                        _ => {}
+                    }
+                }
+                Ok((attr, expr))
+            })
+            .collect()
+    }
+
     // Helper to get types from `CedarValueJson`s. Currently doesn't support all
     // `CedarValueJson` types. Note: If this function is extended to cover move
     // `CedarValueJson`s, we must update `convert_attr_jsonval_map_to_attributes` to
@@ -423,6 +497,32 @@ impl ValidatorNamespaceDef {
         ))
     }
 
+    // Evaluate the default values of context attributes for an action
+    fn convert_context_defaults(
+        defaults: HashMap<SmolStr, CedarValueJson>,
+        action_id: &EntityUID,
+        extensions: Extensions<'_>,
+    ) -> Result<HashMap<SmolStr, PartialValueSerializedAsExpr>> {
+        let evaluator = RestrictedEvaluator::new(&extensions);
+        defaults
+            .into_iter()
+            .map(|(attr, v)| {
+                let invalid = |reason: String| SchemaError::InvalidContextDefault {
+                    action: action_id.clone(),
+                    attr: attr.clone(),
+                    reason,
+                };
+                let e = v
+                    .into_expr(|| JsonDeserializationErrorContext::Context)
+                    .map_err(|err| invalid(err.to_string()))?;
+                let pv = evaluator
+                    .partial_interpret(e.as_borrowed())
+                    .map_err(|err| invalid(err.to_string()))?;
+                Ok((attr, pv.into()))
+            })
+            .collect()
+    }
+
     // Transform the schema data structures for actions into the structures used
     // internally by the validator. This is mostly accomplished by directly
     // copying data between fields.
@@ -440,13 +540,14 @@ impl ValidatorNamespaceDef {
                         schema_namespace,
                     );
 
-                    let (principal_types, resource_types, context) = action_type
+                    let (principal_types, resource_types, context, context_defaults) = action_type
                         .applies_to
                         .map(|applies_to| {
                             (
                                 applies_to.principal_types,
                                 applies_to.resource_types,
                                 applies_to.context,
+                                applies_to.context_defaults,
                             )
                         })
                         .unwrap_or_default();
@@ -464,6 +565,12 @@ impl ValidatorNamespaceDef {
                         context.into_inner(),
                     )?;
 
+                    let context_defaults = Self::convert_context_defaults(
+                        context_defaults.unwrap_or_default(),
+                        &action_id,
+                        extensions,
+                    )?;
+
                     let parents = action_type
                         .member_of
                         .unwrap_or_default()
@@ -488,6 +595,7 @@ impl ValidatorNamespaceDef {
                             parents,
                             attribute_types,
                             attributes,
+                            context_defaults,
                         },
                     ))
                 })
@@ -0,0 +1,411 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains the rebasing of a `ValidatorSchema`, or the
+//! `SchemaFragment` it was constructed from, from one namespace into another,
+//! used when vendoring a schema into a namespace of its own.
+
+use std::collections::{HashMap, HashSet};
+
+use cedar_policy_core::ast::{Name, PartialValueSerializedAsExpr};
+use cedar_policy_core::entities::CedarValueJson;
+use smol_str::SmolStr;
+
+use super::{action::ValidatorApplySpec, ValidatorActionId, ValidatorEntityType, ValidatorSchema};
+use crate::err::*;
+use crate::{
+    ActionEntityUID, ActionType, ApplySpec, AttributesOrContext, EntityType, NamespaceDefinition,
+    SchemaFragment, SchemaType, SchemaTypeVariant, TypeOfAttribute,
+};
+
+impl ValidatorSchema {
+    /// Move every entity type, action, and common type declared in the
+    /// namespace `from`, or in a namespace nested in `from`, into `to`,
+    /// rewriting every reference to them: in the hierarchy, in `appliesTo`,
+    /// in attribute and context types, and in action attribute values. For
+    /// instance, rebasing from `Vendor` to `MyApp::Vendor` turns the entity
+    /// type `Vendor::User` into `MyApp::Vendor::User`. Names outside of `from`
+    /// are unchanged.
+    ///
+    /// This fails if a moved entity type, action, or common type has the same
+    /// name as one already declared in `to`. Otherwise, rebasing the result
+    /// from `to` back to `from` gives the original schema, as long as nothing
+    /// was declared in `to` before.
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> Result<ValidatorSchema> {
+        let mut entity_types = HashMap::with_capacity(self.entity_types.len());
+        for ety in self.entity_types.values() {
+            let ety = ValidatorEntityType {
+                name: ety.name.rebase_namespace(from, to),
+                descendants: ety
+                    .descendants
+                    .iter()
+                    .map(|name| name.rebase_namespace(from, to))
+                    .collect(),
+                attributes: ety.attributes.rebase_namespace(from, to),
+                open_attributes: ety.open_attributes,
+                derived_attributes: ety
+                    .derived_attributes
+                    .iter()
+                    .map(|(attr, expr)| (attr.clone(), expr.rebase_namespace(from, to)))
+                    .collect(),
+                mutually_exclusive: ety.mutually_exclusive.clone(),
+            };
+            if let Some(ety) = entity_types.insert(ety.name.clone(), ety) {
+                return Err(SchemaError::DuplicateEntityType(ety.name.to_string()));
+            }
+        }
+
+        let rebase_values = |values: &HashMap<SmolStr, PartialValueSerializedAsExpr>| {
+            values
+                .iter()
+                .map(|(k, v)| (k.clone(), v.rebase_namespace(from, to).into()))
+                .collect()
+        };
+        let mut action_ids = HashMap::with_capacity(self.action_ids.len());
+        for action in self.action_ids.values() {
+            let action = ValidatorActionId {
+                name: action.name.rebase_namespace(from, to),
+                applies_to: ValidatorApplySpec::new(
+                    action
+                        .applies_to
+                        .applicable_principal_types()
+                        .map(|ty| ty.rebase_namespace(from, to))
+                        .collect(),
+                    action
+                        .applies_to
+                        .applicable_resource_types()
+                        .map(|ty| ty.rebase_namespace(from, to))
+                        .collect(),
+                ),
+                descendants: action
+                    .descendants
+                    .iter()
+                    .map(|euid| euid.rebase_namespace(from, to))
+                    .collect(),
+                context: action.context.rebase_namespace(from, to),
+                attribute_types: action.attribute_types.rebase_namespace(from, to),
+                attributes: rebase_values(&action.attributes),
+                context_defaults: rebase_values(&action.context_defaults),
+            };
+            if let Some(action) = action_ids.insert(action.name.clone(), action) {
+                return Err(SchemaError::DuplicateAction(action.name.to_string()));
+            }
+        }
+
+        let mut common_types = HashSet::with_capacity(self.common_types.len());
+        for name in &self.common_types {
+            let name = name.rebase_namespace(from, to);
+            if !common_types.insert(name.clone()) {
+                return Err(SchemaError::DuplicateCommonType(name.to_string()));
+            }
+        }
+
+        Ok(ValidatorSchema {
+            entity_types,
+            action_ids,
+            common_types,
+        })
+    }
+}
+
+impl SchemaFragment {
+    /// Move every namespace which is `from` or nested in `from` into `to`,
+    /// and rewrite every qualified name in `from`, like
+    /// [`ValidatorSchema::rebase_namespace`] does for a `ValidatorSchema`.
+    /// Unqualified names are unchanged, since they refer to the namespace
+    /// they are declared in. If a moved namespace is already declared, the
+    /// two are combined with [`SchemaFragment::merge`], so this should only
+    /// be used when rebasing the `ValidatorSchema` doesn't fail.
+    pub fn rebase_namespace(&self, from: &Name, to: &Name) -> SchemaFragment {
+        SchemaFragment::merge(self.0.iter().map(|(ns, ns_def)| {
+            let ns = ns.as_ref().map(|ns| {
+                if ns == from {
+                    to.clone()
+                } else {
+                    ns.rebase_namespace(from, to)
+                }
+            });
+            SchemaFragment(HashMap::from([(ns, ns_def.rebase_namespace(from, to))]))
+        }))
+    }
+}
+
+impl NamespaceDefinition {
+    fn rebase_namespace(&self, from: &Name, to: &Name) -> NamespaceDefinition {
+        NamespaceDefinition {
+            common_types: self
+                .common_types
+                .iter()
+                .map(|(id, ty)| (id.clone(), ty.rebase_namespace(from, to)))
+                .collect(),
+            entity_types: self
+                .entity_types
+                .iter()
+                .map(|(id, ety)| {
+                    let ety = EntityType {
+                        member_of_types: rebase_names(&ety.member_of_types, from, to),
+                        shape: ety.shape.rebase_namespace(from, to),
+                        derived_attributes: ety.derived_attributes.clone(),
+                        mutually_exclusive: ety.mutually_exclusive.clone(),
+                    };
+                    (id.clone(), ety)
+                })
+                .collect(),
+            actions: self
+                .actions
+                .iter()
+                .map(|(id, action)| (id.clone(), action.rebase_namespace(from, to)))
+                .collect(),
+        }
+    }
+}
+
+impl ActionType {
+    fn rebase_namespace(&self, from: &Name, to: &Name) -> ActionType {
+        ActionType {
+            attributes: self
+                .attributes
+                .as_ref()
+                .map(|attrs| rebase_values(attrs, from, to)),
+            applies_to: self.applies_to.as_ref().map(|spec| ApplySpec {
+                resource_types: spec
+                    .resource_types
+                    .as_ref()
+                    .map(|tys| rebase_names(tys, from, to)),
+                principal_types: spec
+                    .principal_types
+                    .as_ref()
+                    .map(|tys| rebase_names(tys, from, to)),
+                context: spec.context.rebase_namespace(from, to),
+                context_defaults: spec
+                    .context_defaults
+                    .as_ref()
+                    .map(|defaults| rebase_values(defaults, from, to)),
+            }),
+            member_of: self.member_of.as_ref().map(|parents| {
+                parents
+                    .iter()
+                    .map(|parent| ActionEntityUID {
+                        id: parent.id.clone(),
+                        ty: parent.ty.as_ref().map(|ty| ty.rebase_namespace(from, to)),
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
+impl AttributesOrContext {
+    fn rebase_namespace(&self, from: &Name, to: &Name) -> AttributesOrContext {
+        AttributesOrContext(self.0.rebase_namespace(from, to))
+    }
+}
+
+impl SchemaType {
+    fn rebase_namespace(&self, from: &Name, to: &Name) -> SchemaType {
+        match self {
+            SchemaType::TypeDef { type_name } => SchemaType::TypeDef {
+                type_name: type_name.rebase_namespace(from, to),
+            },
+            SchemaType::Type(SchemaTypeVariant::Entity { name }) => {
+                SchemaType::Type(SchemaTypeVariant::Entity {
+                    name: name.rebase_namespace(from, to),
+                })
+            }
+            SchemaType::Type(SchemaTypeVariant::Set { element }) => {
+                SchemaType::Type(SchemaTypeVariant::Set {
+                    element: Box::new(element.rebase_namespace(from, to)),
+                })
+            }
+            SchemaType::Type(SchemaTypeVariant::Record {
+                attributes,
+                additional_attributes,
+            }) => SchemaType::Type(SchemaTypeVariant::Record {
+                attributes: attributes
+                    .iter()
+                    .map(|(attr, ty)| {
+                        let ty = TypeOfAttribute {
+                            ty: ty.ty.rebase_namespace(from, to),
+                            required: ty.required,
+                        };
+                        (attr.clone(), ty)
+                    })
+                    .collect(),
+                additional_attributes: *additional_attributes,
+            }),
+            SchemaType::Type(
+                SchemaTypeVariant::String
+                | SchemaTypeVariant::Long
+                | SchemaTypeVariant::Boolean
+                | SchemaTypeVariant::Extension { .. },
+            ) => self.clone(),
+        }
+    }
+}
+
+fn rebase_names(names: &[Name], from: &Name, to: &Name) -> Vec<Name> {
+    names
+        .iter()
+        .map(|name| name.rebase_namespace(from, to))
+        .collect()
+}
+
+fn rebase_values(
+    values: &HashMap<SmolStr, CedarValueJson>,
+    from: &Name,
+    to: &Name,
+) -> HashMap<SmolStr, CedarValueJson> {
+    values
+        .iter()
+        .map(|(k, v)| (k.clone(), v.rebase_namespace(from, to)))
+        .collect()
+}
+
+// PANIC SAFETY: Unit Test Code
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use cedar_policy_core::{ast::EntityUID, extensions::Extensions};
+
+    use super::*;
+    use crate::types::Type;
+
+    fn schema(src: &str) -> ValidatorSchema {
+        ValidatorSchema::from_str_natural(src, Extensions::all_available())
+            .unwrap()
+            .0
+    }
+
+    fn name(s: &str) -> Name {
+        s.parse().unwrap()
+    }
+
+    const VENDOR: &str = r#"
+        namespace Vendor {
+            type Owner = { user: User };
+            entity Group;
+            entity User in [Group] = { manager?: User, groups: Set<Group> };
+            entity Doc = { owner: Owner };
+            action read appliesTo { principal: User, resource: Doc, context: { by: User } };
+            action write in [read] appliesTo { principal: User, resource: Doc };
+        }
+        entity Admin;
+    "#;
+
+    #[test]
+    fn rebases_all_references() {
+        let (from, to) = (name("Vendor"), name("MyApp::Vendor"));
+        let rebased = schema(VENDOR).rebase_namespace(&from, &to).unwrap();
+
+        assert!(rebased.get_entity_type(&name("Vendor::User")).is_none());
+        assert!(rebased.get_entity_type(&name("Admin")).is_some());
+        let group = rebased
+            .get_entity_type(&name("MyApp::Vendor::Group"))
+            .unwrap();
+        assert!(group.descendants.contains(&name("MyApp::Vendor::User")));
+
+        let user = rebased
+            .get_entity_type(&name("MyApp::Vendor::User"))
+            .unwrap();
+        assert_eq!(
+            user.attr("manager").unwrap().attr_type,
+            Type::named_entity_reference(name("MyApp::Vendor::User"))
+        );
+        assert_eq!(
+            user.attr("groups").unwrap().attr_type,
+            Type::set(Type::named_entity_reference(name("MyApp::Vendor::Group")))
+        );
+        // Common types are inlined, so references in them are rebased too
+        let doc = rebased
+            .get_entity_type(&name("MyApp::Vendor::Doc"))
+            .unwrap();
+        assert_eq!(
+            doc.attr("owner").unwrap().attr_type,
+            Type::closed_record_with_required_attributes([(
+                "user".into(),
+                Type::named_entity_reference(name("MyApp::Vendor::User"))
+            )])
+        );
+
+        let read: EntityUID = r#"MyApp::Vendor::Action::"read""#.parse().unwrap();
+        let write: EntityUID = r#"MyApp::Vendor::Action::"write""#.parse().unwrap();
+        let read_action = rebased.get_action_id(&read).unwrap();
+        assert!(read_action.descendants().any(|a| a == &write));
+        assert_eq!(
+            read_action
+                .applicable_principal_types()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["MyApp::Vendor::User"]
+        );
+        assert_eq!(
+            read_action.context_type(),
+            Type::closed_record_with_required_attributes([(
+                "by".into(),
+                Type::named_entity_reference(name("MyApp::Vendor::User"))
+            )])
+        );
+    }
+
+    #[test]
+    fn reversible() {
+        let original = schema(VENDOR);
+        let (from, to) = (name("Vendor"), name("MyApp::Vendor"));
+        let roundtrip = original
+            .rebase_namespace(&from, &to)
+            .unwrap()
+            .rebase_namespace(&to, &from)
+            .unwrap();
+        assert_eq!(roundtrip, original);
+    }
+
+    #[test]
+    fn rebases_fragment() {
+        let (from, to) = (name("Vendor"), name("MyApp::Vendor"));
+        let src = format!("{VENDOR} namespace App {{ entity Folder in [Vendor::Group]; }}");
+        let (fragment, _) = SchemaFragment::from_str_natural(&src).unwrap();
+        let rebased = fragment.rebase_namespace(&from, &to);
+        assert!(rebased.0.contains_key(&Some(to.clone())));
+        assert!(!rebased.0.contains_key(&Some(from.clone())));
+        // qualified names are rebased
+        assert_eq!(
+            rebased.0[&Some(name("App"))].entity_types[&"Folder".parse().unwrap()].member_of_types,
+            vec![name("MyApp::Vendor::Group")]
+        );
+        assert_eq!(
+            ValidatorSchema::try_from(rebased.clone()).unwrap(),
+            schema(&src).rebase_namespace(&from, &to).unwrap()
+        );
+        assert_eq!(rebased.rebase_namespace(&to, &from), fragment);
+    }
+
+    #[test]
+    fn collisions() {
+        let original = schema(&format!("{VENDOR} namespace App {{ entity User; }}"));
+        match original.rebase_namespace(&name("Vendor"), &name("App")) {
+            Err(SchemaError::DuplicateEntityType(name)) => assert_eq!(name, "App::User"),
+            other => panic!("expected a duplicate entity type, got {other:?}"),
+        }
+
+        let original = schema(&format!("{VENDOR} namespace App {{ action read; }}"));
+        match original.rebase_namespace(&name("Vendor"), &name("App")) {
+            Err(SchemaError::DuplicateAction(name)) => {
+                assert_eq!(name, r#"App::Action::"read""#)
+            }
+            other => panic!("expected a duplicate action, got {other:?}"),
+        }
+    }
+}
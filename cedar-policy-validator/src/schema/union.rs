@@ -0,0 +1,233 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains the union of several `ValidatorSchema`s, used to
+//! validate policies against all of them at once.
+
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use cedar_policy_core::{
+    ast::{EntityUID, Name},
+    transitive_closure::compute_tc,
+};
+
+use super::ValidatorSchema;
+use crate::err::*;
+
+/// Records which of the schemas given to [`crate::Validator::from_schemas`]
+/// declared each entity type and action. Schemas are identified by their
+/// index in that list.
+#[derive(Clone, Debug, Default)]
+pub struct SchemaProvenance {
+    entity_types: HashMap<Name, Vec<usize>>,
+    actions: HashMap<EntityUID, Vec<usize>>,
+}
+
+impl SchemaProvenance {
+    /// The schemas declaring the entity type `ety`, in increasing order. This
+    /// is empty if no schema declares it.
+    pub fn entity_type_sources(&self, ety: &Name) -> &[usize] {
+        self.entity_types.get(ety).map_or(&[], Vec::as_slice)
+    }
+
+    /// The schemas declaring the action `action`, in increasing order. This is
+    /// empty if no schema declares it.
+    pub fn action_sources(&self, action: &EntityUID) -> &[usize] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// The entity types declared by more than one schema, along with the
+    /// schemas declaring them.
+    pub fn ambiguous_entity_types(&self) -> impl Iterator<Item = (&Name, &[usize])> {
+        self.entity_types
+            .iter()
+            .filter(|(_, sources)| sources.len() > 1)
+            .map(|(ety, sources)| (ety, sources.as_slice()))
+    }
+
+    /// The actions declared by more than one schema, along with the schemas
+    /// declaring them.
+    pub fn ambiguous_actions(&self) -> impl Iterator<Item = (&EntityUID, &[usize])> {
+        self.actions
+            .iter()
+            .filter(|(_, sources)| sources.len() > 1)
+            .map(|(action, sources)| (action, sources.as_slice()))
+    }
+
+    /// Feed where each entity type and action was declared into `hasher`, in
+    /// an order which doesn't depend on the order of the `HashMap`s.
+    pub(crate) fn hash_sources<H: Hasher>(&self, hasher: &mut H) {
+        self.entity_types
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .hash(hasher);
+        self.actions.iter().collect::<BTreeMap<_, _>>().hash(hasher);
+    }
+}
+
+impl ValidatorSchema {
+    /// Combine `schemas` into one schema declaring every entity type and
+    /// action declared by any of them, and record where each was declared.
+    ///
+    /// An entity type or action declared by more than one schema keeps the
+    /// attributes, context, and `appliesTo` of the first schema declaring it,
+    /// but its descendants are those given by any of the schemas. This fails
+    /// if combining the action hierarchies creates a cycle.
+    pub(crate) fn union<'a>(
+        schemas: impl IntoIterator<Item = &'a ValidatorSchema>,
+    ) -> Result<(ValidatorSchema, SchemaProvenance)> {
+        let mut entity_types = HashMap::new();
+        let mut action_ids = HashMap::new();
+        let mut common_types = HashSet::new();
+        let mut provenance = SchemaProvenance::default();
+
+        for (i, schema) in schemas.into_iter().enumerate() {
+            for (name, ety) in &schema.entity_types {
+                provenance
+                    .entity_types
+                    .entry(name.clone())
+                    .or_default()
+                    .push(i);
+                match entity_types.entry(name.clone()) {
+                    Entry::Vacant(v) => {
+                        v.insert(ety.clone());
+                    }
+                    Entry::Occupied(mut o) => {
+                        o.get_mut()
+                            .descendants
+                            .extend(ety.descendants.iter().cloned());
+                    }
+                }
+            }
+            for (euid, action) in &schema.action_ids {
+                provenance.actions.entry(euid.clone()).or_default().push(i);
+                match action_ids.entry(euid.clone()) {
+                    Entry::Vacant(v) => {
+                        v.insert(action.clone());
+                    }
+                    Entry::Occupied(mut o) => {
+                        o.get_mut()
+                            .descendants
+                            .extend(action.descendants.iter().cloned());
+                    }
+                }
+            }
+            common_types.extend(schema.common_types.iter().cloned());
+        }
+
+        // Each schema's hierarchies were already transitively closed, but
+        // edges contributed by different schemas may now be chained.
+        compute_tc(&mut entity_types, false)?;
+        compute_tc(&mut action_ids, true)?;
+
+        Ok((
+            ValidatorSchema {
+                entity_types,
+                action_ids,
+                common_types,
+            },
+            provenance,
+        ))
+    }
+}
+
+// PANIC SAFETY: Unit Test Code
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use cedar_policy_core::extensions::Extensions;
+
+    use super::*;
+
+    fn schema(src: &str) -> ValidatorSchema {
+        ValidatorSchema::from_str_natural(src, Extensions::all_available())
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn union_of_disjoint_schemas() {
+        let photos = schema(
+            "entity User, Photo; action view appliesTo { principal: User, resource: Photo };",
+        );
+        let docs = schema("entity Doc; action edit;");
+        let (union, provenance) = ValidatorSchema::union([&photos, &docs]).unwrap();
+
+        let user: Name = "User".parse().unwrap();
+        let doc: Name = "Doc".parse().unwrap();
+        let edit: EntityUID = r#"Action::"edit""#.parse().unwrap();
+        assert!(union.get_entity_type(&user).is_some());
+        assert!(union.get_entity_type(&doc).is_some());
+        assert!(union.get_action_id(&edit).is_some());
+        assert_eq!(provenance.entity_type_sources(&user), &[0]);
+        assert_eq!(provenance.entity_type_sources(&doc), &[1]);
+        assert_eq!(provenance.action_sources(&edit), &[1]);
+        assert!(provenance
+            .entity_type_sources(&"Missing".parse().unwrap())
+            .is_empty());
+        assert_eq!(provenance.ambiguous_entity_types().count(), 0);
+        assert_eq!(provenance.ambiguous_actions().count(), 0);
+    }
+
+    #[test]
+    fn union_merges_hierarchies() {
+        let first = schema("entity Group; entity User in [Group]; entity Org; action read;");
+        let second =
+            schema("entity Org; entity Group in [Org]; action read; action write in [read];");
+        let (union, provenance) = ValidatorSchema::union([&first, &second]).unwrap();
+
+        let org = union.get_entity_type(&"Org".parse().unwrap()).unwrap();
+        assert!(org.descendants.contains(&"Group".parse().unwrap()));
+        assert!(org.descendants.contains(&"User".parse().unwrap()));
+        let read = union
+            .get_action_id(&r#"Action::"read""#.parse().unwrap())
+            .unwrap();
+        assert!(read
+            .descendants()
+            .any(|a| a == &r#"Action::"write""#.parse().unwrap()));
+
+        let mut ambiguous = provenance
+            .ambiguous_entity_types()
+            .map(|(ety, sources)| (ety.to_string(), sources.to_vec()))
+            .collect::<Vec<_>>();
+        ambiguous.sort();
+        assert_eq!(
+            ambiguous,
+            vec![
+                ("Group".to_string(), vec![0, 1]),
+                ("Org".to_string(), vec![0, 1])
+            ]
+        );
+        assert_eq!(
+            provenance
+                .ambiguous_actions()
+                .map(|(action, sources)| (action.to_string(), sources.to_vec()))
+                .collect::<Vec<_>>(),
+            vec![(r#"Action::"read""#.to_string(), vec![0, 1])]
+        );
+    }
+
+    #[test]
+    fn union_action_cycle() {
+        let first = schema("action read; action write in [read];");
+        let second = schema("action write; action read in [write];");
+        assert!(matches!(
+            ValidatorSchema::union([&first, &second]),
+            Err(SchemaError::CycleInActionHierarchy(_))
+        ));
+    }
+}
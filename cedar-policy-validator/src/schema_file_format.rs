@@ -26,7 +26,7 @@ use serde::{
 };
 use serde_with::serde_as;
 use smol_str::{SmolStr, ToSmolStr};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
 
 use crate::{
     human_schema::{
@@ -130,6 +130,28 @@ impl SchemaFragment {
         let src = human_schema::json_schema_to_custom_schema_str(self)?;
         Ok(src)
     }
+
+    /// Combine `fragments` into a single [`SchemaFragment`]. The declarations
+    /// in namespaces with the same name are combined into one namespace. If
+    /// more than one fragment declares the same name in the same namespace,
+    /// the last declaration is kept.
+    pub fn merge(fragments: impl IntoIterator<Item = SchemaFragment>) -> SchemaFragment {
+        let mut merged: HashMap<Option<Name>, NamespaceDefinition> = HashMap::new();
+        for (ns, ns_def) in fragments.into_iter().flat_map(|f| f.0) {
+            match merged.entry(ns) {
+                Entry::Vacant(v) => {
+                    v.insert(ns_def);
+                }
+                Entry::Occupied(mut o) => {
+                    let merged_def = o.get_mut();
+                    merged_def.common_types.extend(ns_def.common_types);
+                    merged_def.entity_types.extend(ns_def.entity_types);
+                    merged_def.actions.extend(ns_def.actions);
+                }
+            }
+        }
+        SchemaFragment(merged)
+    }
 }
 
 /// A single namespace definition from a SchemaFragment.
@@ -180,6 +202,20 @@ pub struct EntityType {
     #[serde(default)]
     #[serde(skip_serializing_if = "AttributesOrContext::is_empty_record")]
     pub shape: AttributesOrContext,
+    /// This maps names of attributes which are computed from the entity's
+    /// other attributes to the Cedar expression computing each, in which the
+    /// entity is `resource`. Requires the experimental `derived-attributes`
+    /// feature.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "derivedAttributes")]
+    pub derived_attributes: Option<HashMap<SmolStr, SmolStr>>,
+    /// Groups of optional attributes of which entities of this type may have
+    /// at most one
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "mutuallyExclusive")]
+    pub mutually_exclusive: Option<Vec<Vec<SmolStr>>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -258,6 +294,13 @@ pub struct ApplySpec {
     #[serde(default)]
     #[serde(skip_serializing_if = "AttributesOrContext::is_empty_record")]
     pub context: AttributesOrContext,
+    /// This maps names of optional `context` attributes to the
+    /// `cedar_policy_core::entities::json::value::CedarValueJson` value used
+    /// for that attribute when a request omits it.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "contextDefaults")]
+    pub context_defaults: Option<HashMap<SmolStr, CedarValueJson>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -869,6 +912,7 @@ mod test {
             resource_types: Some(vec!["Album".parse().unwrap()]),
             principal_types: Some(vec!["User".parse().unwrap()]),
             context: AttributesOrContext::default(),
+            context_defaults: None,
         };
         assert_eq!(at.applies_to, Some(spec));
         assert_eq!(
@@ -1539,6 +1583,8 @@ mod test_json_roundtrip {
                             attributes: BTreeMap::new(),
                             additional_attributes: false,
                         })),
+                        derived_attributes: None,
+                        mutually_exclusive: None,
                     },
                 )]),
                 actions: HashMap::from([(
@@ -1554,6 +1600,7 @@ mod test_json_roundtrip {
                                     additional_attributes: false,
                                 },
                             )),
+                            context_defaults: None,
                         }),
                         member_of: None,
                     },
@@ -1580,6 +1627,8 @@ mod test_json_roundtrip {
                                     additional_attributes: false,
                                 },
                             )),
+                            derived_attributes: None,
+                            mutually_exclusive: None,
                         },
                     )]),
                     actions: HashMap::new(),
@@ -1603,6 +1652,7 @@ mod test_json_roundtrip {
                                         additional_attributes: false,
                                     },
                                 )),
+                                context_defaults: None,
                             }),
                             member_of: None,
                         },
@@ -299,14 +299,46 @@ pub enum TypeErrorKind {
     HierarchyNotRespected(HierarchyNotRespected),
 }
 
+impl TypeErrorKind {
+    /// A code identifying the kind of this type error, e.g.,
+    /// `"cedar::validation::type::unexpected_type"`. The code for a kind of
+    /// error is stable across releases, even when the error message changes.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::UnexpectedType(_) => "cedar::validation::type::unexpected_type",
+            Self::IncompatibleTypes(_) => "cedar::validation::type::incompatible_types",
+            Self::UnsafeAttributeAccess(_) => "cedar::validation::type::unsafe_attribute_access",
+            Self::UnsafeOptionalAttributeAccess(_) => {
+                "cedar::validation::type::unsafe_optional_attribute_access"
+            }
+            #[allow(deprecated)]
+            Self::ImpossiblePolicy => "cedar::validation::type::impossible_policy",
+            Self::UndefinedFunction(_) => "cedar::validation::type::undefined_function",
+            Self::MultiplyDefinedFunction(_) => {
+                "cedar::validation::type::multiply_defined_function"
+            }
+            Self::WrongNumberArguments(_) => "cedar::validation::type::wrong_number_arguments",
+            Self::WrongCallStyle(_) => "cedar::validation::type::wrong_call_style",
+            Self::FunctionArgumentValidationError(_) => {
+                "cedar::validation::type::function_argument_validation"
+            }
+            Self::EmptySetForbidden => "cedar::validation::type::empty_set_forbidden",
+            Self::NonLitExtConstructor => {
+                "cedar::validation::type::non_literal_extension_constructor"
+            }
+            Self::HierarchyNotRespected(_) => "cedar::validation::type::hierarchy_not_respected",
+        }
+    }
+}
+
 /// Structure containing details about an unexpected type error.
 #[derive(Diagnostic, Error, Debug, Clone, Hash, Eq, PartialEq)]
 #[error("unexpected type: expected {} but saw {}",
     match .expected.iter().next() {
-        Some(single) if .expected.len() == 1 => format!("{}", single),
-        _ => .expected.iter().join(", or ")
+        Some(single) if .expected.len() == 1 => single.display_cedarschema(),
+        _ => .expected.iter().map(Type::display_cedarschema).join(", or ")
     },
-    .actual
+    .actual.display_cedarschema()
 )]
 pub struct UnexpectedType {
     expected: BTreeSet<Type>,
@@ -351,7 +383,9 @@ pub struct IncompatibleTypes {
 impl Display for IncompatibleTypes {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "the types ")?;
-        join_with_conjunction(f, "and", self.types.iter(), |f, t| write!(f, "{t}"))?;
+        join_with_conjunction(f, "and", self.types.iter(), |f, t| {
+            write!(f, "{}", t.display_cedarschema())
+        })?;
         write!(f, " are not compatible")
     }
 }
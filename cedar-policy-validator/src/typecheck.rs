@@ -39,7 +39,7 @@ use crate::{
     extension_schema::{ExtensionFunctionType, ExtensionSchema},
     extensions::all_available_extension_schemas,
     fuzzy_match::fuzzy_search,
-    schema::{is_action_entity_type, ValidatorSchema},
+    schema::{is_action_entity_type, ValidatorSchema, ACTION_ENTITY_TYPE},
     types::{
         AttributeType, Effect, EffectSet, EntityRecordKind, OpenTag, Primitive, RequestEnv, Type,
     },
@@ -51,8 +51,9 @@ use super::type_error::TypeError;
 
 use cedar_policy_core::ast::{
     BinaryOp, EntityType, EntityUID, Expr, ExprBuilder, ExprKind, Literal, Name,
-    PrincipalOrResourceConstraint, SlotId, Template, UnaryOp, Var,
+    PrincipalOrResourceConstraint, SlotEnv, SlotId, Template, UnaryOp, Var,
 };
+use nonempty::NonEmpty;
 
 #[cfg(not(target_arch = "wasm32"))]
 const REQUIRED_STACK_SPACE: usize = 1024 * 100;
@@ -62,8 +63,9 @@ const REQUIRED_STACK_SPACE: usize = 1024 * 100;
 pub(crate) enum TypecheckAnswer<'a> {
     /// Typechecking succeeded, and we know the type and a possibly empty effect
     /// set for the expression. The effect set is the set of
-    /// (expression, attribute) pairs that are known as safe to access under the
-    /// assumption that the expression evaluates to true.
+    /// (expression, attribute) pairs that are known as safe to access, and
+    /// (expression, entity type) pairs that are known to pass an `is` test,
+    /// under the assumption that the expression evaluates to true.
     TypecheckSuccess {
         expr_type: Expr<Option<Type>>,
         expr_effect: EffectSet<'a>,
@@ -154,6 +156,33 @@ impl<'a> TypecheckAnswer<'a> {
         }
     }
 
+    /// Transform the type of this TypecheckAnswer without modifying the
+    /// success or effect.
+    pub fn map_type<F>(self, f: F) -> Self
+    where
+        F: FnOnce(Option<Type>) -> Option<Type>,
+    {
+        match self {
+            TypecheckAnswer::TypecheckSuccess {
+                mut expr_type,
+                expr_effect,
+            } => {
+                expr_type.set_data(f(expr_type.data().clone()));
+                TypecheckAnswer::TypecheckSuccess {
+                    expr_type,
+                    expr_effect,
+                }
+            }
+            TypecheckAnswer::TypecheckFail {
+                mut expr_recovery_type,
+            } => {
+                expr_recovery_type.set_data(f(expr_recovery_type.data().clone()));
+                TypecheckAnswer::TypecheckFail { expr_recovery_type }
+            }
+            TypecheckAnswer::RecursionLimit => self,
+        }
+    }
+
     /// Convert this TypecheckAnswer into an equivalent answer for an expression
     /// that has failed to typecheck. If this is already TypecheckFail, then no
     /// change is required, otherwise, a TypecheckFail is constructed containing
@@ -245,6 +274,13 @@ pub struct Typechecker<'a> {
     schema: &'a ValidatorSchema,
     extensions: HashMap<Name, ExtensionSchema>,
     mode: ValidationMode,
+    /// In partial validation, whether accessing an undeclared attribute of an
+    /// entity type with closed attributes is an error even when the entity may
+    /// also be of an entity type with open attributes
+    strict_closed_attributes: bool,
+    /// Whether a call to an unknown extension function is allowed, with type
+    /// `Never`, instead of being an error
+    unknown_functions_as_warnings: bool,
 }
 
 impl<'a> Typechecker<'a> {
@@ -259,9 +295,27 @@ impl<'a> Typechecker<'a> {
             schema,
             extensions,
             mode,
+            strict_closed_attributes: false,
+            unknown_functions_as_warnings: false,
         }
     }
 
+    /// Set whether, in partial validation, accessing an undeclared attribute
+    /// of an entity type with closed attributes is an error even when the
+    /// entity may also be of an entity type with open attributes.
+    pub(crate) fn with_strict_closed_attributes(mut self, strict: bool) -> Self {
+        self.strict_closed_attributes = strict;
+        self
+    }
+
+    /// Set whether a call to an unknown extension function is allowed instead
+    /// of being an error. Such a call always errors when evaluated, so it is
+    /// given the type `Never`. The validator reports these calls as warnings.
+    pub(crate) fn with_unknown_functions_as_warnings(mut self, allow: bool) -> Self {
+        self.unknown_functions_as_warnings = allow;
+        self
+    }
+
     /// The main entry point for typechecking policies. Checks that the policy
     /// expression has type boolean. If typechecking succeeds, then the method
     /// will return true, and no items will be added to the output list.
@@ -318,25 +372,172 @@ impl<'a> Typechecker<'a> {
         t: &'b Template,
     ) -> Vec<(RequestEnv, PolicyCheck)> {
         self.apply_typecheck_fn_by_request_env(t, |request, expr| {
-            let mut type_errors = Vec::new();
-            let empty_prior_eff = EffectSet::new();
-            let ty = self.expect_type(
+            self.typecheck_policy_condition(request, expr)
+        })
+    }
+
+    /// Typecheck the template `t` as linked with `slots`, i.e., under the
+    /// request environments considered by `typecheck_policy` in which each
+    /// slot has the entity type of the entity it is linked to. Type errors are
+    /// added to `type_errors`. Returns `true` if typechecking succeeds.
+    ///
+    /// `typecheck_policy` reports the errors for every possible link of a
+    /// template. This reports just the errors for one link.
+    pub fn typecheck_link(
+        &self,
+        t: &Template,
+        slots: &SlotEnv,
+        type_errors: &mut HashSet<TypeError>,
+    ) -> bool {
+        let slot_type = |slot| slots.get(&slot).map(|euid| euid.entity_type().clone());
+        let (principal_slot, resource_slot) = (
+            slot_type(SlotId::principal()),
+            slot_type(SlotId::resource()),
+        );
+        let policy_condition = &t.condition();
+        let mut all_succ = true;
+        for request in self
+            .unlinked_request_envs()
+            .flat_map(|env| self.link_request_env(env, t))
+            .filter(|env| match env {
+                RequestEnv::UndeclaredAction => true,
+                RequestEnv::DeclaredAction { .. } => {
+                    env.principal_slot() == &principal_slot && env.resource_slot() == &resource_slot
+                }
+            })
+        {
+            match self.typecheck_policy_condition(&request, policy_condition) {
+                PolicyCheck::Success(_) => (),
+                PolicyCheck::Irrelevant(errs) | PolicyCheck::Fail(errs) => {
+                    all_succ &= errs.is_empty();
+                    type_errors.extend(errs);
+                }
+            }
+        }
+        all_succ
+    }
+
+    /// Typecheck the condition of a policy, `expr`, under one request
+    /// environment
+    fn typecheck_policy_condition(&self, request: &RequestEnv, expr: &Expr) -> PolicyCheck {
+        let mut type_errors = Vec::new();
+        let empty_prior_eff = EffectSet::new();
+        let ty = self.expect_type(
+            request,
+            &empty_prior_eff,
+            expr,
+            Type::primitive_boolean(),
+            &mut type_errors,
+            |_| None,
+        );
+
+        let is_false = ty.contains_type(&Type::singleton_boolean(false));
+        match (is_false, ty.typechecked(), ty.into_typed_expr()) {
+            (false, true, None) => PolicyCheck::Fail(type_errors),
+            (false, true, Some(e)) => PolicyCheck::Success(e),
+            (false, false, _) => PolicyCheck::Fail(type_errors),
+            (true, _, _) => PolicyCheck::Irrelevant(type_errors),
+        }
+    }
+
+    /// Typecheck the expression computing a derived attribute of entities of
+    /// type `entity_type`, in which the entity is `resource`. Return the type
+    /// of the attribute, or why the expression is invalid. The expression may
+    /// read the attributes of `resource`, and of records, but not of other
+    /// entities.
+    pub(crate) fn typecheck_derived_attribute(
+        &self,
+        entity_type: &Name,
+        expr: &Expr,
+    ) -> Result<Type, String> {
+        // PANIC SAFETY: `ACTION_ENTITY_TYPE` is a valid entity type name, as tested by `action_entity_type_parses`
+        #[allow(clippy::expect_used)]
+        let action = EntityUID::with_eid_and_type(ACTION_ENTITY_TYPE, "")
+            .expect("`ACTION_ENTITY_TYPE` does not parse");
+        let entity_type = EntityType::Specified(entity_type.clone());
+        // The expression can't use `principal`, `action` or `context`, so
+        // their types don't matter.
+        let request_env = RequestEnv::DeclaredAction {
+            principal: &entity_type,
+            action: &action,
+            resource: &entity_type,
+            context: &Type::record_with_attributes(None, OpenTag::ClosedAttributes),
+            principal_slot: None,
+            resource_slot: None,
+        };
+        let mut type_errors = Vec::new();
+        let ans = self.typecheck(&request_env, &EffectSet::new(), expr, &mut type_errors);
+        if !type_errors.is_empty() {
+            return Err(type_errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; "));
+        }
+        let Some(typed_expr) = ans.typechecked().then(|| ans.into_typed_expr()).flatten() else {
+            return Err("the expression does not typecheck".to_owned());
+        };
+        for e in typed_expr.subexpressions() {
+            let (expr, targets) = match e.expr_kind() {
+                ExprKind::GetAttr { expr, attr } => (expr, vec![(expr.data().clone(), attr)]),
+                ExprKind::HasAttr { expr, attr, path } => (
+                    expr,
+                    Type::attribute_path_targets(
+                        self.schema,
+                        expr.data().as_ref(),
+                        std::iter::once(attr).chain(path),
+                    ),
+                ),
+                _ => continue,
+            };
+            // Only the first attribute is read from `resource` itself
+            let on_resource = matches!(expr.expr_kind(), ExprKind::Var(Var::Resource));
+            for (i, (ty, attr)) in targets.into_iter().enumerate() {
+                if !(on_resource && i == 0)
+                    && matches!(
+                        ty,
+                        Some(Type::EntityOrRecord(
+                            EntityRecordKind::AnyEntity
+                                | EntityRecordKind::Entity(_)
+                                | EntityRecordKind::ActionEntity { .. }
+                        ))
+                    )
+                {
+                    return Err(format!(
+                        "it reads the attribute `{attr}` of an entity other than `resource`"
+                    ));
+                }
+            }
+        }
+        // A derived attribute has the same type for every entity, even if this
+        // expression always evaluates to the same boolean
+        Ok(match typed_expr.data() {
+            Some(Type::True | Type::False) => Type::primitive_boolean(),
+            Some(ty) => ty.clone(),
+            None => Type::Never,
+        })
+    }
+
+    /// Typecheck `t` under every schema-defined request environment, like
+    /// `typecheck_by_request_env`, but return the type-annotated policy
+    /// condition for every environment, including those where typechecking
+    /// failed. Subexpressions whose type could not be determined are annotated
+    /// with `None`.
+    pub(crate) fn typed_conditions_by_request_env(&self, t: &Template) -> Vec<Expr<Option<Type>>> {
+        self.apply_typecheck_fn_by_request_env(t, |request, expr| {
+            self.expect_type(
                 request,
-                &empty_prior_eff,
+                &EffectSet::new(),
                 expr,
                 Type::primitive_boolean(),
-                &mut type_errors,
+                &mut Vec::new(),
                 |_| None,
-            );
-
-            let is_false = ty.contains_type(&Type::singleton_boolean(false));
-            match (is_false, ty.typechecked(), ty.into_typed_expr()) {
-                (false, true, None) => PolicyCheck::Fail(type_errors),
-                (false, true, Some(e)) => PolicyCheck::Success(e),
-                (false, false, _) => PolicyCheck::Fail(type_errors),
-                (true, _, _) => PolicyCheck::Irrelevant(type_errors),
-            }
+            )
+            .into_typed_expr()
         })
+        .into_iter()
+        .filter_map(|(_, typed)| typed)
+        .collect()
     }
 
     /// Utility abstracting the common logic for strict and regular typechecking
@@ -554,6 +755,48 @@ impl<'a> Typechecker<'a> {
             return TypecheckAnswer::RecursionLimit;
         }
 
+        let ans = self.typecheck_unnarrowed(request_env, prior_eff, e, type_errors);
+        // If the prior effect tells us that the expression passed an `is`
+        // test, we can narrow its type to the tested entity type. This enables
+        // an attribute access `principal.foo` after a condition
+        // `principal is User` when `foo` is an attribute of `User` but not of
+        // the other entity types the principal might have.
+        let mut tested_entity_types = prior_eff.entity_types_of(e).peekable();
+        if tested_entity_types.peek().is_none() {
+            return ans;
+        }
+        ans.map_type(|ty| match ty {
+            Some(Type::EntityOrRecord(EntityRecordKind::Entity(lub))) => {
+                match tested_entity_types
+                    .filter(|name| lub.contains_entity_type(name))
+                    .min()
+                {
+                    Some(name) => Some(Type::named_entity_reference(name.clone())),
+                    None => Some(Type::EntityOrRecord(EntityRecordKind::Entity(lub))),
+                }
+            }
+            Some(Type::EntityOrRecord(EntityRecordKind::AnyEntity)) => {
+                match tested_entity_types
+                    .filter(|name| self.schema.get_entity_type(name).is_some())
+                    .min()
+                {
+                    Some(name) => Some(Type::named_entity_reference(name.clone())),
+                    None => Some(Type::EntityOrRecord(EntityRecordKind::AnyEntity)),
+                }
+            }
+            ty => ty,
+        })
+    }
+
+    /// Typecheck an expression as in `typecheck`, without narrowing its type
+    /// by the prior effect.
+    fn typecheck_unnarrowed<'b>(
+        &self,
+        request_env: &RequestEnv,
+        prior_eff: &EffectSet<'b>,
+        e: &'b Expr,
+        type_errors: &mut Vec<TypeError>,
+    ) -> TypecheckAnswer<'b> {
         match e.expr_kind() {
             // Principal, resource, and context have types defined by
             // the request type.
@@ -1015,8 +1258,17 @@ impl<'a> Typechecker<'a> {
                             // the attribute but there may be additional
                             // attributes, we do not fail and instead return the
                             // bottom type (`Never`).
+                            // With `strict_closed_attributes`, this also
+                            // requires that every closed entity type the
+                            // value may have declares the attribute.
                             None if self.mode.is_partial()
-                                && Type::may_have_attr(self.schema, typ_actual, attr) =>
+                                && Type::may_have_attr(self.schema, typ_actual, attr)
+                                && (!self.strict_closed_attributes
+                                    || Type::closed_entity_types_declare_attr(
+                                        self.schema,
+                                        typ_actual,
+                                        attr,
+                                    )) =>
                             {
                                 TypecheckAnswer::success(
                                     ExprBuilder::with_data(Some(Type::Never))
@@ -1050,7 +1302,14 @@ impl<'a> Typechecker<'a> {
                 })
             }
 
-            ExprKind::HasAttr { expr, attr } => {
+            ExprKind::HasAttr { expr, attr, path } => {
+                let type_error_help = |actual: &Type| match actual {
+                    Type::Set { .. } => Some(UnexpectedTypeHelp::TryUsingContains),
+                    Type::Primitive {
+                        primitive_type: Primitive::String,
+                    } => Some(UnexpectedTypeHelp::TryUsingLike),
+                    _ => None,
+                };
                 // `has` applies to an entity or a record
                 let actual = self.expect_one_of_types(
                     request_env,
@@ -1058,73 +1317,64 @@ impl<'a> Typechecker<'a> {
                     expr,
                     &[Type::any_entity_reference(), Type::any_record()],
                     type_errors,
-                    |actual| match actual {
-                        Type::Set { .. } => Some(UnexpectedTypeHelp::TryUsingContains),
-                        Type::Primitive {
-                            primitive_type: Primitive::String,
-                        } => Some(UnexpectedTypeHelp::TryUsingLike),
-                        _ => None,
-                    },
+                    type_error_help,
                 );
-                actual.then_typecheck(|typ_expr_actual, _| match typ_expr_actual.data() {
-                    Some(typ_actual) => {
-                        match Type::lookup_attribute_type(self.schema, typ_actual, attr) {
-                            Some(AttributeType {
-                                attr_type: _,
-                                is_required: true,
-                            }) => {
-                                // Since an entity doesn't always have to exist
-                                // in the entity store, and `has` evaluates to
-                                // `false` when this is the case, we can't
-                                // conclude that `has` is true just because an
-                                // attribute is required for an entity type.
-                                let exists_in_store = matches!(
-                                    typ_actual,
-                                    Type::EntityOrRecord(EntityRecordKind::Record { .. })
-                                );
-                                // However, we can make an exception when the attribute
-                                // access of the expression is already in the prior effect,
-                                // which means the entity must exist.
-                                let in_prior_effs = prior_eff.contains(&Effect::new(expr, attr));
-                                let type_of_has = if exists_in_store || in_prior_effs {
-                                    Type::singleton_boolean(true)
-                                } else {
-                                    Type::primitive_boolean()
-                                };
-                                TypecheckAnswer::success_with_effect(
-                                    ExprBuilder::with_data(Some(type_of_has))
-                                        .with_same_source_loc(e)
-                                        .has_attr(typ_expr_actual, attr.clone()),
-                                    EffectSet::singleton(Effect::new(expr, attr)),
-                                )
-                            }
-                            // This is where effect information is generated. If
-                            // the `HasAttr` for an optional attribute evaluates
-                            // to `true`, then we know that it is safe to access
-                            // that attribute, so we add an entry to the effect
-                            // set.
-                            Some(AttributeType {
-                                attr_type: _,
-                                is_required: false,
-                            }) => TypecheckAnswer::success_with_effect(
-                                ExprBuilder::with_data(Some(
-                                    // The optional attribute `HasAttr` can have
-                                    // type `true` if it occurs after the attribute
-                                    // access of the expression is already in the
-                                    // prior effect.
-                                    if prior_eff.contains(&Effect::new(expr, attr)) {
-                                        Type::singleton_boolean(true)
-                                    } else {
-                                        Type::primitive_boolean()
-                                    },
-                                ))
+                actual.then_typecheck(|typ_expr_actual, _| {
+                    let Some(typ_actual) = typ_expr_actual.data().clone() else {
+                        return TypecheckAnswer::fail(
+                            ExprBuilder::with_data(Some(Type::primitive_boolean()))
                                 .with_same_source_loc(e)
-                                .has_attr(typ_expr_actual, attr.clone()),
-                                EffectSet::singleton(Effect::new(expr, attr)),
-                            ),
-                            None => TypecheckAnswer::success(
-                                ExprBuilder::with_data(Some(
-                                    if Type::may_have_attr(self.schema, typ_actual, attr) {
+                                .has_attr_path(
+                                    typ_expr_actual,
+                                    NonEmpty::from((attr.clone(), path.clone())),
+                                ),
+                        );
+                    };
+                    // `e has a.b` is typed as `e has a && e.a has b`, except
+                    // that the access `e.a` is never an error, so each
+                    // attribute in the path is tested on the type of the
+                    // attribute before it, with the effects of the tests
+                    // before it.
+                    let attrs = || std::iter::once(attr).chain(path);
+                    let mut on_type = typ_actual;
+                    let mut type_of_has = Type::singleton_boolean(true);
+                    let mut effects = EffectSet::new();
+                    for (i, step_attr) in attrs().enumerate() {
+                        let effect = Effect::on_path(expr, attrs().take(i), step_attr);
+                        let in_prior_effs =
+                            prior_eff.contains(&effect) || effects.contains(&effect);
+                        let (type_of_step, attr_type) =
+                            match Type::lookup_attribute_type(self.schema, &on_type, step_attr) {
+                                Some(AttributeType {
+                                    attr_type,
+                                    is_required,
+                                }) => {
+                                    // Since an entity doesn't always have to exist
+                                    // in the entity store, and `has` evaluates to
+                                    // `false` when this is the case, we can't
+                                    // conclude that `has` is true just because an
+                                    // attribute is required for an entity type.
+                                    let exists_in_store = matches!(
+                                        on_type,
+                                        Type::EntityOrRecord(EntityRecordKind::Record { .. })
+                                    );
+                                    // However, we can make an exception when the
+                                    // attribute access of the expression is
+                                    // already in the prior effect, which means the
+                                    // entity must exist. This is also where effect
+                                    // information is generated. If the `HasAttr`
+                                    // evaluates to `true`, then we know that it is
+                                    // safe to access that attribute, so we add an
+                                    // entry to the effect set.
+                                    effects.insert(effect);
+                                    if in_prior_effs || (is_required && exists_in_store) {
+                                        (Type::singleton_boolean(true), Some(attr_type))
+                                    } else {
+                                        (Type::primitive_boolean(), Some(attr_type))
+                                    }
+                                }
+                                None => (
+                                    if Type::may_have_attr(self.schema, &on_type, step_attr) {
                                         // The type might have the attribute, but we
                                         // can not conclude one way or the other.
                                         // This applies to record types and least
@@ -1139,17 +1389,71 @@ impl<'a> Typechecker<'a> {
                                         // have the attribute.
                                         Type::singleton_boolean(false)
                                     },
-                                ))
-                                .with_same_source_loc(e)
-                                .has_attr(typ_expr_actual, attr.clone()),
-                            ),
+                                    None,
+                                ),
+                            };
+                        type_of_has = match (type_of_has, type_of_step) {
+                            (Type::False, _) | (_, Type::False) => Type::False,
+                            (Type::True, Type::True) => Type::True,
+                            _ => Type::primitive_boolean(),
+                        };
+                        // Nothing is known about the rest of the path once
+                        // the type of an attribute is unknown or the `has`
+                        // can only be false.
+                        let Some(attr_type) = attr_type else {
+                            break;
+                        };
+                        if type_of_has == Type::False {
+                            break;
                         }
+                        // Evaluating the `has` tests for the next attribute on
+                        // this one, so it must be an entity or a record.
+                        if i < path.len()
+                            && !Type::is_subtype(
+                                self.schema,
+                                &attr_type,
+                                &Type::any_entity_reference(),
+                                ValidationMode::Permissive,
+                            )
+                            && !Type::is_subtype(
+                                self.schema,
+                                &attr_type,
+                                &Type::any_record(),
+                                ValidationMode::Permissive,
+                            )
+                        {
+                            let on_expr =
+                                attrs()
+                                    .take(i + 1)
+                                    .fold(Expr::clone(expr), |on_expr, attr| {
+                                        ExprBuilder::new()
+                                            .with_same_source_loc(e)
+                                            .get_attr(on_expr, attr.clone())
+                                    });
+                            type_errors.push(TypeError::expected_one_of_types(
+                                on_expr,
+                                [Type::any_entity_reference(), Type::any_record()],
+                                attr_type.clone(),
+                                type_error_help(&attr_type),
+                            ));
+                            return TypecheckAnswer::fail(
+                                ExprBuilder::with_data(Some(Type::primitive_boolean()))
+                                    .with_same_source_loc(e)
+                                    .has_attr_path(
+                                        typ_expr_actual,
+                                        NonEmpty::from((attr.clone(), path.clone())),
+                                    ),
+                            );
+                        }
+                        on_type = attr_type;
                     }
-                    None => TypecheckAnswer::fail(
-                        ExprBuilder::with_data(Some(Type::primitive_boolean()))
-                            .with_same_source_loc(e)
-                            .has_attr(typ_expr_actual, attr.clone()),
-                    ),
+                    let typed_has = ExprBuilder::with_data(Some(type_of_has))
+                        .with_same_source_loc(e)
+                        .has_attr_path(
+                            typ_expr_actual,
+                            NonEmpty::from((attr.clone(), path.clone())),
+                        );
+                    TypecheckAnswer::success_with_effect(typed_has, effects)
                 })
             }
 
@@ -1208,10 +1512,14 @@ impl<'a> Typechecker<'a> {
                                 Type::primitive_boolean()
                             };
 
-                            TypecheckAnswer::success(
+                            // When the `is` evaluates to `true`, we know that
+                            // the expression has the entity type, so its type
+                            // can be narrowed to that entity type.
+                            TypecheckAnswer::success_with_effect(
                                 ExprBuilder::with_data(Some(type_of_is))
                                     .with_same_source_loc(e)
                                     .is_entity_type(expr_ty, entity_type.clone()),
+                                EffectSet::singleton(Effect::entity_type(expr, entity_type)),
                             )
                         }
                         Some(Type::EntityOrRecord(EntityRecordKind::ActionEntity {
@@ -1234,12 +1542,14 @@ impl<'a> Typechecker<'a> {
                             )
                         }
                         // For `AnyEntity` we don't know anything about what
-                        // entity type it could be, so we just return `Bool`.
+                        // entity type it could be, so we just return `Bool`,
+                        // but the type can still be narrowed when it is `true`.
                         Some(Type::EntityOrRecord(EntityRecordKind::AnyEntity { .. })) => {
-                            TypecheckAnswer::success(
+                            TypecheckAnswer::success_with_effect(
                                 ExprBuilder::with_data(Some(Type::primitive_boolean()))
                                     .with_same_source_loc(e)
                                     .is_entity_type(expr_ty, entity_type.clone()),
+                                EffectSet::singleton(Effect::entity_type(expr, entity_type)),
                             )
                         }
                         // Expression type is not an entity type or is `None`.
@@ -2557,6 +2867,29 @@ impl<'a> Typechecker<'a> {
                     )
                 }
             }
+            Err(_)
+                if self.unknown_functions_as_warnings
+                    && !self
+                        .extensions
+                        .values()
+                        .any(|ext| ext.get_function_type(fn_name).is_some()) =>
+            {
+                let typechecked_args = args
+                    .iter()
+                    .map(|arg| self.typecheck(request_env, prior_eff, arg, type_errors));
+                TypecheckAnswer::sequence_all_then_typecheck(
+                    typechecked_args,
+                    |arg_exprs_effects| {
+                        let (typed_arg_exprs, _): (Vec<Expr<Option<Type>>>, Vec<_>) =
+                            arg_exprs_effects.into_iter().unzip();
+                        TypecheckAnswer::success(
+                            ExprBuilder::with_data(Some(Type::Never))
+                                .with_same_source_loc(ext_expr)
+                                .call_extension_fn(fn_name.clone(), typed_arg_exprs),
+                        )
+                    },
+                )
+            }
             Err(typ_err) => {
                 type_errors.push(typ_err(ext_expr.clone()));
                 match typed_arg_exprs(type_errors) {
@@ -59,6 +59,8 @@ fn slot_in_typechecks() {
     let etype = EntityType {
         member_of_types: vec![],
         shape: AttributesOrContext::default(),
+        derived_attributes: None,
+        mutually_exclusive: None,
     };
     let schema = NamespaceDefinition::new([("typename".parse().unwrap(), etype)], []);
     assert_typechecks_for_mode(
@@ -88,6 +90,8 @@ fn slot_equals_typechecks() {
     let etype = EntityType {
         member_of_types: vec![],
         shape: AttributesOrContext::default(),
+        derived_attributes: None,
+        mutually_exclusive: None,
     };
     // These don't typecheck in strict mode because the test_util expression
     // typechecker doesn't have access to a schema, so it can't link
@@ -35,6 +35,12 @@ fn ip_extension_typechecks() {
     let expr = Expr::from_str("ip(\"127.0.0.1\").isInRange(ip(\"1:2:3:4::/48\"))")
         .expect("parsing should succeed");
     assert_typechecks_empty_schema(expr, Type::primitive_boolean());
+    let expr = Expr::from_str("ip(\"10.0.0.0/8\").overlaps(ip(\"1:2:3:4::/48\"))")
+        .expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::primitive_boolean());
+    let expr = Expr::from_str("ip(\"::1\").isLoopback() && ip(\"224.0.0.1\").isMulticast()")
+        .expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::primitive_boolean());
 }
 
 #[test]
@@ -73,11 +79,23 @@ fn ip_extension_typecheck_fails() {
         Type::primitive_boolean(),
         vec![TypeError::expected_type(
             Expr::val(3),
-            Type::extension(ipaddr_name),
+            Type::extension(ipaddr_name.clone()),
             Type::primitive_long(),
             None,
         )],
     );
+    let expr =
+        Expr::from_str("ip(\"127.0.0.1\").overlaps(\"::1\")").expect("parsing should succeed");
+    assert_typecheck_fails_empty_schema(
+        expr,
+        Type::primitive_boolean(),
+        vec![TypeError::expected_type(
+            Expr::val("::1"),
+            Type::extension(ipaddr_name),
+            Type::primitive_string(),
+            None,
+        )],
+    );
 }
 
 #[test]
@@ -86,7 +104,7 @@ fn decimal_extension_typechecks() {
     let decimal_name =
         Name::parse_unqualified_name("decimal").expect("should be a valid identifier");
     let expr = Expr::from_str("decimal(\"1.23\")").expect("parsing should succeed");
-    assert_typechecks_empty_schema(expr, Type::extension(decimal_name));
+    assert_typechecks_empty_schema(expr, Type::extension(decimal_name.clone()));
     let expr = Expr::from_str("decimal(\"1.23\").lessThan(decimal(\"1.24\"))")
         .expect("parsing should succeed");
     assert_typechecks_empty_schema(expr, Type::primitive_boolean());
@@ -99,6 +117,19 @@ fn decimal_extension_typechecks() {
     let expr = Expr::from_str("decimal(\"1.23\").greaterThanOrEqual(decimal(\"1.24\"))")
         .expect("parsing should succeed");
     assert_typechecks_empty_schema(expr, Type::primitive_boolean());
+    let expr =
+        Expr::from_str("decimalWithScale(\"1.2345678\", 8)").expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::extension(decimal_name.clone()));
+    let expr = Expr::from_str("decimalWithScale(\"1.2345678\", 8).lessThan(decimal(\"1.24\"))")
+        .expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::primitive_boolean());
+    let expr = Expr::from_str("decimal(\"1.23\").round(1)").expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::extension(decimal_name.clone()));
+    let expr = Expr::from_str("decimal(\"1.23\").floor()").expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::extension(decimal_name.clone()));
+    let expr = Expr::from_str("decimal(\"1.23\").ceil().lessThan(decimal(\"1.24\"))")
+        .expect("parsing should succeed");
+    assert_typechecks_empty_schema(expr, Type::primitive_boolean());
 }
 
 #[test]
@@ -138,8 +169,29 @@ fn decimal_extension_typecheck_fails() {
         Type::primitive_boolean(),
         vec![TypeError::expected_type(
             Expr::val(3),
-            Type::extension(decimal_name),
+            Type::extension(decimal_name.clone()),
+            Type::primitive_long(),
+            None,
+        )],
+    );
+    let expr =
+        Expr::from_str("decimalWithScale(\"1.2345678\", 2)").expect("parsing should succeed");
+    assert_typecheck_fails_empty_schema(
+        expr.clone(),
+        Type::extension(decimal_name.clone()),
+        vec![TypeError::arg_validation_error(
+            expr,
+            "Failed to parse as a decimal value with scale 2: `\"1.2345678\"`".into(),
+        )],
+    );
+    let expr = Expr::from_str("decimal(\"1.23\").round(\"1\")").expect("parsing should succeed");
+    assert_typecheck_fails_empty_schema(
+        expr,
+        Type::extension(decimal_name),
+        vec![TypeError::expected_type(
+            Expr::val("1"),
             Type::primitive_long(),
+            Type::primitive_string(),
             None,
         )],
     );
@@ -26,8 +26,10 @@ use cedar_policy_core::{
 use smol_str::SmolStr;
 
 use crate::{
-    type_error::TypeError, types::EntityLUB, AttributeAccess, NamespaceDefinition,
-    NamespaceDefinitionWithActionAttributes, ValidationWarningKind,
+    type_error::TypeError,
+    types::{EntityLUB, Type},
+    AttributeAccess, NamespaceDefinition, NamespaceDefinitionWithActionAttributes,
+    ValidationWarningKind,
 };
 
 use super::test_utils::{
@@ -809,3 +811,177 @@ fn action_attrs_failing() {
     .expect("Policy should parse.");
     assert_policy_typecheck_fails(schema, failing_policy, vec![]);
 }
+
+fn schema_with_nested_context() -> NamespaceDefinition {
+    serde_json::from_str::<NamespaceDefinition>(
+        r#"
+{
+    "entityTypes": {
+        "User": {}
+    },
+    "actions": {
+        "view": {
+            "appliesTo": {
+                "principalTypes": ["User"],
+                "resourceTypes": ["User"],
+                "context": {
+                    "type": "Record",
+                    "attributes": {
+                        "mfa": {
+                            "type": "Record",
+                            "attributes": {
+                                "verified": { "type": "Boolean" },
+                                "method": { "type": "String", "required": false }
+                            }
+                        },
+                        "session": {
+                            "type": "Record",
+                            "required": false,
+                            "attributes": {
+                                "ip": { "type": "String" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+    "#,
+    )
+    .expect("Expected valid schema.")
+}
+
+#[test]
+fn nested_context_attributes() {
+    let passing_policies = [
+        r#"permit(principal, action == Action::"view", resource) when { context.mfa.verified };"#,
+        r#"permit(principal, action == Action::"view", resource) when { context.mfa has method && context.mfa.method == "totp" };"#,
+        r#"permit(principal, action == Action::"view", resource) when { context has session && context.session.ip == "" };"#,
+    ];
+    for src in passing_policies {
+        assert_policy_typechecks(
+            schema_with_nested_context(),
+            parse_policy(Some("0".to_string()), src).expect("Policy should parse."),
+        );
+    }
+}
+
+#[test]
+fn nested_context_attribute_typo() {
+    let failing_policy = parse_policy(
+        Some("0".to_string()),
+        r#"permit(principal, action == Action::"view", resource) when { context.mfa.verfied };"#,
+    )
+    .expect("Policy should parse.");
+    let expected = TypeError::unsafe_attribute_access(
+        Expr::get_attr(
+            Expr::get_attr(Expr::var(Var::Context), "mfa".into()),
+            "verfied".into(),
+        ),
+        AttributeAccess::Context(
+            r#"Action::"view""#.parse().unwrap(),
+            vec!["verfied".into(), "mfa".into()],
+        ),
+        Some("verified".to_string()),
+        false,
+    );
+    // reads like the errors for entity attributes
+    assert_eq!(
+        expected.to_string(),
+        r#"attribute `mfa.verfied` in context for Action::"view" not found"#
+    );
+    assert_policy_typecheck_fails(schema_with_nested_context(), failing_policy, vec![expected]);
+
+    let failing_policy = parse_policy(
+        Some("0".to_string()),
+        r#"permit(principal, action == Action::"view", resource) when { context has session && context.session.port == 0 };"#,
+    )
+    .expect("Policy should parse.");
+    assert_policy_typecheck_fails(
+        schema_with_nested_context(),
+        failing_policy,
+        vec![TypeError::unsafe_attribute_access(
+            Expr::get_attr(
+                Expr::get_attr(Expr::var(Var::Context), "session".into()),
+                "port".into(),
+            ),
+            AttributeAccess::Context(
+                r#"Action::"view""#.parse().unwrap(),
+                vec!["port".into(), "session".into()],
+            ),
+            Some("ip".to_string()),
+            false,
+        )],
+    );
+}
+
+#[test]
+fn nested_context_optional_attributes_unguarded() {
+    let failing_policy = parse_policy(
+        Some("0".to_string()),
+        r#"permit(principal, action == Action::"view", resource) when { context.mfa.method == "totp" };"#,
+    )
+    .expect("Policy should parse.");
+    assert_policy_typecheck_fails(
+        schema_with_nested_context(),
+        failing_policy,
+        vec![TypeError::unsafe_optional_attribute_access(
+            Expr::get_attr(
+                Expr::get_attr(Expr::var(Var::Context), "mfa".into()),
+                "method".into(),
+            ),
+            AttributeAccess::Context(
+                r#"Action::"view""#.parse().unwrap(),
+                vec!["method".into(), "mfa".into()],
+            ),
+        )],
+    );
+
+    let failing_policy = parse_policy(
+        Some("0".to_string()),
+        r#"permit(principal, action == Action::"view", resource) when { context.session.ip == "" };"#,
+    )
+    .expect("Policy should parse.");
+    assert_policy_typecheck_fails(
+        schema_with_nested_context(),
+        failing_policy,
+        vec![TypeError::unsafe_optional_attribute_access(
+            Expr::get_attr(Expr::var(Var::Context), "session".into()),
+            AttributeAccess::Context(r#"Action::"view""#.parse().unwrap(), vec!["session".into()]),
+        )],
+    );
+}
+
+#[test]
+fn nested_context_has_attribute_path() {
+    let passing_policies = [
+        r#"permit(principal, action == Action::"view", resource) when { context has mfa.method && context.mfa.method == "totp" };"#,
+        r#"permit(principal, action == Action::"view", resource) when { context has session.ip && context.session.ip == "" };"#,
+    ];
+    for src in passing_policies {
+        assert_policy_typechecks(
+            schema_with_nested_context(),
+            parse_policy(Some("0".to_string()), src).expect("Policy should parse."),
+        );
+    }
+
+    let failing_policy = parse_policy(
+        Some("0".to_string()),
+        r#"permit(principal, action == Action::"view", resource) when { context has mfa.verified.x };"#,
+    )
+    .expect("Policy should parse.");
+    assert_policy_typecheck_fails(
+        schema_with_nested_context(),
+        failing_policy,
+        vec![TypeError::expected_one_of_types(
+            Expr::get_attr(
+                Expr::get_attr(Expr::var(Var::Context), "mfa".into()),
+                "verified".into(),
+            ),
+            [Type::any_entity_reference(), Type::any_record()],
+            Type::primitive_boolean(),
+            None,
+        )],
+    );
+}
@@ -721,3 +721,73 @@ mod fail_partial_schema {
         );
     }
 }
+
+mod strict_closed_attributes {
+    use std::str::FromStr;
+
+    use super::*;
+
+    /// Typecheck `src` against the partial schema, treating closed entity
+    /// types strictly if `strict`, and return the type errors
+    fn type_errors(src: &str, strict: bool) -> HashSet<TypeError> {
+        let schema: ValidatorSchema = partial_schema_file()
+            .try_into()
+            .expect("Failed to construct schema.");
+        let typechecker = Typechecker::new(&schema, ValidationMode::Partial)
+            .with_strict_closed_attributes(strict);
+        let mut type_errors = HashSet::new();
+        let mut warnings = HashSet::new();
+        typechecker.typecheck_policy(
+            &Template::link_static_policy(parse_policy(None, src).unwrap()).0,
+            &mut type_errors,
+            &mut warnings,
+        );
+        type_errors
+    }
+
+    #[test]
+    fn open_entity_type_is_permissive() {
+        let src = r#"permit(principal, action, resource) when { User::"alice".unknown };"#;
+        assert_eq!(type_errors(src, false), HashSet::new());
+        assert_eq!(type_errors(src, true), HashSet::new());
+    }
+
+    #[test]
+    fn closed_entity_type_is_strict() {
+        let src = r#"permit(principal, action, resource) when { Group::"admins".unknown };"#;
+        assert_eq!(type_errors(src, false).len(), 1);
+        assert_eq!(type_errors(src, true).len(), 1);
+    }
+
+    #[test]
+    fn lub_of_open_and_closed_entity_types() {
+        // `User` has open attributes, but `Group` has closed attributes and
+        // doesn't declare `name`
+        let src = r#"permit(principal, action, resource) when {
+            (if context.flag then User::"alice" else Group::"admins").name == "alice"
+        };"#;
+        assert_eq!(type_errors(src, false), HashSet::new());
+        assert_expected_type_errors(
+            &vec![TypeError::unsafe_attribute_access(
+                Expr::from_str(r#"(if context.flag then User::"alice" else Group::"admins").name"#)
+                    .unwrap(),
+                AttributeAccess::EntityLUB(
+                    EntityLUB::single_entity("User".parse().unwrap())
+                        .least_upper_bound(&EntityLUB::single_entity("Group".parse().unwrap())),
+                    vec!["name".into()],
+                ),
+                None,
+                true,
+            )],
+            &type_errors(src, true),
+        );
+    }
+
+    #[test]
+    fn lub_of_open_entity_types_is_permissive() {
+        let src = r#"permit(principal, action, resource) when {
+            (if context.flag then User::"alice" else Photo::"vacation").unknown
+        };"#;
+        assert_eq!(type_errors(src, true), HashSet::new());
+    }
+}
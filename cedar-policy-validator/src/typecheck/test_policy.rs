@@ -705,6 +705,96 @@ fn is_action() {
     );
 }
 
+#[test]
+fn is_narrows_entity_lub_in_and() {
+    assert_policy_typechecks_permissive_simple_schema(
+        parse_policy(
+            None,
+            r#"
+            permit(principal, action, resource) when {
+                (if 1 > 0 then User::"alice" else Photo::"vacation.jpg") is User &&
+                (if 1 > 0 then User::"alice" else Photo::"vacation.jpg").age > 0
+            };
+        "#,
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn is_narrows_entity_lub_in_then_branch() {
+    assert_policy_typechecks_permissive_simple_schema(
+        parse_policy(
+            None,
+            r#"
+            permit(principal, action, resource) when {
+                if (if 1 > 0 then User::"alice" else Photo::"vacation.jpg") is Photo
+                then (if 1 > 0 then User::"alice" else Photo::"vacation.jpg").file_type == "jpg"
+                else false
+            };
+        "#,
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn is_does_not_narrow_entity_lub_in_else_branch() {
+    let p = parse_policy(
+        Some("0".to_string()),
+        r#"
+            permit(principal, action, resource) when {
+                if (if 1 > 0 then User::"alice" else Photo::"vacation.jpg") is Photo
+                then true
+                else (if 1 > 0 then User::"alice" else Photo::"vacation.jpg").file_type == "jpg"
+            };
+        "#,
+    )
+    .unwrap();
+    assert_policy_typecheck_permissive_fails_simple_schema(
+        p,
+        vec![TypeError::unsafe_attribute_access(
+            Expr::from_str(r#"(if 1 > 0 then User::"alice" else Photo::"vacation.jpg").file_type"#)
+                .unwrap(),
+            AttributeAccess::EntityLUB(
+                EntityLUB::single_entity("User".parse().unwrap())
+                    .least_upper_bound(&EntityLUB::single_entity("Photo".parse().unwrap())),
+                vec!["file_type".into()],
+            ),
+            None,
+            true,
+        )],
+    );
+}
+
+#[test]
+fn is_does_not_narrow_entity_lub_in_or() {
+    let p = parse_policy(
+        Some("0".to_string()),
+        r#"
+            permit(principal, action, resource) when {
+                (if 1 > 0 then User::"alice" else Photo::"vacation.jpg") is Photo ||
+                (if 1 > 0 then User::"alice" else Photo::"vacation.jpg").file_type == "jpg"
+            };
+        "#,
+    )
+    .unwrap();
+    assert_policy_typecheck_permissive_fails_simple_schema(
+        p,
+        vec![TypeError::unsafe_attribute_access(
+            Expr::from_str(r#"(if 1 > 0 then User::"alice" else Photo::"vacation.jpg").file_type"#)
+                .unwrap(),
+            AttributeAccess::EntityLUB(
+                EntityLUB::single_entity("User".parse().unwrap())
+                    .least_upper_bound(&EntityLUB::single_entity("Photo".parse().unwrap())),
+                vec!["file_type".into()],
+            ),
+            None,
+            true,
+        )],
+    );
+}
+
 #[test]
 fn entity_record_lub_is_none() {
     assert_policy_typecheck_fails_simple_schema(parse_policy(
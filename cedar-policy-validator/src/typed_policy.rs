@@ -0,0 +1,262 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module contains `TypedPolicy`, which exposes the types the typechecker
+//! infers for the subexpressions of a policy.
+
+use std::collections::{BTreeMap, HashSet};
+
+use cedar_policy_core::ast::Template;
+use miette::SourceSpan;
+
+use crate::{typecheck::Typechecker, types::Type, ValidationMode, Validator};
+
+/// The types inferred for the subexpressions of a policy, as returned by
+/// [`Validator::typecheck_with_types`].
+///
+/// A policy is typechecked once for every request environment (i.e., every
+/// combination of principal type, action, and resource type) it may apply to,
+/// and a subexpression like `principal` may have a different type in each.
+/// So each subexpression is given the list of distinct types it has across
+/// all of these environments, in no particular order.
+///
+/// Subexpressions are identified by their source spans. Some subexpressions
+/// share a span with the expression containing them, e.g., the parser gives
+/// `resource.owner` the span of the whole `resource.owner.age`; in that case
+/// the span has the types of the outermost expression.
+#[derive(Debug, Clone, Default)]
+pub struct TypedPolicy {
+    /// Types of the subexpressions with source locations, keyed by the offset
+    /// and length of their source spans
+    types: BTreeMap<(usize, usize), Vec<Type>>,
+    /// Types of the policy's entire condition
+    condition_types: Vec<Type>,
+}
+
+impl TypedPolicy {
+    /// The inferred types of every subexpression of the policy with a source
+    /// location, ordered by the location
+    pub fn types(&self) -> impl Iterator<Item = (SourceSpan, &[Type])> {
+        self.types
+            .iter()
+            .map(|((offset, len), types)| (SourceSpan::from((*offset, *len)), types.as_slice()))
+    }
+
+    /// The inferred types of the subexpression with exactly the source span
+    /// `span`. This is empty if there is no such subexpression, or if no type
+    /// could be inferred for it.
+    pub fn types_at(&self, span: SourceSpan) -> &[Type] {
+        self.types
+            .get(&(span.offset(), span.len()))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The innermost subexpression whose source span contains `offset`, along
+    /// with its inferred types. This is what an editor would show when hovering
+    /// over `offset`.
+    pub fn innermost_types_containing(&self, offset: usize) -> Option<(SourceSpan, &[Type])> {
+        self.types()
+            .filter(|(span, _)| span.offset() <= offset && offset < span.offset() + span.len())
+            .min_by_key(|(span, _)| span.len())
+    }
+
+    /// The inferred types of the policy's entire condition: the conjunction of
+    /// its scope, `when` conditions, and negated `unless` conditions. For a
+    /// policy which typechecks, this is a boolean type.
+    pub fn condition_types(&self) -> &[Type] {
+        &self.condition_types
+    }
+
+    fn add(types: &mut Vec<Type>, ty: &Type) {
+        if !types.contains(ty) {
+            types.push(ty.clone());
+        }
+    }
+}
+
+impl Validator {
+    /// Typecheck a single static policy or template, returning the types
+    /// inferred for each of its subexpressions.
+    ///
+    /// This returns types even if the policy fails to typecheck, in which case
+    /// some subexpressions may have no type. Use [`Validator::validate`] to
+    /// find the errors.
+    pub fn typecheck_with_types(&self, policy: &Template, mode: ValidationMode) -> TypedPolicy {
+        let typechecker = Typechecker::new(&self.schema, mode)
+            .with_strict_closed_attributes(self.strict_closed_attributes)
+            .with_unknown_functions_as_warnings(self.unknown_functions_as_warnings);
+        let policy_span = policy.loc().as_ref().map(|loc| loc.span);
+        let mut typed_policy = TypedPolicy::default();
+        for condition in typechecker.typed_conditions_by_request_env(policy) {
+            if let Some(ty) = condition.data() {
+                TypedPolicy::add(&mut typed_policy.condition_types, ty);
+            }
+            // `subexpressions()` visits an expression before the expressions
+            // it contains, so the first expression with a given span is the
+            // outermost one
+            let mut seen = HashSet::new();
+            for expr in condition.subexpressions() {
+                let Some(loc) = expr.source_loc() else {
+                    continue;
+                };
+                // the scope constraints carry the span of the whole policy;
+                // their types are reported by `condition_types()` instead
+                if Some(loc.span) == policy_span || !seen.insert(loc.span) {
+                    continue;
+                }
+                if let Some(ty) = expr.data() {
+                    let types = typed_policy
+                        .types
+                        .entry((loc.span.offset(), loc.span.len()))
+                        .or_default();
+                    TypedPolicy::add(types, ty);
+                }
+            }
+        }
+        typed_policy
+    }
+}
+
+// PANIC SAFETY: Unit Test Code
+#[allow(clippy::panic)]
+#[cfg(test)]
+mod test {
+    use cedar_policy_core::{extensions::Extensions, parser::parse_policy_template};
+
+    use super::*;
+    use crate::ValidatorSchema;
+
+    fn validator() -> Validator {
+        let (schema, _) = ValidatorSchema::from_str_natural(
+            r#"
+            entity User = { age: Long };
+            entity Admin in [User] = { age: Long };
+            entity Photo = { owner: User, tags: Set<String> };
+            action view appliesTo { principal: [User, Admin], resource: Photo };
+            "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    fn typecheck(src: &str) -> TypedPolicy {
+        let policy = parse_policy_template(Some("p".to_string()), src).unwrap();
+        validator().typecheck_with_types(&policy, ValidationMode::Strict)
+    }
+
+    fn span_of(src: &str, snippet: &str) -> SourceSpan {
+        (src.find(snippet).unwrap(), snippet.len()).into()
+    }
+
+    fn display(types: &[Type]) -> Vec<String> {
+        let mut types = types.iter().map(ToString::to_string).collect::<Vec<_>>();
+        types.sort();
+        types
+    }
+
+    #[test]
+    fn types_of_subexpressions() {
+        let src = r#"permit(principal, action == Action::"view", resource) when { resource.owner.age > 18 && resource.tags.contains("public") };"#;
+        let typed = typecheck(src);
+        assert_eq!(
+            display(typed.types_at(span_of(src, "resource.owner.age"))),
+            vec!["Long"]
+        );
+        assert_eq!(
+            display(typed.types_at(span_of(src, "resource.owner.age > 18"))),
+            vec!["Bool"]
+        );
+        assert_eq!(
+            display(typed.types_at(span_of(src, r#"resource.tags.contains("public")"#))),
+            vec!["Bool"]
+        );
+        assert_eq!(
+            display(typed.types_at(span_of(src, r#""public""#))),
+            vec!["String"]
+        );
+        assert_eq!(display(typed.condition_types()), vec!["Bool"]);
+
+        // hovering over `18` shows the type of `18`, not of the comparison
+        let (span, types) = typed
+            .innermost_types_containing(src.find("18").unwrap())
+            .unwrap();
+        assert_eq!(span, span_of(src, "18"));
+        assert_eq!(display(types), vec!["Long"]);
+    }
+
+    #[test]
+    fn types_across_request_environments() {
+        let src =
+            r#"permit(principal, action == Action::"view", resource) when { principal.age > 18 };"#;
+        let typed = typecheck(src);
+        assert_eq!(
+            display(typed.types_at(span_of(src, "principal.age > 18"))),
+            vec!["Bool"]
+        );
+        let principal_span = SourceSpan::from((src.rfind("principal").unwrap(), "principal".len()));
+        assert_eq!(
+            display(typed.types_at(principal_span)),
+            vec!["Admin", "User"]
+        );
+    }
+
+    #[test]
+    fn types_of_ill_typed_policy() {
+        let src = r#"permit(principal, action == Action::"view", resource) when { resource.owner.age + "one" > 18 };"#;
+        let typed = typecheck(src);
+        assert_eq!(
+            display(typed.types_at(span_of(src, "resource.owner.age"))),
+            vec!["Long"]
+        );
+        assert_eq!(
+            display(typed.types_at(span_of(src, r#"resource.owner.age + "one""#))),
+            vec!["Long"]
+        );
+        // the operand which fails to typecheck has no type
+        assert!(typed.types_at(span_of(src, r#""one""#)).is_empty());
+    }
+
+    #[test]
+    fn types_of_inapplicable_policy() {
+        // there is no action `edit`, so the scope is false in every request
+        // environment
+        let typed = typecheck(r#"permit(principal, action == Action::"edit", resource);"#);
+        assert_eq!(typed.types().count(), 0);
+        assert_eq!(
+            display(typed.condition_types()),
+            vec![Type::singleton_boolean(false).to_string()]
+        );
+    }
+
+    #[test]
+    fn types_with_unknown_functions_as_warnings() {
+        let src = r#"permit(principal, action == Action::"view", resource) when { datetime("2024-01-01") == principal.age };"#;
+        let (_, set, _) =
+            cedar_policy_core::parser::parse_policyset_allowing_unknown_functions(src).unwrap();
+        let policy = set.all_templates().next().unwrap();
+        let call = span_of(src, r#"datetime("2024-01-01")"#);
+
+        let typed = validator().typecheck_with_types(policy, ValidationMode::Strict);
+        assert!(typed.types_at(call).is_empty());
+
+        let typed = validator()
+            .with_unknown_functions_as_warnings(true)
+            .typecheck_with_types(policy, ValidationMode::Strict);
+        assert_eq!(display(typed.types_at(call)), vec![Type::Never.to_string()]);
+        assert_eq!(display(typed.condition_types()), vec!["Bool"]);
+    }
+}
@@ -27,8 +27,8 @@ use std::{
 
 use cedar_policy_core::{
     ast::{
-        BorrowedRestrictedExpr, EntityType, EntityUID, Expr, ExprShapeOnly, Name, PartialValue,
-        RestrictedExpr, Value,
+        BorrowedRestrictedExpr, EntityType, EntityUID, Expr, ExprKind, ExprShapeOnly, Id, Name,
+        PartialValue, RestrictedExpr, Value,
     },
     entities::{conformance::typecheck_restricted_expr_against_schematype, GetSchemaTypeError},
     extensions::Extensions,
@@ -432,6 +432,28 @@ impl Type {
         }
     }
 
+    /// Get the type of each expression that `e has a.b.c` tests for an
+    /// attribute on, paired with that attribute, given the type `ty` of `e`:
+    /// `e` and `a`, `e.a` and `b`, then `e.a.b` and `c`. The type is `None`
+    /// once it is not known.
+    pub(crate) fn attribute_path_targets<'a>(
+        schema: &ValidatorSchema,
+        ty: Option<&Type>,
+        attrs: impl IntoIterator<Item = &'a SmolStr>,
+    ) -> Vec<(Option<Type>, &'a SmolStr)> {
+        let mut ty = ty.cloned();
+        attrs
+            .into_iter()
+            .map(|attr| {
+                let next = ty
+                    .as_ref()
+                    .and_then(|ty| Self::lookup_attribute_type(schema, ty, attr))
+                    .map(|attr_ty| attr_ty.attr_type);
+                (std::mem::replace(&mut ty, next), attr)
+            })
+            .collect()
+    }
+
     /// Get all statically known attributes of an entity or record type.
     /// Returns an empty vector if no attributes or type is not an entity or record type.
     pub fn all_attributes(&self, schema: &ValidatorSchema) -> Vec<SmolStr> {
@@ -482,6 +504,29 @@ impl Type {
         }
     }
 
+    /// Return true if every entity type in `ty` which is declared in the schema
+    /// with closed attributes declares `attr`. Entity types with open
+    /// attributes and undeclared entity types are ignored, as are all types
+    /// other than entity types. Combined with `may_have_attr`, this decides
+    /// whether an attribute access in partial validation may succeed when
+    /// closed entity types are treated strictly.
+    pub(crate) fn closed_entity_types_declare_attr(
+        schema: &ValidatorSchema,
+        ty: &Type,
+        attr: &str,
+    ) -> bool {
+        match ty {
+            Type::EntityOrRecord(EntityRecordKind::Entity(entity_lub)) => {
+                entity_lub.lub_elements.iter().all(|entity| {
+                    schema.get_entity_type(entity).map_or(true, |entity_type| {
+                        entity_type.open_attributes.is_open() || entity_type.attr(attr).is_some()
+                    })
+                })
+            }
+            _ => true,
+        }
+    }
+
     /// Return true if we know that any value in this type must be a specified
     /// entity.
     pub(crate) fn must_be_specified_entity(ty: &Type) -> bool {
@@ -843,6 +888,158 @@ impl Display for Type {
     }
 }
 
+impl Type {
+    /// Render this type the way it is written in the human-readable schema
+    /// syntax, e.g., `{ name: String, age?: Long }`, `Set<User>`, or `ipaddr`.
+    ///
+    /// Some types inferred by the validator can't be written in a schema, like
+    /// the type of an empty set literal or a union of entity types. These are
+    /// rendered with `__cedar::internal` names, as by the `Display` impl.
+    pub fn display_cedarschema(&self) -> String {
+        match self {
+            Type::Never => "__cedar::internal::Never".to_string(),
+            // the boolean singleton types are written `Bool` in a schema
+            Type::True
+            | Type::False
+            | Type::Primitive {
+                primitive_type: Primitive::Bool,
+            } => "Bool".to_string(),
+            Type::Primitive {
+                primitive_type: Primitive::Long,
+            } => "Long".to_string(),
+            Type::Primitive {
+                primitive_type: Primitive::String,
+            } => "String".to_string(),
+            Type::Set {
+                element_type: Some(element_type),
+            } => format!("Set<{}>", element_type.display_cedarschema()),
+            Type::Set { element_type: None } => "Set<__cedar::internal::Any>".to_string(),
+            Type::EntityOrRecord(EntityRecordKind::Record {
+                attrs,
+                open_attributes,
+            }) => {
+                let attrs = attrs
+                    .iter()
+                    .map(|(name, ty)| {
+                        format!(
+                            "{}{}: {}",
+                            cedarschema_attr_name(name),
+                            if ty.is_required { "" } else { "?" },
+                            ty.attr_type.display_cedarschema()
+                        )
+                    })
+                    .join(", ");
+                let record = if attrs.is_empty() {
+                    "{}".to_string()
+                } else {
+                    format!("{{ {attrs} }}")
+                };
+                if open_attributes.is_open() {
+                    format!("__cedar::internal::OpenRecord{record}")
+                } else {
+                    record
+                }
+            }
+            // entity types, extension types, and types with no schema syntax
+            // are rendered the same way as by the `Display` impl
+            Type::EntityOrRecord(_) | Type::ExtensionType { .. } => self.to_string(),
+        }
+    }
+
+    /// Render this type the way it is written in the JSON schema syntax, e.g.,
+    /// `{"type":"Set","element":{"type":"Entity","name":"User"}}`.
+    ///
+    /// Returns `None` for types which can't be written in the JSON schema
+    /// syntax, e.g., the type of an empty set or of an expression which may be
+    /// an entity of several different types.
+    pub fn display_json_schema(&self) -> Option<String> {
+        self.json_schema().map(|json| json.to_string())
+    }
+
+    /// The JSON schema syntax for this type, as rendered by
+    /// [`Type::display_json_schema`]
+    fn json_schema(&self) -> Option<serde_json::Value> {
+        use serde_json::{json, Map, Value};
+        match self {
+            Type::True
+            | Type::False
+            | Type::Primitive {
+                primitive_type: Primitive::Bool,
+            } => Some(json!({ "type": "Boolean" })),
+            Type::Primitive {
+                primitive_type: Primitive::Long,
+            } => Some(json!({ "type": "Long" })),
+            Type::Primitive {
+                primitive_type: Primitive::String,
+            } => Some(json!({ "type": "String" })),
+            Type::Set {
+                element_type: Some(element_type),
+            } => Some(json!({ "type": "Set", "element": element_type.json_schema()? })),
+            Type::EntityOrRecord(EntityRecordKind::Record {
+                attrs,
+                open_attributes,
+            }) => {
+                let attributes = attrs
+                    .iter()
+                    .map(|(name, ty)| {
+                        let mut attr = match ty.attr_type.json_schema()? {
+                            Value::Object(attr) => attr,
+                            _ => return None,
+                        };
+                        if !ty.is_required {
+                            attr.insert("required".into(), json!(false));
+                        }
+                        Some((name.to_string(), Value::Object(attr)))
+                    })
+                    .collect::<Option<Map<_, _>>>()?;
+                let mut record = Map::new();
+                record.insert("type".into(), json!("Record"));
+                record.insert("attributes".into(), Value::Object(attributes));
+                if open_attributes.is_open() {
+                    record.insert("additionalAttributes".into(), json!(true));
+                }
+                Some(Value::Object(record))
+            }
+            Type::EntityOrRecord(EntityRecordKind::ActionEntity { name, .. }) => {
+                Some(json!({ "type": "Entity", "name": name.to_string() }))
+            }
+            Type::EntityOrRecord(EntityRecordKind::Entity(lub)) => lub
+                .get_single_entity()
+                .map(|name| json!({ "type": "Entity", "name": name.to_string() })),
+            Type::ExtensionType { name } => {
+                Some(json!({ "type": "Extension", "name": name.to_string() }))
+            }
+            Type::Set { element_type: None }
+            | Type::Never
+            | Type::EntityOrRecord(EntityRecordKind::AnyEntity) => None,
+        }
+    }
+
+    /// Rebase every entity type in this type, including in nested sets and
+    /// records, with [`Name::rebase_namespace`]
+    pub(crate) fn rebase_namespace(&self, from: &Name, to: &Name) -> Type {
+        match self {
+            Type::Set {
+                element_type: Some(element_type),
+            } => Type::Set {
+                element_type: Some(Box::new(element_type.rebase_namespace(from, to))),
+            },
+            Type::EntityOrRecord(kind) => Type::EntityOrRecord(kind.rebase_namespace(from, to)),
+            ty => ty.clone(),
+        }
+    }
+}
+
+/// An attribute name as written in a record type in the human-readable schema
+/// syntax: as is if it is an identifier, and quoted otherwise
+fn cedarschema_attr_name(name: &str) -> String {
+    if name.parse::<Id>().is_ok() {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.escape_debug())
+    }
+}
+
 impl TryFrom<Type> for cedar_policy_core::entities::SchemaType {
     type Error = String;
     fn try_from(ty: Type) -> Result<cedar_policy_core::entities::SchemaType, String> {
@@ -1176,6 +1373,17 @@ impl Attributes {
             Self::attributes_lub_iter(schema, attrs0, attrs1, ValidationMode::Permissive).flatten(),
         )
     }
+
+    /// Rebase every entity type in the types of these attributes with
+    /// [`Name::rebase_namespace`]
+    pub(crate) fn rebase_namespace(&self, from: &Name, to: &Name) -> Attributes {
+        Attributes::with_attributes(self.iter().map(|(attr, ty)| {
+            (
+                attr.clone(),
+                AttributeType::new(ty.attr_type.rebase_namespace(from, to), ty.is_required),
+            )
+        }))
+    }
 }
 
 impl IntoIterator for Attributes {
@@ -1241,6 +1449,31 @@ pub enum EntityRecordKind {
 }
 
 impl EntityRecordKind {
+    /// Rebase every entity type in this kind with [`Name::rebase_namespace`]
+    pub(crate) fn rebase_namespace(&self, from: &Name, to: &Name) -> EntityRecordKind {
+        match self {
+            EntityRecordKind::Record {
+                attrs,
+                open_attributes,
+            } => EntityRecordKind::Record {
+                attrs: attrs.rebase_namespace(from, to),
+                open_attributes: *open_attributes,
+            },
+            EntityRecordKind::AnyEntity => EntityRecordKind::AnyEntity,
+            EntityRecordKind::Entity(lub) => EntityRecordKind::Entity(EntityLUB {
+                lub_elements: lub
+                    .lub_elements
+                    .iter()
+                    .map(|name| name.rebase_namespace(from, to))
+                    .collect(),
+            }),
+            EntityRecordKind::ActionEntity { name, attrs } => EntityRecordKind::ActionEntity {
+                name: name.rebase_namespace(from, to),
+                attrs: attrs.rebase_namespace(from, to),
+            },
+        }
+    }
+
     pub(crate) fn as_entity_lub(&self) -> Option<EntityLUB> {
         match self {
             EntityRecordKind::Record { .. } => None,
@@ -1527,7 +1760,7 @@ pub enum Primitive {
 }
 
 /// A set of effects. Used to represent knowledge about attribute existence
-/// before and after evaluating an expression.
+/// and entity types before and after evaluating an expression.
 #[derive(Eq, PartialEq, Debug, Clone, Default)]
 pub struct EffectSet<'a>(HashSet<Effect<'a>>);
 
@@ -1542,6 +1775,10 @@ impl<'a> EffectSet<'a> {
         set
     }
 
+    pub fn insert(&mut self, e: Effect<'a>) {
+        self.0.insert(e);
+    }
+
     pub fn union(&self, other: &Self) -> Self {
         EffectSet(self.0.union(&other.0).cloned().collect())
     }
@@ -1553,23 +1790,81 @@ impl<'a> EffectSet<'a> {
     pub fn contains(&self, e: &Effect) -> bool {
         self.0.contains(e)
     }
+
+    /// Get the entity types which `on_expr` is known to have, as learned from
+    /// `is` tests on it.
+    pub fn entity_types_of<'b>(&'b self, on_expr: &'b Expr) -> impl Iterator<Item = &'a Name> + 'b {
+        let (on_expr, path) = Effect::split_attr_path(on_expr);
+        self.0.iter().filter_map(move |e| match e.kind {
+            EffectKind::EntityType(name) if e.on_expr == on_expr && e.path == path => Some(name),
+            _ => None,
+        })
+    }
 }
 
 /// Represent a single effect, which is an expression and some attribute that is
-/// known to exist for that expression.
+/// known to exist for that expression, or some entity type that the expression
+/// is known to have.
+///
+/// An attribute access expression `e.a.b` is stored as `e` and the path of
+/// attributes `a.b` accessed on it, so that the effect of `e has a.b.c` on
+/// `e.a.b` is equal to the effect of `e.a.b has c`.
 #[derive(Hash, Eq, PartialEq, Debug, Clone)]
 pub struct Effect<'a> {
     on_expr: ExprShapeOnly<'a>,
-    attribute: &'a str,
+    path: Vec<&'a str>,
+    kind: EffectKind<'a>,
+}
+
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+enum EffectKind<'a> {
+    /// The attribute exists for the expression
+    Attribute(&'a str),
+    /// The expression is an entity of the entity type
+    EntityType(&'a Name),
 }
 
 impl<'a> Effect<'a> {
     pub fn new(on_expr: &'a Expr, attribute: &'a str) -> Self {
+        Self::on_path(on_expr, std::iter::empty(), attribute)
+    }
+
+    /// The effect that `attribute` exists for the expression accessing the
+    /// attributes in `path` on `on_expr`.
+    pub fn on_path(
+        on_expr: &'a Expr,
+        path: impl IntoIterator<Item = &'a SmolStr>,
+        attribute: &'a str,
+    ) -> Self {
+        let (on_expr, mut on_path) = Self::split_attr_path(on_expr);
+        on_path.extend(path.into_iter().map(SmolStr::as_str));
         Self {
-            on_expr: ExprShapeOnly::new(on_expr),
-            attribute,
+            on_expr,
+            path: on_path,
+            kind: EffectKind::Attribute(attribute),
         }
     }
+
+    pub fn entity_type(on_expr: &'a Expr, entity_type: &'a Name) -> Self {
+        let (on_expr, path) = Self::split_attr_path(on_expr);
+        Self {
+            on_expr,
+            path,
+            kind: EffectKind::EntityType(entity_type),
+        }
+    }
+
+    /// Split an expression into the expression which is not an attribute
+    /// access that it accesses attributes on and the path of those attributes.
+    fn split_attr_path(mut on_expr: &'a Expr) -> (ExprShapeOnly<'a>, Vec<&'a str>) {
+        let mut path = Vec::new();
+        while let ExprKind::GetAttr { expr, attr } = on_expr.expr_kind() {
+            path.push(attr.as_str());
+            on_expr = expr;
+        }
+        path.reverse();
+        (ExprShapeOnly::new(on_expr), path)
+    }
 }
 
 // PANIC SAFETY unit tests
@@ -2374,10 +2669,84 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_type_display_cedarschema() {
+        assert_eq!(Type::primitive_long().display_cedarschema(), "Long");
+        assert_eq!(Type::True.display_cedarschema(), "Bool");
+        assert_eq!(
+            Type::set(Type::named_entity_reference_from_str("User")).display_cedarschema(),
+            "Set<User>"
+        );
+        assert_eq!(
+            Type::record_with_attributes(
+                [
+                    (
+                        "name".into(),
+                        AttributeType::new(Type::primitive_string(), true)
+                    ),
+                    (
+                        "age".into(),
+                        AttributeType::new(Type::primitive_long(), false)
+                    ),
+                    (
+                        "first name".into(),
+                        AttributeType::new(Type::primitive_string(), true)
+                    ),
+                ],
+                OpenTag::ClosedAttributes,
+            )
+            .display_cedarschema(),
+            r#"{ age?: Long, "first name": String, name: String }"#
+        );
+        assert_eq!(
+            Type::closed_record_with_attributes([]).display_cedarschema(),
+            "{}"
+        );
+    }
+
+    #[test]
+    fn test_type_display_json_schema() {
+        assert_eq!(
+            Type::set(Type::named_entity_reference_from_str("User"))
+                .display_json_schema()
+                .as_deref(),
+            Some(r#"{"type":"Set","element":{"type":"Entity","name":"User"}}"#)
+        );
+        assert_eq!(
+            Type::record_with_attributes(
+                [(
+                    "age".into(),
+                    AttributeType::new(Type::primitive_long(), false)
+                )],
+                OpenTag::OpenAttributes,
+            )
+            .display_json_schema()
+            .as_deref(),
+            Some(
+                r#"{"type":"Record","attributes":{"age":{"type":"Long","required":false}},"additionalAttributes":true}"#
+            )
+        );
+        assert_eq!(Type::any_entity_reference().display_json_schema(), None);
+        assert_eq!(Type::any_set().display_json_schema(), None);
+        assert_eq!(Type::Never.display_json_schema(), None);
+        assert_eq!(
+            Type::least_upper_bound(
+                &ValidatorSchema::empty(),
+                &Type::named_entity_reference_from_str("User"),
+                &Type::named_entity_reference_from_str("Group"),
+                ValidationMode::Permissive,
+            )
+            .expect("entity types should have a LUB in permissive mode")
+            .display_json_schema(),
+            None
+        );
+    }
+
     #[test]
     #[cfg(feature = "ipaddr")]
     fn text_extension_type_dislay() {
         let ipaddr = Name::parse_unqualified_name("ipaddr").expect("should be a valid identifier");
-        assert_type_display_roundtrip(Type::extension(ipaddr));
+        assert_type_display_roundtrip(Type::extension(ipaddr.clone()));
+        assert_eq!(Type::extension(ipaddr).display_cedarschema(), "ipaddr");
     }
 }
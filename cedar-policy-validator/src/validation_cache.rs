@@ -0,0 +1,209 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Contains [`ValidationCache`], which lets [`Validator::validate_cached`]
+//! skip validating policies which haven't changed since the last time.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use cedar_policy_core::ast::{PolicyID, PolicySet, Template};
+use cedar_policy_core::parser::Loc;
+
+use crate::{
+    confusable_string_checks, SourceLocation, ValidationError, ValidationMode, ValidationResult,
+    ValidationWarning, Validator,
+};
+
+/// The errors and warnings found by validating a single static policy or
+/// template, kept between calls to [`Validator::validate_cached`].
+///
+/// Entries are keyed by the policy id and hold the text of the policy, so a
+/// policy is validated again whenever its text changes. Every entry is
+/// discarded when the cache is used with a different schema, a different
+/// [`ValidationMode`], or a `Validator` with different options.
+#[derive(Debug, Default)]
+pub struct ValidationCache {
+    /// Fingerprint of the `Validator` and `ValidationMode` the entries were
+    /// computed with
+    fingerprint: Option<u64>,
+    entries: HashMap<PolicyID, CachedPolicy>,
+}
+
+/// The result of validating one static policy or template
+#[derive(Debug)]
+struct CachedPolicy {
+    /// Text of the policy the results were computed for
+    text: String,
+    /// Location of the policy the results were computed for. Locations in the
+    /// results are rebased from this when the policy has moved.
+    loc: Option<Loc>,
+    errors: Vec<ValidationError>,
+    warnings: Vec<ValidationWarning>,
+}
+
+impl ValidationCache {
+    /// Construct an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of policies with cached results
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Are there no cached results
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Discard all cached results
+    pub fn clear(&mut self) {
+        self.fingerprint = None;
+        self.entries.clear();
+    }
+}
+
+impl CachedPolicy {
+    /// Update the locations in this entry for the policy now being at `loc`.
+    /// This only changes locations into the source text the policy was
+    /// parsed from, e.g., it doesn't change locations in a schema.
+    fn rebase(&mut self, loc: &Option<Loc>) {
+        let (Some(from), Some(to)) = (&self.loc, loc) else {
+            return;
+        };
+        if from == to {
+            return;
+        }
+        let rebase_loc = |source_loc: Option<&Loc>| -> Option<Loc> {
+            let source_loc = source_loc?;
+            if Arc::ptr_eq(&source_loc.src, &from.src)
+                && from.start() <= source_loc.start()
+                && source_loc.end() <= from.end()
+            {
+                Some(Loc::new(
+                    (
+                        source_loc.start() - from.start() + to.start(),
+                        source_loc.span.len(),
+                    ),
+                    Arc::clone(&to.src),
+                ))
+            } else {
+                Some(source_loc.clone())
+            }
+        };
+        for error in &mut self.errors {
+            let source_loc = rebase_loc(error.location().source_loc());
+            let (location, kind) = error.clone().into_location_and_error_kind();
+            *error =
+                ValidationError::with_policy_id(location.policy_id().clone(), source_loc, kind);
+        }
+        for warning in &mut self.warnings {
+            let source_loc = rebase_loc(warning.location.source_loc());
+            warning.location =
+                SourceLocation::new(warning.location.policy_id().clone(), source_loc);
+        }
+        self.loc = loc.clone();
+    }
+}
+
+/// The text of a policy. This is the source text it was parsed from when it
+/// is available, and the policy as displayed otherwise.
+fn policy_text(p: &Template) -> String {
+    match p.loc().as_ref().and_then(Loc::snippet) {
+        Some(text) => text.to_string(),
+        None => p.to_string(),
+    }
+}
+
+impl Validator {
+    /// Validate all templates, links, and static policies in a policy set,
+    /// like [`Validator::validate`], reusing the results in `cache` for static
+    /// policies and templates which were already validated. Only policies
+    /// which are new or whose text changed are type-checked. Links and the
+    /// checks for confusable strings, which depend on the whole policy set,
+    /// are always checked again.
+    ///
+    /// The result is the same as `validate` would return. Afterwards, `cache`
+    /// holds the results for exactly the policies in `policies`. Using the
+    /// cache with a different schema, a different `mode`, or a `Validator` with
+    /// different options discards all of its entries.
+    pub fn validate_cached(
+        &self,
+        policies: &PolicySet,
+        mode: ValidationMode,
+        cache: &mut ValidationCache,
+    ) -> ValidationResult {
+        let fingerprint = self.fingerprint(mode);
+        if cache.fingerprint != Some(fingerprint) {
+            cache.entries.clear();
+            cache.fingerprint = Some(fingerprint);
+        }
+        let mut entries = HashMap::new();
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        for p in policies.all_templates() {
+            let text = policy_text(p);
+            let entry = match cache.entries.remove(p.id()) {
+                Some(mut entry) if entry.text == text => {
+                    entry.rebase(p.loc());
+                    entry
+                }
+                _ => {
+                    let (errors, warnings) = self.validate_policy(p, mode);
+                    CachedPolicy {
+                        text,
+                        loc: p.loc().clone(),
+                        errors: errors.collect(),
+                        warnings: warnings.collect(),
+                    }
+                }
+            };
+            errors.extend(entry.errors.iter().cloned());
+            warnings.extend(entry.warnings.iter().cloned());
+            entries.insert(p.id().clone(), entry);
+        }
+        cache.entries = entries;
+        let link_errs = policies
+            .policies()
+            .filter_map(|p| self.validate_slots(p, mode))
+            .flatten();
+        ValidationResult::new(
+            errors.into_iter().chain(link_errs),
+            warnings
+                .into_iter()
+                .chain(confusable_string_checks(policies.all_templates())),
+        )
+    }
+
+    /// Hash of everything besides the policy itself which affects the result
+    /// of validating a policy in `mode`
+    fn fingerprint(&self, mode: ValidationMode) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.schema.fingerprint().hash(&mut hasher);
+        if let Some(provenance) = &self.provenance {
+            provenance.hash_sources(&mut hasher);
+        }
+        self.strict_closed_attributes.hash(&mut hasher);
+        self.closed_context.hash(&mut hasher);
+        self.unknown_functions_as_warnings.hash(&mut hasher);
+        mode.hash(&mut hasher);
+        hasher.finish()
+    }
+}
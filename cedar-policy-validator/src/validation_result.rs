@@ -16,6 +16,7 @@
 
 use cedar_policy_core::ast::PolicyID;
 use cedar_policy_core::parser::Loc;
+use itertools::Itertools;
 use miette::Diagnostic;
 use thiserror::Error;
 
@@ -201,6 +202,12 @@ pub enum ValidationErrorKind {
     #[error(transparent)]
     #[diagnostic(transparent)]
     UnspecifiedEntity(#[from] UnspecifiedEntityError),
+    /// A policy accesses an attribute of `context` which is not declared in
+    /// the context of an action it applies to. Only reported when validating
+    /// with [`crate::Validator::with_closed_context`].
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UndeclaredContextAttribute(#[from] UndeclaredContextAttribute),
 }
 
 impl ValidationErrorKind {
@@ -244,6 +251,38 @@ impl ValidationErrorKind {
     pub(crate) fn unspecified_entity(entity_id: String) -> ValidationErrorKind {
         UnspecifiedEntityError { entity_id }.into()
     }
+
+    pub(crate) fn undeclared_context_attribute(
+        action: String,
+        attribute: String,
+        context_type: String,
+        suggested_attribute: Option<String>,
+    ) -> ValidationErrorKind {
+        UndeclaredContextAttribute {
+            action,
+            attribute,
+            context_type,
+            suggested_attribute,
+        }
+        .into()
+    }
+
+    /// A code identifying the kind of this error, e.g.,
+    /// `"cedar::validation::unrecognized_entity_type"`. Type errors use the
+    /// more specific [`TypeErrorKind::error_code`]. The code for a kind of
+    /// error is stable across releases, even when the error message changes.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::UnrecognizedEntityType(_) => "cedar::validation::unrecognized_entity_type",
+            Self::UnrecognizedActionId(_) => "cedar::validation::unrecognized_action_id",
+            Self::InvalidActionApplication(_) => "cedar::validation::invalid_action_application",
+            Self::TypeError(kind) => kind.error_code(),
+            Self::UnspecifiedEntity(_) => "cedar::validation::unspecified_entity",
+            Self::UndeclaredContextAttribute(_) => {
+                "cedar::validation::undeclared_context_attribute"
+            }
+        }
+    }
 }
 
 /// Structure containing details about an unrecognized entity type error.
@@ -320,6 +359,35 @@ pub struct UnspecifiedEntityError {
     pub(crate) entity_id: String,
 }
 
+/// Structure containing details about an undeclared context attribute error.
+#[derive(Debug, Clone, Error, Eq, PartialEq)]
+#[error("attribute `{attribute}` is not declared in the context of action `{action}`")]
+pub struct UndeclaredContextAttribute {
+    /// The action whose context does not declare the attribute.
+    pub(crate) action: String,
+    /// The attribute accessed by the policy.
+    pub(crate) attribute: String,
+    /// The context type declared for the action, in the human-readable schema
+    /// syntax.
+    pub(crate) context_type: String,
+    /// An attribute declared in the context that the user might reasonably
+    /// have intended to write.
+    pub(crate) suggested_attribute: Option<String>,
+}
+
+impl Diagnostic for UndeclaredContextAttribute {
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        let expected = format!(
+            "the context of action `{}` has type `{}`",
+            self.action, self.context_type
+        );
+        match &self.suggested_attribute {
+            Some(s) => Some(Box::new(format!("did you mean `{s}`? {expected}"))),
+            None => Some(Box::new(expected)),
+        }
+    }
+}
+
 /// The structure for validation warnings.
 #[derive(Hash, Eq, PartialEq, Error, Debug, Clone)]
 pub struct ValidationWarning {
@@ -427,4 +495,38 @@ pub enum ValidationWarningKind {
         "policy is impossible: the policy expression evaluates to false for all valid requests"
     )]
     ImpossiblePolicy,
+    /// When validating against several schemas, the policy references an
+    /// entity type or action which more than one of the schemas declare. The
+    /// declaration from the first of these schemas is used.
+    #[error("`{name}` is declared by more than one schema (schemas {})", .schemas.iter().join(", "))]
+    AmbiguousSchemaName {
+        /// The entity type or action
+        name: String,
+        /// Indices of the schemas declaring `name`
+        schemas: Vec<usize>,
+    },
+    /// With [`crate::Validator::with_unknown_functions_as_warnings`], the
+    /// policy calls a function which is not a known extension function. The
+    /// call is an error whenever it is evaluated.
+    #[error("`{0}` is not a known extension function, so calling it is an error when evaluated")]
+    UnknownFunction(String),
+}
+
+impl ValidationWarningKind {
+    /// A code identifying the kind of this warning, e.g.,
+    /// `"cedar::validation::impossible_policy"`. The code for a kind of
+    /// warning is stable across releases, even when the warning message
+    /// changes.
+    pub fn warning_code(&self) -> &'static str {
+        match self {
+            Self::MixedScriptString(_) => "cedar::validation::mixed_script_string",
+            Self::BidiCharsInString(_) => "cedar::validation::bidi_chars_in_string",
+            Self::BidiCharsInIdentifier(_) => "cedar::validation::bidi_chars_in_identifier",
+            Self::MixedScriptIdentifier(_) => "cedar::validation::mixed_script_identifier",
+            Self::ConfusableIdentifier(_) => "cedar::validation::confusable_identifier",
+            Self::ImpossiblePolicy => "cedar::validation::impossible_policy",
+            Self::AmbiguousSchemaName { .. } => "cedar::validation::ambiguous_schema_name",
+            Self::UnknownFunction(_) => "cedar::validation::unknown_function",
+        }
+    }
 }
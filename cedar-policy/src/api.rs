@@ -27,8 +27,36 @@ pub use id::*;
 mod err;
 pub use err::*;
 
+mod combine;
+pub use combine::*;
+mod determining_policy;
+pub use determining_policy::*;
+mod entities_diff;
+pub use entities_diff::*;
+mod entity_type_info;
+pub use entity_type_info::*;
+mod entity_validation;
+pub use entity_validation::*;
+mod into_context;
+pub use into_context::*;
+mod layered;
+pub use layered::*;
+mod policy_complexity;
+pub use policy_complexity::*;
+mod policy_set_stats;
+pub use policy_set_stats::*;
+mod recovered_policy;
+pub use recovered_policy::*;
+mod sarif;
+mod schema_builder;
+pub use schema_builder::*;
+mod trace;
+pub use trace::*;
+mod validation_json;
+pub use validation_json::VALIDATION_RESULT_FORMAT_VERSION;
+
 pub use ast::Effect;
-pub use authorizer::Decision;
+pub use authorizer::{AuthorizerConfig, Decision, ErrorHandling, PerPolicyOutcome};
 use cedar_policy_core::ast;
 #[cfg(feature = "partial-eval")]
 use cedar_policy_core::ast::BorrowedRestrictedExpr;
@@ -36,23 +64,31 @@ use cedar_policy_core::ast::{
     ContextCreationError, ExprConstructionError, Integer, RestrictedExprParseError,
 }; // `ContextCreationError` is unsuitable for `pub use` because it contains internal types like `RestrictedExpr`
 use cedar_policy_core::authorizer;
-use cedar_policy_core::entities::{ContextSchema, Dereference};
+use cedar_policy_core::entities::json::err::JsonDeserializationErrorContext;
+pub use cedar_policy_core::entities::EntitiesParseMode;
+use cedar_policy_core::entities::{CedarValueJson, ContextSchema, Dereference};
 use cedar_policy_core::est;
 use cedar_policy_core::est::{Link, PolicyEntry};
-use cedar_policy_core::evaluator::Evaluator;
+pub use cedar_policy_core::evaluator::EvalLimits;
 #[cfg(feature = "partial-eval")]
 use cedar_policy_core::evaluator::RestrictedEvaluator;
+use cedar_policy_core::evaluator::{fold_template_constants, Evaluator};
 pub use cedar_policy_core::extensions;
 use cedar_policy_core::extensions::Extensions;
 use cedar_policy_core::parser;
+pub use cedar_policy_core::parser::ParseLimits;
 use cedar_policy_core::FromNormalizedStr;
 use cedar_policy_validator::RequestValidationError; // this type is unsuitable for `pub use` because it contains internal types like `EntityUID` and `EntityType`
+pub use cedar_policy_validator::{
+    AttributeOwner, CompatibilityReport, EntityViolationKind, SchemaChange,
+};
 use itertools::{Either, Itertools};
 use miette::Diagnostic;
 use ref_cast::RefCast;
 use smol_str::SmolStr;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::Arc;
 
 /// Extended functionality for `Entities` struct
 pub mod entities {
@@ -77,7 +113,7 @@ pub mod entities {
     /// Errors around entities
     pub mod err {
         pub use cedar_policy_core::entities::err::{
-            Duplicate, EntitiesError, TransitiveClosureError,
+            Duplicate, EntitiesError, MissingParent, TransitiveClosureError,
         };
     }
 
@@ -102,15 +138,16 @@ pub mod entities {
         /// Errors around conformance
         pub mod err {
             pub use cedar_policy_core::entities::conformance::err::{
-                ActionDeclarationMismatch, EntitySchemaConformanceError, ExtensionFunctionLookup,
-                HeterogeneousSet, InvalidAncestorType, MissingRequiredEntityAttr, TypeMismatch,
+                ActionDeclarationMismatch, DerivedEntityAttr, EntitySchemaConformanceError,
+                ExtensionFunctionLookup, HeterogeneousSet, InvalidAncestorType,
+                MissingRequiredEntityAttr, MutuallyExclusiveEntityAttrs, TypeMismatch,
                 UndeclaredAction, UnexpectedEntityAttr, UnexpectedEntityTypeError,
             };
         }
     }
 }
 
-use entities::json::err::JsonDeserializationError;
+use entities::json::err::{JsonDeserializationError, JsonSerializationError};
 
 /// Entity datatype
 // INVARIANT(UidOfEntityNotUnspecified): The `EntityUid` of an `Entity` cannot be unspecified
@@ -204,6 +241,25 @@ impl Entity {
         EntityUid::new(self.0.uid().clone())
     }
 
+    /// Get the direct parents of this entity, as they were given when it was
+    /// constructed. To get all of its ancestors, including the parents of its
+    /// parents, use [`Entities::ancestors`] on an `Entities` containing it.
+    /// ```
+    /// # use cedar_policy::{Entities, EntityUid};
+    /// let entities = Entities::from_json_str(r#"[
+    ///     { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [{ "type": "Group", "id": "staff" }] },
+    ///     { "uid": { "type": "Group", "id": "staff" }, "attrs": {}, "parents": [{ "type": "Group", "id": "all" }] },
+    ///     { "uid": { "type": "Group", "id": "all" }, "attrs": {}, "parents": [] }
+    /// ]"#, None).unwrap();
+    /// let alice: EntityUid = r#"User::"alice""#.parse().unwrap();
+    /// let parents: Vec<String> = entities.get(&alice).unwrap().parents().map(ToString::to_string).collect();
+    /// assert_eq!(parents, vec![r#"Group::"staff""#]);
+    /// assert_eq!(entities.ancestors(&alice).unwrap().count(), 2);
+    /// ```
+    pub fn parents(&self) -> impl Iterator<Item = &EntityUid> {
+        self.0.parents().map(EntityUid::ref_cast)
+    }
+
     /// Get the value for the given attribute, or `None` if not present.
     ///
     /// This can also return Some(Err) if the attribute is not a value (i.e., is
@@ -232,6 +288,79 @@ impl Entity {
         Some(Ok(EvalResult::from(v)))
     }
 
+    /// Get the value of the attribute `attr` of this entity as a `Long`.
+    ///
+    /// Returns `None` if the entity has no such attribute, and a [`TypeError`]
+    /// if its value is not a `Long`.
+    /// ```
+    /// # use cedar_policy::{Entity, EntityUid, RestrictedExpression};
+    /// # use std::collections::{HashMap, HashSet};
+    /// # use std::str::FromStr;
+    /// let attrs = HashMap::from([
+    ///     ("age".to_string(), RestrictedExpression::new_long(21)),
+    ///     ("department".to_string(), RestrictedExpression::new_string("CS".to_string())),
+    /// ]);
+    /// let uid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let entity = Entity::new(uid, attrs, HashSet::new()).unwrap();
+    /// assert_eq!(entity.attr_long("age").unwrap().unwrap(), 21);
+    /// assert!(entity.attr_long("department").unwrap().is_err());
+    /// assert!(entity.attr_long("foo").is_none());
+    /// ```
+    pub fn attr_long(&self, attr: &str) -> Option<Result<Integer, TypeError>> {
+        self.typed_attr(attr, ast::Type::Long, |v| match v {
+            EvalResult::Long(i) => Some(i),
+            _ => None,
+        })
+    }
+
+    /// Get the value of the attribute `attr` of this entity as a `String`.
+    ///
+    /// Returns `None` if the entity has no such attribute, and a [`TypeError`]
+    /// if its value is not a `String`.
+    pub fn attr_string(&self, attr: &str) -> Option<Result<String, TypeError>> {
+        self.typed_attr(attr, ast::Type::String, |v| match v {
+            EvalResult::String(s) => Some(s),
+            _ => None,
+        })
+    }
+
+    /// Get the value of the attribute `attr` of this entity as a `Bool`.
+    ///
+    /// Returns `None` if the entity has no such attribute, and a [`TypeError`]
+    /// if its value is not a `Bool`.
+    pub fn attr_bool(&self, attr: &str) -> Option<Result<bool, TypeError>> {
+        self.typed_attr(attr, ast::Type::Bool, |v| match v {
+            EvalResult::Bool(b) => Some(b),
+            _ => None,
+        })
+    }
+
+    /// Get the value of the attribute `attr` of this entity as a `Set`.
+    ///
+    /// Returns `None` if the entity has no such attribute, and a [`TypeError`]
+    /// if its value is not a `Set`.
+    pub fn attr_set(&self, attr: &str) -> Option<Result<Set, TypeError>> {
+        self.typed_attr(attr, ast::Type::Set, |v| match v {
+            EvalResult::Set(set) => Some(set),
+            _ => None,
+        })
+    }
+
+    // Get the value of an attribute, converted by `into` if it has the
+    // `expected` type
+    fn typed_attr<T>(
+        &self,
+        attr: &str,
+        expected: ast::Type,
+        into: impl FnOnce(EvalResult) -> Option<T>,
+    ) -> Option<Result<T, TypeError>> {
+        let Ok(v) = ast::Value::try_from(self.0.get(attr)?.clone()) else {
+            return Some(Err(TypeError::new(attr, expected, None)));
+        };
+        let actual = ast::StaticallyTyped::type_of(&v);
+        Some(into(EvalResult::from(v)).ok_or_else(|| TypeError::new(attr, expected, Some(actual))))
+    }
+
     /// Consume the entity and return the entity's owned Uid, attributes and parents.
     pub fn into_inner(
         self,
@@ -291,7 +420,9 @@ impl Entities {
         Self(cedar_policy_core::entities::Entities::new())
     }
 
-    /// Get the `Entity` with the given Uid, if any
+    /// Get the `Entity` with the given Uid, if any. Uids are stored and
+    /// compared structurally, by entity type and id, so how `uid` was written
+    /// doesn't matter and no normalization happens at lookup.
     pub fn get(&self, uid: &EntityUid) -> Option<&Entity> {
         match self.0.entity(uid.as_ref()) {
             Dereference::Residual(_) | Dereference::NoSuchEntity => None,
@@ -299,6 +430,47 @@ impl Entities {
         }
     }
 
+    /// Get the `Entity` whose Uid matches `uid` ignoring ASCII case, in both
+    /// the entity type name and the entity id. An exact match is preferred;
+    /// otherwise, if several entities match, the one whose Uid sorts first is
+    /// returned.
+    ///
+    /// This is a convenience for tooling such as migration scripts which
+    /// detect inconsistent casing in entity data. It is not used in
+    /// authorization: Cedar always compares entity Uids case-sensitively, so
+    /// `User::"Alice"` and `User::"alice"` remain distinct entities for
+    /// policies and for [`Entities::get`].
+    /// ```
+    /// # use cedar_policy::{Entities, EntityUid};
+    /// let entities = Entities::from_json_str(
+    ///     r#"[{ "uid": { "type": "User", "id": "Alice" }, "attrs": {}, "parents": [] }]"#,
+    ///     None,
+    /// ).unwrap();
+    /// let uid: EntityUid = r#"user::"alice""#.parse().unwrap();
+    /// assert!(entities.get(&uid).is_none());
+    /// let entity = entities.get_ignore_ascii_case(&uid).unwrap();
+    /// assert_eq!(entity.uid().to_string(), r#"User::"Alice""#);
+    /// ```
+    pub fn get_ignore_ascii_case(&self, uid: &EntityUid) -> Option<&Entity> {
+        if let Some(entity) = self.get(uid) {
+            return Some(entity);
+        }
+        let uid: &ast::EntityUID = uid.as_ref();
+        let type_name = uid.entity_type().to_string();
+        let eid: &str = uid.eid().as_ref();
+        self.0
+            .iter()
+            .filter(|e| {
+                <ast::Eid as AsRef<str>>::as_ref(e.uid().eid()).eq_ignore_ascii_case(eid)
+                    && e.uid()
+                        .entity_type()
+                        .to_string()
+                        .eq_ignore_ascii_case(&type_name)
+            })
+            .min_by_key(|e| e.uid().to_string())
+            .map(Entity::ref_cast)
+    }
+
     /// Transform the store into a partial store, where
     /// attempting to dereference a non-existent `EntityUID` results in
     /// a residual instead of an error.
@@ -309,9 +481,32 @@ impl Entities {
         Self(self.0.partial())
     }
 
-    /// Iterate over the `Entity`'s in the `Entities`
+    /// Iterate over the `Entity`'s in the `Entities`, ordered by Uid
+    /// ```
+    /// # use cedar_policy::Entities;
+    /// let entities = Entities::from_json_str(r#"[
+    ///     { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+    ///     { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [] }
+    /// ]"#, None).unwrap();
+    /// let uids: Vec<String> = entities.iter().map(|e| e.uid().id().to_string()).collect();
+    /// assert_eq!(uids, vec!["alice", "bob"]);
+    /// ```
     pub fn iter(&self) -> impl Iterator<Item = &Entity> {
-        self.0.iter().map(Entity::ref_cast)
+        self.0
+            .iter()
+            .sorted_by(|e1, e2| e1.uid().cmp(e2.uid()))
+            .map(Entity::ref_cast)
+    }
+
+    /// Get the number of `Entity`'s in the `Entities`, including any action
+    /// entities added from a schema
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Test if the `Entities` is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 
     /// Create an `Entities` object with the given entities.
@@ -346,14 +541,20 @@ impl Entities {
     /// Add all of the [`Entity`]s in the collection to this [`Entities`]
     /// structure, re-computing the transitive closure.
     ///
+    /// The transitive closure is extended from the added entities, rather than
+    /// recomputed for the whole structure. This returns an error if the added
+    /// entities introduce a cycle in the entity hierarchy, or if one of them
+    /// has the same Uid as an entity already in the structure.
+    ///
     /// If a `schema` is provided, this method will ensure that the added
     /// entities fully conform to the schema -- for instance, it will error if
     /// attributes have the wrong types (e.g., string instead of integer), or if
     /// required attributes are missing or superfluous attributes are provided.
     /// (This method will not add action entities from the `schema`.)
     ///
-    /// Re-computing the transitive closure can be expensive, so it is advised
-    /// to not call this method in a loop.
+    /// Updating the transitive closure still visits every entity, so it is
+    /// advised to add entities in batches rather than calling this method in
+    /// a loop.
     pub fn add_entities(
         self,
         entities: impl IntoIterator<Item = Entity>,
@@ -371,6 +572,81 @@ impl Entities {
         ))
     }
 
+    /// Remove the [`Entity`] with the given Uid from this [`Entities`]
+    /// structure. Returns an error if there is no such entity.
+    ///
+    /// The ancestors of the entities which had the removed entity as an
+    /// ancestor are recomputed from their parents, so they are no longer
+    /// descendants of the entities they were only in through it. This is the
+    /// same as if the removed entity had never been added.
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, EntityUid};
+    /// # use std::str::FromStr;
+    /// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let entities = Entities::from_json_value(
+    ///     serde_json::json!([{ "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [] }]),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let entities = entities.remove_entity(&alice).unwrap();
+    /// assert!(entities.get(&alice).is_none());
+    /// assert!(entities.remove_entity(&alice).is_err());
+    /// ```
+    pub fn remove_entity(self, uid: &EntityUid) -> Result<Self, EntitiesError> {
+        Ok(Self(self.0.remove_entity(uid.as_ref())?))
+    }
+
+    /// Set the given attributes of the [`Entity`] with the given Uid in this
+    /// [`Entities`] structure. Attributes in `attrs` replace any previous
+    /// value, and the entity's other attributes are left unchanged. Returns an
+    /// error if there is no such entity, or if an attribute value fails to
+    /// evaluate.
+    ///
+    /// If a `schema` is provided, this method will ensure that the updated
+    /// entity still fully conforms to the schema.
+    ///
+    /// Attributes do not affect the entity hierarchy, so the transitive
+    /// closure is unchanged.
+    ///
+    /// ```
+    /// # use cedar_policy::{Entities, EntityUid, RestrictedExpression};
+    /// # use std::collections::HashMap;
+    /// # use std::str::FromStr;
+    /// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let entities = Entities::from_json_value(
+    ///     serde_json::json!([{ "uid": { "type": "User", "id": "alice" }, "attrs": { "age": 19 }, "parents": [] }]),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let entities = entities
+    ///     .update_attributes(
+    ///         &alice,
+    ///         HashMap::from([("age".to_string(), RestrictedExpression::new_long(20))]),
+    ///         None,
+    ///     )
+    ///     .unwrap();
+    /// let age = entities.get(&alice).unwrap().attr("age").unwrap().unwrap();
+    /// assert_eq!(age, cedar_policy::EvalResult::Long(20));
+    /// ```
+    pub fn update_attributes(
+        self,
+        uid: &EntityUid,
+        attrs: HashMap<String, RestrictedExpression>,
+        schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesError> {
+        Ok(Self(
+            self.0.update_attributes(
+                uid.as_ref(),
+                attrs.into_iter().map(|(k, v)| (SmolStr::from(k), v.0)),
+                schema
+                    .map(|s| cedar_policy_validator::CoreSchema::new(&s.0))
+                    .as_ref(),
+                Extensions::all_available(),
+            )?,
+        ))
+    }
+
     /// Parse an entities JSON file (in [&str] form) and add them into this
     /// [`Entities`] structure, re-computing the transitive closure
     ///
@@ -515,12 +791,26 @@ impl Entities {
     /// # assert_eq!(ip, EvalResult::ExtensionValue("10.0.1.101/32".to_string()));
     /// ```
     pub fn from_json_str(json: &str, schema: Option<&Schema>) -> Result<Self, EntitiesError> {
+        Self::from_json_str_with_mode(json, schema, EntitiesParseMode::default())
+    }
+
+    /// Parse an entities JSON file (in `&str` form) into an `Entities` object,
+    /// like [`Entities::from_json_str`], checking the parents of the entities
+    /// according to `mode`. With [`EntitiesParseMode::RequireParentsPresent`],
+    /// this errors if a parent of an entity is neither one of the entities
+    /// nor an action declared in the `schema`.
+    pub fn from_json_str_with_mode(
+        json: &str,
+        schema: Option<&Schema>,
+        mode: EntitiesParseMode,
+    ) -> Result<Self, EntitiesError> {
         let schema = schema.map(|s| cedar_policy_validator::CoreSchema::new(&s.0));
         let eparser = cedar_policy_core::entities::EntityJsonParser::new(
             schema.as_ref(),
             Extensions::all_available(),
             cedar_policy_core::entities::TCComputation::ComputeNow,
-        );
+        )
+        .with_parse_mode(mode);
         eparser.from_json_str(json).map(Entities)
     }
 
@@ -566,13 +856,39 @@ impl Entities {
     pub fn from_json_value(
         json: serde_json::Value,
         schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesError> {
+        Self::from_json_value_with_mode(json, schema, EntitiesParseMode::default())
+    }
+
+    /// Parse an entities JSON file (in `serde_json::Value` form) into an `Entities` object,
+    /// like [`Entities::from_json_value`], checking the parents of the entities
+    /// according to `mode`. With [`EntitiesParseMode::RequireParentsPresent`],
+    /// this errors if a parent of an entity is neither one of the entities
+    /// nor an action declared in the `schema`.
+    /// ```
+    /// # use cedar_policy::{Entities, EntitiesParseMode};
+    /// let data = serde_json::json!([
+    ///     { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [{ "type": "Group", "id": "admin" }] }
+    /// ]);
+    /// assert!(Entities::from_json_value_with_mode(data.clone(), None, EntitiesParseMode::AllowMissingParents).is_ok());
+    /// let err = Entities::from_json_value_with_mode(data, None, EntitiesParseMode::RequireParentsPresent).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     r#"entity `User::"alice"` has parent `Group::"admin"`, which is not one of the entities"#
+    /// );
+    /// ```
+    pub fn from_json_value_with_mode(
+        json: serde_json::Value,
+        schema: Option<&Schema>,
+        mode: EntitiesParseMode,
     ) -> Result<Self, EntitiesError> {
         let schema = schema.map(|s| cedar_policy_validator::CoreSchema::new(&s.0));
         let eparser = cedar_policy_core::entities::EntityJsonParser::new(
             schema.as_ref(),
             Extensions::all_available(),
             cedar_policy_core::entities::TCComputation::ComputeNow,
-        );
+        )
+        .with_parse_mode(mode);
         eparser.from_json_value(json).map(Entities)
     }
 
@@ -596,13 +912,27 @@ impl Entities {
     pub fn from_json_file(
         json: impl std::io::Read,
         schema: Option<&Schema>,
+    ) -> Result<Self, EntitiesError> {
+        Self::from_json_file_with_mode(json, schema, EntitiesParseMode::default())
+    }
+
+    /// Parse an entities JSON file (in `std::io::Read` form) into an `Entities` object,
+    /// like [`Entities::from_json_file`], checking the parents of the entities
+    /// according to `mode`. With [`EntitiesParseMode::RequireParentsPresent`],
+    /// this errors if a parent of an entity is neither one of the entities
+    /// nor an action declared in the `schema`.
+    pub fn from_json_file_with_mode(
+        json: impl std::io::Read,
+        schema: Option<&Schema>,
+        mode: EntitiesParseMode,
     ) -> Result<Self, EntitiesError> {
         let schema = schema.map(|s| cedar_policy_validator::CoreSchema::new(&s.0));
         let eparser = cedar_policy_core::entities::EntityJsonParser::new(
             schema.as_ref(),
             Extensions::all_available(),
             cedar_policy_core::entities::TCComputation::ComputeNow,
-        );
+        )
+        .with_parse_mode(mode);
         eparser.from_json_file(json).map(Entities)
     }
 
@@ -629,6 +959,65 @@ impl Entities {
         Some(entity.ancestors().map(EntityUid::ref_cast))
     }
 
+    /// Get the smallest subset of these entities which gives the same
+    /// authorization decision for `request` and `policies`: the entities the
+    /// evaluation of `policies` actually uses, i.e., the entities whose
+    /// attributes are accessed and the entities on the left-hand side of `in`.
+    /// Each entity in the slice keeps all of its attributes and ancestors,
+    /// and the attributes derived from them by the schema the entities were
+    /// checked against.
+    ///
+    /// Unlike collecting the entity UIDs appearing in `request` and `policies`,
+    /// this follows chains of attribute accesses (e.g., `resource.owner.team`)
+    /// and skips the branches of `&&`, `||`, and `if` which evaluation skips.
+    /// The slice is only guaranteed to give the same decision for this
+    /// `request`, and for a concrete (not partial) store.
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, Entities, EntityUid, PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// let entities = Entities::from_json_str(
+    ///     r#"[
+    ///         {"uid": {"type": "User", "id": "alice"}, "attrs": {}, "parents": [{"type": "Team", "id": "admins"}]},
+    ///         {"uid": {"type": "User", "id": "bob"}, "attrs": {}, "parents": []},
+    ///         {"uid": {"type": "Team", "id": "admins"}, "attrs": {}, "parents": []}
+    ///     ]"#,
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let policies =
+    ///     PolicySet::from_str(r#"permit(principal in Team::"admins", action, resource);"#).unwrap();
+    /// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let request = Request::new(
+    ///     Some(alice.clone()),
+    ///     Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Photo::"trip""#).unwrap()),
+    ///     Context::empty(),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let slice = entities.slice_for(&request, &policies);
+    /// assert_eq!(slice.iter().map(|e| e.uid()).collect::<Vec<_>>(), vec![alice]);
+    /// let authorizer = Authorizer::new();
+    /// assert_eq!(
+    ///     authorizer.is_authorized(&request, &policies, &slice).decision(),
+    ///     authorizer.is_authorized(&request, &policies, &entities).decision(),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn slice_for(&self, request: &Request, policies: &PolicySet) -> Self {
+        let extensions = Extensions::all_available();
+        let eval = Evaluator::new(request.0.clone(), &self.0, &extensions);
+        let used = policies
+            .policies
+            .values()
+            .flat_map(|policy| {
+                eval.interpret_traced_entities(&policy.ast.condition(), policy.ast.env())
+                    .1
+            })
+            .collect::<HashSet<_>>();
+        Self(self.0.subset(&used))
+    }
+
     /// Dump an `Entities` object into an entities JSON file.
     ///
     /// The resulting JSON will be suitable for parsing in via
@@ -716,7 +1105,53 @@ impl Authorizer {
     /// let r = authorizer.is_authorized(&request, &policy, &entities);
     /// ```
     pub fn new() -> Self {
-        Self(authorizer::Authorizer::new())
+        Self::with_config(AuthorizerConfig::default())
+    }
+
+    /// Create a new `Authorizer` with the given configuration.
+    ///
+    /// By default, a policy whose evaluation errors is skipped: the decision
+    /// is as if the policy did not exist, and the error is reported in the
+    /// diagnostics of the response. The `error_handling` of `config` can
+    /// instead make an error in a `forbid` policy
+    /// ([`ErrorHandling::DenyOnForbidError`]), or in any policy
+    /// ([`ErrorHandling::DenyOnAnyError`]), force the decision to be `Deny`,
+    /// with the erroring policy reported in the reason for the decision.
+    /// These modes depart from the semantics given by the language spec and
+    /// formal model, so that an error can never lead to `Allow` where a
+    /// correctly evaluated policy might have denied the request.
+    /// ```
+    /// # use cedar_policy::{Authorizer, AuthorizerConfig, Context, Decision, Entities, EntityUid, ErrorHandling, PolicyId, PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// let policies = PolicySet::from_str(
+    ///     r#"permit(principal, action, resource);
+    ///     forbid(principal, action, resource) when { context.risk > 5 };"#,
+    /// )
+    /// .unwrap();
+    /// let request = Request::new(
+    ///     Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Photo::"trip""#).unwrap()),
+    ///     Context::empty(),
+    ///     None,
+    /// )
+    /// .unwrap();
+    ///
+    /// // `context.risk` does not exist, so the `forbid` policy errors
+    /// let response = Authorizer::new().is_authorized(&request, &policies, &Entities::empty());
+    /// assert_eq!(response.decision(), Decision::Allow);
+    ///
+    /// let authorizer = Authorizer::with_config(AuthorizerConfig {
+    ///     error_handling: ErrorHandling::DenyOnForbidError,
+    /// });
+    /// let response = authorizer.is_authorized(&request, &policies, &Entities::empty());
+    /// assert_eq!(response.decision(), Decision::Deny);
+    /// let reason = response.diagnostics().reason().collect::<Vec<_>>();
+    /// assert_eq!(reason, vec![&PolicyId::new("policy1")]);
+    /// assert_eq!(response.diagnostics().errors().count(), 1);
+    /// ```
+    pub fn with_config(config: AuthorizerConfig) -> Self {
+        Self(authorizer::Authorizer::with_config(config))
     }
 
     /// Returns an authorization response for `r` with respect to the given
@@ -724,6 +1159,13 @@ impl Authorizer {
     ///
     /// The language spec and formal model give a precise definition of how this
     /// is computed.
+    ///
+    /// The response is deterministic: neither policies nor extension functions
+    /// can read the current time, or any other state besides `r`, the
+    /// `PolicySet`, and the `Entities`. Policies which depend on time should
+    /// compare against a timestamp passed in the request context, so that
+    /// reproducing a decision, or asking for one as of another time, only
+    /// takes a request with the corresponding timestamp.
     /// ```
     /// # use cedar_policy::{Authorizer,Context,Decision,Entities,EntityId,EntityTypeName, EntityUid, Request,PolicySet};
     /// # use std::str::FromStr;
@@ -773,7 +1215,211 @@ impl Authorizer {
     /// assert_eq!(response.decision(), Decision::Allow);
     /// ```
     pub fn is_authorized(&self, r: &Request, p: &PolicySet, e: &Entities) -> Response {
-        self.0.is_authorized(r.0.clone(), &p.ast, &e.0).into()
+        Response::from(self.0.is_authorized(r.0.clone(), &p.ast, &e.0)).with_determining_policies(p)
+    }
+
+    /// Evaluate every policy in `p` for `r` independently, ignoring how the
+    /// results are combined into a decision, and return the outcome for each
+    /// policy, ordered by policy id.
+    ///
+    /// Unlike [`Authorizer::is_authorized`], which only reports the policies
+    /// determining the decision, this reports every policy: whether it is
+    /// satisfied, not satisfied, or errors. In particular, a satisfied
+    /// `permit` is reported even if a `forbid` is also satisfied. The error
+    /// handling chosen with [`Authorizer::with_config`] has no effect.
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, Effect, Entities, EntityUid, PerPolicyOutcome, PolicyId, PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// let policies = PolicySet::from_str(
+    ///     r#"permit(principal, action, resource);
+    ///     forbid(principal, action, resource) when { context.risk > 5 };"#,
+    /// )
+    /// .unwrap();
+    /// let request = Request::new(
+    ///     Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Photo::"trip""#).unwrap()),
+    ///     Context::empty(),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let outcomes = Authorizer::new().evaluate_each(&request, &policies, &Entities::empty());
+    /// assert_eq!(
+    ///     outcomes[0],
+    ///     (PolicyId::new("policy0"), PerPolicyOutcome::Satisfied { effect: Effect::Permit })
+    /// );
+    /// // `context.risk` does not exist
+    /// assert!(matches!(outcomes[1], (_, PerPolicyOutcome::Error(_))));
+    /// ```
+    pub fn evaluate_each(
+        &self,
+        r: &Request,
+        p: &PolicySet,
+        e: &Entities,
+    ) -> Vec<(PolicyId, PerPolicyOutcome)> {
+        self.0
+            .evaluate_each(r.0.clone(), &p.ast, &e.0)
+            .into_iter()
+            .map(|(id, outcome)| (PolicyId::new(id), outcome))
+            .collect()
+    }
+
+    /// Get the set of actions declared in `schema` which `principal` is
+    /// allowed to perform on `resource`, with respect to the given
+    /// `PolicySet` and `Entities`.
+    ///
+    /// The candidate actions are those declared in `schema` whose `appliesTo`
+    /// lists the entity types of both `principal` and `resource`. This runs one
+    /// authorization request per candidate action, each with a context holding
+    /// only the defaults declared for that action (see
+    /// [`Schema::context_defaults`]). Each request is only evaluated against
+    /// the policies whose action scope may match that action, according to the
+    /// action hierarchy in `schema`; an action is skipped entirely if none of
+    /// those policies is a `permit`.
+    pub fn allowed_actions(
+        &self,
+        principal: &EntityUid,
+        resource: &EntityUid,
+        policies: &PolicySet,
+        entities: &Entities,
+        schema: &Schema,
+    ) -> HashSet<EntityUid> {
+        let principal_ty = principal.as_ref().entity_type();
+        let resource_ty = resource.as_ref().entity_type();
+        schema
+            .0
+            .action_ids()
+            .filter(|action| {
+                action.is_applicable_principal_type(principal_ty)
+                    && action.is_applicable_resource_type(resource_ty)
+            })
+            .filter_map(|action| {
+                let slice = policies
+                    .ast
+                    .policies()
+                    .filter(|p| schema.action_scope_may_match(p.action_constraint(), action.name()))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if !slice.iter().any(|p| p.effect() == Effect::Permit) {
+                    return None;
+                }
+                // PANIC SAFETY: every policy in `slice` comes from the same `PolicySet`, so ids are unique and templates agree
+                #[allow(clippy::expect_used)]
+                let slice = ast::PolicySet::try_from_iter(slice)
+                    .expect("policies sliced from a policy set should form a policy set");
+                let request = ast::Request::new_unchecked(
+                    ast::EntityUIDEntry::concrete(principal.as_ref().clone(), None),
+                    ast::EntityUIDEntry::concrete(action.name().clone(), None),
+                    ast::EntityUIDEntry::concrete(resource.as_ref().clone(), None),
+                    Some(schema.fill_context_defaults(action.name(), ast::Context::empty())),
+                );
+                let response = self.0.is_authorized(request, &slice, &entities.0);
+                (response.decision == Decision::Allow)
+                    .then(|| EntityUid::new(action.name().clone()))
+            })
+            .collect()
+    }
+
+    /// Validate the `PolicySet` with `validator` in strict mode and, only if
+    /// validation passes, return the authorization response for `r` with
+    /// respect to the `PolicySet` and `Entities`. If validation fails, no
+    /// policy is evaluated, and the `ValidationResult` is returned instead.
+    ///
+    /// This guards against evaluating policies which could encounter type
+    /// errors at runtime. Note that only the policies are validated; to also
+    /// validate the request and entities, construct them with the schema.
+    ///
+    /// The validation results are kept in `cache` like with
+    /// [`Validator::validate_cached`], so calling this repeatedly with the
+    /// same `Validator` and cache only validates the policies whose text has
+    /// changed.
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, Decision, Entities, EntityUid, PolicySet, Request, Schema, ValidationCache, Validator};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "entity User = { age: Long }; action view appliesTo { principal: User, resource: User };",
+    /// ).unwrap();
+    /// let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// let request = Request::new(
+    ///     Some(alice.clone()),
+    ///     Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+    ///     Some(alice),
+    ///     Context::empty(),
+    ///     Some(&schema),
+    /// ).unwrap();
+    /// let authorizer = Authorizer::new();
+    /// let validator = Validator::new(schema);
+    /// let mut cache = ValidationCache::new();
+    ///
+    /// let valid = PolicySet::from_str("permit(principal, action, resource);").unwrap();
+    /// let response = authorizer
+    ///     .validate_and_authorize(&request, &valid, &Entities::empty(), &validator, &mut cache)
+    ///     .unwrap();
+    /// assert_eq!(response.decision(), Decision::Allow);
+    ///
+    /// let invalid = PolicySet::from_str(
+    ///     r#"permit(principal, action, resource) when { principal.age == "18" };"#,
+    /// ).unwrap();
+    /// let result = authorizer
+    ///     .validate_and_authorize(&request, &invalid, &Entities::empty(), &validator, &mut cache)
+    ///     .unwrap_err();
+    /// assert!(!result.validation_passed());
+    /// ```
+    pub fn validate_and_authorize(
+        &self,
+        r: &Request,
+        p: &PolicySet,
+        e: &Entities,
+        validator: &Validator,
+        cache: &mut ValidationCache,
+    ) -> Result<Response, ValidationResult> {
+        let result = validator.validate_cached(p, ValidationMode::Strict, cache);
+        if result.validation_passed() {
+            Ok(self.is_authorized(r, p, e))
+        } else {
+            Err(result)
+        }
+    }
+
+    /// Returns an authorization response for `r` with respect to the given
+    /// `PolicySet` and `Entities`, like [`Authorizer::is_authorized`], but
+    /// bounding the evaluation work by the given `EvalLimits`.
+    ///
+    /// The limits apply to the evaluation of all policies together. If
+    /// evaluation exceeds them, this returns an [`EvalLimitExceeded`] error
+    /// instead of a decision. Callers evaluating untrusted requests or entities
+    /// will typically want to treat that error as a `Deny`, and report it.
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, Entities, EntityUid, EvalLimits, EvalLimitExceeded, PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// let request = Request::new(
+    ///     Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Album::"trip""#).unwrap()),
+    ///     Context::empty(),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let policy = PolicySet::from_str(
+    ///     r#"permit(principal, action, resource) when { [1, 2, 3].contains(3) };"#,
+    /// )
+    /// .unwrap();
+    /// let authorizer = Authorizer::new();
+    ///
+    /// let limits = EvalLimits::new().with_max_set_size(2);
+    /// let response = authorizer.is_authorized_bounded(&request, &policy, &Entities::empty(), limits);
+    /// assert!(matches!(response, Err(EvalLimitExceeded::SetSize { limit: 2, size: 3 })));
+    /// ```
+    pub fn is_authorized_bounded(
+        &self,
+        r: &Request,
+        p: &PolicySet,
+        e: &Entities,
+        limits: EvalLimits,
+    ) -> Result<Response, EvalLimitExceeded> {
+        self.0
+            .is_authorized_bounded(r.0.clone(), &p.ast, &e.0, limits)
+            .map(|response| Response::from(response).with_determining_policies(p))
     }
 
     /// A partially evaluated authorization request.
@@ -796,14 +1442,27 @@ impl Authorizer {
 }
 
 /// Authorization response returned from the `Authorizer`
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Clone)]
 pub struct Response {
     /// Authorization decision
     pub(crate) decision: Decision,
     /// Diagnostics providing more information on how this decision was reached
     pub(crate) diagnostics: Diagnostics,
+    /// Details of the policies in `diagnostics.reason`, when the policy set
+    /// they come from is known
+    pub(crate) determining_policies: Vec<DeterminingPolicy>,
+}
+
+// `determining_policies` only adds details about the policies in
+// `diagnostics`, so it is not compared.
+impl PartialEq for Response {
+    fn eq(&self, other: &Self) -> bool {
+        self.decision == other.decision && self.diagnostics == other.diagnostics
+    }
 }
 
+impl Eq for Response {}
+
 /// A partially evaluated authorization response.
 /// Splits the results into several categories: satisfied, false, and residual for each policy effect.
 /// Also tracks all the errors that were encountered during evaluation.
@@ -1068,6 +1727,7 @@ impl Response {
         Self {
             decision,
             diagnostics: Diagnostics { reason, errors },
+            determining_policies: Vec::new(),
         }
     }
 
@@ -1088,6 +1748,7 @@ impl From<authorizer::Response> for Response {
         Self {
             decision: a.decision,
             diagnostics: a.diagnostics.into(),
+            determining_policies: Vec::new(),
         }
     }
 }
@@ -1135,6 +1796,68 @@ impl Validator {
         Self(cedar_policy_validator::Validator::new(schema.0))
     }
 
+    /// Set whether validation treats the `context` of every action as a
+    /// closed record, reporting an error for every access to a `context`
+    /// attribute, with `.` or `has`, which is not declared in the context of
+    /// an action the policy applies to. This catches misspelled attributes in
+    /// `has` tests, which are otherwise just `false`. The default is `false`.
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationMode, Validator};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "entity User; action view appliesTo { principal: User, resource: User, context: { mfa: Bool } };",
+    /// ).unwrap();
+    /// let pset = PolicySet::from_str(r#"
+    ///     permit(principal, action, resource) when { context has mfaa && context.mfaa };
+    /// "#).unwrap();
+    /// let validator = Validator::new(schema);
+    /// assert!(validator.validate(&pset, ValidationMode::Strict).validation_passed());
+    /// let validator = validator.with_closed_context(true);
+    /// let result = validator.validate(&pset, ValidationMode::Strict);
+    /// assert_eq!(
+    ///     result.validation_errors().next().unwrap().to_string(),
+    ///     r#"attribute `mfaa` is not declared in the context of action `Action::"view"`"#,
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_closed_context(self, closed: bool) -> Self {
+        Self(self.0.with_closed_context(closed))
+    }
+
+    /// Set whether a call to a function which is not a known extension
+    /// function is reported as a warning instead of an error. Use this to
+    /// validate policies parsed with
+    /// [`PolicySet::from_str_allowing_unknown_functions`]. The call is an
+    /// error whenever it is evaluated, and the rest of the policy is still
+    /// validated. The default is `false`.
+    ///
+    /// This weakens the guarantees of validation: a policy which validates
+    /// may error when evaluated, and a misspelled function name is no longer
+    /// caught. Only use this during a controlled migration to a build of
+    /// Cedar with more extensions enabled.
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationMode, Validator};
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "entity User = { created: String }; action view appliesTo { principal: User, resource: User };",
+    /// ).unwrap();
+    /// let (pset, _) = PolicySet::from_str_allowing_unknown_functions(r#"
+    ///     permit(principal, action, resource) when { datetime("2024-10-15") == resource.created };
+    /// "#).unwrap();
+    /// let validator = Validator::new(schema);
+    /// assert!(!validator.validate(&pset, ValidationMode::Strict).validation_passed());
+    /// let validator = validator.with_unknown_functions_as_warnings(true);
+    /// let result = validator.validate(&pset, ValidationMode::Strict);
+    /// assert!(result.validation_passed());
+    /// assert_eq!(
+    ///     result.validation_warnings().next().unwrap().warning_kind().to_string(),
+    ///     "`datetime` is not a known extension function, so calling it is an error when evaluated",
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_unknown_functions_as_warnings(self, allow: bool) -> Self {
+        Self(self.0.with_unknown_functions_as_warnings(allow))
+    }
+
     /// Validate all policies in a policy set, collecting all validation errors
     /// found into the returned `ValidationResult`. Each error is returned together with the
     /// policy id of the policy where the error was found. If a policy id
@@ -1145,6 +1868,93 @@ impl Validator {
     pub fn validate(&self, pset: &PolicySet, mode: ValidationMode) -> ValidationResult {
         ValidationResult::from(self.0.validate(&pset.ast, mode.into()))
     }
+
+    /// Validate all policies in a policy set like [`Validator::validate`],
+    /// reusing the results in `cache` for policies and templates whose text
+    /// hasn't changed since they were last validated with it. The result is
+    /// the same as `validate` would return.
+    ///
+    /// Afterwards, `cache` holds the results for exactly the policies in
+    /// `pset`. Using the cache with a different schema, a different `mode`, or
+    /// a `Validator` with different options discards all of its entries.
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationCache, ValidationMode, Validator};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "entity User = { age: Long }; action view appliesTo { principal: User, resource: User };",
+    /// ).unwrap();
+    /// let validator = Validator::new(schema);
+    /// let mut cache = ValidationCache::new();
+    /// let pset = PolicySet::from_str(
+    ///     "permit(principal, action, resource) when { principal.age > 18 };",
+    /// ).unwrap();
+    /// let result = validator.validate_cached(&pset, ValidationMode::Strict, &mut cache);
+    /// assert!(result.validation_passed());
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    pub fn validate_cached(
+        &self,
+        pset: &PolicySet,
+        mode: ValidationMode,
+        cache: &mut ValidationCache,
+    ) -> ValidationResult {
+        ValidationResult::from(self.0.validate_cached(&pset.ast, mode.into(), &mut cache.0))
+    }
+
+    /// Get the validation errors which strict validation finds in `pset` but
+    /// permissive validation does not. These are the errors which must be
+    /// fixed before switching from permissive to strict validation. Errors
+    /// are matched between the two modes by their policy id and kind.
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, Validator};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "entity User = { age: Long }; action view appliesTo { principal: User, resource: User };",
+    /// ).unwrap();
+    /// let validator = Validator::new(schema);
+    /// let pset = PolicySet::from_str(r#"
+    ///     permit(principal, action, resource) when { principal.age == "18" };
+    ///     permit(principal, action, resource) when { principal.name == "alice" };
+    /// "#).unwrap();
+    /// let delta = validator.validation_delta(&pset);
+    /// assert_eq!(delta.len(), 1);
+    /// assert_eq!(delta[0].location().policy_id().to_string(), "policy0");
+    /// ```
+    pub fn validation_delta(&self, pset: &PolicySet) -> Vec<ValidationError> {
+        self.0
+            .validation_delta(&pset.ast)
+            .into_iter()
+            .map(ValidationError::from)
+            .collect()
+    }
+}
+
+/// The results of validating individual policies, kept between calls to
+/// [`Validator::validate_cached`] so that policies which haven't changed
+/// aren't validated again.
+#[derive(Debug, Default)]
+pub struct ValidationCache(cedar_policy_validator::ValidationCache);
+
+impl ValidationCache {
+    /// Construct an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of policies with cached results
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Are there no cached results
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Discard all cached results
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
 }
 
 /// Contains all the type information used to construct a `Schema` that can be
@@ -1240,6 +2050,7 @@ impl TryInto<Schema> for SchemaFragment {
     fn try_into(self) -> Result<Schema, Self::Error> {
         Ok(Schema(
             cedar_policy_validator::ValidatorSchema::from_schema_fragments([self.value])?,
+            self.lossless,
         ))
     }
 }
@@ -1262,9 +2073,23 @@ impl FromStr for SchemaFragment {
 }
 
 /// Object containing schema information used by the validator.
-#[repr(transparent)]
-#[derive(Debug, Clone, RefCast)]
-pub struct Schema(pub(crate) cedar_policy_validator::ValidatorSchema);
+///
+/// Two schemas are equal if they declare the same entity types, actions, and
+/// types, even if they were written differently.
+#[derive(Debug, Clone)]
+pub struct Schema(
+    pub(crate) cedar_policy_validator::ValidatorSchema,
+    /// The schema as written, used to convert it back into either format
+    cedar_policy_validator::SchemaFragment,
+);
+
+impl PartialEq for Schema {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Schema {}
 
 impl FromStr for Schema {
     type Err = SchemaError;
@@ -1276,10 +2101,21 @@ impl FromStr for Schema {
     /// found to not be a valid attribute name according to the Cedar
     /// grammar.
     fn from_str(schema_src: &str) -> Result<Self, Self::Err> {
-        Ok(Self(schema_src.parse()?))
+        Self::from_lossless(serde_json::from_str(schema_src)?)
     }
 }
 
+/// Internal helper function to get the names of the specified entity types in
+/// `types`, skipping the unspecified entity type
+fn specified_entity_type_names<'a>(
+    types: impl Iterator<Item = &'a ast::EntityType>,
+) -> impl Iterator<Item = &'a EntityTypeName> {
+    types.filter_map(|ty| match ty {
+        ast::EntityType::Specified(name) => Some(EntityTypeName::ref_cast(name)),
+        ast::EntityType::Unspecified => None,
+    })
+}
+
 impl Schema {
     /// Create a `Schema` from multiple `SchemaFragment`. The individual
     /// fragments may references entity types that are not declared in that
@@ -1288,58 +2124,417 @@ impl Schema {
     pub fn from_schema_fragments(
         fragments: impl IntoIterator<Item = SchemaFragment>,
     ) -> Result<Self, SchemaError> {
+        let (values, lossless): (Vec<_>, Vec<_>) =
+            fragments.into_iter().map(|f| (f.value, f.lossless)).unzip();
         Ok(Self(
-            cedar_policy_validator::ValidatorSchema::from_schema_fragments(
-                fragments.into_iter().map(|f| f.value),
-            )?,
+            cedar_policy_validator::ValidatorSchema::from_schema_fragments(values)?,
+            cedar_policy_validator::SchemaFragment::merge(lossless),
         ))
     }
 
     /// Create a `Schema` from a JSON value (which should be an object of the
     /// shape required for Cedar schemas).
     pub fn from_json_value(json: serde_json::Value) -> Result<Self, SchemaError> {
+        Self::from_lossless(cedar_policy_validator::SchemaFragment::from_json_value(
+            json,
+        )?)
+    }
+
+    /// Create a `Schema` directly from a file.
+    pub fn from_file(file: impl std::io::Read) -> Result<Self, SchemaError> {
+        Self::from_lossless(cedar_policy_validator::SchemaFragment::from_file(file)?)
+    }
+
+    /// Parse the schema from a reader
+    pub fn from_file_natural(
+        file: impl std::io::Read,
+    ) -> Result<(Self, impl Iterator<Item = SchemaWarning>), HumanSchemaError> {
+        let (lossless, warnings) = cedar_policy_validator::SchemaFragment::from_file_natural(file)?;
+        Ok((Self::from_lossless(lossless)?, warnings))
+    }
+
+    /// Parse the schema from a string
+    pub fn from_str_natural(
+        src: &str,
+    ) -> Result<(Self, impl Iterator<Item = SchemaWarning>), HumanSchemaError> {
+        let (lossless, warnings) = cedar_policy_validator::SchemaFragment::from_str_natural(src)?;
+        Ok((Self::from_lossless(lossless)?, warnings))
+    }
+
+    /// Construct a `Schema` from the schema as written
+    fn from_lossless(
+        lossless: cedar_policy_validator::SchemaFragment,
+    ) -> Result<Self, SchemaError> {
         Ok(Self(
-            cedar_policy_validator::ValidatorSchema::from_json_value(
-                json,
-                Extensions::all_available(),
-            )?,
+            cedar_policy_validator::ValidatorSchema::try_from(lossless.clone())?,
+            lossless,
+        ))
+    }
+
+    /// Serialize this schema in the JSON schema format.
+    ///
+    /// The result declares the same entity types, actions, and common types
+    /// as this schema, in the same namespaces, and common types are still
+    /// referred to by name. A schema constructed from multiple fragments is
+    /// serialized as a single schema. Neither comments nor the order of the
+    /// declarations are preserved.
+    /// ```
+    /// # use cedar_policy::Schema;
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "type Name = String; entity User = { name: Name };",
+    /// )
+    /// .unwrap();
+    /// let json = schema.to_json_string().unwrap();
+    /// assert_eq!(Schema::from_str(&json).unwrap(), schema);
+    /// ```
+    pub fn to_json_string(&self) -> Result<String, SchemaError> {
+        Ok(serde_json::to_string(&self.1)?)
+    }
+
+    /// Serialize this schema in the human-readable schema format, like
+    /// [`Schema::to_json_string`] does for the JSON schema format.
+    ///
+    /// Some features of the JSON schema format can't be written in the
+    /// human-readable format, and don't round-trip through it:
+    /// - Action attributes (`attributes` of an action) are not written.
+    /// - Records allowing additional attributes (`additionalAttributes`) are
+    ///   written as records without additional attributes.
+    ///
+    /// This fails if some declarations can't be written unambiguously, e.g.,
+    /// if a common type has the same name as an entity type, or if an
+    /// action's context defaults can't be written as `@default` annotations.
+    pub fn to_human_string(&self) -> Result<String, ToHumanSyntaxError> {
+        Ok(self.1.as_natural_schema()?)
+    }
+
+    /// Extract from the schema an `Entities` containing the action entities
+    /// declared in the schema.
+    pub fn action_entities(&self) -> Result<Entities, EntitiesError> {
+        Ok(Entities(self.0.action_entities()?))
+    }
+
+    /// Move every entity type, action, and common type declared in the
+    /// namespace `from`, or in a namespace nested in `from`, into `to`, and
+    /// rewrite every reference to them, including in attribute and context
+    /// types. Names outside of `from` are unchanged.
+    ///
+    /// Fails if a moved declaration has the same name as one already declared
+    /// in `to`. Otherwise, rebasing the result from `to` back to `from` gives
+    /// the original schema, as long as nothing was declared in `to` before.
+    /// Use [`PolicySet::rebase_namespace`] to move policies written against
+    /// this schema along with it.
+    /// ```
+    /// # use cedar_policy::{EntityNamespace, EntityUid, Schema};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(r#"
+    ///     namespace Vendor {
+    ///         entity User;
+    ///         action read appliesTo { principal: User, resource: User };
+    ///     }
+    /// "#).unwrap();
+    /// let from = EntityNamespace::from_str("Vendor").unwrap();
+    /// let to = EntityNamespace::from_str("MyApp::Vendor").unwrap();
+    /// let rebased = schema.rebase_namespace(&from, &to).unwrap();
+    /// let read = EntityUid::from_str(r#"MyApp::Vendor::Action::"read""#).unwrap();
+    /// assert!(rebased.action_entities().unwrap().get(&read).is_some());
+    /// assert_eq!(rebased.rebase_namespace(&to, &from).unwrap(), schema);
+    /// ```
+    pub fn rebase_namespace(
+        &self,
+        from: &EntityNamespace,
+        to: &EntityNamespace,
+    ) -> Result<Self, SchemaError> {
+        Ok(Self(
+            self.0.rebase_namespace(&from.0, &to.0)?,
+            self.1.rebase_namespace(&from.0, &to.0),
         ))
     }
 
-    /// Create a `Schema` directly from a file.
-    pub fn from_file(file: impl std::io::Read) -> Result<Self, SchemaError> {
-        Ok(Self(cedar_policy_validator::ValidatorSchema::from_file(
-            file,
-            Extensions::all_available(),
-        )?))
+    /// Generate an arbitrary `Request` which is valid for this schema: the
+    /// action is declared in the schema, the principal and resource have entity
+    /// types the action applies to, and the context has all the required
+    /// attributes declared for the action, with values of the declared types.
+    ///
+    /// Fails if the schema declares no action with principal and resource
+    /// types it applies to.
+    #[cfg(feature = "arbitrary")]
+    pub fn arbitrary_request(
+        &self,
+        u: &mut arbitrary::Unstructured<'_>,
+    ) -> arbitrary::Result<Request> {
+        self.0.arbitrary_request(u).map(Request)
+    }
+
+    /// Generate arbitrary `Entities` which are valid for this schema: each
+    /// entity has an entity type declared in the schema, all the required
+    /// attributes of that type with values of the declared types, and only
+    /// parents of types it may be a member of. The entity hierarchy is always
+    /// acyclic. The action entities declared in the schema are included.
+    #[cfg(feature = "arbitrary")]
+    pub fn arbitrary_entities(
+        &self,
+        u: &mut arbitrary::Unstructured<'_>,
+    ) -> arbitrary::Result<Entities> {
+        self.0.arbitrary_entities(u).map(Entities)
+    }
+
+    /// Get a `Context` containing the default values declared in the schema
+    /// for optional attributes of the given action's context. Optional
+    /// attributes without a declared default are absent from the returned
+    /// `Context`.
+    ///
+    /// Returns `None` if the action is not declared in the schema.
+    pub fn context_defaults(&self, action: &EntityUid) -> Option<Context> {
+        self.0.get_action_id(action.as_ref())?;
+        Some(Context(self.fill_context_defaults(
+            action.as_ref(),
+            ast::Context::empty(),
+        )))
+    }
+
+    /// Check that the request conforms to this schema: that the types of its
+    /// principal and resource are declared and are ones its action applies
+    /// to, and that its context has the action's context type. This is the
+    /// same check done by [`Request::new`] when it is given a schema.
+    ///
+    /// Only the known parts of a partial request are checked. For instance,
+    /// when the action is unknown, this only checks that the types of the
+    /// principal and resource are declared.
+    /// ```
+    /// # use cedar_policy::{Context, EntityUid, Request, Schema};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "entity User; entity Photo; action view appliesTo { principal: User, resource: Photo, context: { mfa: Bool } };",
+    /// )
+    /// .unwrap();
+    /// let request = Request::new(
+    ///     Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Photo::"pic""#).unwrap()),
+    ///     Context::empty(),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let err = schema.validate_request(&request).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     r#"context is missing the required attribute `mfa` for `Action::"view"`"#
+    /// );
+    /// ```
+    pub fn validate_request(&self, request: &Request) -> Result<(), RequestValidationError> {
+        ast::RequestSchema::validate_request(&self.0, &request.0, Extensions::all_available())
+    }
+
+    /// Get the principal entity types the given action applies to, as
+    /// declared in the action's `appliesTo`.
+    ///
+    /// Returns `None` if the action is not declared in the schema, and an
+    /// empty iterator if it is declared without any principal types.
+    /// ```
+    /// # use cedar_policy::{EntityTypeName, EntityUid, Schema};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "entity User; entity Photo; action view appliesTo { principal: User, resource: Photo };",
+    /// )
+    /// .unwrap();
+    /// let view = EntityUid::from_str(r#"Action::"view""#).unwrap();
+    /// let principals = schema.principals_for_action(&view).unwrap().collect::<Vec<_>>();
+    /// assert_eq!(principals, vec![&EntityTypeName::from_str("User").unwrap()]);
+    ///
+    /// let edit = EntityUid::from_str(r#"Action::"edit""#).unwrap();
+    /// assert!(schema.principals_for_action(&edit).is_none());
+    /// ```
+    pub fn principals_for_action(
+        &self,
+        action: &EntityUid,
+    ) -> Option<impl Iterator<Item = &EntityTypeName>> {
+        self.0
+            .get_action_id(action.as_ref())
+            .map(|action| specified_entity_type_names(action.applicable_principal_types()))
+    }
+
+    /// Get the resource entity types the given action applies to, as
+    /// declared in the action's `appliesTo`.
+    ///
+    /// Returns `None` if the action is not declared in the schema, and an
+    /// empty iterator if it is declared without any resource types.
+    pub fn resources_for_action(
+        &self,
+        action: &EntityUid,
+    ) -> Option<impl Iterator<Item = &EntityTypeName>> {
+        self.0
+            .get_action_id(action.as_ref())
+            .map(|action| specified_entity_type_names(action.applicable_resource_types()))
+    }
+
+    /// Get the actions which are members of the given action, directly or
+    /// transitively, as declared with `memberOf` (`in` in the natural syntax),
+    /// in no particular order. A policy whose scope constrains the action with
+    /// `action in` the given action applies to all of these actions, in
+    /// addition to the given action itself.
+    ///
+    /// Returns `None` if the action is not declared in the schema.
+    /// ```
+    /// # use cedar_policy::{EntityUid, Schema};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "action manage; action edit in [manage]; action rename in [edit];",
+    /// )
+    /// .unwrap();
+    /// let manage = EntityUid::from_str(r#"Action::"manage""#).unwrap();
+    /// let mut members = schema
+    ///     .action_descendants(&manage)
+    ///     .unwrap()
+    ///     .map(ToString::to_string)
+    ///     .collect::<Vec<_>>();
+    /// members.sort();
+    /// assert_eq!(members, vec![r#"Action::"edit""#, r#"Action::"rename""#]);
+    /// ```
+    pub fn action_descendants(
+        &self,
+        action: &EntityUid,
+    ) -> Option<impl Iterator<Item = &EntityUid>> {
+        self.0
+            .get_action_id(action.as_ref())
+            .map(|action| action.descendants().map(EntityUid::ref_cast))
+    }
+
+    /// Compare this schema with a `newer` version of it, classifying each
+    /// change between them as compatible or breaking. A change is breaking if
+    /// a policy which validates against this schema may fail to validate
+    /// against `newer`, or if entities or requests which are valid for this
+    /// schema may be invalid for `newer`. For instance, removing an entity
+    /// type, action, or attribute, changing the type of an attribute, or
+    /// making an optional attribute required are breaking, while adding an
+    /// entity type, action, or optional attribute is compatible.
+    /// ```
+    /// # use cedar_policy::Schema;
+    /// let (older, _) = Schema::from_str_natural(
+    ///     "entity User = { name: String, age?: Long }; action view appliesTo { principal: User, resource: User };",
+    /// )
+    /// .unwrap();
+    /// let (newer, _) = Schema::from_str_natural(
+    ///     "entity User = { name: String, age: Long }; action view appliesTo { principal: User, resource: User };",
+    /// )
+    /// .unwrap();
+    /// let report = older.compatibility(&newer);
+    /// assert!(!report.is_compatible());
+    /// assert_eq!(
+    ///     report.breaking_changes().map(ToString::to_string).collect::<Vec<_>>(),
+    ///     vec!["attribute `age` of entity type `User` is now required"]
+    /// );
+    /// ```
+    pub fn compatibility(&self, newer: &Self) -> CompatibilityReport {
+        self.0.compatibility(&newer.0)
+    }
+
+    /// Get the actions declared in the schema which apply to principals of
+    /// type `principal_ty` and resources of type `resource_ty`, i.e., whose
+    /// `appliesTo` lists both types, in no particular order.
+    ///
+    /// Cedar has no subtyping between entity types: `memberOfTypes` constrains
+    /// which entities may be parents of which, but an entity of type `User`
+    /// which is a member of some `Group` is still not a `Group`. So an action
+    /// whose `appliesTo` lists `Group` principals is not returned for `User`
+    /// principals, just as the validator rejects such requests.
+    /// ```
+    /// # use cedar_policy::{EntityTypeName, EntityUid, Schema};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     r#"
+    ///     entity User; entity Document;
+    ///     action read, write appliesTo { principal: User, resource: Document };
+    ///     action invite appliesTo { principal: User, resource: User };
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// let user = EntityTypeName::from_str("User").unwrap();
+    /// let document = EntityTypeName::from_str("Document").unwrap();
+    /// let mut actions = schema
+    ///     .applicable_actions(&user, &document)
+    ///     .map(ToString::to_string)
+    ///     .collect::<Vec<_>>();
+    /// actions.sort();
+    /// assert_eq!(actions, vec![r#"Action::"read""#, r#"Action::"write""#]);
+    /// ```
+    pub fn applicable_actions(
+        &self,
+        principal_ty: &EntityTypeName,
+        resource_ty: &EntityTypeName,
+    ) -> impl Iterator<Item = &EntityUid> {
+        let principal_ty = ast::EntityType::Specified(principal_ty.as_ref().clone());
+        let resource_ty = ast::EntityType::Specified(resource_ty.as_ref().clone());
+        self.0
+            .action_ids()
+            .filter(move |action| {
+                action.is_applicable_principal_type(&principal_ty)
+                    && action.is_applicable_resource_type(&resource_ty)
+            })
+            .map(|action| EntityUid::ref_cast(action.name()))
     }
 
-    /// Parse the schema from a reader
-    pub fn from_file_natural(
-        file: impl std::io::Read,
-    ) -> Result<(Self, impl Iterator<Item = SchemaWarning>), HumanSchemaError> {
-        let (schema, warnings) = cedar_policy_validator::ValidatorSchema::from_file_natural(
-            file,
-            Extensions::all_available(),
-        )?;
-        Ok((Self(schema), warnings))
+    /// Internal helper function to fill in the declared context defaults of
+    /// `action` for any attributes missing from `context`. The `context` is
+    /// returned unchanged if the action is not declared in the schema.
+    fn fill_context_defaults(
+        &self,
+        action: &ast::EntityUID,
+        context: ast::Context,
+    ) -> ast::Context {
+        match self.0.get_action_id(action) {
+            Some(action) => context.with_defaults(
+                action
+                    .context_defaults()
+                    .map(|(attr, value)| (attr.clone(), value.clone())),
+            ),
+            None => context,
+        }
     }
 
-    /// Parse the schema from a string
-    pub fn from_str_natural(
-        src: &str,
-    ) -> Result<(Self, impl Iterator<Item = SchemaWarning>), HumanSchemaError> {
-        let (schema, warnings) = cedar_policy_validator::ValidatorSchema::from_str_natural(
-            src,
-            Extensions::all_available(),
-        )?;
-        Ok((Self(schema), warnings))
+    /// Internal helper function to determine whether a policy with the given
+    /// action scope constraint may apply to `action`, using the action
+    /// hierarchy declared in the schema.
+    fn action_scope_may_match(
+        &self,
+        constraint: &ast::ActionConstraint,
+        action: &ast::EntityUID,
+    ) -> bool {
+        match constraint {
+            ast::ActionConstraint::Any => true,
+            ast::ActionConstraint::Eq(euid) => euid.as_ref() == action,
+            ast::ActionConstraint::In(euids) => euids.iter().any(|euid| {
+                euid.as_ref() == action
+                    || self
+                        .0
+                        .get_action_id(euid)
+                        .is_some_and(|group| group.descendants().contains(action))
+            }),
+        }
     }
 
-    /// Extract from the schema an `Entities` containing the action entities
-    /// declared in the schema.
-    pub fn action_entities(&self) -> Result<Entities, EntitiesError> {
-        Ok(Entities(self.0.action_entities()?))
+    /// Internal helper function to determine whether every action satisfying
+    /// the action scope constraint `premise` also satisfies `conclusion`,
+    /// using the action hierarchy declared in the schema.
+    fn action_scope_implies(
+        &self,
+        premise: &ast::ActionConstraint,
+        conclusion: &ast::ActionConstraint,
+    ) -> bool {
+        match premise {
+            ast::ActionConstraint::Any => matches!(conclusion, ast::ActionConstraint::Any),
+            ast::ActionConstraint::Eq(euid) => self.action_scope_may_match(conclusion, euid),
+            // `action in euid` is satisfied by `euid` and its descendants. An
+            // action missing from the schema could have any descendants.
+            ast::ActionConstraint::In(euids) => euids.iter().all(|euid| {
+                self.action_scope_may_match(conclusion, euid)
+                    && self.0.get_action_id(euid).is_some_and(|group| {
+                        group
+                            .descendants()
+                            .all(|action| self.action_scope_may_match(conclusion, action))
+                    })
+            }),
+        }
     }
 }
 
@@ -1347,7 +2542,7 @@ impl Schema {
 /// issues found by validation and whether validation succeeds or fails.
 /// Validation succeeds if there are no fatal errors. There may still be
 /// non-fatal warnings present when validation passes.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ValidationResult {
     validation_errors: Vec<ValidationError>,
     validation_warnings: Vec<ValidationWarning>,
@@ -1564,7 +2759,67 @@ impl FromStr for PolicySet {
     /// See [`Policy`] for more.
     fn from_str(policies: &str) -> Result<Self, Self::Err> {
         let (texts, pset) = parser::parse_policyset_and_also_return_policy_text(policies)?;
-        // PANIC SAFETY: By the invariant on `parse_policyset_and_also_return_policy_text(policies)`, every `PolicyId` in `pset.policies()` occurs as a key in `text`.
+        Ok(Self::from_texts_and_ast(&texts, pset))
+    }
+}
+
+impl PolicySet {
+    /// Create a policy set from multiple statements, like [`PolicySet::from_str`],
+    /// except that a policy or template with an `@id("...")` annotation gets the
+    /// annotation's value as its id. Policies and templates without an `@id`
+    /// annotation default to "policy*" ids based on their position, as in
+    /// [`PolicySet::from_str`].
+    ///
+    /// It is a parse error for two policies or templates to end up with the
+    /// same id, whether both ids come from annotations or one is a default id.
+    pub fn from_str_with_ids(policies: &str) -> Result<Self, ParseErrors> {
+        let (texts, pset) =
+            parser::parse_policyset_with_annotated_ids_and_also_return_policy_text(policies)?;
+        Ok(Self::from_texts_and_ast(&texts, pset))
+    }
+
+    /// Create a policy set from multiple statements, like [`PolicySet::from_str`],
+    /// except that the policies are first checked against `limits`, without
+    /// parsing them. This is useful for untrusted policies, as, e.g., deeply
+    /// nested expressions are rejected rather than overflowing the stack.
+    /// ```
+    /// # use cedar_policy::{ParseLimits, PolicySet};
+    /// let limits = ParseLimits::new().with_max_depth(5);
+    /// let policies = "permit(principal, action, resource) when { (1 + (2 + 3)) == 6 };";
+    /// assert!(PolicySet::from_str_with_limits(policies, limits).is_ok());
+    ///
+    /// let policies = "permit(principal, action, resource) when { (1 + (2 + (3 + 4))) == 10 };";
+    /// assert!(PolicySet::from_str_with_limits(policies, limits).is_err());
+    /// ```
+    pub fn from_str_with_limits(policies: &str, limits: ParseLimits) -> Result<Self, ParseErrors> {
+        limits.check(policies)?;
+        Self::from_str(policies)
+    }
+
+    /// Create a policy set from multiple statements, like [`PolicySet::from_str`],
+    /// except that a call to a function which is not a known extension
+    /// function, e.g., one from an extension which is not enabled in this
+    /// build, is not a parse error. The call is kept in the policy, and is an
+    /// error whenever it is evaluated. The errors for these calls are returned
+    /// alongside the policy set, as warnings.
+    ///
+    /// This accepts misspelled function names too, so it should only be used
+    /// during a controlled migration to a build of Cedar with more extensions
+    /// enabled. See [`Validator::with_unknown_functions_as_warnings`] to
+    /// validate the resulting policies.
+    pub fn from_str_allowing_unknown_functions(
+        policies: &str,
+    ) -> Result<(Self, ParseErrors), ParseErrors> {
+        let (texts, pset, unknown_functions) =
+            parser::parse_policyset_allowing_unknown_functions(policies)?;
+        Ok((Self::from_texts_and_ast(&texts, pset), unknown_functions))
+    }
+
+    /// Pair each policy and template in `pset` with its original text.
+    ///
+    /// INVARIANT: every `PolicyId` in `pset` must occur as a key in `texts`.
+    fn from_texts_and_ast(texts: &HashMap<ast::PolicyID, &str>, pset: ast::PolicySet) -> Self {
+        // PANIC SAFETY: By the invariant on `from_texts_and_ast`, every `PolicyId` in `pset.policies()` occurs as a key in `text`.
         #[allow(clippy::expect_used)]
         let policies = pset.policies().map(|p|
             (
@@ -1580,15 +2835,13 @@ impl FromStr for PolicySet {
                 Template { lossless: LosslessPolicy::policy_or_template_text(*texts.get(t.id()).expect("internal invariant violation: template id exists in asts but not ests")), ast: t.clone() }
             )
         ).collect();
-        Ok(Self {
+        Self {
             ast: pset,
             policies,
             templates,
-        })
+        }
     }
-}
 
-impl PolicySet {
     /// Build the policy set AST from the EST
     fn from_est(est: est::PolicySet) -> Result<Self, PolicySetError> {
         let mut pset = Self::default();
@@ -1775,6 +3028,324 @@ impl PolicySet {
             )
     }
 
+    /// Get the ids of the policies linked to the `Template` with id
+    /// `template_id`, ordered by id.
+    ///
+    /// Unlike [`PolicySet::get_linked_policies`], this does not error if there
+    /// is no such template; the result is just empty.
+    pub fn template_links(&self, template_id: &PolicyId) -> Vec<PolicyId> {
+        let mut links = self
+            .policies()
+            .filter(|policy| policy.template_id() == Some(template_id))
+            .map(|policy| policy.id().clone())
+            .collect::<Vec<_>>();
+        links.sort_by_cached_key(ToString::to_string);
+        links
+    }
+
+    /// Get the policies linked to the `Template` with id `template_id`,
+    /// ordered by id.
+    ///
+    /// Returns an error if `template_id` is the id of a static policy, or of
+    /// no policy at all.
+    pub fn linked_policies(
+        &self,
+        template_id: &PolicyId,
+    ) -> Result<impl Iterator<Item = &Policy>, PolicySetError> {
+        if !self.templates.contains_key(template_id) {
+            return Err(if self.policies.contains_key(template_id) {
+                PolicySetError::ExpectedTemplate
+            } else {
+                PolicySetError::TemplateNonexistentError(template_id.clone())
+            });
+        }
+        let mut links = self
+            .policies()
+            .filter(|policy| policy.template_id() == Some(template_id))
+            .collect::<Vec<_>>();
+        links.sort_by_cached_key(|policy| policy.id().to_string());
+        Ok(links.into_iter())
+    }
+
+    /// Fold the constant sub-expressions in the `when` and `unless`
+    /// conditions of every policy and template in the `PolicySet`, and remove
+    /// `if`, `&&` and `||` branches which can never be taken. For example,
+    /// `when { true && resource.public }` becomes `when { resource.public }`.
+    ///
+    /// The optimized policies give the same result as the originals for every
+    /// request and set of entities, including evaluation errors: a constant
+    /// sub-expression which errors is left as it is.
+    ///
+    /// Policies which change are re-rendered from their new AST, so their
+    /// text (as returned by `Display` and `to_json()`) no longer follows the
+    /// original formatting. Unchanged policies are kept as they were.
+    /// ```
+    /// # use cedar_policy::{PolicyId, PolicySet};
+    /// let mut policies: PolicySet = r#"
+    ///     permit(principal, action, resource) when { true && resource.public };
+    /// "#.parse().unwrap();
+    /// policies.optimize();
+    /// let policy = policies.policy(&PolicyId::new("policy0")).unwrap();
+    /// assert!(policy.to_string().contains("when {\n  resource[\"public\"]\n}"));
+    /// ```
+    pub fn optimize(&mut self) {
+        let extensions = Extensions::all_available();
+        let mut optimized = Self::new();
+        // PANIC SAFETY: the optimized policies and templates have the same ids
+        // and slots as the ones already in this `PolicySet`
+        #[allow(clippy::expect_used)]
+        {
+            for template in self.templates.values() {
+                let ast = fold_template_constants(&template.ast, &extensions);
+                let template = if ast
+                    .non_scope_constraints()
+                    .eq_shape(template.ast.non_scope_constraints())
+                {
+                    template.clone()
+                } else {
+                    Template {
+                        lossless: LosslessPolicy::policy_or_template_text(ast.to_string()),
+                        ast,
+                    }
+                };
+                optimized
+                    .add_template(template)
+                    .expect("template ids are unique");
+            }
+            for policy in self.policies.values() {
+                if let Some(template_id) = policy.template_id() {
+                    optimized
+                        .link(
+                            template_id.clone(),
+                            policy.id().clone(),
+                            policy.template_links().unwrap_or_default(),
+                        )
+                        .expect("links are to existing templates, with the same slots");
+                } else {
+                    let ast = fold_template_constants(policy.ast.template(), &extensions);
+                    let policy = if ast
+                        .non_scope_constraints()
+                        .eq_shape(policy.ast.non_scope_constraints())
+                    {
+                        policy.clone()
+                    } else {
+                        let ast = ast::StaticPolicy::try_from(ast)
+                            .expect("folding a static policy doesn't introduce slots");
+                        Policy::from_ast(ast.into())
+                    };
+                    optimized.add(policy).expect("policy ids are unique");
+                }
+            }
+        }
+        *self = optimized;
+    }
+
+    /// Rewrite every reference to an entity type or action in the namespace
+    /// `from`, or in a namespace nested in `from`, so that it refers to the
+    /// same name in `to` instead. This covers the scope and conditions of
+    /// every policy and template, and the values of template links. Policy
+    /// and template ids are unchanged.
+    ///
+    /// This is the counterpart of [`Schema::rebase_namespace`]: policies
+    /// valid for a schema are valid for the schema rebased the same way.
+    /// Rebasing the result from `to` back to `from` gives the original
+    /// policies, as long as they did not refer to any name in `to` already.
+    ///
+    /// Rebased policies and templates are re-rendered from their new AST, so
+    /// their text no longer follows the original formatting.
+    /// ```
+    /// # use cedar_policy::{EntityNamespace, PolicyId, PolicySet};
+    /// # use std::str::FromStr;
+    /// let policies: PolicySet = r#"
+    ///     permit(principal is Vendor::User, action == Vendor::Action::"read", resource)
+    ///     when { resource.owner == Vendor::User::"alice" };
+    /// "#.parse().unwrap();
+    /// let rebased = policies.rebase_namespace(
+    ///     &EntityNamespace::from_str("Vendor").unwrap(),
+    ///     &EntityNamespace::from_str("MyApp::Vendor").unwrap(),
+    /// );
+    /// let policy = rebased.policy(&PolicyId::new("policy0")).unwrap().to_string();
+    /// assert!(policy.contains(r#"MyApp::Vendor::Action::"read""#));
+    /// assert!(policy.contains(r#"MyApp::Vendor::User::"alice""#));
+    /// assert!(!policy.contains(" Vendor::"));
+    /// ```
+    #[must_use]
+    pub fn rebase_namespace(&self, from: &EntityNamespace, to: &EntityNamespace) -> Self {
+        let (from, to) = (&from.0, &to.0);
+        let mut rebased = Self::new();
+        // PANIC SAFETY: the rebased policies and templates have the same ids
+        // and slots as the ones already in this `PolicySet`
+        #[allow(clippy::expect_used)]
+        {
+            for template in self.templates.values() {
+                let ast = template.ast.rebase_namespace(from, to);
+                rebased
+                    .add_template(Template {
+                        lossless: LosslessPolicy::policy_or_template_text(ast.to_string()),
+                        ast,
+                    })
+                    .expect("template ids are unique");
+            }
+            for policy in self.policies.values() {
+                if let Some(template_id) = policy.template_id() {
+                    let links = policy
+                        .template_links()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|(slot, uid)| {
+                            (
+                                slot,
+                                EntityUid::new(uid.as_ref().rebase_namespace(from, to)),
+                            )
+                        })
+                        .collect();
+                    rebased
+                        .link(template_id.clone(), policy.id().clone(), links)
+                        .expect("links are to existing templates, with the same slots");
+                } else {
+                    let ast = ast::StaticPolicy::try_from(
+                        policy.ast.template().rebase_namespace(from, to),
+                    )
+                    .expect("rebasing a static policy doesn't introduce slots");
+                    rebased
+                        .add(Policy::from_ast(ast.into()))
+                        .expect("policy ids are unique");
+                }
+            }
+        }
+        rebased
+    }
+
+    /// Get the ids of the `Template`s in the `PolicySet` which have no linked
+    /// policies, ordered by id. A template without any links never affects
+    /// authorization, so this is usually a mistake.
+    ///
+    /// See [`PolicySet::unlinked_templates_except_annotated`] to ignore
+    /// templates which are intentionally left unlinked.
+    pub fn unlinked_templates(&self) -> Vec<PolicyId> {
+        let mut unlinked = self
+            .templates
+            .keys()
+            .filter(|id| self.template_links(id).is_empty())
+            .cloned()
+            .collect::<Vec<_>>();
+        unlinked.sort_by_cached_key(ToString::to_string);
+        unlinked
+    }
+
+    /// Like [`PolicySet::unlinked_templates`], but ignoring templates with the
+    /// annotation `key`, whatever its value. This supports marking templates
+    /// which are only meant to be linked elsewhere, e.g., with `@library("...")`.
+    /// ```
+    /// # use cedar_policy::{PolicyId, PolicySet};
+    /// # use std::str::FromStr;
+    /// let policies = PolicySet::from_str_with_ids(
+    ///     r#"
+    ///     @id("unused")
+    ///     permit(principal == ?principal, action, resource);
+    ///     @id("shared")
+    ///     @library("photos")
+    ///     permit(principal, action, resource == ?resource);
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(
+    ///     policies.unlinked_templates_except_annotated("library"),
+    ///     vec![PolicyId::from_str("unused").unwrap()]
+    /// );
+    /// ```
+    pub fn unlinked_templates_except_annotated(&self, key: impl AsRef<str>) -> Vec<PolicyId> {
+        self.unlinked_templates()
+            .into_iter()
+            .filter(|id| self.template_annotation(id, key.as_ref()).is_none())
+            .collect()
+    }
+
+    /// Check every template-linked policy in the `PolicySet` against its
+    /// template and `schema`, reporting each slot of the template which the
+    /// policy leaves unfilled or links to an entity of the wrong type: one not
+    /// declared in `schema`, or one that none of the actions of the template
+    /// apply to. This catches links which have drifted out of date with their
+    /// templates or the schema. The errors are ordered by template id, then by
+    /// policy id, then by slot.
+    /// ```
+    /// # use cedar_policy::{EntityUid, LinkValidationErrorKind, PolicyId, PolicySet, Schema, SlotId};
+    /// # use std::{collections::HashMap, str::FromStr};
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     r#"entity User; entity Photo;
+    ///     action view appliesTo { principal: User, resource: Photo };"#,
+    /// )
+    /// .unwrap();
+    /// let mut policies = PolicySet::from_str_with_ids(
+    ///     r#"@id("template")
+    ///     permit(principal == ?principal, action, resource);"#,
+    /// )
+    /// .unwrap();
+    /// policies
+    ///     .link(
+    ///         PolicyId::from_str("template").unwrap(),
+    ///         PolicyId::from_str("link").unwrap(),
+    ///         HashMap::from([(
+    ///             SlotId::principal(),
+    ///             EntityUid::from_str(r#"Photo::"vacation""#).unwrap(),
+    ///         )]),
+    ///     )
+    ///     .unwrap();
+    /// let errors = policies.validate_links(&schema);
+    /// assert_eq!(errors.len(), 1);
+    /// assert_eq!(errors[0].link_id(), &PolicyId::from_str("link").unwrap());
+    /// assert_eq!(errors[0].slot(), SlotId::principal());
+    /// assert_eq!(errors[0].kind(), LinkValidationErrorKind::MismatchedEntityType);
+    /// ```
+    pub fn validate_links(&self, schema: &Schema) -> Vec<LinkValidationError> {
+        cedar_policy_validator::Validator::new(schema.0.clone())
+            .validate_links(&self.ast)
+            .into_iter()
+            .map(LinkValidationError::from)
+            .collect()
+    }
+
+    /// Find the attributes of each entity type which the policies and
+    /// templates in the `PolicySet` may read, either with `.` or `[]` or by
+    /// testing for them with `has`, given `schema`. Attributes not listed for
+    /// an entity type can be removed from entities of that type without
+    /// changing any authorization decision, e.g., to minimize the entity data
+    /// sent to an authorizer.
+    ///
+    /// The entity type of the entity an attribute is accessed on is the type
+    /// inferred by the typechecker. When that is ambiguous, e.g., for an
+    /// expression which fails to typecheck, the attribute is included for
+    /// every entity type which may have it.
+    /// ```
+    /// # use cedar_policy::{EntityTypeName, PolicySet, Schema};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     r#"entity User = { name: String, age: Long };
+    ///     entity Photo = { owner: User, private: Bool };
+    ///     action view appliesTo { principal: User, resource: Photo };"#,
+    /// )
+    /// .unwrap();
+    /// let policies = PolicySet::from_str(
+    ///     "permit(principal, action, resource) when { resource.owner == principal };",
+    /// )
+    /// .unwrap();
+    /// let accessed = policies.accessed_attributes(&schema);
+    /// let photo = EntityTypeName::from_str("Photo").unwrap();
+    /// assert_eq!(accessed.len(), 1);
+    /// assert!(accessed[&photo].contains("owner"));
+    /// assert!(!accessed[&photo].contains("private"));
+    /// ```
+    pub fn accessed_attributes(
+        &self,
+        schema: &Schema,
+    ) -> HashMap<EntityTypeName, HashSet<SmolStr>> {
+        cedar_policy_validator::Validator::new(schema.0.clone())
+            .accessed_attributes(&self.ast)
+            .into_iter()
+            .map(|(name, attrs)| (EntityTypeName::ref_cast(&name).clone(), attrs))
+            .collect()
+    }
+
     /// Iterate over all the `Policy`s in the `PolicySet`.
     ///
     /// This will include both static and template-linked policies.
@@ -1922,6 +3493,107 @@ impl PolicySet {
             }
         }
     }
+
+    /// Find the `permit` policies in this `PolicySet` which can never grant
+    /// access because a `forbid` policy applies whenever they do. Returns
+    /// `(permit, forbid)` pairs of `PolicyId`s, sorted by the `permit` id and
+    /// then the `forbid` id.
+    ///
+    /// This analysis is sound but incomplete: every pair returned is a real
+    /// shadowing, but some shadowings are not found. A `forbid` policy is only
+    /// found to shadow a `permit` policy when
+    ///   1) the `forbid` policy has no conditions, or only conditions which
+    ///      are the literal `true`, and
+    ///   2) each scope constraint of the `forbid` policy is implied by the
+    ///      corresponding scope constraint of the `permit` policy, without
+    ///      knowing the entity hierarchy. For instance, `principal in
+    ///      Group::"a"` is implied by `principal == Group::"a"` and by
+    ///      `principal is User in Group::"a"`, but not by `principal ==
+    ///      User::"alice"`. For actions, the action hierarchy declared in
+    ///      `schema` is used, so the result is only guaranteed for requests
+    ///      with the action entities from that schema.
+    ///
+    /// The conditions of the `permit` policy are ignored, as they never make
+    /// it apply to more requests. Templates are not analyzed, but
+    /// template-linked policies are.
+    pub fn find_shadowed_permits(&self, schema: &Schema) -> Vec<(PolicyId, PolicyId)> {
+        let (permits, forbids): (Vec<_>, Vec<_>) = self
+            .policies()
+            .partition(|policy| policy.effect() == Effect::Permit);
+        let forbids = forbids
+            .into_iter()
+            .filter(|forbid| is_trivially_true(forbid.ast.non_scope_constraints()))
+            .collect::<Vec<_>>();
+        let mut shadowed = permits
+            .into_iter()
+            .flat_map(|permit| {
+                forbids
+                    .iter()
+                    .filter(move |forbid| {
+                        entity_scope_implies(
+                            permit.ast.principal_constraint().as_inner(),
+                            forbid.ast.principal_constraint().as_inner(),
+                        ) && schema.action_scope_implies(
+                            permit.ast.action_constraint(),
+                            forbid.ast.action_constraint(),
+                        ) && entity_scope_implies(
+                            permit.ast.resource_constraint().as_inner(),
+                            forbid.ast.resource_constraint().as_inner(),
+                        )
+                    })
+                    .map(move |forbid| (permit.id().clone(), forbid.id().clone()))
+            })
+            .collect::<Vec<_>>();
+        shadowed.sort_by_cached_key(|(permit, forbid)| (permit.to_string(), forbid.to_string()));
+        shadowed
+    }
+}
+
+/// Is `expr` the literal `true`, or a conjunction of such literals? The
+/// conditions of a policy without `when` or `unless` clauses are `true`.
+fn is_trivially_true(expr: &ast::Expr) -> bool {
+    match expr.expr_kind() {
+        ast::ExprKind::Lit(ast::Literal::Bool(b)) => *b,
+        ast::ExprKind::And { left, right } => is_trivially_true(left) && is_trivially_true(right),
+        _ => false,
+    }
+}
+
+/// Does every principal (resource) satisfying the scope constraint `premise`
+/// also satisfy `conclusion`, regardless of the entity hierarchy?
+fn entity_scope_implies(
+    premise: &ast::PrincipalOrResourceConstraint,
+    conclusion: &ast::PrincipalOrResourceConstraint,
+) -> bool {
+    use ast::{EntityReference, PrincipalOrResourceConstraint as Constraint};
+    // Slots never appear in the constraints of a static or linked policy, but
+    // we don't know what they would be, so don't claim anything about them.
+    let euid = |eref: &EntityReference| match eref {
+        EntityReference::EUID(euid) => Some(euid.as_ref().clone()),
+        EntityReference::Slot => None,
+    };
+    let is_type = |euid: &Option<ast::EntityUID>, ety: &ast::Name| {
+        euid.as_ref().is_some_and(|euid| match euid.entity_type() {
+            ast::EntityType::Specified(name) => name == ety,
+            ast::EntityType::Unspecified => false,
+        })
+    };
+    match (premise, conclusion) {
+        (_, Constraint::Any) => true,
+        (Constraint::Eq(x), Constraint::Eq(e)) => euid(x).is_some() && euid(x) == euid(e),
+        (Constraint::Eq(x) | Constraint::In(x) | Constraint::IsIn(_, x), Constraint::In(e)) => {
+            euid(x).is_some() && euid(x) == euid(e)
+        }
+        (Constraint::Eq(x), Constraint::Is(ety)) => is_type(&euid(x), ety),
+        (Constraint::Is(t) | Constraint::IsIn(t, _), Constraint::Is(ety)) => t == ety,
+        (Constraint::Eq(x), Constraint::IsIn(ety, e)) => {
+            is_type(&euid(x), ety) && euid(x) == euid(e)
+        }
+        (Constraint::IsIn(t, x), Constraint::IsIn(ety, e)) => {
+            t == ety && euid(x).is_some() && euid(x) == euid(e)
+        }
+        _ => false,
+    }
 }
 
 impl std::fmt::Display for PolicySet {
@@ -2058,6 +3730,53 @@ impl Template {
         self.ast.slots().map(|slot| SlotId::ref_cast(&slot.id))
     }
 
+    /// The distinct slots this `Template` declares, with `?principal` before
+    /// `?resource`. These are exactly the slots [`Template::link`] needs values
+    /// for: a template which only uses `?principal` does not list `?resource`.
+    /// ```
+    /// # use cedar_policy::{SlotId, Template};
+    /// let template = Template::parse(None, "permit(principal in ?principal, action, resource);").unwrap();
+    /// assert_eq!(template.slot_ids().collect::<Vec<_>>(), vec![SlotId::principal()]);
+    /// ```
+    pub fn slot_ids(&self) -> impl Iterator<Item = SlotId> {
+        self.ast
+            .slots()
+            .map(|slot| SlotId::from(slot.id))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    /// Produce the template-linked policy obtained by filling the slots of
+    /// this `Template` with `vals`, without adding it to any `PolicySet`.
+    /// This fails if `vals` is missing a value for a slot in the template, or
+    /// has a value for a slot which is not in the template.
+    ///
+    /// The resulting `Policy` has the same `PolicyId` as this `Template`. Use
+    /// [`Policy::new_id`] to give it a different one.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn link(&self, vals: HashMap<SlotId, EntityUid>) -> Result<Policy, LinkError> {
+        let unwrapped_vals: HashMap<ast::SlotId, ast::EntityUID> = vals
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        let ast = ast::Template::link(
+            Arc::new(self.ast.clone()),
+            self.ast.id().clone(),
+            unwrapped_vals.clone(),
+        )?;
+        // PANIC SAFETY: `lossless.link()` will not fail after `ast::Template::link()` succeeds
+        #[allow(clippy::expect_used)]
+        let lossless = self
+            .lossless
+            .clone()
+            .link(unwrapped_vals.iter().map(|(k, v)| (*k, v)))
+            // As in `PolicySet::link()`, the only error case for
+            // `lossless.link()` is a template with unfilled slots, which
+            // `ast::Template::link()` already rejects.
+            .expect("ast::Template::link() didn't fail above, so this shouldn't fail");
+        Ok(Policy { ast, lossless })
+    }
+
     /// Get the scope constraint on this policy's principal
     pub fn principal_constraint(&self) -> TemplatePrincipalConstraint {
         match self.ast.principal_constraint().as_inner() {
@@ -2362,6 +4081,72 @@ impl Policy {
         }
     }
 
+    /// Clone this `Policy`, replacing every reference to an entity type in
+    /// `mapping` with the type it maps to: in the scope constraints, in `is`
+    /// tests, and in entity literals, including the values of linked slots.
+    /// Entity literals keep their ids. Entity types not in `mapping` are left
+    /// unchanged, as are the id, annotations, and template id of the policy.
+    /// ```
+    /// # use cedar_policy::{EntityTypeName, Policy};
+    /// # use std::collections::HashMap;
+    /// # use std::str::FromStr;
+    /// let policy = Policy::parse(
+    ///     None,
+    ///     r#"permit(principal is Legacy::User, action, resource) when { resource.owner == Legacy::User::"alice" };"#,
+    /// )
+    /// .unwrap();
+    /// let mapping = HashMap::from([(
+    ///     EntityTypeName::from_str("Legacy::User").unwrap(),
+    ///     EntityTypeName::from_str("Corp::Employee").unwrap(),
+    /// )]);
+    /// let renamed = policy.substitute_entity_types(&mapping).to_string();
+    /// assert!(renamed.contains("principal is Corp::Employee"));
+    /// assert!(renamed.contains(r#"Corp::Employee::"alice""#));
+    /// assert!(!renamed.contains("Legacy::User"));
+    /// ```
+    #[must_use]
+    pub fn substitute_entity_types(
+        &self,
+        mapping: &HashMap<EntityTypeName, EntityTypeName>,
+    ) -> Self {
+        let rename = |name: &ast::Name| {
+            mapping
+                .get(EntityTypeName::ref_cast(name))
+                .map_or_else(|| name.clone(), |to| to.as_ref().clone())
+        };
+        let template = self.ast.template().rename_entity_types(&rename);
+        // PANIC SAFETY: renaming entity types doesn't change the slots of the
+        // template, so it is static exactly when this policy is, and the
+        // renamed slot values fill the same slots
+        #[allow(clippy::expect_used)]
+        match self.template_links() {
+            None => {
+                let ast = ast::StaticPolicy::try_from(template)
+                    .expect("renaming a static policy doesn't introduce slots");
+                Self::from_ast(ast.into())
+            }
+            Some(links) => {
+                let template = Template {
+                    lossless: LosslessPolicy::policy_or_template_text(template.to_string()),
+                    ast: template,
+                };
+                let links = links
+                    .into_iter()
+                    .map(|(slot, uid)| {
+                        (
+                            slot,
+                            EntityUid::new(uid.as_ref().rename_entity_types(&rename)),
+                        )
+                    })
+                    .collect();
+                template
+                    .link(links)
+                    .expect("the links fill the same slots as before")
+                    .new_id(self.id().clone())
+            }
+        }
+    }
+
     /// Returns `true` if this is a static policy, `false` otherwise.
     pub fn is_static(&self) -> bool {
         self.ast.is_static()
@@ -2406,6 +4191,43 @@ impl Policy {
         }
     }
 
+    /// Get the actions declared in `schema` which satisfy this policy's action
+    /// scope constraint, expanding action groups using the action hierarchy
+    /// declared in the schema. This is useful for checking that a broad
+    /// policy, e.g., a `forbid` on all actions in a group, does not cover an
+    /// action it was meant to exempt.
+    ///
+    /// Actions which are not declared in the schema are never returned, even
+    /// if the scope constraint names them.
+    /// ```
+    /// # use cedar_policy::{EntityUid, Policy, Schema};
+    /// # use std::collections::HashSet;
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(r#"
+    ///     entity User;
+    ///     action write;
+    ///     action read, edit in write appliesTo { principal: User, resource: User };
+    ///     action delete appliesTo { principal: User, resource: User };
+    /// "#).unwrap();
+    /// let policy = Policy::from_str(
+    ///     r#"forbid(principal, action in Action::"write", resource);"#,
+    /// ).unwrap();
+    /// let expected: HashSet<EntityUid> = [r#"Action::"write""#, r#"Action::"read""#, r#"Action::"edit""#]
+    ///     .into_iter()
+    ///     .map(|s| s.parse().unwrap())
+    ///     .collect();
+    /// assert_eq!(policy.matched_actions(&schema), expected);
+    /// ```
+    pub fn matched_actions(&self, schema: &Schema) -> HashSet<EntityUid> {
+        let constraint = self.ast.template().action_constraint();
+        schema
+            .0
+            .action_ids()
+            .filter(|action| schema.action_scope_may_match(constraint, action.name()))
+            .map(|action| EntityUid::ref_cast(action.name()).clone())
+            .collect()
+    }
+
     /// Get the scope constraint on this policy's resource
     pub fn resource_constraint(&self) -> ResourceConstraint {
         let slot_id = ast::SlotId::resource();
@@ -2875,6 +4697,55 @@ impl RestrictedExpression {
         ))
     }
 
+    /// Serialize this expression as JSON, in the format of entity attribute
+    /// values in the entities JSON format.
+    ///
+    /// Entity uids and extension function calls are written with the
+    /// `__entity` and `__extn` escapes, e.g., `{ "__extn": { "fn": "ip", "arg":
+    /// "10.0.0.1" } }`, rather than the implicit forms which are only
+    /// accepted when parsing with a schema. This makes the JSON self-describing,
+    /// so that [`RestrictedExpression::from_json_value`] gets back exactly this
+    /// expression without a schema.
+    ///
+    /// Errors if the expression contains a record with a key reserved for
+    /// these escapes, or an extension function call without exactly one
+    /// argument, which the JSON format can't represent.
+    /// ```
+    /// # use cedar_policy::RestrictedExpression;
+    /// # use std::str::FromStr;
+    /// let expr = RestrictedExpression::from_str(r#"{ addr: ip("10.0.0.1"), weights: [decimal("0.5")] }"#).unwrap();
+    /// let json = expr.to_json_value().unwrap();
+    /// assert_eq!(
+    ///     json,
+    ///     serde_json::json!({
+    ///         "addr": { "__extn": { "fn": "ip", "arg": "10.0.0.1" } },
+    ///         "weights": [{ "__extn": { "fn": "decimal", "arg": "0.5" } }],
+    ///     })
+    /// );
+    /// let round_tripped = RestrictedExpression::from_json_value(json.clone()).unwrap();
+    /// assert_eq!(round_tripped.to_json_value().unwrap(), json);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn to_json_value(&self) -> Result<serde_json::Value, JsonSerializationError> {
+        let json = CedarValueJson::from_expr(self.0.as_borrowed())?;
+        serde_json::to_value(json).map_err(|e| JsonSerializationError::Serde(e.into()))
+    }
+
+    /// Parse an expression from JSON in the format of entity attribute values
+    /// in the entities JSON format, as produced by
+    /// [`RestrictedExpression::to_json_value`].
+    ///
+    /// Since no schema is available, entity uids and extension function calls
+    /// must use the `__entity` and `__extn` escapes; a JSON object without
+    /// them is parsed as a record.
+    #[allow(clippy::result_large_err)]
+    pub fn from_json_value(json: serde_json::Value) -> Result<Self, JsonDeserializationError> {
+        let json: CedarValueJson =
+            serde_json::from_value(json).map_err(|e| JsonDeserializationError::Serde(e.into()))?;
+        json.into_expr(|| JsonDeserializationErrorContext::RestrictedExpression)
+            .map(Self)
+    }
+
     /// Deconstruct an [`RestrictedExpression`] to get the internal type.
     /// This function is only intended to be used internally.
     #[cfg(test)]
@@ -2904,10 +4775,26 @@ impl FromStr for RestrictedExpression {
     }
 }
 
+impl std::fmt::Display for RestrictedExpression {
+    /// Display the expression in Cedar syntax
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Builder for a [`Request`]
 ///
 /// The default for principal, action, resource, and context fields is Unknown
 /// for partial evaluation.
+///
+/// In particular, leaving the action unknown while setting the principal and
+/// resource asks what the authorization decision is across actions: the
+/// residual policies returned by [`Authorizer::is_authorized_partial`] then
+/// only depend on the action, e.g., `unknown("action") == Action::"view"`.
+/// Comparisons with the action by `in` are resolved with the action hierarchy
+/// in the entities when the response is reauthorized with a concrete action.
+/// To instead list the actions declared in a schema which are allowed, see
+/// [`Authorizer::allowed_actions`].
 #[doc = include_str!("../experimental_warning.md")]
 #[cfg(feature = "partial-eval")]
 #[derive(Debug)]
@@ -3048,12 +4935,21 @@ impl RequestBuilder<UnsetSchema> {
 #[cfg(feature = "partial-eval")]
 impl RequestBuilder<&Schema> {
     /// Create the [`Request`]
+    ///
+    /// Any optional context attributes with a default declared in the schema
+    /// are filled in if missing from the context.
     pub fn build(self) -> Result<Request, RequestValidationError> {
+        let context = match (self.context, self.action.uid()) {
+            (Some(context), Some(action)) => {
+                Some(self.schema.fill_context_defaults(action, context))
+            }
+            (context, _) => context,
+        };
         Ok(Request(ast::Request::new_with_unknowns(
             self.principal,
             self.action,
             self.resource,
-            self.context,
+            context,
             Some(&self.schema.0),
             Extensions::all_available(),
         )?))
@@ -3091,7 +4987,9 @@ impl Request {
     /// a unique entity UID that is not equal to any UID in the store.
     ///
     /// If `schema` is present, this constructor will validate that the
-    /// `Request` complies with the given `schema`.
+    /// `Request` complies with the given `schema`. Any optional context
+    /// attributes with a default declared in the `schema` are filled in if
+    /// missing from `context`.
     pub fn new(
         principal: Option<EntityUid>,
         action: Option<EntityUid>,
@@ -3111,55 +5009,77 @@ impl Request {
             || ast::EntityUID::unspecified_from_eid(ast::Eid::new("resource")),
             EntityUid::into,
         );
+        let context = match schema {
+            Some(schema) => schema.fill_context_defaults(&a, context.0),
+            None => context.0,
+        };
         Ok(Self(ast::Request::new(
             (p, None),
             (a, None),
             (r, None),
-            context.0,
+            context,
             schema.map(|schema| &schema.0),
             Extensions::all_available(),
         )?))
     }
 
-    /// Get the principal component of the request. Returns `None` if the principal is
-    /// "unspecified" (i.e., constructed by passing `None` into the constructor) or
-    /// "unknown" (i.e., constructed using the partial evaluation APIs).
-    pub fn principal(&self) -> Option<&EntityUid> {
-        match self.0.principal() {
-            ast::EntityUIDEntry::Known { euid, .. } => match euid.entity_type() {
-                // INVARIANT: we ensure Concrete-ness here
-                ast::EntityType::Specified(_) => Some(EntityUid::ref_cast(euid.as_ref())),
-                ast::EntityType::Unspecified => None,
-            },
-            ast::EntityUIDEntry::Unknown { .. } => None,
-        }
+    /// Get the principal component of the request, which is
+    /// [`RequestComponent::Unspecified`] if it was constructed by passing `None`
+    /// into the constructor, or [`RequestComponent::Unknown`] if it was left
+    /// unknown using the partial evaluation APIs.
+    pub fn principal(&self) -> RequestComponent {
+        RequestComponent::from_entry(self.0.principal())
+    }
+
+    /// Get the action component of the request, which is
+    /// [`RequestComponent::Unspecified`] if it was constructed by passing `None`
+    /// into the constructor, or [`RequestComponent::Unknown`] if it was left
+    /// unknown using the partial evaluation APIs.
+    pub fn action(&self) -> RequestComponent {
+        RequestComponent::from_entry(self.0.action())
+    }
+
+    /// Get the resource component of the request, which is
+    /// [`RequestComponent::Unspecified`] if it was constructed by passing `None`
+    /// into the constructor, or [`RequestComponent::Unknown`] if it was left
+    /// unknown using the partial evaluation APIs.
+    pub fn resource(&self) -> RequestComponent {
+        RequestComponent::from_entry(self.0.resource())
     }
+}
+
+/// The principal, action, or resource of a [`Request`], as returned by
+/// [`Request::principal`], [`Request::action`], and [`Request::resource`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestComponent {
+    /// The component is the given entity
+    Concrete(EntityUid),
+    /// The component was not specified, i.e., `None` was passed into
+    /// [`Request::new`]
+    Unspecified,
+    /// The component was left unknown for partial evaluation
+    Unknown,
+}
 
-    /// Get the action component of the request. Returns `None` if the action is
-    /// "unspecified" (i.e., constructed by passing `None` into the constructor) or
-    /// "unknown" (i.e., constructed using the partial evaluation APIs).
-    pub fn action(&self) -> Option<&EntityUid> {
-        match self.0.action() {
+impl RequestComponent {
+    fn from_entry(entry: &ast::EntityUIDEntry) -> Self {
+        match entry {
             ast::EntityUIDEntry::Known { euid, .. } => match euid.entity_type() {
                 // INVARIANT: we ensure Concrete-ness here
-                ast::EntityType::Specified(_) => Some(EntityUid::ref_cast(euid.as_ref())),
-                ast::EntityType::Unspecified => None,
+                ast::EntityType::Specified(_) => {
+                    Self::Concrete(EntityUid::new(euid.as_ref().clone()))
+                }
+                ast::EntityType::Unspecified => Self::Unspecified,
             },
-            ast::EntityUIDEntry::Unknown { .. } => None,
+            ast::EntityUIDEntry::Unknown { .. } => Self::Unknown,
         }
     }
 
-    /// Get the resource component of the request. Returns `None` if the resource is
-    /// "unspecified" (i.e., constructed by passing `None` into the constructor) or
-    /// "unknown" (i.e., constructed using the partial evaluation APIs).
-    pub fn resource(&self) -> Option<&EntityUid> {
-        match self.0.resource() {
-            ast::EntityUIDEntry::Known { euid, .. } => match euid.entity_type() {
-                // INVARIANT: we ensure Concrete-ness here
-                ast::EntityType::Specified(_) => Some(EntityUid::ref_cast(euid.as_ref())),
-                ast::EntityType::Unspecified => None,
-            },
-            ast::EntityUIDEntry::Unknown { .. } => None,
+    /// Get the entity, if the component is [`RequestComponent::Concrete`]
+    pub fn concrete(&self) -> Option<&EntityUid> {
+        match self {
+            Self::Concrete(uid) => Some(uid),
+            Self::Unspecified | Self::Unknown => None,
         }
     }
 }
@@ -3204,6 +5124,44 @@ impl Context {
         )?))
     }
 
+    /// Get the value of `key` in this `Context`, or `None` if it has no such
+    /// key or the whole `Context` is unknown. Values are returned in
+    /// evaluated form, e.g., `1 + 2` is returned as `3`.
+    /// ```
+    /// # use cedar_policy::{Context, RestrictedExpression};
+    /// # use std::str::FromStr;
+    /// let context = Context::from_pairs([
+    ///   ("age".to_string(), RestrictedExpression::from_str("18").unwrap()),
+    /// ]).unwrap();
+    /// assert_eq!(context.get("age").unwrap().to_string(), "18");
+    /// assert!(context.get("name").is_none());
+    /// ```
+    pub fn get(&self, key: &str) -> Option<RestrictedExpression> {
+        self.0.get(key).map(|v| {
+            RestrictedExpression(match v {
+                ast::PartialValue::Value(v) => v.into(),
+                // Residuals in a `Context` come from partially evaluating a
+                // restricted expression, so they are restricted expressions
+                ast::PartialValue::Residual(e) => ast::RestrictedExpr::new_unchecked(e),
+            })
+        })
+    }
+
+    /// Iterate over the keys in this `Context`, in sorted order. This is
+    /// empty if the whole `Context` is unknown.
+    /// ```
+    /// # use cedar_policy::{Context, RestrictedExpression};
+    /// # use std::str::FromStr;
+    /// let context = Context::from_pairs([
+    ///   ("name".to_string(), RestrictedExpression::from_str(r#""alice""#).unwrap()),
+    ///   ("age".to_string(), RestrictedExpression::from_str("18").unwrap()),
+    /// ]).unwrap();
+    /// assert_eq!(context.keys().collect::<Vec<_>>(), vec!["age", "name"]);
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(SmolStr::as_str)
+    }
+
     /// Create a `Context` from a string containing JSON (which must be a JSON
     /// object, not any other JSON type, or you will get an error here).
     /// JSON here must use the `__entity` and `__extn` escapes for entity
@@ -3516,6 +5474,31 @@ impl std::fmt::Display for EvalResult {
 /// Evaluates an expression.
 /// If evaluation results in an error (e.g., attempting to access a non-existent Entity or Record,
 /// passing the wrong number of arguments to a function etc.), that error is returned as a String
+///
+/// To evaluate an expression given as Cedar source, parse it with
+/// [`Expression::from_str`] first:
+/// ```
+/// # use cedar_policy::{eval_expression, Context, Entities, EntityUid, EvalResult, Expression, Request};
+/// # use std::str::FromStr;
+/// let entities = Entities::from_json_str(
+///     r#"[{ "uid": { "type": "User", "id": "alice" }, "attrs": { "department": "eng" }, "parents": [] }]"#,
+///     None,
+/// )
+/// .unwrap();
+/// let request = Request::new(
+///     Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+///     None,
+///     None,
+///     Context::empty(),
+///     None,
+/// )
+/// .unwrap();
+/// let expr = Expression::from_str(r#"principal.department == "eng""#).unwrap();
+/// assert_eq!(
+///     eval_expression(&request, &entities, &expr).unwrap(),
+///     EvalResult::Bool(true)
+/// );
+/// ```
 pub fn eval_expression(
     request: &Request,
     entities: &Entities,
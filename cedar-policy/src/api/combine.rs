@@ -0,0 +1,280 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines how the [`Response`]s and [`Diagnostics`] of several
+//! authorizations are combined into one, as in [`Response::combine`] and
+//! [`Diagnostics::merge`].
+
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use cedar_policy_core::ast;
+
+use super::{AuthorizationError, Decision, Diagnostics, Effect, PolicyId, Response};
+
+/// How [`Response::combine`] reaches a decision from the decisions of the
+/// responses it combines.
+///
+/// A response *applies* to the request if any policy is satisfied, i.e., if
+/// it has any reasons. A response which doesn't apply is the default `Deny`
+/// of a policy set with nothing to say about the request, so it has no say in
+/// the combined decision. If no response applies, the decision is `Deny`.
+/// These are the same semantics as those of the corresponding
+/// [`LayerCombination`](super::LayerCombination)s across layers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombineStrategy {
+    /// The decision is `Deny` if any of the responses which apply is `Deny`,
+    /// and otherwise `Allow` if any response applies
+    DenyOverrides,
+    /// The decision is `Allow` if any of the responses which apply is
+    /// `Allow`, and `Deny` otherwise
+    PermitOverrides,
+}
+
+impl Response {
+    /// Combine the `responses` of authorizing the same request against
+    /// several policy sets, e.g., ones owned by different teams, into one
+    /// response whose decision is reached by `strategy`. Combining no
+    /// responses, or only responses which don't apply to the request, results
+    /// in `Deny`, whatever the strategy, since nothing allowed the request.
+    ///
+    /// The reasons of the combined response are those of the responses whose
+    /// decision is the combined decision, and its errors are those of all the
+    /// responses. See [`Response::combine_labeled`] to tell which response
+    /// each of them comes from.
+    /// ```
+    /// # use cedar_policy::{CombineStrategy, Decision, PolicyId, Response};
+    /// # use std::collections::HashSet;
+    /// let allow = Response::new(
+    ///     Decision::Allow,
+    ///     HashSet::from([PolicyId::new("permit_all")]),
+    ///     Vec::new(),
+    /// );
+    /// let deny = Response::new(
+    ///     Decision::Deny,
+    ///     HashSet::from([PolicyId::new("forbid_guests")]),
+    ///     Vec::new(),
+    /// );
+    /// let combined = Response::combine([allow, deny], CombineStrategy::DenyOverrides);
+    /// assert_eq!(combined.decision(), Decision::Deny);
+    /// assert_eq!(
+    ///     combined.diagnostics().reason().collect::<Vec<_>>(),
+    ///     vec![&PolicyId::new("forbid_guests")]
+    /// );
+    /// ```
+    pub fn combine(responses: impl IntoIterator<Item = Self>, strategy: CombineStrategy) -> Self {
+        Self::combine_all(responses.into_iter().collect(), strategy)
+    }
+
+    /// Like [`Response::combine`], but with each response given a `label`
+    /// identifying its source. The ids of the policies in the reasons, errors,
+    /// and determining policies of the combined response are tagged with the
+    /// label of the response they come from, as `<label>/<id>`, so policies
+    /// with the same id in different sources remain distinct.
+    /// ```
+    /// # use cedar_policy::{CombineStrategy, Decision, PolicyId, Response};
+    /// # use std::collections::HashSet;
+    /// let photos = Response::new(
+    ///     Decision::Allow,
+    ///     HashSet::from([PolicyId::new("policy0")]),
+    ///     Vec::new(),
+    /// );
+    /// let billing = Response::new(
+    ///     Decision::Allow,
+    ///     HashSet::from([PolicyId::new("policy0")]),
+    ///     Vec::new(),
+    /// );
+    /// let combined = Response::combine_labeled(
+    ///     [("photos", photos), ("billing", billing)],
+    ///     CombineStrategy::DenyOverrides,
+    /// );
+    /// assert_eq!(combined.decision(), Decision::Allow);
+    /// let mut reasons = combined.diagnostics().reason().map(ToString::to_string).collect::<Vec<_>>();
+    /// reasons.sort();
+    /// assert_eq!(reasons, vec!["billing/policy0", "photos/policy0"]);
+    /// ```
+    pub fn combine_labeled<L: Display>(
+        responses: impl IntoIterator<Item = (L, Self)>,
+        strategy: CombineStrategy,
+    ) -> Self {
+        Self::combine_all(
+            responses
+                .into_iter()
+                .map(|(label, response)| response.with_label(&label.to_string()))
+                .collect(),
+            strategy,
+        )
+    }
+
+    fn combine_all(responses: Vec<Self>, strategy: CombineStrategy) -> Self {
+        let mut applicable = responses
+            .iter()
+            .filter(|response| response.applies())
+            .map(|response| response.decision)
+            .peekable();
+        let decision = match strategy {
+            CombineStrategy::DenyOverrides => {
+                if applicable.peek().is_some()
+                    && applicable.all(|decision| decision == Decision::Allow)
+                {
+                    Decision::Allow
+                } else {
+                    Decision::Deny
+                }
+            }
+            CombineStrategy::PermitOverrides => {
+                if applicable.any(|decision| decision == Decision::Allow) {
+                    Decision::Allow
+                } else {
+                    Decision::Deny
+                }
+            }
+        };
+
+        let mut reason = HashSet::new();
+        let mut errors = Vec::new();
+        let mut determining_policies = Vec::new();
+        for response in responses {
+            let agrees = response.decision == decision;
+            let (response_reason, response_errors) = response.diagnostics.into_components();
+            if agrees {
+                reason.extend(response_reason);
+                determining_policies.extend(response.determining_policies);
+            }
+            errors.extend(response_errors);
+        }
+        let decisive_effect = match decision {
+            Decision::Allow => Effect::Permit,
+            Decision::Deny => Effect::Forbid,
+        };
+        determining_policies.sort_by_cached_key(|policy| {
+            (policy.effect() != decisive_effect, policy.id().to_string())
+        });
+        Self {
+            decision,
+            diagnostics: Diagnostics { reason, errors },
+            determining_policies,
+        }
+    }
+
+    /// Does this response apply to the request, i.e., was any policy
+    /// satisfied. See [`CombineStrategy`].
+    pub(crate) fn applies(&self) -> bool {
+        !self.diagnostics.reason.is_empty()
+    }
+
+    /// Tag the ids of the policies in this response with `label`
+    fn with_label(self, label: &str) -> Self {
+        Self {
+            decision: self.decision,
+            diagnostics: self.diagnostics.with_label(label),
+            determining_policies: self
+                .determining_policies
+                .into_iter()
+                .map(|policy| policy.with_label(label))
+                .collect(),
+        }
+    }
+}
+
+impl Diagnostics {
+    /// Merge the diagnostics of several authorizations, e.g., of the same
+    /// request against independently managed policy sets, into one. The
+    /// reasons are the union of all the reasons, and the errors are all the
+    /// errors in order, dropping any with the same message as an earlier one.
+    /// See [`Diagnostics::merge_labeled`] to tell which policy set each
+    /// reason and error comes from.
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, Diagnostics, Entities, EntityUid, PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// let request = Request::new(
+    ///     Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Photo::"vacation""#).unwrap()),
+    ///     Context::empty(),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let authorizer = Authorizer::new();
+    /// let diagnostics = [
+    ///     "permit(principal, action, resource);",
+    ///     "permit(principal, action, resource) when { principal.missing };",
+    ///     "permit(principal, action, resource) when { principal.missing };",
+    /// ]
+    /// .into_iter()
+    /// .map(|src| {
+    ///     let policies = PolicySet::from_str(src).unwrap();
+    ///     let response = authorizer.is_authorized(&request, &policies, &Entities::empty());
+    ///     response.diagnostics().clone()
+    /// });
+    /// let merged = Diagnostics::merge(diagnostics);
+    /// assert_eq!(merged.reason().count(), 1);
+    /// assert_eq!(merged.errors().count(), 1);
+    /// ```
+    pub fn merge(diagnostics: impl Iterator<Item = Self>) -> Self {
+        let mut reason = HashSet::new();
+        let mut errors = Vec::new();
+        let mut error_messages = HashSet::new();
+        for diagnostics in diagnostics {
+            reason.extend(diagnostics.reason);
+            errors.extend(
+                diagnostics
+                    .errors
+                    .into_iter()
+                    .filter(|err| error_messages.insert(err.to_string())),
+            );
+        }
+        Self { reason, errors }
+    }
+
+    /// Like [`Diagnostics::merge`], but with each `Diagnostics` given a
+    /// `label` identifying the policy set it comes from. The ids of the
+    /// policies in the merged reasons and errors are tagged with the label, as
+    /// `<label>/<id>`, as in [`Response::combine_labeled`].
+    pub fn merge_labeled<L: Display>(diagnostics: impl Iterator<Item = (L, Self)>) -> Self {
+        Self::merge(
+            diagnostics.map(|(label, diagnostics)| diagnostics.with_label(&label.to_string())),
+        )
+    }
+
+    /// Tag the ids of the policies in these diagnostics with `label`
+    fn with_label(self, label: &str) -> Self {
+        Self {
+            reason: self
+                .reason
+                .into_iter()
+                .map(|id| labeled_id(label, &id))
+                .collect(),
+            errors: self
+                .errors
+                .into_iter()
+                .map(|err| match err {
+                    AuthorizationError::PolicyEvaluationError { id, error } => {
+                        AuthorizationError::PolicyEvaluationError {
+                            id: ast::PolicyID::from_string(format!("{label}/{id}")),
+                            error,
+                        }
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The id `id` tagged with `label`
+pub(super) fn labeled_id(label: &str, id: &PolicyId) -> PolicyId {
+    PolicyId::new(format!("{label}/{id}"))
+}
@@ -0,0 +1,139 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines the policies determining an authorization decision, as
+//! returned by [`Response::determining_policies`].
+
+use std::collections::HashMap;
+
+use super::combine::labeled_id;
+use super::{Decision, Effect, EntityUid, Policy, PolicyId, PolicySet, Response, SlotId};
+
+/// A policy which determined the decision of a [`Response`], as returned by
+/// [`Response::determining_policies`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeterminingPolicy {
+    id: PolicyId,
+    effect: Effect,
+    template: Option<(PolicyId, HashMap<SlotId, EntityUid>)>,
+}
+
+impl DeterminingPolicy {
+    /// The id of the policy
+    pub fn id(&self) -> &PolicyId {
+        &self.id
+    }
+
+    /// The effect of the policy. This is `Permit` for all the determining
+    /// policies of an `Allow` decision. A `Deny` decision may also be
+    /// determined by a `Permit` policy which errored, when the authorizer is
+    /// configured to deny on errors.
+    pub fn effect(&self) -> Effect {
+        self.effect
+    }
+
+    /// The id of the template the policy is linked from, or `None` if it is a
+    /// static policy
+    pub fn template_id(&self) -> Option<&PolicyId> {
+        self.template.as_ref().map(|(id, _)| id)
+    }
+
+    /// The values the slots of the template are linked to, or `None` if it is
+    /// a static policy
+    pub fn template_links(&self) -> Option<&HashMap<SlotId, EntityUid>> {
+        self.template.as_ref().map(|(_, links)| links)
+    }
+
+    /// Tag the ids of the policy and its template with `label`, as in
+    /// [`Response::combine_labeled`]
+    pub(crate) fn with_label(self, label: &str) -> Self {
+        Self {
+            id: labeled_id(label, &self.id),
+            effect: self.effect,
+            template: self
+                .template
+                .map(|(template_id, links)| (labeled_id(label, &template_id), links)),
+        }
+    }
+
+    fn new(policy: &Policy) -> Self {
+        Self {
+            id: policy.id().clone(),
+            effect: policy.effect(),
+            template: policy.template_id().cloned().zip(policy.template_links()),
+        }
+    }
+}
+
+impl Response {
+    /// Record the details of the policies in `policies` which determined this
+    /// response, ordered as documented on [`Response::determining_policies`]
+    pub(crate) fn with_determining_policies(mut self, policies: &PolicySet) -> Self {
+        let decisive_effect = match self.decision {
+            Decision::Allow => Effect::Permit,
+            Decision::Deny => Effect::Forbid,
+        };
+        let mut determining = self
+            .diagnostics
+            .reason()
+            .filter_map(|id| policies.policy(id))
+            .map(DeterminingPolicy::new)
+            .collect::<Vec<_>>();
+        determining
+            .sort_by_cached_key(|policy| (policy.effect != decisive_effect, policy.id.to_string()));
+        self.determining_policies = determining;
+        self
+    }
+
+    /// Get the policies which determined the decision, with their effect and,
+    /// for template-linked policies, the template and slot values they are
+    /// linked with. These are the policies of
+    /// [`Diagnostics::reason`](super::Diagnostics::reason).
+    ///
+    /// The policies with the effect of the decision (`Forbid` for `Deny`,
+    /// `Permit` for `Allow`) come first, followed by any policies with the
+    /// other effect, each ordered by policy id.
+    ///
+    /// This is only available for responses returned by an
+    /// [`Authorizer`](super::Authorizer), and is empty for responses built with
+    /// [`Response::new`].
+    /// ```
+    /// # use cedar_policy::{Authorizer, Context, Effect, Entities, EntityUid, PolicyId, PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// let policies = PolicySet::from_str(
+    ///     r#"permit(principal, action, resource);
+    ///     forbid(principal == User::"alice", action, resource);"#,
+    /// )
+    /// .unwrap();
+    /// let request = Request::new(
+    ///     Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Photo::"vacation""#).unwrap()),
+    ///     Context::empty(),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let response = Authorizer::new().is_authorized(&request, &policies, &Entities::empty());
+    /// let determining = response.determining_policies();
+    /// assert_eq!(determining.len(), 1);
+    /// assert_eq!(determining[0].id(), &PolicyId::from_str("policy1").unwrap());
+    /// assert_eq!(determining[0].effect(), Effect::Forbid);
+    /// assert!(determining[0].template_id().is_none());
+    /// ```
+    pub fn determining_policies(&self) -> &[DeterminingPolicy] {
+        &self.determining_policies
+    }
+}
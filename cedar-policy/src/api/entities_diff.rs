@@ -0,0 +1,383 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines the report returned by [`Entities::diff`], which
+//! describes how two entity stores differ.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{Entities, EntityUid};
+use cedar_policy_core::ast::{self, PartialValue, Value, ValueKind};
+use ref_cast::RefCast;
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+use smol_str::SmolStr;
+
+/// The differences between two entity stores, as returned by
+/// [`Entities::diff`]. The two stores are called "self" and "other", after
+/// the receiver and the argument of [`Entities::diff`].
+///
+/// Everything in the report is sorted, so two diffs of the same stores are
+/// always identical, including when serialized.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntitiesDiff {
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    only_in_self: Vec<EntityUid>,
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    only_in_other: Vec<EntityUid>,
+    changed: Vec<EntityDiff>,
+}
+
+impl EntitiesDiff {
+    /// The entities in self but not in other, ordered by UID
+    pub fn only_in_self(&self) -> impl Iterator<Item = &EntityUid> {
+        self.only_in_self.iter()
+    }
+
+    /// The entities in other but not in self, ordered by UID
+    pub fn only_in_other(&self) -> impl Iterator<Item = &EntityUid> {
+        self.only_in_other.iter()
+    }
+
+    /// The entities in both stores whose attributes or parents differ,
+    /// ordered by UID
+    pub fn changed(&self) -> impl Iterator<Item = &EntityDiff> {
+        self.changed.iter()
+    }
+
+    /// Whether the two stores contain exactly the same entities
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty() && self.only_in_other.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The differences between the two versions of an entity which is in both
+/// stores compared by [`Entities::diff`]
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityDiff {
+    #[serde_as(as = "DisplayFromStr")]
+    uid: EntityUid,
+    attributes: Vec<AttributeDiff>,
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    parents_only_in_self: Vec<EntityUid>,
+    #[serde_as(as = "Vec<DisplayFromStr>")]
+    parents_only_in_other: Vec<EntityUid>,
+}
+
+impl EntityDiff {
+    /// The UID of the entity
+    pub fn uid(&self) -> &EntityUid {
+        &self.uid
+    }
+
+    /// The differences between the entity's attributes, ordered by path
+    pub fn attributes(&self) -> impl Iterator<Item = &AttributeDiff> {
+        self.attributes.iter()
+    }
+
+    /// The ancestors the entity has in self but not in other, ordered by UID
+    pub fn parents_only_in_self(&self) -> impl Iterator<Item = &EntityUid> {
+        self.parents_only_in_self.iter()
+    }
+
+    /// The ancestors the entity has in other but not in self, ordered by UID
+    pub fn parents_only_in_other(&self) -> impl Iterator<Item = &EntityUid> {
+        self.parents_only_in_other.iter()
+    }
+}
+
+/// A difference between the values of an attribute, or of an attribute nested
+/// in records, of an entity in the two stores compared by [`Entities::diff`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AttributeDiff {
+    path: Vec<String>,
+    change: ValueChange,
+}
+
+impl AttributeDiff {
+    /// The path to the attribute: the entity's attribute, followed by the
+    /// attributes of the nested records leading to it, if any. For instance,
+    /// a difference in `principal.address.city` has path
+    /// `["address", "city"]`.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// How the attribute's value differs
+    pub fn change(&self) -> &ValueChange {
+        &self.change
+    }
+}
+
+/// How the value of an attribute differs between the two stores compared by
+/// [`Entities::diff`]. Values are written in Cedar syntax.
+///
+/// Records present in both stores are never reported as changed as a whole;
+/// instead, their attributes are compared, producing [`AttributeDiff`]s with
+/// longer paths.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ValueChange {
+    /// The attribute is only present in self
+    OnlyInSelf {
+        /// The value in self
+        value: String,
+    },
+    /// The attribute is only present in other
+    OnlyInOther {
+        /// The value in other
+        value: String,
+    },
+    /// The attribute is a set in both stores. Sets are compared element by
+    /// element, since their elements have no path.
+    #[serde(rename_all = "camelCase")]
+    SetElements {
+        /// The elements only in self's set, in sorted order
+        only_in_self: Vec<String>,
+        /// The elements only in other's set, in sorted order
+        only_in_other: Vec<String>,
+    },
+    /// The attribute has different values, which are not both records or
+    /// both sets
+    #[serde(rename_all = "camelCase")]
+    Changed {
+        /// The value in self
+        self_value: String,
+        /// The value in other
+        other_value: String,
+    },
+}
+
+impl Entities {
+    /// Compare this store with `other`, reporting the entities present in only
+    /// one of them, and for each entity present in both, the differences
+    /// between its attributes and between its ancestors.
+    ///
+    /// Attributes are compared deeply: nested records are compared attribute
+    /// by attribute, and sets element by element.
+    /// ```
+    /// # use cedar_policy::{Entities, ValueChange};
+    /// let staging = Entities::from_json_str(
+    ///     r#"[{ "uid": { "type": "User", "id": "alice" }, "attrs": { "level": 3 }, "parents": [] }]"#,
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let prod = Entities::from_json_str(
+    ///     r#"[{ "uid": { "type": "User", "id": "alice" }, "attrs": { "level": 2 }, "parents": [] }]"#,
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let diff = staging.diff(&prod);
+    /// let alice = diff.changed().next().unwrap();
+    /// let level = alice.attributes().next().unwrap();
+    /// assert_eq!(level.path(), ["level"]);
+    /// assert_eq!(
+    ///     level.change(),
+    ///     &ValueChange::Changed { self_value: "3".into(), other_value: "2".into() }
+    /// );
+    /// ```
+    pub fn diff(&self, other: &Self) -> EntitiesDiff {
+        let ours = by_uid(&self.0);
+        let theirs = by_uid(&other.0);
+        let only_in = |a: &BTreeMap<&ast::EntityUID, &ast::Entity>,
+                       b: &BTreeMap<&ast::EntityUID, &ast::Entity>| {
+            a.keys()
+                .filter(|uid| !b.contains_key(*uid))
+                .map(|uid| EntityUid::ref_cast(uid).clone())
+                .collect()
+        };
+        let changed = ours
+            .iter()
+            .filter_map(|(uid, ours)| {
+                let theirs = theirs.get(uid)?;
+                let diff = diff_entity(ours, theirs);
+                let unchanged = diff.attributes.is_empty()
+                    && diff.parents_only_in_self.is_empty()
+                    && diff.parents_only_in_other.is_empty();
+                (!unchanged).then_some(diff)
+            })
+            .collect();
+        EntitiesDiff {
+            only_in_self: only_in(&ours, &theirs),
+            only_in_other: only_in(&theirs, &ours),
+            changed,
+        }
+    }
+}
+
+/// The entities of `entities`, sorted by UID
+fn by_uid(
+    entities: &cedar_policy_core::entities::Entities,
+) -> BTreeMap<&ast::EntityUID, &ast::Entity> {
+    entities.iter().map(|e| (e.uid(), e)).collect()
+}
+
+/// Compare two versions of the same entity
+fn diff_entity(ours: &ast::Entity, theirs: &ast::Entity) -> EntityDiff {
+    let our_parents = ours.ancestors().collect::<BTreeSet<_>>();
+    let their_parents = theirs.ancestors().collect::<BTreeSet<_>>();
+    let parents_only_in = |a: &BTreeSet<&ast::EntityUID>, b: &BTreeSet<&ast::EntityUID>| {
+        a.difference(b)
+            .map(|uid| EntityUid::ref_cast(uid).clone())
+            .collect()
+    };
+
+    let mut attributes = vec![];
+    diff_attrs(
+        &mut vec![],
+        &ours
+            .attrs()
+            .map(|(k, v)| (k, v.into()))
+            .collect::<BTreeMap<_, _>>(),
+        &theirs
+            .attrs()
+            .map(|(k, v)| (k, v.into()))
+            .collect::<BTreeMap<_, _>>(),
+        &mut attributes,
+    );
+
+    EntityDiff {
+        uid: EntityUid::ref_cast(ours.uid()).clone(),
+        attributes,
+        parents_only_in_self: parents_only_in(&our_parents, &their_parents),
+        parents_only_in_other: parents_only_in(&their_parents, &our_parents),
+    }
+}
+
+/// Compare two sets of attributes found at `path`, pushing the differences
+/// onto `diffs` in order of path
+fn diff_attrs(
+    path: &mut Vec<String>,
+    ours: &BTreeMap<&SmolStr, AttrValue<'_>>,
+    theirs: &BTreeMap<&SmolStr, AttrValue<'_>>,
+    diffs: &mut Vec<AttributeDiff>,
+) {
+    let attrs = ours.keys().chain(theirs.keys()).collect::<BTreeSet<_>>();
+    for attr in attrs {
+        path.push(attr.to_string());
+        match (ours.get(*attr), theirs.get(*attr)) {
+            (Some(ours), Some(theirs)) => diff_values(*ours, *theirs, path, diffs),
+            (Some(ours), None) => diffs.push(AttributeDiff {
+                path: path.clone(),
+                change: ValueChange::OnlyInSelf {
+                    value: ours.render(),
+                },
+            }),
+            (None, Some(theirs)) => diffs.push(AttributeDiff {
+                path: path.clone(),
+                change: ValueChange::OnlyInOther {
+                    value: theirs.render(),
+                },
+            }),
+            (None, None) => {}
+        }
+        path.pop();
+    }
+}
+
+/// Compare the two values of the attribute at `path`, pushing the differences
+/// onto `diffs`
+fn diff_values(
+    ours: AttrValue<'_>,
+    theirs: AttrValue<'_>,
+    path: &mut Vec<String>,
+    diffs: &mut Vec<AttributeDiff>,
+) {
+    match (ours, theirs) {
+        (AttrValue::Value(ours), AttrValue::Value(theirs)) => {
+            match (ours.value_kind(), theirs.value_kind()) {
+                (ValueKind::Record(ours), ValueKind::Record(theirs)) => diff_attrs(
+                    path,
+                    &ours
+                        .iter()
+                        .map(|(k, v)| (k, AttrValue::Value(v)))
+                        .collect::<BTreeMap<_, _>>(),
+                    &theirs
+                        .iter()
+                        .map(|(k, v)| (k, AttrValue::Value(v)))
+                        .collect::<BTreeMap<_, _>>(),
+                    diffs,
+                ),
+                (ValueKind::Set(ours), ValueKind::Set(theirs)) => {
+                    let ours = ours.iter().collect::<BTreeSet<_>>();
+                    let theirs = theirs.iter().collect::<BTreeSet<_>>();
+                    if ours != theirs {
+                        diffs.push(AttributeDiff {
+                            path: path.clone(),
+                            change: ValueChange::SetElements {
+                                only_in_self: ours
+                                    .difference(&theirs)
+                                    .map(|v| v.to_cedar_str())
+                                    .collect(),
+                                only_in_other: theirs
+                                    .difference(&ours)
+                                    .map(|v| v.to_cedar_str())
+                                    .collect(),
+                            },
+                        });
+                    }
+                }
+                _ if ours == theirs => {}
+                _ => diffs.push(AttributeDiff {
+                    path: path.clone(),
+                    change: ValueChange::Changed {
+                        self_value: ours.to_cedar_str(),
+                        other_value: theirs.to_cedar_str(),
+                    },
+                }),
+            }
+        }
+        (ours, theirs) if ours == theirs => {}
+        (ours, theirs) => diffs.push(AttributeDiff {
+            path: path.clone(),
+            change: ValueChange::Changed {
+                self_value: ours.render(),
+                other_value: theirs.render(),
+            },
+        }),
+    }
+}
+
+/// An attribute value, which is either a `Value` or, in a partial store, a
+/// residual expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttrValue<'a> {
+    Value(&'a Value),
+    Residual(&'a ast::Expr),
+}
+
+impl AttrValue<'_> {
+    /// Render the value in Cedar syntax
+    fn render(self) -> String {
+        match self {
+            Self::Value(v) => v.to_cedar_str(),
+            Self::Residual(e) => e.to_string(),
+        }
+    }
+}
+
+impl<'a> From<&'a PartialValue> for AttrValue<'a> {
+    fn from(value: &'a PartialValue) -> Self {
+        match value {
+            PartialValue::Value(v) => Self::Value(v),
+            PartialValue::Residual(e) => Self::Residual(e),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines the view of an entity type returned by
+//! [`Schema::entity_type`].
+
+use std::collections::BTreeMap;
+
+use super::{EntityTypeName, Schema};
+use cedar_policy_validator::ValidatorEntityType;
+use smol_str::SmolStr;
+
+/// The declaration of an entity type in a [`Schema`], as returned by
+/// [`Schema::entity_type`]. This is a snapshot of the schema: it owns its
+/// contents and does not borrow from the `Schema`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityTypeInfo {
+    name: EntityTypeName,
+    attributes: BTreeMap<SmolStr, AttributeInfo>,
+    open_attributes: bool,
+}
+
+impl EntityTypeInfo {
+    /// The fully qualified name of the entity type
+    pub fn name(&self) -> &EntityTypeName {
+        &self.name
+    }
+
+    /// The attributes declared for the entity type, ordered by name
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &AttributeInfo)> {
+        self.attributes
+            .iter()
+            .map(|(name, info)| (name.as_str(), info))
+    }
+
+    /// The declaration of the attribute `attr`, or `None` if the entity type
+    /// has no such attribute
+    pub fn attribute(&self, attr: &str) -> Option<&AttributeInfo> {
+        self.attributes.get(attr)
+    }
+
+    /// Whether entities of this type may have attributes other than the
+    /// declared ones. This is only possible with partial schema validation.
+    pub fn has_open_attributes(&self) -> bool {
+        self.open_attributes
+    }
+}
+
+/// The declaration of an attribute of an entity type, as part of an
+/// [`EntityTypeInfo`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeInfo {
+    ty: String,
+    required: bool,
+}
+
+impl AttributeInfo {
+    /// The type of the attribute, written as in the human-readable schema
+    /// syntax, e.g., `Set<String>`. Entity types and common types are fully
+    /// qualified, and common types are replaced by their definitions.
+    pub fn type_str(&self) -> &str {
+        &self.ty
+    }
+
+    /// Whether every entity of the type must have this attribute
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+}
+
+impl EntityTypeInfo {
+    fn new(name: EntityTypeName, ety: &ValidatorEntityType) -> Self {
+        Self {
+            name,
+            attributes: ety
+                .attributes()
+                .map(|(attr, ty)| {
+                    (
+                        attr.clone(),
+                        AttributeInfo {
+                            ty: ty.attr_type.display_cedarschema(),
+                            required: ty.is_required,
+                        },
+                    )
+                })
+                .collect(),
+            open_attributes: ety.has_open_attributes(),
+        }
+    }
+}
+
+impl Schema {
+    /// Get the declaration of the entity type `name`: its attributes, their
+    /// types, and whether they are required. Returns `None` if the entity
+    /// type is not declared in the schema.
+    /// ```
+    /// # use cedar_policy::{EntityTypeName, Schema};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "namespace App { entity User = { name: String, groups?: Set<Group> }; entity Group; }",
+    /// )
+    /// .unwrap();
+    /// let user = schema
+    ///     .entity_type(&EntityTypeName::from_str("App::User").unwrap())
+    ///     .unwrap();
+    /// let groups = user.attribute("groups").unwrap();
+    /// assert_eq!(groups.type_str(), "Set<App::Group>");
+    /// assert!(!groups.is_required());
+    /// assert_eq!(
+    ///     user.attributes().map(|(attr, _)| attr).collect::<Vec<_>>(),
+    ///     vec!["groups", "name"]
+    /// );
+    /// ```
+    pub fn entity_type(&self, name: &EntityTypeName) -> Option<EntityTypeInfo> {
+        self.0
+            .get_entity_type(name.as_ref())
+            .map(|ety| EntityTypeInfo::new(name.clone(), ety))
+    }
+}
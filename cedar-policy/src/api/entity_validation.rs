@@ -0,0 +1,127 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines the report returned by
+//! [`Validator::validate_entities_sampled`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::{Entities, EntityTypeName, EntityViolationKind, Validator};
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::conformance::err::EntitySchemaConformanceError;
+use ref_cast::RefCast;
+
+/// The violations of the schema found by
+/// [`Validator::validate_entities_sampled`]: the number of violations of each
+/// kind and for each entity type, and the detailed errors for the first few of
+/// them
+#[repr(transparent)]
+#[derive(Debug, RefCast)]
+pub struct EntityValidationReport(cedar_policy_validator::EntityValidationReport);
+
+impl EntityValidationReport {
+    /// Check if every entity conforms to the schema
+    pub fn validation_passed(&self) -> bool {
+        self.0.validation_passed()
+    }
+
+    /// The number of entities which were checked
+    pub fn entities_checked(&self) -> usize {
+        self.0.entities_checked()
+    }
+
+    /// The number of entities with at least one violation
+    pub fn invalid_entities(&self) -> usize {
+        self.0.invalid_entities()
+    }
+
+    /// The total number of violations, which may be more than the number of
+    /// invalid entities, since an entity may violate the schema in several
+    /// ways
+    pub fn total_violations(&self) -> usize {
+        self.0.total_violations()
+    }
+
+    /// The number of violations of each kind. Kinds without violations are not
+    /// included.
+    pub fn violations_by_kind(&self) -> &BTreeMap<EntityViolationKind, usize> {
+        self.0.violations_by_kind()
+    }
+
+    /// The number of violations by entities of each entity type. Entity types
+    /// without violations are not included.
+    pub fn violations_by_entity_type(&self) -> HashMap<&EntityTypeName, usize> {
+        self.0
+            .violations_by_entity_type()
+            .iter()
+            .filter_map(|(ty, count)| match ty {
+                ast::EntityType::Specified(name) => Some((EntityTypeName::ref_cast(name), *count)),
+                // `Entities` never contain entities of unspecified type
+                ast::EntityType::Unspecified => None,
+            })
+            .collect()
+    }
+
+    /// The detailed errors for the first violations found, up to the maximum
+    /// passed to [`Validator::validate_entities_sampled`]
+    pub fn sample(&self) -> &[EntitySchemaConformanceError] {
+        self.0.sample()
+    }
+
+    /// Check if there were more violations than are in the
+    /// [`sample`](Self::sample)
+    pub fn is_truncated(&self) -> bool {
+        self.0.is_truncated()
+    }
+}
+
+impl Validator {
+    /// Check every entity in `entities` against the schema, reporting all the
+    /// ways in which each entity violates it. Unlike [`Entities::from_json_str`]
+    /// with a schema, this does not stop at the first error: all violations
+    /// are counted, by kind and by entity type, but the detailed error is only
+    /// kept for the first `max_errors` of them. This makes it practical to
+    /// validate a huge number of entities, parsed without a schema.
+    /// ```
+    /// # use cedar_policy::{Entities, EntityTypeName, EntityViolationKind, Schema, Validator};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural("entity User = { name: String };").unwrap();
+    /// let entities = Entities::from_json_value(
+    ///     serde_json::json!([
+    ///         { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [] },
+    ///         { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+    ///     ]),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let report = Validator::new(schema).validate_entities_sampled(&entities, 1);
+    /// assert_eq!(report.total_violations(), 2);
+    /// assert_eq!(report.violations_by_kind()[&EntityViolationKind::MissingRequiredAttr], 2);
+    /// assert_eq!(
+    ///     report.violations_by_entity_type()[&EntityTypeName::from_str("User").unwrap()],
+    ///     2,
+    /// );
+    /// assert_eq!(report.sample().len(), 1);
+    /// assert!(report.is_truncated());
+    /// ```
+    pub fn validate_entities_sampled(
+        &self,
+        entities: &Entities,
+        max_errors: usize,
+    ) -> EntityValidationReport {
+        EntityValidationReport(self.0.validate_entities_sampled(&entities.0, max_errors))
+    }
+}
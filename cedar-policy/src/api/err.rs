@@ -19,12 +19,13 @@
 use crate::EntityTypeName;
 use crate::EntityUid;
 use crate::PolicyId;
+use crate::SlotId;
 use cedar_policy_core::ast;
 use cedar_policy_core::ast::Name;
 use cedar_policy_core::authorizer;
 use cedar_policy_core::entities::json::ContextJsonDeserializationError;
 use cedar_policy_core::est;
-pub use cedar_policy_core::evaluator::{EvaluationError, EvaluationErrorKind};
+pub use cedar_policy_core::evaluator::{EvalLimitExceeded, EvaluationError, EvaluationErrorKind};
 use cedar_policy_core::parser;
 pub use cedar_policy_core::parser::err::ParseErrors;
 pub use cedar_policy_validator::human_schema::SchemaWarning;
@@ -61,6 +62,15 @@ impl AuthorizationError {
             Self::PolicyEvaluationError { id, error: _ } => PolicyId::ref_cast(id),
         }
     }
+
+    /// A code identifying the kind of this error, e.g.,
+    /// `"cedar::authorization::policy_evaluation"`. The code for a kind of
+    /// error is stable across releases, even when the error message changes.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::PolicyEvaluationError { .. } => "cedar::authorization::policy_evaluation",
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -165,6 +175,86 @@ pub enum SchemaError {
     /// Support for this escape form has been dropped.
     #[error("schema contained the non-supported `__expr` escape")]
     ExprEscapeUsed,
+    /// A default value given for a context attribute is invalid: it does not
+    /// evaluate, is not for an optional attribute of the action's context, or
+    /// does not have that attribute's type.
+    #[error("invalid default for context attribute `{attr}` of action `{action}`: {reason}")]
+    InvalidContextDefault {
+        /// Action whose context has the invalid default
+        action: EntityUid,
+        /// Context attribute with the invalid default
+        attr: SmolStr,
+        /// Why the default is invalid
+        reason: String,
+    },
+    /// The expression computing a derived attribute of an entity type is
+    /// invalid: it does not parse or typecheck, reads something other than the
+    /// entity's own attributes, or depends on itself.
+    #[error("invalid derived attribute `{attr}` of entity type `{entity_type}`: {reason}")]
+    InvalidDerivedAttribute {
+        /// Entity type with the invalid derived attribute
+        entity_type: EntityTypeName,
+        /// The invalid derived attribute
+        attr: SmolStr,
+        /// Why the derived attribute is invalid
+        reason: String,
+    },
+    /// A group of mutually exclusive attributes of an entity type is invalid:
+    /// it has fewer than two attributes, or an attribute which is not an
+    /// optional attribute of the entity type.
+    #[error("invalid `mutuallyExclusive` attributes of entity type `{entity_type}`: {reason}")]
+    InvalidMutuallyExclusiveAttrs {
+        /// Entity type with the invalid group of attributes
+        entity_type: EntityTypeName,
+        /// Why the group is invalid
+        reason: String,
+    },
+    /// A name given to a [`crate::SchemaBuilder`] is not a valid identifier
+    #[error("invalid name `{name}` in schema: {err}")]
+    InvalidName {
+        /// The invalid name
+        name: String,
+        /// Why the name is invalid
+        err: ParseErrors,
+    },
+}
+
+impl SchemaError {
+    /// A code identifying the kind of this error, e.g.,
+    /// `"cedar::schema::duplicate_entity_type"`. The code for a kind of error
+    /// is stable across releases, even when the error message changes.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::Serde(_) => "cedar::schema::json_syntax",
+            Self::ActionTransitiveClosure(_) => "cedar::schema::action_transitive_closure",
+            Self::EntityTypeTransitiveClosure(_) => "cedar::schema::entity_type_transitive_closure",
+            Self::UnsupportedFeature(_) => "cedar::schema::unsupported_feature",
+            Self::UndeclaredEntityTypes(_) => "cedar::schema::undeclared_entity_types",
+            Self::UndeclaredActions(_) => "cedar::schema::undeclared_actions",
+            Self::UndeclaredCommonTypes(_) => "cedar::schema::undeclared_common_types",
+            Self::DuplicateEntityType(_) => "cedar::schema::duplicate_entity_type",
+            Self::DuplicateAction(_) => "cedar::schema::duplicate_action",
+            Self::DuplicateCommonType(_) => "cedar::schema::duplicate_common_type",
+            Self::CycleInActionHierarchy(_) => "cedar::schema::cycle_in_action_hierarchy",
+            Self::CycleInCommonTypeReferences(_) => {
+                "cedar::schema::cycle_in_common_type_references"
+            }
+            Self::ActionEntityTypeDeclared => "cedar::schema::action_entity_type_declared",
+            Self::ContextOrShapeNotRecord(_) => "cedar::schema::context_or_shape_not_record",
+            Self::ActionAttributesContainEmptySet(_) => {
+                "cedar::schema::action_attributes_contain_empty_set"
+            }
+            Self::UnsupportedActionAttribute(_, _) => "cedar::schema::unsupported_action_attribute",
+            Self::ActionAttrEval(_) => "cedar::schema::action_attribute_evaluation",
+            Self::ExprEscapeUsed => "cedar::schema::expr_escape_used",
+            Self::InvalidContextDefault { .. } => "cedar::schema::invalid_context_default",
+            Self::InvalidDerivedAttribute { .. } => "cedar::schema::invalid_derived_attribute",
+            Self::InvalidMutuallyExclusiveAttrs { .. } => {
+                "cedar::schema::invalid_mutually_exclusive_attrs"
+            }
+            Self::InvalidName { .. } => "cedar::schema::invalid_name",
+        }
+    }
 }
 
 /// Errors serializing Schemas to the natural syntax
@@ -173,6 +263,35 @@ pub enum ToHumanSyntaxError {
     /// Duplicate names were found in the schema
     #[error("There are type name collisions: [{}]", .0.iter().join(", "))]
     NameCollisions(NonEmpty<SmolStr>),
+    /// An action has context defaults which can't be written as `@default`
+    /// annotations, e.g., because its context is declared with a common type
+    #[error(
+        "The context defaults of action `{0}` cannot be represented in the natural schema syntax"
+    )]
+    #[diagnostic(help(
+        "defaults may only be given for attributes of a context declared as a record type"
+    ))]
+    UnrepresentableContextDefaults(SmolStr),
+    /// An entity type has derived attributes, which can only be declared in
+    /// the JSON schema syntax
+    #[error(
+        "The derived attributes of entity type `{0}` cannot be represented in the natural schema syntax"
+    )]
+    #[diagnostic(help("derived attributes can only be declared in the JSON schema syntax"))]
+    UnrepresentableDerivedAttributes(SmolStr),
+    /// An action has attributes, which can only be declared in the JSON schema
+    /// syntax
+    #[error("The attributes of action `{0}` cannot be represented in the natural schema syntax")]
+    #[diagnostic(help("action attributes can only be declared in the JSON schema syntax"))]
+    UnrepresentableActionAttributes(SmolStr),
+    /// The declaration of an entity type, common type, or action context has a
+    /// record type with `additionalAttributes`, which can only be declared in
+    /// the JSON schema syntax
+    #[error("The declaration of `{0}` has a record type with additional attributes, which cannot be represented in the natural schema syntax")]
+    #[diagnostic(help(
+        "records with `additionalAttributes` can only be declared in the JSON schema syntax"
+    ))]
+    UnrepresentableAdditionalAttributes(SmolStr),
 }
 
 #[doc(hidden)]
@@ -182,6 +301,18 @@ impl From<cedar_policy_validator::human_schema::ToHumanSchemaStrError> for ToHum
             cedar_policy_validator::human_schema::ToHumanSchemaStrError::NameCollisions(
                 collisions,
             ) => Self::NameCollisions(collisions),
+            cedar_policy_validator::human_schema::ToHumanSchemaStrError::UnrepresentableContextDefaults(
+                action,
+            ) => Self::UnrepresentableContextDefaults(action),
+            cedar_policy_validator::human_schema::ToHumanSchemaStrError::UnrepresentableDerivedAttributes(
+                entity_type,
+            ) => Self::UnrepresentableDerivedAttributes(entity_type),
+            cedar_policy_validator::human_schema::ToHumanSchemaStrError::UnrepresentableActionAttributes(
+                action,
+            ) => Self::UnrepresentableActionAttributes(action),
+            cedar_policy_validator::human_schema::ToHumanSchemaStrError::UnrepresentableAdditionalAttributes(
+                name,
+            ) => Self::UnrepresentableAdditionalAttributes(name),
         }
     }
 }
@@ -244,6 +375,46 @@ impl From<ast::EntityAttrEvaluationError> for EntityAttrEvaluationError {
     }
 }
 
+/// Error returned by the typed attribute accessors of [`crate::Entity`], such
+/// as [`crate::Entity::attr_long`], when the attribute does not have the
+/// requested type
+#[derive(Debug, Diagnostic, Error)]
+pub struct TypeError {
+    attr: SmolStr,
+    expected: ast::Type,
+    /// `None` if the value of the attribute is not fully known
+    actual: Option<ast::Type>,
+}
+
+impl TypeError {
+    pub(crate) fn new(attr: &str, expected: ast::Type, actual: Option<ast::Type>) -> Self {
+        Self {
+            attr: attr.into(),
+            expected,
+            actual,
+        }
+    }
+
+    /// The attribute which does not have the requested type
+    pub fn attr(&self) -> &str {
+        &self.attr
+    }
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "expected attribute `{}` to have type {}, ",
+            self.attr, self.expected
+        )?;
+        match &self.actual {
+            Some(ty) => write!(f, "but it has type {ty}"),
+            None => write!(f, "but its value contains an unknown"),
+        }
+    }
+}
+
 /// Describes in what action context or entity type shape a schema parsing error
 /// occurred.
 #[derive(Debug)]
@@ -331,6 +502,31 @@ impl From<cedar_policy_validator::SchemaError> for SchemaError {
                 Self::ActionAttrEval(err.into())
             }
             cedar_policy_validator::SchemaError::ExprEscapeUsed => Self::ExprEscapeUsed,
+            cedar_policy_validator::SchemaError::InvalidContextDefault {
+                action,
+                attr,
+                reason,
+            } => Self::InvalidContextDefault {
+                action: EntityUid::new(action),
+                attr,
+                reason,
+            },
+            cedar_policy_validator::SchemaError::InvalidDerivedAttribute {
+                entity_type,
+                attr,
+                reason,
+            } => Self::InvalidDerivedAttribute {
+                entity_type: EntityTypeName::new(entity_type),
+                attr,
+                reason,
+            },
+            cedar_policy_validator::SchemaError::InvalidMutuallyExclusiveAttrs {
+                entity_type,
+                reason,
+            } => Self::InvalidMutuallyExclusiveAttrs {
+                entity_type: EntityTypeName::new(entity_type),
+                reason,
+            },
         }
     }
 }
@@ -356,6 +552,14 @@ impl ValidationError {
     pub fn location(&self) -> &SourceLocation {
         SourceLocation::ref_cast(self.error.location())
     }
+
+    /// A code identifying the kind of issue detected by the validator, e.g.,
+    /// `"cedar::validation::unrecognized_entity_type"`. This is the
+    /// [`ValidationErrorKind::error_code`] of [`Self::error_kind`], so it is
+    /// stable across releases, even when the error message changes.
+    pub fn error_code(&self) -> &'static str {
+        self.error_kind().error_code()
+    }
 }
 
 #[doc(hidden)]
@@ -396,6 +600,11 @@ impl SourceLocation {
             .as_ref()
             .map(|loc| (loc.start(), loc.end()))
     }
+
+    /// Get the source location, including the text of the policy
+    pub(crate) fn source_loc(&self) -> Option<&parser::Loc> {
+        self.0.source_loc()
+    }
 }
 
 impl std::fmt::Display for SourceLocation {
@@ -433,6 +642,14 @@ impl ValidationWarning {
     pub fn location(&self) -> &SourceLocation {
         SourceLocation::ref_cast(self.warning.location())
     }
+
+    /// A code identifying the kind of issue detected by the validator, e.g.,
+    /// `"cedar::validation::impossible_policy"`. This is the
+    /// [`ValidationWarningKind::warning_code`] of [`Self::warning_kind`], so it
+    /// is stable across releases, even when the warning message changes.
+    pub fn warning_code(&self) -> &'static str {
+        self.warning_kind().warning_code()
+    }
 }
 
 #[doc(hidden)]
@@ -512,6 +729,202 @@ impl From<ast::UnexpectedSlotError> for PolicySetError {
     }
 }
 
+/// Error when linking a [`crate::Template`] without adding the result to a
+/// `PolicySet`, as in [`crate::Template::link`]
+#[derive(Debug, Diagnostic, Error)]
+#[error(transparent)]
+#[diagnostic(transparent)]
+pub struct LinkError {
+    /// Underlying error
+    #[from]
+    err: ast::LinkingError,
+}
+
+impl LinkError {
+    /// Slots of the template which were not given a value
+    pub fn unbound_slots(&self) -> impl Iterator<Item = SlotId> + '_ {
+        let unbound: &[ast::SlotId] = match &self.err {
+            ast::LinkingError::ArityError { unbound_values, .. } => unbound_values,
+            _ => &[],
+        };
+        unbound.iter().map(|slot| SlotId::from(*slot))
+    }
+
+    /// Values which were given for slots that do not appear in the template
+    pub fn extra_slots(&self) -> impl Iterator<Item = SlotId> + '_ {
+        let extra: &[ast::SlotId] = match &self.err {
+            ast::LinkingError::ArityError { extra_values, .. } => extra_values,
+            _ => &[],
+        };
+        extra.iter().map(|slot| SlotId::from(*slot))
+    }
+}
+
+/// Error returned by [`crate::PolicySet::validate_links`] for a slot of a
+/// template-linked policy which is not valid for its template and the schema
+#[derive(Debug, Diagnostic, Error)]
+#[error(transparent)]
+#[diagnostic(transparent)]
+pub struct LinkValidationError {
+    /// Underlying error
+    #[from]
+    err: cedar_policy_validator::LinkValidationError,
+}
+
+impl LinkValidationError {
+    /// The id of the template the policy is linked from
+    pub fn template_id(&self) -> &PolicyId {
+        PolicyId::ref_cast(self.err.template_id())
+    }
+
+    /// The id of the template-linked policy
+    pub fn link_id(&self) -> &PolicyId {
+        PolicyId::ref_cast(self.err.link_id())
+    }
+
+    /// The slot with the problem
+    pub fn slot(&self) -> SlotId {
+        SlotId::from(self.err.slot())
+    }
+
+    /// The problem with the slot
+    pub fn kind(&self) -> LinkValidationErrorKind {
+        match self.err.kind() {
+            cedar_policy_validator::LinkValidationErrorKind::Unfilled(_) => {
+                LinkValidationErrorKind::Unfilled
+            }
+            cedar_policy_validator::LinkValidationErrorKind::UndeclaredEntityType(..) => {
+                LinkValidationErrorKind::UndeclaredEntityType
+            }
+            cedar_policy_validator::LinkValidationErrorKind::MismatchedEntityType(..) => {
+                LinkValidationErrorKind::MismatchedEntityType
+            }
+        }
+    }
+
+    /// The entity the slot is linked to, or `None` if the slot is not filled
+    pub fn value(&self) -> Option<&EntityUid> {
+        match self.err.kind() {
+            cedar_policy_validator::LinkValidationErrorKind::Unfilled(_) => None,
+            cedar_policy_validator::LinkValidationErrorKind::UndeclaredEntityType(_, euid)
+            | cedar_policy_validator::LinkValidationErrorKind::MismatchedEntityType(_, euid) => {
+                Some(EntityUid::ref_cast(euid))
+            }
+        }
+    }
+}
+
+/// The kind of problem reported by a [`LinkValidationError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkValidationErrorKind {
+    /// The template has the slot, but the policy does not link it to a value
+    Unfilled,
+    /// The slot is linked to an entity whose type is not declared in the schema
+    UndeclaredEntityType,
+    /// The slot is linked to an entity of a type which none of the actions of
+    /// the template apply to
+    MismatchedEntityType,
+}
+
+/// Error when parsing an [`EntityUid`] from a string
+///
+/// Common mistakes are reported with the byte offset in the input where they
+/// occur, so that tooling can suggest a fix.
+#[derive(Debug, Diagnostic, Error)]
+pub enum EntityUidParseError {
+    /// The entity type and the quoted entity id are not separated by `::`, as
+    /// in `alice` or `User:"alice"`
+    #[error("expected `::` between the entity type and the entity id")]
+    #[diagnostic(help(
+        "entity uids are written as an entity type, `::`, and a quoted entity id, e.g., `User::\"alice\"`"
+    ))]
+    MissingSeparator {
+        /// Byte offset at which `::` was expected
+        offset: usize,
+    },
+    /// The entity id is missing its closing quote, as in `User::"alice`
+    #[error("unterminated string literal in entity id")]
+    UnterminatedString {
+        /// Byte offset of the opening quote
+        offset: usize,
+    },
+    /// The entity type is not a valid name, as in `my-type::"alice"`
+    #[error("invalid entity type `{type_name}`")]
+    #[diagnostic(help(
+        "entity types are identifiers, optionally qualified by namespaces, e.g., `MyApp::User`"
+    ))]
+    InvalidTypeName {
+        /// The invalid entity type, as written
+        type_name: String,
+        /// Byte offset of the start of the entity type
+        offset: usize,
+    },
+    /// Any other error parsing the entity uid
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Other(#[from] ParseErrors),
+}
+
+impl EntityUidParseError {
+    /// The byte offset in the input at which the error occurs, or `None` for
+    /// [`EntityUidParseError::Other`]
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::MissingSeparator { offset }
+            | Self::UnterminatedString { offset }
+            | Self::InvalidTypeName { offset, .. } => Some(*offset),
+            Self::Other(_) => None,
+        }
+    }
+
+    /// Explain why `src` failed to parse as an entity uid with errors `errs`,
+    /// recognizing the common mistakes which have their own variants.
+    pub(crate) fn new(src: &str, errs: ParseErrors) -> Self {
+        let Some(quote) = src.find('"') else {
+            // no entity id at all, as in `alice`
+            return if src.contains("::") {
+                Self::Other(errs)
+            } else {
+                Self::MissingSeparator {
+                    offset: src.trim_end().len(),
+                }
+            };
+        };
+        let before_quote = src[..quote].trim_end();
+        let Some(type_part) = before_quote.strip_suffix("::") else {
+            // point at a lone `:` if there is one, as in `User:"alice"`
+            return Self::MissingSeparator {
+                offset: before_quote.trim_end_matches(':').len(),
+            };
+        };
+        let mut chars = src[quote + 1..].chars();
+        let mut terminated = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => {
+                    terminated = true;
+                    break;
+                }
+                _ => (),
+            }
+        }
+        if !terminated {
+            return Self::UnterminatedString { offset: quote };
+        }
+        // whitespace is allowed here; it is only rejected for not being normalized
+        if type_part.parse::<Name>().is_err() {
+            return Self::InvalidTypeName {
+                type_name: type_part.trim().to_string(),
+                offset: type_part.len() - type_part.trim_start().len(),
+            };
+        }
+        Self::Other(errs)
+    }
+}
+
 /// Errors that can happen when getting the JSON representation of a policy
 #[derive(Debug, Diagnostic, Error)]
 pub enum PolicyToJsonError {
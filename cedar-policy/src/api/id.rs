@@ -18,7 +18,7 @@
 //! `EntityUid` and `PolicyId`.
 
 use crate::entities::json::err::JsonDeserializationError;
-use crate::ParseErrors;
+use crate::{EntityUidParseError, ParseErrors};
 use cedar_policy_core::ast;
 use cedar_policy_core::entities::json::err::JsonDeserializationErrorContext;
 use cedar_policy_core::FromNormalizedStr;
@@ -155,6 +155,13 @@ impl std::fmt::Display for EntityTypeName {
     }
 }
 
+#[doc(hidden)]
+impl AsRef<ast::Name> for EntityTypeName {
+    fn as_ref(&self) -> &ast::Name {
+        &self.0
+    }
+}
+
 /// Unique id for an entity, such as `User::"alice"`.
 ///
 /// An `EntityUid` contains an [`EntityTypeName`] and [`EntityId`]. It can
@@ -232,6 +239,51 @@ impl EntityUid {
         ))
     }
 
+    /// Parse an [`EntityUid`] which may not be in its normalized representation
+    ///
+    /// This accepts, e.g., whitespace or comments in the type path, as in
+    /// `User :: "alice"`, as produced by some other systems. The result is
+    /// [normalized](Self::normalize), so it is equal to the [`EntityUid`]
+    /// parsed from the normalized representation, and finds the same entity
+    /// in [`Entities`](crate::Entities). Use [`EntityUid::from_str`] to only
+    /// accept the normalized representation.
+    /// ```
+    /// # use cedar_policy::EntityUid;
+    /// # use std::str::FromStr;
+    /// let euid = EntityUid::from_unnormalized_str(r#"NS :: User :: "alice""#).unwrap();
+    /// assert_eq!(euid, EntityUid::from_str(r#"NS::User::"alice""#).unwrap());
+    /// assert_eq!(euid.to_string(), r#"NS::User::"alice""#);
+    /// ```
+    pub fn from_unnormalized_str(uid_str: &str) -> Result<Self, EntityUidParseError> {
+        // INVARIANT there is no way to write down the unspecified entity
+        ast::EntityUID::from_str(uid_str)
+            .map(|uid| Self::new(uid.normalize()))
+            .map_err(|errs| EntityUidParseError::new(uid_str, errs))
+    }
+
+    /// Get the normalized form of this [`EntityUid`]
+    ///
+    /// The normalized form doesn't depend on the source the [`EntityUid`] was
+    /// parsed from: the type path is written with plain `::` separators, and
+    /// the source location is dropped. The [`EntityId`] is kept exactly as is,
+    /// because entity ids are case- and whitespace-sensitive.
+    ///
+    /// Two [`EntityUid`]s refer to the same entity exactly when their
+    /// normalized forms are equal, which is what `==` compares, so lookups in
+    /// [`Entities`](crate::Entities) find the same entity for both.
+    /// ```
+    /// # use cedar_policy::EntityUid;
+    /// # use std::str::FromStr;
+    /// let euid = EntityUid::from_str(r#"User::"alice""#).unwrap();
+    /// assert_eq!(euid.normalize(), euid);
+    /// assert_ne!(EntityUid::from_str(r#"User::" alice""#).unwrap().normalize(), euid);
+    /// ```
+    #[must_use]
+    pub fn normalize(&self) -> Self {
+        // INVARIANT: normalizing preserves the entity type
+        Self(self.0.normalize())
+    }
+
     /// Testing utility for creating `EntityUids` a bit easier
     #[cfg(test)]
     pub(crate) fn from_strs(typename: &str, id: &str) -> Self {
@@ -249,7 +301,7 @@ impl EntityUid {
 }
 
 impl FromStr for EntityUid {
-    type Err = ParseErrors;
+    type Err = EntityUidParseError;
 
     /// Parse an [`EntityUid`].
     ///
@@ -274,9 +326,15 @@ impl FromStr for EntityUid {
     ///
     /// __DO NOT__ create [`EntityUid`]'s via string concatenation.
     /// If you have separate components of an [`EntityUid`], use [`EntityUid::from_type_name_and_id`]
+    ///
+    /// If parsing fails because of a missing `::`, an unterminated entity id, or
+    /// an invalid entity type, the error reports which, along with where it
+    /// occurs in `uid_str`.
     fn from_str(uid_str: &str) -> Result<Self, Self::Err> {
         // INVARIANT there is no way to write down the unspecified entity
-        ast::EntityUID::from_normalized_str(uid_str).map(Self::new)
+        ast::EntityUID::from_normalized_str(uid_str)
+            .map(Self::new)
+            .map_err(|errs| EntityUidParseError::new(uid_str, errs))
     }
 }
 
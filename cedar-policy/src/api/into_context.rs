@@ -0,0 +1,123 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines the [`IntoRestrictedExpression`] conversion from Rust
+//! values to Cedar values, which `#[derive(IntoContext)]` (with the `derive`
+//! feature) uses for the fields of a struct.
+
+use std::collections::{BTreeSet, HashSet};
+
+use super::{EntityUid, RestrictedExpression};
+
+/// A Rust value which converts to a Cedar value.
+///
+/// `#[derive(IntoContext)]` requires this of the type of every field of the
+/// struct. It is implemented for `String` and `&str` (Cedar strings), `i64`
+/// (longs), `bool`, [`EntityUid`] (entity references), `Vec`, `HashSet` and
+/// `BTreeSet` (sets), and [`IpAddr`] and [`Decimal`] (extension values).
+/// Deriving `IntoContext` for a struct also implements it for the struct, as
+/// a record, so that structs can be nested.
+pub trait IntoRestrictedExpression {
+    /// Convert this value to a `RestrictedExpression`
+    fn into_restricted_expression(self) -> RestrictedExpression;
+}
+
+/// An `ipaddr` extension value, given by its string representation, e.g.,
+/// `"192.168.0.1"` or `"10.0.0.0/8"`. The string is only checked when the
+/// value is evaluated, e.g., when building a `Context`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IpAddr(pub String);
+
+/// A `decimal` extension value, given by its string representation, e.g.,
+/// `"1.25"`. The string is only checked when the value is evaluated, e.g.,
+/// when building a `Context`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Decimal(pub String);
+
+impl IntoRestrictedExpression for RestrictedExpression {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        self
+    }
+}
+
+impl IntoRestrictedExpression for String {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_string(self)
+    }
+}
+
+impl IntoRestrictedExpression for &str {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_string(self.to_string())
+    }
+}
+
+impl IntoRestrictedExpression for i64 {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_long(self)
+    }
+}
+
+impl IntoRestrictedExpression for bool {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_bool(self)
+    }
+}
+
+impl IntoRestrictedExpression for EntityUid {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_entity_uid(self)
+    }
+}
+
+impl IntoRestrictedExpression for IpAddr {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_ip(self.0)
+    }
+}
+
+impl IntoRestrictedExpression for Decimal {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_decimal(self.0)
+    }
+}
+
+impl<T: IntoRestrictedExpression> IntoRestrictedExpression for Vec<T> {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_set(
+            self.into_iter()
+                .map(IntoRestrictedExpression::into_restricted_expression),
+        )
+    }
+}
+
+impl<T: IntoRestrictedExpression, S> IntoRestrictedExpression for HashSet<T, S> {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_set(
+            self.into_iter()
+                .map(IntoRestrictedExpression::into_restricted_expression),
+        )
+    }
+}
+
+impl<T: IntoRestrictedExpression> IntoRestrictedExpression for BTreeSet<T> {
+    fn into_restricted_expression(self) -> RestrictedExpression {
+        RestrictedExpression::new_set(
+            self.into_iter()
+                .map(IntoRestrictedExpression::into_restricted_expression),
+        )
+    }
+}
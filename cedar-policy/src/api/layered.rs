@@ -0,0 +1,199 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines the [`LayeredAuthorizer`], which authorizes requests
+//! against ordered layers of policy sets, where higher layers take precedence
+//! over lower ones.
+
+use std::collections::HashSet;
+
+use super::{Authorizer, Decision, Diagnostics, Entities, PolicySet, Request, Response};
+
+/// How a [`LayeredAuthorizer`] reaches a decision from the decisions of its
+/// layers.
+///
+/// Within each layer, the decision is reached as usual in Cedar: a satisfied
+/// `forbid` overrides any satisfied `permit`. A layer *applies* to a request
+/// if any of its policies is satisfied. A layer which doesn't apply has no say
+/// in the decision, rather than denying the request, so that a layer only
+/// needs policies for the requests it cares about. `DenyOverrides` and
+/// `PermitOverrides` reach the same decision as [`Response::combine`] with
+/// the [`CombineStrategy`](super::CombineStrategy) of the same name, which treats responses which
+/// don't apply the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerCombination {
+    /// The decision is that of the highest layer which applies
+    FirstApplicable,
+    /// The decision is `Deny` if any layer which applies denies the request,
+    /// and otherwise `Allow` if any layer applies
+    DenyOverrides,
+    /// The decision is `Allow` if any layer which applies allows the request,
+    /// and otherwise `Deny`
+    PermitOverrides,
+}
+
+/// Authorizes requests against ordered layers of [`PolicySet`]s
+///
+/// For instance, an organization-wide policy set may be layered above the
+/// policy sets of its teams. The decisions of the layers are combined as given
+/// by a [`LayerCombination`]. If no layer applies to a request, the request is
+/// denied.
+#[derive(Debug)]
+pub struct LayeredAuthorizer {
+    authorizer: Authorizer,
+    layers: Vec<PolicySet>,
+    combination: LayerCombination,
+}
+
+impl LayeredAuthorizer {
+    /// Create a `LayeredAuthorizer` for the policy sets in `layers`, ordered
+    /// from the highest layer, which takes precedence, to the lowest
+    pub fn new(layers: Vec<PolicySet>, combination: LayerCombination) -> Self {
+        Self {
+            authorizer: Authorizer::new(),
+            layers,
+            combination,
+        }
+    }
+
+    /// Use `authorizer`, e.g., one with a non-default configuration, to
+    /// authorize requests against each layer
+    #[must_use]
+    pub fn with_authorizer(self, authorizer: Authorizer) -> Self {
+        Self { authorizer, ..self }
+    }
+
+    /// Get the policy sets of the layers, from the highest to the lowest
+    pub fn layers(&self) -> &[PolicySet] {
+        &self.layers
+    }
+
+    /// Authorize `request` against the layers, from the highest down, stopping
+    /// at the first layer whose decision can't be overridden by the layers
+    /// below it.
+    ///
+    /// The reasons and determining policies of the response are those of the
+    /// layer which decided, and its errors are those of every layer which was
+    /// evaluated.
+    /// ```
+    /// # use cedar_policy::{Context, Decision, Entities, EntityUid, LayerCombination, LayeredAuthorizer, PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// let org = PolicySet::from_str(r#"forbid(principal, action == Action::"delete", resource);"#).unwrap();
+    /// let team = PolicySet::from_str("permit(principal, action, resource);").unwrap();
+    /// let authorizer = LayeredAuthorizer::new(vec![org, team], LayerCombination::FirstApplicable);
+    /// let request = |action: &str| {
+    ///     Request::new(
+    ///         Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+    ///         Some(EntityUid::from_str(action).unwrap()),
+    ///         Some(EntityUid::from_str(r#"Photo::"vacation""#).unwrap()),
+    ///         Context::empty(),
+    ///         None,
+    ///     )
+    ///     .unwrap()
+    /// };
+    ///
+    /// let response = authorizer.is_authorized(&request(r#"Action::"delete""#), &Entities::empty());
+    /// assert_eq!(response.decision(), Decision::Deny);
+    /// assert_eq!(response.deciding_layer(), Some(0));
+    ///
+    /// let response = authorizer.is_authorized(&request(r#"Action::"view""#), &Entities::empty());
+    /// assert_eq!(response.decision(), Decision::Allow);
+    /// assert_eq!(response.deciding_layer(), Some(1));
+    /// ```
+    pub fn is_authorized(&self, request: &Request, entities: &Entities) -> LayeredResponse {
+        let final_decision = match self.combination {
+            LayerCombination::FirstApplicable => None,
+            LayerCombination::DenyOverrides => Some(Decision::Deny),
+            LayerCombination::PermitOverrides => Some(Decision::Allow),
+        };
+        let mut deciding: Option<(usize, Response)> = None;
+        let mut errors = Vec::new();
+        for (layer, policies) in self.layers.iter().enumerate() {
+            let mut response = self.authorizer.is_authorized(request, policies, entities);
+            errors.append(&mut response.diagnostics.errors);
+            if !response.applies()
+                || (deciding.is_some() && Some(response.decision) != final_decision)
+            {
+                continue;
+            }
+            let decision = response.decision;
+            deciding = Some((layer, response));
+            if final_decision.map_or(true, |final_decision| decision == final_decision) {
+                break;
+            }
+        }
+
+        let (deciding_layer, response) = match deciding {
+            Some((layer, response)) => (
+                Some(layer),
+                Response {
+                    diagnostics: Diagnostics {
+                        reason: response.diagnostics.reason,
+                        errors,
+                    },
+                    ..response
+                },
+            ),
+            None => (
+                None,
+                Response {
+                    decision: Decision::Deny,
+                    diagnostics: Diagnostics {
+                        reason: HashSet::new(),
+                        errors,
+                    },
+                    determining_policies: Vec::new(),
+                },
+            ),
+        };
+        LayeredResponse {
+            response,
+            deciding_layer,
+        }
+    }
+}
+
+/// The result of [`LayeredAuthorizer::is_authorized`]: the combined
+/// [`Response`] and the layer which decided it
+#[derive(Debug, Clone)]
+pub struct LayeredResponse {
+    response: Response,
+    deciding_layer: Option<usize>,
+}
+
+impl LayeredResponse {
+    /// The combined decision
+    pub fn decision(&self) -> Decision {
+        self.response.decision()
+    }
+
+    /// The combined response
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+
+    /// Consume this `LayeredResponse`, getting the combined response
+    pub fn into_response(self) -> Response {
+        self.response
+    }
+
+    /// The index in [`LayeredAuthorizer::layers`] of the layer which decided
+    /// the response, or `None` if no layer applies to the request, so that it
+    /// is denied by default
+    pub fn deciding_layer(&self) -> Option<usize> {
+        self.deciding_layer
+    }
+}
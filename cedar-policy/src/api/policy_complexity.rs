@@ -0,0 +1,105 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines the estimate returned by [`Policy::complexity`].
+
+use super::{policy_set_stats::depth, Policy};
+use cedar_policy_core::ast::{BinaryOp, ExprKind};
+use serde::Serialize;
+
+/// Static measures of the complexity of a [`Policy`]'s `when` and `unless`
+/// conditions, as returned by [`Policy::complexity`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyComplexity {
+    max_depth: usize,
+    expression_size: usize,
+    attribute_accesses: usize,
+    set_operations: usize,
+    extension_calls: usize,
+}
+
+impl PolicyComplexity {
+    /// The depth of the expression tree formed by the conditions. A policy
+    /// without conditions has depth 1, as its condition is just `true`.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// The number of nodes in the expression tree formed by the conditions
+    pub fn expression_size(&self) -> usize {
+        self.expression_size
+    }
+
+    /// The number of attribute accesses, with `.` or `has`
+    pub fn attribute_accesses(&self) -> usize {
+        self.attribute_accesses
+    }
+
+    /// The number of `contains`, `containsAll`, and `containsAny` operations
+    pub fn set_operations(&self) -> usize {
+        self.set_operations
+    }
+
+    /// The number of calls to extension functions and methods, e.g.,
+    /// `ip("10.0.0.1")` or `d.lessThan(d2)`
+    pub fn extension_calls(&self) -> usize {
+        self.extension_calls
+    }
+}
+
+impl Policy {
+    /// Statically estimate the complexity of this policy's `when` and `unless`
+    /// conditions, without evaluating them. This is a deterministic traversal
+    /// of the policy, which can be used to reject pathologically complex
+    /// policies before adding them to a [`super::PolicySet`].
+    /// ```
+    /// # use cedar_policy::Policy;
+    /// # use std::str::FromStr;
+    /// let policy = Policy::from_str(r#"permit(principal, action, resource) when {
+    ///     principal.groups.containsAny(resource.groups) && context.ip.isLoopback()
+    /// };"#).unwrap();
+    /// let complexity = policy.complexity();
+    /// assert_eq!(complexity.attribute_accesses(), 3);
+    /// assert_eq!(complexity.set_operations(), 1);
+    /// assert_eq!(complexity.extension_calls(), 1);
+    /// ```
+    pub fn complexity(&self) -> PolicyComplexity {
+        let conditions = self.ast.non_scope_constraints();
+        let mut complexity = PolicyComplexity {
+            max_depth: depth(conditions),
+            expression_size: 0,
+            attribute_accesses: 0,
+            set_operations: 0,
+            extension_calls: 0,
+        };
+        for expr in conditions.subexpressions() {
+            complexity.expression_size += 1;
+            match expr.expr_kind() {
+                ExprKind::GetAttr { .. } => complexity.attribute_accesses += 1,
+                // each attribute in the path is tested for
+                ExprKind::HasAttr { path, .. } => complexity.attribute_accesses += 1 + path.len(),
+                ExprKind::BinaryApp {
+                    op: BinaryOp::Contains | BinaryOp::ContainsAll | BinaryOp::ContainsAny,
+                    ..
+                } => complexity.set_operations += 1,
+                ExprKind::ExtensionFunctionApp { .. } => complexity.extension_calls += 1,
+                _ => (),
+            }
+        }
+        complexity
+    }
+}
@@ -0,0 +1,192 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines the summary returned by [`PolicySet::statistics`].
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{Effect, PolicySet};
+use cedar_policy_core::ast::{Expr, ExprKind, Literal};
+use serde::Serialize;
+
+/// Aggregate statistics about a [`PolicySet`], as returned by
+/// [`PolicySet::statistics`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicySetStats {
+    permits: usize,
+    forbids: usize,
+    static_policies: usize,
+    templates: usize,
+    linked_policies: usize,
+    distinct_actions: usize,
+    average_condition_depth: f64,
+    entity_type_references: BTreeMap<String, usize>,
+}
+
+impl PolicySetStats {
+    /// The number of static and template-linked policies with effect `permit`
+    pub fn permits(&self) -> usize {
+        self.permits
+    }
+
+    /// The number of static and template-linked policies with effect `forbid`
+    pub fn forbids(&self) -> usize {
+        self.forbids
+    }
+
+    /// The number of static policies
+    pub fn static_policies(&self) -> usize {
+        self.static_policies
+    }
+
+    /// The number of templates
+    pub fn templates(&self) -> usize {
+        self.templates
+    }
+
+    /// The number of template-linked policies
+    pub fn linked_policies(&self) -> usize {
+        self.linked_policies
+    }
+
+    /// The number of distinct actions referenced by static policies and
+    /// templates, in their scope or conditions
+    pub fn distinct_actions(&self) -> usize {
+        self.distinct_actions
+    }
+
+    /// The average depth of the expression tree formed by the `when` and
+    /// `unless` conditions of each static policy and template, or 0 if there
+    /// are none. A policy without conditions has depth 1, as its condition is
+    /// just `true`.
+    pub fn average_condition_depth(&self) -> f64 {
+        self.average_condition_depth
+    }
+
+    /// For each entity type referenced by a static policy or template, in an
+    /// entity literal or an `is` test, the number of static policies and
+    /// templates referencing it. Ordered by entity type name.
+    pub fn entity_type_references(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.entity_type_references
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+    }
+
+    /// The entity types in [`Self::entity_type_references`], ordered from the
+    /// most referenced to the least, and then by name
+    pub fn most_referenced_entity_types(&self) -> Vec<(&str, usize)> {
+        let mut types = self.entity_type_references().collect::<Vec<_>>();
+        types.sort_by(|(name1, count1), (name2, count2)| {
+            count2.cmp(count1).then_with(|| name1.cmp(name2))
+        });
+        types
+    }
+}
+
+impl PolicySet {
+    /// Compute aggregate statistics about this `PolicySet`: how many permits
+    /// and forbids, static policies, templates, and links it contains, and
+    /// which actions and entity types its policies reference.
+    ///
+    /// Statistics about the contents of policies are computed over the
+    /// static policies and templates, so that a template counts once no
+    /// matter how many times it is linked.
+    pub fn statistics(&self) -> PolicySetStats {
+        let mut permits = 0;
+        let mut forbids = 0;
+        let mut static_policies = 0;
+        let mut linked_policies = 0;
+        for policy in self.policies.values() {
+            match policy.effect() {
+                Effect::Permit => permits += 1,
+                Effect::Forbid => forbids += 1,
+            }
+            if policy.is_static() {
+                static_policies += 1;
+            } else {
+                linked_policies += 1;
+            }
+        }
+
+        let mut actions = BTreeSet::new();
+        let mut entity_type_references = BTreeMap::new();
+        let mut total_depth = 0;
+        let bodies = self.ast.all_templates().collect::<Vec<_>>();
+        for body in &bodies {
+            total_depth += depth(body.non_scope_constraints());
+            let mut entity_types = BTreeSet::new();
+            for expr in body.condition().subexpressions() {
+                match expr.expr_kind() {
+                    ExprKind::Lit(Literal::EntityUID(euid)) => {
+                        if euid.is_action() {
+                            actions.insert(euid.to_string());
+                        }
+                        entity_types.insert(euid.entity_type().to_string());
+                    }
+                    ExprKind::Is { entity_type, .. } => {
+                        entity_types.insert(entity_type.to_string());
+                    }
+                    _ => (),
+                }
+            }
+            for entity_type in entity_types {
+                *entity_type_references.entry(entity_type).or_default() += 1;
+            }
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let average_condition_depth = if bodies.is_empty() {
+            0.0
+        } else {
+            total_depth as f64 / bodies.len() as f64
+        };
+
+        PolicySetStats {
+            permits,
+            forbids,
+            static_policies,
+            templates: self.templates.len(),
+            linked_policies,
+            distinct_actions: actions.len(),
+            average_condition_depth,
+            entity_type_references,
+        }
+    }
+}
+
+/// The depth of the expression tree of `expr`
+pub(super) fn depth(expr: &Expr) -> usize {
+    let children: Vec<&Expr> = match expr.expr_kind() {
+        ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => vec![],
+        ExprKind::If {
+            test_expr,
+            then_expr,
+            else_expr,
+        } => vec![test_expr, then_expr, else_expr],
+        ExprKind::And { left, right } | ExprKind::Or { left, right } => vec![left, right],
+        ExprKind::BinaryApp { arg1, arg2, .. } => vec![arg1, arg2],
+        ExprKind::UnaryApp { arg: expr, .. }
+        | ExprKind::GetAttr { expr, .. }
+        | ExprKind::HasAttr { expr, .. }
+        | ExprKind::Like { expr, .. }
+        | ExprKind::Is { expr, .. } => vec![expr],
+        ExprKind::ExtensionFunctionApp { args: exprs, .. } | ExprKind::Set(exprs) => {
+            exprs.iter().collect()
+        }
+        ExprKind::Record(fields) => fields.values().collect(),
+    };
+    1 + children.into_iter().map(depth).max().unwrap_or(0)
+}
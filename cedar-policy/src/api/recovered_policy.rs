@@ -0,0 +1,184 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`parse_policy_tolerant`] and the [`RecoveredPolicy`]
+//! it returns.
+
+use super::{
+    ActionConstraint, Effect, LosslessPolicy, ParseErrors, PolicyToJsonError, SlotId, Template,
+    TemplatePrincipalConstraint, TemplateResourceConstraint,
+};
+use cedar_policy_core::est;
+use cedar_policy_core::parser::{self, RecoveredTemplate};
+
+/// A best-effort reconstruction of a policy or template that failed to parse,
+/// as returned by [`parse_policy_tolerant`].
+///
+/// A `RecoveredPolicy` is possibly incomplete: annotations that could not be
+/// parsed are left out, and scope constraints and `when` or `unless`
+/// conditions that could not be parsed are reported as missing. It is meant for tooling such as editors, which want to inspect the valid
+/// portions of a policy while it is being written. For that reason it can be
+/// inspected, but there is no way to turn it into a [`super::Policy`] or
+/// [`Template`], or to add it to a [`super::PolicySet`]: it must never be used
+/// for authorization.
+#[derive(Debug, Clone)]
+pub struct RecoveredPolicy {
+    template: Template,
+    principal_recovered: bool,
+    action_recovered: bool,
+    resource_recovered: bool,
+    conditions: Vec<Option<est::Clause>>,
+}
+
+impl RecoveredPolicy {
+    /// Get the `Effect` (`Forbid` or `Permit`) of this `RecoveredPolicy`
+    pub fn effect(&self) -> Effect {
+        self.template.effect()
+    }
+
+    /// Get an annotation value of this `RecoveredPolicy`
+    pub fn annotation(&self, key: impl AsRef<str>) -> Option<&str> {
+        self.template.annotation(key)
+    }
+
+    /// Iterate through the recovered annotations of this `RecoveredPolicy` as
+    /// key-value pairs
+    pub fn annotations(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.template.annotations()
+    }
+
+    /// Iterate over the slots in the scope of this `RecoveredPolicy`
+    pub fn slots(&self) -> impl Iterator<Item = &SlotId> {
+        self.template.slots()
+    }
+
+    /// Get the scope constraint on this policy's principal, or `None` if it
+    /// could not be recovered
+    pub fn principal_constraint(&self) -> Option<TemplatePrincipalConstraint> {
+        self.principal_recovered
+            .then(|| self.template.principal_constraint())
+    }
+
+    /// Get the scope constraint on this policy's action, or `None` if it could
+    /// not be recovered
+    pub fn action_constraint(&self) -> Option<ActionConstraint> {
+        self.action_recovered
+            .then(|| self.template.action_constraint())
+    }
+
+    /// Get the scope constraint on this policy's resource, or `None` if it
+    /// could not be recovered
+    pub fn resource_constraint(&self) -> Option<TemplateResourceConstraint> {
+        self.resource_recovered
+            .then(|| self.template.resource_constraint())
+    }
+
+    /// Get the JSON representation of each `when` and `unless` condition of
+    /// this `RecoveredPolicy`, in source order, or `None` for a condition that
+    /// could not be recovered. Each condition is represented as in the
+    /// `conditions` of [`Template::to_json`].
+    pub fn conditions_json(&self) -> Result<Vec<Option<serde_json::Value>>, PolicyToJsonError> {
+        self.conditions
+            .iter()
+            .map(|clause| {
+                clause
+                    .as_ref()
+                    .map(serde_json::to_value)
+                    .transpose()
+                    .map_err(Into::into)
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for RecoveredPolicy {
+    /// Displays the recovered portions of the policy. Each scope constraint
+    /// and condition that could not be recovered is displayed as `<invalid>`,
+    /// so the result is not a valid policy unless everything was recovered.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const INVALID: &str = "<invalid>";
+        let ast = &self.template.ast;
+        for (key, value) in ast.annotations() {
+            writeln!(f, "@{key}(\"{}\")", value.val.escape_debug())?;
+        }
+        write!(f, "{}(", ast.effect())?;
+        if self.principal_recovered {
+            write!(f, "{}, ", ast.principal_constraint())?;
+        } else {
+            write!(f, "principal {INVALID}, ")?;
+        }
+        if self.action_recovered {
+            write!(f, "{}, ", ast.action_constraint())?;
+        } else {
+            write!(f, "action {INVALID}, ")?;
+        }
+        if self.resource_recovered {
+            write!(f, "{})", ast.resource_constraint())?;
+        } else {
+            write!(f, "resource {INVALID})")?;
+        }
+        for condition in &self.conditions {
+            match condition {
+                Some(condition) => write!(f, " {condition}")?,
+                None => write!(f, " {INVALID}")?,
+            }
+        }
+        write!(f, ";")
+    }
+}
+
+/// Parse a single policy or template, recovering as much of it as possible if
+/// it has errors.
+///
+/// Returns all the errors encountered, together with a [`RecoveredPolicy`] if
+/// the parser was able to recover at least the effect of the policy. If there
+/// are no errors, the `RecoveredPolicy` matches the source, but it is still
+/// not usable for authorization; use [`super::Policy::parse`] or
+/// [`Template::parse`] for that.
+///
+/// ```
+/// # use cedar_policy::{parse_policy_tolerant, ActionConstraint, Effect};
+/// let src = r#"permit(principal, action == Action::"view", resource)
+///     when { principal.level > };"#;
+/// let (recovered, errs) = parse_policy_tolerant(src);
+/// assert!(!errs.is_empty());
+/// let recovered = recovered.unwrap();
+/// assert_eq!(recovered.effect(), Effect::Permit);
+/// assert!(matches!(recovered.action_constraint(), Some(ActionConstraint::Eq(_))));
+/// assert_eq!(recovered.conditions_json().unwrap(), vec![None]);
+/// ```
+pub fn parse_policy_tolerant(src: impl AsRef<str>) -> (Option<RecoveredPolicy>, ParseErrors) {
+    let (ast, errs) = parser::parse_policy_tolerant(None, src.as_ref());
+    let recovered = ast.map(
+        |RecoveredTemplate {
+             template,
+             principal_recovered,
+             action_recovered,
+             resource_recovered,
+             conditions,
+         }| RecoveredPolicy {
+            template: Template {
+                lossless: LosslessPolicy::policy_or_template_text(template.to_string()),
+                ast: template,
+            },
+            principal_recovered,
+            action_recovered,
+            resource_recovered,
+            conditions,
+        },
+    );
+    (recovered, errs)
+}
@@ -0,0 +1,181 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module serializes a [`ValidationResult`] to SARIF, the Static
+//! Analysis Results Interchange Format understood by code scanning tools.
+
+use super::{SourceLocation, ValidationResult};
+use miette::Diagnostic;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// SARIF `level` of validation errors
+const ERROR_LEVEL: &str = "error";
+/// SARIF `level` of validation warnings
+const WARNING_LEVEL: &str = "warning";
+
+impl ValidationResult {
+    /// Serialize the errors and warnings to a [SARIF
+    /// 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+    /// log, for code scanning tools which render findings inline.
+    ///
+    /// Each kind of error or warning is a rule, whose id is derived from its
+    /// stable code, e.g., `cedar/unrecognized-entity-type` for
+    /// `cedar::validation::unrecognized_entity_type`. Errors have level
+    /// `error` and warnings have level `warning`.
+    ///
+    /// Each result names the policy it was found in. If `artifact_uri`, the
+    /// URI of the file the policies were parsed from, is provided, results
+    /// with source locations also give the region of that file, with columns
+    /// counted in Unicode code points.
+    /// ```
+    /// # use cedar_policy::{PolicySet, Schema, ValidationMode, Validator};
+    /// # use std::str::FromStr;
+    /// let (schema, _) = Schema::from_str_natural(
+    ///     "entity User; action view appliesTo { principal: User, resource: User };",
+    /// )
+    /// .unwrap();
+    /// let policies = PolicySet::from_str(r#"permit(principal == Usr::"alice", action, resource);"#).unwrap();
+    /// let result = Validator::new(schema).validate(&policies, ValidationMode::Strict);
+    /// let sarif = result.to_sarif(Some("policies.cedar"));
+    /// let finding = &sarif["runs"][0]["results"][0];
+    /// assert_eq!(finding["ruleId"], "cedar/unrecognized-entity-type");
+    /// assert_eq!(finding["level"], "error");
+    /// let region = &finding["locations"][0]["physicalLocation"]["region"];
+    /// assert_eq!(region["startColumn"], 21);
+    /// ```
+    pub fn to_sarif(&self, artifact_uri: Option<&str>) -> Value {
+        let findings = self
+            .validation_errors()
+            .map(|error| {
+                (
+                    error.error_code(),
+                    ERROR_LEVEL,
+                    error as &dyn Diagnostic,
+                    error.location(),
+                )
+            })
+            .chain(self.validation_warnings().map(|warning| {
+                (
+                    warning.warning_code(),
+                    WARNING_LEVEL,
+                    warning as &dyn Diagnostic,
+                    warning.location(),
+                )
+            }))
+            .collect::<Vec<_>>();
+
+        // rules are ordered by id, so their indices are stable
+        let rules = findings
+            .iter()
+            .map(|(code, level, _, _)| (rule_id(code), *level))
+            .collect::<BTreeMap<_, _>>();
+        let rule_indices = rules
+            .keys()
+            .enumerate()
+            .map(|(index, id)| (id.clone(), index))
+            .collect::<BTreeMap<_, _>>();
+
+        let results = findings
+            .iter()
+            .map(|(code, level, diagnostic, location)| {
+                let id = rule_id(code);
+                json!({
+                    "ruleId": id,
+                    "ruleIndex": rule_indices.get(&id),
+                    "level": level,
+                    "message": { "text": message(*diagnostic) },
+                    "locations": [sarif_location(location, artifact_uri)],
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "cedar",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": "https://www.cedarpolicy.com",
+                        "rules": rules
+                            .iter()
+                            .map(|(id, level)| json!({
+                                "id": id,
+                                "defaultConfiguration": { "level": level },
+                            }))
+                            .collect::<Vec<_>>(),
+                    }
+                },
+                "columnKind": "unicodeCodePoints",
+                "results": results,
+            }]
+        })
+    }
+}
+
+/// The SARIF rule id for the error or warning code `code`, e.g.,
+/// `cedar/unsafe-attribute-access` for
+/// `cedar::validation::type::unsafe_attribute_access`
+fn rule_id(code: &str) -> String {
+    let name = code.rsplit("::").next().unwrap_or(code);
+    format!("cedar/{}", name.replace('_', "-"))
+}
+
+/// The message of `diagnostic`, followed by its help, if any
+fn message(diagnostic: &dyn Diagnostic) -> String {
+    diagnostic.help().map_or_else(
+        || diagnostic.to_string(),
+        |help| format!("{diagnostic}\nhelp: {help}"),
+    )
+}
+
+fn sarif_location(location: &SourceLocation, artifact_uri: Option<&str>) -> Value {
+    let mut sarif = Map::new();
+    sarif.insert(
+        "logicalLocations".into(),
+        json!([{ "name": location.policy_id().to_string() }]),
+    );
+    if let (Some(uri), Some(loc)) = (artifact_uri, location.source_loc()) {
+        let (start_line, start_column) = line_and_column(&loc.src, loc.start());
+        let (end_line, end_column) = line_and_column(&loc.src, loc.end());
+        sarif.insert(
+            "physicalLocation".into(),
+            json!({
+                "artifactLocation": { "uri": uri },
+                "region": {
+                    "startLine": start_line,
+                    "startColumn": start_column,
+                    "endLine": end_line,
+                    "endColumn": end_column,
+                },
+            }),
+        );
+    }
+    Value::Object(sarif)
+}
+
+/// The 1-based line and column, in Unicode code points, of the byte `offset`
+/// of `src`
+pub(super) fn line_and_column(src: &str, offset: usize) -> (usize, usize) {
+    let before = src.get(..offset).unwrap_or(src);
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
@@ -0,0 +1,445 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines [`SchemaBuilder`], for constructing a [`Schema`] in
+//! code instead of parsing it from the JSON or human-readable syntax.
+
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+
+use super::{EntityNamespace, EntityTypeName, Schema, SchemaError};
+use cedar_policy_core::ast;
+use cedar_policy_core::FromNormalizedStr;
+use cedar_policy_validator::{
+    ActionEntityUID, ActionType, ApplySpec, AttributesOrContext, EntityType, NamespaceDefinition,
+    SchemaFragment, SchemaType, SchemaTypeVariant, TypeOfAttribute, ValidatorSchema,
+};
+use smol_str::SmolStr;
+
+/// The type of an attribute of an entity type, record, or context, for use
+/// with [`SchemaBuilder`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Type(SchemaType);
+
+impl Type {
+    /// The `Long` type
+    pub fn long() -> Self {
+        Self(SchemaTypeVariant::Long.into())
+    }
+
+    /// The `String` type
+    pub fn string() -> Self {
+        Self(SchemaTypeVariant::String.into())
+    }
+
+    /// The `Bool` type
+    pub fn bool() -> Self {
+        Self(SchemaTypeVariant::Boolean.into())
+    }
+
+    /// The type of sets with elements of type `element`
+    pub fn set(element: Self) -> Self {
+        Self(
+            SchemaTypeVariant::Set {
+                element: Box::new(element.0),
+            }
+            .into(),
+        )
+    }
+
+    /// The type of entities of type `name`. As in the schema syntaxes, an
+    /// unqualified name refers to an entity type in the namespace where it is
+    /// used.
+    pub fn entity(name: &EntityTypeName) -> Self {
+        Self(
+            SchemaTypeVariant::Entity {
+                name: name.as_ref().clone(),
+            }
+            .into(),
+        )
+    }
+
+    /// The `ipaddr` extension type
+    pub fn ipaddr() -> Self {
+        Self::extension("ipaddr")
+    }
+
+    /// The `decimal` extension type
+    pub fn decimal() -> Self {
+        Self::extension("decimal")
+    }
+
+    fn extension(name: &str) -> Self {
+        // PANIC SAFETY: only called with the names of extension types, which are valid `Id`s
+        #[allow(clippy::unwrap_used)]
+        let name = ast::Id::from_normalized_str(name).unwrap();
+        Self(SchemaTypeVariant::Extension { name }.into())
+    }
+
+    /// The type of records with exactly the attributes `attrs`
+    pub fn record(attrs: impl IntoIterator<Item = (impl Into<SmolStr>, AttrType)>) -> Self {
+        Self(
+            SchemaTypeVariant::Record {
+                attributes: attrs
+                    .into_iter()
+                    .map(|(name, ty)| (name.into(), ty.0))
+                    .collect(),
+                additional_attributes: false,
+            }
+            .into(),
+        )
+    }
+}
+
+/// The type of an attribute together with whether the attribute is required,
+/// for use with [`SchemaBuilder`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttrType(TypeOfAttribute);
+
+impl AttrType {
+    /// A required attribute of type `ty`
+    pub fn required(ty: Type) -> Self {
+        Self(TypeOfAttribute {
+            ty: ty.0,
+            required: true,
+        })
+    }
+
+    /// An optional attribute of type `ty`
+    pub fn optional(ty: Type) -> Self {
+        Self(TypeOfAttribute {
+            ty: ty.0,
+            required: false,
+        })
+    }
+}
+
+/// Builder for a [`Schema`], declaring its entity types and actions in code.
+///
+/// ```
+/// # use cedar_policy::{AttrType, SchemaBuilder, Type};
+/// let schema = SchemaBuilder::new()
+///     .namespace("PhotoApp".parse().unwrap())
+///     .entity_type("Group")
+///     .entity_type("User")
+///     .member_of(&"Group".parse().unwrap())
+///     .attribute("age", AttrType::optional(Type::long()))
+///     .entity_type("Photo")
+///     .action("view")
+///     .principal_types(["User".parse().unwrap()])
+///     .resource_types(["Photo".parse().unwrap()])
+///     .context_attribute("ip", AttrType::required(Type::ipaddr()))
+///     .build()
+///     .unwrap();
+/// # assert_eq!(schema.action_entities().unwrap().iter().count(), 1);
+/// ```
+///
+/// Entity types and actions are declared in the namespace most recently set
+/// with [`SchemaBuilder::namespace`], or in the empty namespace if it was
+/// never called. [`SchemaBuilder::build`] performs the same checks as
+/// parsing a schema, e.g., that every referenced entity type is declared and
+/// that the action hierarchy has no cycles.
+#[derive(Debug, Default)]
+pub struct SchemaBuilder {
+    namespaces: HashMap<Option<ast::Name>, NamespaceDefinition>,
+    namespace: Option<ast::Name>,
+    /// The first error encountered while building, reported by `build()`
+    error: Option<SchemaError>,
+}
+
+impl SchemaBuilder {
+    /// Create a builder for an empty schema
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare subsequent entity types and actions in `namespace`
+    #[must_use]
+    pub fn namespace(self, namespace: EntityNamespace) -> Self {
+        Self {
+            namespace: Some(namespace.0),
+            ..self
+        }
+    }
+
+    /// Declare an entity type named `name`, which must be an unqualified
+    /// identifier. Its attributes and parent types are given by calling methods
+    /// on the returned builder.
+    #[must_use]
+    pub fn entity_type(mut self, name: &str) -> EntityTypeBuilder {
+        let name = match ast::Id::from_normalized_str(name) {
+            Ok(name) => Some(name),
+            Err(err) => {
+                self.record_error(SchemaError::InvalidName {
+                    name: name.to_string(),
+                    err,
+                });
+                None
+            }
+        };
+        EntityTypeBuilder {
+            schema: self,
+            name,
+            decl: EntityType {
+                member_of_types: Vec::new(),
+                shape: AttributesOrContext::default(),
+                derived_attributes: None,
+                mutually_exclusive: None,
+            },
+        }
+    }
+
+    /// Declare an action with id `name`. The types it applies to, its context,
+    /// and the action groups it is a member of are given by calling methods on
+    /// the returned builder.
+    #[must_use]
+    pub fn action(self, name: &str) -> ActionBuilder {
+        ActionBuilder {
+            schema: self,
+            name: name.into(),
+            decl: ActionType {
+                attributes: None,
+                applies_to: None,
+                member_of: None,
+            },
+        }
+    }
+
+    /// Build the [`Schema`], checking that it is valid
+    pub fn build(self) -> Result<Schema, SchemaError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        let lossless = SchemaFragment(self.namespaces);
+        Ok(Schema(
+            ValidatorSchema::try_from(lossless.clone())?,
+            lossless,
+        ))
+    }
+
+    fn current_namespace(&mut self) -> &mut NamespaceDefinition {
+        self.namespaces
+            .entry(self.namespace.clone())
+            .or_insert_with(|| NamespaceDefinition::new([], []))
+    }
+
+    fn record_error(&mut self, err: SchemaError) {
+        self.error.get_or_insert(err);
+    }
+
+    fn declare_entity_type(&mut self, name: ast::Id, decl: EntityType) {
+        let duplicate = match self.current_namespace().entity_types.entry(name) {
+            Entry::Vacant(v) => {
+                v.insert(decl);
+                None
+            }
+            Entry::Occupied(o) => Some(o.key().to_string()),
+        };
+        if let Some(name) = duplicate {
+            let name = self.qualify(&name);
+            self.record_error(SchemaError::DuplicateEntityType(name));
+        }
+    }
+
+    fn declare_action(&mut self, name: SmolStr, decl: ActionType) {
+        let duplicate = match self.current_namespace().actions.entry(name) {
+            Entry::Vacant(v) => {
+                v.insert(decl);
+                None
+            }
+            Entry::Occupied(o) => Some(format!("Action::\"{}\"", o.key().escape_debug())),
+        };
+        if let Some(name) = duplicate {
+            let name = self.qualify(&name);
+            self.record_error(SchemaError::DuplicateAction(name));
+        }
+    }
+
+    /// Qualify `name` with the current namespace, for error messages
+    fn qualify(&self, name: &str) -> String {
+        self.namespace.as_ref().map_or_else(
+            || name.to_string(),
+            |namespace| format!("{namespace}::{name}"),
+        )
+    }
+}
+
+/// Builder for an entity type declared by [`SchemaBuilder::entity_type`].
+///
+/// The declaration is complete once another entity type, action, or namespace
+/// is started, or the schema is built.
+#[derive(Debug)]
+pub struct EntityTypeBuilder {
+    schema: SchemaBuilder,
+    /// `None` if the name was invalid, in which case the error is already
+    /// recorded in `schema`
+    name: Option<ast::Id>,
+    decl: EntityType,
+}
+
+impl EntityTypeBuilder {
+    /// Declare an attribute of this entity type
+    #[must_use]
+    pub fn attribute(mut self, name: impl Into<SmolStr>, ty: AttrType) -> Self {
+        add_attribute(&mut self.decl.shape, name.into(), ty);
+        self
+    }
+
+    /// Declare that entities of this type may be members of entities of type
+    /// `parent`
+    #[must_use]
+    pub fn member_of(mut self, parent: &EntityTypeName) -> Self {
+        self.decl.member_of_types.push(parent.as_ref().clone());
+        self
+    }
+
+    /// Finish declaring this entity type, and declare subsequent entity types
+    /// and actions in `namespace`
+    #[must_use]
+    pub fn namespace(self, namespace: EntityNamespace) -> SchemaBuilder {
+        self.finish().namespace(namespace)
+    }
+
+    /// Finish declaring this entity type, and declare another
+    #[must_use]
+    pub fn entity_type(self, name: &str) -> Self {
+        self.finish().entity_type(name)
+    }
+
+    /// Finish declaring this entity type, and declare an action
+    #[must_use]
+    pub fn action(self, name: &str) -> ActionBuilder {
+        self.finish().action(name)
+    }
+
+    /// Finish declaring this entity type, and build the [`Schema`]
+    pub fn build(self) -> Result<Schema, SchemaError> {
+        self.finish().build()
+    }
+
+    /// Finish declaring this entity type, returning the schema builder
+    #[must_use]
+    pub fn finish(self) -> SchemaBuilder {
+        let mut schema = self.schema;
+        if let Some(name) = self.name {
+            schema.declare_entity_type(name, self.decl);
+        }
+        schema
+    }
+}
+
+/// Builder for an action declared by [`SchemaBuilder::action`].
+///
+/// The declaration is complete once another entity type, action, or namespace
+/// is started, or the schema is built.
+#[derive(Debug)]
+pub struct ActionBuilder {
+    schema: SchemaBuilder,
+    name: SmolStr,
+    decl: ActionType,
+}
+
+impl ActionBuilder {
+    /// Declare the principal types this action applies to
+    #[must_use]
+    pub fn principal_types(mut self, types: impl IntoIterator<Item = EntityTypeName>) -> Self {
+        self.applies_to().principal_types =
+            Some(types.into_iter().map(|ty| ty.as_ref().clone()).collect());
+        self
+    }
+
+    /// Declare the resource types this action applies to
+    #[must_use]
+    pub fn resource_types(mut self, types: impl IntoIterator<Item = EntityTypeName>) -> Self {
+        self.applies_to().resource_types =
+            Some(types.into_iter().map(|ty| ty.as_ref().clone()).collect());
+        self
+    }
+
+    /// Declare an attribute of the context for this action
+    #[must_use]
+    pub fn context_attribute(mut self, name: impl Into<SmolStr>, ty: AttrType) -> Self {
+        add_attribute(&mut self.applies_to().context, name.into(), ty);
+        self
+    }
+
+    /// Declare that this action is a member of the action with id `parent`,
+    /// in the same namespace
+    #[must_use]
+    pub fn member_of(mut self, parent: &str) -> Self {
+        self.decl
+            .member_of
+            .get_or_insert_with(Vec::new)
+            .push(ActionEntityUID::default_type(parent.into()));
+        self
+    }
+
+    /// Finish declaring this action, and declare subsequent entity types and
+    /// actions in `namespace`
+    #[must_use]
+    pub fn namespace(self, namespace: EntityNamespace) -> SchemaBuilder {
+        self.finish().namespace(namespace)
+    }
+
+    /// Finish declaring this action, and declare an entity type
+    #[must_use]
+    pub fn entity_type(self, name: &str) -> EntityTypeBuilder {
+        self.finish().entity_type(name)
+    }
+
+    /// Finish declaring this action, and declare another
+    #[must_use]
+    pub fn action(self, name: &str) -> Self {
+        self.finish().action(name)
+    }
+
+    /// Finish declaring this action, and build the [`Schema`]
+    pub fn build(self) -> Result<Schema, SchemaError> {
+        self.finish().build()
+    }
+
+    /// Finish declaring this action, returning the schema builder
+    #[must_use]
+    pub fn finish(self) -> SchemaBuilder {
+        let mut schema = self.schema;
+        schema.declare_action(self.name, self.decl);
+        schema
+    }
+
+    fn applies_to(&mut self) -> &mut ApplySpec {
+        self.decl.applies_to.get_or_insert_with(|| ApplySpec {
+            resource_types: None,
+            principal_types: None,
+            context: AttributesOrContext::default(),
+            context_defaults: None,
+        })
+    }
+}
+
+/// Add an attribute to the record type of an entity type's shape or an
+/// action's context. These are always built as records by `SchemaBuilder`.
+fn add_attribute(record: &mut AttributesOrContext, name: SmolStr, ty: AttrType) {
+    if let SchemaType::Type(SchemaTypeVariant::Record { attributes, .. }) = &mut record.0 {
+        attributes.insert(name, ty.0);
+    } else {
+        *record = AttributesOrContext(
+            SchemaTypeVariant::Record {
+                attributes: BTreeMap::from([(name, ty.0)]),
+                additional_attributes: false,
+            }
+            .into(),
+        );
+    }
+}
@@ -0,0 +1,303 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module defines the trace returned by
+//! [`Authorizer::is_authorized_traced`], which explains how each policy was
+//! evaluated for an authorization request.
+
+use super::{Authorizer, Effect, Entities, EvalResult, PolicyId, PolicySet, Request, Response};
+use cedar_policy_core::ast;
+use cedar_policy_core::est;
+pub use cedar_policy_core::evaluator::AttributeAccessKind;
+use cedar_policy_core::evaluator::{EvaluationError, Evaluator};
+use cedar_policy_core::extensions::Extensions;
+
+/// How each policy in a [`PolicySet`] was evaluated for an authorization
+/// request, as returned by [`Authorizer::is_authorized_traced`]
+#[derive(Debug)]
+pub struct AuthorizationTrace {
+    /// Sorted by policy id
+    policies: Vec<PolicyTrace>,
+}
+
+impl AuthorizationTrace {
+    /// The traces of every policy in the policy set, ordered by policy id
+    pub fn policies(&self) -> impl Iterator<Item = &PolicyTrace> {
+        self.policies.iter()
+    }
+
+    /// The trace of the policy with the given id, if it is in the policy set
+    pub fn policy(&self, id: &PolicyId) -> Option<&PolicyTrace> {
+        self.policies.iter().find(|trace| &trace.id == id)
+    }
+}
+
+/// How a single policy was evaluated for an authorization request
+#[derive(Debug)]
+pub struct PolicyTrace {
+    id: PolicyId,
+    effect: Effect,
+    scope_matched: bool,
+    conditions: Vec<ConditionTrace>,
+}
+
+impl PolicyTrace {
+    /// The id of the policy
+    pub fn id(&self) -> &PolicyId {
+        &self.id
+    }
+
+    /// The effect of the policy
+    pub fn effect(&self) -> Effect {
+        self.effect
+    }
+
+    /// Whether the request's principal, action, and resource matched the
+    /// policy's scope
+    pub fn scope_matched(&self) -> bool {
+        self.scope_matched
+    }
+
+    /// The `when` and `unless` conditions of the policy which were evaluated,
+    /// in order. Like authorization itself, this stops at the first condition
+    /// which does not pass, so it is empty if the scope did not match, and
+    /// does not include any conditions after one which did not pass.
+    pub fn conditions(&self) -> impl Iterator<Item = &ConditionTrace> {
+        self.conditions.iter()
+    }
+
+    /// Whether the policy was satisfied, i.e., whether it contributed to the
+    /// authorization decision
+    pub fn satisfied(&self) -> bool {
+        self.scope_matched && self.conditions.iter().all(ConditionTrace::passed)
+    }
+}
+
+/// Whether a condition of a policy is a `when` or an `unless` condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionKind {
+    /// A `when` condition, which must evaluate to `true`
+    When,
+    /// An `unless` condition, which must evaluate to `false`
+    Unless,
+}
+
+/// How a single `when` or `unless` condition of a policy was evaluated
+#[derive(Debug)]
+pub struct ConditionTrace {
+    kind: ConditionKind,
+    value: Result<EvalResult, EvaluationError>,
+    attribute_accesses: Vec<AttributeAccess>,
+}
+
+impl ConditionTrace {
+    /// Whether this is a `when` or an `unless` condition
+    pub fn kind(&self) -> ConditionKind {
+        self.kind
+    }
+
+    /// The value the condition evaluated to, or the error evaluating it. For
+    /// an `unless` condition, this is the value of the condition itself, not
+    /// of its negation.
+    pub fn value(&self) -> Result<&EvalResult, &EvaluationError> {
+        self.value.as_ref()
+    }
+
+    /// The attribute accesses performed while evaluating the condition, in
+    /// evaluation order
+    pub fn attribute_accesses(&self) -> impl Iterator<Item = &AttributeAccess> {
+        self.attribute_accesses.iter()
+    }
+
+    /// Whether the condition allows the policy to be satisfied: `true` for a
+    /// `when` condition evaluating to `true`, or an `unless` condition
+    /// evaluating to `false`
+    pub fn passed(&self) -> bool {
+        match (self.kind, &self.value) {
+            (ConditionKind::When, Ok(EvalResult::Bool(b))) => *b,
+            (ConditionKind::Unless, Ok(EvalResult::Bool(b))) => !*b,
+            (_, _) => false,
+        }
+    }
+}
+
+/// An attribute access performed while evaluating a condition
+#[derive(Debug)]
+pub struct AttributeAccess {
+    kind: AttributeAccessKind,
+    target: EvalResult,
+    attribute: String,
+    result: Option<EvalResult>,
+}
+
+impl AttributeAccess {
+    /// Whether the attribute was read (`e.attr`) or tested for (`e has attr`)
+    pub fn kind(&self) -> AttributeAccessKind {
+        self.kind
+    }
+
+    /// The entity or record whose attribute was accessed. For an entity, this
+    /// is the entity's UID.
+    pub fn target(&self) -> &EvalResult {
+        &self.target
+    }
+
+    /// The attribute accessed
+    pub fn attribute(&self) -> &str {
+        &self.attribute
+    }
+
+    /// The attribute's value for [`AttributeAccessKind::Get`], or whether the
+    /// attribute exists for [`AttributeAccessKind::Has`]. This is `None` if the
+    /// access failed, e.g., because the attribute does not exist.
+    pub fn result(&self) -> Option<&EvalResult> {
+        self.result.as_ref()
+    }
+}
+
+impl From<cedar_policy_core::evaluator::AttributeAccess> for AttributeAccess {
+    fn from(access: cedar_policy_core::evaluator::AttributeAccess) -> Self {
+        Self {
+            kind: access.kind,
+            target: access.target.into(),
+            attribute: access.attr.to_string(),
+            result: access.result.map(Into::into),
+        }
+    }
+}
+
+impl Authorizer {
+    /// Returns an authorization response for `r` with respect to the given
+    /// `PolicySet` and `Entities`, like [`Authorizer::is_authorized`], along
+    /// with a trace of how each policy was evaluated: whether its scope
+    /// matched, the value of each of its `when` and `unless` conditions, and
+    /// the attribute accesses performed while evaluating them.
+    ///
+    /// The trace is computed by evaluating each policy again, piece by piece,
+    /// after the response is computed, so this is considerably slower than
+    /// [`Authorizer::is_authorized`] and is meant for debugging. Calls to
+    /// [`Authorizer::is_authorized`] are unaffected.
+    /// ```
+    /// # use cedar_policy::{Authorizer, ConditionKind, Context, Decision, Entities, EntityUid, EvalResult, PolicyId, PolicySet, Request};
+    /// # use std::str::FromStr;
+    /// let request = Request::new(
+    ///     Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+    ///     Some(EntityUid::from_str(r#"Album::"trip""#).unwrap()),
+    ///     Context::empty(),
+    ///     None,
+    /// )
+    /// .unwrap();
+    /// let policies = PolicySet::from_str(
+    ///     r#"permit(principal, action == Action::"view", resource) when { context has ip };"#,
+    /// )
+    /// .unwrap();
+    /// let (response, trace) =
+    ///     Authorizer::new().is_authorized_traced(&request, &policies, &Entities::empty());
+    /// assert_eq!(response.decision(), Decision::Deny);
+    ///
+    /// let policy = trace.policy(&PolicyId::from_str("policy0").unwrap()).unwrap();
+    /// assert!(policy.scope_matched());
+    /// let condition = policy.conditions().next().unwrap();
+    /// assert_eq!(condition.kind(), ConditionKind::When);
+    /// assert_eq!(condition.value().unwrap(), &EvalResult::Bool(false));
+    /// assert_eq!(condition.attribute_accesses().next().unwrap().attribute(), "ip");
+    /// ```
+    pub fn is_authorized_traced(
+        &self,
+        r: &Request,
+        p: &PolicySet,
+        e: &Entities,
+    ) -> (Response, AuthorizationTrace) {
+        let response = self.is_authorized(r, p, e);
+        let extensions = Extensions::all_available();
+        let eval = Evaluator::new(r.0.clone(), &e.0, &extensions);
+        let mut policies = p
+            .policies
+            .iter()
+            .map(|(id, policy)| trace_policy(&eval, id, &policy.ast, &policy.lossless))
+            .collect::<Vec<_>>();
+        policies.sort_by_cached_key(|trace| trace.id.to_string());
+        (response, AuthorizationTrace { policies })
+    }
+}
+
+/// Trace the evaluation of `policy`, whose original form is `lossless`
+fn trace_policy(
+    eval: &Evaluator<'_>,
+    id: &PolicyId,
+    policy: &ast::Policy,
+    lossless: &super::LosslessPolicy,
+) -> PolicyTrace {
+    let slots = policy.env();
+    let scope = ast::Expr::and(
+        policy.principal_constraint().as_expr(),
+        ast::Expr::and(
+            policy.action_constraint().as_expr(),
+            policy.resource_constraint().as_expr(),
+        ),
+    );
+    let scope_matched = matches!(
+        eval.interpret(&scope, slots),
+        Ok(v) if v == ast::Value::from(true)
+    );
+
+    // The AST conjoins all conditions into one expression, so recover the
+    // individual clauses from the EST. If that fails, fall back to treating
+    // the conjunction as a single `when` condition.
+    let clauses = lossless
+        .est()
+        .ok()
+        .and_then(|est| {
+            est.conditions()
+                .map(|clause| {
+                    let (kind, expr) = match clause {
+                        est::Clause::When(expr) => (ConditionKind::When, expr),
+                        est::Clause::Unless(expr) => (ConditionKind::Unless, expr),
+                    };
+                    expr.clone()
+                        .try_into_ast(policy.id().clone())
+                        .ok()
+                        .map(|expr| (kind, expr))
+                })
+                .collect::<Option<Vec<_>>>()
+        })
+        .unwrap_or_else(|| vec![(ConditionKind::When, policy.non_scope_constraints().clone())]);
+
+    let mut conditions = vec![];
+    if scope_matched {
+        for (kind, expr) in clauses {
+            let (value, accesses) = eval.interpret_traced(&expr, slots);
+            let condition = ConditionTrace {
+                kind,
+                value: value.map(Into::into),
+                attribute_accesses: accesses.into_iter().map(Into::into).collect(),
+            };
+            let passed = condition.passed();
+            conditions.push(condition);
+            if !passed {
+                break;
+            }
+        }
+    }
+
+    PolicyTrace {
+        id: id.clone(),
+        effect: policy.effect(),
+        scope_matched,
+        conditions,
+    }
+}
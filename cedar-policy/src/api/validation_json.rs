@@ -0,0 +1,132 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! This module implements `Serialize` for [`ValidationResult`],
+//! [`ValidationError`], and [`ValidationWarning`], for tools which render
+//! validation findings without parsing the error messages.
+//!
+//! A `ValidationResult` is serialized as an object with the fields
+//! - `version`: [`VALIDATION_RESULT_FORMAT_VERSION`]
+//! - `passed`: whether validation passed, i.e., there are no errors
+//! - `errors`: an array with each error
+//! - `warnings`: an array with each warning
+//!
+//! Each error and warning is serialized as an object with the fields
+//! - `code`: the stable code of the error or warning, e.g.,
+//!   `cedar::validation::unrecognized_entity_type`. See
+//!   [`ValidationError::error_code`] and [`ValidationWarning::warning_code`].
+//! - `message`: the error or warning message. This is meant for humans, and
+//!   may change between releases.
+//! - `help`: a suggestion for fixing the problem, or `null`
+//! - `policyId`: the id of the policy the problem was found in
+//! - `sourceSpan`: the part of the policy source the problem was found in, or
+//!   `null` if it isn't known. This is an object with `start` and `end`
+//!   positions, each of which is an object with the byte `offset` into the
+//!   policy source, and the 1-based `line` and `column`. Columns are counted
+//!   in Unicode code points. `end` is exclusive.
+//!
+//! Fields may be added in later releases without changing the version. The
+//! version changes when a field is removed or its meaning changes.
+
+use super::sarif::line_and_column;
+use super::{SourceLocation, ValidationError, ValidationResult, ValidationWarning};
+use miette::Diagnostic;
+use serde::{Serialize, Serializer};
+
+/// The version of the JSON format of [`ValidationResult`], which is its
+/// `version` field
+pub const VALIDATION_RESULT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct ValidationResultJson<'a> {
+    version: u32,
+    passed: bool,
+    errors: Vec<&'a ValidationError>,
+    warnings: Vec<&'a ValidationWarning>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FindingJson<'a> {
+    code: &'static str,
+    message: String,
+    help: Option<String>,
+    policy_id: &'a str,
+    source_span: Option<SpanJson>,
+}
+
+#[derive(Serialize)]
+struct SpanJson {
+    start: PositionJson,
+    end: PositionJson,
+}
+
+#[derive(Serialize)]
+struct PositionJson {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> FindingJson<'a> {
+    fn new(code: &'static str, diagnostic: &dyn Diagnostic, location: &'a SourceLocation) -> Self {
+        Self {
+            code,
+            message: diagnostic.to_string(),
+            help: diagnostic.help().map(|help| help.to_string()),
+            policy_id: location.policy_id().as_ref(),
+            source_span: location.source_loc().map(|loc| SpanJson {
+                start: PositionJson::new(&loc.src, loc.start()),
+                end: PositionJson::new(&loc.src, loc.end()),
+            }),
+        }
+    }
+}
+
+impl PositionJson {
+    fn new(src: &str, offset: usize) -> Self {
+        let (line, column) = line_and_column(src, offset);
+        Self {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl Serialize for ValidationResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ValidationResultJson {
+            version: VALIDATION_RESULT_FORMAT_VERSION,
+            passed: self.validation_passed(),
+            errors: self.validation_errors().collect(),
+            warnings: self.validation_warnings().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl Serialize for ValidationError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FindingJson::new(self.error_code(), self, self.location()).serialize(serializer)
+    }
+}
+
+impl Serialize for ValidationWarning {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FindingJson::new(self.warning_code(), self, self.location()).serialize(serializer)
+    }
+}
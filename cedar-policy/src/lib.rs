@@ -42,9 +42,47 @@ mod api;
 
 pub use api::*;
 
+/// Build a `Context` from a struct, for example:
+/// ```
+/// use cedar_policy::{Context, IntoContext, IpAddr};
+///
+/// #[derive(IntoContext)]
+/// struct Device {
+///     ip: IpAddr,
+///     managed: bool,
+/// }
+///
+/// #[derive(IntoContext)]
+/// struct RequestContext {
+///     device: Device,
+///     #[cedar(rename = "mfa-age")]
+///     mfa_age: Option<i64>,
+///     scopes: Vec<String>,
+/// }
+///
+/// let context: Context = RequestContext {
+///     device: Device { ip: IpAddr("10.0.0.1".into()), managed: true },
+///     mfa_age: Some(30),
+///     scopes: vec!["read".into()],
+/// }
+/// .try_into()
+/// .unwrap();
+/// ```
+#[cfg(feature = "derive")]
+pub use cedar_policy_derive::IntoContext;
+
+/// Items used by the code generated by `#[derive(IntoContext)]`, which are
+/// not part of the public API
+#[doc(hidden)]
+pub mod __private {
+    pub use cedar_policy_core::ast::ContextCreationError;
+}
+
 /// FFI utilities, see comments in the module itself
 pub mod ffi;
 
+pub mod transitive_closure;
+
 mod prop_test_policy_set;
 mod tests;
 
@@ -246,16 +246,96 @@ permit(principal ==  A :: B
         assert_eq!(reparsed.id().as_ref(), r"b'ob");
     }
 
+    /// common mistakes in `EntityUid` strings are reported with their offsets
     #[test]
-    fn accessing_unspecified_entity_returns_none() {
+    fn parse_euid_errors() {
+        assert_matches!(
+            EntityUid::from_str("alice"),
+            Err(EntityUidParseError::MissingSeparator { offset: 5 })
+        );
+        assert_matches!(
+            EntityUid::from_str(r#"User:"alice""#),
+            Err(EntityUidParseError::MissingSeparator { offset: 4 })
+        );
+        assert_matches!(
+            EntityUid::from_str(r#"User"alice""#),
+            Err(EntityUidParseError::MissingSeparator { offset: 4 })
+        );
+        assert_matches!(
+            EntityUid::from_str(r#"User::"alice"#),
+            Err(EntityUidParseError::UnterminatedString { offset: 6 })
+        );
+        assert_matches!(
+            EntityUid::from_str(r#"User::"al\"ice"#),
+            Err(EntityUidParseError::UnterminatedString { offset: 6 })
+        );
+        assert_matches!(
+            EntityUid::from_str(r#"My-App::User::"alice""#),
+            Err(EntityUidParseError::InvalidTypeName { type_name, offset: 0 }) => {
+                assert_eq!(type_name, "My-App::User");
+            }
+        );
+        assert_matches!(
+            EntityUid::from_str(r#"User::"alice" extra"#),
+            Err(EntityUidParseError::Other(_))
+        );
+
+        let err = EntityUid::from_str(r#"User:"alice""#).unwrap_err();
+        assert_eq!(err.offset(), Some(4));
+        expect_err(
+            "",
+            &Report::new(err),
+            &ExpectedErrorMessageBuilder::error(
+                "expected `::` between the entity type and the entity id",
+            )
+            .help(r#"entity uids are written as an entity type, `::`, and a quoted entity id, e.g., `User::"alice"`"#)
+            .build(),
+        );
+        assert_eq!(
+            EntityUid::from_str(r#"User::"alice" extra"#)
+                .unwrap_err()
+                .offset(),
+            None
+        );
+    }
+
+    #[test]
+    fn accessing_unspecified_entity_returns_unspecified() {
         let c = Context::empty();
         let request = Request::new(None, None, None, c, None).unwrap();
         let p = request.principal();
         let a = request.action();
         let r = request.resource();
-        assert_matches!(p, None);
-        assert_matches!(a, None);
-        assert_matches!(r, None);
+        assert_matches!(p, RequestComponent::Unspecified);
+        assert_matches!(a, RequestComponent::Unspecified);
+        assert_matches!(r, RequestComponent::Unspecified);
+        assert_eq!(p.concrete(), None);
+    }
+
+    #[test]
+    fn accessing_specified_entity_returns_concrete() {
+        let alice = EntityUid::from_strs("User", "alice");
+        let request =
+            Request::new(Some(alice.clone()), None, None, Context::empty(), None).unwrap();
+        assert_eq!(
+            request.principal(),
+            RequestComponent::Concrete(alice.clone())
+        );
+        assert_eq!(request.principal().concrete(), Some(&alice));
+        assert_eq!(request.action(), RequestComponent::Unspecified);
+    }
+
+    #[cfg(feature = "partial-eval")]
+    #[test]
+    fn accessing_unknown_entity_returns_unknown() {
+        let alice = EntityUid::from_strs("User", "alice");
+        let request = Request::builder()
+            .principal(Some(alice.clone()))
+            .action(None)
+            .build();
+        assert_eq!(request.principal(), RequestComponent::Concrete(alice));
+        assert_eq!(request.action(), RequestComponent::Unspecified);
+        assert_eq!(request.resource(), RequestComponent::Unknown);
     }
 }
 
@@ -545,6 +625,76 @@ mod policy_set_tests {
         );
     }
 
+    #[test]
+    fn template_link_without_policy_set() {
+        let template = Template::parse(
+            Some("t".into()),
+            "permit(principal == ?principal, action, resource in ?resource);",
+        )
+        .expect("Failed to parse");
+        let principal = EntityUid::from_strs("User", "alice");
+        let resource = EntityUid::from_strs("Folder", "docs");
+        let env: HashMap<SlotId, EntityUid> = [
+            (SlotId::principal(), principal.clone()),
+            (SlotId::resource(), resource.clone()),
+        ]
+        .into_iter()
+        .collect();
+
+        let linked = template.link(env.clone()).expect("Failed to link");
+        assert_eq!(linked.id(), &PolicyId::from_str("t").unwrap());
+        assert_eq!(
+            linked.template_id(),
+            Some(&PolicyId::from_str("t").unwrap())
+        );
+        assert_eq!(linked.template_links(), Some(env));
+        assert_eq!(
+            linked.principal_constraint(),
+            PrincipalConstraint::Eq(principal)
+        );
+        assert_eq!(
+            linked.resource_constraint(),
+            ResourceConstraint::In(resource)
+        );
+        let json = linked.to_json().expect("Failed to convert to JSON");
+        assert_eq!(
+            json["principal"],
+            serde_json::json!({"op": "==", "entity": {"__entity": {"type": "User", "id": "alice"}}})
+        );
+
+        let renamed = linked.new_id(PolicyId::from_str("preview").unwrap());
+        assert_eq!(renamed.id(), &PolicyId::from_str("preview").unwrap());
+    }
+
+    #[test]
+    fn template_link_without_policy_set_arity() {
+        let template = Template::parse(
+            Some("t".into()),
+            "permit(principal == ?principal, action, resource);",
+        )
+        .expect("Failed to parse");
+
+        let err = template.link(HashMap::new()).unwrap_err();
+        assert_eq!(
+            err.unbound_slots().collect::<Vec<_>>(),
+            vec![SlotId::principal()]
+        );
+        assert_eq!(err.extra_slots().count(), 0);
+
+        let env: HashMap<SlotId, EntityUid> = [
+            (SlotId::principal(), EntityUid::from_strs("User", "alice")),
+            (SlotId::resource(), EntityUid::from_strs("Folder", "docs")),
+        ]
+        .into_iter()
+        .collect();
+        let err = template.link(env).unwrap_err();
+        assert_eq!(err.unbound_slots().count(), 0);
+        assert_eq!(
+            err.extra_slots().collect::<Vec<_>>(),
+            vec![SlotId::resource()]
+        );
+    }
+
     #[test]
     fn link_conflicts() {
         let mut pset = PolicySet::new();
@@ -1370,6 +1520,218 @@ mod policy_set_tests {
             ))
         );
     }
+
+    #[test]
+    fn template_links_and_unlinked_templates() {
+        let mut pset = PolicySet::from_str_with_ids(
+            r#"
+            @id("static")
+            permit(principal, action, resource);
+            @id("t0")
+            permit(principal == ?principal, action, resource);
+            @id("t1")
+            @library("shared")
+            forbid(principal, action, resource in ?resource);
+            @id("t2")
+            permit(principal in ?principal, action, resource);
+            "#,
+        )
+        .unwrap();
+        let id = |s: &str| PolicyId::from_str(s).unwrap();
+        let link = |pset: &mut PolicySet, template: &str, new_id: &str| {
+            pset.link(
+                id(template),
+                id(new_id),
+                HashMap::from([(SlotId::principal(), EntityUid::from_strs("User", "a"))]),
+            )
+            .unwrap();
+        };
+        link(&mut pset, "t0", "link1");
+        link(&mut pset, "t0", "link0");
+
+        assert_eq!(
+            pset.template_links(&id("t0")),
+            vec![id("link0"), id("link1")]
+        );
+        assert_eq!(pset.template_links(&id("t2")), vec![]);
+        // neither a static policy nor a missing template has links
+        assert_eq!(pset.template_links(&id("static")), vec![]);
+        assert_eq!(pset.template_links(&id("missing")), vec![]);
+
+        assert_eq!(pset.unlinked_templates(), vec![id("t1"), id("t2")]);
+        assert_eq!(
+            pset.unlinked_templates_except_annotated("library"),
+            vec![id("t2")]
+        );
+        assert_eq!(
+            pset.unlinked_templates_except_annotated("other"),
+            vec![id("t1"), id("t2")]
+        );
+
+        link(&mut pset, "t2", "link2");
+        assert_eq!(pset.unlinked_templates(), vec![id("t1")]);
+        pset.unlink(id("link2")).unwrap();
+        assert_eq!(pset.unlinked_templates(), vec![id("t1"), id("t2")]);
+    }
+
+    #[test]
+    fn linked_policies() {
+        let mut pset = PolicySet::from_str_with_ids(
+            r#"
+            @id("static")
+            permit(principal, action, resource);
+            @id("t0")
+            permit(principal == ?principal, action, resource);
+            @id("t1")
+            permit(principal in ?principal, action, resource);
+            "#,
+        )
+        .unwrap();
+        let id = |s: &str| PolicyId::from_str(s).unwrap();
+        for (template, new_id) in [("t0", "b"), ("t1", "c"), ("t0", "a")] {
+            pset.link(
+                id(template),
+                id(new_id),
+                HashMap::from([(SlotId::principal(), EntityUid::from_strs("User", new_id))]),
+            )
+            .unwrap();
+        }
+
+        let links = pset.linked_policies(&id("t0")).unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            links.iter().map(|policy| policy.id()).collect::<Vec<_>>(),
+            vec![&id("a"), &id("b")]
+        );
+        assert_eq!(
+            links[0].template_links(),
+            Some(HashMap::from([(
+                SlotId::principal(),
+                EntityUid::from_strs("User", "a")
+            )]))
+        );
+
+        pset.unlink(id("c")).unwrap();
+        assert_eq!(pset.linked_policies(&id("t1")).unwrap().count(), 0);
+
+        assert_matches!(
+            pset.linked_policies(&id("static")).map(Iterator::count),
+            Err(PolicySetError::ExpectedTemplate)
+        );
+        assert_matches!(
+            pset.linked_policies(&id("a")).map(Iterator::count),
+            Err(PolicySetError::ExpectedTemplate)
+        );
+        assert_matches!(
+            pset.linked_policies(&id("missing")).map(Iterator::count),
+            Err(PolicySetError::TemplateNonexistentError(missing)) if missing == id("missing")
+        );
+    }
+
+    #[test]
+    fn optimize() {
+        let src = r#"
+            @id("folded")
+            permit(principal, action, resource) when { true && resource.public };
+            @id("unchanged")
+            forbid(principal, action, resource) when { resource.secret };
+            @id("error")
+            forbid(principal, action, resource) unless { 1 + "a" == 2 };
+            @id("t")
+            permit(principal == ?principal, action, resource)
+            when { if [1, 2].contains(1) then context.a else context.b };
+        "#;
+        let mut pset = PolicySet::from_str_with_ids(src).unwrap();
+        let id = |s: &str| PolicyId::from_str(s).unwrap();
+        pset.link(
+            id("t"),
+            id("link"),
+            HashMap::from([(SlotId::principal(), EntityUid::from_strs("User", "alice"))]),
+        )
+        .unwrap();
+        let original = pset.clone();
+        pset.optimize();
+
+        let expected = Policy::parse(
+            Some("folded".into()),
+            r#"@id("folded") permit(principal, action, resource) when { resource.public };"#,
+        )
+        .unwrap();
+        assert_eq!(
+            pset.policy(&id("folded")).unwrap().to_json().unwrap(),
+            expected.to_json().unwrap()
+        );
+        assert_eq!(
+            pset.policy(&id("unchanged")).unwrap().to_string(),
+            original.policy(&id("unchanged")).unwrap().to_string()
+        );
+        let expected = Template::parse(
+            Some("t".into()),
+            r#"@id("t") permit(principal == ?principal, action, resource) when { context.a };"#,
+        )
+        .unwrap();
+        assert_eq!(
+            pset.template(&id("t")).unwrap().to_json().unwrap(),
+            expected.to_json().unwrap()
+        );
+        let link = pset.policy(&id("link")).unwrap();
+        assert_eq!(link.template_id(), Some(&id("t")));
+        assert_eq!(
+            link.template_links(),
+            original.policy(&id("link")).unwrap().template_links()
+        );
+        // the optimized policies round-trip through their text and JSON
+        assert!(PolicySet::from_str(&pset.to_string()).is_ok());
+        assert!(PolicySet::from_json_value(pset.clone().to_json().unwrap()).is_ok());
+
+        // the optimized policies make the same decisions, with the same errors
+        let authorizer = Authorizer::new();
+        for (public, secret, a) in [
+            (true, false, true),
+            (true, true, false),
+            (false, false, true),
+        ] {
+            let request = Request::new(
+                Some(EntityUid::from_strs("User", "alice")),
+                Some(EntityUid::from_strs("Action", "view")),
+                Some(EntityUid::from_strs("Doc", "d")),
+                Context::from_pairs([
+                    ("a".into(), RestrictedExpression::new_bool(a)),
+                    ("b".into(), RestrictedExpression::new_bool(!a)),
+                ])
+                .unwrap(),
+                None,
+            )
+            .unwrap();
+            let entities = Entities::from_json_value(
+                serde_json::json!([{
+                    "uid": { "type": "Doc", "id": "d" },
+                    "attrs": { "public": public, "secret": secret },
+                    "parents": []
+                }]),
+                None,
+            )
+            .unwrap();
+            let before = authorizer.is_authorized(&request, &original, &entities);
+            let after = authorizer.is_authorized(&request, &pset, &entities);
+            assert_eq!(before.decision(), after.decision());
+            assert_eq!(
+                before.diagnostics().reason().collect::<HashSet<_>>(),
+                after.diagnostics().reason().collect::<HashSet<_>>()
+            );
+            assert_eq!(
+                before
+                    .diagnostics()
+                    .errors()
+                    .map(AuthorizationError::id)
+                    .collect::<HashSet<_>>(),
+                after
+                    .diagnostics()
+                    .errors()
+                    .map(AuthorizationError::id)
+                    .collect::<HashSet<_>>()
+            );
+        }
+    }
 }
 
 mod schema_tests {
@@ -1479,91 +1841,447 @@ mod schema_tests {
             Err(SchemaError::Serde(_))
         );
     }
-}
 
-mod ancestors_tests {
-    use super::*;
+    fn schema_with_context_defaults() -> Schema {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            entity User, Photo;
+            action view appliesTo {
+                principal: User,
+                resource: Photo,
+                context: {
+                    authenticated: Bool,
+                    @default("false") mfa?: Bool,
+                    region?: String,
+                }
+            };
+            "#,
+        )
+        .expect("schema should be valid");
+        schema
+    }
 
     #[test]
-    fn test_ancestors() {
-        let a_euid: EntityUid = EntityUid::from_strs("test", "A");
-        let b_euid: EntityUid = EntityUid::from_strs("test", "b");
-        let c_euid: EntityUid = EntityUid::from_strs("test", "C");
-        let a = Entity::new_no_attrs(a_euid.clone(), HashSet::new());
-        let b = Entity::new_no_attrs(b_euid.clone(), std::iter::once(a_euid.clone()).collect());
-        let c = Entity::new_no_attrs(c_euid.clone(), std::iter::once(b_euid.clone()).collect());
-        let es = Entities::from_entities([a, b, c], None).unwrap();
-        let ans = es.ancestors(&c_euid).unwrap().collect::<HashSet<_>>();
-        assert_eq!(ans.len(), 2);
-        assert!(ans.contains(&b_euid));
-        assert!(ans.contains(&a_euid));
+    fn context_defaults() {
+        let schema = schema_with_context_defaults();
+        let view = EntityUid::from_str(r#"Action::"view""#).unwrap();
+        let defaults = schema.context_defaults(&view).unwrap();
+        let request = Request::new(None, None, None, defaults, None).unwrap();
+        assert_eq!(
+            eval_expression(
+                &request,
+                &Entities::empty(),
+                &Expression::from_str("context == { mfa: false }").unwrap()
+            )
+            .unwrap(),
+            EvalResult::Bool(true)
+        );
+
+        let undeclared = EntityUid::from_str(r#"Action::"edit""#).unwrap();
+        assert!(schema.context_defaults(&undeclared).is_none());
     }
-}
 
-/// A few tests of validating entities.
-/// Many other validation-related tests are in the separate module focusing on
-/// schema-based parsing.
-mod entity_validate_tests {
-    use super::*;
-    use entities::err::EntitiesError;
-    use serde_json::json;
+    #[test]
+    fn request_fills_context_defaults() {
+        let schema = schema_with_context_defaults();
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { context.mfa == false };"#,
+        )
+        .unwrap();
+        let request = |context: Context, schema: Option<&Schema>| {
+            Request::new(
+                Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+                Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+                Some(EntityUid::from_str(r#"Photo::"vacation""#).unwrap()),
+                context,
+                schema,
+            )
+            .unwrap()
+        };
+        let authenticated = || {
+            Context::from_pairs([("authenticated".into(), RestrictedExpression::new_bool(true))])
+                .unwrap()
+        };
 
-    fn schema() -> Schema {
-        Schema::from_json_value(json!(
-        {"": {
-            "entityTypes": {
-                "Employee": {
-                    "memberOfTypes": [],
-                    "shape": {
-                        "type": "Record",
-                        "attributes": {
-                            "isFullTime": { "type": "Boolean" },
-                            "numDirectReports": { "type": "Long" },
-                            "department": { "type": "String" },
-                            "manager": { "type": "Entity", "name": "Employee" },
-                            "hr_contacts": { "type": "Set", "element": {
-                                "type": "Entity", "name": "HR" } },
-                            "json_blob": { "type": "Record", "attributes": {
-                                "inner1": { "type": "Boolean" },
-                                "inner2": { "type": "String" },
-                                "inner3": { "type": "Record", "attributes": {
-                                    "innerinner": { "type": "Entity", "name": "Employee" }
-                                }}
-                            }},
-                            "home_ip": { "type": "Extension", "name": "ipaddr" },
-                            "work_ip": { "type": "Extension", "name": "ipaddr" },
-                            "trust_score": { "type": "Extension", "name": "decimal" },
-                            "tricky": { "type": "Record", "attributes": {
-                                "type": { "type": "String" },
-                                "id": { "type": "String" }
-                            }}
-                        }
-                    }
-                },
-                "HR": {
-                    "memberOfTypes": []
-                }
-            },
-            "actions": {
-                "view": { }
-            }
-        }}
-        ))
-        .expect("should be a valid schema")
-    }
+        // With the schema, the omitted `mfa` attribute takes its default
+        let response = Authorizer::new().is_authorized(
+            &request(authenticated(), Some(&schema)),
+            &policies,
+            &Entities::empty(),
+        );
+        assert_eq!(response.decision(), Decision::Allow);
 
-    fn validate_entity(entity: Entity, schema: &Schema) -> Result<(), EntitiesError> {
-        let _ = Entities::from_entities([entity], Some(schema))?;
-        Ok(())
+        // Without the schema, `context.mfa` is missing and the policy errors
+        let response = Authorizer::new().is_authorized(
+            &request(authenticated(), None),
+            &policies,
+            &Entities::empty(),
+        );
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(response.diagnostics().errors().count(), 1);
+
+        // An explicitly provided value is not replaced by the default
+        let context = Context::from_pairs([
+            ("authenticated".into(), RestrictedExpression::new_bool(true)),
+            ("mfa".into(), RestrictedExpression::new_bool(true)),
+        ])
+        .unwrap();
+        let response = Authorizer::new().is_authorized(
+            &request(context, Some(&schema)),
+            &policies,
+            &Entities::empty(),
+        );
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(response.diagnostics().errors().count(), 0);
     }
 
     #[test]
-    fn valid_entity() {
-        let entity = Entity::new(
-            EntityUid::from_strs("Employee", "123"),
-            HashMap::from_iter([
-                ("isFullTime".into(), RestrictedExpression::new_bool(false)),
-                ("numDirectReports".into(), RestrictedExpression::new_long(3)),
+    fn principals_and_resources_for_action() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            entity User, Group, Photo, Album;
+            action view appliesTo { principal: [User, Group], resource: [Photo, Album] };
+            action share appliesTo { principal: User, resource: Photo };
+            action noop;
+            "#,
+        )
+        .expect("schema should be valid");
+        fn names<'a>(
+            types: Option<impl Iterator<Item = &'a EntityTypeName>>,
+        ) -> Option<Vec<String>> {
+            types.map(|types| {
+                let mut names = types.map(ToString::to_string).collect::<Vec<_>>();
+                names.sort();
+                names
+            })
+        }
+        let action = |name: &str| {
+            EntityUid::from_type_name_and_id(
+                EntityTypeName::from_str("Action").unwrap(),
+                EntityId::from_str(name).unwrap(),
+            )
+        };
+
+        let view = action("view");
+        assert_eq!(
+            names(schema.principals_for_action(&view)),
+            Some(vec!["Group".to_string(), "User".to_string()])
+        );
+        assert_eq!(
+            names(schema.resources_for_action(&view)),
+            Some(vec!["Album".to_string(), "Photo".to_string()])
+        );
+
+        let share = action("share");
+        assert_eq!(
+            names(schema.principals_for_action(&share)),
+            Some(vec!["User".to_string()])
+        );
+
+        // declared without any principals or resources
+        let noop = action("noop");
+        assert_eq!(schema.principals_for_action(&noop).unwrap().count(), 0);
+        assert_eq!(schema.resources_for_action(&noop).unwrap().count(), 0);
+
+        // not declared
+        let edit = action("edit");
+        assert!(schema.principals_for_action(&edit).is_none());
+        assert!(schema.resources_for_action(&edit).is_none());
+    }
+
+    #[test]
+    fn action_descendants() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            entity User, Photo;
+            action manage;
+            action edit in [manage];
+            action view in [manage] appliesTo { principal: User, resource: Photo };
+            action rename, delete in [edit] appliesTo { principal: User, resource: Photo };
+            "#,
+        )
+        .expect("schema should be valid");
+        let action = |name: &str| EntityUid::from_strs("Action", name);
+        let members = |name: &str| {
+            schema.action_descendants(&action(name)).map(|members| {
+                let mut members = members.map(|a| a.id().to_string()).collect::<Vec<_>>();
+                members.sort();
+                members
+            })
+        };
+
+        assert_eq!(
+            members("manage"),
+            Some(vec![
+                "delete".to_string(),
+                "edit".to_string(),
+                "rename".to_string(),
+                "view".to_string()
+            ])
+        );
+        assert_eq!(
+            members("edit"),
+            Some(vec!["delete".to_string(), "rename".to_string()])
+        );
+        assert_eq!(members("view"), Some(vec![]));
+        assert_eq!(members("share"), None);
+    }
+
+    #[test]
+    fn to_json_and_human_string() {
+        let src = r#"
+            namespace App {
+                type Name = String;
+                entity Group;
+                entity User in [Group] = { name: Name, manager?: User };
+                action view appliesTo { principal: User, resource: Group, context: { by: Name } };
+            }
+        "#;
+        let (schema, _) = Schema::from_str_natural(src).expect("schema should be valid");
+
+        let json = schema.to_json_string().unwrap();
+        let from_json = Schema::from_str(&json).expect("schema should be valid");
+        assert_eq!(from_json, schema);
+        // common types are kept
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            json["App"]["commonTypes"]["Name"],
+            json!({ "type": "String" })
+        );
+
+        let human = schema.to_human_string().unwrap();
+        assert!(human.contains("type Name = __cedar::String;"), "{human}");
+        let (from_human, _) = Schema::from_str_natural(&human).expect("schema should be valid");
+        assert_eq!(from_human, schema);
+        assert_eq!(from_human.to_human_string().unwrap().len(), human.len());
+    }
+
+    #[test]
+    fn to_json_string_merges_fragments() {
+        let (users, _) = SchemaFragment::from_str_natural(
+            "namespace App { entity User; action view appliesTo { principal: User, resource: Doc }; }",
+        )
+        .unwrap();
+        let (docs, _) = SchemaFragment::from_str_natural("namespace App { entity Doc; }").unwrap();
+        let schema = Schema::from_schema_fragments([users, docs]).expect("schema should be valid");
+        let json: serde_json::Value =
+            serde_json::from_str(&schema.to_json_string().unwrap()).unwrap();
+        let mut entity_types = json["App"]["entityTypes"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        entity_types.sort();
+        assert_eq!(entity_types, vec!["Doc", "User"]);
+        assert_eq!(
+            Schema::from_json_value(json).expect("schema should be valid"),
+            schema
+        );
+    }
+
+    #[test]
+    fn rebased_to_json_string() {
+        let (schema, _) = Schema::from_str_natural(
+            "namespace Vendor { entity User; action read appliesTo { principal: User, resource: User }; }",
+        )
+        .unwrap();
+        let from = EntityNamespace::from_str("Vendor").unwrap();
+        let to = EntityNamespace::from_str("MyApp::Vendor").unwrap();
+        let rebased = schema.rebase_namespace(&from, &to).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&rebased.to_json_string().unwrap()).unwrap();
+        assert!(json["MyApp::Vendor"]["entityTypes"]["User"].is_object());
+        assert!(json.get("Vendor").is_none());
+        assert_eq!(
+            Schema::from_json_value(json).expect("schema should be valid"),
+            rebased
+        );
+    }
+
+    #[test]
+    fn compatibility() {
+        let (older, _) = Schema::from_str_natural(
+            r#"
+            entity User = { name: String };
+            entity Photo;
+            action view appliesTo { principal: User, resource: Photo };
+            "#,
+        )
+        .expect("schema should be valid");
+        let (newer, _) = Schema::from_str_natural(
+            r#"
+            entity User = { name: String, email?: String };
+            action view appliesTo { principal: User, resource: User };
+            "#,
+        )
+        .expect("schema should be valid");
+
+        let report = older.compatibility(&older);
+        assert!(report.is_compatible());
+        assert_eq!(report.changes().count(), 0);
+
+        let report = older.compatibility(&newer);
+        assert!(!report.is_compatible());
+        assert_eq!(
+            report.breaking_changes().cloned().collect::<Vec<_>>(),
+            vec![
+                SchemaChange::EntityTypeRemoved {
+                    entity_type: "Photo".to_string()
+                },
+                SchemaChange::AppliesToTypeRemoved {
+                    action: r#"Action::"view""#.to_string(),
+                    variable: "resource".to_string(),
+                    entity_type: "Photo".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({
+                "changes": [
+                    { "kind": "entityTypeRemoved", "entityType": "Photo" },
+                    {
+                        "kind": "attributeAdded",
+                        "owner": { "kind": "entityType", "name": "User" },
+                        "path": ["email"],
+                        "required": false
+                    },
+                    {
+                        "kind": "appliesToTypeRemoved",
+                        "action": "Action::\"view\"",
+                        "variable": "resource",
+                        "entityType": "Photo"
+                    },
+                    {
+                        "kind": "appliesToTypeAdded",
+                        "action": "Action::\"view\"",
+                        "variable": "resource",
+                        "entityType": "User"
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn applicable_actions() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            entity Group;
+            entity User in [Group];
+            entity Document;
+            action read, write appliesTo { principal: User, resource: Document };
+            action manage appliesTo { principal: Group, resource: Document };
+            action invite appliesTo { principal: User, resource: User };
+            action noop;
+            "#,
+        )
+        .expect("schema should be valid");
+        let actions = |principal_ty: &str, resource_ty: &str| {
+            let mut actions = schema
+                .applicable_actions(
+                    &EntityTypeName::from_str(principal_ty).unwrap(),
+                    &EntityTypeName::from_str(resource_ty).unwrap(),
+                )
+                .map(|action| action.id().as_ref().to_string())
+                .collect::<Vec<_>>();
+            actions.sort();
+            actions
+        };
+
+        assert_eq!(actions("User", "Document"), vec!["read", "write"]);
+        assert_eq!(actions("User", "User"), vec!["invite"]);
+        // `User` is a member of `Group`, but not a subtype of it
+        assert_eq!(actions("Group", "Document"), vec!["manage"]);
+        assert!(actions("Document", "User").is_empty());
+        assert!(actions("Undeclared", "Document").is_empty());
+    }
+}
+
+mod ancestors_tests {
+    use super::*;
+
+    #[test]
+    fn test_ancestors() {
+        let a_euid: EntityUid = EntityUid::from_strs("test", "A");
+        let b_euid: EntityUid = EntityUid::from_strs("test", "b");
+        let c_euid: EntityUid = EntityUid::from_strs("test", "C");
+        let a = Entity::new_no_attrs(a_euid.clone(), HashSet::new());
+        let b = Entity::new_no_attrs(b_euid.clone(), std::iter::once(a_euid.clone()).collect());
+        let c = Entity::new_no_attrs(c_euid.clone(), std::iter::once(b_euid.clone()).collect());
+        let es = Entities::from_entities([a, b, c], None).unwrap();
+        let ans = es.ancestors(&c_euid).unwrap().collect::<HashSet<_>>();
+        assert_eq!(ans.len(), 2);
+        assert!(ans.contains(&b_euid));
+        assert!(ans.contains(&a_euid));
+    }
+}
+
+/// A few tests of validating entities.
+/// Many other validation-related tests are in the separate module focusing on
+/// schema-based parsing.
+mod entity_validate_tests {
+    use super::*;
+    use entities::err::EntitiesError;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_json_value(json!(
+        {"": {
+            "entityTypes": {
+                "Employee": {
+                    "memberOfTypes": [],
+                    "shape": {
+                        "type": "Record",
+                        "attributes": {
+                            "isFullTime": { "type": "Boolean" },
+                            "numDirectReports": { "type": "Long" },
+                            "department": { "type": "String" },
+                            "manager": { "type": "Entity", "name": "Employee" },
+                            "hr_contacts": { "type": "Set", "element": {
+                                "type": "Entity", "name": "HR" } },
+                            "json_blob": { "type": "Record", "attributes": {
+                                "inner1": { "type": "Boolean" },
+                                "inner2": { "type": "String" },
+                                "inner3": { "type": "Record", "attributes": {
+                                    "innerinner": { "type": "Entity", "name": "Employee" }
+                                }}
+                            }},
+                            "home_ip": { "type": "Extension", "name": "ipaddr" },
+                            "work_ip": { "type": "Extension", "name": "ipaddr" },
+                            "trust_score": { "type": "Extension", "name": "decimal" },
+                            "tricky": { "type": "Record", "attributes": {
+                                "type": { "type": "String" },
+                                "id": { "type": "String" }
+                            }}
+                        }
+                    }
+                },
+                "HR": {
+                    "memberOfTypes": []
+                }
+            },
+            "actions": {
+                "view": { }
+            }
+        }}
+        ))
+        .expect("should be a valid schema")
+    }
+
+    fn validate_entity(entity: Entity, schema: &Schema) -> Result<(), EntitiesError> {
+        let _ = Entities::from_entities([entity], Some(schema))?;
+        Ok(())
+    }
+
+    #[test]
+    fn valid_entity() {
+        let entity = Entity::new(
+            EntityUid::from_strs("Employee", "123"),
+            HashMap::from_iter([
+                ("isFullTime".into(), RestrictedExpression::new_bool(false)),
+                ("numDirectReports".into(), RestrictedExpression::new_long(3)),
                 (
                     "department".into(),
                     RestrictedExpression::new_string("Sales".into()),
@@ -3145,6 +3863,74 @@ mod partial_schema {
         )
         .unwrap();
     }
+
+    #[test]
+    fn nested_record_extra_attr() {
+        let schema = Schema::from_json_value(json!(
+        {
+            "": {
+                "entityTypes": {
+                    "Employee": {
+                        "shape": {
+                            "type": "Record",
+                            "attributes": {
+                                "details": {
+                                    "type": "Record",
+                                    "attributes": { "level": { "type": "Long" } },
+                                    "additionalAttributes": true,
+                                },
+                            },
+                            "additionalAttributes": true,
+                        },
+                    }
+                },
+                "actions": {
+                    "view": {
+                        "appliesTo": {
+                            "principalTypes": ["Employee"],
+                            "resourceTypes": ["Employee"],
+                        }
+                    }
+                }
+            }
+        }
+        ))
+        .unwrap();
+        let entitiesjson = json!(
+            [
+                {
+                    "uid": { "type": "Employee", "id": "alice" },
+                    "attrs": {
+                        "details": { "level": 3, "agency": "Acme" },
+                        "tags": ["onsite"]
+                    },
+                    "parents": []
+                }
+            ]
+        );
+        let entities = Entities::from_json_value(entitiesjson, Some(&schema))
+            .expect("Parsing with a partial schema should allow unknown attributes.");
+
+        let alice = EntityUid::from_strs("Employee", "alice");
+        let request = Request::new(
+            Some(alice.clone()),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(alice),
+            Context::empty(),
+            Some(&schema),
+        )
+        .unwrap();
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action, resource) when {
+                principal.details.level == 3 &&
+                principal.details.agency == "Acme" &&
+                principal.tags.contains("onsite")
+            };"#,
+        )
+        .unwrap();
+        let response = Authorizer::new().is_authorized(&request, &policies, &entities);
+        assert_eq!(response.decision(), Decision::Allow);
+    }
 }
 
 mod template_tests {
@@ -3170,6 +3956,30 @@ mod template_tests {
             "permit(principal == ?principal, action, resource in ?resource);".to_string()
         );
     }
+
+    #[test]
+    fn test_policy_template_slot_ids() {
+        use crate::SlotId;
+
+        let slot_ids = |src| {
+            Template::parse(None, src)
+                .unwrap()
+                .slot_ids()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            slot_ids("permit(principal, action, resource in ?resource);"),
+            vec![SlotId::resource()]
+        );
+        assert_eq!(
+            slot_ids("permit(principal == ?principal, action, resource in ?resource);"),
+            vec![SlotId::principal(), SlotId::resource()]
+        );
+        assert_eq!(
+            slot_ids(r#"permit(principal, action, resource) when { principal == User::"alice" };"#),
+            vec![]
+        );
+    }
 }
 
 mod issue_326 {
@@ -3710,6 +4520,50 @@ mod decimal_ip_constructors {
                 )
         );
     }
+
+    #[test]
+    fn source_ip_in_range_and_not_loopback() {
+        let expr = Expression::from_str(
+            r#"context.source_ip.isInRange(ip("10.0.0.0/8")) && !context.source_ip.isLoopback()"#,
+        )
+        .unwrap();
+        let evaluate = |source_ip: &str| {
+            let context = Context::from_pairs([(
+                "source_ip".into(),
+                RestrictedExpression::new_ip(source_ip),
+            )])
+            .unwrap();
+            let r = Request::new(None, None, None, context, None).unwrap();
+            eval_expression(&r, &Entities::empty(), &expr)
+        };
+        assert_matches!(evaluate("10.1.2.3"), Ok(EvalResult::Bool(true)));
+        assert_matches!(evaluate("11.1.2.3"), Ok(EvalResult::Bool(false)));
+        // IPv6 addresses are never in an IPv4 range, rather than erroring
+        assert_matches!(evaluate("::1"), Ok(EvalResult::Bool(false)));
+        assert_matches!(evaluate("::ffff:a01:203"), Ok(EvalResult::Bool(false)));
+    }
+
+    #[test]
+    fn ip_overlaps() {
+        let overlaps = |lhs: &str, rhs: &str| {
+            let expr =
+                Expression::from_str(&format!(r#"ip("{lhs}").overlaps(ip("{rhs}"))"#)).unwrap();
+            evaluate_empty(&expr)
+        };
+        assert_matches!(
+            overlaps("10.0.0.0/8", "10.10.0.0/16"),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_matches!(
+            overlaps("10.10.0.0/16", "10.0.0.0/8"),
+            Ok(EvalResult::Bool(true))
+        );
+        assert_matches!(
+            overlaps("10.0.0.0/8", "192.168.0.0/16"),
+            Ok(EvalResult::Bool(false))
+        );
+        assert_matches!(overlaps("0.0.0.0/0", "::/0"), Ok(EvalResult::Bool(false)));
+    }
 }
 
 mod into_iter_entities {
@@ -4384,3 +5238,3907 @@ mod policy_set_est_tests {
         assert!(err.contains("while parsing a template link, expected a literal entity reference"));
     }
 }
+
+mod allowed_actions_tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::from_str_natural(
+            r#"
+            entity User, Document;
+            action read, write appliesTo {
+                principal: [User],
+                resource: [Document],
+                context: { @default("false") mfa?: Bool }
+            };
+            action comment in [read] appliesTo {
+                principal: [User],
+                resource: [Document]
+            };
+            action invite appliesTo { principal: [User], resource: [User] };
+            "#,
+        )
+        .unwrap()
+        .0
+    }
+
+    #[test]
+    fn allowed_actions() {
+        let schema = schema();
+        let policies = PolicySet::from_str(
+            r#"
+            permit(principal, action in Action::"read", resource);
+            permit(principal, action == Action::"write", resource) when { context.mfa };
+            permit(principal, action == Action::"invite", resource);
+            forbid(principal, action == Action::"comment", resource == Document::"locked");
+            "#,
+        )
+        .unwrap();
+        let entities = schema.action_entities().unwrap();
+        let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        let doc = EntityUid::from_str(r#"Document::"doc""#).unwrap();
+        let locked = EntityUid::from_str(r#"Document::"locked""#).unwrap();
+        let authorizer = Authorizer::new();
+
+        // `write` requires `context.mfa`, which defaults to `false`, and
+        // `invite` doesn't apply to documents
+        assert_eq!(
+            authorizer.allowed_actions(&alice, &doc, &policies, &entities, &schema),
+            HashSet::from([
+                EntityUid::from_str(r#"Action::"read""#).unwrap(),
+                EntityUid::from_str(r#"Action::"comment""#).unwrap(),
+            ])
+        );
+        assert_eq!(
+            authorizer.allowed_actions(&alice, &locked, &policies, &entities, &schema),
+            HashSet::from([EntityUid::from_str(r#"Action::"read""#).unwrap()])
+        );
+        assert_eq!(
+            authorizer.allowed_actions(&alice, &alice, &policies, &entities, &schema),
+            HashSet::from([EntityUid::from_str(r#"Action::"invite""#).unwrap()])
+        );
+        // no action applies to a document principal
+        assert!(authorizer
+            .allowed_actions(&doc, &doc, &policies, &entities, &schema)
+            .is_empty());
+    }
+}
+
+mod error_code_tests {
+    use super::*;
+
+    #[test]
+    fn parse_error_code() {
+        let err = PolicySet::from_str("permit(principal, action, resource").unwrap_err();
+        assert_eq!(err.error_code(), "cedar::parse::syntax");
+        let err =
+            PolicySet::from_str("permit(principal, action, resource) when { foo };").unwrap_err();
+        assert_eq!(err.error_code(), "cedar::parse::ill_formed");
+        let err = Expression::from_str("1 +").unwrap_err();
+        assert_eq!(err.error_code(), "cedar::parse::syntax");
+    }
+
+    #[test]
+    fn entities_error_code() {
+        let err = Entities::from_json_str(
+            r#"[
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [] }
+            ]"#,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.error_code(), "cedar::entities::duplicate");
+        let err = Entities::from_json_str("{", None).unwrap_err();
+        assert_eq!(err.error_code(), "cedar::entities::deserialization");
+    }
+
+    #[test]
+    fn schema_error_code() {
+        let err = Schema::from_str(
+            r#"{ "": { "entityTypes": {}, "actions": { "view": { "memberOf": [{ "id": "read" }] } } } }"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.error_code(), "cedar::schema::undeclared_actions");
+        let err = Schema::from_str("{").unwrap_err();
+        assert_eq!(err.error_code(), "cedar::schema::json_syntax");
+    }
+
+    #[test]
+    fn validation_error_code() {
+        let schema = Schema::from_str(
+            r#"{ "": { "entityTypes": { "User": {} }, "actions": { "view": { "appliesTo": { "principalTypes": ["User"], "resourceTypes": ["User"] } } } } }"#,
+        )
+        .unwrap();
+        let policies = PolicySet::from_str(
+            r#"
+            permit(principal == Usr::"alice", action, resource);
+            permit(principal, action == Action::"view", resource) when { principal.name == 1 };
+            permit(principal, action == Action::"view", resource) when { 1 == "a" };
+            "#,
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&policies, ValidationMode::default());
+        let codes = result
+            .validation_errors()
+            .map(ValidationError::error_code)
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            codes,
+            HashSet::from([
+                "cedar::validation::unrecognized_entity_type",
+                // `principal == Usr::"alice"` also can't match any action
+                "cedar::validation::invalid_action_application",
+                "cedar::validation::type::unsafe_attribute_access",
+                "cedar::validation::type::incompatible_types",
+            ])
+        );
+    }
+
+    #[test]
+    fn authorization_error_code() {
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { principal.missing };"#,
+        )
+        .unwrap();
+        let request = Request::new(
+            Some(EntityUid::from_strs("User", "alice")),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("User", "alice")),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let response = Authorizer::new().is_authorized(&request, &policies, &Entities::empty());
+        let codes = response
+            .diagnostics()
+            .errors()
+            .map(AuthorizationError::error_code)
+            .collect::<Vec<_>>();
+        assert_eq!(codes, vec!["cedar::authorization::policy_evaluation"]);
+    }
+
+    #[test]
+    fn validation_warning_code() {
+        let schema = Schema::from_str(
+            r#"{ "": { "entityTypes": { "User": {} }, "actions": { "view": { "appliesTo": { "principalTypes": ["User"], "resourceTypes": ["User"] } } } } }"#,
+        )
+        .unwrap();
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action == Action::"view", resource) when { false };"#,
+        )
+        .unwrap();
+        let result = Validator::new(schema).validate(&policies, ValidationMode::default());
+        let codes = result
+            .validation_warnings()
+            .map(ValidationWarning::warning_code)
+            .collect::<Vec<_>>();
+        assert_eq!(codes, vec!["cedar::validation::impossible_policy"]);
+    }
+}
+
+mod shadowed_permits_tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::from_str_natural(
+            r#"
+            entity Group;
+            entity User in [Group];
+            entity Document;
+            action read, write appliesTo { principal: [User], resource: [Document] };
+            action edit in [write] appliesTo { principal: [User], resource: [Document] };
+            "#,
+        )
+        .unwrap()
+        .0
+    }
+
+    fn shadowed(src: &str) -> Vec<(String, String)> {
+        PolicySet::from_str(src)
+            .unwrap()
+            .find_shadowed_permits(&schema())
+            .into_iter()
+            .map(|(permit, forbid)| (permit.to_string(), forbid.to_string()))
+            .collect()
+    }
+
+    fn pair(permit: &str, forbid: &str) -> (String, String) {
+        (permit.to_string(), forbid.to_string())
+    }
+
+    #[test]
+    fn matching_scopes() {
+        assert_eq!(
+            shadowed(
+                r#"
+                permit(principal == User::"alice", action == Action::"read", resource);
+                forbid(principal == User::"alice", action == Action::"read", resource);
+                "#
+            ),
+            vec![pair("policy0", "policy1")]
+        );
+        // `when { true }` is the same as no condition
+        assert_eq!(
+            shadowed(
+                r#"
+                permit(principal, action, resource) when { principal.isAdmin };
+                forbid(principal, action, resource) when { true };
+                "#
+            ),
+            vec![pair("policy0", "policy1")]
+        );
+    }
+
+    #[test]
+    fn broader_forbid_scopes() {
+        assert_eq!(
+            shadowed(
+                r#"
+                permit(principal == User::"alice", action == Action::"edit", resource == Document::"d");
+                permit(principal is User in Group::"g", action in Action::"write", resource);
+                forbid(principal is User, action in Action::"write", resource);
+                forbid(principal in Group::"g", action, resource is Document);
+                "#
+            ),
+            // `policy3` needs `alice` to be in `Group::"g"` for `policy0`,
+            // and puts a constraint on the resource that `policy1` doesn't
+            vec![pair("policy0", "policy2"), pair("policy1", "policy2")]
+        );
+    }
+
+    #[test]
+    fn not_flagged() {
+        assert!(shadowed(
+            r#"
+            permit(principal == User::"alice", action == Action::"read", resource);
+            forbid(principal == User::"alice", action == Action::"read", resource) when { context.blocked };
+            forbid(principal == User::"bob", action == Action::"read", resource);
+            forbid(principal, action == Action::"write", resource);
+            forbid(principal in Group::"g", action, resource);
+            "#
+        )
+        .is_empty());
+        // `write` has the descendant `edit`, which `forbid` doesn't match
+        assert!(shadowed(
+            r#"
+            permit(principal, action in Action::"write", resource);
+            forbid(principal, action == Action::"write", resource);
+            "#
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn linked_policies() {
+        let mut policies = PolicySet::from_str(
+            r#"
+            forbid(principal, action, resource == Document::"secret");
+            @id("template")
+            permit(principal == ?principal, action, resource == ?resource);
+            "#,
+        )
+        .unwrap();
+        let template = policies.templates().next().unwrap().id().clone();
+        for (id, resource) in [("public", "readme"), ("secret", "secret")] {
+            policies
+                .link(
+                    template.clone(),
+                    PolicyId::from_str(id).unwrap(),
+                    HashMap::from([
+                        (SlotId::principal(), EntityUid::from_strs("User", "alice")),
+                        (
+                            SlotId::resource(),
+                            EntityUid::from_strs("Document", resource),
+                        ),
+                    ]),
+                )
+                .unwrap();
+        }
+        assert_eq!(
+            policies.find_shadowed_permits(&schema()),
+            vec![(
+                PolicyId::from_str("secret").unwrap(),
+                PolicyId::from_str("policy0").unwrap()
+            )]
+        );
+    }
+}
+
+mod from_str_with_ids_tests {
+    use super::*;
+
+    #[test]
+    fn annotated_ids_are_used() {
+        let policies = PolicySet::from_str_with_ids(
+            r#"
+            @id("allow_alice")
+            permit(principal == User::"alice", action, resource);
+            forbid(principal, action, resource) when { resource.private };
+            @id("read_template")
+            permit(principal == ?principal, action == Action::"read", resource);
+            "#,
+        )
+        .unwrap();
+        let alice = policies
+            .policy(&PolicyId::from_str("allow_alice").unwrap())
+            .unwrap();
+        assert_eq!(alice.effect(), Effect::Permit);
+        assert!(alice.to_string().starts_with("@id(\"allow_alice\")"));
+        let positional = policies
+            .policy(&PolicyId::from_str("policy1").unwrap())
+            .unwrap();
+        assert_eq!(positional.effect(), Effect::Forbid);
+        assert!(policies
+            .template(&PolicyId::from_str("read_template").unwrap())
+            .is_some());
+        assert_eq!(policies.policies().count(), 2);
+        assert_eq!(policies.templates().count(), 1);
+    }
+
+    #[test]
+    fn without_annotations_matches_from_str() {
+        let src = r#"
+            permit(principal, action, resource);
+            forbid(principal, action, resource) when { resource.private };
+        "#;
+        assert_eq!(
+            PolicySet::from_str_with_ids(src).unwrap(),
+            PolicySet::from_str(src).unwrap()
+        );
+    }
+
+    /// The source text underlined by each label of `err`
+    fn underlined<'a>(src: &'a str, err: &ParseErrors) -> Vec<&'a str> {
+        err.iter()
+            .flat_map(|e| miette::Diagnostic::labels(e).into_iter().flatten())
+            .map(|label| &src[label.offset()..label.offset() + label.len()])
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_annotated_ids() {
+        let src = r#"
+            @id("dup")
+            permit(principal, action, resource);
+            @id("dup")
+            forbid(principal, action, resource);
+            "#;
+        let err = PolicySet::from_str_with_ids(src).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "policy id `dup` is used by more than one policy"
+        );
+        assert_eq!(
+            underlined(src, &err),
+            vec![r#"@id("dup")"#, r#"@id("dup")"#]
+        );
+    }
+
+    #[test]
+    fn annotated_id_collides_with_default_id() {
+        let src = r#"
+            permit(principal, action, resource);
+            @id("policy0")
+            forbid(principal, action, resource);
+            "#;
+        let err = PolicySet::from_str_with_ids(src).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "policy id `policy0` is used by more than one policy"
+        );
+        assert_eq!(
+            underlined(src, &err),
+            vec![r#"@id("policy0")"#, "permit(principal, action, resource);"]
+        );
+    }
+}
+
+mod authorization_trace_tests {
+    use super::*;
+
+    fn request(principal: &str) -> Request {
+        Request::new(
+            Some(EntityUid::from_strs("User", principal)),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("Photo", "trip")),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn entities() -> Entities {
+        Entities::from_json_value(
+            serde_json::json!([
+                {
+                    "uid": { "type": "User", "id": "alice" },
+                    "attrs": { "age": 19, "suspended": false },
+                    "parents": []
+                },
+                {
+                    "uid": { "type": "Photo", "id": "trip" },
+                    "attrs": { "owner": { "__entity": { "type": "User", "id": "alice" } } },
+                    "parents": []
+                }
+            ]),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn policies() -> PolicySet {
+        PolicySet::from_str_with_ids(
+            r#"
+            @id("owner")
+            permit(principal, action == Action::"view", resource)
+            when { resource.owner == principal }
+            unless { principal.suspended };
+
+            @id("adults")
+            permit(principal, action, resource)
+            when { principal.age >= 21 }
+            when { principal.suspended };
+
+            @id("bob")
+            forbid(principal == User::"bob", action, resource);
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn trace_explains_each_policy() {
+        let (response, trace) =
+            Authorizer::new().is_authorized_traced(&request("alice"), &policies(), &entities());
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(
+            trace
+                .policies()
+                .map(|p| p.id().to_string())
+                .collect::<Vec<_>>(),
+            vec!["adults", "bob", "owner"]
+        );
+
+        let owner = trace.policy(&PolicyId::from_str("owner").unwrap()).unwrap();
+        assert_eq!(owner.effect(), Effect::Permit);
+        assert!(owner.scope_matched());
+        assert!(owner.satisfied());
+        let conditions = owner.conditions().collect::<Vec<_>>();
+        assert_eq!(conditions.len(), 2);
+        assert_eq!(conditions[0].kind(), ConditionKind::When);
+        assert_eq!(conditions[0].value().unwrap(), &EvalResult::Bool(true));
+        let accesses = conditions[0].attribute_accesses().collect::<Vec<_>>();
+        assert_eq!(accesses.len(), 1);
+        assert_eq!(accesses[0].kind(), AttributeAccessKind::Get);
+        assert_eq!(accesses[0].attribute(), "owner");
+        assert_eq!(
+            accesses[0].target(),
+            &EvalResult::EntityUid(EntityUid::from_strs("Photo", "trip"))
+        );
+        assert_eq!(
+            accesses[0].result(),
+            Some(&EvalResult::EntityUid(EntityUid::from_strs(
+                "User", "alice"
+            )))
+        );
+        assert_eq!(conditions[1].kind(), ConditionKind::Unless);
+        assert_eq!(conditions[1].value().unwrap(), &EvalResult::Bool(false));
+        assert!(conditions[1].passed());
+
+        // evaluation stops at the first condition which does not pass
+        let adults = trace
+            .policy(&PolicyId::from_str("adults").unwrap())
+            .unwrap();
+        assert!(adults.scope_matched());
+        assert!(!adults.satisfied());
+        let conditions = adults.conditions().collect::<Vec<_>>();
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].value().unwrap(), &EvalResult::Bool(false));
+        assert_eq!(
+            conditions[0].attribute_accesses().next().unwrap().result(),
+            Some(&EvalResult::Long(19))
+        );
+
+        let bob = trace.policy(&PolicyId::from_str("bob").unwrap()).unwrap();
+        assert_eq!(bob.effect(), Effect::Forbid);
+        assert!(!bob.scope_matched());
+        assert!(!bob.satisfied());
+        assert_eq!(bob.conditions().count(), 0);
+
+        assert!(trace
+            .policy(&PolicyId::from_str("missing").unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn trace_agrees_with_response() {
+        for principal in ["alice", "bob", "carol"] {
+            let (response, trace) = Authorizer::new().is_authorized_traced(
+                &request(principal),
+                &policies(),
+                &entities(),
+            );
+            let satisfied = trace
+                .policies()
+                .filter(|p| p.satisfied())
+                .map(|p| p.id().clone())
+                .collect::<HashSet<_>>();
+            assert_eq!(
+                &satisfied,
+                &response.diagnostics().reason().cloned().collect(),
+                "for principal {principal}"
+            );
+        }
+    }
+
+    #[test]
+    fn trace_reports_errors() {
+        let (response, trace) =
+            Authorizer::new().is_authorized_traced(&request("carol"), &policies(), &entities());
+        assert_eq!(response.decision(), Decision::Deny);
+        let owner = trace.policy(&PolicyId::from_str("owner").unwrap()).unwrap();
+        let condition = owner.conditions().next().unwrap();
+        assert_eq!(condition.value().unwrap(), &EvalResult::Bool(false));
+        let adults = trace
+            .policy(&PolicyId::from_str("adults").unwrap())
+            .unwrap();
+        let condition = adults.conditions().next().unwrap();
+        assert!(condition.value().is_err());
+        assert!(!condition.passed());
+        let access = condition.attribute_accesses().next().unwrap();
+        assert_eq!(access.attribute(), "age");
+        assert_eq!(access.result(), None);
+    }
+
+    #[test]
+    fn trace_linked_policies() {
+        let mut policies = PolicySet::from_str_with_ids(
+            r#"
+            @id("template")
+            permit(principal == ?principal, action, resource)
+            when { principal.age > 18 };
+            "#,
+        )
+        .unwrap();
+        policies
+            .link(
+                PolicyId::from_str("template").unwrap(),
+                PolicyId::from_str("alice").unwrap(),
+                HashMap::from([(SlotId::principal(), EntityUid::from_strs("User", "alice"))]),
+            )
+            .unwrap();
+        let (response, trace) =
+            Authorizer::new().is_authorized_traced(&request("alice"), &policies, &entities());
+        assert_eq!(response.decision(), Decision::Allow);
+        let linked = trace.policy(&PolicyId::from_str("alice").unwrap()).unwrap();
+        assert!(linked.scope_matched());
+        assert!(linked.satisfied());
+        // templates themselves are not evaluated
+        assert_eq!(trace.policies().count(), 1);
+    }
+}
+
+mod entities_diff_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entities(json: serde_json::Value) -> Entities {
+        Entities::from_json_value(json, None).unwrap()
+    }
+
+    fn staging() -> Entities {
+        entities(json!([
+            {
+                "uid": { "type": "User", "id": "alice" },
+                "attrs": {
+                    "level": 3,
+                    "address": { "city": "Seattle", "zip": "98101" },
+                    "roles": ["admin", "viewer"],
+                    "nickname": "al",
+                },
+                "parents": [{ "type": "Group", "id": "admins" }]
+            },
+            { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+            { "uid": { "type": "User", "id": "carol" }, "attrs": {}, "parents": [] },
+        ]))
+    }
+
+    fn prod() -> Entities {
+        entities(json!([
+            {
+                "uid": { "type": "User", "id": "alice" },
+                "attrs": {
+                    "level": "3",
+                    "address": { "city": "Portland", "zip": "98101" },
+                    "roles": ["editor", "viewer"],
+                    "email": "alice@example.com",
+                },
+                "parents": [{ "type": "Group", "id": "editors" }]
+            },
+            { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+            { "uid": { "type": "User", "id": "dave" }, "attrs": {}, "parents": [] },
+        ]))
+    }
+
+    #[test]
+    fn identical_stores() {
+        assert!(staging().diff(&staging()).is_empty());
+        assert!(Entities::empty().diff(&Entities::empty()).is_empty());
+    }
+
+    #[test]
+    fn entities_in_one_store() {
+        let diff = staging().diff(&prod());
+        assert_eq!(
+            diff.only_in_self()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![r#"User::"carol""#]
+        );
+        assert_eq!(
+            diff.only_in_other()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![r#"User::"dave""#]
+        );
+        // `bob` is identical in both stores, so only `alice` is changed
+        assert_eq!(
+            diff.changed()
+                .map(|e| e.uid().to_string())
+                .collect::<Vec<_>>(),
+            vec![r#"User::"alice""#]
+        );
+    }
+
+    #[test]
+    fn attribute_and_parent_differences() {
+        let diff = staging().diff(&prod());
+        let alice = diff.changed().next().unwrap();
+        assert_eq!(
+            alice
+                .attributes()
+                .map(|a| (a.path().join("."), a.change().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "address.city".to_string(),
+                    ValueChange::Changed {
+                        self_value: r#""Seattle""#.into(),
+                        other_value: r#""Portland""#.into(),
+                    }
+                ),
+                (
+                    "email".to_string(),
+                    ValueChange::OnlyInOther {
+                        value: r#""alice@example.com""#.into(),
+                    }
+                ),
+                (
+                    "level".to_string(),
+                    ValueChange::Changed {
+                        self_value: "3".into(),
+                        other_value: r#""3""#.into(),
+                    }
+                ),
+                (
+                    "nickname".to_string(),
+                    ValueChange::OnlyInSelf {
+                        value: r#""al""#.into(),
+                    }
+                ),
+                (
+                    "roles".to_string(),
+                    ValueChange::SetElements {
+                        only_in_self: vec![r#""admin""#.into()],
+                        only_in_other: vec![r#""editor""#.into()],
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            alice
+                .parents_only_in_self()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![r#"Group::"admins""#]
+        );
+        assert_eq!(
+            alice
+                .parents_only_in_other()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![r#"Group::"editors""#]
+        );
+    }
+
+    #[test]
+    fn serialized_diff() {
+        let diff = entities(json!([
+            { "uid": { "type": "User", "id": "alice" }, "attrs": { "level": 3 }, "parents": [] },
+            { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [] },
+        ]))
+        .diff(&entities(json!([
+            { "uid": { "type": "User", "id": "alice" }, "attrs": { "level": 2 }, "parents": [] },
+        ])));
+        assert_eq!(
+            serde_json::to_value(diff).unwrap(),
+            json!({
+                "onlyInSelf": [r#"User::"bob""#],
+                "onlyInOther": [],
+                "changed": [{
+                    "uid": r#"User::"alice""#,
+                    "attributes": [{
+                        "path": ["level"],
+                        "change": { "kind": "changed", "selfValue": "3", "otherValue": "2" }
+                    }],
+                    "parentsOnlyInSelf": [],
+                    "parentsOnlyInOther": [],
+                }]
+            })
+        );
+    }
+}
+
+mod entities_slice_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entities() -> Entities {
+        Entities::from_json_value(
+            json!([
+                {
+                    "uid": { "type": "User", "id": "alice" },
+                    "attrs": { "team": { "__entity": { "type": "Team", "id": "photos" } } },
+                    "parents": [{ "type": "Group", "id": "staff" }]
+                },
+                {
+                    "uid": { "type": "User", "id": "bob" },
+                    "attrs": { "team": { "__entity": { "type": "Team", "id": "videos" } } },
+                    "parents": []
+                },
+                {
+                    "uid": { "type": "Team", "id": "photos" },
+                    "attrs": { "lead": { "__entity": { "type": "User", "id": "bob" } } },
+                    "parents": [{ "type": "Org", "id": "media" }]
+                },
+                {
+                    "uid": { "type": "Photo", "id": "trip" },
+                    "attrs": {
+                        "owner": { "__entity": { "type": "User", "id": "alice" } },
+                        "public": false
+                    },
+                    "parents": []
+                },
+                { "uid": { "type": "Group", "id": "staff" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "Org", "id": "media" }, "attrs": {}, "parents": [] }
+            ]),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn request(principal: &str) -> Request {
+        Request::new(
+            Some(EntityUid::from_strs("User", principal)),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("Photo", "trip")),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    /// The sorted UIDs of the slice of `entities()` for `principal` and
+    /// `policies`, checking that the slice gives the same decision
+    #[track_caller]
+    fn slice(principal: &str, policies: &str) -> Vec<String> {
+        let entities = entities();
+        let policies = PolicySet::from_str(policies).unwrap();
+        let request = request(principal);
+        let slice = entities.slice_for(&request, &policies);
+        let authorizer = Authorizer::new();
+        assert_eq!(
+            authorizer
+                .is_authorized(&request, &policies, &slice)
+                .decision(),
+            authorizer
+                .is_authorized(&request, &policies, &entities)
+                .decision(),
+        );
+        let mut uids = slice
+            .iter()
+            .map(|entity| entity.uid().to_string())
+            .collect::<Vec<_>>();
+        uids.sort();
+        uids
+    }
+
+    #[test]
+    fn follows_attribute_chains() {
+        assert_eq!(
+            slice(
+                "alice",
+                r#"permit(principal, action, resource) when { resource.owner.team in Org::"media" };"#
+            ),
+            vec![r#"Photo::"trip""#, r#"Team::"photos""#, r#"User::"alice""#]
+        );
+    }
+
+    #[test]
+    fn keeps_ancestors_of_in() {
+        // the scope needs `principal`'s ancestors, but not `Group::"staff"`
+        // itself
+        assert_eq!(
+            slice(
+                "alice",
+                r#"permit(principal in Group::"staff", action, resource);"#
+            ),
+            vec![r#"User::"alice""#]
+        );
+        assert_eq!(
+            slice(
+                "bob",
+                r#"permit(principal in Group::"staff", action, resource);"#
+            ),
+            vec![r#"User::"bob""#]
+        );
+    }
+
+    #[test]
+    fn skips_unevaluated_branches() {
+        let policies = r#"
+            permit(principal, action, resource) when { resource.public || principal.team.lead == principal };
+            forbid(principal == User::"bob", action, resource) unless { resource.owner.team.lead == principal };
+        "#;
+        // the forbid policy's condition is not evaluated for alice, as its
+        // scope does not match
+        assert_eq!(
+            slice("alice", policies),
+            vec![r#"Photo::"trip""#, r#"Team::"photos""#, r#"User::"alice""#]
+        );
+        // `||` is not short-circuited as `resource.public` is false
+        assert_eq!(
+            slice("bob", policies),
+            vec![
+                r#"Photo::"trip""#,
+                r#"Team::"photos""#,
+                r#"User::"alice""#,
+                r#"User::"bob""#
+            ]
+        );
+    }
+
+    #[test]
+    fn preserves_errors() {
+        // `Team::"videos"` does not exist, so the policy errors in both the
+        // full store and the slice
+        assert_eq!(
+            slice(
+                "bob",
+                r#"permit(principal, action, resource) when { principal.team.lead == principal };"#
+            ),
+            vec![r#"User::"bob""#]
+        );
+        assert_eq!(
+            slice("alice", "permit(principal, action, resource);"),
+            Vec::<String>::new()
+        );
+    }
+}
+
+mod entities_incremental_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use entities::err::EntitiesError;
+    use serde_json::json;
+
+    fn uid(ty: &str, id: &str) -> EntityUid {
+        EntityUid::from_strs(ty, id)
+    }
+
+    #[test]
+    fn add_update_remove() {
+        let entities = Entities::from_json_value(
+            json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "level": 1 }, "parents": [{ "type": "Group", "id": "staff" }] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let staff = Entity::new_no_attrs(
+            uid("Group", "staff"),
+            HashSet::from([uid("Group", "everyone")]),
+        );
+        let entities = entities.add_entities([staff], None).unwrap();
+        assert!(entities.is_ancestor_of(&uid("Group", "everyone"), &uid("User", "alice")));
+
+        let entities = entities
+            .update_attributes(
+                &uid("User", "alice"),
+                HashMap::from([("level".to_string(), RestrictedExpression::new_long(2))]),
+                None,
+            )
+            .unwrap();
+        let alice = entities.get(&uid("User", "alice")).unwrap();
+        assert_eq!(alice.attr("level").unwrap().unwrap(), EvalResult::Long(2));
+        // updating attributes doesn't change the hierarchy
+        assert!(entities.is_ancestor_of(&uid("Group", "everyone"), &uid("User", "alice")));
+
+        let entities = entities.remove_entity(&uid("Group", "staff")).unwrap();
+        assert!(entities.get(&uid("Group", "staff")).is_none());
+        // alice was only in `everyone` through `staff`
+        assert!(!entities.is_ancestor_of(&uid("Group", "everyone"), &uid("User", "alice")));
+
+        // the closure is the same as if it was computed from scratch
+        let from_scratch = Entities::from_entities(entities.iter().cloned(), None).unwrap();
+        assert_eq!(entities, from_scratch);
+    }
+
+    #[test]
+    fn add_cycle() {
+        let entities = Entities::from_json_value(
+            json!([
+                { "uid": { "type": "Group", "id": "a" }, "attrs": {}, "parents": [{ "type": "Group", "id": "b" }] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let b = Entity::new_no_attrs(uid("Group", "b"), HashSet::from([uid("Group", "a")]));
+        assert_matches!(
+            entities.add_entities([b], None),
+            Err(EntitiesError::TransitiveClosureError(_))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let (schema, _) = Schema::from_str_natural(
+            "entity User = { level: Long }; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let entities = Entities::from_json_value(
+            json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "level": 1 }, "parents": [] },
+            ]),
+            Some(&schema),
+        )
+        .unwrap();
+        let err = entities
+            .clone()
+            .update_attributes(
+                &uid("User", "alice"),
+                HashMap::from([(
+                    "level".to_string(),
+                    RestrictedExpression::new_string("high".to_string()),
+                )]),
+                Some(&schema),
+            )
+            .unwrap_err();
+        assert_matches!(err, EntitiesError::InvalidEntity(_));
+        let err = entities
+            .clone()
+            .update_attributes(&uid("User", "bob"), HashMap::new(), None)
+            .unwrap_err();
+        assert_eq!(err.error_code(), "cedar::entities::no_such_entity");
+        assert_eq!(err.to_string(), r#"entity `User::"bob"` does not exist"#);
+        let err = entities.remove_entity(&uid("User", "bob")).unwrap_err();
+        assert_matches!(err, EntitiesError::NoSuchEntity(_));
+    }
+}
+
+mod entities_ignore_case_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn get_ignore_ascii_case() {
+        let entities = Entities::from_json_value(
+            json!([
+                { "uid": { "type": "User", "id": "Alice" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "App::User", "id": "BOB" }, "attrs": {}, "parents": [] },
+                { "uid": { "type": "User", "id": "Ålice" }, "attrs": {}, "parents": [] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let get = |ty: &str, id: &str| {
+            entities
+                .get_ignore_ascii_case(&EntityUid::from_strs(ty, id))
+                .map(|e| e.uid().to_string())
+        };
+        // an exact match is preferred
+        assert_eq!(get("User", "alice").as_deref(), Some(r#"User::"alice""#));
+        assert_eq!(get("User", "Alice").as_deref(), Some(r#"User::"Alice""#));
+        // otherwise the first matching uid
+        assert_eq!(get("USER", "ALICE").as_deref(), Some(r#"User::"Alice""#));
+        assert_eq!(
+            get("app::user", "bob").as_deref(),
+            Some(r#"App::User::"BOB""#)
+        );
+        // only ASCII case is ignored
+        assert_eq!(get("User", "åLICE"), None);
+        assert_eq!(get("User", "bob"), None);
+        assert_eq!(get("App", "bob"), None);
+
+        // the lookup doesn't change authorization
+        let policies =
+            PolicySet::from_str(r#"permit(principal == User::"ALICE", action, resource);"#)
+                .unwrap();
+        let request = Request::new(
+            Some(EntityUid::from_strs("User", "alice")),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("Doc", "d")),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let response = Authorizer::new().is_authorized(&request, &policies, &entities);
+        assert_eq!(response.decision(), Decision::Deny);
+    }
+}
+
+mod policy_set_stats_tests {
+    use super::*;
+
+    #[test]
+    fn statistics() {
+        let mut pset = PolicySet::from_str(
+            r#"
+            permit(principal, action == Action::"view", resource is Photo);
+            forbid(principal, action in [Action::"view", Action::"edit"], resource)
+            when { resource.owner != principal && principal.age < 18 };
+            forbid(principal == User::"mallory", action, resource)
+            unless { [User::"alice", Photo::"p"].contains(resource) };
+            @id("template")
+            permit(principal == ?principal, action == NS::Action::"share", resource in ?resource);
+            "#,
+        )
+        .unwrap();
+        let template = PolicyId::new("policy3");
+        for i in 0..3 {
+            pset.link(
+                template.clone(),
+                PolicyId::new(format!("link{i}")),
+                HashMap::from([
+                    (SlotId::principal(), EntityUid::from_strs("User", "alice")),
+                    (SlotId::resource(), EntityUid::from_strs("Album", "a")),
+                ]),
+            )
+            .unwrap();
+        }
+
+        let stats = pset.statistics();
+        assert_eq!(stats.permits(), 4);
+        assert_eq!(stats.forbids(), 2);
+        assert_eq!(stats.static_policies(), 3);
+        assert_eq!(stats.templates(), 1);
+        assert_eq!(stats.linked_policies(), 3);
+        // `Action::"view"`, `Action::"edit"`, and `NS::Action::"share"`
+        assert_eq!(stats.distinct_actions(), 3);
+        // `true`, `(resource.owner != principal) && (principal.age < 18)`
+        // (where `!=` is a negated `==`), `!([...].contains(resource))`, and
+        // `true`
+        assert_eq!(
+            stats.average_condition_depth(),
+            (1.0 + 5.0 + 4.0 + 1.0) / 4.0
+        );
+        assert_eq!(
+            stats.most_referenced_entity_types(),
+            vec![("Action", 2), ("Photo", 2), ("NS::Action", 1), ("User", 1),]
+        );
+        assert_eq!(
+            serde_json::to_value(&stats).unwrap(),
+            serde_json::json!({
+                "permits": 4,
+                "forbids": 2,
+                "staticPolicies": 3,
+                "templates": 1,
+                "linkedPolicies": 3,
+                "distinctActions": 3,
+                "averageConditionDepth": 2.75,
+                "entityTypeReferences": {
+                    "Action": 2,
+                    "NS::Action": 1,
+                    "Photo": 2,
+                    "User": 1,
+                },
+            })
+        );
+
+        let stats = PolicySet::new().statistics();
+        assert_eq!(stats.permits() + stats.forbids(), 0);
+        assert_eq!(stats.average_condition_depth(), 0.0);
+        assert_eq!(stats.entity_type_references().count(), 0);
+    }
+}
+
+mod parse_policy_tolerant_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn recovers_valid_portions() {
+        let src = r#"
+            @id("draft")
+            permit(principal in Group::"admins", action == , resource is Photo)
+            when { resource.public }
+            when { context. };
+        "#;
+        let (recovered, errs) = parse_policy_tolerant(src);
+        assert_eq!(errs.len(), 2, "{errs:?}");
+        let recovered = recovered.unwrap();
+        assert_eq!(recovered.effect(), Effect::Permit);
+        assert_eq!(recovered.annotation("id"), Some("draft"));
+        assert_eq!(
+            recovered.principal_constraint(),
+            Some(TemplatePrincipalConstraint::In(Some(EntityUid::from_strs(
+                "Group", "admins"
+            ))))
+        );
+        assert_eq!(recovered.action_constraint(), None);
+        assert_eq!(
+            recovered.resource_constraint(),
+            Some(TemplateResourceConstraint::Is(
+                EntityTypeName::from_str("Photo").unwrap()
+            ))
+        );
+        assert_eq!(recovered.slots().count(), 0);
+        assert_eq!(
+            recovered.conditions_json().unwrap(),
+            vec![
+                Some(json!({
+                    "kind": "when",
+                    "body": {
+                        ".": {
+                            "left": { "Var": "resource" },
+                            "attr": "public"
+                        }
+                    }
+                })),
+                None
+            ]
+        );
+        // the parts which could not be recovered are marked, rather than
+        // displayed as unconstrained
+        let displayed = recovered.to_string();
+        assert_eq!(
+            displayed,
+            r#"@id("draft")
+permit(principal in Group::"admins", action <invalid>, resource is Photo) when { resource["public"] } <invalid>;"#
+        );
+        assert!(Policy::from_str(&displayed).is_err());
+    }
+
+    #[test]
+    fn valid_source() {
+        let src = r#"forbid(principal == ?principal, action, resource) unless { context.ok };"#;
+        let (recovered, errs) = parse_policy_tolerant(src);
+        assert!(errs.is_empty());
+        let recovered = recovered.unwrap();
+        let template = Template::from_str(src).unwrap();
+        assert_eq!(recovered.effect(), template.effect());
+        assert_eq!(
+            recovered.principal_constraint(),
+            Some(template.principal_constraint())
+        );
+        assert_eq!(
+            recovered.conditions_json().unwrap(),
+            vec![Some(template.to_json().unwrap()["conditions"][0].clone())]
+        );
+        assert_eq!(
+            recovered.slots().collect::<Vec<_>>(),
+            vec![&SlotId::principal()]
+        );
+    }
+
+    #[test]
+    fn unrecoverable() {
+        let (recovered, errs) = parse_policy_tolerant("permit(");
+        assert!(recovered.is_none());
+        assert!(!errs.is_empty());
+    }
+}
+
+mod sarif_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate(policies: &str) -> ValidationResult {
+        let (schema, _) = Schema::from_str_natural(
+            "entity User = { name: String }; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let policies = PolicySet::from_str(policies).unwrap();
+        Validator::new(schema).validate(&policies, ValidationMode::Strict)
+    }
+
+    #[test]
+    fn errors_and_warnings() {
+        let result = validate(
+            r#"permit(principal == Usr::"alice", action == Action::"view", resource);
+permit(principal, action == Action::"view", resource) when { false };
+forbid(principal, action == Action::"view", resource)
+when { principal.age > 1 };"#,
+        );
+        let sarif = result.to_sarif(Some("policies.cedar"));
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "cedar");
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        let rule_ids = rules.iter().map(|rule| &rule["id"]).collect::<Vec<_>>();
+        assert_eq!(
+            rule_ids,
+            vec![
+                "cedar/impossible-policy",
+                // `principal == Usr::"alice"` also can't match any action
+                "cedar/invalid-action-application",
+                "cedar/unrecognized-entity-type",
+                "cedar/unsafe-attribute-access",
+            ]
+        );
+        assert_eq!(rules[0]["defaultConfiguration"]["level"], "warning");
+        assert_eq!(rules[1]["defaultConfiguration"]["level"], "error");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 4);
+        for result in results {
+            let rule_index = result["ruleIndex"].as_u64().unwrap() as usize;
+            assert_eq!(result["ruleId"], rules[rule_index]["id"]);
+        }
+
+        let unrecognized = results
+            .iter()
+            .find(|result| result["ruleId"] == "cedar/unrecognized-entity-type")
+            .unwrap();
+        assert_eq!(unrecognized["level"], "error");
+        assert!(unrecognized["message"]["text"]
+            .as_str()
+            .unwrap()
+            .contains("did you mean `User`?"));
+        assert_eq!(
+            unrecognized["locations"][0],
+            json!({
+                "logicalLocations": [{ "name": "policy0" }],
+                "physicalLocation": {
+                    "artifactLocation": { "uri": "policies.cedar" },
+                    "region": { "startLine": 1, "startColumn": 21, "endLine": 1, "endColumn": 24 },
+                },
+            })
+        );
+
+        let impossible = results
+            .iter()
+            .find(|result| result["ruleId"] == "cedar/impossible-policy")
+            .unwrap();
+        assert_eq!(impossible["level"], "warning");
+        assert_eq!(
+            impossible["locations"][0]["logicalLocations"][0]["name"],
+            "policy1"
+        );
+
+        // the region of an error on a later line
+        let unsafe_access = results
+            .iter()
+            .find(|result| result["ruleId"] == "cedar/unsafe-attribute-access")
+            .unwrap();
+        assert_eq!(
+            unsafe_access["locations"][0]["physicalLocation"]["region"],
+            json!({ "startLine": 4, "startColumn": 8, "endLine": 4, "endColumn": 21 })
+        );
+    }
+
+    #[test]
+    fn without_artifact_uri() {
+        let result = validate(r#"permit(principal == Usr::"alice", action, resource);"#);
+        let sarif = result.to_sarif(None);
+        let location = &sarif["runs"][0]["results"][0]["locations"][0];
+        assert_eq!(
+            location,
+            &json!({ "logicalLocations": [{ "name": "policy0" }] })
+        );
+    }
+
+    #[test]
+    fn no_findings() {
+        let result = validate(r#"permit(principal, action == Action::"view", resource);"#);
+        let sarif = result.to_sarif(None);
+        assert_eq!(sarif["runs"][0]["results"], json!([]));
+        assert_eq!(sarif["runs"][0]["tool"]["driver"]["rules"], json!([]));
+    }
+}
+
+mod schema_builder_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn type_name(name: &str) -> EntityTypeName {
+        EntityTypeName::from_str(name).unwrap()
+    }
+
+    fn validate(schema: Schema, policy: &str) -> ValidationResult {
+        let policies = PolicySet::from_str(policy).unwrap();
+        Validator::new(schema).validate(&policies, ValidationMode::Strict)
+    }
+
+    #[test]
+    fn builds_like_parsed_schema() {
+        let schema = SchemaBuilder::new()
+            .namespace("App".parse().unwrap())
+            .entity_type("Group")
+            .entity_type("User")
+            .member_of(&type_name("Group"))
+            .attribute("name", AttrType::required(Type::string()))
+            .attribute(
+                "address",
+                AttrType::optional(Type::record([("zip", AttrType::required(Type::long()))])),
+            )
+            .attribute("tags", AttrType::required(Type::set(Type::string())))
+            .entity_type("Photo")
+            .attribute(
+                "owner",
+                AttrType::required(Type::entity(&type_name("User"))),
+            )
+            .action("all")
+            .action("view")
+            .member_of("all")
+            .principal_types([type_name("User")])
+            .resource_types([type_name("Photo")])
+            .context_attribute("ip", AttrType::required(Type::ipaddr()))
+            .build()
+            .unwrap();
+
+        assert!(validate(
+            schema.clone(),
+            r#"permit(principal in App::Group::"admins", action in App::Action::"all", resource)
+            when { resource.owner == principal && principal.address.zip > 10000 && context.ip.isLoopback() };"#,
+        )
+        .validation_errors()
+        .next()
+        .is_some());
+        assert!(validate(
+            schema.clone(),
+            r#"permit(principal in App::Group::"admins", action in App::Action::"all", resource)
+            when { resource.owner == principal && principal has address && principal.address.zip > 10000 && context.ip.isLoopback() };"#,
+        )
+        .validation_passed());
+
+        // the `descendants` of the action hierarchy are computed
+        let entities = schema.action_entities().unwrap();
+        let all = EntityUid::from_str(r#"App::Action::"all""#).unwrap();
+        let view = EntityUid::from_str(r#"App::Action::"view""#).unwrap();
+        assert!(entities.is_ancestor_of(&all, &view));
+    }
+
+    #[test]
+    fn undeclared_entity_type() {
+        let err = SchemaBuilder::new()
+            .entity_type("User")
+            .member_of(&type_name("Group"))
+            .build()
+            .unwrap_err();
+        assert_matches!(err, SchemaError::UndeclaredEntityTypes(types) if types == HashSet::from(["Group".to_string()]));
+    }
+
+    #[test]
+    fn cycle_in_action_hierarchy() {
+        let err = SchemaBuilder::new()
+            .action("a")
+            .member_of("b")
+            .action("b")
+            .member_of("a")
+            .build()
+            .unwrap_err();
+        assert_matches!(err, SchemaError::CycleInActionHierarchy(_));
+    }
+
+    #[test]
+    fn duplicate_declarations() {
+        let err = SchemaBuilder::new()
+            .namespace("App".parse().unwrap())
+            .entity_type("User")
+            .entity_type("User")
+            .build()
+            .unwrap_err();
+        assert_matches!(err, SchemaError::DuplicateEntityType(name) if name == "App::User");
+
+        let err = SchemaBuilder::new()
+            .action("view")
+            .action("view")
+            .build()
+            .unwrap_err();
+        assert_matches!(err, SchemaError::DuplicateAction(name) if name == r#"Action::"view""#);
+    }
+
+    #[test]
+    fn invalid_name() {
+        let err = SchemaBuilder::new()
+            .entity_type("App::User")
+            .build()
+            .unwrap_err();
+        assert_matches!(err, SchemaError::InvalidName { name, .. } if name == "App::User");
+    }
+}
+
+mod matched_actions_tests {
+    use super::*;
+    use itertools::Itertools;
+
+    fn schema() -> Schema {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            entity User;
+            action all;
+            action write in all;
+            action read, edit in write appliesTo { principal: User, resource: User };
+            action delete in all appliesTo { principal: User, resource: User };
+            action audit appliesTo { principal: User, resource: User };
+            "#,
+        )
+        .unwrap();
+        schema
+    }
+
+    fn matched_actions(src: &str) -> Vec<String> {
+        let policy = Policy::from_str(src).unwrap();
+        policy
+            .matched_actions(&schema())
+            .into_iter()
+            .map(|action| action.id().to_string())
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn any() {
+        assert_eq!(
+            matched_actions("forbid(principal, action, resource);"),
+            vec!["all", "audit", "delete", "edit", "read", "write"]
+        );
+    }
+
+    #[test]
+    fn eq() {
+        assert_eq!(
+            matched_actions(r#"forbid(principal, action == Action::"write", resource);"#),
+            vec!["write"]
+        );
+    }
+
+    #[test]
+    fn in_nested_groups() {
+        assert_eq!(
+            matched_actions(r#"forbid(principal, action in Action::"all", resource);"#),
+            vec!["all", "delete", "edit", "read", "write"]
+        );
+        assert_eq!(
+            matched_actions(
+                r#"forbid(principal, action in [Action::"write", Action::"audit"], resource);"#
+            ),
+            vec!["audit", "edit", "read", "write"]
+        );
+    }
+
+    #[test]
+    fn undeclared_actions() {
+        assert!(
+            matched_actions(r#"forbid(principal, action == Action::"unknown", resource);"#)
+                .is_empty()
+        );
+        assert_eq!(
+            matched_actions(
+                r#"forbid(principal, action in [Action::"unknown", Action::"read"], resource);"#
+            ),
+            vec!["read"]
+        );
+    }
+
+    #[test]
+    fn linked_policy() {
+        let template = Template::parse(
+            Some("t".to_string()),
+            r#"permit(principal == ?principal, action in Action::"write", resource);"#,
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        pset.add_template(template).unwrap();
+        pset.link(
+            PolicyId::from_str("t").unwrap(),
+            PolicyId::from_str("link").unwrap(),
+            HashMap::from([(SlotId::principal(), EntityUid::from_strs("User", "alice"))]),
+        )
+        .unwrap();
+        let policy = pset.policy(&PolicyId::from_str("link").unwrap()).unwrap();
+        assert_eq!(
+            policy
+                .matched_actions(&schema())
+                .into_iter()
+                .map(|action| action.id().to_string())
+                .sorted()
+                .collect::<Vec<_>>(),
+            vec!["edit", "read", "write"]
+        );
+    }
+}
+
+mod policy_complexity_tests {
+    use super::*;
+
+    #[test]
+    fn no_conditions() {
+        let policy = Policy::from_str("permit(principal, action, resource);").unwrap();
+        let complexity = policy.complexity();
+        assert_eq!(complexity.max_depth(), 1);
+        assert_eq!(complexity.expression_size(), 1);
+        assert_eq!(complexity.attribute_accesses(), 0);
+        assert_eq!(complexity.set_operations(), 0);
+        assert_eq!(complexity.extension_calls(), 0);
+    }
+
+    #[test]
+    fn counts() {
+        let policy = Policy::from_str(
+            r#"permit(principal, action, resource)
+            when { principal has tags && principal.tags.contains("admin") }
+            unless { [1, 2].containsAll(resource.ids) || decimal("1.0").lessThan(context.limit) };"#,
+        )
+        .unwrap();
+        let complexity = policy.complexity();
+        assert_eq!(complexity.attribute_accesses(), 4);
+        assert_eq!(complexity.set_operations(), 2);
+        // `decimal(...)` and `.lessThan(...)`
+        assert_eq!(complexity.extension_calls(), 2);
+        assert_eq!(
+            serde_json::to_value(&complexity).unwrap()["attributeAccesses"],
+            4
+        );
+    }
+
+    #[test]
+    fn depth_grows_with_nesting() {
+        let shallow =
+            Policy::from_str("permit(principal, action, resource) when { principal.a == 1 };")
+                .unwrap()
+                .complexity();
+        let deep = Policy::from_str(
+            "permit(principal, action, resource) when { if principal.a == 1 then (if principal.b then principal.c.d.e else false) else false };",
+        )
+        .unwrap()
+        .complexity();
+        assert!(deep.max_depth() > shallow.max_depth());
+        assert!(deep.expression_size() > shallow.expression_size());
+    }
+
+    #[test]
+    fn linked_policy() {
+        let template = Template::parse(
+            Some("t".to_string()),
+            "permit(principal == ?principal, action, resource) when { resource.owner == principal };",
+        )
+        .unwrap();
+        let mut pset = PolicySet::new();
+        pset.add_template(template).unwrap();
+        pset.link(
+            PolicyId::from_str("t").unwrap(),
+            PolicyId::from_str("link").unwrap(),
+            HashMap::from([(SlotId::principal(), EntityUid::from_strs("User", "alice"))]),
+        )
+        .unwrap();
+        let policy = pset.policy(&PolicyId::from_str("link").unwrap()).unwrap();
+        assert_eq!(policy.complexity().attribute_accesses(), 1);
+    }
+}
+
+mod entities_container_tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        let entities = Entities::empty();
+        assert_eq!(entities.len(), 0);
+        assert!(entities.is_empty());
+        assert_eq!(entities.iter().count(), 0);
+    }
+
+    #[test]
+    fn len_includes_schema_actions() {
+        let (schema, _) = Schema::from_str_natural(
+            "entity User; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let entities = Entities::from_entities(
+            [Entity::with_uid(EntityUid::from_strs("User", "alice"))],
+            Some(&schema),
+        )
+        .unwrap();
+        assert_eq!(entities.len(), 2);
+        assert!(!entities.is_empty());
+    }
+
+    #[test]
+    fn iter_is_ordered_by_uid() {
+        let uids = [
+            EntityUid::from_strs("User", "bob"),
+            EntityUid::from_strs("Group", "admins"),
+            EntityUid::from_strs("User", "alice"),
+            EntityUid::from_strs("App::User", "carol"),
+        ];
+        let entities =
+            Entities::from_entities(uids.iter().cloned().map(Entity::with_uid), None).unwrap();
+        assert_eq!(entities.len(), 4);
+        let mut expected = uids.to_vec();
+        expected.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        assert_eq!(
+            entities.iter().map(|e| e.uid()).collect::<Vec<_>>(),
+            expected
+        );
+        // The order doesn't depend on the order entities were added in
+        let reversed =
+            Entities::from_entities(uids.iter().rev().cloned().map(Entity::with_uid), None)
+                .unwrap();
+        assert_eq!(
+            reversed.iter().map(|e| e.uid()).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn parents_and_ancestors() {
+        let alice = EntityUid::from_strs("User", "alice");
+        let staff = EntityUid::from_strs("Group", "staff");
+        let everyone = EntityUid::from_strs("Group", "everyone");
+        let entities = Entities::from_entities(
+            [
+                Entity::new_no_attrs(alice.clone(), HashSet::from([staff.clone()])),
+                Entity::new_no_attrs(staff.clone(), HashSet::from([everyone.clone()])),
+            ],
+            None,
+        )
+        .unwrap();
+        let entity = entities.get(&alice).unwrap();
+        assert_eq!(entity.parents().collect::<Vec<_>>(), vec![&staff]);
+        assert_eq!(
+            entities.ancestors(&alice).unwrap().collect::<HashSet<_>>(),
+            HashSet::from([&staff, &everyone])
+        );
+        // `everyone` isn't in the store, but is still a parent of `staff`
+        assert_eq!(
+            entities.get(&staff).unwrap().parents().collect::<Vec<_>>(),
+            vec![&everyone]
+        );
+        assert!(entities.ancestors(&everyone).is_none());
+    }
+}
+
+mod validate_and_authorize_tests {
+    use super::*;
+
+    fn validator(age_type: &str) -> Validator {
+        let (schema, _) = Schema::from_str_natural(&format!(
+            "entity User = {{ age: {age_type} }}; action view appliesTo {{ principal: User, resource: User }};"
+        ))
+        .unwrap();
+        Validator::new(schema)
+    }
+
+    fn request() -> Request {
+        let alice = EntityUid::from_strs("User", "alice");
+        Request::new(
+            Some(alice.clone()),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(alice),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn entities() -> Entities {
+        Entities::from_json_str(
+            r#"[{ "uid": { "type": "User", "id": "alice" }, "attrs": { "age": 20 }, "parents": [] }]"#,
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn authorizes_valid_policies() {
+        let authorizer = Authorizer::new();
+        let long = validator("Long");
+        let mut cache = ValidationCache::new();
+        let policies =
+            PolicySet::from_str("permit(principal, action, resource) when { principal.age > 18 };")
+                .unwrap();
+        // Repeated calls with the same policies give the same answer
+        for _ in 0..2 {
+            let response = authorizer
+                .validate_and_authorize(&request(), &policies, &entities(), &long, &mut cache)
+                .unwrap();
+            assert_eq!(response.decision(), Decision::Allow);
+        }
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn does_not_evaluate_invalid_policies() {
+        let authorizer = Authorizer::new();
+        let long = validator("Long");
+        let mut cache = ValidationCache::new();
+        let policies = PolicySet::from_str(
+            r#"forbid(principal, action, resource) when { principal.age == "20" };
+            permit(principal, action, resource);"#,
+        )
+        .unwrap();
+        let result = authorizer
+            .validate_and_authorize(&request(), &policies, &entities(), &long, &mut cache)
+            .unwrap_err();
+        assert_eq!(result.validation_errors().count(), 1);
+    }
+
+    #[test]
+    fn revalidates_when_inputs_change() {
+        let authorizer = Authorizer::new();
+        let (long, string) = (validator("Long"), validator("String"));
+        let mut cache = ValidationCache::new();
+        let mut policies =
+            PolicySet::from_str("permit(principal, action, resource) when { principal.age > 18 };")
+                .unwrap();
+        assert!(authorizer
+            .validate_and_authorize(&request(), &policies, &entities(), &long, &mut cache)
+            .is_ok());
+
+        // A different validator, under which the same policies are invalid
+        assert!(authorizer
+            .validate_and_authorize(&request(), &policies, &entities(), &string, &mut cache)
+            .is_err());
+        assert!(authorizer
+            .validate_and_authorize(&request(), &policies, &entities(), &long, &mut cache)
+            .is_ok());
+
+        // A modified policy set
+        policies
+            .add(
+                Policy::parse(
+                    Some("invalid".to_string()),
+                    "permit(principal, action, resource) when { principal.name == \"alice\" };",
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        assert!(authorizer
+            .validate_and_authorize(&request(), &policies, &entities(), &long, &mut cache)
+            .is_err());
+    }
+}
+
+mod rebase_namespace_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    const SCHEMA: &str = r#"
+        namespace Vendor {
+            entity Group;
+            entity User in [Group] = { manager?: User };
+            entity Doc = { owner: User };
+            action read appliesTo { principal: User, resource: Doc };
+        }
+    "#;
+
+    const POLICIES: &str = r#"
+        permit(principal in Vendor::Group::"admins", action == Vendor::Action::"read", resource is Vendor::Doc)
+        when { resource.owner == principal || (resource.owner has manager && resource.owner.manager == Vendor::User::"root") };
+        permit(principal == ?principal, action in [Vendor::Action::"read"], resource)
+        when { principal is Vendor::User };
+    "#;
+
+    fn namespace(s: &str) -> EntityNamespace {
+        s.parse().unwrap()
+    }
+
+    fn policies() -> PolicySet {
+        let mut policies: PolicySet = POLICIES.parse().unwrap();
+        policies
+            .link(
+                PolicyId::new("policy1"),
+                PolicyId::new("link"),
+                HashMap::from([(
+                    SlotId::principal(),
+                    EntityUid::from_str(r#"Vendor::User::"alice""#).unwrap(),
+                )]),
+            )
+            .unwrap();
+        policies
+    }
+
+    #[test]
+    fn rebased_policies_validate_against_rebased_schema() {
+        let (from, to) = (namespace("Vendor"), namespace("MyApp::Vendor"));
+        let (schema, _) = Schema::from_str_natural(SCHEMA).unwrap();
+        let policies = policies();
+        assert!(Validator::new(schema.clone())
+            .validate(&policies, ValidationMode::Strict)
+            .validation_passed());
+
+        let schema = schema.rebase_namespace(&from, &to).unwrap();
+        let rebased = policies.rebase_namespace(&from, &to);
+
+        let validator = Validator::new(schema);
+        assert!(validator
+            .validate(&rebased, ValidationMode::Strict)
+            .validation_passed());
+        // The original policies only mention names which no longer exist
+        assert!(!validator
+            .validate(&policies, ValidationMode::Strict)
+            .validation_passed());
+
+        let link = rebased.policy(&PolicyId::new("link")).unwrap();
+        assert_eq!(
+            link.template_links().unwrap()[&SlotId::principal()],
+            EntityUid::from_str(r#"MyApp::Vendor::User::"alice""#).unwrap()
+        );
+        assert!(link.to_string().contains(r#"MyApp::Vendor::User::"alice""#));
+    }
+
+    #[test]
+    fn reversible() {
+        let (from, to) = (namespace("Vendor"), namespace("MyApp::Vendor"));
+        // Rebasing a namespace onto itself changes nothing but the formatting
+        let policies = policies().rebase_namespace(&from, &from);
+        let roundtrip = policies
+            .rebase_namespace(&from, &to)
+            .rebase_namespace(&to, &from);
+        for policy in policies.policies() {
+            let other = roundtrip.policy(policy.id()).unwrap();
+            assert_eq!(other.to_string(), policy.to_string());
+        }
+        for template in policies.templates() {
+            let other = roundtrip.template(template.id()).unwrap();
+            assert_eq!(other.to_string(), template.to_string());
+        }
+
+        let (schema, _) = Schema::from_str_natural(SCHEMA).unwrap();
+        let roundtrip = schema
+            .rebase_namespace(&from, &to)
+            .unwrap()
+            .rebase_namespace(&to, &from)
+            .unwrap();
+        assert_eq!(roundtrip, schema);
+    }
+
+    #[test]
+    fn schema_collision() {
+        let (schema, _) =
+            Schema::from_str_natural(&format!("{SCHEMA} namespace App {{ entity Doc; }}")).unwrap();
+        assert_matches!(
+            schema.rebase_namespace(&namespace("Vendor"), &namespace("App")),
+            Err(SchemaError::DuplicateEntityType(name)) if name == "App::Doc"
+        );
+    }
+}
+
+mod unknown_functions_tests {
+    use super::*;
+
+    const POLICIES: &str = r#"
+        permit(principal, action, resource) when { context.legacy || datetime(context.now) > datetime("2024-10-15") };
+    "#;
+
+    #[test]
+    fn unknown_functions_are_rejected_by_default() {
+        let errs = PolicySet::from_str(POLICIES).unwrap_err();
+        assert_eq!(errs.to_string(), "`datetime` is not a function");
+    }
+
+    #[test]
+    fn calls_error_only_when_reached() {
+        let (policies, warnings) =
+            PolicySet::from_str_allowing_unknown_functions(POLICIES).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings
+            .iter()
+            .all(|warning| warning.to_string() == "`datetime` is not a function"));
+
+        let request = |legacy: bool| {
+            Request::new(
+                Some(EntityUid::from_strs("User", "alice")),
+                Some(EntityUid::from_strs("Action", "view")),
+                Some(EntityUid::from_strs("Doc", "doc")),
+                Context::from_pairs([
+                    ("legacy".into(), RestrictedExpression::new_bool(legacy)),
+                    (
+                        "now".into(),
+                        RestrictedExpression::new_string("2024-10-16".into()),
+                    ),
+                ])
+                .unwrap(),
+                None,
+            )
+            .unwrap()
+        };
+        let authorizer = Authorizer::new();
+        let response = authorizer.is_authorized(&request(true), &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(response.diagnostics().errors().count(), 0);
+
+        let response = authorizer.is_authorized(&request(false), &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Deny);
+        let errors = response.diagnostics().errors().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("datetime"));
+    }
+
+    #[test]
+    fn validation_warnings() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            entity User, Doc;
+            action view appliesTo { principal: User, resource: Doc, context: { legacy: Bool, now: String } };
+            "#,
+        )
+        .unwrap();
+        let (policies, _) = PolicySet::from_str_allowing_unknown_functions(POLICIES).unwrap();
+
+        let validator = Validator::new(schema);
+        assert!(!validator
+            .validate(&policies, ValidationMode::Strict)
+            .validation_passed());
+        let validator = validator.with_unknown_functions_as_warnings(true);
+        let result = validator.validate(&policies, ValidationMode::Strict);
+        assert!(result.validation_passed());
+        assert_eq!(
+            result
+                .validation_warnings()
+                .map(|warning| warning.warning_kind().warning_code())
+                .collect::<Vec<_>>(),
+            vec!["cedar::validation::unknown_function"; 2]
+        );
+    }
+}
+
+mod error_handling_tests {
+    use super::*;
+    use itertools::Itertools;
+
+    fn request() -> Request {
+        Request::new(
+            Some(EntityUid::from_strs("User", "alice")),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("Photo", "trip")),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn authorize(error_handling: ErrorHandling, src: &str) -> Response {
+        Authorizer::with_config(AuthorizerConfig { error_handling }).is_authorized(
+            &request(),
+            &PolicySet::from_str(src).unwrap(),
+            &Entities::empty(),
+        )
+    }
+
+    fn reason(response: &Response) -> Vec<String> {
+        response
+            .diagnostics()
+            .reason()
+            .map(ToString::to_string)
+            .sorted()
+            .collect()
+    }
+
+    #[test]
+    fn default_skips_errors() {
+        assert_eq!(
+            AuthorizerConfig::default().error_handling,
+            ErrorHandling::Skip
+        );
+        let response = authorize(
+            ErrorHandling::Skip,
+            r#"permit(principal, action, resource);
+            forbid(principal, action, resource) when { context.risk > 5 };"#,
+        );
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(reason(&response), vec!["policy0"]);
+        assert_eq!(response.diagnostics().errors().count(), 1);
+    }
+
+    #[test]
+    fn deny_on_forbid_error() {
+        let response = authorize(
+            ErrorHandling::DenyOnForbidError,
+            r#"permit(principal, action, resource);
+            forbid(principal, action, resource) when { context.risk > 5 };"#,
+        );
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(reason(&response), vec!["policy1"]);
+        assert_eq!(response.diagnostics().errors().count(), 1);
+
+        // Errors in `permit` policies are still skipped
+        let response = authorize(
+            ErrorHandling::DenyOnForbidError,
+            r#"permit(principal, action, resource);
+            permit(principal, action, resource) when { context.risk < 5 };"#,
+        );
+        assert_eq!(response.decision(), Decision::Allow);
+        assert_eq!(reason(&response), vec!["policy0"]);
+    }
+
+    #[test]
+    fn deny_on_any_error() {
+        let response = authorize(
+            ErrorHandling::DenyOnAnyError,
+            r#"permit(principal, action, resource);
+            permit(principal, action, resource) when { context.risk < 5 };"#,
+        );
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(reason(&response), vec!["policy1"]);
+        assert_eq!(response.diagnostics().errors().count(), 1);
+
+        let response = authorize(
+            ErrorHandling::DenyOnAnyError,
+            "permit(principal, action, resource);",
+        );
+        assert_eq!(response.decision(), Decision::Allow);
+    }
+}
+
+mod validation_cache_tests {
+    use super::*;
+
+    #[test]
+    fn validate_cached() {
+        let (schema, _) = Schema::from_str_natural(
+            "entity User = { age: Long }; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+        let mut cache = ValidationCache::new();
+        let pset = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource) when { principal.age > 18 };
+            permit(principal, action, resource) when { principal.age == "old" };
+            "#,
+        )
+        .unwrap();
+        let errors = |result: ValidationResult| {
+            result
+                .validation_errors()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let uncached = validator.validate(&pset, ValidationMode::Strict);
+        let cached = validator.validate_cached(&pset, ValidationMode::Strict, &mut cache);
+        assert_eq!(errors(cached), errors(uncached));
+        assert_eq!(cache.len(), 2);
+
+        let cached = validator.validate_cached(&pset, ValidationMode::Strict, &mut cache);
+        assert_eq!(
+            errors(cached),
+            errors(validator.validate(&pset, ValidationMode::Strict))
+        );
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
+
+mod evaluate_each_tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_every_policy() {
+        let policies = PolicySet::from_str(
+            r#"
+            permit(principal == User::"alice", action, resource);
+            permit(principal == User::"bob", action, resource);
+            forbid(principal, action, resource) when { resource.private };
+            forbid(principal, action, resource) when { resource.owner == principal };
+            "#,
+        )
+        .unwrap();
+        let entities = Entities::from_json_str(
+            r#"[{ "uid": { "type": "Photo", "id": "trip" }, "attrs": { "private": true }, "parents": [] }]"#,
+            None,
+        )
+        .unwrap();
+        let request = Request::new(
+            Some(EntityUid::from_strs("User", "alice")),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("Photo", "trip")),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+
+        let authorizer = Authorizer::new();
+        let response = authorizer.is_authorized(&request, &policies, &entities);
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(
+            response.diagnostics().reason().collect::<Vec<_>>(),
+            vec![&PolicyId::new("policy2")]
+        );
+
+        let outcomes = authorizer.evaluate_each(&request, &policies, &entities);
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|(id, _)| id.to_string())
+                .collect::<Vec<_>>(),
+            vec!["policy0", "policy1", "policy2", "policy3"]
+        );
+        assert_eq!(
+            outcomes[0].1,
+            PerPolicyOutcome::Satisfied {
+                effect: Effect::Permit
+            }
+        );
+        assert_eq!(outcomes[1].1, PerPolicyOutcome::NotSatisfied);
+        assert_eq!(
+            outcomes[2].1,
+            PerPolicyOutcome::Satisfied {
+                effect: Effect::Forbid
+            }
+        );
+        let PerPolicyOutcome::Error(err) = &outcomes[3].1 else {
+            panic!("expected an error, got {:?}", outcomes[3].1);
+        };
+        assert!(err.to_string().contains("owner"));
+    }
+}
+
+mod validation_json_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate(policies: &str) -> ValidationResult {
+        let (schema, _) = Schema::from_str_natural(
+            "entity User = { name: String }; action view appliesTo { principal: User, resource: User };",
+        )
+        .unwrap();
+        let policies = PolicySet::from_str(policies).unwrap();
+        Validator::new(schema).validate(&policies, ValidationMode::Strict)
+    }
+
+    #[test]
+    fn errors_and_warnings() {
+        let result = validate(
+            r#"permit(principal, action == Action::"view", resource) when { false };
+forbid(principal, action == Action::"view", resource)
+when { principal.age > 1 };"#,
+        );
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["version"], VALIDATION_RESULT_FORMAT_VERSION);
+        assert_eq!(json["passed"], false);
+
+        let errors = json["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0]["code"],
+            "cedar::validation::type::unsafe_attribute_access"
+        );
+        assert_eq!(errors[0]["policyId"], "policy1");
+        assert!(errors[0]["message"].as_str().unwrap().contains("`age`"));
+        assert_eq!(
+            errors[0]["sourceSpan"],
+            json!({
+                "start": { "offset": 131, "line": 3, "column": 8 },
+                "end": { "offset": 144, "line": 3, "column": 21 },
+            })
+        );
+
+        let warnings = json["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0]["code"], "cedar::validation::impossible_policy");
+        assert_eq!(warnings[0]["policyId"], "policy0");
+        assert_eq!(warnings[0]["sourceSpan"]["start"]["line"], 1);
+    }
+
+    #[test]
+    fn help() {
+        let result = validate(r#"permit(principal == Usr::"alice", action, resource);"#);
+        let error = result
+            .validation_errors()
+            .find(|error| error.error_code() == "cedar::validation::unrecognized_entity_type")
+            .unwrap();
+        let json = serde_json::to_value(error).unwrap();
+        assert_eq!(json["help"], "did you mean `User`?");
+        assert_eq!(
+            json["sourceSpan"],
+            json!({
+                "start": { "offset": 20, "line": 1, "column": 21 },
+                "end": { "offset": 23, "line": 1, "column": 24 },
+            })
+        );
+    }
+
+    #[test]
+    fn passed() {
+        let result = validate(r#"permit(principal, action == Action::"view", resource);"#);
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            json!({
+                "version": VALIDATION_RESULT_FORMAT_VERSION,
+                "passed": true,
+                "errors": [],
+                "warnings": [],
+            })
+        );
+    }
+}
+
+mod context_tests {
+    use super::*;
+
+    #[test]
+    fn get_and_keys() {
+        let context = Context::from_pairs([
+            (
+                "user".to_string(),
+                RestrictedExpression::from_str(r#"User::"alice""#).unwrap(),
+            ),
+            (
+                "tags".to_string(),
+                RestrictedExpression::from_str(r#"["a", "b"]"#).unwrap(),
+            ),
+            (
+                "ip".to_string(),
+                RestrictedExpression::from_str(r#"ip("10.0.0.1")"#).unwrap(),
+            ),
+        ])
+        .unwrap();
+        assert_eq!(
+            context.keys().collect::<Vec<_>>(),
+            vec!["ip", "tags", "user"]
+        );
+        assert_eq!(context.get("user").unwrap().to_string(), r#"User::"alice""#);
+        assert_eq!(context.get("tags").unwrap().to_string(), r#"["a", "b"]"#);
+        assert_eq!(context.get("ip").unwrap().to_string(), r#"ip("10.0.0.1")"#);
+        assert!(context.get("missing").is_none());
+
+        assert_eq!(Context::empty().keys().count(), 0);
+        assert!(Context::empty().get("user").is_none());
+    }
+
+    #[cfg(feature = "partial-eval")]
+    #[test]
+    fn unknowns() {
+        let context = Context::from_pairs([
+            ("a".to_string(), RestrictedExpression::new_long(1)),
+            (
+                "b".to_string(),
+                RestrictedExpression::from_str(r#"unknown("b")"#).unwrap(),
+            ),
+        ])
+        .unwrap();
+        assert_eq!(context.keys().collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(context.get("a").unwrap().to_string(), "1");
+        assert_eq!(context.get("b").unwrap().to_string(), r#"unknown("b")"#);
+    }
+}
+
+#[cfg(feature = "derived-attributes")]
+mod derived_attributes_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use entities::conformance::err::EntitySchemaConformanceError;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_json_value(json!({
+            "": {
+                "entityTypes": {
+                    "User": {
+                        "shape": {
+                            "type": "Record",
+                            "attributes": { "age": { "type": "Long" } }
+                        },
+                        "derivedAttributes": {
+                            "isAdult": "resource.age >= 18",
+                            "ageNextYear": "resource.age + 1"
+                        }
+                    },
+                    "Document": {}
+                },
+                "actions": {
+                    "view": {
+                        "appliesTo": {
+                            "principalTypes": ["User"],
+                            "resourceTypes": ["Document"]
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn entities(schema: &Schema) -> Entities {
+        Entities::from_json_value(
+            json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "age": 20 }, "parents": [] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": { "age": 10 }, "parents": [] },
+                { "uid": { "type": "Document", "id": "doc" }, "attrs": {}, "parents": [] }
+            ]),
+            Some(schema),
+        )
+        .unwrap()
+    }
+
+    fn decision(policy: &str, principal: &str, entities: &Entities) -> Decision {
+        let request = Request::new(
+            Some(EntityUid::from_strs("User", principal)),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("Document", "doc")),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let pset = PolicySet::from_str(policy).unwrap();
+        Authorizer::new()
+            .is_authorized(&request, &pset, entities)
+            .decision()
+    }
+
+    #[test]
+    fn authorize() {
+        let schema = schema();
+        let entities = entities(&schema);
+        let policy = "permit(principal, action, resource) when { principal.isAdult };";
+        assert_eq!(decision(policy, "alice", &entities), Decision::Allow);
+        assert_eq!(decision(policy, "bob", &entities), Decision::Deny);
+        let policy = "permit(principal, action, resource) when { principal has isAdult && principal.ageNextYear == 11 };";
+        assert_eq!(decision(policy, "alice", &entities), Decision::Deny);
+        assert_eq!(decision(policy, "bob", &entities), Decision::Allow);
+    }
+
+    #[test]
+    fn slice_for() {
+        let schema = schema();
+        let entities = entities(&schema);
+        let policy = "permit(principal, action, resource) when { principal.isAdult };";
+        let request = Request::new(
+            Some(EntityUid::from_strs("User", "alice")),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("Document", "doc")),
+            Context::empty(),
+            None,
+        )
+        .unwrap();
+        let slice = entities.slice_for(&request, &PolicySet::from_str(policy).unwrap());
+        assert_eq!(slice.len(), 1);
+        assert_eq!(decision(policy, "alice", &slice), Decision::Allow);
+    }
+
+    #[test]
+    fn validate() {
+        let validator = Validator::new(schema());
+        let pset = PolicySet::from_str(
+            "permit(principal, action, resource) when { principal.isAdult && principal.ageNextYear > 1 };",
+        )
+        .unwrap();
+        assert!(validator
+            .validate(&pset, ValidationMode::default())
+            .validation_passed());
+        let pset = PolicySet::from_str(
+            "permit(principal, action, resource) when { principal.isAdult > 1 };",
+        )
+        .unwrap();
+        assert!(!validator
+            .validate(&pset, ValidationMode::default())
+            .validation_passed());
+    }
+
+    #[test]
+    fn cannot_be_set() {
+        let schema = schema();
+        assert_matches!(
+            Entities::from_json_value(
+                json!([
+                    { "uid": { "type": "User", "id": "alice" }, "attrs": { "age": 20, "isAdult": false }, "parents": [] }
+                ]),
+                Some(&schema),
+            ),
+            Err(entities::err::EntitiesError::Deserialization(
+                entities::json::err::JsonDeserializationError::EntitySchemaConformance(
+                    EntitySchemaConformanceError::DerivedEntityAttr(_)
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn to_human_string() {
+        assert_matches!(
+            schema().to_human_string(),
+            Err(ToHumanSyntaxError::UnrepresentableDerivedAttributes(ety)) => assert_eq!(ety, "User")
+        );
+        assert!(schema()
+            .to_json_string()
+            .unwrap()
+            .contains("derivedAttributes"));
+    }
+}
+
+mod mutually_exclusive_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use entities::conformance::err::EntitySchemaConformanceError;
+    use serde_json::json;
+
+    #[test]
+    fn entities_from_json() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            @mutuallyExclusive("userId", "serviceAccountId")
+            entity Principal { userId?: String, serviceAccountId?: String };
+            "#,
+        )
+        .unwrap();
+        assert!(Entities::from_json_value(
+            json!([
+                { "uid": { "type": "Principal", "id": "a" }, "attrs": { "userId": "a" }, "parents": [] },
+                { "uid": { "type": "Principal", "id": "b" }, "attrs": { "serviceAccountId": "b" }, "parents": [] },
+                { "uid": { "type": "Principal", "id": "c" }, "attrs": {}, "parents": [] }
+            ]),
+            Some(&schema),
+        )
+        .is_ok());
+        let err = assert_matches!(
+            Entities::from_json_value(
+                json!([
+                    { "uid": { "type": "Principal", "id": "a" }, "attrs": { "userId": "a", "serviceAccountId": "b" }, "parents": [] }
+                ]),
+                Some(&schema),
+            ),
+            Err(entities::err::EntitiesError::InvalidEntity(
+                err @ EntitySchemaConformanceError::MutuallyExclusiveEntityAttrs(_)
+            )) => err
+        );
+        assert_eq!(
+            err.to_string(),
+            r#"`Principal::"a"` has both attributes `userId` and `serviceAccountId`, but they are mutually exclusive according to the schema"#
+        );
+    }
+}
+
+mod validate_request_tests {
+    use super::*;
+    use cedar_policy_validator::RequestValidationError;
+    use cool_asserts::assert_matches;
+
+    fn schema() -> Schema {
+        Schema::from_str_natural(
+            r#"
+            entity User;
+            entity Photo;
+            action view appliesTo {
+                principal: User,
+                resource: Photo,
+                context: { mfa: Bool, reason?: String }
+            };
+            "#,
+        )
+        .unwrap()
+        .0
+    }
+
+    fn request(principal: &str, resource: &str, context: Context) -> Request {
+        Request::new(
+            Some(EntityUid::from_str(principal).unwrap()),
+            Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+            Some(EntityUid::from_str(resource).unwrap()),
+            context,
+            None,
+        )
+        .unwrap()
+    }
+
+    fn context(pairs: &[(&str, RestrictedExpression)]) -> Context {
+        Context::from_pairs(
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone())),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn valid() {
+        let schema = schema();
+        let mfa = context(&[("mfa", RestrictedExpression::new_bool(true))]);
+        assert_matches!(
+            schema.validate_request(&request(r#"User::"alice""#, r#"Photo::"pic""#, mfa)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let schema = schema();
+        let mfa = || context(&[("mfa", RestrictedExpression::new_bool(true))]);
+        assert_matches!(
+            schema.validate_request(&request(r#"Admin::"alice""#, r#"Photo::"pic""#, mfa())),
+            Err(RequestValidationError::UndeclaredPrincipalType { .. })
+        );
+        assert_matches!(
+            schema.validate_request(&request(r#"User::"alice""#, r#"User::"bob""#, mfa())),
+            Err(RequestValidationError::InvalidResourceType { .. })
+        );
+        assert_matches!(
+            schema.validate_request(&request(
+                r#"User::"alice""#,
+                r#"Photo::"pic""#,
+                Context::empty()
+            )),
+            Err(RequestValidationError::MissingContextAttribute { attr, .. }) => assert_eq!(attr, "mfa")
+        );
+        assert_matches!(
+            schema.validate_request(&request(
+                r#"User::"alice""#,
+                r#"Photo::"pic""#,
+                context(&[
+                    ("mfa", RestrictedExpression::new_bool(true)),
+                    ("ip", RestrictedExpression::new_string("10.0.0.1".into())),
+                ])
+            )),
+            Err(RequestValidationError::UnexpectedContextAttribute { attr, .. }) => assert_eq!(attr, "ip")
+        );
+        assert_matches!(
+            schema.validate_request(&request(
+                r#"User::"alice""#,
+                r#"Photo::"pic""#,
+                context(&[("mfa", RestrictedExpression::new_long(1))])
+            )),
+            Err(RequestValidationError::InvalidContext { .. })
+        );
+    }
+
+    #[cfg(feature = "partial-eval")]
+    #[test]
+    fn partial() {
+        let schema = schema();
+        let request = RequestBuilder::default()
+            .principal(Some(EntityUid::from_str(r#"User::"alice""#).unwrap()))
+            .build();
+        assert_matches!(schema.validate_request(&request), Ok(()));
+        let request = RequestBuilder::default()
+            .principal(Some(EntityUid::from_str(r#"Admin::"alice""#).unwrap()))
+            .build();
+        assert_matches!(
+            schema.validate_request(&request),
+            Err(RequestValidationError::UndeclaredPrincipalType { .. })
+        );
+        let request = RequestBuilder::default()
+            .action(Some(EntityUid::from_str(r#"Action::"view""#).unwrap()))
+            .build();
+        assert_matches!(schema.validate_request(&request), Ok(()));
+    }
+}
+
+mod entity_type_info_tests {
+    use super::*;
+
+    #[test]
+    fn attributes() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            type Address = { street: String, city?: String };
+            entity Group;
+            entity User in [Group] = {
+                name: String,
+                age?: Long,
+                address: Address,
+                groups: Set<Group>,
+                ip?: ipaddr,
+            };
+            "#,
+        )
+        .unwrap();
+        let user = schema
+            .entity_type(&EntityTypeName::from_str("User").unwrap())
+            .unwrap();
+        assert_eq!(user.name(), &EntityTypeName::from_str("User").unwrap());
+        assert!(!user.has_open_attributes());
+        let attrs = user
+            .attributes()
+            .map(|(attr, info)| (attr, info.type_str(), info.is_required()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            attrs,
+            vec![
+                ("address", "{ city?: String, street: String }", true),
+                ("age", "Long", false),
+                ("groups", "Set<Group>", true),
+                ("ip", "ipaddr", false),
+                ("name", "String", true),
+            ]
+        );
+        assert!(user.attribute("email").is_none());
+
+        let group = schema
+            .entity_type(&EntityTypeName::from_str("Group").unwrap())
+            .unwrap();
+        assert_eq!(group.attributes().count(), 0);
+    }
+
+    #[test]
+    fn undeclared() {
+        let (schema, _) = Schema::from_str_natural("entity User;").unwrap();
+        assert!(schema
+            .entity_type(&EntityTypeName::from_str("Group").unwrap())
+            .is_none());
+        assert!(schema
+            .entity_type(&EntityTypeName::from_str("Action").unwrap())
+            .is_none());
+    }
+}
+
+mod has_attr_path_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn eval(expr: &str, context: serde_json::Value) -> Result<EvalResult, EvaluationError> {
+        let request = Request::new(
+            Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+            Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+            Some(EntityUid::from_str(r#"User::"bob""#).unwrap()),
+            Context::from_json_value(context, None).unwrap(),
+            None,
+        )
+        .unwrap();
+        eval_expression(
+            &request,
+            &Entities::empty(),
+            &Expression::from_str(expr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn evaluate() {
+        let expr = "context has request.headers.authorization";
+        assert_eq!(eval(expr, json!({})).unwrap(), EvalResult::Bool(false));
+        assert_eq!(
+            eval(expr, json!({ "request": {} })).unwrap(),
+            EvalResult::Bool(false)
+        );
+        assert_eq!(
+            eval(expr, json!({ "request": { "headers": {} } })).unwrap(),
+            EvalResult::Bool(false)
+        );
+        assert_eq!(
+            eval(
+                expr,
+                json!({ "request": { "headers": { "authorization": "token" } } })
+            )
+            .unwrap(),
+            EvalResult::Bool(true)
+        );
+        // an attribute before the last which isn't a record or entity doesn't
+        // have the next attribute
+        assert_eq!(
+            eval(expr, json!({ "request": { "headers": 1 } })).unwrap(),
+            EvalResult::Bool(false)
+        );
+        assert_eq!(
+            eval("context has a.b", json!({ "a": 1 })).unwrap(),
+            EvalResult::Bool(false)
+        );
+        // but, like any other `has`, testing for an attribute of the target
+        // when it isn't a record or entity is an error
+        assert!(eval("context.a has b.c", json!({ "a": 1 })).is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            entity User;
+            action view appliesTo {
+                principal: User,
+                resource: User,
+                context: {
+                    request?: { headers?: { authorization?: String } },
+                    attempts?: Long,
+                }
+            };
+            "#,
+        )
+        .unwrap();
+        let validator = Validator::new(schema);
+
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action, resource) when {
+                context has request.headers.authorization &&
+                context.request.headers.authorization == "token"
+            };"#,
+        )
+        .unwrap();
+        let result = validator.validate(&policies, ValidationMode::Strict);
+        assert!(result.validation_passed(), "{result:?}");
+
+        let policies = PolicySet::from_str(
+            r#"permit(principal, action, resource) when { context has attempts.count };"#,
+        )
+        .unwrap();
+        let result = validator.validate(&policies, ValidationMode::Strict);
+        assert!(!result.validation_passed());
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let policy = Policy::parse(
+            None,
+            "permit(principal, action, resource) when { context has request.headers.authorization };",
+        )
+        .unwrap();
+        let json = policy.to_json().unwrap();
+        assert_eq!(
+            json.get("conditions"),
+            Some(&json!([{
+                "kind": "when",
+                "body": {
+                    "has": {
+                        "left": { "Var": "context" },
+                        "attr": "request",
+                        "path": ["headers", "authorization"]
+                    }
+                }
+            }]))
+        );
+        let roundtripped = Policy::from_json(None, json.clone()).unwrap();
+        assert_eq!(roundtripped.to_json().unwrap(), json);
+
+        let template = Template::parse(
+            None,
+            "permit(principal == ?principal, action, resource) when { context has request.headers };",
+        )
+        .unwrap();
+        let json = template.to_json().unwrap();
+        let roundtripped = Template::from_json(None, json.clone()).unwrap();
+        assert_eq!(roundtripped.to_json().unwrap(), json);
+    }
+}
+
+mod entity_typed_attr_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+
+    fn entity() -> Entity {
+        let attrs = HashMap::from([
+            ("age".to_string(), RestrictedExpression::new_long(21)),
+            (
+                "name".to_string(),
+                RestrictedExpression::new_string("Alice".to_string()),
+            ),
+            ("admin".to_string(), RestrictedExpression::new_bool(true)),
+            (
+                "tags".to_string(),
+                RestrictedExpression::new_set([
+                    RestrictedExpression::new_string("a".to_string()),
+                    RestrictedExpression::new_string("b".to_string()),
+                ]),
+            ),
+        ]);
+        Entity::new(EntityUid::from_strs("User", "alice"), attrs, HashSet::new()).unwrap()
+    }
+
+    #[test]
+    fn typed_attrs() {
+        let entity = entity();
+        assert_matches!(entity.attr_long("age"), Some(Ok(21)));
+        assert_matches!(entity.attr_string("name"), Some(Ok(name)) => assert_eq!(name, "Alice"));
+        assert_matches!(entity.attr_bool("admin"), Some(Ok(true)));
+        assert_matches!(entity.attr_set("tags"), Some(Ok(tags)) => {
+            assert_eq!(tags.len(), 2);
+            assert!(tags.contains(&EvalResult::String("a".to_string())));
+        });
+    }
+
+    #[test]
+    fn missing_attrs() {
+        let entity = entity();
+        assert!(entity.attr_long("foo").is_none());
+        assert!(entity.attr_string("foo").is_none());
+        assert!(entity.attr_bool("foo").is_none());
+        assert!(entity.attr_set("foo").is_none());
+    }
+
+    #[test]
+    fn wrong_type() {
+        let entity = entity();
+        assert_matches!(entity.attr_long("name"), Some(Err(err)) => {
+            assert_eq!(err.attr(), "name");
+            assert_eq!(
+                err.to_string(),
+                "expected attribute `name` to have type long, but it has type string"
+            );
+        });
+        assert_matches!(entity.attr_string("age"), Some(Err(_)));
+        assert_matches!(entity.attr_bool("tags"), Some(Err(_)));
+        assert_matches!(entity.attr_set("admin"), Some(Err(_)));
+    }
+}
+
+mod determining_policies_tests {
+    use super::*;
+
+    fn request() -> Request {
+        Request::new(
+            Some(EntityUid::from_strs("User", "alice")),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("Photo", "trip")),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn allow() {
+        let mut policies = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource);
+            permit(principal == ?principal, action, resource);
+            "#,
+        )
+        .unwrap();
+        let template_id = PolicyId::new("policy1");
+        let links = HashMap::from([(SlotId::principal(), EntityUid::from_strs("User", "alice"))]);
+        policies
+            .link(template_id.clone(), PolicyId::new("linked"), links.clone())
+            .unwrap();
+        let response = Authorizer::new().is_authorized(&request(), &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Allow);
+        let determining = response.determining_policies();
+        assert_eq!(
+            determining
+                .iter()
+                .map(|p| p.id().to_string())
+                .collect::<Vec<_>>(),
+            vec!["linked", "policy0"]
+        );
+        assert!(determining.iter().all(|p| p.effect() == Effect::Permit));
+        assert_eq!(determining[0].template_id(), Some(&template_id));
+        assert_eq!(determining[0].template_links(), Some(&links));
+        assert_eq!(determining[1].template_id(), None);
+        assert_eq!(determining[1].template_links(), None);
+    }
+
+    #[test]
+    fn deny_forbids_first() {
+        let policies = PolicySet::from_str(
+            r#"
+            permit(principal, action, resource) when { context.risk > 5 };
+            forbid(principal, action, resource);
+            "#,
+        )
+        .unwrap();
+        let authorizer = Authorizer::with_config(AuthorizerConfig {
+            error_handling: ErrorHandling::DenyOnAnyError,
+        });
+        let response = authorizer.is_authorized(&request(), &policies, &Entities::empty());
+        assert_eq!(response.decision(), Decision::Deny);
+        assert_eq!(
+            response
+                .determining_policies()
+                .iter()
+                .map(|p| (p.id().to_string(), p.effect()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("policy1".to_string(), Effect::Forbid),
+                ("policy0".to_string(), Effect::Permit)
+            ]
+        );
+    }
+
+    #[test]
+    fn response_new() {
+        let response = Response::new(
+            Decision::Allow,
+            HashSet::from([PolicyId::new("policy0")]),
+            Vec::new(),
+        );
+        assert!(response.determining_policies().is_empty());
+    }
+}
+
+mod entities_parse_mode_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use serde_json::json;
+
+    #[test]
+    fn require_parents_present() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            entity Group;
+            entity User in [Group];
+            action view appliesTo { principal: User, resource: Group };
+            "#,
+        )
+        .unwrap();
+        let data = json!([
+            { "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [{ "type": "Group", "id": "admins" }] },
+            { "uid": { "type": "User", "id": "bob" }, "attrs": {}, "parents": [{ "type": "Group", "id": "staff" }] },
+            { "uid": { "type": "Group", "id": "admins" }, "attrs": {}, "parents": [] }
+        ]);
+        assert!(Entities::from_json_value(data.clone(), Some(&schema)).is_ok());
+        assert!(Entities::from_json_value_with_mode(
+            data.clone(),
+            Some(&schema),
+            EntitiesParseMode::AllowMissingParents
+        )
+        .is_ok());
+        assert_matches!(
+            Entities::from_json_value_with_mode(
+                data.clone(),
+                Some(&schema),
+                EntitiesParseMode::RequireParentsPresent
+            ),
+            Err(err @ entities::err::EntitiesError::MissingParent(_)) => {
+                assert_eq!(err.error_code(), "cedar::entities::missing_parent");
+                assert_eq!(
+                    err.to_string(),
+                    r#"entity `User::"bob"` has parent `Group::"staff"`, which is not one of the entities"#
+                );
+            }
+        );
+        assert_matches!(
+            Entities::from_json_str_with_mode(
+                &data.to_string(),
+                Some(&schema),
+                EntitiesParseMode::RequireParentsPresent
+            ),
+            Err(entities::err::EntitiesError::MissingParent(_))
+        );
+        assert_matches!(
+            Entities::from_json_file_with_mode(
+                data.to_string().as_bytes(),
+                Some(&schema),
+                EntitiesParseMode::RequireParentsPresent
+            ),
+            Err(entities::err::EntitiesError::MissingParent(_))
+        );
+    }
+}
+
+mod validate_links_tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::from_str_natural(
+            r#"
+            entity User in [Group];
+            entity Group;
+            entity Photo;
+            action view appliesTo { principal: User, resource: Photo };
+            "#,
+        )
+        .unwrap()
+        .0
+    }
+
+    fn link(policies: &mut PolicySet, template: &str, link: &str, vals: &[(SlotId, &str)]) {
+        policies
+            .link(
+                PolicyId::from_str(template).unwrap(),
+                PolicyId::from_str(link).unwrap(),
+                vals.iter()
+                    .map(|(slot, euid)| (slot.clone(), EntityUid::from_str(euid).unwrap()))
+                    .collect(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn valid_links() {
+        let mut policies = PolicySet::from_str_with_ids(
+            r#"
+            @id("principal_eq")
+            permit(principal == ?principal, action, resource);
+            @id("principal_in")
+            permit(principal in ?principal, action == Action::"view", resource == ?resource);
+            permit(principal, action, resource);
+            "#,
+        )
+        .unwrap();
+        link(
+            &mut policies,
+            "principal_eq",
+            "link0",
+            &[(SlotId::principal(), r#"User::"alice""#)],
+        );
+        link(
+            &mut policies,
+            "principal_in",
+            "link1",
+            &[
+                (SlotId::principal(), r#"Group::"admins""#),
+                (SlotId::resource(), r#"Photo::"vacation""#),
+            ],
+        );
+        assert!(policies.validate_links(&schema()).is_empty());
+    }
+
+    #[test]
+    fn invalid_links() {
+        let mut policies = PolicySet::from_str_with_ids(
+            r#"
+            @id("t1")
+            permit(principal == ?principal, action == Action::"view", resource in ?resource);
+            @id("t0")
+            permit(principal, action, resource == ?resource);
+            "#,
+        )
+        .unwrap();
+        link(
+            &mut policies,
+            "t1",
+            "link1",
+            &[
+                (SlotId::principal(), r#"Group::"admins""#),
+                (SlotId::resource(), r#"Album::"trips""#),
+            ],
+        );
+        link(
+            &mut policies,
+            "t0",
+            "link0",
+            &[(SlotId::resource(), r#"User::"alice""#)],
+        );
+        link(
+            &mut policies,
+            "t0",
+            "link2",
+            &[(SlotId::resource(), r#"Photo::"vacation""#)],
+        );
+
+        let errors = policies.validate_links(&schema());
+        let summary = errors
+            .iter()
+            .map(|err| {
+                (
+                    err.template_id().to_string(),
+                    err.link_id().to_string(),
+                    err.slot(),
+                    err.kind(),
+                    err.value().map(ToString::to_string),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "t0".to_string(),
+                    "link0".to_string(),
+                    SlotId::resource(),
+                    LinkValidationErrorKind::MismatchedEntityType,
+                    Some(r#"User::"alice""#.to_string())
+                ),
+                (
+                    "t1".to_string(),
+                    "link1".to_string(),
+                    SlotId::principal(),
+                    LinkValidationErrorKind::MismatchedEntityType,
+                    Some(r#"Group::"admins""#.to_string())
+                ),
+                (
+                    "t1".to_string(),
+                    "link1".to_string(),
+                    SlotId::resource(),
+                    LinkValidationErrorKind::UndeclaredEntityType,
+                    Some(r#"Album::"trips""#.to_string())
+                ),
+            ]
+        );
+        assert_eq!(
+            errors[2].to_string(),
+            r#"in policy `link1` linked from template `t1`, slot `?resource` is linked to `Album::"trips"`, whose entity type is not declared in the schema"#
+        );
+    }
+}
+
+mod combine_responses_tests {
+    use super::*;
+
+    fn request() -> Request {
+        Request::new(
+            Some(EntityUid::from_strs("User", "alice")),
+            Some(EntityUid::from_strs("Action", "view")),
+            Some(EntityUid::from_strs("Photo", "vacation")),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn authorize(policies: &str) -> Response {
+        Authorizer::new().is_authorized(
+            &request(),
+            &PolicySet::from_str(policies).unwrap(),
+            &Entities::empty(),
+        )
+    }
+
+    fn reasons(response: &Response) -> Vec<String> {
+        let mut reasons = response
+            .diagnostics()
+            .reason()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        reasons.sort();
+        reasons
+    }
+
+    #[test]
+    fn deny_overrides() {
+        let allow = authorize("permit(principal, action, resource);");
+        let deny = authorize(r#"forbid(principal == User::"alice", action, resource);"#);
+        let combined = Response::combine(
+            [allow.clone(), deny.clone()],
+            CombineStrategy::DenyOverrides,
+        );
+        assert_eq!(combined.decision(), Decision::Deny);
+        assert_eq!(reasons(&combined), vec!["policy0"]);
+
+        let combined = Response::combine([allow.clone(), allow], CombineStrategy::DenyOverrides);
+        assert_eq!(combined.decision(), Decision::Allow);
+        assert_eq!(reasons(&combined), vec!["policy0"]);
+    }
+
+    #[test]
+    fn permit_overrides() {
+        let allow = authorize("permit(principal, action, resource);");
+        let deny = authorize(r#"forbid(principal == User::"alice", action, resource);"#);
+        let combined = Response::combine([deny.clone(), allow], CombineStrategy::PermitOverrides);
+        assert_eq!(combined.decision(), Decision::Allow);
+
+        let combined = Response::combine([deny.clone(), deny], CombineStrategy::PermitOverrides);
+        assert_eq!(combined.decision(), Decision::Deny);
+    }
+
+    #[test]
+    fn not_applicable() {
+        // A policy set with no satisfied policy doesn't override the others
+        let allow = authorize("permit(principal, action, resource);");
+        let not_applicable = authorize(r#"permit(principal == User::"bob", action, resource);"#);
+        assert_eq!(not_applicable.decision(), Decision::Deny);
+        let combined = Response::combine(
+            [allow, not_applicable.clone()],
+            CombineStrategy::DenyOverrides,
+        );
+        assert_eq!(combined.decision(), Decision::Allow);
+        assert_eq!(reasons(&combined), vec!["policy0"]);
+
+        for strategy in [
+            CombineStrategy::DenyOverrides,
+            CombineStrategy::PermitOverrides,
+        ] {
+            let combined = Response::combine([not_applicable.clone()], strategy);
+            assert_eq!(combined.decision(), Decision::Deny);
+        }
+    }
+
+    #[test]
+    fn empty() {
+        for strategy in [
+            CombineStrategy::DenyOverrides,
+            CombineStrategy::PermitOverrides,
+        ] {
+            let combined = Response::combine(std::iter::empty(), strategy);
+            assert_eq!(combined.decision(), Decision::Deny);
+            assert_eq!(combined.diagnostics().reason().count(), 0);
+        }
+    }
+
+    #[test]
+    fn labeled() {
+        let photos = authorize(
+            r#"permit(principal, action, resource);
+            permit(principal, action, resource) when { principal.missing };"#,
+        );
+        let billing = authorize("permit(principal, action, resource);");
+        let audit = authorize("forbid(principal, action, resource) when { false };");
+        let combined = Response::combine_labeled(
+            [("photos", photos), ("billing", billing), ("audit", audit)],
+            CombineStrategy::PermitOverrides,
+        );
+        assert_eq!(combined.decision(), Decision::Allow);
+        assert_eq!(
+            reasons(&combined),
+            vec!["billing/policy0", "photos/policy0"]
+        );
+        assert_eq!(
+            combined
+                .diagnostics()
+                .errors()
+                .map(|err| err.id().to_string())
+                .collect::<Vec<_>>(),
+            vec!["photos/policy1"]
+        );
+        assert_eq!(
+            combined
+                .determining_policies()
+                .iter()
+                .map(|policy| policy.id().to_string())
+                .collect::<Vec<_>>(),
+            vec!["billing/policy0", "photos/policy0"]
+        );
+    }
+
+    #[test]
+    fn merge_diagnostics() {
+        let first = authorize(
+            r#"permit(principal, action, resource);
+            permit(principal, action, resource) when { principal.missing };"#,
+        );
+        let second = authorize(
+            r#"forbid(principal, action, resource) when { false };
+            permit(principal, action, resource) when { principal.missing };"#,
+        );
+        let third = authorize("permit(principal, action, resource) when { resource.missing };");
+
+        let merged = Diagnostics::merge(
+            [&first, &second, &third]
+                .into_iter()
+                .map(|response| response.diagnostics().clone()),
+        );
+        assert_eq!(
+            merged.reason().cloned().collect::<HashSet<_>>(),
+            HashSet::from([PolicyId::new("policy0")])
+        );
+        // the error for `policy1` is the same in `first` and `second`
+        assert_eq!(
+            merged
+                .errors()
+                .map(|err| err.id().to_string())
+                .collect::<Vec<_>>(),
+            vec!["policy1", "policy0"]
+        );
+
+        let merged = Diagnostics::merge_labeled(
+            [("first", &first), ("second", &second)]
+                .into_iter()
+                .map(|(label, response)| (label, response.diagnostics().clone())),
+        );
+        assert_eq!(
+            merged.reason().cloned().collect::<HashSet<_>>(),
+            HashSet::from([PolicyId::new("first/policy0")])
+        );
+        let mut errors = merged
+            .errors()
+            .map(|err| err.id().to_string())
+            .collect::<Vec<_>>();
+        errors.sort();
+        assert_eq!(errors, vec!["first/policy1", "second/policy1"]);
+    }
+}
+
+mod accessed_attributes_tests {
+    use super::*;
+
+    #[test]
+    fn accessed_attributes() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"
+            namespace App {
+                entity User = { name: String, age: Long, department: String };
+                entity Document = { owner: User, classification: String, size: Long };
+                action read appliesTo { principal: User, resource: Document };
+            }
+            "#,
+        )
+        .unwrap();
+        let mut policies = PolicySet::from_str_with_ids(
+            r#"
+            @id("owner")
+            permit(principal, action, resource) when { resource.owner == principal };
+            @id("department")
+            permit(principal == ?principal, action, resource)
+            when { principal has department && resource.owner.department == principal.department };
+            "#,
+        )
+        .unwrap();
+        policies
+            .add(
+                Policy::from_str(
+                    r#"forbid(principal, action, resource) when { resource["classification"] == "secret" };"#,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+        let accessed = policies.accessed_attributes(&schema);
+        let mut accessed = accessed
+            .into_iter()
+            .map(|(name, attrs)| {
+                let mut attrs = attrs.into_iter().map(String::from).collect::<Vec<_>>();
+                attrs.sort();
+                (name.to_string(), attrs)
+            })
+            .collect::<Vec<_>>();
+        accessed.sort();
+        assert_eq!(
+            accessed,
+            vec![
+                (
+                    "App::Document".to_string(),
+                    vec!["classification".to_string(), "owner".to_string()]
+                ),
+                ("App::User".to_string(), vec!["department".to_string()]),
+            ]
+        );
+    }
+}
+
+#[cfg(feature = "partial-eval")]
+mod partial_unknown_action_tests {
+    use super::*;
+
+    fn policies() -> PolicySet {
+        PolicySet::from_str(
+            r#"permit(principal == User::"alice", action == Action::"view", resource);
+            permit(principal, action in [Action::"edit", Action::"view"], resource) when { resource.public };
+            forbid(principal, action == Action::"delete", resource == Photo::"vacation");
+            permit(principal == User::"bob", action, resource);"#,
+        )
+        .unwrap()
+    }
+
+    fn entities() -> Entities {
+        Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "Photo", "id": "vacation" }, "attrs": { "public": true }, "parents": [] }
+            ]),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn request() -> Request {
+        // the action is unknown, since it isn't set
+        RequestBuilder::default()
+            .principal(Some(EntityUid::from_str(r#"User::"alice""#).unwrap()))
+            .resource(Some(EntityUid::from_str(r#"Photo::"vacation""#).unwrap()))
+            .context(Context::empty())
+            .build()
+    }
+
+    #[test]
+    fn residuals_depend_only_on_action() {
+        let response =
+            Authorizer::new().is_authorized_partial(&request(), &policies(), &entities());
+        assert_eq!(response.decision(), None);
+        let residuals = response
+            .nontrivial_residuals()
+            .map(|policy| (policy.id().to_string(), policy.to_string()))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(residuals.len(), 3);
+        assert!(residuals["policy0"].contains(r#"unknown("action") == Action::"view""#));
+        assert!(residuals["policy1"]
+            .contains(r#"unknown("action") in [Action::"edit", Action::"view"]"#));
+        assert!(residuals["policy2"].contains(r#"unknown("action") == Action::"delete""#));
+        // the residuals mention nothing but the action
+        for residual in residuals.values() {
+            assert!(!residual.contains("principal =="), "{residual}");
+            assert!(!residual.contains("resource."), "{residual}");
+        }
+        // `policy3` can't apply to `alice`, whatever the action
+        assert!(response
+            .get(&PolicyId::from_str("policy3").unwrap())
+            .unwrap()
+            .to_string()
+            .contains("false"));
+    }
+
+    #[test]
+    fn reauthorize_with_action() {
+        let authorizer = Authorizer::new();
+        let response = authorizer.is_authorized_partial(&request(), &policies(), &entities());
+        let decision = |action: &str| {
+            let action = EntityUid::from_str(action).unwrap();
+            let concrete = Request::new(
+                Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+                Some(action.clone()),
+                Some(EntityUid::from_str(r#"Photo::"vacation""#).unwrap()),
+                Context::empty(),
+                None,
+            )
+            .unwrap();
+            response
+                .reauthorize(
+                    HashMap::from([(
+                        "action".into(),
+                        RestrictedExpression::new_entity_uid(action),
+                    )]),
+                    &authorizer,
+                    concrete,
+                    &entities(),
+                )
+                .unwrap()
+                .decision()
+        };
+        assert_eq!(decision(r#"Action::"view""#), Some(Decision::Allow));
+        assert_eq!(decision(r#"Action::"edit""#), Some(Decision::Allow));
+        assert_eq!(decision(r#"Action::"delete""#), Some(Decision::Deny));
+        assert_eq!(decision(r#"Action::"share""#), Some(Decision::Deny));
+    }
+}
+
+mod substitute_entity_types_tests {
+    use super::*;
+
+    fn mapping() -> HashMap<EntityTypeName, EntityTypeName> {
+        HashMap::from([
+            (
+                EntityTypeName::from_str("Legacy::User").unwrap(),
+                EntityTypeName::from_str("Corp::Employee").unwrap(),
+            ),
+            (
+                EntityTypeName::from_str("Folder").unwrap(),
+                EntityTypeName::from_str("Corp::Drive").unwrap(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn static_policy() {
+        let policy = Policy::parse(
+            Some("p".to_string()),
+            r#"@note("kept")
+            permit(
+                principal is Legacy::User in Legacy::Group::"admins",
+                action == Action::"read",
+                resource in Folder::"root"
+            ) when {
+                resource is Folder && context.approvers.contains(Legacy::User::"bob")
+            };"#,
+        )
+        .unwrap();
+        let renamed = policy.substitute_entity_types(&mapping());
+        let expected = Policy::parse(
+            Some("p".to_string()),
+            r#"@note("kept")
+            permit(
+                principal is Corp::Employee in Legacy::Group::"admins",
+                action == Action::"read",
+                resource in Corp::Drive::"root"
+            ) when {
+                resource is Corp::Drive && context.approvers.contains(Corp::Employee::"bob")
+            };"#,
+        )
+        .unwrap();
+        assert_eq!(renamed.to_json().unwrap(), expected.to_json().unwrap());
+        assert_eq!(renamed.id(), policy.id());
+        assert_eq!(renamed.annotation("note"), Some("kept"));
+        assert!(renamed.is_static());
+    }
+
+    #[test]
+    fn unmapped_types_unchanged() {
+        let policy = Policy::parse(
+            None,
+            r#"permit(principal == User::"alice", action, resource is Legacy::UserGroup) when { resource.owner == Team::"core" };"#,
+        )
+        .unwrap();
+        assert_eq!(
+            policy
+                .substitute_entity_types(&mapping())
+                .to_json()
+                .unwrap(),
+            policy.to_json().unwrap()
+        );
+    }
+
+    #[test]
+    fn linked_policy() {
+        let mut policies = PolicySet::new();
+        policies
+            .add_template(
+                Template::parse(
+                    Some("t".to_string()),
+                    r#"permit(principal == ?principal, action, resource in ?resource) when { principal is Legacy::User };"#,
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        policies
+            .link(
+                PolicyId::new("t"),
+                PolicyId::new("link"),
+                HashMap::from([
+                    (
+                        SlotId::principal(),
+                        EntityUid::from_str(r#"Legacy::User::"alice""#).unwrap(),
+                    ),
+                    (
+                        SlotId::resource(),
+                        EntityUid::from_str(r#"Photo::"vacation""#).unwrap(),
+                    ),
+                ]),
+            )
+            .unwrap();
+        let renamed = policies
+            .policy(&PolicyId::new("link"))
+            .unwrap()
+            .substitute_entity_types(&mapping());
+        assert_eq!(renamed.id(), &PolicyId::new("link"));
+        assert_eq!(renamed.template_id(), Some(&PolicyId::new("t")));
+        assert_eq!(
+            renamed.template_links(),
+            Some(HashMap::from([
+                (
+                    SlotId::principal(),
+                    EntityUid::from_str(r#"Corp::Employee::"alice""#).unwrap(),
+                ),
+                (
+                    SlotId::resource(),
+                    EntityUid::from_str(r#"Photo::"vacation""#).unwrap(),
+                ),
+            ]))
+        );
+        let text = renamed.to_string();
+        assert!(text.contains(r#"principal == Corp::Employee::"alice""#));
+        assert!(text.contains("principal is Corp::Employee"));
+        assert!(!text.contains("Legacy::User"));
+    }
+}
+
+mod validate_entities_sampled_tests {
+    use super::*;
+
+    #[test]
+    fn counts_past_sample() {
+        let (schema, _) = Schema::from_str_natural(
+            r#"entity Group; entity User in [Group] = { name: String, age?: Long };"#,
+        )
+        .unwrap();
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "User", "id": "alice" }, "attrs": { "name": "Alice" }, "parents": [] },
+                { "uid": { "type": "User", "id": "bob" }, "attrs": { "age": "old" }, "parents": [] },
+                { "uid": { "type": "Group", "id": "g" }, "attrs": { "name": "g" }, "parents": [] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let report = Validator::new(schema).validate_entities_sampled(&entities, 0);
+        assert!(!report.validation_passed());
+        assert_eq!(report.entities_checked(), 3);
+        assert_eq!(report.invalid_entities(), 2);
+        assert_eq!(report.total_violations(), 3);
+        assert_eq!(
+            report.violations_by_entity_type(),
+            HashMap::from([
+                (&EntityTypeName::from_str("User").unwrap(), 2),
+                (&EntityTypeName::from_str("Group").unwrap(), 1),
+            ])
+        );
+        assert!(report.sample().is_empty());
+        assert!(report.is_truncated());
+    }
+}
+
+mod layered_authorizer_tests {
+    use super::*;
+
+    fn request(action: &str) -> Request {
+        Request::new(
+            Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+            Some(EntityUid::from_str(&format!(r#"Action::"{action}""#)).unwrap()),
+            Some(EntityUid::from_str(r#"Photo::"vacation""#).unwrap()),
+            Context::empty(),
+            None,
+        )
+        .unwrap()
+    }
+
+    /// An org layer permitting `view` and forbidding `delete`, above a team
+    /// layer permitting `delete` and `share` and forbidding `view`
+    fn authorizer(combination: LayerCombination) -> LayeredAuthorizer {
+        let org = PolicySet::from_str(
+            r#"permit(principal, action == Action::"view", resource);
+            forbid(principal, action == Action::"delete", resource);"#,
+        )
+        .unwrap();
+        let team = PolicySet::from_str(
+            r#"permit(principal, action in [Action::"delete", Action::"share"], resource);
+            forbid(principal, action == Action::"view", resource);
+            permit(principal, action, resource) when { principal.missing };"#,
+        )
+        .unwrap();
+        LayeredAuthorizer::new(vec![org, team], combination)
+    }
+
+    fn decide(authorizer: &LayeredAuthorizer, action: &str) -> (Decision, Option<usize>) {
+        let response = authorizer.is_authorized(&request(action), &Entities::empty());
+        (response.decision(), response.deciding_layer())
+    }
+
+    #[test]
+    fn first_applicable() {
+        let authorizer = authorizer(LayerCombination::FirstApplicable);
+        assert_eq!(decide(&authorizer, "view"), (Decision::Allow, Some(0)));
+        assert_eq!(decide(&authorizer, "delete"), (Decision::Deny, Some(0)));
+        assert_eq!(decide(&authorizer, "share"), (Decision::Allow, Some(1)));
+        assert_eq!(decide(&authorizer, "edit"), (Decision::Deny, None));
+    }
+
+    #[test]
+    fn deny_overrides() {
+        let authorizer = authorizer(LayerCombination::DenyOverrides);
+        assert_eq!(decide(&authorizer, "view"), (Decision::Deny, Some(1)));
+        assert_eq!(decide(&authorizer, "delete"), (Decision::Deny, Some(0)));
+        assert_eq!(decide(&authorizer, "share"), (Decision::Allow, Some(1)));
+        assert_eq!(decide(&authorizer, "edit"), (Decision::Deny, None));
+    }
+
+    #[test]
+    fn permit_overrides() {
+        let authorizer = authorizer(LayerCombination::PermitOverrides);
+        assert_eq!(decide(&authorizer, "view"), (Decision::Allow, Some(0)));
+        assert_eq!(decide(&authorizer, "delete"), (Decision::Allow, Some(1)));
+        assert_eq!(decide(&authorizer, "share"), (Decision::Allow, Some(1)));
+        assert_eq!(decide(&authorizer, "edit"), (Decision::Deny, None));
+    }
+
+    #[test]
+    fn same_as_combine() {
+        for (combination, strategy) in [
+            (
+                LayerCombination::DenyOverrides,
+                CombineStrategy::DenyOverrides,
+            ),
+            (
+                LayerCombination::PermitOverrides,
+                CombineStrategy::PermitOverrides,
+            ),
+        ] {
+            let authorizer = authorizer(combination);
+            for action in ["view", "delete", "share", "edit"] {
+                let responses = authorizer.layers().iter().map(|policies| {
+                    Authorizer::new().is_authorized(&request(action), policies, &Entities::empty())
+                });
+                assert_eq!(
+                    decide(&authorizer, action).0,
+                    Response::combine(responses, strategy).decision(),
+                    "for {action} with {strategy:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reasons_from_deciding_layer() {
+        let authorizer = authorizer(LayerCombination::DenyOverrides);
+        let response = authorizer
+            .is_authorized(&request("view"), &Entities::empty())
+            .into_response();
+        assert_eq!(
+            response.diagnostics().reason().collect::<Vec<_>>(),
+            vec![&PolicyId::new("policy1")]
+        );
+        // The error of the team layer's `principal.missing` policy
+        assert_eq!(response.diagnostics().errors().count(), 1);
+
+        // The team layer isn't evaluated when the org layer decides first
+        let authorizer = self::authorizer(LayerCombination::FirstApplicable);
+        let response = authorizer.is_authorized(&request("view"), &Entities::empty());
+        assert_eq!(response.response().diagnostics().errors().count(), 0);
+    }
+}
+
+mod normalize_entity_uid_tests {
+    use super::*;
+
+    #[test]
+    fn unnormalized_variants_are_equal() {
+        let expected = EntityUid::from_str(r#"NS::User::"alice""#).unwrap();
+        for src in [
+            r#"NS::User::"alice""#,
+            r#"NS :: User :: "alice""#,
+            "NS::User // the user type\n::\"alice\"",
+        ] {
+            let euid = EntityUid::from_unnormalized_str(src).unwrap();
+            assert_eq!(euid, expected, "for {src}");
+            assert_eq!(euid.to_string(), expected.to_string(), "for {src}");
+            assert_eq!(euid.normalize(), euid, "for {src}");
+        }
+        assert!(EntityUid::from_str(r#"NS :: User :: "alice""#).is_err());
+        assert!(EntityUid::from_unnormalized_str(r#"NS::User::alice"#).is_err());
+    }
+
+    #[test]
+    fn entity_ids_are_not_normalized() {
+        let alice = EntityUid::from_str(r#"User::"alice""#).unwrap();
+        for src in [r#"User::" alice""#, r#"User::"Alice""#] {
+            let euid = EntityUid::from_unnormalized_str(src).unwrap();
+            assert_ne!(euid.normalize(), alice, "for {src}");
+        }
+    }
+
+    #[test]
+    fn entities_lookup() {
+        let entities = Entities::from_json_value(
+            serde_json::json!([
+                { "uid": { "type": "NS::User", "id": "alice" }, "attrs": {}, "parents": [] },
+            ]),
+            None,
+        )
+        .unwrap();
+        let from_json = EntityUid::from_json(
+            serde_json::json!({ "__entity": { "type": "NS::User", "id": "alice" } }),
+        )
+        .unwrap();
+        let from_parts = EntityUid::from_type_name_and_id(
+            EntityTypeName::from_str("NS::User").unwrap(),
+            EntityId::new("alice"),
+        );
+        let unnormalized = EntityUid::from_unnormalized_str(r#"NS :: User :: "alice""#).unwrap();
+        for euid in [from_json, from_parts, unnormalized] {
+            assert_eq!(entities.get(&euid).map(Entity::uid), Some(euid));
+        }
+        let other = EntityUid::from_unnormalized_str(r#"NS :: User :: "alice ""#).unwrap();
+        assert!(entities.get(&other).is_none());
+    }
+}
+
+mod restricted_expression_json_tests {
+    use super::*;
+    use crate::entities::json::err::{JsonDeserializationError, JsonSerializationError};
+    use cool_asserts::assert_matches;
+
+    #[track_caller]
+    fn assert_round_trips(src: &str) {
+        let expr = RestrictedExpression::from_str(src).unwrap();
+        let json = expr.to_json_value().unwrap();
+        let round_tripped = RestrictedExpression::from_json_value(json.clone()).unwrap();
+        assert_eq!(round_tripped.to_json_value().unwrap(), json, "for {src}");
+        assert!(
+            ast::Expr::from(round_tripped.into_inner()).eq_shape(&expr.into_inner().into()),
+            "for {src}"
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        for src in [
+            "true",
+            "false",
+            "0",
+            "-9223372036854775808",
+            "9223372036854775807",
+            r#""""#,
+            r#""a \"quoted\"\nstring ✓""#,
+            r#"User::"alice""#,
+            r#"NS::User::"a \"quoted\" id""#,
+            r#"ip("10.0.0.1")"#,
+            r#"ip("10.0.0.0/24")"#,
+            r#"decimal("-1.2345")"#,
+            "[]",
+            r#"[1, "two", [true, User::"alice"], ip("::1")]"#,
+            "{}",
+            r#"{ a: { b: { c: [decimal("0.5")] } }, "key with spaces": 1, ip: "not an ip" }"#,
+        ] {
+            assert_round_trips(src);
+        }
+    }
+
+    #[test]
+    fn escapes() {
+        let expr =
+            RestrictedExpression::from_str(r#"{ owner: User::"alice", addr: ip("10.0.0.1") }"#)
+                .unwrap();
+        assert_eq!(
+            expr.to_json_value().unwrap(),
+            serde_json::json!({
+                "owner": { "__entity": { "type": "User", "id": "alice" } },
+                "addr": { "__extn": { "fn": "ip", "arg": "10.0.0.1" } },
+            })
+        );
+        // without a schema, an implicit extension call is just a record
+        let expr =
+            RestrictedExpression::from_json_value(serde_json::json!({ "fn": "ip", "arg": "1" }))
+                .unwrap();
+        let keys = expr
+            .into_inner()
+            .as_record_pairs()
+            .map(|pairs| pairs.map(|(k, _)| k.to_string()).collect::<Vec<_>>());
+        assert_eq!(keys, Some(vec!["arg".to_string(), "fn".to_string()]));
+    }
+
+    #[test]
+    fn unrepresentable() {
+        let expr = RestrictedExpression::from_str(r#"{ __entity: { type: "User", id: "alice" } }"#)
+            .unwrap();
+        assert_matches!(
+            expr.to_json_value(),
+            Err(JsonSerializationError::ReservedKey(_))
+        );
+        let expr = RestrictedExpression::from_str(r#"ip("10.0.0.1").isInRange(ip("10.0.0.0/8"))"#)
+            .unwrap();
+        assert_matches!(
+            expr.to_json_value(),
+            Err(JsonSerializationError::ExtnCall2OrMoreArguments(_))
+        );
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert_matches!(
+            RestrictedExpression::from_json_value(serde_json::Value::Null),
+            Err(JsonDeserializationError::Null(_))
+        );
+        assert_matches!(
+            RestrictedExpression::from_json_value(serde_json::json!({ "__expr": "1 + 1" })),
+            Err(JsonDeserializationError::ExprTag(_))
+        );
+        assert_matches!(
+            RestrictedExpression::from_json_value(
+                serde_json::json!({ "__entity": { "type": "User :: Admin", "id": "alice" } })
+            ),
+            Err(JsonDeserializationError::ParseEscape(_))
+        );
+    }
+}
+
+mod parse_limits_tests {
+    use super::*;
+    use cool_asserts::assert_matches;
+    use miette::Diagnostic;
+
+    #[test]
+    fn within_limits() {
+        let src = r#"
+            permit(principal, action, resource) when { if context.a then [1, 2] else [3] };
+            forbid(principal, action, resource) unless { principal["if"] == {"if": 1}["if"] };
+        "#;
+        let limits = ParseLimits::new().with_max_depth(6);
+        let pset = PolicySet::from_str_with_limits(src, limits).unwrap();
+        assert_eq!(pset, PolicySet::from_str(src).unwrap());
+    }
+
+    #[test]
+    fn nesting_too_deep() {
+        let src = format!(
+            "permit(principal, action, resource) when {{ {}true{} }};",
+            "(".repeat(100_000),
+            ")".repeat(100_000)
+        );
+        let limits = ParseLimits::new().with_max_depth(64);
+        assert_matches!(PolicySet::from_str_with_limits(&src, limits), Err(errs) => {
+            assert_eq!(errs.len(), 1);
+            let err = errs.iter().next().unwrap();
+            assert_eq!(err.to_string(), "expression is nested more than 64 levels deep");
+            // points at the first bracket too deep
+            let span = err.labels().unwrap().next().unwrap();
+            assert_eq!(span.offset(), src.find("((").unwrap() + 63);
+        });
+    }
+
+    #[test]
+    fn long_operator_chains() {
+        for (terms, max_depth) in [(20_000, 5), (100_000, 64)] {
+            for (op, term) in [(" && ", "principal.a"), (" + ", "1")] {
+                let src = format!(
+                    "permit(principal, action, resource) when {{ {} }};",
+                    vec![term; terms].join(op)
+                );
+                let limits = ParseLimits::new().with_max_depth(max_depth);
+                assert_matches!(PolicySet::from_str_with_limits(&src, limits), Err(errs) => {
+                    assert_eq!(
+                        errs.to_string(),
+                        format!("expression is nested more than {max_depth} levels deep")
+                    );
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Computing the transitive closure of a graph, as Cedar does for entity and
+//! action hierarchies. This is a generic utility, and not specific to Cedar.
+
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+pub use cedar_policy_core::transitive_closure::{HasCycle, MissingTcEdge, TCNode, TcError};
+
+/// Compute the transitive closure of the graph formed by `nodes`, adding an
+/// edge from each node to every node reachable from it.
+///
+/// Edges to keys that do not belong to any node in `nodes` are kept as they
+/// are, but are not followed any further.
+///
+/// # Errors
+///
+/// The graph must be acyclic. If it is not, this returns a
+/// [`TcError::HasCycle`] whose [`HasCycle::vertex_with_loop`] is a node lying
+/// on a cycle. The transitive closure has still been computed for `nodes` in
+/// that case, so every node on a cycle has an edge to itself.
+///
+/// ```
+/// # use cedar_policy::transitive_closure::{compute_tc, TCNode};
+/// # use std::collections::HashSet;
+/// struct Node {
+///     key: u32,
+///     edges: HashSet<u32>,
+/// }
+///
+/// impl TCNode<u32> for Node {
+///     fn get_key(&self) -> u32 {
+///         self.key
+///     }
+///     fn add_edge_to(&mut self, k: u32) {
+///         self.edges.insert(k);
+///     }
+///     fn out_edges(&self) -> Box<dyn Iterator<Item = &u32> + '_> {
+///         Box::new(self.edges.iter())
+///     }
+///     fn has_edge_to(&self, k: &u32) -> bool {
+///         self.edges.contains(k)
+///     }
+/// }
+///
+/// // 1 -> 2 -> 3
+/// let mut nodes = vec![
+///     Node { key: 1, edges: HashSet::from([2]) },
+///     Node { key: 2, edges: HashSet::from([3]) },
+///     Node { key: 3, edges: HashSet::new() },
+/// ];
+/// compute_tc(&mut nodes).unwrap();
+/// assert!(nodes[0].has_edge_to(&3));
+///
+/// // 3 -> 1 closes a cycle
+/// nodes[2].add_edge_to(1);
+/// let err = compute_tc(&mut nodes).unwrap_err();
+/// assert!(matches!(err, cedar_policy::transitive_closure::TcError::HasCycle(_)));
+/// ```
+pub fn compute_tc<K, N>(nodes: &mut [N]) -> Result<(), TcError<K>>
+where
+    K: Clone + Eq + Hash + Debug + Display,
+    N: TCNode<K>,
+{
+    cedar_policy_core::transitive_closure::compute_tc_slice(nodes, true)
+}
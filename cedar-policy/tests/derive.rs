@@ -0,0 +1,123 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use cedar_policy::{
+    Authorizer, Context, Decimal, Decision, Entities, EntityUid, IpAddr, PolicySet, Request, Schema,
+};
+use cedar_policy_derive::IntoContext;
+
+#[derive(IntoContext)]
+struct Device {
+    ip: IpAddr,
+    managed: bool,
+}
+
+#[derive(IntoContext)]
+struct RequestContext {
+    device: Device,
+    owner: EntityUid,
+    #[cedar(rename = "mfa-age")]
+    mfa_age: Option<i64>,
+    scopes: Vec<String>,
+    tags: HashSet<String>,
+    risk: Decimal,
+}
+
+fn request_context(mfa_age: Option<i64>) -> RequestContext {
+    RequestContext {
+        device: Device {
+            ip: IpAddr("10.0.0.1".into()),
+            managed: true,
+        },
+        owner: EntityUid::from_str(r#"User::"alice""#).unwrap(),
+        mfa_age,
+        scopes: vec!["read".into(), "write".into()],
+        tags: HashSet::from(["internal".into()]),
+        risk: Decimal("0.25".into()),
+    }
+}
+
+fn is_authorized(context: Context, schema: Option<&Schema>) -> Decision {
+    let policies = PolicySet::from_str(
+        r#"
+        permit(principal, action, resource) when {
+            context.device.ip.isInRange(ip("10.0.0.0/8")) &&
+            context.device.managed &&
+            context.owner == principal &&
+            context has "mfa-age" && context["mfa-age"] < 60 &&
+            context.scopes.contains("write") &&
+            context.tags.contains("internal") &&
+            context.risk.lessThan(decimal("0.5"))
+        };
+        "#,
+    )
+    .unwrap();
+    let request = Request::new(
+        Some(EntityUid::from_str(r#"User::"alice""#).unwrap()),
+        Some(EntityUid::from_str(r#"Action::"view""#).unwrap()),
+        Some(EntityUid::from_str(r#"Doc::"doc""#).unwrap()),
+        context,
+        schema,
+    )
+    .unwrap();
+    Authorizer::new()
+        .is_authorized(&request, &policies, &Entities::empty())
+        .decision()
+}
+
+#[test]
+fn fields_become_attributes() {
+    let context: Context = request_context(Some(30)).try_into().unwrap();
+    assert_eq!(is_authorized(context, None), Decision::Allow);
+
+    // `None` leaves the attribute out
+    let context: Context = request_context(None).try_into().unwrap();
+    assert_eq!(is_authorized(context, None), Decision::Deny);
+}
+
+#[test]
+fn matches_schema() {
+    let (schema, _) = Schema::from_str_natural(
+        r#"
+        entity User, Doc;
+        action view appliesTo {
+            principal: User,
+            resource: Doc,
+            context: {
+                device: { ip: ipaddr, managed: Bool },
+                owner: User,
+                "mfa-age"?: Long,
+                scopes: Set<String>,
+                tags: Set<String>,
+                risk: decimal,
+            },
+        };
+        "#,
+    )
+    .unwrap();
+    let context: Context = request_context(Some(30)).try_into().unwrap();
+    assert_eq!(is_authorized(context, Some(&schema)), Decision::Allow);
+}
+
+#[test]
+fn invalid_extension_value() {
+    let mut context = request_context(None);
+    context.device.ip = IpAddr("not an ip".into());
+    assert!(Context::try_from(context).is_err());
+}
@@ -54,6 +54,7 @@ pub fn wasm_format_policies(
     let config = Config {
         line_width,
         indent_width,
+        ..Config::default()
     };
     match policies_str_to_pretty(policies_str, &config) {
         Ok(prettified_policy) => FormattingResult::Success {